@@ -13,6 +13,7 @@ use std::time::Duration;
 #[actix_rt::main]
 async fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    set_build_info_env();
     let c = Rc::new(make_client());
 
     for h in [
@@ -21,12 +22,93 @@ async fn main() {
         spawn(download_deps(c.clone(), "tabler-icons.svg")),
         spawn(download_deps(c.clone(), "apexcharts.js")),
         spawn(download_deps(c.clone(), "tomselect.js")),
+        spawn(download_deps(c.clone(), "leaflet.js")),
+        spawn(download_deps(c.clone(), "leaflet.css")),
         spawn(download_deps(c.clone(), "favicon.svg")),
     ] {
         h.await.unwrap();
     }
 }
 
+/// Exposes the git commit, build date, target and enabled features to the crate, in the
+/// `SQLPAGE_GIT_COMMIT`, `SQLPAGE_BUILD_DATE`, `SQLPAGE_TARGET` and `SQLPAGE_FEATURES` environment variables.
+fn set_build_info_env() {
+    println!("cargo:rustc-env=SQLPAGE_GIT_COMMIT={}", git_commit());
+    println!("cargo:rustc-env=SQLPAGE_BUILD_DATE={}", build_date());
+    println!(
+        "cargo:rustc-env=SQLPAGE_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=SQLPAGE_FEATURES={}", features.join(","));
+}
+
+/// The commit SQLPage is built from. It can be set with the `SQLPAGE_GIT_COMMIT` environment variable
+/// when the sources are not in a git repository, as in docker builds.
+fn git_commit() -> String {
+    println!("cargo:rerun-if-env-changed=SQLPAGE_GIT_COMMIT");
+    if let Ok(commit) = std::env::var("SQLPAGE_GIT_COMMIT") {
+        return commit;
+    }
+    let git_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(".git");
+    let head = git_dir.join("HEAD");
+    if !head.exists() {
+        return "unknown".into();
+    }
+    println!("cargo:rerun-if-changed={}", head.display());
+    if let Some(branch) = std::fs::read_to_string(&head)
+        .ok()
+        .and_then(|h| Some(git_dir.join(h.strip_prefix("ref: ")?.trim())))
+        .filter(|branch| branch.exists())
+    {
+        println!("cargo:rerun-if-changed={}", branch.display());
+    }
+    std::process::Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map_or_else(|| "unknown".into(), |commit| commit.trim().to_string())
+}
+
+/// The UTC date of the build, in the YYYY-MM-DD format.
+/// `SOURCE_DATE_EPOCH` is used instead of the current time when it is set, for reproducible builds.
+fn build_date() -> String {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64)
+        });
+    // Converts a number of days since 1970-01-01 to a date in the proleptic Gregorian calendar
+    let days = timestamp.div_euclid(86400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
 fn make_client() -> awc::Client {
     awc::ClientBuilder::new()
         .timeout(Duration::from_secs(10))
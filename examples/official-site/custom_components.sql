@@ -70,6 +70,20 @@ SELECT ''my_list'' AS component, ''My list'' AS title;
 SELECT first_name AS my_property, last_name AS other_property FROM clients;
 ```
 
+### Escaping
+
+`{{my_property}}` escapes the HTML special characters of the value, so that it is displayed as text.
+`{{{my_property}}}`, with three braces, inserts the value in the page as HTML, without escaping it.
+When the value comes from the database, and may have been written by a visitor,
+this lets anyone inject scripts in your pages. This is called a [cross-site scripting](https://owasp.org/www-community/attacks/xss/) attack.
+
+Only render with three braces the properties that contain trusted HTML, and give them a name that ends with `html`, such as `html` or `unsafe_html`.
+In development, SQLPage logs a warning for every other property that a component renders with three braces,
+and the `sqlpage check` command reports it for every page that uses the component.
+The `markdown` helper escapes HTML by default, so `{{{markdown description}}}` is safe.
+To display HTML written by your users, for instance with a rich text editor, use `{{sanitize_html description}}`:
+it keeps formatting tags such as `<b>`, `<p>`, `<ul>`, `<a href>` or `<img src>`, and removes scripts, styles, event handlers and `javascript:` links.
+
 ### Styling
 
 SQLPage uses [tabler](https://tabler.io/) for its default styling.
@@ -109,7 +123,12 @@ and SQLPage adds a few more:
 - `stringify`: converts a value to its json string representation, useful to pass parameters from the database to javascript functions
 - `parse_json`: parses a json string into a value, useful to accept complex parameters from databases that don''t have a native json type
 - `default`: returns the first argument if it is not null, otherwise returns the second argument. For instance: `{{default my_value ''default value''}}`.
+  When called with a single argument, null values are replaced by the `null_placeholder` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md), or by an empty string if it is not set.
 - `entries`: returns the entries of an object as a list of `{key, value}` objects.
+- `query_string`: builds a query string from an object of URL parameters, changing the parameters given as pairs of names and values. A null value removes the parameter.
+  `<a href="{{query_string @url_parameters ''page'' 2}}">next page</a>` links to the current page with `page=2`, keeping the other parameters.
+- `each_key`: iterates over the keys of an object, for components that display arbitrary columns. Inside the block, `this` is the name of the column and `@value` its value:
+  `{{#each_key this}}<td title="{{this}}">{{@value}}</td>{{/each_key}}`.
 - `delay` and `flush_delayed`: temporarily saves a value to memory, and outputs it later. For instance:
     - ```handlebars
         {{#if complex_condition}}
@@ -123,6 +142,7 @@ and SQLPage adds a few more:
         ```
 - `sort`: sorts a list of values
 - `plus`, `minus`, `sum`: mathematical operators
+- `percentage`: the share of a total represented by a value, between 0 and 100, rounded to one decimal: `{{percentage sold target}}` returns `37.5` when 3 of 8 items are sold. Useful to set the width of a progress bar.
 - `starts_with`: returns true if a string starts with another string
 - `to_array`: useful to accept parameters that can optionally be repeated:
    - if the argument is a list, returns it unchanged,
@@ -132,11 +152,17 @@ and SQLPage adds a few more:
 - `static_path`: returns the path to one of the static files bundled with SQLPage. Accepts arguments like `sqlpage.js`, `sqlpage.css`, `apexcharts.js`, etc.
 - `app_config`: returns the value of a configuration parameter from sqlpage''s configuration file, such as `max_uploaded_file_size`, `site_prefix`, etc.
 - `icon_img`: generate an svg icon from a *tabler* icon name
+- `icon`: like `icon_img`, but copies the drawing of the icon in the page, instead of referencing the icons file bundled with SQLPage. `{{icon ''home'' 32}}` renders a 32 pixels *home* icon. Useful when the page is displayed where the icons file cannot be loaded, such as in an email.
 - `markdown`: renders markdown text
+- `sanitize_html`: renders HTML from an untrusted source, keeping only safe tags and attributes. `{{sanitize_html ''<b onclick="hack()">Hi</b><script>hack()</script>''}}` renders `<b>Hi</b>`.
+- `highlight`: renders code with its syntax highlighted, with the classes of [highlight.js](https://highlightjs.org/) themes. `{{highlight contents ''sql''}}`. Code in an unsupported language is displayed as plain text.
 - `each_row`: iterates over the rows of a query result
 - `typeof`: returns the type of a value (`string`, `number`, `boolean`, `object`, `array`, `null`)
 - `rfc2822_date`: formats a date as a string in the [RFC 2822](https://tools.ietf.org/html/rfc2822#section-3.3) format, that is, `Thu, 21 Dec 2000 16:01:07 +0200`
 - `url_encode`: percent-encodes a string for use in a URL. For instance, `{{url_encode "hello world"}}` returns `hello%20world`.
+- `to_timezone`: converts a timestamp stored in UTC to a time zone. `{{to_timezone created_at @timezone}}` returns `2024-07-14T12:00:00+02:00` for a French visitor,
+  and `{{to_timezone created_at @timezone "%d/%m/%Y %H:%M"}}` formats it as `14/07/2024 12:00` (see the [list of format specifiers](https://docs.rs/chrono/latest/chrono/format/strftime/index.html)).
+  Values that are not timestamps are left unchanged.
 
 ### Attributes
 
@@ -145,7 +171,13 @@ SQLPage adds the following attributes to the context of your components:
 
  - `@component_index` : the index of the current component in the page. Useful to generate unique ids or classes.
  - `@row_index` : the index of the current row in the current component. Useful to implement special behavior on the first row, for instance.
- - `@csp_nonce` : a random nonce that you must use as the `nonce` attribute of your `<script>` tags if you include external scripts.
+ - `@first`, `@last` : whether the current row is the first or the last row of the current component. Useful to render separators between rows: `{{#unless @last}}, {{/unless}}`.
+ - `@odd`, `@even` : whether the current row is an odd (first, third, ...) or an even row. Useful for zebra striping.
+ - `@row_count` : in the part of the template that comes after `{{#each_row}}...{{/each_row}}`, the number of rows in the component. Useful to display a footer like `{{@row_count}} results`.
+ - `@group`, `@group_start`, `@group_end` : when the component has a `group_by` top-level property, the value of that column in the current row, and whether the row is the first or the last of a group of consecutive rows with the same value. `@group_row_count` and `@group_sum` contain the number of rows of the group and the sums of its numeric columns so far: on the last row of the group, they are its totals.
+ - `@timezone` : the time zone of the visitor, such as `Europe/Paris`, detected by their browser. It is the `default_timezone` of the [configuration](https://github.com/sqlpage/SQLPage/blob/main/configuration.md) on the first page they visit.
+ - `@url_parameters` : the parameters of the URL of the page, as an object. `{{lookup @url_parameters ''id''}}` is the value of `$id`.
+ - `@csp_nonce` : a random nonce that you must use as the `nonce` attribute of your `<script>` tags. It changes on every page load, and is the value of `{NONCE}` in a custom `content_security_policy`.
 
 ## External javascript
 
@@ -169,6 +201,40 @@ you can create a file called `sqlpage/templates/shell.handlebars` and write your
 If you don''t want to start from scratch, you can copy the default `shell` component
 [from the SQLPage source code](https://github.com/sqlpage/SQLPage/blob/main/sqlpage/templates/shell.handlebars).
 
+### Wrapping a default component
+
+A copied component does not receive the improvements and fixes of new SQLPage versions.
+If you only want to add something around a default component, include it with the `builtin/` prefix instead of copying it.
+For instance, this `sqlpage/templates/table.handlebars` file adds a title above all the tables of your site,
+and keeps using the default `table` component for everything else:
+
+```handlebars
+<h2 class="my-table-title">{{title}}</h2>
+{{> builtin/table}}
+```
+
+`{{> builtin/...}}` must appear at the top level of your template, not inside a block such as `{{#if}}`.
+
+### Sharing components between sites
+
+Components can be shared between several websites by putting them in a common directory,
+and listing it in the `plugin_template_directories` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+When several components have the same name, SQLPage uses the first one it finds in:
+
+ 1. the `sqlpage/templates` folder of the website (or the `sqlpage_files` table in the database),
+ 2. the plugin template directories, in the order in which they are listed,
+ 3. the default components.
+
+Plugin components are loaded when SQLPage starts, so you need to restart it after modifying them.
+
+### Listing the available components
+
+When the `component_gallery` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md) is enabled,
+SQLPage serves a page at `/sqlpage/components` that lists all the components your site can use,
+where each of them comes from, and the properties it accepts.
+Each component is displayed with example data in which every property contains its own name,
+so you can see where each property appears.
+
 ## Examples
 
 All the default components are written in handlebars, and you can read their source code to learn how to write your own.
@@ -57,4 +57,26 @@ select 'SQL code to use' as title, 'sql' as language, printf('select
   ''shell'' as component,
   ''%s'' as layout,
   %s as sidebar;
-', $layout, case when $sidebar then 'true' else 'false' end) as contents;
\ No newline at end of file
+', $layout, case when $sidebar then 'true' else 'false' end) as contents;
+select 'text' as component, '
+## Components side by side
+
+By default, components are displayed one below the other.
+Give them a `grid_width` between 1 and 12 to display consecutive components side by side,
+like the two charts below, which each take half of the width of the page (`6 as grid_width`).
+On small screens, they are displayed one below the other again.
+
+```sql
+select ''chart'' as component, ''Visits'' as title, 6 as grid_width;
+select ...;
+
+select ''chart'' as component, ''Sales'' as title, ''bar'' as type, 6 as grid_width;
+select ...;
+```
+' as contents_md;
+
+select 'chart' as component, 'Visits' as title, 'area' as type, 6 as grid_width;
+select 'Monday' as x, 120 as y union all select 'Tuesday', 150 union all select 'Wednesday', 90;
+
+select 'chart' as component, 'Sales' as title, 'bar' as type, 6 as grid_width;
+select 'Monday' as x, 12 as y union all select 'Tuesday', 18 union all select 'Wednesday', 7;
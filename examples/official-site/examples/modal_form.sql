@@ -0,0 +1,3 @@
+select 'form' as component, '/examples/show_variables.sql' as action, 'Create' as validate;
+select 'name' as name, 'Name' as label, true as required;
+select 'email' as name, 'email' as type, 'Email' as label;
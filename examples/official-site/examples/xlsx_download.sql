@@ -0,0 +1,2 @@
+select 'xlsx' as component, 'components' as filename, 'Components' as sheet_name;
+select name, description, introduced_in_version from component;
@@ -80,6 +80,14 @@ that disallows the execution of any inline JavaScript code, and only allows load
 If you have some legitimate JavaScript code that you want to execute on your website, you can use the `javascript`
 parameter of the [`shell`](documentation.sql?component=shell#component) component to do so.
 
+You can write a stricter policy with the `content_security_policy` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+SQLPage generates a new random *nonce* for every page, and adds it to all the inline scripts of its built-in components.
+Put `{NONCE}` in your policy to allow these scripts, and only them:
+
+```json
+{ "content_security_policy": "default-src ''self''; script-src ''self'' ''nonce-{NONCE}''" }
+```
+
 ## Authentication
 
 SQLPage provides an [authentication](/documentation.sql?component=authentication#component) component that allows you to
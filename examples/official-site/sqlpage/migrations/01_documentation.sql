@@ -1168,9 +1168,18 @@ INSERT INTO parameter(component, name, description, type, top_level, optional) S
     ('refresh', 'Number of seconds after which the page should refresh. This can be useful to display dynamic content that updates automatically.', 'INTEGER', TRUE, TRUE),
     ('sidebar', 'Whether the menu defined by menu_item should be displayed on the left side of the page instead of the top. Introduced in v0.27.', 'BOOLEAN', TRUE, TRUE),
     ('sidebar_theme', 'Used with sidebar property, It can be set to "dark" to exclusively set the sidebar into dark theme.', 'BOOLEAN', TRUE, TRUE),
-    ('theme', 'Set to "dark" to use a dark theme.', 'TEXT', TRUE, TRUE),
+    ('theme', 'Set to "dark" to use a dark theme, "light" to use a light theme, or "auto" to follow the user''s operating system preferences. Defaults to the "default_theme" configuration option.', 'TEXT', TRUE, TRUE),
+    ('theme_toggle', 'Displays a button in the navigation bar that lets users switch between the light, dark and automatic themes. Their choice is remembered in their browser.', 'BOOLEAN', TRUE, TRUE),
+    ('primary_color', 'The main color of the site, used for buttons, links and highlights. One of the colors of the tabler palette: blue, azure, indigo, purple, pink, red, orange, yellow, lime, green, teal, or cyan.', 'COLOR', TRUE, TRUE),
     ('footer', 'Muted text to display in the footer of the page. This can be used to display a link to the terms and conditions of your application, for instance. By default, shows "Built with SQLPage". Supports links with markdown.', 'TEXT', TRUE, TRUE),
     ('preview_image', 'The URL of an image to display as a link preview when the page is shared on social media', 'URL', TRUE, TRUE),
+    ('social_title', 'The title to display in link previews when the page is shared on social media and in chat applications. Defaults to the title of the page.', 'TEXT', TRUE, TRUE),
+    ('social_description', 'The text to display in link previews when the page is shared on social media and in chat applications. Defaults to the description of the page.', 'TEXT', TRUE, TRUE),
+    ('social_image_alt', 'A textual description of the preview image, for users who cannot see it.', 'TEXT', TRUE, TRUE),
+    ('site_name', 'The name of the website the page belongs to, displayed above the title in link previews.', 'TEXT', TRUE, TRUE),
+    ('canonical', 'The full URL of the page, including the domain name. Search engines and social networks use it as the main address of the page, even when it was reached with additional URL parameters. [`sqlpage.canonical_url()`](functions.sql?function=canonical_url) returns the URL of the current page in its preferred form.', 'URL', TRUE, TRUE),
+    ('twitter_card', 'The layout of the link preview on X (Twitter). "summary_large_image" (the default when a preview image is set) displays a large image above the title, "summary" displays a small thumbnail next to it.', 'TEXT', TRUE, TRUE),
+    ('twitter_site', 'The X (Twitter) username of the website, starting with @.', 'TEXT', TRUE, TRUE),
     ('navbar_title', 'The title to display in the top navigation bar. Used to display a different title in the top menu than the one that appears in the tab of the browser.', 'TEXT', TRUE, TRUE),
     ('target', '"_blank" to open the link in a new tab, "_self" to open it in the same tab, "_parent" to open it in the parent frame, or "_top" to open it in the full body of the window', 'TEXT', TRUE, TRUE)
 ) x;
@@ -1347,6 +1356,29 @@ SELECT
 ```
 ', NULL),
     ('shell', '
+### Theming
+
+The look of all the components can be changed from the shell, without modifying their templates:
+
+ - `theme` switches between a light and a dark theme, or follows the user''s system preferences when set to `auto`.
+   A default for all pages can be set with the `default_theme` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+ - `theme_toggle` adds a button to the navigation bar that lets users pick their preferred theme.
+ - `primary_color` changes the main color of the site.
+
+The components use [CSS variables](https://developer.mozilla.org/en-US/docs/Web/CSS/Using_CSS_custom_properties) for all their colors,
+so you can go further by overriding the `--tblr-*` variables in a custom stylesheet loaded with the `css` property.
+', json('[{"component":"shell", "title": "Themed site", "theme": "auto", "theme_toggle": true, "primary_color": "teal"}]')),
+    ('shell', '
+### Link previews
+
+When a link to your site is shared on social media or in a chat application,
+it is displayed as a card with a title, a short text, and an image.
+The shell generates the [Open Graph](https://ogp.me/) tags these applications read from the `title`, `description` and `preview_image` properties.
+Use `social_title` and `social_description` to display a different text in the previews than on the page itself.
+
+Image and page URLs in link previews should be absolute, including the domain name of your site.
+', json('[{"component":"shell", "title": "Concert tickets", "description": "Book your seats for the summer concert.", "site_name": "City Hall Events", "social_title": "Summer concert: 50 seats left!", "preview_image": "https://example.com/concert.jpg", "social_image_alt": "The orchestra on the main stage", "canonical": "https://example.com/concert.sql", "twitter_site": "@cityhall"}]')),
+    ('shell', '
 ### A page without a shell
 SQLPage provides the `shell-empty` component to create a page without a shell.
 In this case, the `html` and `body` tags are not generated, and the components are rendered directly in the page
@@ -160,7 +160,7 @@ VALUES
     (
         'map',
         'geojson',
-        'A GeoJSON geometry (line, polygon, ...) to display on the map. Can be styled using geojson properties using the name of leaflet path options. Introduced in 0.15.1. Accepts raw strings in addition to JSON objects since 0.15.2.',
+        'A GeoJSON geometry (line, polygon, ...) to display on the map. Can be styled using geojson properties using the name of leaflet path options. Introduced in 0.15.1. Accepts raw strings in addition to JSON objects since 0.15.2. Since 0.34, geometry columns from PostGIS, MySQL and SpatiaLite, and WKT text, can be used directly.',
         'JSON',
         FALSE,
         TRUE
@@ -199,7 +199,10 @@ and clickable markers that link to external content - perfect for educational or
 
 It uses [GeoJSON](https://en.wikipedia.org/wiki/GeoJSON) to display polygons and lines.
 
- - You can generate GeoJSON data from PostGIS geometries using the [`ST_AsGeoJSON`](https://postgis.net/docs/ST_AsGeoJSON.html) function.
+ - Since v0.34, SQLPage converts geometry values to GeoJSON automatically: PostGIS `geometry` and `geography` columns,
+   MySQL `GEOMETRY` columns, and SpatiaLite or GeoPackage geometries in SQLite can be selected directly, as in `select the_geom as geojson from regions`.
+   Geometries stored as [WKT](https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry) text (`POINT(28.49 -31.96)`) or hex-encoded WKB are also converted when they are returned in a column named `geojson`.
+ - You can also generate GeoJSON data from PostGIS geometries using the [`ST_AsGeoJSON`](https://postgis.net/docs/ST_AsGeoJSON.html) function.
  - In spatialite, you can use the [`AsGeoJSON`](https://www.gaia-gis.it/gaia-sins/spatialite-sql-5.1.0.html#p3misc) function.
  - In MySQL, you can use the [`ST_AsGeoJSON()`](https://dev.mysql.com/doc/refman/8.0/en/spatial-geojson-functions.html#function_st-asgeojson) function.
 ',
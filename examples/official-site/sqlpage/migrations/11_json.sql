@@ -45,9 +45,10 @@ VALUES
     (
         'json',
         'type',
-        'The type of the JSON payload to send: "array", "jsonlines", or "sse".
+        'The type of the JSON payload to send: "array", "jsonlines", "jsonl", or "sse".
 In "array" mode, each query result is rendered as a JSON object in a single top-level array.
 In "jsonlines" mode, results are rendered as JSON objects in separate lines, without a top-level array.
+In "jsonl" mode, each result is rendered as a JSON object followed by a newline, with the "application/x-ndjson" content type. Rows are sent as soon as they are received from the database, which is suited to large exports.
 In "sse" mode, results are rendered as JSON objects in separate lines, prefixed by "data: ", which allows you to read the results as server-sent events in real-time from javascript.',
         'TEXT',
         TRUE,
@@ -120,6 +121,29 @@ select * from users where id = $user_id LIMIT 1;
 ```json
 { "username":"James", "userid":1 }
 ```
+'
+    ),
+    (
+        'json',
+        '
+## Stream a large export: `''jsonl'' as type`
+
+In `jsonl` mode, the response is in the [JSON Lines](https://jsonlines.org/) format (also called NDJSON):
+each row is a JSON object on its own line, terminated by a newline character.
+Rows are sent to the client as soon as the database returns them,
+so clients can start processing a large export without waiting for the whole result,
+and neither SQLPage nor the client need to hold it in memory.
+
+```sql
+select ''json'' AS component, ''jsonl'' AS type;
+select * from orders;
+```
+
+The export can be piped directly into data tools:
+
+```bash
+curl https://example.com/orders.sql | jq -c ''select(.total > 100)''
+```
 '
     ),
     (
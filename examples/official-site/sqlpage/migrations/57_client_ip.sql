@@ -26,20 +26,15 @@ The function returns:
 
 ### ⚠️ Important Notes for Production Use
 
-When running behind a reverse proxy (e.g., Nginx, Apache, Cloudflare):
-- This function will return the IP address of the reverse proxy, not the actual client
-- To get the real client IP, use [`sqlpage.header`](?function=header): `sqlpage.header(''x-forwarded-for'')` or `sqlpage.header(''x-real-ip'')`
-  - The exact header name depends on your reverse proxy configuration
-
-Example with reverse proxy:
-```sql
--- Choose the appropriate header based on your setup
-select coalesce(
-    sqlpage.header(''x-forwarded-for''),
-    sqlpage.header(''x-real-ip''),
-    sqlpage.client_ip()
-) as real_client_ip;
-```
+When running behind a reverse proxy (e.g., Nginx, Apache, Cloudflare),
+the connection to SQLPage comes from the proxy, not from the actual client.
+List the addresses of your proxies in the `trusted_proxies` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md),
+for instance `"trusted_proxies": ["10.0.0.0/8"]`, and this function will return the client address
+found in the `X-Forwarded-For` header set by the proxy.
+
+The `X-Forwarded-For` header is ignored for requests that do not come from a trusted proxy,
+since any client can send it. Do not read it directly with `sqlpage.header(''x-forwarded-for'')`
+for security-sensitive uses such as rate limiting or access logs.
 
 For security-critical applications, ensure your reverse proxy is properly configured to set and validate these headers.
 '
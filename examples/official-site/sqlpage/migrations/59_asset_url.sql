@@ -0,0 +1,60 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'asset_url',
+        '0.34.0',
+        'file-check',
+        'Returns the URL of a static file from your website (a stylesheet, a script, an image, ...),
+with a fingerprint of the contents of the file added to it.
+
+### Example
+
+```sql
+select ''shell'' as component, sqlpage.asset_url(''/css/app.css'') as css;
+```
+
+This returns something like `/css/app.css?v=2f1c6a7e0b9d4c38`.
+
+### Why use it
+
+Browsers keep copies of the static files they download, to avoid downloading them again on every page.
+This makes websites faster, but it also means that after you modify a file,
+some of your users may keep seeing the old version for a while.
+
+The fingerprint in the URL returned by `sqlpage.asset_url` changes every time the contents of the file change.
+SQLPage serves files requested with a fingerprint with headers telling browsers
+that they can keep them forever without ever checking for updates.
+When you modify the file, its URL changes, and browsers download the new version immediately.
+
+### Details
+
+ - The file path is relative to the root of your website, and the file is read from the same place SQLPage serves it from
+   (the web root directory or the `sqlpage_files` table in the database).
+ - The file is read every time the function is called, to compute its fingerprint.
+   For very large files, prefer linking to them directly.
+ - If the file does not exist, an error is returned.
+'
+    );
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'asset_url',
+        1,
+        'path',
+        'Path of the static file, relative to the root of the website. For example: `/css/app.css`.',
+        'TEXT'
+    );
@@ -0,0 +1,42 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'canonical_url',
+        '0.34.0',
+        'link',
+        'Returns the full URL of the current page, in its preferred form.
+
+### Example
+
+```sql
+select ''shell'' as component, ''My product'' as title, sqlpage.canonical_url() as canonical;
+```
+
+When a user opens `https://example.com/product.sql?id=3&utm_source=newsletter`,
+this returns `https://example.com/product?id=3&utm_source=newsletter` if the
+[`url_style` configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md) is set to `without_extension`.
+
+### Why use it
+
+The same SQLPage page can be accessed from several URLs: `/product`, `/product.sql`, or `/product.sql?id=3`.
+Search engines may consider them as duplicate pages.
+Telling them which URL is the main one, with the `canonical` property of the [shell](?component=shell#component) component,
+avoids this problem.
+
+### Details
+
+ - The path of the URL follows the `url_style` configuration option:
+   - `without_extension`: `/page.sql` becomes `/page`, and `/dir/index.sql` becomes `/dir/`,
+   - `with_extension`: `/page` becomes `/page.sql`,
+   - `any` (the default): the path is left as it was requested.
+ - The query string parameters are sorted by name, so that the same page always gets the same URL.
+   The internal parameters of SQLPage, whose name starts with `_sqlpage`, are removed.
+ - The protocol and domain name come from the request. If the request has no `Host` header, only the path and query string are returned.
+'
+    );
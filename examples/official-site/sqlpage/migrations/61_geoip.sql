@@ -0,0 +1,87 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'client_country',
+        '0.34.0',
+        'world',
+        'Returns the two-letter [ISO code](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2) of the country of the client making the HTTP request, such as `FR` or `US`.
+
+### Example
+
+```sql
+select ''text'' as component,
+    case sqlpage.client_country()
+        when ''FR'' then ''Bonjour !''
+        when ''DE'' then ''Hallo!''
+        else ''Hello!''
+    end as contents;
+```
+
+### Setup
+
+The country is found from the [IP address of the client](?function=client_ip) in a geolocation database in the MaxMind format,
+such as the free [GeoLite2 Country](https://dev.maxmind.com/geoip/geolite2-free-geolocation-data) database.
+Download the `.mmdb` file, and set its path in the `geoip_database_path` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+
+The function returns `null` when the address of the client is unknown or not in the database.
+When SQLPage runs behind a reverse proxy, set the `trusted_proxies` configuration option so that the address of the actual client is used.
+'
+    ),
+    (
+        'geoip',
+        '0.34.0',
+        'map-pin',
+        'Returns a json object describing the location of an IP address, found in the geolocation database configured with the `geoip_database_path` [configuration option](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+
+### Example: log the countries of your visitors
+
+```sql
+insert into visits (path, country, city)
+select sqlpage.path(), location->>''country'', location->>''city''
+from (select sqlpage.geoip(sqlpage.client_ip())::json as location) as l;
+```
+
+### Result
+
+```json
+{
+  "country": "GB",
+  "country_name": "United Kingdom",
+  "continent": "EU",
+  "city": "London",
+  "latitude": 51.5142,
+  "longitude": -0.0931,
+  "time_zone": "Europe/London"
+}
+```
+
+Country databases, such as GeoLite2 Country, do not contain the `city`, `latitude`, `longitude` and `time_zone` fields, which are then `null`.
+Names are in English.
+
+The function returns `null` when the address is not in the database, or when its argument is `null`.
+To get only the country of the current client, use [`sqlpage.client_country()`](?function=client_country).
+'
+    );
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'geoip',
+        1,
+        'ip',
+        'The IPv4 or IPv6 address to locate, such as `81.2.69.160` or the result of [`sqlpage.client_ip()`](?function=client_ip).',
+        'TEXT'
+    );
@@ -0,0 +1,73 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'client_is_mobile',
+        '0.34.0',
+        'device-mobile',
+        'Returns `true` if the client making the request is a phone or a tablet, and `false` otherwise,
+based on the [`User-Agent`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent) header sent by the browser.
+
+### Example: a simpler layout on mobile
+
+```sql
+select ''table'' as component,
+    sqlpage.client_is_mobile() = ''true'' as small;
+select * from products;
+```
+
+### Details
+
+The result is the text `true` or `false`. Compare it with `''true''` to use it as a condition.
+It is `null` if the request has no `User-Agent` header.
+
+The user agent is sent by the client, which can set it to any value:
+use this function to adapt the presentation of your pages, not for security.
+'
+    ),
+    (
+        'client_browser',
+        '0.34.0',
+        'browser',
+        'Returns the name of the web browser of the client making the request, such as `Chrome`, `Firefox`, `Safari` or `Edge`,
+based on its [`User-Agent`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent) header.
+
+### Example: record the browsers of your visitors
+
+```sql
+insert into visits (path, browser, os, is_mobile)
+values (sqlpage.path(), sqlpage.client_browser(), sqlpage.client_os(), sqlpage.client_is_mobile());
+```
+
+Search engine crawlers are also recognized, with names such as `Googlebot`.
+The function returns `null` if the request has no `User-Agent` header, or if the browser is not recognized.
+'
+    ),
+    (
+        'client_os',
+        '0.34.0',
+        'device-desktop',
+        'Returns the name of the operating system of the client making the request, such as `Windows 10`, `Mac OSX`, `Linux`, `Android`, `iPhone` or `iPad`,
+based on its [`User-Agent`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/User-Agent) header.
+
+### Example: a download link for the right platform
+
+```sql
+select ''button'' as component;
+select
+    ''Download the app'' as title,
+    case
+        when sqlpage.client_os() = ''Android'' then ''https://play.google.com/store/apps/details?id=com.example''
+        when sqlpage.client_os() in (''iPhone'', ''iPad'') then ''https://apps.apple.com/app/id000000000''
+        else ''/download.sql''
+    end as link;
+```
+
+The function returns `null` if the request has no `User-Agent` header, or if the operating system is not recognized.
+'
+    );
@@ -0,0 +1,45 @@
+UPDATE sqlpage_functions
+SET description_md = 'Returns the current version of SQLPage as a string.
+
+### Example
+
+```sql
+select ''text'' as component, ''Running SQLPage v'' || sqlpage.version() as contents;
+```
+
+### Build information
+
+Called with `''full''` as argument, `sqlpage.version(''full'')` returns a JSON object
+with the details of the build of SQLPage that is running, which is useful in bug reports:
+
+```json
+{
+  "version": "0.34.0",
+  "git_commit": "3f2a1c9d8e7b",
+  "build_date": "2025-03-01",
+  "features": [],
+  "target": "x86_64-unknown-linux-gnu"
+}
+```
+
+The same information is displayed by `sqlpage --version`, and returned by the `/sqlpage/version` page
+when the [administration pages](https://github.com/sqlpage/SQLPage/blob/main/configuration.md) are enabled.
+'
+WHERE "name" = 'version';
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'version',
+        1,
+        'detail',
+        'Optional. `''full''` to get the details of the build as JSON instead of the version number.',
+        'TEXT'
+    );
@@ -0,0 +1,37 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('xlsx', 'file-spreadsheet', 'Lets the user download data as an Excel spreadsheet (`.xlsx` file).
+Each column from the items in the component will map to a column in the spreadsheet,
+and the first row of the sheet will contain the column names, with filters enabled.
+
+Numbers and booleans are stored as such in the spreadsheet, so that they can be used in Excel formulas.
+Text values that look like dates (`2024-01-31`) or dates with a time (`2024-01-31 12:00:00` or `2024-01-31T12:00:00`)
+are stored as dates. Other values are stored as text.
+
+This is a **header component**: it must be the first component of the page, and it triggers the download of the file directly on page load.
+The rows are streamed from the database to the browser as they are produced,
+which makes it possible to export large tables without loading them in memory.
+A spreadsheet can contain at most 1,048,576 rows and 16,384 columns.
+', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'xlsx', * FROM (VALUES
+    ('filename', 'The name of the file that should be downloaded (without the extension).', 'TEXT', TRUE, TRUE),
+    ('title', 'Used as the file name when no filename is given.', 'TEXT', TRUE, TRUE),
+    ('sheet_name', 'The name of the sheet in the spreadsheet. "Sheet1" by default. It cannot be longer than 31 characters, or contain any of the characters [ ] : * ? / \', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('xlsx', '
+### Creating an Excel download URL
+
+Create a page that will trigger the download of a spreadsheet when the user visits it.
+
+#### `xlsx_download.sql`
+
+```sql
+select ''xlsx'' as component, ''sales_report'' as filename, ''Sales'' as sheet_name;
+select order_date, customer, amount FROM orders;
+```
+
+#### `index.sql`
+',
+        json('[{"component":"button"}, {"title": "Download the sales report", "link": "/examples/xlsx_download.sql", "icon": "file-spreadsheet"}]'));
@@ -0,0 +1,47 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('ical', 'calendar-event', 'Returns the rows of a query as an iCalendar feed (`.ics` file), the format understood by calendar applications.
+
+Each row is an event. Users can subscribe to the URL of the page in Google Calendar, Outlook, Apple Calendar or Thunderbird,
+and the events of your database will appear in their calendar, updated every time the application refreshes the feed.
+
+This is a **header component**: it must be the first component of the page, and the page should not contain any other component.
+
+Dates can be given as `2024-12-31` for all-day events, or with a time, as in `2024-12-31 18:30:00`.
+Times without a time zone are displayed at the same local time for everyone.
+Times with a time zone, such as `2024-12-31T18:30:00+02:00`, are converted to the local time of each user.
+', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'ical', * FROM (VALUES
+    -- top level
+    ('title', 'Name of the calendar, displayed by the calendar applications that support it.', 'TEXT', TRUE, TRUE),
+    ('description', 'Description of the calendar, displayed by the calendar applications that support it.', 'TEXT', TRUE, TRUE),
+    ('filename', 'When set, the calendar is downloaded as a file with this name, instead of being displayed by the browser. The .ics extension is added if the name has none.', 'TEXT', TRUE, TRUE),
+    -- item level
+    ('summary', 'Title of the event.', 'TEXT', FALSE, FALSE),
+    ('start', 'Start of the event: a date for all-day events, or a date and time.', 'TEXT', FALSE, FALSE),
+    ('end', 'End of the event. For all-day events, this is the day after the last day of the event.', 'TEXT', FALSE, TRUE),
+    ('location', 'Where the event takes place.', 'TEXT', FALSE, TRUE),
+    ('description', 'Longer description of the event.', 'TEXT', FALSE, TRUE),
+    ('url', 'Link to a page with more information about the event.', 'URL', FALSE, TRUE),
+    ('uid', 'Unique identifier of the event, such as its id in your database. Calendar applications use it to update events that changed instead of duplicating them. When it is not set, an identifier is computed from the start and summary of the event.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description) VALUES
+    ('ical', '
+### A subscribable calendar of events
+
+#### `events.ics.sql`
+
+```sql
+select ''ical'' as component, ''Company events'' as title;
+select
+    title as summary,
+    starts_at as start,
+    ends_at as "end",
+    room as location,
+    ''event-'' || id as uid
+from events;
+```
+
+Users can then subscribe to `https://example.com/events.ics.sql` in their calendar application.
+');
@@ -0,0 +1,40 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('xml', 'file-code', 'Returns the rows of a query as an XML document, for systems that only consume XML.
+
+The document contains a root element, with one element per row.
+Each column of a row becomes a child element of the row element, named after the column,
+or an attribute of the row element if it is listed in `attributes`.
+Columns that contain JSON objects or arrays become nested elements, and `NULL` values are omitted.
+Characters that are not allowed in XML names, such as spaces, are replaced by underscores in element names.
+
+This is a **header component**: it must be the first component of the page, and the page should not contain any other component.
+', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'xml', * FROM (VALUES
+    ('root', 'Name of the root element of the document. "rows" by default.', 'TEXT', TRUE, TRUE),
+    ('row', 'Name of the element that contains each row. "row" by default.', 'TEXT', TRUE, TRUE),
+    ('attributes', 'Comma-separated list of columns that are written as attributes of the row element instead of child elements, such as ''id,status''.', 'TEXT', TRUE, TRUE),
+    ('filename', 'When set, the document is downloaded as a file with this name, instead of being displayed by the browser. The .xml extension is added if the name has none.', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description) VALUES
+    ('xml', '
+### Exporting orders to XML
+
+#### `orders.xml.sql`
+
+```sql
+select ''xml'' as component, ''orders'' as root, ''order'' as row, ''id'' as attributes;
+select id, customer, total from orders;
+```
+
+returns
+
+```xml
+<?xml version="1.0" encoding="UTF-8"?>
+<orders>
+<order id="1"><customer>Alice</customer><total>42.5</total></order>
+<order id="2"><customer>Bob</customer><total>12</total></order>
+</orders>
+```
+');
@@ -0,0 +1,41 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('parquet', 'table-export', 'Downloads the rows of a query as an [Apache Parquet](https://parquet.apache.org/) file,
+a compressed columnar format that keeps the type of each column.
+
+Parquet files can be opened directly by data analysis tools such as pandas (`pandas.read_parquet`), Polars, DuckDB or Spark,
+which makes this component a convenient way to let data scientists pull the datasets behind your dashboards.
+
+The type of each column is detected from the values of the first 10 000 rows:
+numbers, booleans, dates (`2024-12-31`), timestamps (`2024-12-31 18:30:00`, or with a time zone, which is stored in UTC),
+JSON objects and arrays, and text. Columns that contain values of different types are stored as text.
+All columns are nullable. The column names and types are taken from the first row.
+
+This component is only available in versions of SQLPage built with the `parquet` cargo feature:
+`cargo build --release --features parquet`.
+
+This is a **header component**: it must be the first component of the page, and the page should not contain any other component.
+If an error occurs while the file is being generated, the end of the file is not written, so that an incomplete dataset cannot be read by mistake.
+', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'parquet', * FROM (VALUES
+    ('filename', 'Name of the downloaded file. The .parquet extension is added if the name has none.', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description) VALUES
+    ('parquet', '
+### Downloading a dataset
+
+#### `sales.parquet.sql`
+
+```sql
+select ''parquet'' as component, ''sales'' as filename;
+select sale_date, product, quantity, unit_price from sales;
+```
+
+The file can then be loaded in Python with
+
+```python
+import pandas as pd
+sales = pd.read_parquet("https://example.com/sales.parquet.sql")
+```
+');
@@ -0,0 +1,53 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'timezone',
+        '0.34.0',
+        'world',
+        'Returns the time zone of the visitor, as an [IANA time zone name](https://en.wikipedia.org/wiki/List_of_tz_database_time_zones) such as `Europe/Paris`.
+
+SQLPage detects the time zone of the browser and stores it in a cookie named `sqlpage_timezone`.
+On the first page a visitor opens, before the cookie is set, this returns the `default_timezone` of the
+[configuration](https://github.com/sqlpage/SQLPage/blob/main/configuration.md), or `UTC` if it is not set.
+
+### Example
+
+```sql
+select ''text'' as component, ''Your time zone is '' || sqlpage.timezone() as contents;
+```
+'
+    ),
+    (
+        'to_timezone',
+        '0.34.0',
+        'clock',
+        'Converts a timestamp to a time zone, which is the [time zone of the visitor](?function=timezone#function) by default.
+
+Timestamps are best stored in UTC in the database, and displayed in the local time of the person reading them.
+This function does the conversion, so that the same page shows the right hour to visitors from Paris and from Tokyo.
+
+### Example
+
+```sql
+select ''list'' as component;
+select title, ''Published on '' || sqlpage.to_timezone(published_at) as description from posts;
+```
+
+For a visitor in Paris, a post published at `2024-07-14 10:00:00` is described as `Published on 2024-07-14T12:00:00+02:00`.
+
+### Details
+
+ - The first argument is a timestamp, such as `2024-07-14 10:00:00` or `2024-07-14T10:00:00+05:00`.
+   Timestamps without a time zone are considered to be in UTC.
+ - The second, optional, argument is the name of the time zone to convert to, such as `America/New_York`.
+ - The result is in the [RFC 3339](https://datatracker.ietf.org/doc/html/rfc3339) format, which contains the offset of the time zone.
+   To display it in another format in a custom component, use the `to_timezone` handlebars helper, which accepts a format string.
+ - `NULL` is returned when the timestamp is `NULL`, and an error is raised when it is not a valid timestamp.
+'
+    );
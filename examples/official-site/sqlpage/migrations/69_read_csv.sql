@@ -0,0 +1,67 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'read_csv',
+        '0.34.0',
+        'file-spreadsheet',
+        'Reads a CSV file, such as a file uploaded by the user, and returns its rows as a JSON array.
+
+Each row is a JSON object whose keys are the column names from the first line of the file.
+Use your database''s JSON functions to insert the rows into a table, validate them, or display them.
+
+### Example: bulk import of products
+
+#### `upload_products.sql`
+
+```sql
+select ''form'' as component, ''import_products.sql'' as action;
+select ''file'' as type, ''products'' as name, ''text/csv'' as accept;
+```
+
+#### `import_products.sql`
+
+In SQLite:
+
+```sql
+set rows = sqlpage.read_csv(sqlpage.uploaded_file_path(''products''));
+
+insert into products (name, price)
+select value->>''name'', cast(value->>''price'' as real)
+from json_each($rows);
+
+select ''redirect'' as component, ''products.sql'' as link;
+```
+
+In PostgreSQL, use `from json_array_elements($rows::json) as value`.
+
+Unlike [`COPY ... FROM`](?component=form#component), which inserts the file directly into a table,
+this lets you transform the values, insert them into several tables, or show them to the user for confirmation before they are saved.
+
+### Options
+
+The second, optional, argument is a JSON object with the following properties:
+
+ - `delimiter`: the character that separates the columns. When it is not set, the most frequent of `,`, `;`, tab and `|` in the first line is used,
+   so that files exported from spreadsheets in any language are read correctly.
+ - `quote`: the character that surrounds values containing the delimiter. `"` by default.
+ - `header`: set it to `false` for files without a header line. Each row is then an array of values instead of an object.
+ - `encoding`: the encoding of the file, such as `utf-8`, `utf-16` or `windows-1252`. When it is not set, files that start with a byte order mark are read as UTF-8 or UTF-16,
+   valid UTF-8 files as UTF-8, and other files as Windows-1252, the encoding used by Excel on Windows.
+
+```sql
+set rows = sqlpage.read_csv(sqlpage.uploaded_file_path(''data''), json_object(''delimiter'', ''|'', ''header'', false));
+```
+
+### Errors
+
+When a line of the file does not have as many values as the first line, the error message tells which line is wrong:
+`Line 12 of the CSV file has 2 fields, but the first line has 3`.
+All values are returned as text; the file is read entirely, so invalid files are rejected before anything is inserted.
+'
+    );
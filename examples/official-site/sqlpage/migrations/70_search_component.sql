@@ -0,0 +1,57 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('search', 'search', 'A search box bound to a URL parameter.
+The page is reloaded with the search term in the URL (`?q=...` by default), so that the following queries can filter their results with it,
+and the occurrences of the search term are highlighted in the results.
+Optionally, the search is submitted automatically while the user types, and the component can display its own list of results.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'search', * FROM (VALUES
+    ('name', 'Name of the URL parameter that contains the search term. Defaults to "q", which can be read with $q in the following queries.', 'TEXT', TRUE, TRUE),
+    ('value', 'The current search term, displayed in the search box and highlighted in the results. Usually the URL parameter itself, such as $q.', 'TEXT', TRUE, TRUE),
+    ('placeholder', 'Text displayed in the search box when it is empty. Defaults to "Search…".', 'TEXT', TRUE, TRUE),
+    ('action', 'URL of the page that displays the results. Defaults to the current page.', 'URL', TRUE, TRUE),
+    ('debounce', 'Submit the search automatically when the user stops typing for this number of milliseconds, instead of waiting for the Enter key. 300 is a good value.', 'INTEGER', TRUE, TRUE),
+    ('highlight', 'Whether to highlight the search term in the results of this component and of the component that follows it. Defaults to true.', 'BOOLEAN', TRUE, TRUE),
+    ('autofocus', 'Focus the search box when the page is loaded.', 'BOOLEAN', TRUE, TRUE),
+    ('icon', 'Name of the icon displayed in the search box. Defaults to "search".', 'ICON', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('title', 'Title of a search result.', 'TEXT', FALSE, TRUE),
+    ('description', 'Text displayed below the title of a search result.', 'TEXT', FALSE, TRUE),
+    ('link', 'URL opened when clicking on the search result.', 'URL', FALSE, TRUE),
+    ('icon', 'Name of an icon displayed next to the search result.', 'ICON', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the search result.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('search', '
+### Full-text search in a table
+
+The search box sets the `q` URL parameter, which the next query uses to filter its rows.
+The matches are highlighted in the table that follows the search box, and the search is submitted
+300 milliseconds after the user stops typing.
+
+```sql
+select ''search'' as component, $q as value, 300 as debounce;
+
+select name, description from products
+where $q is null or name like ''%'' || $q || ''%'' or description like ''%'' || $q || ''%'';
+```', json('[
+        {"component":"search", "value": "sql", "debounce": 300},
+        {"component":"table"},
+        {"name": "SQLPage", "description": "Build web applications in SQL"},
+        {"name": "PostgreSQL", "description": "An open-source relational database"}
+    ]')),
+    ('search', '
+### Search results in the component itself
+
+Rows returned after the search component are displayed as a list of results below the search box.
+
+```sql
+select ''search'' as component, $q as value, ''Search the documentation'' as placeholder;
+select title, description, link, icon from pages
+where title like ''%'' || $q || ''%'';
+```', json('[
+        {"component":"search", "value": "comp", "placeholder": "Search the documentation"},
+        {"title": "Components", "description": "The list of all SQLPage components", "link": "/documentation.sql", "icon": "components"},
+        {"title": "Custom components", "description": "Create your own components with handlebars", "link": "/custom_components.sql", "icon": "code"}
+    ]'));
@@ -0,0 +1,41 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'form', * FROM (VALUES
+    ('options_source', 'For select fields. URL of a SQL file that returns the options of the field as the user types, instead of loading all of them with the page. The file receives the text typed by the user in the $search parameter, and must use the json component to return objects with a value and a label. When editing an existing record, pass the current value in `value`; to display its label instead of the raw value, also return the selected option in `options`.', 'URL', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('form', '
+### Autocomplete over a large table
+
+When a select field references a large table (customers, products, ...), loading all the options with the page is not practical.
+With `options_source`, the options are fetched from another SQL file as the user types.
+
+The current value of the field is kept when editing an existing record, and can be labeled by returning it in `options`:
+
+```sql
+select ''form'' as component;
+select
+    ''customer_id'' as name,
+    ''select'' as type,
+    ''customers_search.sql'' as options_source,
+    customer_id as value,
+    json_array(json_object(''value'', customer_id, ''label'', customer_name)) as options
+from orders natural join customers
+where order_id = $id;
+```
+
+`customers_search.sql` receives what the user typed in `$search`:
+
+```sql
+select ''json'' as component;
+select id as value, name as label
+from customers
+where name like $search || ''%''
+order by name
+limit 50;
+```
+', json('[{"component":"form", "action":"examples/show_variables.sql"},
+    {"name": "component", "type": "select", "value": "form",
+    "options_source": "examples/from_component_options_source.sql",
+    "options": [{"value": "form", "label": "form"}],
+    "description": "Start typing the name of another component, like ''map'' or ''chart''..."
+    }]'));
@@ -0,0 +1,40 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT c.column1, x.* FROM (VALUES
+    ('load_more', 'URL of the next page of rows, usually the current page with a different cursor or page parameter, such as ''?after='' || max(id). A "Load more" button is displayed after the rows. Clicking it fetches the page without its shell, and appends its rows to this component, without reloading the page. Leave it null when there are no more rows.', 'URL', TRUE, TRUE),
+    ('load_more_text', 'Text of the button that loads the next rows. Defaults to "Load more".', 'TEXT', TRUE, TRUE),
+    ('infinite_scroll', 'Load the next rows automatically when the user scrolls to the end of the component, instead of waiting for a click on the "Load more" button. Requires load_more.', 'BOOLEAN', TRUE, TRUE)
+) x, (VALUES ('list'), ('table'), ('card')) c;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('list', '
+### Infinite scroll
+
+Long feeds can be loaded one page at a time.
+The `load_more` link points to the same page with a cursor: the id of the last row displayed.
+When it is loaded, only the rows of the list are added to the current page.
+
+```sql
+set after = coalesce($after, 0);
+
+select
+    ''list'' as component,
+    true as infinite_scroll,
+    (
+        select ''?after='' || max(id) from (
+            select id from posts where id > $after order by id limit 20
+        ) as page
+        having count(*) = 20
+    ) as load_more;
+
+select title, excerpt as description, ''post.sql?id='' || id as link
+from posts
+where id > $after
+order by id
+limit 20;
+```
+
+The `having` clause hides the button on the last page, when fewer than 20 rows are left.
+', json('[
+        {"component":"list", "load_more": "?after=2"},
+        {"title":"Getting started with SQLPage", "description": "Build your first website in five minutes"},
+        {"title":"Forms and user input", "description": "Collect and store data with the form component"}
+    ]'));
@@ -0,0 +1,31 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('tab', 'target', 'Id of an element of the page, such as a component with an `id`, that is displayed only when this tab is active. Switching between tabs that have a target happens in the browser, without reloading the page. The active tab is the one whose target is in the URL hash, or the one marked as active, or the first one.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('tab', '
+### Client-side tabs
+
+When all the sections of a dashboard are cheap to compute, they can be sent in a single page, and the tabs switch between them instantly, without reloading the page.
+Give an `id` to the component of each section, and set it as the `target` of the corresponding tab.
+
+```sql
+select ''tab'' as component;
+select ''Sales'' as title, ''sales'' as target;
+select ''Stock'' as title, ''stock'' as target;
+
+select ''big_number'' as component, ''sales'' as id;
+select ''Revenue'' as title, sum(amount) as value from orders;
+
+select ''table'' as component, ''stock'' as id;
+select name, quantity from products;
+```
+', json('[
+        {"component": "tab"},
+        {"title": "Sales", "target": "tab_example_sales", "icon": "chart-bar"},
+        {"title": "Stock", "target": "tab_example_stock", "icon": "box"},
+        {"component": "big_number", "id": "tab_example_sales"},
+        {"title": "Revenue", "value": 12345, "unit": "$"},
+        {"component": "table", "id": "tab_example_stock"},
+        {"name": "Keyboard", "quantity": 12},
+        {"name": "Mouse", "quantity": 31}
+    ]'));
@@ -0,0 +1,72 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('modal', 'app-window', 'A dialog box displayed on top of the page.
+It is hidden when the page loads, and opened by a button of the [button component](?component=button) that has its id in `modal`.
+A modal can contain a text, another page embedded in it (such as a form that posts to another page), and buttons.
+Use it to confirm destructive actions, such as deletions, without writing any JavaScript.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'modal', * FROM (VALUES
+    ('id', 'Identifier of the modal, used in the `modal` property of the button that opens it.', 'TEXT', TRUE, FALSE),
+    ('title', 'Title displayed at the top of the modal.', 'TEXT', TRUE, TRUE),
+    ('contents', 'Text displayed in the modal.', 'TEXT', TRUE, TRUE),
+    ('contents_md', 'Text displayed in the modal, in Markdown.', 'TEXT', TRUE, TRUE),
+    ('embed', 'URL of a page whose contents are displayed in the modal, without its shell. The page is loaded when the modal is opened for the first time. Useful to display a form in the modal.', 'URL', TRUE, TRUE),
+    ('open', 'Open the modal when the page loads, for instance to display a message after a form was submitted.', 'BOOLEAN', TRUE, TRUE),
+    ('size', 'Size of the modal: sm, lg, or xl.', 'TEXT', TRUE, TRUE),
+    ('color', 'Color of the bar at the top of the modal, such as red for a dangerous action.', 'COLOR', TRUE, TRUE),
+    ('close', 'Text of the button that closes the modal. Defaults to "Close".', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the modal.', 'TEXT', TRUE, TRUE),
+    ('title', 'Text of a button displayed at the bottom of the modal.', 'TEXT', FALSE, FALSE),
+    ('link', 'URL opened when clicking on the button.', 'URL', FALSE, FALSE),
+    ('method', 'Set to POST to send a POST request to the link, instead of simply opening it. Use it for actions that modify data, such as deletions, so that they cannot be triggered by following a link.', 'TEXT', FALSE, TRUE),
+    ('color', 'Color of the button. Defaults to primary.', 'COLOR', FALSE, TRUE),
+    ('icon', 'Name of an icon displayed on the button.', 'ICON', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the button.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('button', 'modal', 'Id of a modal component of the page, which is opened when the button is clicked.', 'TEXT', FALSE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('modal', '
+### Confirm before deleting
+
+The delete button opens a modal, and the deletion only happens when the user confirms it.
+The confirmation sends a POST request to `delete_user.sql`, which deletes the user and redirects back.
+
+```sql
+select ''button'' as component;
+select ''Delete user'' as title, ''red'' as color, ''trash'' as icon, ''confirm_delete'' as modal;
+
+select
+    ''modal'' as component,
+    ''confirm_delete'' as id,
+    ''Delete '' || name || ''?'' as title,
+    ''red'' as color,
+    ''This cannot be undone.'' as contents,
+    ''Cancel'' as close
+from users where id = $id;
+select ''Delete'' as title, ''delete_user.sql?id='' || $id as link, ''POST'' as method, ''red'' as color;
+```
+', json('[
+        {"component": "button"},
+        {"title": "Delete user", "color": "red", "icon": "trash", "modal": "confirm_delete"},
+        {"component": "modal", "id": "confirm_delete", "title": "Delete Ophir?", "color": "red", "contents": "This cannot be undone.", "close": "Cancel"},
+        {"title": "Delete", "link": "?component=modal", "method": "POST", "color": "red"}
+    ]')),
+    ('modal', '
+### A form in a modal
+
+The contents of `embed` are loaded when the modal opens.
+Here, the form of another page is displayed in the modal, and posts its data to that page.
+
+```sql
+select ''button'' as component;
+select ''New user'' as title, ''user-plus'' as icon, ''new_user'' as modal;
+
+select ''modal'' as component, ''new_user'' as id, ''New user'' as title, ''user_form.sql'' as embed;
+```
+', json('[
+        {"component": "button"},
+        {"title": "New user", "icon": "user-plus", "modal": "new_user"},
+        {"component": "modal", "id": "new_user", "title": "New user", "embed": "/examples/modal_form.sql"}
+    ]'));
@@ -0,0 +1,19 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('breadcrumb', 'path', 'A URL path, usually sqlpage.path(), from which the links of the breadcrumb are generated: one link for the home page, one for each directory, and one for the current page. Their titles are the names of the directories and files, without the .sql extension, and with dashes and underscores replaced by spaces. Rows are added after the generated links.', 'TEXT', TRUE, TRUE),
+    ('breadcrumb', 'home_title', 'Title of the link to the home page, when using path. Defaults to "Home".', 'TEXT', TRUE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('breadcrumb', '
+### Automatic breadcrumb
+
+On sites with multiple levels of directories, the breadcrumb can be generated from the path of the current page.
+Put this in a file that every page includes, with [`sqlpage.run_sql`](/functions.sql?function=run_sql):
+
+```sql
+select ''breadcrumb'' as component, sqlpage.path() as path;
+```
+
+On the page `/products/garden-tools/lawn_mower.sql`, it displays:
+', json('[
+        {"component":"breadcrumb", "path": "/products/garden-tools/lawn_mower.sql"}
+    ]'));
@@ -0,0 +1,48 @@
+UPDATE parameter SET description = 'Adds a menu item in the navigation bar at the top of the page. The menu item will have the specified name, and will link to as .sql file of the same name.
+A dropdown can be generated by passing a json object with a `title` and `submenu` properties. The entries of a submenu can have their own `submenu`, displayed as a titled section of the dropdown.
+Menu items and submenu entries can have a `link`, an `icon`, an `image`, a `target`, and an `active` property to highlight the current page.
+When no entry is marked as active, the entries that link to the current page are highlighted automatically.
+The property can be repeated, or given as a JSON array, which makes it possible to generate the whole menu from a database table.'
+WHERE component = 'shell' AND name = 'menu_item';
+
+INSERT INTO example(component, description, properties) VALUES
+    ('shell', '
+### Navigation menu generated from the database
+
+The menu of a site can be stored in a table, and the shell can generate it with a single query.
+Here, each row of the `menu` table has a title, a link, an icon, and a parent menu.
+
+```sql
+select
+    ''shell'' as component,
+    ''My shop'' as title,
+    (
+        select json_group_array(json_object(
+            ''title'', parent.title,
+            ''icon'', parent.icon,
+            ''link'', parent.link,
+            ''submenu'', json((
+                select json_group_array(json_object(''title'', child.title, ''link'', child.link, ''icon'', child.icon))
+                from menu as child where child.parent_id = parent.id
+            ))
+        ))
+        from menu as parent where parent.parent_id is null
+    ) as menu_item;
+```
+
+The links to the current page are highlighted automatically.
+', json('[{
+            "component": "shell",
+            "title": "My shop",
+            "icon": "building-store",
+            "menu_item": [
+                {"title": "Products", "icon": "box", "submenu": [
+                    {"title": "Tools", "submenu": [
+                        {"title": "Hammers", "link": "/hammers.sql", "icon": "hammer"},
+                        {"title": "Saws", "link": "/saws.sql"}
+                    ]},
+                    {"title": "Garden", "link": "/garden.sql", "icon": "plant"}
+                ]},
+                {"title": "Orders", "icon": "shopping-cart", "link": "/orders.sql"}
+            ]
+        }]'));
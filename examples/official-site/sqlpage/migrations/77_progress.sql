@@ -0,0 +1,46 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('progress', 'progress', 'Progress bars that show how far values are from their target, such as the completion of tasks or objectives.
+Each row is a bar. Together with the [big_number](?component=big_number) component, it makes it possible to build KPI dashboards with simple queries.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'progress', * FROM (VALUES
+    ('title', 'Title of the card that contains the progress bars.', 'TEXT', TRUE, TRUE),
+    ('size', 'Height of the bars: sm or lg.', 'TEXT', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('title', 'Name of the value measured by the bar.', 'TEXT', FALSE, TRUE),
+    ('value', 'Current value. Without a target, it is a percentage between 0 and 100.', 'REAL', FALSE, FALSE),
+    ('target', 'The value that corresponds to a full bar. Defaults to 100.', 'REAL', FALSE, TRUE),
+    ('unit', 'Unit of the value and the target, such as € or km.', 'TEXT', FALSE, TRUE),
+    ('description', 'Text displayed below the bar.', 'TEXT', FALSE, TRUE),
+    ('color', 'Color of the bar.', 'COLOR', FALSE, TRUE),
+    ('icon', 'Name of an icon displayed before the title.', 'ICON', FALSE, TRUE),
+    ('id', 'ID attribute added to the bar in HTML.', 'TEXT', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the bar.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('big_number', 'target', 'The objective for the value. It is displayed next to the value, and a progress bar shows how close the value is to the target, unless progress_percent is set.', 'REAL', FALSE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('progress', '
+### Objectives of the quarter
+
+```sql
+select ''progress'' as component, ''Objectives'' as title;
+select ''New customers'' as title, count(*) as value, 200 as target, ''users'' as icon
+from customers where created_at > date(''now'', ''start of month'', ''-2 months'');
+select ''Revenue'' as title, sum(amount) as value, 50000 as target, ''€'' as unit, ''green'' as color
+from orders where created_at > date(''now'', ''start of month'', ''-2 months'');
+select ''Test coverage'' as title, 87 as value, ''yellow'' as color, ''Measured on the main branch'' as description;
+```
+', json('[
+        {"component": "progress", "title": "Objectives"},
+        {"title": "New customers", "value": 142, "target": 200, "icon": "users"},
+        {"title": "Revenue", "value": 41250, "target": 50000, "unit": "€", "color": "green"},
+        {"title": "Test coverage", "value": 87, "color": "yellow", "description": "Measured on the main branch"}
+    ]')),
+    ('big_number', 'A big number with a target. The progress bar is computed from the value and the target.', json('[
+        {"component": "big_number", "columns": 2},
+        {"title": "Monthly sales", "value": 1530, "target": 2000, "unit": "$", "progress_color": "green"},
+        {"title": "Open tickets", "value": 12, "target": 50, "color": "orange"}
+    ]'));
@@ -0,0 +1,27 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('timeline', 'timestamp', 'When the event happened, as a timestamp stored in UTC, such as 2024-07-14T10:00:00Z. It is displayed in the time zone of the visitor, with date_format. Use it instead of date for activity feeds and audit histories.', 'TIMESTAMP', FALSE, TRUE),
+    ('timeline', 'date_format', 'How the timestamps of the events are displayed, with chrono format specifiers (docs.rs/chrono/latest/chrono/format/strftime). Defaults to %Y-%m-%d %H:%M.', 'TEXT', TRUE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('timeline', '
+### Audit history
+
+Events stored with a UTC `timestamp` are displayed in the local time of each visitor.
+
+```sql
+select ''timeline'' as component, ''%d/%m/%Y %H:%M'' as date_format;
+select
+    user_name || '' '' || action as title,
+    details as description,
+    created_at as timestamp,
+    case action when ''deleted'' then ''trash'' else ''edit'' end as icon,
+    case action when ''deleted'' then ''red'' else ''blue'' end as color
+from audit_log
+order by created_at desc
+limit 20;
+```
+', json('[
+        {"component": "timeline", "date_format": "%d/%m/%Y %H:%M"},
+        {"title": "Ophir deleted an invoice", "description": "Invoice #1042", "timestamp": "2024-07-14T10:12:00Z", "icon": "trash", "color": "red"},
+        {"title": "Lovasoa edited a customer", "description": "Changed the address of ACME Corp.", "timestamp": "2024-07-13T16:45:00Z", "icon": "edit", "color": "blue"}
+    ]'));
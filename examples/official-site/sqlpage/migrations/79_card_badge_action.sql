@@ -0,0 +1,29 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'card', * FROM (VALUES
+    ('badge', 'A short text displayed in a badge next to the title of the card, such as "New" or a price.', 'TEXT', FALSE, TRUE),
+    ('badge_color', 'The color of the badge. Defaults to primary.', 'COLOR', FALSE, TRUE),
+    ('action', 'A button displayed at the bottom of the card, as a JSON object with a title, a link, and optionally an icon and a color. Can be repeated, or given as a JSON array, to display multiple buttons.', 'JSON', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('card', '
+### A product catalog
+
+Cards with a badge and action buttons. Add a `top_image` to display a picture of each product.
+
+```sql
+select ''card'' as component, 3 as columns;
+select
+    name as title,
+    description,
+    image_url as top_image,
+    price || '' €'' as badge,
+    json_object(''title'', ''Details'', ''link'', ''product.sql?id='' || id, ''icon'', ''eye'') as action,
+    json_object(''title'', ''Add to cart'', ''link'', ''add_to_cart.sql?id='' || id, ''icon'', ''shopping-cart'', ''color'', ''primary'') as action
+from products;
+```
+', json('[
+        {"component": "card", "columns": 3},
+        {"title": "Hammer", "description": "A solid steel hammer.", "badge": "12 €", "action": [{"title": "Details", "link": "#", "icon": "eye"}, {"title": "Add to cart", "link": "#", "icon": "shopping-cart", "color": "primary"}]},
+        {"title": "Screwdriver", "description": "With six interchangeable bits.", "badge": "New", "badge_color": "green", "action": [{"title": "Details", "link": "#", "icon": "eye"}]},
+        {"title": "Saw", "description": "For wood and plastic.", "badge": "8 €", "action": [{"title": "Details", "link": "#", "icon": "eye"}]}
+    ]'));
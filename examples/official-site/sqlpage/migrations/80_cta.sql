@@ -0,0 +1,40 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('cta', 'speakerphone', 'A call to action: a banner with a title, a text, an optional image, and buttons that invite the visitor to take the next step, such as signing up or downloading something.
+Each row is a button. Use it with the [hero](?component=hero) component to build landing pages without writing HTML.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'cta', * FROM (VALUES
+    ('title', 'The main text of the banner.', 'TEXT', TRUE, TRUE),
+    ('description', 'A subtitle displayed below the title.', 'TEXT', TRUE, TRUE),
+    ('description_md', 'A subtitle displayed below the title, in Markdown.', 'TEXT', TRUE, TRUE),
+    ('image', 'URL of an image displayed next to the text.', 'URL', TRUE, TRUE),
+    ('color', 'Background color of the banner.', 'COLOR', TRUE, TRUE),
+    ('center', 'Center the text and the buttons.', 'BOOLEAN', TRUE, TRUE),
+    ('id', 'ID attribute added to the banner in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the banner.', 'TEXT', TRUE, TRUE),
+    ('title', 'Text of the button.', 'TEXT', FALSE, FALSE),
+    ('link', 'URL opened when clicking the button.', 'URL', FALSE, FALSE),
+    ('icon', 'Name of an icon displayed on the button.', 'ICON', FALSE, TRUE),
+    ('color', 'Color of the button. Defaults to primary.', 'COLOR', FALSE, TRUE),
+    ('outline', 'Display the button with an outline of this color, and no background.', 'COLOR', FALSE, TRUE),
+    ('target', 'Where to open the link, such as _blank for a new tab.', 'TEXT', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the button.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'hero', * FROM (VALUES
+    ('secondary_link', 'URL of a second button, displayed next to the main one with a more discreet style.', 'URL', TRUE, TRUE),
+    ('secondary_link_text', 'Text of the second button. Defaults to "Learn more".', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('cta', 'A centered call to action with two buttons', json('[
+        {"component": "cta", "title": "Build your first website in SQL", "description": "SQLPage is free and open source. Download it and create your first page in five minutes.", "center": true},
+        {"title": "Get started", "link": "/your-first-sql-website/", "icon": "rocket"},
+        {"title": "See the examples", "link": "/examples/tabs/", "outline": "secondary"}
+    ]')),
+    ('cta', 'A colored banner with an image', json('[
+        {"component": "cta", "title": "Join the community", "description_md": "Ask questions and share what you built on **GitHub Discussions**.", "color": "indigo", "image": "https://upload.wikimedia.org/wikipedia/commons/thumb/b/be/Tamias-rufus-001.jpg/640px-Tamias-rufus-001.jpg"},
+        {"title": "Open the discussions", "link": "https://github.com/sqlpage/SQLPage/discussions", "icon": "message", "outline": "light", "target": "_blank"}
+    ]')),
+    ('hero', 'A hero with two buttons', json('[
+        {"component": "hero", "title": "SQLPage", "description": "Build data user interfaces entirely in SQL", "link": "/your-first-sql-website/", "link_text": "Get started", "secondary_link": "/documentation.sql", "secondary_link_text": "Documentation"}
+    ]'));
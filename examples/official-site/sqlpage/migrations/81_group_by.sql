@@ -0,0 +1,32 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('group_by', 'Name of a column used to group the rows. Consecutive rows with the same value in this column are displayed under a header row containing this value, so the query should be sorted by this column. Interactive sorting mixes the groups, so it should not be enabled at the same time.', 'TEXT', TRUE, TRUE),
+    ('group_sum', 'Only with group_by. Name of a numeric column, or list of columns, to sum for each group. The totals are displayed in a row at the end of each group.', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'list', * FROM (VALUES
+    ('group_by', 'Name of a column used to group the items. Consecutive items with the same value in this column are displayed under a header containing this value, so the query should be sorted by this column.', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### Grouped rows with subtotals
+
+Group the expenses by month, and display the total of each month. The rows must be sorted by the grouping column.
+
+```sql
+select ''table'' as component, ''month'' as group_by, ''amount'' as group_sum, ''amount'' as align_right;
+select month, label, amount from expenses order by month, label;
+```
+', json('[
+        {"component": "table", "group_by": "month", "group_sum": "amount", "align_right": ["amount"]},
+        {"month": "January", "label": "Rent", "amount": 1200},
+        {"month": "January", "label": "Groceries", "amount": 340},
+        {"month": "February", "label": "Rent", "amount": 1200},
+        {"month": "February", "label": "Train tickets", "amount": 95}
+    ]')),
+    ('list', 'A contact list grouped by the first letter of the name', json('[
+        {"component": "list", "group_by": "letter"},
+        {"letter": "A", "title": "Alice", "description": "alice@example.com"},
+        {"letter": "A", "title": "Antoine", "description": "antoine@example.com"},
+        {"letter": "B", "title": "Bérénice", "description": "berenice@example.com"}
+    ]'));
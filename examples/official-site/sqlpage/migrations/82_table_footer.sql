@@ -0,0 +1,23 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('_sqlpage_footer', 'Displays the row in bold in the footer of the table, for instance to show totals. Footer rows stay at the bottom of the table when it is sorted or searched.', 'BOOLEAN', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### A table with a total row
+
+Add a row with the totals at the end of the query with `UNION ALL`, and mark it as a footer row.
+
+```sql
+select ''table'' as component, true as sort, ''amount'' as money;
+select client, amount, null as _sqlpage_footer from invoices
+union all
+select ''Total'', sum(amount), true from invoices;
+```
+', json('[
+        {"component": "table", "sort": true, "money": ["amount"], "currency": "EUR"},
+        {"client": "Acme", "amount": 1250},
+        {"client": "Globex", "amount": 830},
+        {"client": "Initech", "amount": 2400},
+        {"client": "Total", "amount": 4480, "_sqlpage_footer": true}
+    ]'));
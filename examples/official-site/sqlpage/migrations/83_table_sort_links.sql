@@ -0,0 +1,26 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('sort_links', 'Makes the column headers links that sort the table on the server. Clicking a header reloads the page with the `sort` URL parameter set to the name of the column, and the `dir` parameter set to `asc` or `desc`. The other URL parameters are kept. Use `$sort` and `$dir` in the `ORDER BY` clause of your query. Unlike `sort`, this works with tables that are too large to be loaded at once.', 'BOOLEAN', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### Sorting on the server
+
+With `sort_links`, the column headers set the `$sort` and `$dir` URL parameters, and the active column is marked with an arrow.
+Only the columns listed in the `ORDER BY` clause can be sorted, so visitors cannot inject arbitrary SQL.
+
+```sql
+select ''table'' as component, true as sort_links;
+select name, age from users
+order by
+    case when $sort = ''age'' and $dir = ''asc'' then age end,
+    case when $sort = ''age'' and $dir = ''desc'' then age end desc,
+    case when $dir = ''desc'' then name end desc,
+    name
+limit 100;
+```
+', json('[
+        {"component": "table", "sort_links": true},
+        {"name": "Alice", "age": 31},
+        {"name": "Bob", "age": 25}
+    ]'));
@@ -0,0 +1,66 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'list_files',
+        '0.34.0',
+        'folder-open',
+        'Lists the files and directories in a directory of the web root, and returns them as a JSON array.
+
+Each element of the array is a JSON object with the following properties:
+
+ - `name`: the name of the file, such as `report.pdf`,
+ - `path`: the path of the file from the web root, such as `/documents/report.pdf`, which can be used directly as a link,
+ - `is_directory`: `true` for directories,
+ - `size`: the size of the file, in bytes,
+ - `mime_type`: the type of the file guessed from its extension, such as `application/pdf`, or `null` for directories,
+ - `modified`: the date of the last modification of the file, such as `2025-03-01T12:30:00Z`.
+
+Files are sorted by name. Hidden files, whose name starts with a dot, and the `sqlpage` configuration directory are not listed.
+
+### Example: a document portal
+
+Put your documents in a `documents` folder next to your `.sql` files, and list them in a table:
+
+```sql
+select ''table'' as component, ''name'' as markdown;
+select
+    format(''[%s](%s)'', value->>''name'', value->>''path'') as name,
+    value->>''size'' as size,
+    value->>''modified'' as modified
+from json_each(sqlpage.list_files(''documents''))
+where not (value->>''is_directory'');
+```
+
+In PostgreSQL, use `from json_array_elements(sqlpage.list_files(''documents'')::json) as value`.
+
+Documents added to the folder appear on the page immediately, without a table to keep in sync with the filesystem.
+
+### Notes
+
+ - Only files from the local filesystem are listed. Files stored in the `sqlpage_files` database table are not included.
+ - The path is relative to the web root. Paths containing `..` are rejected.
+'
+    );
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'list_files',
+        1,
+        'path',
+        'Path of the directory to list, relative to the web root. Use `''/''` to list the web root itself.',
+        'TEXT'
+    );
@@ -0,0 +1,64 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'form', * FROM (VALUES
+    ('bot_protection', 'Protects the form against spam bots, without a CAPTCHA. The form gets a hidden field that humans never see, and a signed token with the time at which the form was displayed. Submissions that fill the hidden field, or that arrive less than 2 seconds after the form was displayed, are rejected with a `400 Bad Request` error before your SQL file is executed, as are the forms displayed more than a day before they are submitted. Bots can also send the form without any of the protection fields: check `sqlpage.bot_protection_passed()` in the page that receives the form. Set it to a number of seconds instead of `true` to change the minimum time it takes to fill the form. Only works with the default `post` method.', 'BOOLEAN', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('form', '
+### Protection against spam
+
+Public contact forms attract automated spam. With `bot_protection`, most spam bots are rejected,
+without bothering your visitors with a CAPTCHA.
+
+```sql
+select ''form'' as component, ''contact.sql'' as action, true as bot_protection;
+select ''email'' as name, ''email'' as type;
+select ''message'' as name, ''textarea'' as type;
+```
+
+In `contact.sql`, refuse the submissions that did not go through the protection:
+
+```sql
+select ''status_code'' as component, 403 as status
+where :email is not null and sqlpage.bot_protection_passed() = ''false'';
+```
+
+The tokens are signed with a key generated when SQLPage starts, so forms displayed before a restart of the server
+must be reloaded before they are submitted.
+', json('[
+        {"component": "form", "bot_protection": true},
+        {"name": "email", "type": "email"},
+        {"name": "message", "type": "textarea"}
+    ]'));
+
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'bot_protection_passed',
+        '0.34.0',
+        'robot-off',
+        'Returns `true` when the current request is the submission of a form with the `bot_protection` property of the [form component](?component=form), and `false` otherwise.
+
+Submissions that fill the hidden honeypot field, that arrive too quickly, or whose token is invalid or more than a day old,
+are rejected before the page is executed.
+But a bot can also send the form without any of the protection fields, as if the form were not protected.
+Pages that receive a protected form should use this function to refuse these submissions.
+It always returns `false` in files executed with [`sqlpage.run_sql`](?function=run_sql).
+
+### Example
+
+```sql
+select ''status_code'' as component, 403 as status
+where :email is not null and sqlpage.bot_protection_passed() = ''false'';
+
+insert into messages (email, message)
+select :email, :message
+where sqlpage.bot_protection_passed() = ''true'';
+```
+'
+    );
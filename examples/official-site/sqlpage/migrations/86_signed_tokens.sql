@@ -0,0 +1,94 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'sign_token',
+        '0.34.0',
+        'signature',
+        'Creates a signed token that contains the given text, and that expires after the given number of seconds.
+
+Signed tokens are made for links sent by email, such as email address confirmation links and password reset links.
+Anyone can read the text of a token, but nobody can modify it, or create a new token, without the `token_signing_secret` of your
+[configuration](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+Use [`sqlpage.verify_token`](?function=verify_token) to read the text of a token that was sent back to you.
+
+Tokens do not need to be stored in the database: everything needed to check them is in the token itself.
+
+### Example: password reset link
+
+```sql
+set payload = (
+    select json_object(''purpose'', ''reset_password'', ''email'', email, ''password_changed_at'', password_changed_at)
+    from users where email = :email
+);
+set token = sqlpage.sign_token($payload, 30 * 60);
+-- send an email containing a link to reset_password.sql?token=...
+```
+
+### Security
+
+ - Put the purpose of the token in its payload, so that a token created for one purpose cannot be used for another one.
+ - The payload is not encrypted: do not put secrets in it.
+ - A token can be used several times until it expires. To make a password reset link usable only once,
+   include the date of the last password change in the payload, and check that it did not change.
+ - Start the links sent by email with the public address of your site, stored in the configuration or in an environment variable
+   such as `sqlpage.environment_variable(''SITE_URL'')`. Do not build them from `sqlpage.header(''host'')`:
+   the `Host` header is chosen by the client, and a forged one would send the token to another server.
+
+A complete example of email verification and password reset is available
+[on GitHub](https://github.com/sqlpage/SQLPage/tree/main/examples/password-reset).
+'
+    ),
+    (
+        'verify_token',
+        '0.34.0',
+        'shield-check',
+        'Returns the text contained in a token created by [`sqlpage.sign_token`](?function=sign_token),
+or `NULL` if the token was modified, was signed with another secret, or has expired.
+
+### Example: reset_password.sql
+
+```sql
+set payload = sqlpage.verify_token($token);
+
+select ''alert'' as component, ''This link is invalid or has expired'' as title, ''red'' as color
+where $payload is null or $payload->>''purpose'' <> ''reset_password'';
+```
+'
+    );
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'sign_token',
+        1,
+        'payload',
+        'The text to put in the token, such as a JSON object containing the purpose of the token and the identifier of the user.',
+        'TEXT'
+    ),
+    (
+        'sign_token',
+        2,
+        'expires_in_seconds',
+        'The number of seconds during which the token is valid. One hour by default.',
+        'INTEGER'
+    ),
+    (
+        'verify_token',
+        1,
+        'token',
+        'The token to check, such as the value of a URL parameter.',
+        'TEXT'
+    );
@@ -0,0 +1,84 @@
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'sign_url',
+        '0.34.0',
+        'link',
+        'Adds a signature to a URL, that makes it valid for the given number of seconds, and that prevents anyone from modifying it.
+
+Signed URLs are time-limited links that you can hand out without asking for a password:
+invoice downloads, unsubscribe links, or approval links sent by email.
+They require the `token_signing_secret` option of your [configuration](https://github.com/sqlpage/SQLPage/blob/main/configuration.md).
+
+The signature is added at the end of the URL, in two parameters named `_sqlpage_expires` and `_sqlpage_signature`.
+When SQLPage receives a request to a signed URL, it checks the signature before executing the page.
+If any part of the path or of the parameters was modified, or if the link has expired, the request is rejected with a `403 Forbidden` error.
+In the page, use [`sqlpage.url_is_signed()`](?function=url_is_signed) to refuse visitors who did not come from a signed link.
+
+Relative URLs are resolved from the URL of the current page, and the signed URL returned by the function is absolute.
+
+### Example: invoice download link
+
+```sql
+select ''button'' as component;
+select ''Download invoice'' as title, sqlpage.sign_url(''invoice.sql?id='' || $id, 24 * 60 * 60) as link;
+```
+
+In `invoice.sql`, the `id` parameter can be trusted, since it cannot be modified without invalidating the signature:
+
+```sql
+select ''redirect'' as component, ''/login.sql'' as link where sqlpage.url_is_signed() = ''false'';
+select ''text'' as component, contents from invoices where id = $id;
+```
+'
+    ),
+    (
+        'url_is_signed',
+        '0.34.0',
+        'shield-lock',
+        'Returns `true` when the current page was opened from a valid link created by [`sqlpage.sign_url`](?function=sign_url), and `false` otherwise.
+
+Links that were modified or that have expired are rejected before the page is executed,
+so this function only needs to distinguish signed links from ordinary requests.
+It always returns `false` in files executed with [`sqlpage.run_sql`](?function=run_sql) and in the pages of a batch,
+since their variables are not covered by the signature.
+
+### Example
+
+```sql
+select ''status_code'' as component, 403 as status where sqlpage.url_is_signed() = ''false'';
+select ''text'' as component, ''This page can only be opened from the link we sent you by email.'' as contents
+where sqlpage.url_is_signed() = ''false'';
+```
+'
+    );
+
+INSERT INTO
+    sqlpage_function_parameters (
+        "function",
+        "index",
+        "name",
+        "description_md",
+        "type"
+    )
+VALUES
+    (
+        'sign_url',
+        1,
+        'url',
+        'The URL to sign, such as `invoice.sql?id=12`. It can be relative to the current page, or absolute.',
+        'TEXT'
+    ),
+    (
+        'sign_url',
+        2,
+        'expires_in_seconds',
+        'The number of seconds during which the link is valid. One hour by default.',
+        'INTEGER'
+    );
@@ -0,0 +1,45 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('live', 'broadcast', 'Displays another page inside the current one, and loads it again each time the database sends a notification to a channel.
+Use it to build dashboards and order boards that stay up to date without reloading the page, and without asking the database for changes every few seconds.
+
+This component requires a [PostgreSQL](https://www.postgresql.org/docs/current/sql-notify.html) database.
+The page to display is given in `embed`. It is loaded with the cookies of each visitor, so it only displays what the visitor is allowed to see.
+SQLPage listens to the `channel` with a single database connection, shared by all the visitors,
+and tells their browsers to load the page again when it receives a notification, with `NOTIFY channel` or `pg_notify(''channel'', '''')`.
+Unquoted channel names are converted to lowercase by PostgreSQL, so use lowercase channel names.
+
+### Example: an order board
+
+In `orders.sql`, display the board:
+
+```sql
+select ''live'' as component, ''order_list.sql'' as embed, ''orders'' as channel;
+```
+
+In `order_list.sql`, list the orders:
+
+```sql
+select ''table'' as component;
+select id, customer, status from orders where status <> ''delivered'' order by id;
+```
+
+And in a migration, notify the channel each time the orders change:
+
+```sql
+create function notify_orders() returns trigger as $$
+begin
+    perform pg_notify(''orders'', '''');
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger orders_changed after insert or update or delete on orders
+for each statement execute function notify_orders();
+```', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'live', * FROM (VALUES
+    ('embed', 'URL of the page to display. It is loaded when the page opens, and each time a notification is sent to the channel.', 'URL', TRUE, FALSE),
+    ('channel', 'Name of the PostgreSQL notification channel to listen to.', 'TEXT', TRUE, FALSE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE)
+) x;
@@ -0,0 +1,78 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'button', * FROM (VALUES
+    ('method', 'Turns the button into a small form, that sends a POST request to `link` when it is clicked, instead of opening it. Use it for actions that modify data, such as "Delete" or "Approve". Set it to `POST`, or to `DELETE`, `PUT` or `PATCH`, which are sent as a POST request with a `_method` field that the target page reads with `:_method`. The form contains a signed token that protects it against cross-site request forgery.', 'TEXT', FALSE, TRUE),
+    ('parameters', 'When `method` is set, a JSON object with the values to send to the target page, such as `{"id": 12}`. They are available in the target page as POST variables, such as `:id`.', 'JSON', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('button', '
+### Action buttons
+
+A simple link should never modify data: browsers, bots and link previews follow links without asking.
+Buttons with a `method` send a POST request instead, and let you add "Delete" or "Approve" buttons next to your data,
+without writing a form.
+
+```sql
+select ''button'' as component, ''sm'' as size;
+select
+    ''approve.sql'' as link,
+    ''POST'' as method,
+    json_object(''id'', id) as parameters,
+    ''Approve'' as title,
+    ''check'' as icon,
+    ''green'' as color
+from leave_requests where id = $id;
+```
+
+And in `approve.sql`:
+
+```sql
+select ''status_code'' as component, 403 as status where sqlpage.csrf_verified() = ''false'';
+update leave_requests set approved = true where id = :id and sqlpage.csrf_verified() = ''true'';
+select ''redirect'' as component, ''leave_requests.sql'' as link;
+```
+
+The form of the button contains a token signed by SQLPage, and bound to a random value
+that SQLPage stores in a `sqlpage_csrf` cookie of the browser that displayed the page.
+A submission is rejected with a `403 Forbidden` error, before `approve.sql` is executed, when its token is invalid, more than a day old,
+or was not sent by the same browser.
+Any POST request is also rejected, with or without a token, when the browser says that it comes from a page of another site.
+This prevents other sites from making your logged-in users click on your buttons without their knowledge.
+Requests that do not say where they come from, such as the calls of other servers, can still be sent without the token:
+[`sqlpage.csrf_verified()`](functions.sql?function=csrf_verified) lets `approve.sql` refuse them.
+', json('[
+        {"component": "button", "size": "sm"},
+        {"link": "#", "method": "POST", "parameters": {"id": 1}, "title": "Approve", "icon": "check", "color": "green"},
+        {"link": "#", "method": "DELETE", "parameters": {"id": 1}, "title": "Delete", "icon": "trash", "color": "red", "outline": "red"}
+    ]'));
+
+INSERT INTO
+    sqlpage_functions (
+        "name",
+        "introduced_in_version",
+        "icon",
+        "description_md"
+    )
+VALUES
+    (
+        'csrf_verified',
+        '0.34.0',
+        'shield-check',
+        'Returns `true` when the current request is the submission of an action button, a [button](?component=button) with a `method`,
+with a valid token, from a page of the same site, and `false` otherwise.
+
+Submissions of action buttons whose token is invalid, more than a day old, or created for another browser,
+are rejected with a `403 Forbidden` error before the page is executed,
+and so are all the POST requests that browsers say come from another site.
+But requests that do not say where they come from can also be sent without any token, as if they came from an ordinary form.
+The target pages of action buttons should use this function to refuse these requests.
+It always returns `false` in files executed with [`sqlpage.run_sql`](?function=run_sql).
+
+### Example
+
+```sql
+select ''status_code'' as component, 403 as status where sqlpage.csrf_verified() = ''false'';
+delete from invoices where id = :id and sqlpage.csrf_verified() = ''true'';
+select ''redirect'' as component, ''invoices.sql'' as link;
+```
+'
+    );
@@ -0,0 +1,43 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('edit_url', 'Adds an edit link at the end of each row. `{id}` in the URL is replaced by the `_sqlpage_id` of the row, such as `edit_user.sql?id={id}`.', 'URL', TRUE, TRUE),
+    ('delete_url', 'Adds a delete button at the end of each row, that sends a POST request to this URL. `{id}` in the URL is replaced by the `_sqlpage_id` of the row, such as `delete_user.sql?id={id}`. The request contains a signed token that protects it against cross-site request forgery.', 'URL', TRUE, TRUE),
+    ('delete_confirmation', 'Message displayed in a confirmation dialog before a row is deleted, such as "Delete this user?".', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'list', * FROM (VALUES
+    ('delete_confirmation', 'Message displayed in a confirmation dialog before the `delete_link` of an item is followed.', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'button', * FROM (VALUES
+    ('confirmation', 'When `method` is set, a message displayed in a confirmation dialog before the action is sent, such as "Approve this request?".', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### Edit and delete buttons
+
+Most administration pages list records, with a way to edit or delete each of them.
+Give each row an `_sqlpage_id`, and set `edit_url` and `delete_url`:
+the table gets a last column with an edit link and a delete button, in which `{id}` is replaced by the id of the row.
+
+```sql
+select
+    ''table'' as component,
+    ''user_form.sql?id={id}'' as edit_url,
+    ''delete_user.sql?id={id}'' as delete_url,
+    ''Delete this user?'' as delete_confirmation;
+select id as _sqlpage_id, name, email from users;
+```
+
+The delete button sends a POST request, so that bots and link previews never delete anything.
+In `delete_user.sql`:
+
+```sql
+delete from users where id = $id;
+select ''redirect'' as component, ''users.sql'' as link;
+```
+', json('[
+        {"component": "table", "edit_url": "#edit-{id}", "delete_url": "#delete-{id}", "delete_confirmation": "Delete this user?"},
+        {"_sqlpage_id": 1, "name": "Ada Lovelace", "email": "ada@example.com"},
+        {"_sqlpage_id": 2, "name": "Alan Turing", "email": "alan@example.com"}
+    ]'));
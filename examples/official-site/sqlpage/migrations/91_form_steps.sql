@@ -0,0 +1,43 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'form', * FROM (VALUES
+    ('previous_values', 'Values of the previous steps of a form split on several pages, usually `sqlpage.variables(''post'')`. They are sent again, with the values of the current step, to the `action` page, that receives the values of all the steps as POST variables. They are signed, so visitors cannot modify them. Only works with the default `post` method.', 'JSON', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('form', '
+### Forms in several steps
+
+Long forms are easier to fill in several steps, on several pages: choose a customer, then add order lines, then confirm.
+Each page displays the form of its step, that submits to the page of the next step.
+With `sqlpage.variables(''post'')` as `previous_values`, the form of each step carries the values of the previous steps,
+so that the last page receives all the values at once, and nothing is saved before the order is confirmed.
+
+In `order_customer.sql`:
+
+```sql
+select ''steps'' as component;
+select ''Customer'' as title, true as active;
+select ''Lines'' as title;
+select ''Confirmation'' as title;
+
+select ''form'' as component, ''order_lines.sql'' as action, ''Next'' as validate;
+select ''customer_id'' as name, ''Customer'' as label, ''select'' as type,
+    (select json_group_array(json_object(''label'', name, ''value'', id)) from customers) as options;
+```
+
+In `order_lines.sql`, the form carries the customer chosen in the previous step:
+
+```sql
+select ''form'' as component, ''order_confirm.sql'' as action, ''Next'' as validate,
+    sqlpage.variables(''post'') as previous_values;
+select ''product'' as name;
+select ''quantity'' as name, ''number'' as type;
+```
+
+And `order_confirm.sql` receives `:customer_id`, `:product` and `:quantity`.
+The values of the previous steps are signed when the page is displayed:
+visitors cannot change the customer they chose after `order_lines.sql` checked it.
+', json('[
+        {"component": "form", "action": "#", "validate": "Next", "previous_values": {"customer_id": "12"}},
+        {"name": "product"},
+        {"name": "quantity", "type": "number"}
+    ]'));
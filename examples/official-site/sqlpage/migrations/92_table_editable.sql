@@ -0,0 +1,32 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('editable', 'Name of a column whose cells can be edited directly in the table. This argument can be repeated multiple times to make multiple columns editable. Each change is sent immediately to `save_url`. Requires an `_sqlpage_id` in each row.', 'TEXT', TRUE, TRUE),
+    ('save_url', 'URL of the page that saves the changes made in the `editable` columns. It receives a POST request with the `_sqlpage_id` of the row in `:id`, the name of the column in `:column`, and the new value in `:value`. What the page displays is shown below the edited cell: a success message, or an error message when the page fails.', 'URL', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### Editing cells directly in the table
+
+For quick corrections, such as prices or stock levels, make some columns `editable`.
+Their cells become inputs, and each change is saved as soon as the visitor leaves the cell, without reloading the page.
+
+```sql
+select ''table'' as component, ''price'' as editable, ''stock'' as editable, ''save_product.sql'' as save_url;
+select id as _sqlpage_id, name, price, stock from products;
+```
+
+In `save_product.sql`, check the column name before using it, since the request can be sent by anyone:
+
+```sql
+update products set price = :value where id = :id and :column = ''price'';
+update products set stock = :value where id = :id and :column = ''stock'';
+select ''text'' as component, ''Saved'' as contents;
+```
+
+The page is rendered without the shell, and what it displays is shown below the cell.
+When it fails, for instance because of a constraint in your database, the cell is marked as invalid, and the error is displayed instead.
+', json('[
+        {"component": "table", "editable": ["price", "stock"], "save_url": "#"},
+        {"_sqlpage_id": 1, "name": "Pen", "price": 1.5, "stock": 120},
+        {"_sqlpage_id": 2, "name": "Notebook", "price": 4.2, "stock": 35}
+    ]'));
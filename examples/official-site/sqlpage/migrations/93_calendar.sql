@@ -0,0 +1,51 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('calendar', 'calendar-month', 'Displays events in a calendar, one month or one week at a time, with buttons to go to the previous and next periods.
+Use it for bookings, shifts, classes, or any other events stored in your database.
+
+Each row is an event, with a `title`, a `start` and an optional `end`.
+The navigation buttons reload the page with the `$start` and `$end` URL parameters set to the first day of the displayed period,
+and to the day after its last day, and with `$view` set to `month` or `week`.
+Use them in your query to load only the events of the displayed period.
+
+Dates without a time zone, such as `2024-07-14` or `2024-07-14 10:30`, are displayed as they are, in the time zone of the visitor.
+An event whose `end` is a date without a time lasts until the end of that day.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'calendar', * FROM (VALUES
+    ('title', 'Title displayed above the calendar.', 'TEXT', TRUE, TRUE),
+    ('view', 'Either `month` (the default) or `week`. Usually `$view`, to let visitors switch between the two.', 'TEXT', TRUE, TRUE),
+    ('start', 'A date in the period to display, usually `$start`. Defaults to the current date of the visitor.', 'TEXT', TRUE, TRUE),
+    ('first_day', 'The first day of the week, from 0 for Sunday to 6 for Saturday. Defaults to 1, Monday.', 'INTEGER', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('title', 'Name of the event.', 'TEXT', FALSE, FALSE),
+    ('start', 'Date or date and time at which the event starts, such as `2024-07-14` or `2024-07-14 10:30`.', 'TIMESTAMP', FALSE, FALSE),
+    ('end', 'Date or date and time at which the event ends. Events without an end last for an instant, and are displayed on the day they start.', 'TIMESTAMP', FALSE, TRUE),
+    ('description', 'Displayed with the title when the mouse is over the event.', 'TEXT', FALSE, TRUE),
+    ('color', 'Color of the event.', 'COLOR', FALSE, TRUE),
+    ('link', 'URL opened when the event is clicked, such as a page with the details of the event.', 'URL', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the event.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('calendar', '
+### Room bookings
+
+```sql
+select ''calendar'' as component, ''Meeting room'' as title, $start as start, coalesce($view, ''month'') as view;
+select
+    title,
+    starts_at as start,
+    ends_at as "end",
+    case when confirmed then ''green'' else ''orange'' end as color,
+    ''booking.sql?id='' || id as link
+from bookings
+where starts_at < coalesce($end, date(''now'', ''start of month'', ''+1 month''))
+  and ends_at >= coalesce($start, date(''now'', ''start of month''));
+```
+', json('[
+        {"component": "calendar", "title": "Meeting room", "start": "2024-07-01"},
+        {"title": "Team meeting", "start": "2024-07-08 10:00", "end": "2024-07-08 11:00", "color": "green"},
+        {"title": "Budget review", "start": "2024-07-11 14:30", "end": "2024-07-11 16:00", "color": "orange"},
+        {"title": "Training", "start": "2024-07-16", "end": "2024-07-18", "color": "blue"},
+        {"title": "Team meeting", "start": "2024-07-22 10:00", "end": "2024-07-22 11:00", "color": "green"}
+    ]'));
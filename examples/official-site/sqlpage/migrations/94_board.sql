@@ -0,0 +1,52 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('board', 'layout-kanban', 'A kanban board: items displayed as cards in columns, one column per status.
+Use it to follow tasks, support tickets, job applications, or anything else that goes through successive stages.
+
+Each row is an item, with a `title` and a `status`. Items with the same status are displayed in the same column.
+Columns appear in the order of the `columns` property, and then in the order in which their statuses first appear in the rows.
+
+When `move_url` is set, visitors can drag items from a column to another.
+Each move is sent to the `move_url` page in a POST request, with the `:id` and the new `:status` of the item.
+If this page returns an error, the item goes back to its column, and the error is displayed above the board.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'board', * FROM (VALUES
+    ('title', 'Title displayed above the board.', 'TEXT', TRUE, TRUE),
+    ('columns', 'Statuses of the columns to display, in order, even when they contain no item. Repeat the property to add several columns, or give a JSON array.', 'TEXT', TRUE, TRUE),
+    ('move_url', 'Page that receives the changes of status when visitors move items, with the `:id` and the new `:status` of the item. Items can only be moved when this is set. It can refuse a move by returning an error, for instance with the [`status_code`](?component=status_code) component.', 'URL', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('title', 'Name of the item.', 'TEXT', FALSE, FALSE),
+    ('status', 'Column in which the item is displayed.', 'TEXT', FALSE, FALSE),
+    ('item_id', 'Identifier of the item, sent to the `move_url` page as `:id` when the item is moved.', 'TEXT', FALSE, TRUE),
+    ('description', 'Text displayed below the title.', 'TEXT', FALSE, TRUE),
+    ('description_md', 'Text displayed below the title, in Markdown.', 'TEXT', FALSE, TRUE),
+    ('color', 'Color of the stripe on the side of the item.', 'COLOR', FALSE, TRUE),
+    ('link', 'URL opened when the title of the item is clicked.', 'URL', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the item.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('board', '
+### Tasks that can be moved between columns
+
+```sql
+select ''board'' as component, ''Sprint'' as title, ''move_task.sql'' as move_url,
+    ''To do'' as columns, ''In progress'' as columns, ''Done'' as columns;
+select id as item_id, title, status, assignee as description,
+    case priority when ''high'' then ''red'' end as color,
+    ''task.sql?id='' || id as link
+from tasks order by priority;
+```
+
+with `move_task.sql`:
+
+```sql
+update tasks set status = :status where id = :id;
+```
+', json('[
+        {"component": "board", "title": "Sprint", "columns": ["To do", "In progress", "Done"]},
+        {"item_id": 1, "title": "Write the documentation", "status": "To do", "description": "Alice"},
+        {"item_id": 2, "title": "Fix the login page", "status": "In progress", "description": "Bob", "color": "red"},
+        {"item_id": 3, "title": "Release version 2", "status": "To do", "description": "Alice"},
+        {"item_id": 4, "title": "Set up backups", "status": "Done", "description": "Carol"}
+    ]'));
@@ -0,0 +1,66 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('tree', 'binary-tree', 'Displays hierarchical data as a tree, in which visitors can expand and collapse the branches.
+Use it for org charts, categories of products, folders and files, or any other table in which rows reference a parent row.
+
+Each row is a node, with an `id`, a `label`, and the `parent_id` of the node it is under.
+Nodes without a `parent_id`, or whose parent is not in the results, are at the root of the tree.
+Rows can be in any order, so the whole tree can be loaded with a single query on the table, or with a recursive query to load only a branch.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'tree', * FROM (VALUES
+    ('title', 'Title displayed above the tree.', 'TEXT', TRUE, TRUE),
+    ('expanded', 'Expand all the branches when the page is loaded. By default, only the branches that contain an `active` node are expanded.', 'BOOLEAN', TRUE, TRUE),
+    ('empty_description', 'Text displayed when the query returns no node. Defaults to "No data".', 'TEXT', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('id', 'Identifier of the node, referenced by the `parent_id` of its children.', 'TEXT', FALSE, FALSE),
+    ('parent_id', 'Identifier of the node under which this node is displayed. Nodes without a parent are at the root of the tree.', 'TEXT', FALSE, TRUE),
+    ('label', 'Text of the node.', 'TEXT', FALSE, FALSE),
+    ('link', 'URL opened when the label is clicked.', 'URL', FALSE, TRUE),
+    ('icon', 'Name of an icon displayed before the label.', 'ICON', FALSE, TRUE),
+    ('description', 'Secondary text displayed after the label.', 'TEXT', FALSE, TRUE),
+    ('expanded', 'Show the children of this node when the page is loaded.', 'BOOLEAN', FALSE, TRUE),
+    ('active', 'Highlight this node, such as the one displayed on the current page, and expand the branches that contain it.', 'BOOLEAN', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the node.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('tree', '
+### Organization chart
+
+```sql
+select ''tree'' as component, ''Organization'' as title, true as expanded;
+select id, manager_id as parent_id, name as label, job_title as description,
+    ''employee.sql?id='' || id as link
+from employees;
+```
+', json('[
+        {"component": "tree", "title": "Organization", "expanded": true},
+        {"id": 1, "label": "Alice", "description": "CEO", "icon": "user-star"},
+        {"id": 2, "parent_id": 1, "label": "Bob", "description": "CTO", "icon": "user"},
+        {"id": 3, "parent_id": 1, "label": "Carol", "description": "CFO", "icon": "user"},
+        {"id": 4, "parent_id": 2, "label": "Dave", "description": "Developer", "icon": "user"},
+        {"id": 5, "parent_id": 2, "label": "Eve", "description": "Developer", "icon": "user"}
+    ]')),
+    ('tree', '
+### A branch of a category tree
+
+A recursive query loads the descendants of the category given in the URL, and marks it as active.
+
+```sql
+select ''tree'' as component;
+with recursive branch as (
+    select * from categories where id = $category
+    union all
+    select categories.* from categories join branch on categories.parent_id = branch.id
+)
+select id, parent_id, name as label, ''?category='' || id as link, id = $category as active
+from branch;
+```
+', json('[
+        {"component": "tree"},
+        {"id": 10, "label": "Electronics", "link": "?category=10", "icon": "folder"},
+        {"id": 11, "parent_id": 10, "label": "Computers", "link": "?category=11", "icon": "folder"},
+        {"id": 12, "parent_id": 11, "label": "Laptops", "link": "?category=12", "active": true},
+        {"id": 13, "parent_id": 11, "label": "Desktops", "link": "?category=13"},
+        {"id": 14, "parent_id": 10, "label": "Phones", "link": "?category=14"}
+    ]'));
@@ -0,0 +1,23 @@
+UPDATE parameter SET description = 'Language of the code, used to highlight its syntax: `sql`, `json`, `html`, `xml`, `javascript`, `typescript`, `rust`, `python`, `bash`, `css`, `toml`, `ini` or `yaml`.
+Code in other languages is displayed without highlighting.'
+WHERE component = 'code' AND name = 'language';
+
+INSERT INTO example(component, description, properties) VALUES (
+    'code',
+    'Displays a query, with its syntax highlighted on the server, and a configuration file.',
+    JSON(
+        '[
+            {"component":"code"},
+            {
+                "title":"Recent orders",
+                "language":"sql",
+                "contents":"-- The ten last orders, with their customer\nSELECT orders.id, customers.name, orders.total\nFROM orders\nJOIN customers ON customers.id = orders.customer_id\nWHERE orders.status <> ''cancelled''\nORDER BY orders.created_at DESC\nLIMIT 10;"
+            },
+            {
+                "title":"sqlpage/sqlpage.json",
+                "language":"json",
+                "contents":"{\n  \"database_url\": \"sqlite://data.db\",\n  \"port\": 8080,\n  \"allow_exec\": false\n}"
+            }
+        ]'
+    )
+);
@@ -0,0 +1,54 @@
+INSERT INTO component(name, icon, description, introduced_in_version) VALUES
+    ('gallery', 'photo', 'A grid of images, with their captions. Clicking an image opens it in a lightbox,
+in which visitors can see the images in a larger size and go from one to the next with the arrows of their keyboard.
+Use it for the products of a shop, the works of a portfolio, or the photos of an event.
+
+To show the images one at a time instead, use the [carousel](?component=carousel) component.', '0.34.0');
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'gallery', * FROM (VALUES
+    ('title', 'Title displayed above the gallery.', 'TEXT', TRUE, TRUE),
+    ('columns', 'Number of images per row on large screens, from 1 to 6. Defaults to 3. Small screens always display two images per row.', 'INTEGER', TRUE, TRUE),
+    ('height', 'Height of the thumbnails, in pixels. By default, thumbnails are in the 4:3 format, and as wide as their column.', 'INTEGER', TRUE, TRUE),
+    ('lightbox', 'Open the images in a lightbox when they are clicked. Defaults to true. When false, clicking an image opens the image file.', 'BOOLEAN', TRUE, TRUE),
+    ('id', 'ID attribute added to the container in HTML.', 'TEXT', TRUE, TRUE),
+    ('class', 'CSS class(es) to add to the container.', 'TEXT', TRUE, TRUE),
+    ('image', 'URL of the image.', 'URL', FALSE, FALSE),
+    ('title', 'Caption displayed below the image.', 'TEXT', FALSE, TRUE),
+    ('description', 'Secondary text displayed below the caption.', 'TEXT', FALSE, TRUE),
+    ('alt', 'Description of the image for visitors who cannot see it. Defaults to the title.', 'TEXT', FALSE, TRUE),
+    ('link', 'URL opened when the image is clicked, such as the page of a product, instead of the lightbox.', 'URL', FALSE, TRUE),
+    ('class', 'CSS class(es) to add to the image card.', 'TEXT', FALSE, TRUE)
+) x;
+
+INSERT INTO parameter(component, name, description, type, top_level, optional) VALUES
+    ('carousel', 'link', 'URL opened when the image is clicked.', 'URL', FALSE, TRUE);
+
+INSERT INTO example(component, description, properties) VALUES
+    ('gallery', '
+### Photos of animals
+
+```sql
+select ''gallery'' as component, ''Wildlife'' as title, 4 as columns;
+select photo_url as image, species as title, place as description from photos;
+```
+', json('[
+        {"component": "gallery", "title": "Wildlife", "columns": 4},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/d/d8/Lynx_lynx-4.JPG/640px-Lynx_lynx-4.JPG", "title": "Eurasian lynx"},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/b/be/Tamias-rufus-001.jpg/640px-Tamias-rufus-001.jpg", "title": "Cliff chipmunk"},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/a/ab/Jumping_spiders_%28Salticidae%29.jpg/640px-Jumping_spiders_%28Salticidae%29.jpg", "title": "Jumping spiders"},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/d/d7/Elefantes_africanos_de_sabana_%28Loxodonta_africana%29%2C_Elephant_Sands%2C_Botsuana%2C_2018-07-28%2C_DD_114-117_PAN.jpg/2560px-Elefantes_africanos_de_sabana_%28Loxodonta_africana%29%2C_Elephant_Sands%2C_Botsuana%2C_2018-07-28%2C_DD_114-117_PAN.jpg", "title": "African elephants", "description": "Elephant Sands, Botswana"}
+    ]')),
+    ('gallery', '
+### Destinations linking to their pages
+
+Images with a `link` open the page of the destination instead of the lightbox.
+
+```sql
+select ''gallery'' as component, 2 as columns, 200 as height;
+select picture as image, name as title, ''trip.sql?id='' || id as link from trips;
+```
+', json('[
+        {"component": "gallery", "columns": 2, "height": 200},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/e/e4/Lac_de_Zoug.jpg/640px-Lac_de_Zoug.jpg", "title": "Lake Zug", "link": "#zug"},
+        {"image": "https://upload.wikimedia.org/wikipedia/commons/thumb/9/99/Penguin_Island_panorama_with_ferry_and_dolphins_in_foreground%2C_March_2023_06.jpg/1280px-Penguin_Island_panorama_with_ferry_and_dolphins_in_foreground%2C_March_2023_06.jpg", "title": "Penguin Island", "link": "#penguin-island"}
+    ]'));
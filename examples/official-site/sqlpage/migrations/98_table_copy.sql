@@ -0,0 +1,22 @@
+INSERT INTO parameter(component, name, description, type, top_level, optional) SELECT 'table', * FROM (VALUES
+    ('copy', 'Add a button that copies the visible rows of the table to the clipboard, with their column names, so that they can be pasted in a spreadsheet. Rows hidden by the search bar are not copied, and sorted rows are copied in their displayed order. Numbers are copied without the formatting of the table.', 'BOOLEAN', TRUE, TRUE),
+    ('copy_text', 'Text of the copy button. Defaults to "Copy".', 'TEXT', TRUE, TRUE)
+) x;
+
+INSERT INTO example(component, description, properties) VALUES
+    ('table', '
+### Copying rows to a spreadsheet
+
+With `copy`, visitors can paste the rows of the table in Excel or another spreadsheet, without exporting a file.
+Combined with `search`, they copy only the rows that match their search.
+
+```sql
+select ''table'' as component, true as copy, true as search, true as sort;
+select name, department, salary from employees;
+```
+', json('[
+        {"component": "table", "copy": true, "search": true, "sort": true},
+        {"name": "Alice", "department": "Sales", "salary": 52000},
+        {"name": "Bob", "department": "Engineering", "salary": 61000},
+        {"name": "Carol", "department": "Sales", "salary": 48500}
+    ]'));
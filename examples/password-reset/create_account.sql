@@ -0,0 +1,18 @@
+insert into users (email, password_hash)
+values (:email, sqlpage.hash_password(:password))
+on conflict (email) do nothing;
+
+-- The link in the email proves that the visitor can read the emails sent to this address.
+-- The purpose is part of the signed payload, so that this token cannot be used to reset the password.
+set token = sqlpage.sign_token(json_object('purpose', 'verify_email', 'email', :email), 24 * 60 * 60);
+
+select 'dynamic' as component, sqlpage.run_sql('send_email.sql', json_object(
+    'to', :email,
+    'subject', 'Confirm your email address',
+    'text', 'Open this link to confirm your email address: '
+        || sqlpage.environment_variable('SITE_URL')
+        || '/verify_email.sql?token=' || sqlpage.url_encode($token)
+)) as properties;
+
+select 'alert' as component, 'Check your inbox' as title,
+    'We sent a confirmation link to ' || :email || '.' as description;
@@ -0,0 +1,2 @@
+select 'form' as component, 'Reset your password' as title, 'send_reset_link.sql' as action, true as bot_protection;
+select 'email' as name, 'email' as type, true as required;
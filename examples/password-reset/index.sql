@@ -0,0 +1,3 @@
+select 'list' as component, 'Password reset and email verification' as title;
+select 'Create an account' as title, 'signup.sql' as link, 'user-plus' as icon;
+select 'I forgot my password' as title, 'forgot_password.sql' as link, 'key' as icon;
@@ -0,0 +1,11 @@
+set payload = sqlpage.verify_token($token);
+
+select 'alert' as component, 'This link is invalid or has expired' as title, 'red' as color
+where $payload is null or $payload->>'purpose' <> 'reset_password';
+
+select 'form' as component, 'Choose a new password' as title, 'save_password.sql' as action
+where $payload->>'purpose' = 'reset_password';
+select 'token' as name, 'hidden' as type, $token as value
+where $payload->>'purpose' = 'reset_password';
+select 'password' as name, 'password' as type, true as required, 8 as minlength
+where $payload->>'purpose' = 'reset_password';
@@ -0,0 +1,11 @@
+-- The token is verified again, since the form could have been submitted by anyone
+set payload = sqlpage.verify_token(:token);
+
+update users
+set password_hash = sqlpage.hash_password(:password), password_changed_at = CURRENT_TIMESTAMP
+where email = $payload->>'email'
+    and password_changed_at = $payload->>'password_changed_at'
+    and $payload->>'purpose' = 'reset_password'
+returning 'redirect' as component, 'index.sql?password_changed' as link;
+
+select 'alert' as component, 'This link is invalid, has expired, or was already used' as title, 'red' as color;
@@ -0,0 +1,19 @@
+-- Sends an email with Mailgun. Called with sqlpage.run_sql and the $to, $subject and $text variables.
+-- See the "sending emails" example to use another email service.
+set email_request = json_object(
+    'url', 'https://api.mailgun.net/v3/' || sqlpage.environment_variable('MAILGUN_DOMAIN') || '/messages',
+    'method', 'POST',
+    'headers', json_object(
+        'Content-Type', 'application/x-www-form-urlencoded',
+        'Authorization', 'Basic ' || sqlpage.environment_variable('MAILGUN_AUTHORIZATION')
+    ),
+    'body',
+        'from=noreply@' || sqlpage.environment_variable('MAILGUN_DOMAIN')
+        || '&to=' || sqlpage.url_encode($to)
+        || '&subject=' || sqlpage.url_encode($subject)
+        || '&text=' || sqlpage.url_encode($text)
+);
+set email_response = sqlpage.fetch($email_request);
+
+select 'alert' as component, 'Unable to send the email' as title, $email_response->>'message' as description, 'red' as color
+where $email_response->>'id' is null;
@@ -0,0 +1,24 @@
+-- The token contains the date of the last password change, so it cannot be used again once the password was changed.
+set reset_payload = (
+    select json_object('purpose', 'reset_password', 'email', email, 'password_changed_at', password_changed_at)
+    from users where email = :email
+);
+set token = sqlpage.sign_token($reset_payload, 30 * 60);
+
+-- The link starts with the configured address of the site, and not with the Host header of the request:
+-- an attacker could request a reset for someone else with a forged Host header,
+-- and receive the token when the victim clicks the link.
+
+select 'dynamic' as component, sqlpage.run_sql('send_email.sql', json_object(
+    'to', :email,
+    'subject', 'Reset your password',
+    'text', 'Open this link in the next 30 minutes to choose a new password: '
+        || sqlpage.environment_variable('SITE_URL')
+        || '/reset_password.sql?token=' || sqlpage.url_encode($token)
+)) as properties
+where $token is not null;
+
+-- The same message is displayed whether the account exists or not,
+-- so that this page cannot be used to find out who has an account.
+select 'alert' as component, 'Check your inbox' as title,
+    'If an account exists for ' || :email || ', we sent it a link to reset the password.' as description;
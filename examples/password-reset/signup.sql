@@ -0,0 +1,3 @@
+select 'form' as component, 'Create an account' as title, 'create_account.sql' as action, true as bot_protection;
+select 'email' as name, 'email' as type, true as required;
+select 'password' as name, 'password' as type, true as required, 8 as minlength;
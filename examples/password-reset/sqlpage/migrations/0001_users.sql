@@ -0,0 +1,7 @@
+CREATE TABLE users (
+    email TEXT PRIMARY KEY,
+    password_hash TEXT NOT NULL,
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Changes every time the password changes, so that a password reset link can only be used once
+    password_changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- verify_token returns NULL when the token was modified or has expired
+set payload = sqlpage.verify_token($token);
+
+update users set email_verified = true
+where email = $payload->>'email' and $payload->>'purpose' = 'verify_email'
+returning
+    'alert' as component,
+    'Your email address is confirmed' as title,
+    'success' as color;
+
+select 'alert' as component, 'This link is invalid or has expired' as title, 'red' as color
+where $payload is null or $payload->>'purpose' <> 'verify_email';
@@ -34,3 +34,33 @@ by creating a file in `' || sqlpage.current_working_directory() || '/sqlpage/sql
 
 SELECT '
 Alternatively, you can create a table called `sqlpage_files` in your database with the following columns: `path`, `contents`, and `last_modified`.' as contents_md;
+
+-- The code component displays a few examples of pages you can write
+select 'title' as component, 'Example pages' as contents, 2 as level;
+select 'code' as component;
+select 'A list of items' as title,
+    'Create a file named `todos.sql` to display the rows of a table:' as description_md,
+    'sql' as language,
+    'SELECT ''list'' AS component, ''My todo list'' AS title;
+SELECT title, description FROM todos;' as contents;
+select 'A form' as title,
+    'Forms send their values to the page in their `action`, which can read them with `:field_name`:' as description_md,
+    'sql' as language,
+    'SELECT ''form'' AS component, ''add_todo.sql'' AS action;
+SELECT ''title'' AS name;
+
+-- in add_todo.sql
+INSERT INTO todos (title) VALUES (:title);
+SELECT ''redirect'' AS component, ''todos.sql'' AS link;' as contents;
+select 'A chart' as title,
+    'Any query can feed a chart:' as description_md,
+    'sql' as language,
+    'SELECT ''chart'' AS component, ''bar'' AS type, ''Todos per user'' AS title;
+SELECT user_name AS label, count(*) AS value FROM todos GROUP BY user_name;' as contents;
+
+-- The button component links to the documentation
+select 'button' as component, 'center' as justify;
+select 'Components' as title, 'https://sql-page.com/documentation.sql' as link, 'book' as icon, 'primary' as color;
+select 'Tutorial' as title, 'https://sql-page.com/your-first-sql-website/' as link, 'school' as icon;
+select 'Examples' as title, 'https://github.com/sqlpage/SQLPage/tree/main/examples' as link, 'code' as icon;
+select 'Configuration' as title, 'https://github.com/sqlpage/SQLPage/blob/main/configuration.md' as link, 'settings' as icon;
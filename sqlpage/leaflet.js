@@ -0,0 +1 @@
+/* !include https://cdn.jsdelivr.net/npm/leaflet@1.9.4/dist/leaflet.js */
@@ -28,6 +28,7 @@ function setup_table(root_el) {
   /** @type {HTMLInputElement | null} */
   const search_input = root_el.querySelector("input.search");
   const table_el = root_el.querySelector("table");
+  move_footer_rows(table_el);
   const sort_buttons = [...table_el.querySelectorAll("button.sort[data-sort]")];
   const item_parent = table_el.querySelector("tbody");
   const has_sort = sort_buttons.length > 0;
@@ -40,6 +41,108 @@ function setup_table(root_el) {
 
   // Change number format AFTER parsing and storing the sort keys
   apply_number_formatting(table_el);
+  setup_editable_cells(table_el);
+  setup_table_copy(root_el, table_el);
+}
+
+/**
+ * Copies the visible rows of the table to the clipboard as tab-separated values when the copy button is clicked,
+ * so that they can be pasted in a spreadsheet.
+ * @param {HTMLElement} root_el
+ * @param {HTMLTableElement} table_el
+ */
+function setup_table_copy(root_el, table_el) {
+  const button = root_el.querySelector("[data-sqlpage-copy-table]");
+  if (!button) return;
+  const cell_text = (cell) => {
+    const input = cell.querySelector("[data-sqlpage-editable]");
+    const text = input
+      ? input.value
+      : (cell.dataset.raw_value ?? cell.textContent.trim());
+    return text.replace(/[\t\r\n]+/g, " ");
+  };
+  button.addEventListener("click", async () => {
+    const rows = table_el.querySelectorAll(
+      "thead tr, tbody tr:not(.table-group-header, .table-group-footer), tfoot tr",
+    );
+    const tsv = [...rows]
+      .filter((tr) => tr.style.display !== "none")
+      .map((tr) =>
+        [...tr.querySelectorAll(":scope > th, :scope > td")]
+          .filter((cell) => !cell.classList.contains("_col_actions"))
+          .map(cell_text)
+          .join("\t"),
+      )
+      .join("\n");
+    const label = button.lastChild;
+    const text = label.textContent;
+    try {
+      await navigator.clipboard.writeText(tsv);
+      label.textContent = " Copied";
+    } catch (e) {
+      console.error("Unable to copy the table", e);
+      label.textContent = " Copy failed";
+    }
+    setTimeout(() => {
+      label.textContent = text;
+    }, 2000);
+  });
+}
+
+/**
+ * Sends the new value of an editable cell to the save_url of the table when it changes,
+ * with the id of its row and the name of its column, and displays the response of the page below the cell.
+ * @param {HTMLTableElement} table_el
+ */
+function setup_editable_cells(table_el) {
+  const { sqlpageSaveUrl: save_url, sqlpageCsrfToken: csrf_token } =
+    table_el.dataset;
+  if (!save_url) return;
+  table_el.addEventListener("change", async (event) => {
+    const input = event.target;
+    if (!input.matches("[data-sqlpage-editable]")) return;
+    const body = new URLSearchParams({
+      id: input.closest("tr").dataset.sqlpageRowId,
+      column: input.name,
+      value: input.value,
+      _sqlpage_csrf_token: csrf_token,
+    });
+    input.classList.remove("is-valid", "is-invalid");
+    let feedback = input.nextElementSibling;
+    if (!feedback?.matches(".sqlpage-edit-feedback")) {
+      feedback = document.createElement("div");
+      feedback.className = "sqlpage-edit-feedback";
+      input.after(feedback);
+    }
+    try {
+      const resp = await fetch(save_url, {
+        method: "POST",
+        body,
+        headers: { "SQLPage-Fragment": "true" },
+      });
+      input.classList.add(resp.ok ? "is-valid" : "is-invalid");
+      feedback.className = `sqlpage-edit-feedback d-block ${resp.ok ? "valid" : "invalid"}-feedback`;
+      feedback.innerHTML = await resp.text();
+    } catch (e) {
+      input.classList.add("is-invalid");
+      feedback.className = "sqlpage-edit-feedback d-block invalid-feedback";
+      feedback.textContent = e.message;
+    }
+  });
+}
+
+/**
+ * Moves the rows marked with _sqlpage_footer to the footer of the table,
+ * so that they stay at the bottom when the rows are sorted or filtered.
+ * @param {HTMLTableElement} table_el
+ */
+function move_footer_rows(table_el) {
+  const footer_rows = table_el.querySelectorAll(
+    "tbody > tr[data-sqlpage-footer]",
+  );
+  if (footer_rows.length === 0) return;
+  const tfoot_el = table_el.tFoot || table_el.createTFoot();
+  tfoot_el.append(...footer_rows);
 }
 
 /**
@@ -74,7 +177,7 @@ function apply_number_formatting(table_el) {
   const number_format_digits = table_el.dataset.number_format_digits;
   const currency = table_el.dataset.currency;
 
-  for (const tr_el of table_el.querySelectorAll("tbody tr")) {
+  for (const tr_el of table_el.querySelectorAll("tbody tr, tfoot tr")) {
     const cells = tr_el.getElementsByTagName("td");
     for (let idx = 0; idx < cells.length; idx++) {
       const column_type = col_types[idx];
@@ -83,6 +186,8 @@ function apply_number_formatting(table_el) {
       const text = cell_el.textContent;
 
       if (column_type === "number" && !is_raw_number && text) {
+        // Keep the number as it is, for the copies of the table
+        cell_el.dataset.raw_value = text;
         const num = Number.parseFloat(text);
         const is_money = col_money[idx];
         cell_el.textContent = num.toLocaleString(number_format_locale, {
@@ -158,21 +263,14 @@ let is_leaflet_loaded = false;
 
 function sqlpage_map() {
   const first_map = document.querySelector("[data-pre-init=map]");
-  const leaflet_base_url = "https://cdn.jsdelivr.net/npm/leaflet@1.9.4";
   if (first_map && !is_leaflet_injected) {
-    // Add the leaflet js and css to the page
+    // Add the leaflet js and css, which are embedded in sqlpage, to the page
     const leaflet_css = document.createElement("link");
     leaflet_css.rel = "stylesheet";
-    leaflet_css.href = `${leaflet_base_url}/dist/leaflet.css`;
-    leaflet_css.integrity =
-      "sha256-p4NxAoJBhIIN+hmNHrzRCf9tD/miZyoHS5obTRR9BMY=";
-    leaflet_css.crossOrigin = "anonymous";
+    leaflet_css.href = first_map.dataset.leaflet_css;
     document.head.appendChild(leaflet_css);
     const leaflet_js = document.createElement("script");
-    leaflet_js.src = `${leaflet_base_url}/dist/leaflet.js`;
-    leaflet_js.integrity =
-      "sha256-20nQCchB9co0qIjJZRGuk2/Z9VM+kNiyxNV1lvTlZBo=";
-    leaflet_js.crossOrigin = "anonymous";
+    leaflet_js.src = first_map.dataset.leaflet_js;
     leaflet_js.nonce = nonce;
     leaflet_js.onload = onLeafletLoad;
     document.head.appendChild(leaflet_js);
@@ -246,9 +344,23 @@ function sqlpage_map() {
         iconSize: [size, size],
         iconAnchor: [size / 2, size / 2],
       });
+    } else {
+      options.icon = default_marker_icon(options.color);
     }
     return L.marker(coords, options);
   }
+  // Leaflet's default marker icon is a png image loaded from a path relative to its css.
+  // We draw it as an svg instead, to avoid depending on external images.
+  function default_marker_icon(color) {
+    const fill = get_tabler_color(color || "primary") || "#206bc4";
+    const svg = `<svg xmlns="http://www.w3.org/2000/svg" width="25" height="41" viewBox="0 0 25 41"><path d="M12.5 0C5.6 0 0 5.6 0 12.5 0 21.9 12.5 41 12.5 41S25 21.9 25 12.5C25 5.6 19.4 0 12.5 0z" fill="${fill}"/><circle cx="12.5" cy="12.5" r="5" fill="white"/></svg>`;
+    return L.icon({
+      iconUrl: `data:image/svg+xml,${encodeURIComponent(svg)}`,
+      iconSize: [25, 41],
+      iconAnchor: [12.5, 41],
+      popupAnchor: [0, -34],
+    });
+  }
   function createGeoJSONMarker(marker_elem, options) {
     const geojson = JSON.parse(marker_elem.dataset.geojson);
     if (options.color) {
@@ -266,6 +378,345 @@ function sqlpage_map() {
   }
 }
 
+/**
+ * Parses the dates of calendar events. Dates without a time zone, such as `2024-07-14` or `2024-07-14 10:00`,
+ * are in the time zone of the visitor.
+ * @param {string | undefined} str
+ */
+function parse_calendar_date(str) {
+  if (!str) return null;
+  const m =
+    /^(\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::\d{2}(?:\.\d+)?)?)?$/.exec(
+      str.trim(),
+    );
+  if (m) return new Date(m[1], m[2] - 1, m[3], m[4] || 0, m[5] || 0);
+  const date = new Date(str);
+  return Number.isNaN(date.getTime()) ? null : date;
+}
+
+function calendar_iso_date(date) {
+  const pad = (n) => String(n).padStart(2, "0");
+  return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+}
+
+function calendar_add_days(date, days) {
+  return new Date(date.getFullYear(), date.getMonth(), date.getDate() + days);
+}
+
+/** The first and the day after the last day of the month or week that contains the date */
+function calendar_period(date, view, first_day) {
+  if (view === "week") {
+    const start = calendar_add_days(date, -((date.getDay() - first_day + 7) % 7));
+    return [start, calendar_add_days(start, 7)];
+  }
+  const start = new Date(date.getFullYear(), date.getMonth(), 1);
+  return [start, new Date(date.getFullYear(), date.getMonth() + 1, 1)];
+}
+
+/** Lays out the events of calendar components in a month or a week, with links to the previous and next periods */
+function sqlpage_calendar() {
+  for (const calendar of document.querySelectorAll(
+    "[data-pre-init=calendar]",
+  )) {
+    calendar.removeAttribute("data-pre-init");
+    const view = calendar.dataset.view === "week" ? "week" : "month";
+    const first_day = +calendar.dataset.first_day % 7;
+    const date = parse_calendar_date(calendar.dataset.start) || new Date();
+    const [start, end] = calendar_period(date, view, first_day);
+    const events = [...calendar.querySelectorAll(".calendar-event")]
+      .map((el) => {
+        const event_start = parse_calendar_date(el.dataset.start);
+        let event_end = parse_calendar_date(el.dataset.end) || event_start;
+        // An event that ends on a date without a time lasts until the end of that day
+        if (/^\d{4}-\d{2}-\d{2}$/.test(el.dataset.end?.trim())) {
+          event_end = calendar_add_days(event_end, 1);
+        }
+        return { el, start: event_start, end: event_end };
+      })
+      .filter((event) => event.start);
+
+    const grid = document.createElement("div");
+    grid.className = `calendar-grid calendar-${view}`;
+    const grid_start = calendar_add_days(
+      start,
+      -((start.getDay() - first_day + 7) % 7),
+    );
+    const weekday = new Intl.DateTimeFormat(undefined, { weekday: "short" });
+    const time = new Intl.DateTimeFormat(undefined, { timeStyle: "short" });
+    for (let i = 0; i < 7; i++) {
+      const cell = document.createElement("div");
+      cell.className = "calendar-weekday text-truncate";
+      cell.textContent = weekday.format(calendar_add_days(grid_start, i));
+      grid.append(cell);
+    }
+    const today = calendar_iso_date(new Date());
+    for (let day = grid_start; day < end || day.getDay() !== first_day; ) {
+      const next_day = calendar_add_days(day, 1);
+      const cell = document.createElement("div");
+      cell.className = "calendar-day";
+      if (day < start || day >= end) cell.classList.add("calendar-day-outside");
+      if (calendar_iso_date(day) === today) cell.classList.add("calendar-today");
+      const number = document.createElement("div");
+      number.className = "calendar-day-number";
+      number.textContent = day.getDate();
+      cell.append(number);
+      for (const event of events) {
+        const ends_after = event.end > day || event.start >= day;
+        if (event.start >= next_day || !ends_after) continue;
+        const el = event.el.cloneNode(true);
+        const has_time =
+          event.start >= day &&
+          (event.start.getHours() || event.start.getMinutes());
+        if (has_time) el.prepend(`${time.format(event.start)} `);
+        cell.append(el);
+      }
+      grid.append(cell);
+      day = next_day;
+    }
+    calendar.append(grid);
+
+    const nav = calendar.closest(".card").querySelector(".calendar-nav");
+    const link = (label, target_date, target_view) => {
+      const [target_start, target_end] = calendar_period(
+        target_date,
+        target_view,
+        first_day,
+      );
+      const url = new URL(window.location.href);
+      url.searchParams.set("start", calendar_iso_date(target_start));
+      url.searchParams.set("end", calendar_iso_date(target_end));
+      url.searchParams.set("view", target_view);
+      const a = document.createElement("a");
+      a.className = "btn btn-sm";
+      if (target_view !== view) a.classList.add("btn-ghost-secondary");
+      a.href = url.toString();
+      a.textContent = label;
+      return a;
+    };
+    const title = document.createElement("span");
+    title.className = "align-self-center fw-bold me-2";
+    title.textContent =
+      view === "week"
+        ? new Intl.DateTimeFormat(undefined, { dateStyle: "medium" }).formatRange(
+            start,
+            calendar_add_days(end, -1),
+          )
+        : new Intl.DateTimeFormat(undefined, {
+            month: "long",
+            year: "numeric",
+          }).format(start);
+    const step = view === "week" ? 7 : 31;
+    nav.append(
+      title,
+      link("‹", calendar_add_days(start, -1), view),
+      link("Today", new Date(), view),
+      link("›", calendar_add_days(start, step), view),
+      link(view === "week" ? "Month" : "Week", start, view === "week" ? "month" : "week"),
+    );
+  }
+}
+
+/** Distributes the items of board components in columns by status, and lets visitors move them between columns */
+function sqlpage_board() {
+  for (const board of document.querySelectorAll("[data-pre-init=board]")) {
+    board.removeAttribute("data-pre-init");
+    const columns = new Map(
+      [...board.querySelectorAll(".board-column")].map((c) => [
+        c.dataset.status,
+        c,
+      ]),
+    );
+    const column_of = (status) => {
+      let column = columns.get(status);
+      if (!column) {
+        column = document.createElement("div");
+        column.className = "board-column";
+        column.dataset.status = status;
+        const title = document.createElement("h3");
+        title.className = "board-column-title";
+        title.append(status, " ");
+        const count = document.createElement("span");
+        count.className = "badge board-count";
+        const items = document.createElement("div");
+        items.className = "board-items";
+        title.append(count);
+        column.append(title, items);
+        board.append(column);
+        columns.set(status, column);
+      }
+      return column;
+    };
+    const update_counts = () => {
+      for (const column of columns.values()) {
+        column.querySelector(".board-count").textContent =
+          column.querySelectorAll(".board-item").length;
+      }
+    };
+    for (const item of board.querySelectorAll(":scope > .board-item")) {
+      column_of(item.dataset.status)
+        .querySelector(".board-items")
+        .append(item);
+    }
+    update_counts();
+    const { sqlpageMoveUrl: move_url, sqlpageCsrfToken: csrf_token } =
+      board.dataset;
+    if (!move_url) continue;
+    let dragged = null;
+    board.addEventListener("dragstart", (event) => {
+      dragged = event.target.closest(".board-item");
+      event.dataTransfer.effectAllowed = "move";
+    });
+    board.addEventListener("dragover", (event) => {
+      const column = event.target.closest(".board-column");
+      if (!dragged || !column) return;
+      event.preventDefault();
+      for (const c of columns.values()) {
+        c.classList.toggle("board-drop-target", c === column);
+      }
+    });
+    board.addEventListener("dragend", () => {
+      dragged = null;
+      for (const c of columns.values()) c.classList.remove("board-drop-target");
+    });
+    board.addEventListener("drop", async (event) => {
+      const column = event.target.closest(".board-column");
+      const item = dragged;
+      if (!item || !column || column.dataset.status === item.dataset.status) {
+        return;
+      }
+      event.preventDefault();
+      const previous_column = column_of(item.dataset.status);
+      column.querySelector(".board-items").append(item);
+      update_counts();
+      const feedback = board.previousElementSibling;
+      feedback.innerHTML = "";
+      const body = new URLSearchParams({
+        id: item.dataset.itemId,
+        status: column.dataset.status,
+        _sqlpage_csrf_token: csrf_token,
+      });
+      try {
+        const resp = await fetch(move_url, {
+          method: "POST",
+          body,
+          headers: { "SQLPage-Fragment": "true" },
+        });
+        if (!resp.ok) throw new Error(await resp.text());
+        item.dataset.status = column.dataset.status;
+      } catch (e) {
+        previous_column.querySelector(".board-items").append(item);
+        update_counts();
+        feedback.innerHTML = e.message;
+      }
+    });
+  }
+}
+
+/** Nests the nodes of tree components under their parents, and lets visitors expand and collapse them */
+function sqlpage_tree() {
+  for (const tree of document.querySelectorAll("[data-pre-init=tree]")) {
+    tree.removeAttribute("data-pre-init");
+    const nodes = new Map(
+      [...tree.querySelectorAll(":scope > .tree-node")].map((node) => [
+        node.dataset.nodeId,
+        node,
+      ]),
+    );
+    for (const node of nodes.values()) {
+      const parent = nodes.get(node.dataset.parentId);
+      // Nodes without a parent in the tree, or in a cycle, stay at the root
+      if (!parent || node.contains(parent)) continue;
+      let children = parent.querySelector(":scope > ul");
+      if (!children) {
+        children = document.createElement("ul");
+        children.className = "list-unstyled";
+        parent.append(children);
+      }
+      children.append(node);
+    }
+    for (const node of tree.querySelectorAll("[data-active]")) {
+      let ancestor = node;
+      while ((ancestor = ancestor.parentElement.closest(".tree-node"))) {
+        ancestor.dataset.expanded = "";
+      }
+    }
+    const expand_all = tree.hasAttribute("data-expanded");
+    for (const node of nodes.values()) {
+      const children = node.querySelector(":scope > ul");
+      if (!children) continue;
+      const toggle = document.createElement("button");
+      toggle.type = "button";
+      toggle.className = "tree-toggle";
+      toggle.setAttribute("aria-label", "Expand or collapse");
+      const set_expanded = (expanded) => {
+        toggle.setAttribute("aria-expanded", expanded);
+        children.hidden = !expanded;
+      };
+      set_expanded(expand_all || node.hasAttribute("data-expanded"));
+      toggle.addEventListener("click", () => set_expanded(children.hidden));
+      node.querySelector(".tree-toggle-placeholder").replaceWith(toggle);
+    }
+  }
+}
+
+/** Opens the images of gallery components in a lightbox, in which visitors can go from an image to the next */
+function sqlpage_gallery() {
+  for (const gallery of document.querySelectorAll("[data-pre-init=gallery]")) {
+    gallery.removeAttribute("data-pre-init");
+    const links = [...gallery.querySelectorAll("[data-sqlpage-lightbox]")];
+    if (links.length === 0) continue;
+    const dialog = document.createElement("dialog");
+    dialog.className = "gallery-lightbox";
+    const image = document.createElement("img");
+    const caption = document.createElement("div");
+    caption.className = "gallery-lightbox-caption";
+    const button = (className, label, text) => {
+      const b = document.createElement("button");
+      b.type = "button";
+      b.className = className;
+      b.setAttribute("aria-label", label);
+      b.textContent = text;
+      dialog.append(b);
+      return b;
+    };
+    dialog.append(image, caption);
+    button("btn-close btn-close-white", "Close", "").onclick = () =>
+      dialog.close();
+    let current = 0;
+    const show = (index) => {
+      current = (index + links.length) % links.length;
+      const link = links[current];
+      const thumbnail = link.querySelector("img");
+      image.src = link.href;
+      image.alt = thumbnail.alt;
+      caption.textContent =
+        link.closest("figure").querySelector("figcaption")?.textContent || "";
+    };
+    if (links.length > 1) {
+      button("gallery-lightbox-prev", "Previous", "\u2039").onclick = () =>
+        show(current - 1);
+      button("gallery-lightbox-next", "Next", "\u203a").onclick = () =>
+        show(current + 1);
+      dialog.addEventListener("keydown", (event) => {
+        if (event.key === "ArrowLeft") show(current - 1);
+        if (event.key === "ArrowRight") show(current + 1);
+      });
+    }
+    // Clicking outside of the image closes the lightbox
+    dialog.addEventListener("click", (event) => {
+      if (event.target === dialog) dialog.close();
+    });
+    gallery.after(dialog);
+    links.forEach((link, index) => {
+      link.addEventListener("click", (event) => {
+        event.preventDefault();
+        show(index);
+        dialog.showModal();
+      });
+    });
+  }
+}
+
 function sqlpage_form() {
   const file_inputs = document.querySelectorAll(
     "input[type=file][data-max-size]",
@@ -313,17 +764,304 @@ function load_scripts() {
   }
 }
 
+/** Loads the fragments of live components, and loads them again when the database notifies their channel */
+function sqlpage_live() {
+  for (const live of document.querySelectorAll("[data-pre-init=live]")) {
+    live.removeAttribute("data-pre-init");
+    const url = new URL(live.dataset.embed, window.location.href);
+    url.searchParams.set("_sqlpage_embed", "1");
+    const content = live.querySelector(".live-content");
+    let loading = null;
+    let outdated = false;
+    const load = async () => {
+      // Notifications received while the fragment is loading trigger a single reload
+      if (loading) {
+        outdated = true;
+        return;
+      }
+      try {
+        loading = fetch(url).then((res) => res.text());
+        content.innerHTML = await loading;
+        content.dispatchEvent(
+          new CustomEvent("fragment-loaded", { bubbles: true }),
+        );
+      } finally {
+        loading = null;
+      }
+      if (outdated) {
+        outdated = false;
+        load();
+      }
+    };
+    load();
+    const events = new EventSource(live.dataset.liveUrl);
+    events.addEventListener("notification", () => {
+      if (!live.isConnected) events.close();
+      else load();
+    });
+  }
+}
+
 function add_init_fn(f) {
   document.addEventListener("DOMContentLoaded", f);
   document.addEventListener("fragment-loaded", f);
   if (document.readyState !== "loading") setTimeout(f, 0);
 }
 
+const prefers_dark_theme = window.matchMedia("(prefers-color-scheme: dark)");
+
+/** Applies a theme chosen in the shell: "light", "dark", or "auto" to follow the system preferences */
+function apply_theme(choice) {
+  const root = document.documentElement;
+  root.dataset.sqlpageTheme = choice;
+  const is_dark =
+    choice === "dark" || (choice === "auto" && prefers_dark_theme.matches);
+  root.dataset.bsTheme = is_dark ? "dark" : "light";
+}
+
+prefers_dark_theme.addEventListener("change", () => {
+  if (document.documentElement.dataset.sqlpageTheme === "auto") {
+    apply_theme("auto");
+  }
+});
+
+/** Cycles between the light, dark and automatic themes when clicking the shell's theme toggle */
+function sqlpage_theme_toggle() {
+  const choices = ["light", "dark", "auto"];
+  for (const button of document.querySelectorAll(
+    "[data-sqlpage-theme-toggle]:not([data-initialized])",
+  )) {
+    button.dataset.initialized = "true";
+    button.addEventListener("click", () => {
+      const current = document.documentElement.dataset.sqlpageTheme;
+      const next = choices[(choices.indexOf(current) + 1) % choices.length];
+      localStorage.setItem("sqlpage-theme", next);
+      apply_theme(next);
+    });
+  }
+}
+
+/** Sends the time zone of the browser to the server, which uses it to display timestamps in local time */
+function save_timezone() {
+  const timezone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+  const cookie = `sqlpage_timezone=${timezone}`;
+  if (timezone && !document.cookie.split("; ").includes(cookie)) {
+    document.cookie = `${cookie}; path=/; max-age=31536000; samesite=lax`;
+  }
+}
+
+/** Wraps the occurrences of a search term in the text of an element in <mark> tags */
+function highlight_matches(root, term) {
+  const needle = term.toLocaleLowerCase();
+  const walker = document.createTreeWalker(root, NodeFilter.SHOW_TEXT, {
+    acceptNode: (node) =>
+      node.parentElement.closest("script, style, textarea, input, mark")
+        ? NodeFilter.FILTER_REJECT
+        : NodeFilter.FILTER_ACCEPT,
+  });
+  const nodes = [];
+  while (walker.nextNode()) nodes.push(walker.currentNode);
+  for (let node of nodes) {
+    let index = node.data.toLocaleLowerCase().indexOf(needle);
+    while (index >= 0) {
+      const match = node.splitText(index);
+      node = match.splitText(needle.length);
+      const mark = document.createElement("mark");
+      match.replaceWith(mark);
+      mark.appendChild(match);
+      index = node.data.toLocaleLowerCase().indexOf(needle);
+    }
+  }
+}
+
+/** Submits search forms while the user types, and highlights the search term in the results */
+function sqlpage_search() {
+  for (const search of document.querySelectorAll(
+    "[data-sqlpage-search]:not([data-initialized])",
+  )) {
+    search.dataset.initialized = "true";
+    const form = search.querySelector("form");
+    const input = form.querySelector("input[type=search]");
+    if (form.dataset.debounce) {
+      let timeout;
+      input.addEventListener("input", () => {
+        clearTimeout(timeout);
+        timeout = setTimeout(() => form.submit(), +form.dataset.debounce);
+      });
+    }
+    if (input.value && document.activeElement === document.body) {
+      // keep typing where the user left off before the page was reloaded
+      input.focus();
+      input.setSelectionRange(input.value.length, input.value.length);
+    }
+    const term = (search.dataset.highlight || "").trim();
+    if (!term) continue;
+    highlight_matches(search.querySelector(".list-group"), term);
+    if (search.nextElementSibling) {
+      highlight_matches(search.nextElementSibling, term);
+    }
+  }
+}
+
+/** Fetches the next rows of a list, table or card, rendered by the server without the shell, and appends them */
+async function load_more_rows(button) {
+  const items = document.querySelector(
+    `[data-sqlpage-load-more-items="${button.dataset.sqlpageLoadMore}"]`,
+  );
+  const url = button.href;
+  button.classList.add("disabled");
+  const resp = await fetch(url, { headers: { "SQLPage-Fragment": "true" } });
+  if (!resp.ok) {
+    console.error(`Unable to load more rows from ${url}: ${resp.status}`);
+    button.classList.remove("disabled");
+    return;
+  }
+  const html = await resp.text();
+  const fragment = new DOMParser().parseFromString(html, "text/html");
+  const new_items = fragment.querySelector("[data-sqlpage-load-more-items]");
+  for (const item of [...(new_items?.children || [])]) {
+    if (!item.hasAttribute("data-sqlpage-empty")) items.appendChild(item);
+  }
+  const next = fragment.querySelector("[data-sqlpage-load-more]");
+  if (next) {
+    button.href = next.href;
+    button.classList.remove("disabled");
+  } else {
+    button.remove();
+  }
+  items.dispatchEvent(new CustomEvent("fragment-loaded", { bubbles: true }));
+}
+
+/** Adds the rows of the next page to components that have a load_more link, on click or when scrolling to the end */
+function sqlpage_load_more() {
+  for (const button of document.querySelectorAll(
+    "[data-sqlpage-load-more]:not([data-initialized])",
+  )) {
+    button.dataset.initialized = "true";
+    button.addEventListener("click", (event) => {
+      event.preventDefault();
+      if (!button.classList.contains("disabled")) load_more_rows(button);
+    });
+    if (!("infiniteScroll" in button.dataset)) continue;
+    const observer = new IntersectionObserver(async ([entry]) => {
+      if (!entry.isIntersecting || button.classList.contains("disabled")) {
+        return;
+      }
+      await load_more_rows(button);
+      // observe again, to load the next page if the button is still visible
+      observer.unobserve(button);
+      if (button.isConnected) observer.observe(button);
+    });
+    observer.observe(button);
+  }
+}
+
+/** Switches between tabs without reloading the page, by showing the element targeted by the active tab and hiding the others */
+function sqlpage_tabs() {
+  for (const nav of document.querySelectorAll(
+    "ul.nav:not([data-initialized])",
+  )) {
+    const tabs = [...nav.querySelectorAll("[data-sqlpage-tab-target]")];
+    if (!tabs.length) continue;
+    nav.dataset.initialized = "true";
+    const activate = (tab) => {
+      for (const t of tabs) {
+        const target = document.getElementById(t.dataset.sqlpageTabTarget);
+        t.classList.toggle("active", t === tab);
+        target?.classList.toggle("d-none", t !== tab);
+      }
+    };
+    const from_hash = tabs.find(
+      (t) => `#${t.dataset.sqlpageTabTarget}` === window.location.hash,
+    );
+    const active = tabs.find((t) => t.classList.contains("active"));
+    activate(from_hash || active || tabs[0]);
+    for (const tab of tabs) {
+      tab.addEventListener("click", (event) => {
+        event.preventDefault();
+        activate(tab);
+        history.replaceState(null, "", tab.getAttribute("href"));
+      });
+    }
+  }
+}
+
+/** Opens the modals that must be displayed when the page loads, and loads the pages embedded in modals when they open */
+function sqlpage_modal() {
+  for (const modal of document.querySelectorAll(
+    "[data-sqlpage-modal]:not([data-initialized])",
+  )) {
+    modal.dataset.initialized = "true";
+    const embed = modal.dataset.embed;
+    if (embed) {
+      modal.addEventListener(
+        "show.bs.modal",
+        async () => {
+          const container = modal.querySelector(".modal-embed");
+          const resp = await fetch(embed, {
+            headers: { "SQLPage-Fragment": "true" },
+          });
+          container.innerHTML = await resp.text();
+          container.dispatchEvent(
+            new CustomEvent("fragment-loaded", { bubbles: true }),
+          );
+        },
+        { once: true },
+      );
+    }
+    if ("open" in modal.dataset && window.bootstrap) {
+      bootstrap.Modal.getOrCreateInstance(modal).show();
+    }
+  }
+}
+
+/** Marks the links of the menu that point to the current page as active, unless the page chose its active menu items */
+function sqlpage_menu_active() {
+  for (const nav of document.querySelectorAll(
+    ".navbar-nav:not([data-initialized])",
+  )) {
+    nav.dataset.initialized = "true";
+    if (nav.querySelector(".active")) continue;
+    const page = (path) => path.replace(/\/index\.sql$/, "/");
+    for (const link of nav.querySelectorAll("a.nav-link, a.dropdown-item")) {
+      if (link.getAttribute("href").startsWith("#")) continue;
+      const url = new URL(link.href, window.location.href);
+      if (url.origin !== window.location.origin) continue;
+      if (page(url.pathname) !== page(window.location.pathname)) continue;
+      link.classList.add("active");
+      link.setAttribute("aria-current", "page");
+      const toggle = link.closest(".dropdown")?.querySelector(".nav-link");
+      toggle?.classList.add("active");
+    }
+  }
+}
+
+/** Asks for a confirmation before submitting the forms of actions that cannot be undone, such as deletions */
+function sqlpage_confirm_actions() {
+  document.addEventListener("submit", (event) => {
+    const message = event.target.dataset.sqlpageConfirm;
+    if (message && !window.confirm(message)) event.preventDefault();
+  });
+}
+
+save_timezone();
+sqlpage_confirm_actions();
 add_init_fn(sqlpage_table);
 add_init_fn(sqlpage_map);
+add_init_fn(sqlpage_calendar);
+add_init_fn(sqlpage_board);
+add_init_fn(sqlpage_tree);
+add_init_fn(sqlpage_gallery);
 add_init_fn(sqlpage_card);
 add_init_fn(sqlpage_form);
+add_init_fn(sqlpage_search);
+add_init_fn(sqlpage_load_more);
+add_init_fn(sqlpage_tabs);
+add_init_fn(sqlpage_modal);
+add_init_fn(sqlpage_live);
+add_init_fn(sqlpage_menu_active);
 add_init_fn(load_scripts);
+add_init_fn(sqlpage_theme_toggle);
 
 function init_bootstrap_components(event) {
   if (window.bootstrap) {
@@ -1,15 +1,18 @@
 use crate::webserver::routing::RoutingConfig;
+use actix_web::http::StatusCode;
 use anyhow::Context;
 use clap::Parser;
 use config::Config;
+use ipnet::IpNet;
 use percent_encoding::AsciiSet;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, long_version = crate::build_info::long_version(), about, long_about = None)]
 pub struct Cli {
     /// The directory where the .sql files are located.
     #[clap(short, long)]
@@ -20,6 +23,19 @@ pub struct Cli {
     /// The path to the configuration file.
     #[clap(short = 'c', long)]
     pub config_file: Option<PathBuf>,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Check all the .sql files in the web root for errors, without starting the server.
+    /// Exits with a non-zero status code if any problem is found.
+    Check {
+        /// Only check the SQL syntax and the components, without connecting to the database.
+        #[clap(long)]
+        syntax_only: bool,
+    },
 }
 
 #[cfg(not(feature = "lambda-web"))]
@@ -100,6 +116,152 @@ impl AppConfig {
                 self.configuration_directory
             ));
         }
+        for directory in &self.plugin_template_directories {
+            anyhow::ensure!(
+                directory.is_dir(),
+                "Plugin template directory is not a valid directory: {directory:?}"
+            );
+        }
+        anyhow::ensure!(
+            self.slow_query_log_threshold_seconds
+                .is_none_or(|s| s >= 0.0),
+            "slow_query_log_threshold_seconds must be non-negative"
+        );
+        anyhow::ensure!(
+            self.slow_query_log_table.is_none() || self.slow_query_log_threshold_seconds.is_some(),
+            "slow_query_log_table requires slow_query_log_threshold_seconds to be set"
+        );
+        self.validate_url_paths()?;
+        if let Some(secret) = &self.token_signing_secret {
+            anyhow::ensure!(
+                secret.len() >= 32,
+                "token_signing_secret must be at least 32 characters long"
+            );
+        }
+        self.validate_admin_settings()?;
+        self.validate_database_settings()?;
+        anyhow::ensure!(self.max_pending_rows > 0, "max_pending_rows cannot be null");
+        anyhow::ensure!(
+            self.max_cached_templates > 0,
+            "max_cached_templates must be greater than 0"
+        );
+        anyhow::ensure!(
+            (1..=u32::MAX as usize).contains(&self.max_pending_response_bytes),
+            "max_pending_response_bytes must be between 1 and {}",
+            u32::MAX
+        );
+        anyhow::ensure!(
+            self.http_workers != Some(0),
+            "http_workers must be greater than 0"
+        );
+        anyhow::ensure!(
+            self.http_keep_alive_seconds.is_none_or(|s| s >= 0.0),
+            "http_keep_alive_seconds must be non-negative"
+        );
+        anyhow::ensure!(
+            self.http_client_request_timeout_seconds
+                .is_none_or(|s| s >= 0.0),
+            "http_client_request_timeout_seconds must be non-negative"
+        );
+        anyhow::ensure!(
+            self.http_backlog != Some(0),
+            "http_backlog must be greater than 0"
+        );
+        anyhow::ensure!(
+            self.max_concurrent_queries_per_request > 0,
+            "max_concurrent_queries_per_request must be greater than 0"
+        );
+        anyhow::ensure!(
+            self.pdf_max_concurrent_renders > 0,
+            "pdf_max_concurrent_renders must be greater than 0"
+        );
+        if let Some(site_url) = &self.site_url {
+            let url = url::Url::parse(site_url)
+                .with_context(|| format!("site_url is not a valid URL: {site_url:?}"))?;
+            anyhow::ensure!(
+                matches!(url.scheme(), "http" | "https"),
+                "site_url must start with http:// or https://"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the settings that apply to URL paths
+    fn validate_url_paths(&self) -> anyhow::Result<()> {
+        for path in self.ip_access_rules.keys() {
+            anyhow::ensure!(
+                path.starts_with('/'),
+                "The paths of ip_access_rules must start with a slash: {path:?}"
+            );
+        }
+        for (path, origins) in &self.frame_ancestors {
+            anyhow::ensure!(
+                path.starts_with('/'),
+                "The paths of frame_ancestors must start with a slash: {path:?}"
+            );
+            for origin in origins {
+                anyhow::ensure!(
+                    !origin.is_empty() && !origin.contains([' ', ';', ',']),
+                    "Invalid origin in frame_ancestors for {path:?}: {origin:?}"
+                );
+            }
+        }
+        for (path, route) in &self.proxy_routes {
+            anyhow::ensure!(
+                path.starts_with('/'),
+                "The paths of proxy_routes must start with a slash: {path:?}"
+            );
+            anyhow::ensure!(
+                route.upstream.starts_with("http://") || route.upstream.starts_with("https://"),
+                "The upstream of the proxy route {path:?} must be an http or https URL: {:?}",
+                route.upstream
+            );
+        }
+        for (name, path) in [
+            ("openapi_path", &self.openapi_path),
+            ("batch_path", &self.batch_path),
+        ] {
+            if let Some(path) = path {
+                anyhow::ensure!(
+                    path.starts_with('/'),
+                    "{name} must start with a slash: {path:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_admin_settings(&self) -> anyhow::Result<()> {
+        if let Some(hash) = &self.admin_password_hash {
+            password_hash::PasswordHash::new(hash)
+                .map_err(|e| anyhow::anyhow!("Invalid admin_password_hash: {e}"))?;
+        }
+        anyhow::ensure!(
+            !self.admin_sql_console || self.admin_password_hash.is_some(),
+            "admin_sql_console requires admin_password_hash to be set"
+        );
+        if let Some(directory) = &self.admin_file_manager_directory {
+            anyhow::ensure!(
+                self.admin_password_hash.is_some(),
+                "admin_file_manager_directory requires admin_password_hash to be set"
+            );
+            anyhow::ensure!(
+                directory.is_relative(),
+                "admin_file_manager_directory must be relative to the web root: {directory:?}"
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_database_settings(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.database_credentials.is_none() || !self.database_url.starts_with("sqlite"),
+            "database_credentials cannot be used with SQLite databases"
+        );
+        anyhow::ensure!(
+            self.database_role_query.is_none() || self.database_url.starts_with("postgres"),
+            "database_role_query is only supported with PostgreSQL databases"
+        );
         if self.database_connection_acquire_timeout_seconds <= 0.0 {
             return Err(anyhow::anyhow!(
                 "Database connection acquire timeout must be positive"
@@ -126,7 +288,6 @@ impl AppConfig {
                 ));
             }
         }
-        anyhow::ensure!(self.max_pending_rows > 0, "max_pending_rows cannot be null");
         Ok(())
     }
 }
@@ -150,9 +311,27 @@ pub struct AppConfig {
     /// A separate field for the database password. If set, this will override any password specified in the `database_url`.
     #[serde(default)]
     pub database_password: Option<String>,
+    /// Fetch the database user name and password from a secrets manager, and renew them before they expire.
+    #[serde(default)]
+    pub database_credentials: Option<DatabaseCredentialsProvider>,
+    /// A query executed at the beginning of each request, that returns the database role
+    /// the statements of the request are executed with, in a `role` column.
+    #[serde(default)]
+    pub database_role_query: Option<String>,
+    /// Execute the statements of POST requests in a transaction, that is rolled back
+    /// when a statement fails or the page cannot be rendered completely.
+    #[serde(default)]
+    pub post_transactions: bool,
+    /// What happens after a statement fails. It can be changed for the rest of a page
+    /// with a `-- @on_error` comment in its SQL file.
+    #[serde(default)]
+    pub on_error: OnError,
     pub max_database_pool_connections: Option<u32>,
     pub database_connection_idle_timeout_seconds: Option<f64>,
     pub database_connection_max_lifetime_seconds: Option<f64>,
+    /// Maximum number of additional connections used by a page to execute its `-- @concurrent` queries
+    #[serde(default = "default_max_concurrent_queries_per_request")]
+    pub max_concurrent_queries_per_request: usize,
 
     #[serde(default)]
     pub sqlite_extensions: Vec<String>,
@@ -162,6 +341,20 @@ pub struct AppConfig {
     pub port: Option<u16>,
     pub unix_socket: Option<PathBuf>,
 
+    /// Number of worker threads handling http requests. Defaults to the number of physical CPU cores.
+    pub http_workers: Option<usize>,
+
+    /// Number of seconds an idle http connection is kept open while waiting for a new request.
+    /// Set to 0 to disable keep-alive. Defaults to 5 seconds.
+    pub http_keep_alive_seconds: Option<f64>,
+
+    /// Maximum number of seconds a client has to send the request headers before the connection is closed.
+    /// Set to 0 to disable the timeout. Defaults to 5 seconds.
+    pub http_client_request_timeout_seconds: Option<f64>,
+
+    /// Maximum number of pending connections waiting to be accepted. Defaults to 2048.
+    pub http_backlog: Option<u32>,
+
     /// Number of times to retry connecting to the database after a failure when the server starts
     /// up. Retries will happen every 5 seconds. The default is 6 retries, which means the server
     /// will wait up to 30 seconds for the database to become available.
@@ -173,6 +366,11 @@ pub struct AppConfig {
     #[serde(default = "default_database_connection_acquire_timeout_seconds")]
     pub database_connection_acquire_timeout_seconds: f64,
 
+    /// Whether exact decimal values (NUMERIC and DECIMAL columns) should always be passed to components
+    /// as strings. By default, they are numbers when a 64-bit float represents them exactly, and strings otherwise.
+    #[serde(default)]
+    pub decimals_as_strings: bool,
+
     /// The directory where the .sql files are located. Defaults to the current directory.
     #[serde(default = "default_web_root")]
     pub web_root: PathBuf,
@@ -227,17 +425,169 @@ pub struct AppConfig {
     )]
     pub site_prefix: String,
 
+    /// Which form of the URL of a page is preferred: with or without the `.sql` extension.
+    /// Requests to the other form are permanently redirected to the preferred one.
+    #[serde(default)]
+    pub url_style: UrlStyle,
+
+    /// IP ranges of the reverse proxies in front of `SQLPage`, such as `10.0.0.0/8` or `::1/128`.
+    /// For requests coming from these addresses, the client IP, protocol and host are read from the
+    /// `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// IP ranges allowed and denied for the URLs starting with a given path, such as
+    /// `{"/admin/": {"allow": ["192.168.0.0/16"]}}`. The longest matching path wins.
+    #[serde(default)]
+    pub ip_access_rules: HashMap<String, IpAccessRule>,
+
+    /// Origins allowed to embed the pages whose URL starts with a given path in frames, such as
+    /// `{"/dashboards/": ["'self'", "https://wiki.example.com"]}`. The longest matching path wins.
+    /// When it is set, the other pages can only be embedded in frames by the site itself.
+    #[serde(default)]
+    pub frame_ancestors: HashMap<String, Vec<String>>,
+
+    /// URLs starting with a given path that are forwarded to another server, such as
+    /// `{"/api/": {"upstream": "http://localhost:8080/"}}`. The longest matching path wins.
+    #[serde(default)]
+    pub proxy_routes: HashMap<String, ProxyRoute>,
+
+    /// Path at which an `OpenAPI` description of the pages of the site is served, such as `/sqlpage/openapi.json`.
+    /// It is not served when this is not set.
+    pub openapi_path: Option<String>,
+
+    /// Path at which several pages can be executed with a single POST request, such as `/sqlpage/batch`.
+    /// It is not served when this is not set.
+    pub batch_path: Option<String>,
+
+    /// Path to a Chromium or Google Chrome executable, used to render pages to PDF
+    /// when they are requested with `?_format=pdf`. PDF rendering is disabled when this is not set.
+    pub pdf_chromium_path: Option<PathBuf>,
+
+    /// Public URL of the site, such as `https://example.com`. The browser that renders pages to PDF
+    /// loads their stylesheets, scripts and images from it.
+    /// Defaults to `https://<https_domain>`, or to the local address the server listens on.
+    pub site_url: Option<String>,
+
+    /// Maximum number of browser processes rendering pages to PDF at the same time.
+    /// Other PDF requests wait for one of them to finish.
+    #[serde(default = "default_pdf_max_concurrent_renders")]
+    pub pdf_max_concurrent_renders: usize,
+
+    /// IANA name of the time zone in which timestamps are displayed to viewers whose time zone is not known yet,
+    /// such as `Europe/Paris`. Defaults to UTC.
+    pub default_timezone: Option<chrono_tz::Tz>,
+
+    /// Path to a `MaxMind` database in the `.mmdb` format, such as `GeoLite2-Country.mmdb`,
+    /// used by the `sqlpage.client_country` and `sqlpage.geoip` functions.
+    pub geoip_database_path: Option<PathBuf>,
+
     /// Maximum number of messages that can be stored in memory before sending them to the client.
     /// This prevents a single request from using up all available memory.
     #[serde(default = "default_max_pending_rows")]
     pub max_pending_rows: usize,
 
+    /// Maximum number of components of the `templates` folder kept compiled in memory.
+    /// When it is reached, the least recently used component is removed, and compiled again when it is used.
+    #[serde(default = "default_max_cached_templates")]
+    pub max_cached_templates: usize,
+
+    /// Maximum total size, in bytes, of the rendered data waiting to be sent to a client.
+    /// When it is reached, the execution of the queries is paused until the client receives some data,
+    /// so that large exports to slow clients do not fill the memory of the server.
+    #[serde(default = "default_max_pending_response_bytes")]
+    pub max_pending_response_bytes: usize,
+
     /// Whether to compress the http response body when the client supports it.
     #[serde(default = "default_compress_responses")]
     pub compress_responses: bool,
 
+    /// Theme used by the shell when the page does not set one: "light", "dark", or "auto"
+    /// to follow the user's operating system preferences.
+    pub default_theme: Option<String>,
+
+    /// Text displayed by templates in place of NULL values, in table cells
+    /// and when using the `default` helper with a single argument. Defaults to an empty string.
+    pub null_placeholder: Option<String>,
+
+    /// Whether properties with a NULL value should be removed from the rows passed to components,
+    /// so that templates see them as missing instead of null.
+    #[serde(default)]
+    pub omit_null_properties: bool,
+
+    /// Shell component to use instead of the default `shell` for the pages in a given directory.
+    /// Keys are directory paths relative to the web root, and values are component names,
+    /// such as `{"admin": "shell-admin"}`. The deepest matching directory wins.
+    #[serde(default)]
+    pub directory_shells: HashMap<String, String>,
+
+    /// Whether the page listing all the components of the site, at `/sqlpage/components`, is served.
+    #[serde(default)]
+    pub component_gallery: bool,
+
+    /// Directories containing additional components, shared between sites.
+    /// Their components override the built-in ones, and are overridden by the ones in
+    /// the `templates` folder of the configuration directory.
+    #[serde(default)]
+    pub plugin_template_directories: Vec<PathBuf>,
+
+    /// Statements that take longer than this number of seconds to execute are logged,
+    /// with the `sqlpage::slow_query_log` log target.
+    pub slow_query_log_threshold_seconds: Option<f64>,
+
+    /// Table in which the slow queries are recorded, in addition to the logs.
+    /// It must have the columns `path`, `query`, `parameters`, and `duration_ms`.
+    pub slow_query_log_table: Option<String>,
+
+    /// Table in which every request is recorded, with its date, method, path, user, session, client address,
+    /// status, and duration. It must have the columns `requested_at`, `method`, `path`, `user_name`,
+    /// `session_id`, `client_ip`, `status`, and `duration_ms`.
+    pub audit_log_table: Option<String>,
+
+    /// Name of the cookie whose value is recorded in the `session_id` column of the `audit_log_table`.
+    pub audit_log_session_cookie: Option<String>,
+
+    /// Table in which the idempotency keys of form submissions and the corresponding responses are recorded.
+    /// It must have the columns `idempotency_key`, `path`, and `response`.
+    pub idempotency_table: Option<String>,
+
+    /// Secret that enables the debug mode of a page, which displays the query plan and the duration
+    /// of each statement, when it is sent in the `X-SQLPage-Debug` header.
+    pub debug_token: Option<String>,
+
+    /// Secret used by `sqlpage.sign_token` and `sqlpage.verify_token` to sign tokens,
+    /// such as the ones of password reset links.
+    pub token_signing_secret: Option<String>,
+
+    /// Argon2 hash of the password of the administration pages at `/sqlpage/admin/`,
+    /// as generated by `sqlpage.hash_password`. The user name is `admin`.
+    /// The administration pages are disabled when it is not set.
+    pub admin_password_hash: Option<String>,
+
+    /// Whether the administration pages include a SQL console, at `/sqlpage/admin/sql`,
+    /// in which the administrator can run arbitrary queries.
+    #[serde(default)]
+    pub admin_sql_console: bool,
+
+    /// Whether the queries of the SQL console are run in a read-only transaction that is always rolled back,
+    /// so that they cannot modify the database.
+    #[serde(default = "default_admin_sql_console_read_only")]
+    pub admin_sql_console_read_only: bool,
+
+    /// Kinds of statements allowed in the SQL console, identified by their first keyword,
+    /// such as `SELECT` or `EXPLAIN`. All statements are allowed when the list is empty.
+    #[serde(default)]
+    pub admin_sql_console_allowed_statements: Vec<String>,
+
+    /// Directory of the web root in which the file manager of the administration pages,
+    /// at `/sqlpage/admin/files`, lets the administrator browse, upload, rename, and delete files.
+    /// The file manager is disabled when it is not set.
+    pub admin_file_manager_directory: Option<PathBuf>,
+
     /// Content-Security-Policy header to send to the client.
-    /// If not set, a default policy allowing scripts from the same origin is used and from jsdelivr.net
+    /// If not set, a default policy allowing only scripts from the same origin and inline scripts
+    /// with the nonce of the current page is used.
+    /// Custom policies can contain `{NONCE}`, which is replaced with the nonce of the current page.
     pub content_security_policy: Option<String>,
 
     /// Whether `sqlpage.fetch` should load trusted certificates from the operating system's certificate store
@@ -246,10 +596,18 @@ pub struct AppConfig {
     #[serde(default = "default_system_root_ca_certificates")]
     pub system_root_ca_certificates: bool,
 
-    /// Maximum depth of recursion allowed in the `run_sql` function.
+    /// Maximum depth of recursion allowed in the `run_sql` function, and of `dynamic` components nested in each other.
     #[serde(default = "default_max_recursion_depth")]
     pub max_recursion_depth: u8,
 
+    /// Maximum number of SQL statements executed by a single request, including the files included with `run_sql`.
+    /// Requests that execute more statements are stopped with an error. Unlimited by default.
+    pub max_statements_per_request: Option<u64>,
+
+    /// Maximum number of rows returned by the queries of a single request.
+    /// Requests that return more rows are stopped with an error. Unlimited by default.
+    pub max_rows_per_request: Option<u64>,
+
     #[serde(default = "default_markdown_allow_dangerous_html")]
     pub markdown_allow_dangerous_html: bool,
 
@@ -272,12 +630,191 @@ impl AppConfig {
         }
         addr
     }
+
+    /// The public URL of the site: `site_url`, or the URL at which the server can be reached locally
+    #[must_use]
+    pub fn site_url(&self) -> String {
+        if let Some(site_url) = &self.site_url {
+            return site_url.clone();
+        }
+        if let Some(domain) = &self.https_domain {
+            return format!("https://{domain}");
+        }
+        let mut addr = self.listen_on();
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => std::net::Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        format!("http://{addr}")
+    }
+
+    /// Returns the shell configured in `directory_shells` for the given sql file,
+    /// whose path is relative to the web root.
+    #[must_use]
+    pub fn directory_shell(&self, sql_file_path: &Path) -> Option<&str> {
+        sql_file_path.ancestors().skip(1).find_map(|dir| {
+            self.directory_shells.iter().find_map(|(shell_dir, shell)| {
+                (Path::new(shell_dir.trim_matches('/')) == dir).then_some(shell.as_str())
+            })
+        })
+    }
+
+    /// When the configuration is reloaded, copies the settings that are only read when the server starts
+    /// from `self` to `new`, and returns the names of the ones that were different in `new`.
+    #[allow(clippy::float_cmp)]
+    pub fn keep_startup_settings(&self, new: &mut AppConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! keep {
+            ($($field:ident),* $(,)?) => {$(
+                if self.$field != new.$field {
+                    changed.push(stringify!($field));
+                    new.$field.clone_from(&self.$field);
+                }
+            )*};
+        }
+        keep!(
+            database_url,
+            database_password,
+            database_credentials,
+            database_role_query,
+            max_database_pool_connections,
+            database_connection_idle_timeout_seconds,
+            database_connection_max_lifetime_seconds,
+            sqlite_extensions,
+            listen_on,
+            port,
+            unix_socket,
+            http_workers,
+            http_keep_alive_seconds,
+            http_client_request_timeout_seconds,
+            http_backlog,
+            database_connection_retries,
+            database_connection_acquire_timeout_seconds,
+            web_root,
+            configuration_directory,
+            max_uploaded_file_size,
+            https_domain,
+            https_certificate_email,
+            https_certificate_cache_dir,
+            https_acme_directory_url,
+            environment,
+            site_prefix,
+            openapi_path,
+            batch_path,
+            pdf_max_concurrent_renders,
+            geoip_database_path,
+            max_cached_templates,
+            audit_log_table,
+            compress_responses,
+            default_theme,
+            null_placeholder,
+            plugin_template_directories,
+            content_security_policy,
+            markdown_allow_dangerous_html,
+            markdown_allow_dangerous_protocol,
+            // The key of the forms and live components is derived from it when the server starts
+            token_signing_secret,
+        );
+        changed
+    }
+
+    /// Returns the route of `proxy_routes` with the longest path that contains the given URL path,
+    /// and that path. A route for `/api` applies to `/api` and `/api/users`, but not to `/apiary`.
+    #[must_use]
+    pub fn proxy_route(&self, url_path: &str) -> Option<(&str, &ProxyRoute)> {
+        self.proxy_routes
+            .iter()
+            .filter(|(prefix, _)| is_in_route(url_path, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, route)| (prefix.as_str(), route))
+    }
+
+    /// Returns the rule of `ip_access_rules` that applies to a percent-encoded URL path,
+    /// which includes the `site_prefix`
+    #[must_use]
+    pub fn ip_access_rule_for_url(&self, url_path: &str) -> Option<&IpAccessRule> {
+        if self.ip_access_rules.is_empty() {
+            return None;
+        }
+        self.ip_access_rule(&self.path_in_site(url_path))
+    }
+
+    /// Decodes a percent-encoded URL path, and removes the `site_prefix` from it
+    fn path_in_site(&self, url_path: &str) -> String {
+        let decoded_path = percent_encoding::percent_decode_str(url_path).decode_utf8_lossy();
+        let path = decoded_path
+            .strip_prefix(&self.site_prefix)
+            .unwrap_or(&decoded_path);
+        if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{path}")
+        }
+    }
+
+    /// Returns the sources of the `frame-ancestors` policy of a percent-encoded URL path,
+    /// or `None` when `frame_ancestors` is not set and pages can be embedded anywhere.
+    #[must_use]
+    pub fn frame_ancestors_for_url(&self, url_path: &str) -> Option<String> {
+        if self.frame_ancestors.is_empty() {
+            return None;
+        }
+        let path = self.path_in_site(url_path);
+        let origins = self
+            .frame_ancestors
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, origins)| origins);
+        Some(match origins {
+            None => "'self'".to_string(),
+            Some(origins) if origins.is_empty() => "'none'".to_string(),
+            Some(origins) => origins.join(" "),
+        })
+    }
+
+    /// Returns the rule of `ip_access_rules` with the longest path that contains the given URL path.
+    /// Paths are compared by whole segments, and ignoring case, like the files they lead to on
+    /// case-insensitive file systems: a rule for `/admin` applies to `/ADMIN/users.sql`, but not to `/administration.sql`.
+    #[must_use]
+    pub fn ip_access_rule(&self, url_path: &str) -> Option<&IpAccessRule> {
+        self.ip_access_rules
+            .iter()
+            .filter(|(prefix, _)| is_in_path_ignoring_case(url_path, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rule)| rule)
+    }
+}
+
+/// Whether `path` starts with the path of a route, followed by a `/` or by the end of the path
+pub(crate) fn is_in_route(path: &str, route: &str) -> bool {
+    path.strip_prefix(route)
+        .is_some_and(|rest| route.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Whether `path` is `prefix`, or is inside it when it is a directory, ignoring ASCII case
+fn is_in_path_ignoring_case(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        && matches!(path.as_bytes().get(prefix.len()), None | Some(b'/'))
 }
 
 impl RoutingConfig for AppConfig {
     fn prefix(&self) -> &str {
         &self.site_prefix
     }
+
+    fn proxy_upstream(&self, path: &str) -> Option<(&str, &str)> {
+        self.proxy_route(path)
+            .map(|(prefix, route)| (prefix, route.upstream.as_str()))
+    }
+
+    fn url_style(&self) -> UrlStyle {
+        self.url_style
+    }
 }
 
 /// The directory where the `sqlpage.json` file is located.
@@ -313,19 +850,27 @@ pub fn load_from_directory(directory: &Path) -> anyhow::Result<AppConfig> {
 }
 
 /// Parses and loads the configuration from the given file.
+/// Settings from an environment-specific overlay file next to it, such as `sqlpage.production.toml`
+/// for `sqlpage.json` when `environment` is `production`, take precedence over the ones of the file.
 pub fn load_from_file(config_file: &Path) -> anyhow::Result<AppConfig> {
     log::debug!("Loading configuration from file: {:?}", config_file);
-    let config = Config::builder()
+    let environment = configured_environment(config_file)?;
+    let overlay_file = environment_overlay_file(config_file, &environment);
+    log::debug!("Loading the {environment} configuration overlay from {overlay_file:?}");
+    let mut builder = Config::builder()
         .add_source(config::File::from(config_file).required(false))
+        .add_source(config::File::from(overlay_file).required(false))
         .add_source(env_config())
-        .add_source(env_config().prefix("SQLPAGE"))
-        .build()
-        .with_context(|| {
-            format!(
-                "Unable to build configuration loader for {}",
-                config_file.display()
-            )
-        })?;
+        .add_source(env_config().prefix("SQLPAGE"));
+    for (setting, value) in secrets_from_files()? {
+        builder = builder.set_override(setting, value)?;
+    }
+    let config = builder.build().with_context(|| {
+        format!(
+            "Unable to build configuration loader for {}",
+            config_file.display()
+        )
+    })?;
     log::trace!("Configuration sources: {:#?}", config.cache);
     let app_config = config
         .try_deserialize::<AppConfig>()
@@ -333,11 +878,72 @@ pub fn load_from_file(config_file: &Path) -> anyhow::Result<AppConfig> {
     Ok(app_config)
 }
 
+/// Settings that contain secrets. Instead of the secret itself, the `<SETTING>_FILE` environment variable
+/// can contain the path to a file with the secret, such as a Docker or Kubernetes secret,
+/// so that it does not appear in the environment of the process.
+const SECRET_SETTINGS: &[&str] = &[
+    "database_url",
+    "database_password",
+    "debug_token",
+    "token_signing_secret",
+    "admin_password_hash",
+];
+
+/// Reads the secrets from the files given in `DATABASE_URL_FILE`, `SQLPAGE_DATABASE_PASSWORD_FILE`, etc.
+fn secrets_from_files() -> anyhow::Result<Vec<(&'static str, String)>> {
+    let mut secrets = Vec::new();
+    for &setting in SECRET_SETTINGS {
+        for prefix in ["", "SQLPAGE_"] {
+            let var_name = format!("{prefix}{}", setting.to_uppercase());
+            let file_var_name = format!("{var_name}_FILE");
+            let Some(path) = std::env::var_os(&file_var_name) else {
+                continue;
+            };
+            anyhow::ensure!(
+                std::env::var_os(&var_name).is_none(),
+                "Both {var_name} and {file_var_name} are set. Please set only one of them."
+            );
+            log::debug!("Reading {setting} from {path:?}");
+            let value = std::fs::read_to_string(&path).with_context(|| {
+                format!("Unable to read the file {path:?} given in {file_var_name}")
+            })?;
+            secrets.push((setting, value.trim_end_matches(['\r', '\n']).to_string()));
+        }
+    }
+    Ok(secrets)
+}
+
+/// The `environment` set in the configuration file or in the environment variables, which selects the overlay file
+fn configured_environment(config_file: &Path) -> anyhow::Result<String> {
+    let config = Config::builder()
+        .add_source(config::File::from(config_file).required(false))
+        .add_source(env_config())
+        .add_source(env_config().prefix("SQLPAGE"))
+        .build()
+        .with_context(|| format!("Unable to read {}", config_file.display()))?;
+    Ok(match config.get_string("environment") {
+        Ok(environment) => environment.to_lowercase(),
+        Err(_) => "development".to_string(),
+    })
+}
+
+/// `sqlpage.json` and `sqlpage` both have the overlay `sqlpage.<environment>`, in any supported format
+fn environment_overlay_file(config_file: &Path, environment: &str) -> PathBuf {
+    let stem = config_file
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    config_file.with_file_name(format!("{stem}.{environment}"))
+}
+
 fn env_config() -> config::Environment {
     config::Environment::default()
         .try_parsing(true)
         .list_separator(" ")
         .with_list_parse_key("sqlite_extensions")
+        .with_list_parse_key("plugin_template_directories")
+        .with_list_parse_key("admin_sql_console_allowed_statements")
+        .with_list_parse_key("trusted_proxies")
 }
 
 fn deserialize_socket_addr<'de, D: Deserializer<'de>>(
@@ -436,7 +1042,8 @@ fn create_default_database(configuration_directory: &Path) -> String {
                 "Using the default database file in {}",
                 default_db_path.display()
             );
-            return prefix + &encode_uri(&default_db_path);
+            // Same URL as when the file is created, so that reloading the configuration does not see a change
+            return prefix + &encode_uri(&default_db_path) + "?mode=rwc";
         }
         // Create the default database file if we can
         if let Ok(tmp_file) = std::fs::File::create(&default_db_path) {
@@ -455,7 +1062,7 @@ fn create_default_database(configuration_directory: &Path) -> String {
 }
 
 #[cfg(any(test, not(feature = "lambda-web")))]
-fn encode_uri(path: &Path) -> std::borrow::Cow<str> {
+fn encode_uri(path: &Path) -> std::borrow::Cow<'_, str> {
     const ASCII_SET: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
         .remove(b'-')
         .remove(b'_')
@@ -496,10 +1103,26 @@ fn default_https_acme_directory_url() -> String {
 
 /// If the sending queue exceeds this number of outgoing messages, an error will be thrown
 /// This prevents a single request from using up all available memory
+fn default_max_concurrent_queries_per_request() -> usize {
+    4
+}
+
+fn default_pdf_max_concurrent_renders() -> usize {
+    2
+}
+
 fn default_max_pending_rows() -> usize {
     256
 }
 
+fn default_max_cached_templates() -> usize {
+    256
+}
+
+fn default_max_pending_response_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
 fn default_compress_responses() -> bool {
     true
 }
@@ -513,6 +1136,10 @@ fn default_max_recursion_depth() -> u8 {
     10
 }
 
+fn default_admin_sql_console_read_only() -> bool {
+    true
+}
+
 fn default_markdown_allow_dangerous_html() -> bool {
     false
 }
@@ -521,6 +1148,88 @@ fn default_markdown_allow_dangerous_protocol() -> bool {
     false
 }
 
+/// The preferred form of page URLs, such as `/page` or `/page.sql`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlStyle {
+    /// Pages are accessible both with and without the `.sql` extension
+    #[default]
+    Any,
+    /// `/page.sql` redirects to `/page`, and `/dir/index.sql` to `/dir/`
+    WithoutExtension,
+    /// `/page` redirects to `/page.sql`
+    WithExtension,
+}
+
+/// IP-based access control for the URLs starting with a given path
+#[derive(Debug, Deserialize, PartialEq, Clone, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct IpAccessRule {
+    /// When not empty, only the clients in these ranges can access the path
+    #[serde(default)]
+    pub allow: Vec<IpNet>,
+    /// Clients in these ranges cannot access the path, even if they are in an allowed range
+    #[serde(default)]
+    pub deny: Vec<IpNet>,
+}
+
+/// Forwarding of the requests to the URLs starting with a given path to another server
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyRoute {
+    /// URL that replaces the path of the route, such as `http://localhost:8080/api/`
+    pub upstream: String,
+    /// Headers to set on the forwarded requests. Headers with a null value are removed.
+    #[serde(default)]
+    pub request_headers: HashMap<String, Option<String>>,
+    /// Headers to set on the responses of the upstream server. Headers with a null value are removed.
+    #[serde(default)]
+    pub response_headers: HashMap<String, Option<String>>,
+    /// Maximum time to wait for the upstream server to start responding
+    #[serde(default = "default_proxy_timeout_seconds")]
+    pub timeout_seconds: f64,
+    /// Forward the `Cookie` and `Authorization` headers of the clients, and the `Set-Cookie` headers of the upstream server
+    #[serde(default)]
+    pub forward_credentials: bool,
+}
+
+fn default_proxy_timeout_seconds() -> f64 {
+    30.
+}
+
+impl IpAccessRule {
+    /// Whether a client can access the path. Clients whose address is unknown,
+    /// such as the ones connecting through a unix socket, are only denied by an allowlist.
+    #[must_use]
+    pub fn allows(&self, client_ip: Option<IpAddr>) -> bool {
+        let Some(ip) = client_ip.map(|ip| ip.to_canonical()) else {
+            return self.allow.is_empty();
+        };
+        let in_ranges = |ranges: &[IpNet]| ranges.iter().any(|net| net.contains(&ip));
+        !in_ranges(&self.deny) && (self.allow.is_empty() || in_ranges(&self.allow))
+    }
+}
+
+/// Where to get short-lived database credentials from
+#[derive(Debug, Deserialize, PartialEq, Clone, Eq)]
+#[serde(tag = "provider", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DatabaseCredentialsProvider {
+    /// Dynamic secrets from the database secrets engine of `HashiCorp` Vault
+    Vault {
+        /// Address of the Vault server. Defaults to the `VAULT_ADDR` environment variable.
+        address: Option<String>,
+        /// Path of the credentials, such as `database/creds/my-role`
+        path: String,
+        /// Defaults to the `VAULT_TOKEN` environment variable
+        token: Option<String>,
+    },
+    /// Authentication tokens generated from the AWS credentials of the environment, profile, container or instance, for IAM database authentication on RDS
+    AwsRdsIam {
+        /// Defaults to the `AWS_REGION` environment variable
+        region: Option<String>,
+    },
+}
+
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum DevOrProd {
@@ -534,6 +1243,70 @@ impl DevOrProd {
     }
 }
 
+/// What happens when a SQL statement fails
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy, Eq, Default)]
+#[serde(try_from = "OnErrorConfig")]
+pub enum OnError {
+    /// Display the error, and execute the next statements
+    Continue,
+    /// Display the error, and stop executing the page
+    #[default]
+    Abort,
+    /// Stop executing the page, and respond with this HTTP status code if the response has not started yet
+    Status(StatusCode),
+}
+
+/// `on_error` is a string in the configuration file, or a status code
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OnErrorConfig {
+    Status(u16),
+    Text(String),
+}
+
+impl TryFrom<OnErrorConfig> for OnError {
+    type Error = String;
+    fn try_from(value: OnErrorConfig) -> Result<Self, Self::Error> {
+        match value {
+            OnErrorConfig::Status(status) => Self::status(status),
+            OnErrorConfig::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl OnError {
+    fn status(status: u16) -> Result<Self, String> {
+        match StatusCode::from_u16(status) {
+            Ok(status) if status.is_client_error() || status.is_server_error() => {
+                Ok(Self::Status(status))
+            }
+            _ => Err(format!("{status} is not an HTTP error status code")),
+        }
+    }
+
+    /// Whether the statements that follow a failed one are executed
+    #[must_use]
+    pub fn continues(self) -> bool {
+        self == Self::Continue
+    }
+}
+
+impl std::str::FromStr for OnError {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("continue") => Ok(Self::Continue),
+            s if s.eq_ignore_ascii_case("abort") => Ok(Self::Abort),
+            s => match s.parse() {
+                Ok(status) => Self::status(status),
+                Err(_) => Err(format!(
+                    "Invalid on_error value {s:?}: expected 'continue', 'abort', or an HTTP status code"
+                )),
+            },
+        }
+    }
+}
+
 #[must_use]
 pub fn test_database_url() -> String {
     std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string())
@@ -601,6 +1374,22 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_on_error_parsing() {
+        assert_eq!("continue".parse(), Ok(OnError::Continue));
+        assert_eq!(" Abort".parse(), Ok(OnError::Abort));
+        assert_eq!(
+            "503".parse(),
+            Ok(OnError::Status(StatusCode::SERVICE_UNAVAILABLE))
+        );
+        assert!("200".parse::<OnError>().is_err());
+        assert!("retry".parse::<OnError>().is_err());
+        let config: OnError = serde_json::from_str("404").unwrap();
+        assert_eq!(config, OnError::Status(StatusCode::NOT_FOUND));
+        let config: OnError = serde_json::from_str(r#""continue""#).unwrap();
+        assert_eq!(config, OnError::Continue);
+    }
+
     #[test]
     fn test_cli_argument_parsing() {
         let cli = Cli::parse_from([
@@ -616,6 +1405,14 @@ mod test {
         assert_eq!(cli.web_root, Some(PathBuf::from("/path/to/web")));
         assert_eq!(cli.config_dir, Some(PathBuf::from("/path/to/config")));
         assert_eq!(cli.config_file, Some(PathBuf::from("/path/to/config.json")));
+        assert_eq!(cli.command, None);
+    }
+
+    #[test]
+    fn test_check_command_parsing() {
+        let cli = Cli::parse_from(["sqlpage", "--web-root", "/web", "check", "--syntax-only"]);
+        assert_eq!(cli.web_root, Some(PathBuf::from("/web")));
+        assert_eq!(cli.command, Some(Command::Check { syntax_only: true }));
     }
 
     #[test]
@@ -647,6 +1444,7 @@ mod test {
             web_root: Some(PathBuf::from(".")),
             config_dir: None,
             config_file: None,
+            command: None,
         };
 
         let config = AppConfig::from_cli(&cli).unwrap();
@@ -687,6 +1485,7 @@ mod test {
             web_root: None,
             config_dir: None,
             config_file: Some(config_file_path.clone()),
+            command: None,
         };
 
         let config = AppConfig::from_cli(&cli).unwrap();
@@ -705,6 +1504,7 @@ mod test {
             web_root: Some(cli_web_dir.clone()),
             config_dir: None,
             config_file: Some(config_file_path),
+            command: None,
         };
 
         let config = AppConfig::from_cli(&cli_with_web_root).unwrap();
@@ -722,6 +1522,191 @@ mod test {
         std::fs::remove_dir_all(&temp_dir).unwrap();
     }
 
+    #[test]
+    fn test_environment_overlays() {
+        let _lock = ENV_LOCK
+            .lock()
+            .expect("Another test panicked while holding the lock");
+        let config_dir = std::env::temp_dir().join("sqlpage_overlay_test");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(
+            config_dir.join("sqlpage.json"),
+            r#"{"environment": "production", "max_pending_rows": 10, "debug_token": "base"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            config_dir.join("sqlpage.production.toml"),
+            "max_pending_rows = 20",
+        )
+        .unwrap();
+        std::fs::write(
+            config_dir.join("sqlpage.development.json"),
+            r#"{"max_pending_rows": 30}"#,
+        )
+        .unwrap();
+
+        let config = load_from_directory(&config_dir).unwrap();
+        assert_eq!(config.environment, DevOrProd::Production);
+        assert_eq!(config.max_pending_rows, 20);
+        assert_eq!(config.debug_token.as_deref(), Some("base"));
+
+        env::set_var("SQLPAGE_ENVIRONMENT", "development");
+        let config = load_from_directory(&config_dir);
+        env::remove_var("SQLPAGE_ENVIRONMENT");
+        let config = config.unwrap();
+        assert_eq!(config.environment, DevOrProd::Development);
+        assert_eq!(config.max_pending_rows, 30);
+
+        std::fs::remove_dir_all(&config_dir).unwrap();
+    }
+
+    #[test]
+    fn test_secrets_from_files() {
+        let _lock = ENV_LOCK
+            .lock()
+            .expect("Another test panicked while holding the lock");
+        let secret_file = std::env::temp_dir().join("sqlpage_test_secret");
+        std::fs::write(&secret_file, "my secret password\n").unwrap();
+        env::set_var("SQLPAGE_DATABASE_PASSWORD_FILE", &secret_file);
+        let secrets = secrets_from_files();
+        env::set_var("SQLPAGE_DATABASE_PASSWORD", "another password");
+        let conflicting_secrets = secrets_from_files();
+        env::remove_var("SQLPAGE_DATABASE_PASSWORD");
+        env::remove_var("SQLPAGE_DATABASE_PASSWORD_FILE");
+        std::fs::remove_file(&secret_file).unwrap();
+
+        assert_eq!(
+            secrets.unwrap(),
+            [("database_password", "my secret password".to_string())]
+        );
+        let error = conflicting_secrets.unwrap_err().to_string();
+        assert!(error.contains("SQLPAGE_DATABASE_PASSWORD_FILE"), "{error}");
+    }
+
+    #[test]
+    fn test_directory_shell() {
+        let mut config = tests::test_config();
+        config
+            .directory_shells
+            .insert("admin/".into(), "shell-admin".into());
+        config
+            .directory_shells
+            .insert("admin/public".into(), "shell".into());
+        let shell = |path: &str| config.directory_shell(Path::new(path));
+        assert_eq!(shell("index.sql"), None);
+        assert_eq!(shell("administration/index.sql"), None);
+        assert_eq!(shell("admin/index.sql"), Some("shell-admin"));
+        assert_eq!(shell("admin/users/edit.sql"), Some("shell-admin"));
+        assert_eq!(shell("admin/public/index.sql"), Some("shell"));
+    }
+
+    #[test]
+    fn test_ip_access_rule() {
+        let mut config = tests::test_config();
+        let rule = |allow: &[&str], deny: &[&str]| IpAccessRule {
+            allow: allow.iter().map(|net| net.parse().unwrap()).collect(),
+            deny: deny.iter().map(|net| net.parse().unwrap()).collect(),
+        };
+        let office = rule(&["192.168.0.0/16"], &["192.168.66.0/24"]);
+        let everyone = rule(&[], &[]);
+        config
+            .ip_access_rules
+            .insert("/admin/".into(), office.clone());
+        config
+            .ip_access_rules
+            .insert("/admin/public/".into(), everyone.clone());
+        assert_eq!(config.ip_access_rule("/index.sql"), None);
+        assert_eq!(config.ip_access_rule("/admin/users.sql"), Some(&office));
+        assert_eq!(config.ip_access_rule("/admin/public/"), Some(&everyone));
+        assert_eq!(config.ip_access_rule("/admin"), Some(&office));
+        assert_eq!(config.ip_access_rule("/ADMIN/users.sql"), Some(&office));
+        assert_eq!(
+            config.ip_access_rule("/Admin/Public/x.sql"),
+            Some(&everyone)
+        );
+        assert_eq!(config.ip_access_rule("/administration.sql"), None);
+        assert_eq!(
+            config.ip_access_rule("/admin/publication.sql"),
+            Some(&office)
+        );
+        let allowed = |ip: &str| office.allows(Some(ip.parse().unwrap()));
+        assert!(allowed("192.168.1.1"));
+        assert!(allowed("::ffff:192.168.1.1"));
+        assert!(!allowed("192.168.66.1"));
+        assert!(!allowed("10.0.0.1"));
+        assert!(!office.allows(None));
+        assert!(everyone.allows(None));
+    }
+
+    #[test]
+    fn test_frame_ancestors() {
+        let mut config = tests::test_config();
+        assert_eq!(config.frame_ancestors_for_url("/index.sql"), None);
+        config.frame_ancestors.insert(
+            "/dashboards/".into(),
+            vec!["'self'".into(), "https://wiki.example.com".into()],
+        );
+        config
+            .frame_ancestors
+            .insert("/dashboards/private/".into(), vec![]);
+        assert_eq!(
+            config.frame_ancestors_for_url("/index.sql").as_deref(),
+            Some("'self'")
+        );
+        assert_eq!(
+            config
+                .frame_ancestors_for_url("/dashboards/sales.sql")
+                .as_deref(),
+            Some("'self' https://wiki.example.com")
+        );
+        assert_eq!(
+            config
+                .frame_ancestors_for_url("/dashboards/private/salaries.sql")
+                .as_deref(),
+            Some("'none'")
+        );
+        assert!(config.validate().is_ok());
+        config.frame_ancestors.insert(
+            "/embed/".into(),
+            vec!["https://a.example.com; script-src *".into()],
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_http_server_settings_validation() {
+        let mut config = tests::test_config();
+        assert!(config.validate().is_ok());
+        config.http_workers = Some(0);
+        assert!(config.validate().is_err());
+        config.http_workers = Some(4);
+        config.http_keep_alive_seconds = Some(-1.0);
+        assert!(config.validate().is_err());
+        config.http_keep_alive_seconds = Some(0.0);
+        assert!(config.validate().is_ok(), "0 disables keep-alive");
+        config.http_backlog = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_site_url() {
+        let mut config = tests::test_config();
+        config.listen_on = Some("0.0.0.0:8080".parse().unwrap());
+        assert_eq!(config.site_url(), "http://127.0.0.1:8080");
+        config.listen_on = Some("[::]:80".parse().unwrap());
+        assert_eq!(config.site_url(), "http://[::1]:80");
+        config.https_domain = Some("example.com".into());
+        assert_eq!(config.site_url(), "https://example.com");
+        config.site_url = Some("https://www.example.com/".into());
+        assert_eq!(config.site_url(), "https://www.example.com/");
+        assert!(config.validate().is_ok());
+        config.site_url = Some("www.example.com".into());
+        assert!(config.validate().is_err());
+        config.site_url = None;
+        config.pdf_max_concurrent_renders = 0;
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_default_values() {
         let _lock = ENV_LOCK
@@ -734,6 +1719,7 @@ mod test {
             web_root: None,
             config_dir: None,
             config_file: None,
+            command: None,
         };
 
         let config = AppConfig::from_cli(&cli).unwrap();
@@ -0,0 +1,69 @@
+//! Information about the running build, embedded at compile time by `build.rs`.
+//! It is displayed by `sqlpage --version`, returned by the `/sqlpage/version` administration page,
+//! and by the `sqlpage.version('full')` SQL function.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The abbreviated hash of the git commit of the build, or `unknown`
+pub const GIT_COMMIT: &str = env!("SQLPAGE_GIT_COMMIT");
+/// The UTC date of the build, in the YYYY-MM-DD format
+pub const BUILD_DATE: &str = env!("SQLPAGE_BUILD_DATE");
+/// The comma-separated list of the enabled cargo features
+pub const FEATURES: &str = env!("SQLPAGE_FEATURES");
+
+/// The target triple of the build, such as `x86_64-unknown-linux-gnu`
+pub const TARGET: &str = env!("SQLPAGE_TARGET");
+
+/// The version displayed by `sqlpage --version`, with the details of the build
+#[must_use]
+pub fn long_version() -> &'static str {
+    static LONG_VERSION: OnceLock<String> = OnceLock::new();
+    LONG_VERSION.get_or_init(|| {
+        let info = BuildInfo::get();
+        let features = if info.features.is_empty() {
+            "none".to_string()
+        } else {
+            info.features.join(", ")
+        };
+        format!(
+            "{}\ncommit: {}\nbuild date: {}\ntarget: {}\nfeatures: {features}",
+            info.version, info.git_commit, info.build_date, info.target
+        )
+    })
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub build_date: &'static str,
+    pub features: &'static [&'static str],
+    pub target: &'static str,
+}
+
+impl BuildInfo {
+    #[must_use]
+    pub fn get() -> Self {
+        static FEATURE_LIST: OnceLock<Vec<&'static str>> = OnceLock::new();
+        let features = FEATURE_LIST.get_or_init(|| {
+            FEATURES
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect()
+        });
+        Self {
+            version: VERSION,
+            git_commit: GIT_COMMIT,
+            build_date: BUILD_DATE,
+            features,
+            target: TARGET,
+        }
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
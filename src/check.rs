@@ -0,0 +1,290 @@
+//! Implementation of the `sqlpage check` command,
+//! which validates all the SQL files of a site without serving it.
+//!
+//! Every `.sql` file in the web root is parsed, each of its statements is prepared on the database
+//! (unless running in syntax-only mode), and the components it references are looked up.
+//! Properties that the components do not use, and properties that they render without escaping,
+//! are reported as warnings.
+
+use crate::app_config::AppConfig;
+use crate::render::is_template_free_component;
+use crate::templates::{AllTemplates, SplitTemplate};
+use crate::webserver::database::migrations;
+use crate::webserver::database::{LiteralComponent, ParsedSqlFile};
+use crate::webserver::Database;
+use crate::AppState;
+use anyhow::Context;
+use sqlx::any::AnyKind;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A problem found in a SQL file.
+#[derive(Debug)]
+pub struct Problem {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub files_checked: usize,
+    pub problems: Vec<Problem>,
+    /// Problems that do not prevent the site from working, such as unknown component properties
+    pub warnings: Vec<Problem>,
+}
+
+impl CheckReport {
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn add_problem(&mut self, path: &Path, error: anyhow::Error) {
+        self.problems.push(Problem {
+            path: path.to_path_buf(),
+            error,
+        });
+    }
+
+    fn check_properties(
+        &mut self,
+        path: &Path,
+        component: &LiteralComponent,
+        template: &SplitTemplate,
+    ) {
+        for property in component.properties.iter().flatten() {
+            if !template.properties.is_known(property, true) {
+                self.warnings.push(Problem {
+                    path: path.to_path_buf(),
+                    error: anyhow::anyhow!(
+                        "The '{}' component does not use the '{property}' property",
+                        component.name
+                    ),
+                });
+            }
+        }
+        for unescaped in &template.unescaped_properties {
+            self.warnings.push(Problem {
+                path: path.to_path_buf(),
+                error: anyhow::anyhow!("In the '{}' component, {unescaped}", component.name),
+            });
+        }
+    }
+}
+
+impl Display for CheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for Problem { path, error } in &self.warnings {
+            writeln!(f, "{}: warning: {error:#}\n", path.display())?;
+        }
+        for Problem { path, error } in &self.problems {
+            writeln!(f, "{}: {error:#}\n", path.display())?;
+        }
+        write!(
+            f,
+            "Checked {} files: {} problems found, {} warnings.",
+            self.files_checked,
+            self.problems.len(),
+            self.warnings.len()
+        )
+    }
+}
+
+/// Checks all the SQL files in the web root.
+/// When `syntax_only` is false, the database migrations are applied,
+/// and every statement is prepared on the configured database.
+pub async fn check_site(config: &AppConfig, syntax_only: bool) -> anyhow::Result<CheckReport> {
+    let files = list_sql_files(config)?;
+    let mut report = CheckReport::default();
+    if syntax_only {
+        let db_kind = AnyKind::from_str(&config.database_url)
+            .with_context(|| "Unable to determine the database type from database_url")?;
+        for path in files {
+            let Some(parsed) = read_and_parse(config, db_kind, &path, &mut report) else {
+                continue;
+            };
+            for component in parsed.literal_components(db_kind) {
+                if is_template_free_component(&component.name) {
+                    continue;
+                }
+                match AllTemplates::get_builtin_or_local_template(config, &component.name) {
+                    Ok(Some(template)) => report.check_properties(&path, &component, &template),
+                    Ok(None) => report.add_problem(&path, unknown_component(&component.name)),
+                    Err(err) => {
+                        report.add_problem(&path, err.context(unknown_component(&component.name)));
+                    }
+                }
+            }
+        }
+    } else {
+        let db = Database::init(config).await?;
+        migrations::apply(config, &db).await?;
+        let db_kind = db.connection().any_kind();
+        let app_state = AppState::init_with_db(config, db).await?;
+        for path in files {
+            let Some(parsed) = read_and_parse(config, db_kind, &path, &mut report) else {
+                continue;
+            };
+            for query in parsed.database_queries() {
+                if let Err(err) = sqlx::Executor::prepare(&app_state.db.connection(), query).await {
+                    let err = anyhow::Error::new(err)
+                        .context(format!("The database rejected the query:\n{query}"));
+                    report.add_problem(&path, err);
+                }
+            }
+            for component in parsed.literal_components(db_kind) {
+                if is_template_free_component(&component.name) {
+                    continue;
+                }
+                match app_state
+                    .all_templates
+                    .get_template(&app_state, &component.name)
+                    .await
+                {
+                    Ok(template) => report.check_properties(&path, &component, &template),
+                    Err(err) => {
+                        report.add_problem(&path, err.context(unknown_component(&component.name)));
+                    }
+                }
+            }
+        }
+        app_state.db.close().await?;
+    }
+    Ok(report)
+}
+
+fn read_and_parse(
+    config: &AppConfig,
+    db_kind: AnyKind,
+    path: &Path,
+    report: &mut CheckReport,
+) -> Option<ParsedSqlFile> {
+    report.files_checked += 1;
+    let sql = match std::fs::read_to_string(config.web_root.join(path)) {
+        Ok(sql) => sql,
+        Err(err) => {
+            report.add_problem(
+                path,
+                anyhow::Error::new(err).context("Unable to read the file"),
+            );
+            return None;
+        }
+    };
+    let parsed = ParsedSqlFile::new_for_db_kind(db_kind, &sql, path);
+    let mut has_errors = false;
+    for err in parsed.parse_errors() {
+        has_errors = true;
+        report.add_problem(path, anyhow::anyhow!("{err:#}"));
+    }
+    (!has_errors).then_some(parsed)
+}
+
+fn unknown_component(name: &str) -> anyhow::Error {
+    anyhow::anyhow!("Unknown component '{name}'")
+}
+
+/// Lists the paths of all the .sql files in the web root, relative to the web root.
+/// The `sqlpage` configuration directory and hidden directories are skipped,
+/// since their files cannot be served.
+pub(crate) fn list_sql_files(config: &AppConfig) -> anyhow::Result<Vec<PathBuf>> {
+    let configuration_directory = std::fs::canonicalize(&config.configuration_directory)
+        .unwrap_or_else(|_| config.configuration_directory.clone());
+    let mut files = Vec::new();
+    let mut dirs_to_visit = vec![PathBuf::new()];
+    while let Some(dir) = dirs_to_visit.pop() {
+        let full_dir = config.web_root.join(&dir);
+        let entries = std::fs::read_dir(&full_dir)
+            .with_context(|| format!("Unable to list the files in {}", full_dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let path = dir.join(&name);
+            if entry.file_type()?.is_dir() {
+                let is_hidden = name.to_string_lossy().starts_with('.');
+                let is_config_dir = (dir.as_os_str().is_empty()
+                    && name.eq_ignore_ascii_case("sqlpage"))
+                    || std::fs::canonicalize(entry.path())
+                        .is_ok_and(|p| p == configuration_directory);
+                if !is_hidden && !is_config_dir {
+                    dirs_to_visit.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "sql") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+
+    #[actix_web::test]
+    async fn test_check_site() {
+        let web_root = std::env::temp_dir().join("sqlpage_test_check_site");
+        let _ = std::fs::remove_dir_all(&web_root);
+        std::fs::create_dir_all(web_root.join("sub")).unwrap();
+        std::fs::create_dir_all(web_root.join("sqlpage/migrations")).unwrap();
+        std::fs::create_dir_all(web_root.join("sqlpage/templates")).unwrap();
+        std::fs::write(
+            web_root.join("sqlpage/templates/banner.handlebars"),
+            "<div>{{{message}}}{{{extra_html}}}</div>",
+        )
+        .unwrap();
+        std::fs::write(
+            web_root.join("sub/banner.sql"),
+            "select 'banner' as component, 'Hi' as message;",
+        )
+        .unwrap();
+        std::fs::write(
+            web_root.join("index.sql"),
+            "select 'list' as component, 'Hello' as titel;",
+        )
+        .unwrap();
+        std::fs::write(web_root.join("sub/typo.sql"), "select 'lsit' as component;").unwrap();
+        std::fs::write(web_root.join("sub/syntax.sql"), "select (1 + ;").unwrap();
+        std::fs::write(
+            web_root.join("sub/redirect.sql"),
+            "select 'redirect' as component;",
+        )
+        .unwrap();
+        std::fs::write(
+            web_root.join("sqlpage/migrations/0001_ignored.sql"),
+            "not sql",
+        )
+        .unwrap();
+
+        let mut config = test_config();
+        config.web_root.clone_from(&web_root);
+        config.configuration_directory = web_root.join("sqlpage");
+        let report = check_site(&config, true).await.unwrap();
+        std::fs::remove_dir_all(&web_root).unwrap();
+
+        assert_eq!(report.files_checked, 5);
+        let problem_files: Vec<_> = report.problems.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(
+            problem_files,
+            [
+                PathBuf::from("sub/syntax.sql"),
+                PathBuf::from("sub/typo.sql")
+            ]
+        );
+        assert!(!report.is_ok());
+        assert!(report.to_string().contains("Unknown component 'lsit'"));
+        let warning_files: Vec<_> = report.warnings.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(
+            warning_files,
+            [PathBuf::from("index.sql"), PathBuf::from("sub/banner.sql")]
+        );
+        assert!(report
+            .to_string()
+            .contains("'list' component does not use the 'titel' property"));
+        assert!(report.to_string().contains(
+            "In the 'banner' component, the 'message' property is rendered without escaping"
+        ));
+    }
+}
@@ -3,16 +3,29 @@ use serde_json::Value as JsonValue;
 
 use crate::webserver::database::DbItem;
 
-pub fn parse_dynamic_rows(row: DbItem) -> impl Iterator<Item = DbItem> {
+/// Replaces the rows of the `dynamic` component by the rows contained in their properties.
+/// Dynamic components can contain other dynamic components, up to `max_depth` levels deep.
+pub fn parse_dynamic_rows(row: DbItem, max_depth: u8) -> impl Iterator<Item = DbItem> {
     DynamicComponentIterator {
         stack: vec![],
         db_item: Some(row),
+        max_depth,
     }
 }
 
 struct DynamicComponentIterator {
-    stack: Vec<anyhow::Result<JsonValue>>,
+    stack: Vec<NestedRow>,
     db_item: Option<DbItem>,
+    max_depth: u8,
+}
+
+/// A row that comes from the properties of a dynamic component
+struct NestedRow {
+    /// Number of dynamic components that contain the row
+    depth: u8,
+    /// Where the row is in the properties, such as `properties[2].properties`, for error messages
+    location: String,
+    row: anyhow::Result<JsonValue>,
 }
 
 impl Iterator for DynamicComponentIterator {
@@ -21,13 +34,14 @@ impl Iterator for DynamicComponentIterator {
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(db_item) = self.db_item.take() {
             if let DbItem::Row(mut row) = db_item {
-                match extract_dynamic_properties(&mut row) {
+                match extract_dynamic_properties(&mut row, "the dynamic component") {
                     Ok(None) => {
                         // Most common case: just a regular row. We allocated nothing.
                         return Some(DbItem::Row(row));
                     }
                     Ok(Some(properties)) => {
-                        self.stack = dynamic_properties_to_vec(properties);
+                        self.stack =
+                            nested_rows(properties, 1, "properties".into(), self.max_depth);
                     }
                     Err(err) => {
                         return Some(DbItem::Error(err));
@@ -37,38 +51,47 @@ impl Iterator for DynamicComponentIterator {
                 return Some(db_item);
             }
         }
-        expand_dynamic_stack(&mut self.stack);
-        self.stack.pop().map(|result| match result {
+        self.expand_dynamic_stack();
+        self.stack.pop().map(|nested| match nested.row {
             Ok(row) => DbItem::Row(row),
             Err(err) => DbItem::Error(err),
         })
     }
 }
 
-fn expand_dynamic_stack(stack: &mut Vec<anyhow::Result<JsonValue>>) {
-    while let Some(mut next) = stack.pop() {
-        let next_value = next.as_mut().ok();
-        // .and_then(extract_dynamic_properties);
-        let dyn_props = if let Some(val) = next_value {
-            extract_dynamic_properties(val)
-        } else {
-            Ok(None)
-        };
-        match dyn_props {
-            Ok(None) => {
-                // If the properties are not dynamic, push the row back onto the stack
-                stack.push(next);
-                // return at the first non-dynamic row
-                // we don't support non-dynamic rows after dynamic rows nested in the same array
+impl DynamicComponentIterator {
+    /// Replaces the dynamic components at the top of the stack by their properties,
+    /// until the top of the stack is a regular row or an error
+    fn expand_dynamic_stack(&mut self) {
+        while let Some(mut next) = self.stack.pop() {
+            let Ok(row) = &mut next.row else {
+                self.stack.push(next);
                 return;
-            }
-            Ok(Some(properties)) => {
-                // if the properties contain new (nested) dynamic components, push them onto the stack
-                stack.extend(dynamic_properties_to_vec(properties));
-            }
-            Err(err) => {
-                // if an error occurs, push it onto the stack
-                stack.push(Err(err));
+            };
+            match extract_dynamic_properties(row, &next.location) {
+                Ok(None) => {
+                    // return at the first non-dynamic row
+                    // we don't support non-dynamic rows after dynamic rows nested in the same array
+                    self.stack.push(next);
+                    return;
+                }
+                Ok(Some(properties)) => {
+                    // if the properties contain new (nested) dynamic components, push them onto the stack
+                    let location = format!("{}.properties", next.location);
+                    self.stack.extend(nested_rows(
+                        properties,
+                        next.depth + 1,
+                        location,
+                        self.max_depth,
+                    ));
+                }
+                Err(err) => {
+                    self.stack.push(NestedRow {
+                        row: Err(err),
+                        ..next
+                    });
+                    return;
+                }
             }
         }
     }
@@ -76,13 +99,16 @@ fn expand_dynamic_stack(stack: &mut Vec<anyhow::Result<JsonValue>>) {
 
 /// if row.component == 'dynamic', return Some(row.properties), otherwise return None
 #[inline]
-fn extract_dynamic_properties(data: &mut JsonValue) -> anyhow::Result<Option<JsonValue>> {
+fn extract_dynamic_properties(
+    data: &mut JsonValue,
+    location: &str,
+) -> anyhow::Result<Option<JsonValue>> {
     let component = data.get("component").and_then(|v| v.as_str());
     if component == Some("dynamic") {
         let Some(properties) = data.get_mut("properties").map(JsonValue::take) else {
             anyhow::bail!(
                 "The dynamic component requires a property named \"properties\". \
-                Instead, it received the following: {data}"
+                Instead, {location} received the following: {data}"
             );
         };
         Ok(Some(properties))
@@ -91,50 +117,99 @@ fn extract_dynamic_properties(data: &mut JsonValue) -> anyhow::Result<Option<Jso
     }
 }
 
-/// reverse the order of the vec returned by `dynamic_properties_to_result_vec`,
-/// and wrap each element in a Result
-fn dynamic_properties_to_vec(properties_obj: JsonValue) -> Vec<anyhow::Result<JsonValue>> {
-    dynamic_properties_to_result_vec(properties_obj).map_or_else(
-        |err| vec![Err(err)],
-        |vec| vec.into_iter().rev().map(Ok).collect::<Vec<_>>(),
-    )
+/// The rows contained in the properties of a dynamic component, in reverse order, to be pushed onto the stack.
+/// When the properties are invalid, the only element is the error.
+fn nested_rows(
+    properties: JsonValue,
+    depth: u8,
+    location: String,
+    max_depth: u8,
+) -> Vec<NestedRow> {
+    if depth > max_depth {
+        let row = Err(anyhow::anyhow!(
+            "Too many nested dynamic components in {location}. \
+            A dynamic component can contain other dynamic components, but the depth is limited to {max_depth} levels. \n\
+            If you need more levels, you can increase max_recursion_depth in the configuration file."
+        ));
+        return vec![NestedRow {
+            depth,
+            location,
+            row,
+        }];
+    }
+    let mut rows = Vec::new();
+    match collect_rows(properties, &location, &mut rows) {
+        Ok(()) => rows
+            .into_iter()
+            .rev()
+            .map(|(location, row)| NestedRow {
+                depth,
+                location,
+                row: Ok(row),
+            })
+            .collect(),
+        Err(err) => vec![NestedRow {
+            depth,
+            location,
+            row: Err(err),
+        }],
+    }
 }
 
-/// if properties is a string, parse it as JSON and return a vec with the parsed value
-/// if properties is an array, return it as is
-/// if properties is an object, return it as a single element vec
-/// otherwise, return an error
-fn dynamic_properties_to_result_vec(
-    mut properties_obj: JsonValue,
-) -> anyhow::Result<Vec<JsonValue>> {
-    if let JsonValue::String(s) = properties_obj {
-        properties_obj = serde_json::from_str::<JsonValue>(&s)
-            .with_context(|| format!("Invalid json in dynamic component properties: {s}"))?;
-    }
-    match properties_obj {
-        obj @ JsonValue::Object(_) => Ok(vec![obj]),
+/// if properties is a string, parse it as JSON
+/// if properties is an array, collect the rows of its elements, that can themselves be strings or arrays
+/// if properties is an object, collect it as a single row
+/// otherwise, return an error that says where the invalid value is
+fn collect_rows(
+    properties: JsonValue,
+    location: &str,
+    rows: &mut Vec<(String, JsonValue)>,
+) -> anyhow::Result<()> {
+    let properties = match properties {
+        JsonValue::String(s) => serde_json::from_str::<JsonValue>(&s).with_context(|| {
+            format!("Invalid json in dynamic component properties, in {location}: {s}")
+        })?,
+        other => other,
+    };
+    match properties {
+        JsonValue::Object(row) => {
+            if let Some(component) = row.get("component").filter(|c| !c.is_string()) {
+                anyhow::bail!(
+                    "Invalid dynamic component properties: {location}.component must be the name of a component, \
+                    but it is {component}"
+                );
+            }
+            rows.push((location.to_string(), JsonValue::Object(row)));
+        }
         JsonValue::Array(values) => {
-            let mut vec = Vec::with_capacity(values.len());
-            for value in values {
-                vec.extend_from_slice(&dynamic_properties_to_result_vec(value)?);
+            rows.reserve(values.len());
+            for (index, value) in values.into_iter().enumerate() {
+                collect_rows(value, &format!("{location}[{index}]"), rows)?;
             }
-            Ok(vec)
         }
         other => anyhow::bail!(
-            "Dynamic component expected properties of type array or object, got {other} instead."
+            "Dynamic component expected properties of type array or object, \
+            but {location} is {other} instead."
         ),
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rows(properties: JsonValue) -> anyhow::Result<Vec<JsonValue>> {
+        let mut rows = Vec::new();
+        collect_rows(properties, "properties", &mut rows)?;
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
+    }
+
     #[test]
-    fn test_dynamic_properties_to_result_vec() {
+    fn test_collect_rows() {
         let mut properties = JsonValue::String(r#"{"a": 1}"#.to_string());
         assert_eq!(
-            dynamic_properties_to_result_vec(properties.clone()).unwrap(),
+            rows(properties.clone()).unwrap(),
             vec![JsonValue::Object(
                 serde_json::from_str(r#"{"a": 1}"#).unwrap()
             )]
@@ -142,31 +217,48 @@ mod tests {
 
         properties = JsonValue::Array(vec![JsonValue::String(r#"{"a": 1}"#.to_string())]);
         assert_eq!(
-            dynamic_properties_to_result_vec(properties.clone()).unwrap(),
+            rows(properties.clone()).unwrap(),
             vec![serde_json::json!({"a": 1})]
         );
 
         properties = JsonValue::Object(serde_json::from_str(r#"{"a": 1}"#).unwrap());
         assert_eq!(
-            dynamic_properties_to_result_vec(properties.clone()).unwrap(),
+            rows(properties.clone()).unwrap(),
             vec![JsonValue::Object(
                 serde_json::from_str(r#"{"a": 1}"#).unwrap()
             )]
         );
 
         properties = JsonValue::Null;
-        assert!(dynamic_properties_to_result_vec(properties).is_err());
+        assert!(rows(properties).is_err());
     }
 
     #[test]
-    fn test_dynamic_properties_to_vec() {
+    fn test_invalid_properties_location() {
+        let err = rows(serde_json::json!([{"a": 1}, [{"b": 2}, 42]])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Dynamic component expected properties of type array or object, but properties[1][1] is 42 instead."
+        );
+        let err = rows(serde_json::json!([{"a": 1}, {"component": 3}])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("properties[1].component must be the name of a component"),
+            "{err}"
+        );
+        let err = rows(serde_json::json!(["{\"a\": 1}", "{invalid"])).unwrap_err();
+        assert!(
+            err.to_string().contains("in properties[1]: {invalid"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_nested_rows() {
         let properties = JsonValue::String(r#"{"a": 1}"#.to_string());
+        let nested = nested_rows(properties, 1, "properties".into(), 10);
         assert_eq!(
-            dynamic_properties_to_vec(properties.clone())
-                .first()
-                .unwrap()
-                .as_ref()
-                .unwrap(),
+            nested.first().unwrap().row.as_ref().unwrap(),
             &serde_json::json!({"a": 1})
         );
     }
@@ -180,7 +272,7 @@ mod tests {
                 {"component": "dynamic", "properties": {"nested": 2}},
             ]
         }));
-        let iter = parse_dynamic_rows(row)
+        let iter = parse_dynamic_rows(row, 10)
             .map(|item| match item {
                 DbItem::Row(row) => row,
                 x => panic!("Expected a row, got {x:?}"),
@@ -204,7 +296,7 @@ mod tests {
                 r#"{"b": 2}"#,
             ]
         }));
-        let iter = parse_dynamic_rows(row)
+        let iter = parse_dynamic_rows(row, 10)
             .map(|item| match item {
                 DbItem::Row(row) => row,
                 x => panic!("Expected a row, got {x:?}"),
@@ -215,4 +307,38 @@ mod tests {
             vec![serde_json::json!({"a": 1}), serde_json::json!({"b": 2}),]
         );
     }
+
+    #[test]
+    fn test_depth_limit_per_branch() {
+        let nested = |depth: usize| {
+            let mut value = serde_json::json!({"component": "text"});
+            for _ in 0..depth {
+                value = serde_json::json!({"component": "dynamic", "properties": value});
+            }
+            value
+        };
+        // Siblings do not add up: each branch is limited to the maximum depth
+        let row = DbItem::Row(serde_json::json!({
+            "component": "dynamic",
+            "properties": [nested(2), nested(2), nested(2)]
+        }));
+        let items: Vec<DbItem> = parse_dynamic_rows(row, 3).collect();
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|item| matches!(item, DbItem::Row(_))));
+
+        let row = DbItem::Row(serde_json::json!({
+            "component": "dynamic",
+            "properties": [nested(0), nested(3)]
+        }));
+        let items: Vec<DbItem> = parse_dynamic_rows(row, 3).collect();
+        assert!(matches!(items[0], DbItem::Row(_)));
+        let DbItem::Error(err) = &items[1] else {
+            panic!("Expected an error, got {:?}", items[1]);
+        };
+        assert!(
+            err.to_string()
+                .contains("Too many nested dynamic components in properties[1].properties.properties.properties"),
+            "{err}"
+        );
+    }
 }
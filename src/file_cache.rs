@@ -5,31 +5,38 @@ use actix_web::http::StatusCode;
 use anyhow::Context;
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{
-    AtomicU64,
-    Ordering::{Acquire, Release},
+    AtomicBool, AtomicU64,
+    Ordering::{Acquire, Relaxed, Release},
 };
 use std::sync::Arc;
 use std::time::SystemTime;
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock};
 
 /// The maximum time in milliseconds that a file can be cached before its freshness is checked
-/// (in production mode)
+/// (in production mode, or when the files are watched for changes)
 const MAX_STALE_CACHE_MS: u64 = 150;
 
 #[derive(Default)]
 struct Cached<T> {
     last_checked_at: AtomicU64,
+    /// Value of [`FileCache::use_counter`] when the entry was last read, to find the least recently used entry
+    last_used: AtomicU64,
     content: Arc<T>,
 }
 
 impl<T> Cached<T> {
     fn new(content: T) -> Self {
+        Self::from_arc(Arc::new(content))
+    }
+    fn from_arc(content: Arc<T>) -> Self {
         let s = Self {
             last_checked_at: AtomicU64::new(0),
-            content: Arc::new(content),
+            last_used: AtomicU64::new(0),
+            content,
         };
         s.update_check_time();
         s
@@ -59,12 +66,27 @@ impl<T> Cached<T> {
             .saturating_add(MAX_STALE_CACHE_MS)
             < Self::elapsed()
     }
-    /// Creates a new cached entry with the same content but a new check time set to now
-    fn make_fresh(&self) -> Self {
-        Self {
-            last_checked_at: AtomicU64::from(Self::elapsed()),
-            content: Arc::clone(&self.content),
-        }
+}
+
+/// A file that is known at the beginning of the program.
+/// Files added with their source are only parsed the first time they are used.
+struct StaticFile<T> {
+    source: Cow<'static, str>,
+    parsed: OnceCell<Arc<T>>,
+}
+
+impl<T: AsyncFromStrWithState> StaticFile<T> {
+    async fn get(&self, app_state: &AppState, path: &Path) -> anyhow::Result<Arc<T>> {
+        let parsed = self
+            .parsed
+            .get_or_try_init(|| async {
+                log::trace!("Parsing static file {path:?} on its first use.");
+                let value = T::from_str_with_state(app_state, &self.source, path).await?;
+                anyhow::Ok(Arc::new(value))
+            })
+            .await
+            .with_context(|| format!("Unable to parse the static file {}", path.display()))?;
+        Ok(Arc::clone(parsed))
     }
 }
 
@@ -72,7 +94,14 @@ pub struct FileCache<T: AsyncFromStrWithState> {
     cache: Arc<RwLock<HashMap<PathBuf, Cached<T>>>>,
     /// Files that are loaded at the beginning of the program,
     /// and used as fallback when there is no match for the request in the file system
-    static_files: HashMap<PathBuf, Cached<T>>,
+    static_files: HashMap<PathBuf, StaticFile<T>>,
+    /// When set, the least recently used entries are evicted to keep at most this number of files in the cache
+    max_entries: Option<usize>,
+    use_counter: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Set when a file watcher removes changed files from the cache as soon as they are modified
+    watched: AtomicBool,
 }
 
 impl<T: AsyncFromStrWithState> FileStore for FileCache<T> {
@@ -93,13 +122,70 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
         Self {
             cache: Arc::default(),
             static_files: HashMap::new(),
+            max_entries: None,
+            use_counter: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            watched: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a cache that keeps at most `max_entries` files loaded from the file system,
+    /// evicting the least recently used ones. Static files are not counted.
+    #[must_use]
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            max_entries: Some(max_entries),
+            ..Self::new()
         }
     }
 
     /// Adds a static file to the cache so that it will never be looked up from the disk
     pub fn add_static(&mut self, path: PathBuf, contents: T) {
         log::trace!("Adding static file {path:?} to the cache.");
-        self.static_files.insert(path, Cached::new(contents));
+        let static_file = StaticFile {
+            source: Cow::Borrowed(""),
+            parsed: OnceCell::new_with(Some(Arc::new(contents))),
+        };
+        self.static_files.insert(path, static_file);
+    }
+
+    /// Adds the source of a static file, which is parsed only when it is first used
+    pub fn add_static_source(&mut self, path: PathBuf, source: Cow<'static, str>) {
+        log::trace!("Adding static file {path:?} to the cache, without parsing it.");
+        let static_file = StaticFile {
+            source,
+            parsed: OnceCell::new(),
+        };
+        self.static_files.insert(path, static_file);
+    }
+
+    /// Signals that changes to the files are reported through [`FileCache::invalidate`],
+    /// so that the file system does not need to be checked on every request in development mode.
+    pub fn set_watched(&self) {
+        self.watched.store(true, Release);
+    }
+
+    /// Removes a file from the cache, so that it is read again from the file system the next time it is requested
+    pub async fn invalidate(&self, path: &Path) {
+        if self.cache.write().await.remove(path).is_some() {
+            log::debug!("{path:?} changed on disk, removed it from the cache");
+        }
+    }
+
+    /// Number of requests answered from the cache, and number of files that had to be loaded
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Relaxed),
+            misses: self.misses.load(Relaxed),
+        }
+    }
+
+    fn mark_used(&self, cached: &Cached<T>) {
+        cached
+            .last_used
+            .store(self.use_counter.fetch_add(1, Relaxed), Relaxed);
     }
 
     /// Gets a file from the cache, or loads it from the file system if it's not there
@@ -119,8 +205,12 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
     ) -> anyhow::Result<Arc<T>> {
         log::trace!("Attempting to get from cache {:?}", path);
         if let Some(cached) = self.cache.read().await.get(path) {
-            if app_state.config.environment.is_prod() && !cached.needs_check() {
+            self.mark_used(cached);
+            let checks_skippable =
+                app_state.config().environment.is_prod() || self.watched.load(Acquire);
+            if checks_skippable && !cached.needs_check() {
                 log::trace!("Cache answer without filesystem lookup for {:?}", path);
+                self.hits.fetch_add(1, Relaxed);
                 return Ok(Arc::clone(&cached.content));
             }
             match app_state
@@ -131,6 +221,7 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
                 Ok(false) => {
                     log::trace!("Cache answer with filesystem metadata read for {:?}", path);
                     cached.update_check_time();
+                    self.hits.fetch_add(1, Relaxed);
                     return Ok(Arc::clone(&cached.content));
                 }
                 Ok(true) => log::trace!("{path:?} was changed, updating cache..."),
@@ -139,6 +230,7 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
         }
         // Read lock is released
         log::trace!("Loading and parsing {:?}", path);
+        self.misses.fetch_add(1, Relaxed);
         let file_contents = app_state
             .file_system
             .read_to_string(app_state, path, privileged)
@@ -158,20 +250,28 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
             {
                 if let Some(static_file) = self.static_files.get(path) {
                     log::trace!("File {path:?} not found, loading it from static files instead.");
-                    let cached: Cached<T> = static_file.make_fresh();
-                    Ok(cached)
+                    static_file.get(app_state, path).await.map(Cached::from_arc)
                 } else {
-                    Err(e).with_context(|| format!("Couldn't load {path:?} into cache"))
+                    Err(e).with_context(|| format!("Couldn't load {} into cache", path.display()))
                 }
             }
-            Err(e) => Err(e).with_context(|| format!("Couldn't load {path:?} into cache")),
+            Err(e) => {
+                Err(e).with_context(|| format!("Couldn't load {} into cache", path.display()))
+            }
         };
 
         match parsed {
             Ok(value) => {
                 let new_val = Arc::clone(&value.content);
                 log::trace!("Writing to cache {:?}", path);
-                self.cache.write().await.insert(PathBuf::from(path), value);
+                self.mark_used(&value);
+                let mut cache = self.cache.write().await;
+                if let Some(max_entries) = self.max_entries {
+                    if !cache.contains_key(path) {
+                        evict_least_recently_used(&mut cache, max_entries.saturating_sub(1));
+                    }
+                }
+                cache.insert(PathBuf::from(path), value);
                 log::trace!("Done writing to cache {:?}", path);
                 log::trace!("{:?} loaded in cache", path);
                 Ok(new_val)
@@ -189,6 +289,37 @@ impl<T: AsyncFromStrWithState> FileCache<T> {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Proportion of the requests answered from the cache, if there was any request
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// Removes the least recently used entries until the cache contains at most `max_entries` files
+fn evict_least_recently_used<T>(cache: &mut HashMap<PathBuf, Cached<T>>, max_entries: usize) {
+    while cache.len() > max_entries {
+        let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used.load(Relaxed))
+            .map(|(path, _)| path.clone())
+        else {
+            return;
+        };
+        log::debug!("Evicting {oldest:?} from the cache, which is full");
+        cache.remove(&oldest);
+    }
+}
+
 #[async_trait(? Send)]
 pub trait AsyncFromStrWithState: Sized {
     /// Parses the string into an object.
@@ -198,3 +329,22 @@ pub trait AsyncFromStrWithState: Sized {
         source_path: &Path,
     ) -> anyhow::Result<Self>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let mut cache = HashMap::new();
+        for (name, last_used) in [("a", 3), ("b", 1), ("c", 2)] {
+            let cached = Cached::new(name);
+            cached.last_used.store(last_used, Relaxed);
+            cache.insert(PathBuf::from(name), cached);
+        }
+        evict_least_recently_used(&mut cache, 2);
+        assert!(!cache.contains_key(Path::new("b")));
+        evict_least_recently_used(&mut cache, 1);
+        assert_eq!(cache.keys().collect::<Vec<_>>(), [Path::new("a")]);
+    }
+}
@@ -26,7 +26,7 @@ impl FileSystem {
                         You can host sql files directly in your database by creating the following table: \n\
                         {} \n\
                         The error while trying to use the database file system is: {e:#}",
-                        DbFsQueries::get_create_table_sql(db.connection.any_kind())
+                        DbFsQueries::get_create_table_sql(db.connection().any_kind())
                     );
                     None
                 }
@@ -51,9 +51,9 @@ impl FileSystem {
                     .file_modified_since_in_db(app_state, path, since)
                     .await
             }
-            (Err(e), _) => {
-                Err(e).with_context(|| format!("Unable to read local file metadata for {path:?}"))
-            }
+            (Err(e), _) => Err(e).with_context(|| {
+                format!("Unable to read local file metadata for {}", path.display())
+            }),
         }
     }
 
@@ -64,8 +64,12 @@ impl FileSystem {
         priviledged: bool,
     ) -> anyhow::Result<String> {
         let bytes = self.read_file(app_state, path, priviledged).await?;
-        String::from_utf8(bytes)
-            .with_context(|| format!("The file at {path:?} contains invalid UTF8 characters"))
+        String::from_utf8(bytes).with_context(|| {
+            format!(
+                "The file at {} contains invalid UTF8 characters",
+                path.display()
+            )
+        })
     }
 
     /**
@@ -90,7 +94,27 @@ impl FileSystem {
                 status: actix_web::http::StatusCode::NOT_FOUND,
             }
             .into()),
-            (Err(e), _) => Err(e).with_context(|| format!("Unable to read local file {path:?}")),
+            (Err(e), _) => {
+                Err(e).with_context(|| format!("Unable to read local file {}", path.display()))
+            }
+        }
+    }
+
+    /// Opens a file of the local web root, so that it can be served without reading it entirely.
+    /// Returns `None` when there is no such local file: it may be stored in the database.
+    pub(crate) async fn open_local_file(
+        &self,
+        app_state: &AppState,
+        path: &Path,
+    ) -> anyhow::Result<Option<actix_files::NamedFile>> {
+        let local_path = self.safe_local_path(app_state, path, false)?;
+        match actix_files::NamedFile::open_async(&local_path).await {
+            Ok(file) if file.metadata().is_dir() => Ok(None),
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Unable to open local file {}", path.display()))
+            }
         }
     }
 
@@ -105,7 +129,7 @@ impl FileSystem {
             // but when serving them from the filesystem, we need to serve them from the `SQLPAGE_CONFIGURATION_DIRECTORY/templates` directory
             if let Ok(template_path) = path.strip_prefix(TEMPLATES_DIR) {
                 let normalized = [
-                    &app_state.config.configuration_directory,
+                    &app_state.config().configuration_directory,
                     Path::new("templates"),
                     template_path,
                 ]
@@ -151,6 +175,53 @@ impl FileSystem {
         }
         Ok(local_exists)
     }
+
+    /// Lists the files and directories in a directory of the local web root, sorted by name.
+    /// Hidden files and the `sqlpage` configuration directory are not listed.
+    /// Files stored in the database are not included.
+    pub(crate) async fn list_directory(
+        &self,
+        app_state: &AppState,
+        path: &Path,
+    ) -> anyhow::Result<Vec<DirectoryEntry>> {
+        let local_path = self.safe_local_path(app_state, path, false)?;
+        log::debug!("Listing directory {path:?} from {local_path:?}");
+        let mut read_dir = tokio::fs::read_dir(&local_path)
+            .await
+            .with_context(|| format!("Unable to list the files in {}", path.display()))?;
+        let is_root = path.components().next().is_none();
+        let mut entries = Vec::new();
+        while let Some(entry) = read_dir.next_entry().await? {
+            let Ok(name) = entry.file_name().into_string() else {
+                log::debug!("Skipping file with a non-UTF8 name in {path:?}");
+                continue;
+            };
+            if name.starts_with('.') || (is_root && name.eq_ignore_ascii_case("sqlpage")) {
+                continue;
+            }
+            let metadata = entry.metadata().await.with_context(|| {
+                format!(
+                    "Unable to read the metadata of {name:?} in {}",
+                    path.display()
+                )
+            })?;
+            entries.push(DirectoryEntry {
+                name,
+                is_directory: metadata.is_dir(),
+                size: metadata.len(),
+                modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+}
+
+pub(crate) struct DirectoryEntry {
+    pub name: String,
+    pub is_directory: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
 }
 
 async fn file_modified_since_local(path: &Path, since: DateTime<Utc>) -> tokio::io::Result<bool> {
@@ -178,7 +249,7 @@ impl DbFsQueries {
 
     async fn init(db: &Database) -> anyhow::Result<Self> {
         log::debug!("Initializing database filesystem queries");
-        let db_kind = db.connection.any_kind();
+        let db_kind = db.connection().any_kind();
         Ok(Self {
             was_modified: Self::make_was_modified_query(db, db_kind).await?,
             read_file: Self::make_read_file_query(db, db_kind).await?,
@@ -253,11 +324,14 @@ impl DbFsQueries {
             (since, path)
         );
         query
-            .fetch_optional(&app_state.db.connection)
+            .fetch_optional(&app_state.db.connection())
             .await
             .map(|modified| modified == Some((1,)))
             .with_context(|| {
-                format!("Unable to check when {path:?} was last modified in the database")
+                format!(
+                    "Unable to check when {} was last modified in the database",
+                    path.display()
+                )
             })
     }
 
@@ -266,7 +340,7 @@ impl DbFsQueries {
         self.read_file
             .query_as::<(Vec<u8>,)>()
             .bind(path.display().to_string())
-            .fetch_optional(&app_state.db.connection)
+            .fetch_optional(&app_state.db.connection())
             .await
             .map_err(anyhow::Error::from)
             .and_then(|modified| {
@@ -279,7 +353,7 @@ impl DbFsQueries {
                     .into())
                 }
             })
-            .with_context(|| format!("Unable to read {path:?} from the database"))
+            .with_context(|| format!("Unable to read {} from the database", path.display()))
     }
 
     async fn file_exists(&self, app_state: &AppState, path: &Path) -> anyhow::Result<bool> {
@@ -294,11 +368,14 @@ impl DbFsQueries {
             self.exists.sql(),
             (path,)
         );
-        let result = query.fetch_optional(&app_state.db.connection).await;
+        let result = query.fetch_optional(&app_state.db.connection()).await;
         log::debug!("DB File exists result: {:?}", result);
-        result
-            .map(|result| result.is_some())
-            .with_context(|| format!("Unable to check if {path:?} exists in the database"))
+        result.map(|result| result.is_some()).with_context(|| {
+            format!(
+                "Unable to check if {} exists in the database",
+                path.display()
+            )
+        })
     }
 }
 
@@ -308,14 +385,14 @@ async fn test_sql_file_read_utf8() -> anyhow::Result<()> {
     use sqlx::Executor;
     let config = app_config::tests::test_config();
     let state = AppState::init(&config).await?;
-    let create_table_sql = DbFsQueries::get_create_table_sql(state.db.connection.any_kind());
+    let create_table_sql = DbFsQueries::get_create_table_sql(state.db.connection().any_kind());
     state
         .db
-        .connection
+        .connection()
         .execute(format!("DROP TABLE IF EXISTS sqlpage_files; {create_table_sql}").as_str())
         .await?;
 
-    let db_kind = state.db.connection.any_kind();
+    let db_kind = state.db.connection().any_kind();
     let insert_sql = format!(
         "INSERT INTO sqlpage_files(path, contents) VALUES ({}, {})",
         make_placeholder(db_kind, 1),
@@ -324,7 +401,7 @@ async fn test_sql_file_read_utf8() -> anyhow::Result<()> {
     sqlx::query(&insert_sql)
         .bind("unit test file.txt")
         .bind("Héllö world! 😀".as_bytes())
-        .execute(&state.db.connection)
+        .execute(&state.db.connection())
         .await?;
 
     let fs = FileSystem::init("/", &state.db).await;
@@ -0,0 +1,200 @@
+//! Sanitization of user-submitted HTML, used by the `sanitize_html` template helper.
+//!
+//! The HTML is parsed and serialized again by [`ammonia`], like a browser would parse it,
+//! so malformed HTML cannot smuggle markup through. Only the allowed tags and attributes are kept,
+//! and only safe URLs. Elements that contain code, such as `script` or `style`,
+//! are removed with their contents. Elements left open are closed at the end,
+//! so that the sanitized HTML does not change the structure of the rest of the page.
+
+use ammonia::{Builder, UrlRelative};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Elements kept in the output
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// Elements removed with everything they contain
+const REMOVED_WITH_CONTENTS: &[&str] = &[
+    "script",
+    "style",
+    "iframe",
+    "object",
+    "embed",
+    "template",
+    "noscript",
+    "textarea",
+    "title",
+    "svg",
+    "math",
+    "select",
+    "frameset",
+    "noembed",
+    "noframes",
+    "xmp",
+    "plaintext",
+];
+
+/// Attributes allowed on all the elements
+const GENERIC_ATTRIBUTES: &[&str] = &["title", "lang", "dir"];
+
+/// Attributes allowed on some elements only
+const TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("ol", &["start"]),
+    ("blockquote", &["cite"]),
+    ("q", &["cite"]),
+    ("del", &["cite"]),
+    ("ins", &["cite"]),
+];
+
+/// URL schemes allowed in links and images. URLs without a scheme are relative, and always allowed.
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+fn sanitizer() -> &'static Builder<'static> {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+    SANITIZER.get_or_init(|| {
+        let mut builder = Builder::empty();
+        builder
+            .tags(ALLOWED_TAGS.iter().copied().collect())
+            .clean_content_tags(REMOVED_WITH_CONTENTS.iter().copied().collect())
+            .generic_attributes(GENERIC_ATTRIBUTES.iter().copied().collect())
+            .tag_attributes(
+                TAG_ATTRIBUTES
+                    .iter()
+                    .map(|(tag, attributes)| (*tag, attributes.iter().copied().collect()))
+                    .collect::<HashMap<_, HashSet<_>>>(),
+            )
+            .url_schemes(ALLOWED_SCHEMES.iter().copied().collect())
+            .url_relative(UrlRelative::PassThrough)
+            .link_rel(Some("noopener noreferrer"))
+            .strip_comments(true);
+        builder
+    })
+}
+
+/// Returns HTML that contains only the allowed tags and attributes of `html`, and only safe URLs.
+#[must_use]
+pub fn sanitize_html(html: &str) -> String {
+    sanitizer().clean(html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_markup() {
+        assert_eq!(
+            sanitize_html(r#"<p>Hello <b>world</b>&nbsp;!</p><img src="/cat.png" alt="A cat">"#),
+            r#"<p>Hello <b>world</b>&nbsp;!</p><img src="/cat.png" alt="A cat">"#
+        );
+        assert_eq!(
+            sanitize_html("<A HREF='https://example.com?a=1&amp;b=2'>link</A>"),
+            r#"<a href="https://example.com?a=1&amp;b=2" rel="noopener noreferrer">link</a>"#
+        );
+        assert_eq!(sanitize_html("1 < 2 & 3 > 2"), "1 &lt; 2 &amp; 3 &gt; 2");
+    }
+
+    #[test]
+    fn test_removed_markup() {
+        assert_eq!(
+            sanitize_html("<p onclick=alert(1) style='color:red'>Hi</p><script>alert(1)</script>"),
+            "<p>Hi</p>"
+        );
+        assert_eq!(
+            sanitize_html("<font color=red>red</font><!-- comment --><style>p{}</style>"),
+            "red"
+        );
+        assert_eq!(sanitize_html("<SCRIPT>alert(1)</SCRIPT >after"), "after");
+        assert_eq!(sanitize_html("<script>never closed"), "");
+    }
+
+    #[test]
+    fn test_dangerous_urls() {
+        for url in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "java\tscript:alert(1)",
+            "javascript&#58;alert(1)",
+            "javascript&#x3A;alert(1)",
+            "&#106;avascript:alert(1)",
+            "javascript&colon;alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "vbscript:msgbox(1)",
+        ] {
+            let html = format!("<a href=\"{url}\">x</a>");
+            assert_eq!(
+                sanitize_html(&html),
+                "<a rel=\"noopener noreferrer\">x</a>",
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_html() {
+        assert_eq!(sanitize_html("<b><i>unclosed"), "<b><i>unclosed</i></b>");
+        assert_eq!(sanitize_html("</div>stray<p>"), "stray<p></p>");
+        // Misnested tags are fixed like browsers do
+        assert_eq!(sanitize_html("<b><i>x</b>y</i>"), "<b><i>x</i></b><i>y</i>");
+        assert!(!sanitize_html(r#"<img src="x" onerror="alert(1)"#).contains("onerror"));
+        assert_eq!(
+            sanitize_html("<img src=x onerror=alert(1)//>"),
+            r#"<img src="x">"#
+        );
+        assert!(!sanitize_html("<<script>>alert(1)").contains("<script"));
+    }
+}
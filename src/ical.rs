@@ -0,0 +1,249 @@
+//! Rendering of query results as an iCalendar feed (`.ics` file, [RFC 5545](https://www.rfc-editor.org/rfc/rfc5545)),
+//! for the `ical` component.
+//!
+//! Each row becomes an event. Calendar applications can subscribe to the URL of the page,
+//! and will show the events returned by the query the next time they refresh the calendar.
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::io::Write;
+
+pub const ICAL_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+/// Lines longer than this number of bytes must be folded
+const MAX_LINE_LENGTH: usize = 75;
+
+pub struct IcalBodyRenderer<W: Write> {
+    pub writer: W,
+    /// Time at which the feed was generated, used as the `DTSTAMP` of the events
+    timestamp: String,
+}
+
+impl<W: Write> IcalBodyRenderer<W> {
+    pub fn new(mut writer: W, options: &JsonValue) -> anyhow::Result<Self> {
+        write_line(&mut writer, "BEGIN:VCALENDAR")?;
+        write_line(&mut writer, "VERSION:2.0")?;
+        write_line(
+            &mut writer,
+            &format!(
+                "PRODID:-//SQLPage//SQLPage {}//EN",
+                crate::build_info::VERSION
+            ),
+        )?;
+        write_line(&mut writer, "CALSCALE:GREGORIAN")?;
+        write_line(&mut writer, "METHOD:PUBLISH")?;
+        if let Some(title) = options.get("title").and_then(JsonValue::as_str) {
+            write_line(&mut writer, &format!("X-WR-CALNAME:{}", escape_text(title)))?;
+        }
+        if let Some(description) = options.get("description").and_then(JsonValue::as_str) {
+            write_line(
+                &mut writer,
+                &format!("X-WR-CALDESC:{}", escape_text(description)),
+            )?;
+        }
+        Ok(Self {
+            writer,
+            timestamp: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+        })
+    }
+
+    pub fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let get = |name: &str| match data.get(name) {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::String(s)) => Some(s.clone()),
+            Some(other) => Some(other.to_string()),
+        };
+        let start = get("start").context("Each event of the ical component must have a start")?;
+        let summary = get("summary").unwrap_or_default();
+        let uid = get("uid").unwrap_or_else(|| default_uid(&start, &summary));
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", escape_text(&uid)),
+            format!("DTSTAMP:{}", self.timestamp),
+            format!("DTSTART{}", format_date("start", &start)?),
+        ];
+        if let Some(end) = get("end") {
+            lines.push(format!("DTEND{}", format_date("end", &end)?));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&summary)));
+        for (column, property) in [("description", "DESCRIPTION"), ("location", "LOCATION")] {
+            if let Some(value) = get(column) {
+                lines.push(format!("{property}:{}", escape_text(&value)));
+            }
+        }
+        if let Some(url) = get("url") {
+            lines.push(format!("URL:{}", url.replace(['\r', '\n'], "")));
+        }
+        lines.push("END:VEVENT".to_string());
+        for line in lines {
+            write_line(&mut self.writer, &line)?;
+        }
+        Ok(())
+    }
+
+    /// Errors are written as a non-standard property, which calendar applications ignore
+    pub fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        write_line(
+            &mut self.writer,
+            &format!("X-SQLPAGE-ERROR:{}", escape_text(&error.to_string())),
+        )?;
+        Ok(())
+    }
+
+    pub fn close(mut self) -> W {
+        if let Err(e) = write_line(&mut self.writer, "END:VCALENDAR") {
+            log::error!("Unable to write the end of the calendar: {e}");
+        }
+        self.writer
+    }
+}
+
+/// The value of a `DTSTART` or `DTEND` property, including its parameters:
+/// dates are all-day events, dates with a time zone are converted to UTC,
+/// and dates without a time zone are in the local time of the user.
+fn format_date(column: &str, value: &str) -> anyhow::Result<String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(format!(";VALUE=DATE:{}", date.format("%Y%m%d")));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        let utc = datetime.with_timezone(&Utc);
+        return Ok(format!(":{}", utc.format("%Y%m%dT%H%M%SZ")));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"));
+    match naive {
+        Ok(datetime) => Ok(format!(":{}", datetime.format("%Y%m%dT%H%M%S"))),
+        Err(_) => bail!(
+            "Invalid {column} date for the ical component: {value:?}. Expected a date like 2024-12-31 or 2024-12-31 18:30:00"
+        ),
+    }
+}
+
+/// An identifier for events that do not have a `uid`, which stays the same as long as
+/// the start and summary of the event do not change
+fn default_uid(start: &str, summary: &str) -> String {
+    let hash = Sha256::new()
+        .chain_update(start)
+        .chain_update([0])
+        .chain_update(summary)
+        .finalize();
+    let mut uid = String::with_capacity(40);
+    for byte in &hash[..16] {
+        let _ = write!(uid, "{byte:02x}");
+    }
+    uid.push_str("@sqlpage");
+    uid
+}
+
+/// Escapes the special characters of a text value
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => escaped.push_str("\\n"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folded into lines of at most 75 bytes, and terminated by CRLF
+fn write_line(writer: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            writer.write_all(b"\r\n ")?;
+            // The leading space of a continuation line counts in its length
+            line_length = 1;
+        }
+        let mut buf = [0; 4];
+        writer.write_all(c.encode_utf8(&mut buf).as_bytes())?;
+        line_length += c.len_utf8();
+    }
+    writer.write_all(b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(
+            format_date("start", "2024-12-31").unwrap(),
+            ";VALUE=DATE:20241231"
+        );
+        assert_eq!(
+            format_date("start", "2024-12-31 18:30:00").unwrap(),
+            ":20241231T183000"
+        );
+        assert_eq!(
+            format_date("start", "2024-12-31T18:30:00+02:00").unwrap(),
+            ":20241231T163000Z"
+        );
+        assert!(format_date("end", "tomorrow").is_err());
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a,b;c\\d\r\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_write_line_folds_long_lines() {
+        let mut out = Vec::new();
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        write_line(&mut out, &line).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.split("\r\n").collect();
+        assert_eq!(lines.len(), 3, "{out:?}");
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_LENGTH));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(out.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn test_calendar() {
+        let mut renderer =
+            IcalBodyRenderer::new(Vec::new(), &json!({"title": "Team events"})).unwrap();
+        renderer
+            .handle_row(&json!({
+                "summary": "Sprint review, demo",
+                "start": "2024-12-31 14:00:00",
+                "end": "2024-12-31 15:00:00",
+                "location": "Room 1",
+                "uid": "review-42"
+            }))
+            .unwrap();
+        renderer
+            .handle_row(&json!({"summary": "Holidays", "start": "2025-01-01"}))
+            .unwrap();
+        assert!(renderer
+            .handle_row(&json!({"summary": "No start"}))
+            .is_err());
+        let out = String::from_utf8(renderer.close()).unwrap();
+        assert!(
+            out.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"),
+            "{out}"
+        );
+        assert!(out.contains("X-WR-CALNAME:Team events\r\n"), "{out}");
+        assert!(out.contains("BEGIN:VEVENT\r\nUID:review-42\r\n"), "{out}");
+        assert!(out.contains("DTSTART:20241231T140000\r\nDTEND:20241231T150000\r\nSUMMARY:Sprint review\\, demo\r\nLOCATION:Room 1\r\n"), "{out}");
+        assert!(
+            out.contains("DTSTART;VALUE=DATE:20250101\r\nSUMMARY:Holidays\r\n"),
+            "{out}"
+        );
+        assert!(out.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"), "{out}");
+    }
+}
@@ -0,0 +1,118 @@
+//! Inline SVG icons, extracted from the tabler icons sprite that is embedded in the binary.
+//!
+//! The `icon_img` helper references the icons of the sprite, which browsers download once, at `/sqlpage/tabler-icons.svg`.
+//! The `icon` helper copies the drawing of an icon in the page instead, for the places where the sprite cannot be loaded,
+//! such as emails, exported pages, or sites served with a restrictive content security policy.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::OnceLock;
+
+/// The compressed sprite, as served by [`crate::webserver::static_content::icons`]
+const COMPRESSED_SPRITE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/tabler-icons.svg"));
+
+/// An icon of the sprite
+pub struct Icon {
+    /// Attributes of the `symbol` element, except its id, such as `viewBox` and `stroke-width`
+    attributes: &'static str,
+    /// Shapes that draw the icon
+    contents: &'static str,
+}
+
+impl Icon {
+    /// Writes the icon as an `svg` element of the given size
+    pub fn write_svg(&self, writer: &mut impl std::fmt::Write, size: u64) -> std::fmt::Result {
+        write!(
+            writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\"{} aria-hidden=\"true\">{}</svg>",
+            self.attributes, self.contents
+        )
+    }
+}
+
+/// Finds an icon by its name, such as `home` or `filled-star`.
+/// The sprite is decompressed and indexed the first time an icon is requested.
+pub fn find_icon(name: &str) -> Option<&'static Icon> {
+    static ICONS: OnceLock<HashMap<&'static str, Icon>> = OnceLock::new();
+    ICONS
+        .get_or_init(|| {
+            let mut sprite = String::new();
+            if let Err(e) =
+                flate2::read::GzDecoder::new(COMPRESSED_SPRITE).read_to_string(&mut sprite)
+            {
+                log::error!("Unable to read the embedded icons: {e}");
+            }
+            index_icons(Box::leak(sprite.into_boxed_str()))
+        })
+        .get(name)
+}
+
+/// Indexes the `<symbol id="tabler-name" ...>...</symbol>` elements of the sprite by name
+fn index_icons(sprite: &'static str) -> HashMap<&'static str, Icon> {
+    let mut icons = HashMap::new();
+    let mut rest = sprite;
+    while let Some(start) = rest.find("<symbol") {
+        rest = &rest[start + "<symbol".len()..];
+        let Some(tag_end) = rest.find('>') else { break };
+        let Some(end) = rest.find("</symbol>") else {
+            break;
+        };
+        let (attributes, contents) = (&rest[..tag_end], &rest[tag_end + 1..end]);
+        rest = &rest[end..];
+        let Some(id_start) = attributes.find(" id=\"") else {
+            continue;
+        };
+        let id_and_rest = &attributes[id_start + " id=\"".len()..];
+        let Some(id_end) = id_and_rest.find('"') else {
+            continue;
+        };
+        let id = &id_and_rest[..id_end];
+        let name = id.strip_prefix("tabler-").unwrap_or(id);
+        let attributes = &attributes[..id_start];
+        let other_attributes = &id_and_rest[id_end + 1..];
+        let attributes = if other_attributes.trim().is_empty() {
+            attributes
+        } else if attributes.is_empty() {
+            other_attributes
+        } else {
+            // The id is usually the first attribute, so the attributes are rarely copied
+            Box::leak(format!("{attributes}{other_attributes}").into_boxed_str())
+        };
+        icons.insert(
+            name,
+            Icon {
+                attributes,
+                contents,
+            },
+        );
+    }
+    icons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPRITE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg"><defs>
+<symbol id="tabler-home" viewBox="0 0 24 24" stroke="currentColor"><path d="M5 12l-2 0"/></symbol>
+<symbol viewBox="0 0 24 24" id="tabler-filled-star" fill="currentColor"><path d="M8 7"/></symbol>
+</defs></svg>"#;
+
+    #[test]
+    fn test_index_icons() {
+        let icons = index_icons(SPRITE);
+        assert_eq!(icons.len(), 2);
+        let mut svg = String::new();
+        icons["home"].write_svg(&mut svg, 16).unwrap();
+        assert_eq!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16" viewBox="0 0 24 24" stroke="currentColor" aria-hidden="true"><path d="M5 12l-2 0"/></svg>"#
+        );
+        let star = &icons["filled-star"];
+        assert_eq!(
+            star.attributes,
+            r#" viewBox="0 0 24 24" fill="currentColor""#
+        );
+        assert_eq!(star.contents, r#"<path d="M8 7"/>"#);
+    }
+}
@@ -71,21 +71,37 @@
 extern crate core;
 
 pub mod app_config;
+pub mod build_info;
+pub mod check;
 pub mod dynamic_component;
 pub mod file_cache;
 pub mod filesystem;
+pub mod html_sanitizer;
+pub mod ical;
+pub mod icons;
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
 pub mod render;
+pub mod syntax_highlighting;
 pub mod template_helpers;
 pub mod templates;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 pub mod utils;
 pub mod webserver;
+pub mod xlsx;
+pub mod xml;
 
 use crate::app_config::AppConfig;
 use crate::filesystem::FileSystem;
 use crate::webserver::database::ParsedSqlFile;
+use anyhow::Context;
 use file_cache::FileCache;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
 use templates::AllTemplates;
+use webserver::audit_log::AuditLog;
+use webserver::live::LiveNotifications;
 use webserver::Database;
 
 /// `TEMPLATES_DIR` is the directory where .handlebars files are stored
@@ -96,12 +112,47 @@ pub const MIGRATIONS_DIR: &str = "migrations";
 pub const ON_CONNECT_FILE: &str = "on_connect.sql";
 pub const ON_RESET_FILE: &str = "on_reset.sql";
 
+/// The page displayed when the web root does not contain an `index.sql` file.
+/// It is `index.sql` followed by a description of the database and the web root in use.
+fn welcome_page(kind: sqlx::any::AnyKind, web_root: &Path) -> String {
+    use sqlx::any::AnyKind;
+    let (name, version) = match kind {
+        AnyKind::Postgres => ("PostgreSQL", "version()"),
+        AnyKind::MySql => ("MySQL", "version()"),
+        AnyKind::Sqlite => ("SQLite", "sqlite_version()"),
+        AnyKind::Mssql => ("Microsoft SQL Server", "@@VERSION"),
+    };
+    let web_root = web_root.display().to_string().replace('\'', "''");
+    format!(
+        "{}
+select 'datagrid' as component, 'Your setup' as title;
+select 'Database' as title, '{name}' as description, 'database' as icon;
+select 'Database version' as title, {version} as description, 'versions' as icon;
+select 'Web root' as title, '{web_root}' as description, 'folder' as icon;
+",
+        include_str!("../index.sql")
+    )
+}
+
 pub struct AppState {
     pub db: Database,
     all_templates: AllTemplates,
     sql_file_cache: FileCache<ParsedSqlFile>,
     file_system: FileSystem,
-    config: AppConfig,
+    /// The current configuration, replaced when it is reloaded
+    config: RwLock<Arc<AppConfig>>,
+    /// Database of IP address locations, loaded from `geoip_database_path`
+    geoip: Option<maxminddb::Reader<Vec<u8>>>,
+    /// Parsed `database_role_query`
+    database_role_query: Option<ParsedSqlFile>,
+    /// Writer of the `audit_log_table`
+    audit_log: Option<AuditLog>,
+    /// Database notifications sent to the `live` components, with `PostgreSQL`
+    live_notifications: Option<LiveNotifications>,
+    /// Limits the number of browser processes rendering pages to PDF
+    pdf_renders: tokio::sync::Semaphore,
+    /// Rendered pages waiting to be loaded by the browser that prints them to PDF
+    printed_pages: webserver::pdf::PrintedPages,
 }
 
 impl AppState {
@@ -115,16 +166,78 @@ impl AppState {
         let file_system = FileSystem::init(&config.web_root, &db).await;
         sql_file_cache.add_static(
             PathBuf::from("index.sql"),
-            ParsedSqlFile::new(&db, include_str!("../index.sql"), Path::new("index.sql")),
+            ParsedSqlFile::new(
+                &db,
+                &welcome_page(db.connection().any_kind(), &config.web_root),
+                Path::new("index.sql"),
+            ),
         );
+        let geoip = config
+            .geoip_database_path
+            .as_ref()
+            .map(|path| {
+                maxminddb::Reader::open_readfile(path).with_context(|| {
+                    format!("Unable to load the GeoIP database {}", path.display())
+                })
+            })
+            .transpose()?;
+        let database_role_query = config
+            .database_role_query
+            .as_ref()
+            .map(|sql| ParsedSqlFile::new(&db, sql, Path::new("database_role_query")));
+        let audit_log = config
+            .audit_log_table
+            .as_deref()
+            .map(|table| AuditLog::start(&db, table));
+        let live_notifications = LiveNotifications::new(config)?;
+        webserver::form_protection::init_signing_key(config.token_signing_secret.as_deref());
         Ok(AppState {
             db,
             all_templates,
             sql_file_cache,
             file_system,
-            config: config.clone(),
+            config: RwLock::new(Arc::new(config.clone())),
+            geoip,
+            database_role_query,
+            audit_log,
+            live_notifications,
+            pdf_renders: tokio::sync::Semaphore::new(config.pdf_max_concurrent_renders),
+            printed_pages: webserver::pdf::PrintedPages::default(),
         })
     }
+
+    #[must_use]
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_ref()
+    }
+
+    #[must_use]
+    pub fn printed_pages(&self) -> &webserver::pdf::PrintedPages {
+        &self.printed_pages
+    }
+
+    #[must_use]
+    pub fn live_notifications(&self) -> Option<&LiveNotifications> {
+        self.live_notifications.as_ref()
+    }
+
+    /// The current configuration. Requests that are being handled keep the configuration
+    /// they started with when it is reloaded.
+    #[must_use]
+    pub fn config(&self) -> Arc<AppConfig> {
+        let config = self.config.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&config)
+    }
+
+    /// Replaces the configuration of the running server.
+    /// The settings that are only read when the server starts keep their current value,
+    /// and the names of the ones that are different in `new_config` are returned.
+    pub fn reload_config(&self, mut new_config: AppConfig) -> Vec<&'static str> {
+        let mut config = self.config.write().unwrap_or_else(PoisonError::into_inner);
+        let needs_restart = config.keep_startup_settings(&mut new_config);
+        *config = Arc::new(new_config);
+        needs_restart
+    }
 }
 
 impl std::fmt::Debug for AppState {
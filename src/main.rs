@@ -1,5 +1,7 @@
+use clap::Parser;
 use sqlpage::{
-    app_config,
+    app_config::{AppConfig, Cli, Command},
+    check,
     webserver::{self, Database},
     AppState,
 };
@@ -14,7 +16,11 @@ async fn main() {
 }
 
 async fn start() -> anyhow::Result<()> {
-    let app_config = app_config::load_from_cli()?;
+    let cli = Cli::parse();
+    let app_config = AppConfig::from_cli(&cli)?;
+    if let Some(Command::Check { syntax_only }) = cli.command {
+        return check_site(&app_config, syntax_only).await;
+    }
     let db = Database::init(&app_config).await?;
     webserver::database::migrations::apply(&app_config, &db).await?;
     let state = AppState::init_with_db(&app_config, db).await?;
@@ -24,6 +30,13 @@ async fn start() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn check_site(app_config: &AppConfig, syntax_only: bool) -> anyhow::Result<()> {
+    let report = check::check_site(app_config, syntax_only).await?;
+    println!("{report}");
+    anyhow::ensure!(report.is_ok(), "Some SQL files contain errors");
+    Ok(())
+}
+
 fn init_logging() {
     let load_env = dotenvy::dotenv();
 
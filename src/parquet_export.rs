@@ -0,0 +1,426 @@
+//! Export of query results as an Apache Parquet file, for the `parquet` component.
+//! This module is only compiled when the `parquet` cargo feature is enabled.
+//!
+//! Parquet files are organized in columns, so rows are buffered in groups of [`ROW_GROUP_SIZE`] rows,
+//! and each group is compressed and sent to the client as soon as it is complete.
+//! The type of each column is detected from the values of the first group:
+//! booleans, integers, floating point numbers, dates, timestamps, JSON and text.
+
+use crate::webserver::response_writer::ResponseWriter;
+use anyhow::{bail, Context};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{
+    BoolType, ByteArray, ByteArrayType, DataType, DoubleType, Int32Type, Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::format::MicroSeconds;
+use parquet::schema::types::Type;
+use serde_json::{Map, Value as JsonValue};
+use std::io::Write;
+use std::sync::Arc;
+
+pub const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+/// Number of rows that are buffered before being written to the file
+pub const ROW_GROUP_SIZE: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColumnType {
+    Boolean,
+    Int64,
+    Double,
+    Date,
+    /// A date and time without a time zone
+    Timestamp,
+    /// A date and time with a time zone, stored in UTC
+    TimestampUtc,
+    Json,
+    String,
+}
+
+struct Column {
+    name: String,
+    column_type: ColumnType,
+}
+
+pub struct ParquetBodyRenderer {
+    writer: ResponseWriter,
+    file: Option<SerializedFileWriter<Vec<u8>>>,
+    columns: Vec<Column>,
+    rows: Vec<Map<String, JsonValue>>,
+    /// When an error occurs, the end of the file is not written, so that the file cannot be read
+    failed: bool,
+}
+
+impl ParquetBodyRenderer {
+    #[must_use]
+    pub fn new(writer: ResponseWriter) -> Self {
+        Self {
+            writer,
+            file: None,
+            columns: Vec::new(),
+            rows: Vec::with_capacity(ROW_GROUP_SIZE),
+            failed: false,
+        }
+    }
+
+    pub fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        if let Some(row) = data.as_object() {
+            self.rows.push(row.clone());
+        }
+        if self.rows.len() >= ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+        Ok(())
+    }
+
+    pub fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        log::error!("The parquet file will be incomplete: {error:#}");
+        self.failed = true;
+        self.rows.clear();
+        Ok(())
+    }
+
+    /// Sends the row groups that are ready to the client
+    pub async fn flush(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = &mut self.file {
+            let pending = std::mem::take(file.inner_mut());
+            self.writer.write_all(&pending)?;
+        }
+        self.writer.async_flush().await?;
+        Ok(())
+    }
+
+    pub async fn close(mut self) -> ResponseWriter {
+        if !self.failed {
+            if let Err(e) = self.finish() {
+                log::error!("Unable to finish writing the parquet file: {e:#}");
+            }
+        }
+        if let Err(e) = self.writer.async_flush().await {
+            log::error!("Unable to send the end of the parquet file: {e:#}");
+        }
+        self.writer
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        if !self.rows.is_empty() || self.file.is_none() {
+            self.write_row_group()?;
+        }
+        if let Some(file) = self.file.take() {
+            let end = file.into_inner()?;
+            self.writer.write_all(&end)?;
+        }
+        Ok(())
+    }
+
+    fn write_row_group(&mut self) -> anyhow::Result<()> {
+        if self.failed {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.rows);
+        let file = if let Some(file) = &mut self.file {
+            file
+        } else {
+            self.columns = detect_columns(&rows);
+            let file = SerializedFileWriter::new(
+                Vec::new(),
+                Arc::new(schema(&self.columns)?),
+                Arc::new(
+                    WriterProperties::builder()
+                        .set_compression(Compression::SNAPPY)
+                        .set_created_by(format!("SQLPage {}", crate::build_info::VERSION))
+                        .build(),
+                ),
+            )?;
+            self.file.insert(file)
+        };
+        let mut row_group = file.next_row_group()?;
+        for column in &self.columns {
+            let mut column_writer = row_group
+                .next_column()?
+                .context("The parquet file has less columns than the query")?;
+            let values = rows.iter().map(|row| row.get(&column.name));
+            write_column(&mut column_writer, column, values)
+                .with_context(|| format!("Unable to write the {:?} column", column.name))?;
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        self.rows = rows;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+/// The columns of the first row, with the type that fits all the values of the first rows
+fn detect_columns(rows: &[Map<String, JsonValue>]) -> Vec<Column> {
+    let Some(first) = rows.first() else {
+        return Vec::new();
+    };
+    first
+        .keys()
+        .map(|name| {
+            let column_type = rows
+                .iter()
+                .filter_map(|row| row.get(name))
+                .filter_map(value_type)
+                .reduce(merge_types)
+                .unwrap_or(ColumnType::String);
+            Column {
+                name: name.clone(),
+                column_type,
+            }
+        })
+        .collect()
+}
+
+fn value_type(value: &JsonValue) -> Option<ColumnType> {
+    Some(match value {
+        JsonValue::Null => return None,
+        JsonValue::Bool(_) => ColumnType::Boolean,
+        JsonValue::Number(n) if n.is_i64() => ColumnType::Int64,
+        JsonValue::Number(_) => ColumnType::Double,
+        JsonValue::String(s) if parse_date(s).is_some() => ColumnType::Date,
+        JsonValue::String(s) if parse_timestamp(s).is_some() => ColumnType::Timestamp,
+        JsonValue::String(s) if DateTime::parse_from_rfc3339(s).is_ok() => ColumnType::TimestampUtc,
+        JsonValue::String(_) => ColumnType::String,
+        JsonValue::Array(_) | JsonValue::Object(_) => ColumnType::Json,
+    })
+}
+
+fn merge_types(a: ColumnType, b: ColumnType) -> ColumnType {
+    use ColumnType::{Double, Int64};
+    match (a, b) {
+        (a, b) if a == b => a,
+        (Int64, Double) | (Double, Int64) => Double,
+        _ => ColumnType::String,
+    }
+}
+
+fn schema(columns: &[Column]) -> anyhow::Result<Type> {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let (physical_type, logical_type) = match column.column_type {
+                ColumnType::Boolean => (PhysicalType::BOOLEAN, None),
+                ColumnType::Int64 => (PhysicalType::INT64, None),
+                ColumnType::Double => (PhysicalType::DOUBLE, None),
+                ColumnType::Date => (PhysicalType::INT32, Some(LogicalType::Date)),
+                ColumnType::Timestamp | ColumnType::TimestampUtc => (
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_u_t_c: column.column_type == ColumnType::TimestampUtc,
+                        unit: TimeUnit::MICROS(MicroSeconds {}),
+                    }),
+                ),
+                ColumnType::Json => (PhysicalType::BYTE_ARRAY, Some(LogicalType::Json)),
+                ColumnType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            };
+            Type::primitive_type_builder(&column.name, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical_type)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()?)
+}
+
+fn write_column<'a>(
+    writer: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    column: &Column,
+    values: impl Iterator<Item = Option<&'a JsonValue>>,
+) -> anyhow::Result<()> {
+    let mut definition_levels = Vec::new();
+    let present = values.filter_map(|value| match value {
+        None | Some(JsonValue::Null) => {
+            definition_levels.push(0);
+            None
+        }
+        Some(value) => {
+            definition_levels.push(1);
+            Some(value)
+        }
+    });
+    let column_type = column.column_type;
+    match column_type {
+        ColumnType::Boolean => {
+            let values = convert(present, column_type, JsonValue::as_bool)?;
+            write_typed::<BoolType>(writer, &values, &definition_levels)
+        }
+        ColumnType::Int64 => {
+            let values = convert(present, column_type, JsonValue::as_i64)?;
+            write_typed::<Int64Type>(writer, &values, &definition_levels)
+        }
+        ColumnType::Double => {
+            let values = convert(present, column_type, JsonValue::as_f64)?;
+            write_typed::<DoubleType>(writer, &values, &definition_levels)
+        }
+        ColumnType::Date => {
+            let values = convert(present, column_type, |v| {
+                let days = parse_date(v.as_str()?)?
+                    .signed_duration_since(NaiveDate::default())
+                    .num_days();
+                i32::try_from(days).ok()
+            })?;
+            write_typed::<Int32Type>(writer, &values, &definition_levels)
+        }
+        ColumnType::Timestamp => {
+            let values = convert(present, column_type, |v| {
+                Some(parse_timestamp(v.as_str()?)?.and_utc().timestamp_micros())
+            })?;
+            write_typed::<Int64Type>(writer, &values, &definition_levels)
+        }
+        ColumnType::TimestampUtc => {
+            let values = convert(present, column_type, |v| {
+                Some(
+                    DateTime::parse_from_rfc3339(v.as_str()?)
+                        .ok()?
+                        .timestamp_micros(),
+                )
+            })?;
+            write_typed::<Int64Type>(writer, &values, &definition_levels)
+        }
+        ColumnType::Json | ColumnType::String => {
+            let values: Vec<ByteArray> = present
+                .map(|value| match value {
+                    JsonValue::String(s) => ByteArray::from(s.as_str()),
+                    other => ByteArray::from(other.to_string().into_bytes()),
+                })
+                .collect();
+            write_typed::<ByteArrayType>(writer, &values, &definition_levels)
+        }
+    }
+}
+
+fn convert<'a, T>(
+    values: impl Iterator<Item = &'a JsonValue>,
+    column_type: ColumnType,
+    f: impl Fn(&JsonValue) -> Option<T>,
+) -> anyhow::Result<Vec<T>> {
+    values
+        .map(|value| match f(value) {
+            Some(converted) => Ok(converted),
+            None => bail!("The type of the column was detected as {column_type:?} from the first rows, but it contains {value}"),
+        })
+        .collect()
+}
+
+fn write_typed<T: DataType>(
+    writer: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    values: &[T::T],
+    definition_levels: &[i16],
+) -> anyhow::Result<()> {
+    writer
+        .typed::<T>()
+        .write_batch(values, Some(definition_levels), None)?;
+    Ok(())
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+fn parse_timestamp(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows(values: JsonValue) -> Vec<Map<String, JsonValue>> {
+        serde_json::from_value(values).unwrap()
+    }
+
+    #[test]
+    fn test_detect_columns() {
+        let columns = detect_columns(&rows(json!([
+            {"id": 1, "price": 1, "paid": true, "day": "2024-01-31", "at": "2024-01-31 12:00:00", "tags": ["a"], "name": "x", "empty": null, "mixed": "2024-01-31"},
+            {"id": 2, "price": 1.5, "paid": null, "day": "2024-02-01", "at": "2024-01-31T12:00:00.5", "tags": null, "name": "y", "empty": null, "mixed": "hello"},
+        ])));
+        let types: Vec<ColumnType> = columns.iter().map(|c| c.column_type).collect();
+        assert_eq!(
+            types,
+            [
+                ColumnType::Int64,
+                ColumnType::Double,
+                ColumnType::Boolean,
+                ColumnType::Date,
+                ColumnType::Timestamp,
+                ColumnType::Json,
+                ColumnType::String,
+                ColumnType::String,
+                ColumnType::String,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_file() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        let rows = rows(json!([
+            {"id": 1, "name": "Alice", "born": "1990-05-01"},
+            {"id": 2, "name": null, "born": "2001-12-31"},
+        ]));
+        let columns = detect_columns(&rows);
+        let mut file = SerializedFileWriter::new(
+            Vec::new(),
+            Arc::new(schema(&columns).unwrap()),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = file.next_row_group().unwrap();
+        for column in &columns {
+            let mut writer = row_group.next_column().unwrap().unwrap();
+            write_column(
+                &mut writer,
+                column,
+                rows.iter().map(|r| r.get(&column.name)),
+            )
+            .unwrap();
+            writer.close().unwrap();
+        }
+        row_group.close().unwrap();
+        let bytes = file.into_inner().unwrap();
+
+        let reader = SerializedFileReader::new(actix_web::web::Bytes::from(bytes)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        let read: Vec<String> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert_eq!(
+            read,
+            [
+                r#"{id: 1, name: "Alice", born: 1990-05-01}"#,
+                r"{id: 2, name: null, born: 2001-12-31}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_column() {
+        let columns = vec![Column {
+            name: "n".into(),
+            column_type: ColumnType::Int64,
+        }];
+        let mut file = SerializedFileWriter::new(
+            Vec::new(),
+            Arc::new(schema(&columns).unwrap()),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = file.next_row_group().unwrap();
+        let mut writer = row_group.next_column().unwrap().unwrap();
+        let value = json!("not a number");
+        assert!(write_column(&mut writer, &columns[0], [Some(&value)].into_iter()).is_err());
+    }
+}
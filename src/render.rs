@@ -2,7 +2,7 @@
 //!
 //! This module is responsible for transforming database query results into formatted HTTP responses
 //! by utilizing a component-based rendering system. It supports multiple output formats including HTML,
-//! JSON, and CSV.
+//! JSON, CSV, XML, Excel spreadsheets and iCalendar feeds.
 //!
 //! # Components
 //!
@@ -37,17 +37,32 @@
 //! * HTML: Renders templated HTML output using components
 //! * JSON: Generates JSON responses for API endpoints
 //! * CSV: Creates downloadable CSV files
+//! * XML: Generates XML documents for systems that only consume XML
+//! * XLSX: Creates downloadable Excel spreadsheets
+//! * iCalendar: Creates calendar feeds that calendar applications can subscribe to
 //!
 //! For more details on available components and their usage, see the
 //! [SQLPage documentation](https://sql-page.com/documentation.sql).
 
+use crate::ical::{IcalBodyRenderer, ICAL_CONTENT_TYPE};
+#[cfg(feature = "parquet")]
+use crate::parquet_export::{ParquetBodyRenderer, PARQUET_CONTENT_TYPE};
+use crate::template_helpers::CsrfTokenHelper;
 use crate::templates::SplitTemplate;
+use crate::webserver::audit_log::VerifiedPassword;
+use crate::webserver::csrf::BrowserSecret;
 use crate::webserver::http::RequestContext;
+use crate::webserver::http_request_info::FRAGMENT_HEADER;
 use crate::webserver::response_writer::{AsyncResponseWriter, ResponseWriter};
 use crate::webserver::ErrorWithStatus;
+use crate::xlsx::{XlsxBodyRenderer, XLSX_CONTENT_TYPE};
+use crate::xml::{XmlBodyRenderer, XML_CONTENT_TYPE};
 use crate::AppState;
 use actix_web::cookie::time::format_description::well_known::Rfc3339;
 use actix_web::cookie::time::OffsetDateTime;
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+};
 use actix_web::http::{header, StatusCode};
 use actix_web::{HttpResponse, HttpResponseBuilder, ResponseError};
 use anyhow::{bail, format_err, Context as AnyhowContext};
@@ -56,10 +71,13 @@ use handlebars::{BlockContext, Context, JsonValue, RenderError, Renderable};
 use serde::Serialize;
 use serde_json::{json, Value};
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::io::Write;
 use std::sync::Arc;
+use std::time::Instant;
 
+#[allow(clippy::large_enum_variant)]
 pub enum PageContext {
     /// Indicates that we should stay in the header context
     Header(HeaderContext),
@@ -90,10 +108,21 @@ impl HeaderContext {
         request_context: RequestContext,
         writer: ResponseWriter,
     ) -> Self {
-        let mut response = HttpResponseBuilder::new(StatusCode::OK);
+        let mut response = HttpResponseBuilder::new(request_context.status);
         response.content_type("text/html; charset=utf-8");
-        if app_state.config.content_security_policy.is_none() {
-            response.insert_header(&request_context.content_security_policy);
+        // The same URL returns a full page or a fragment depending on this header
+        response.insert_header((header::VARY, FRAGMENT_HEADER));
+        if let Some(csp) = request_context
+            .content_security_policy
+            .page_header_value(app_state.config().content_security_policy.as_deref())
+        {
+            response.insert_header((header::CONTENT_SECURITY_POLICY, csp));
+        }
+        if let Some(cookie) = request_context
+            .csrf_secret
+            .new_cookie(request_context.is_https)
+        {
+            response.cookie(cookie);
         }
         Self {
             app_state,
@@ -115,6 +144,10 @@ impl HeaderContext {
             Some(HeaderComponent::Redirect) => self.redirect(&data).map(PageContext::Close),
             Some(HeaderComponent::Json) => self.json(&data),
             Some(HeaderComponent::Csv) => self.csv(&data).await,
+            Some(HeaderComponent::Xlsx) => self.xlsx(&data),
+            Some(HeaderComponent::Ical) => self.ical(&data),
+            Some(HeaderComponent::Xml) => self.xml(&data),
+            Some(HeaderComponent::Parquet) => self.parquet(&data),
             Some(HeaderComponent::Cookie) => self.add_cookie(&data).map(PageContext::Header),
             Some(HeaderComponent::Authentication) => self.authentication(data).await,
             None => self.start_body(data).await,
@@ -122,7 +155,7 @@ impl HeaderContext {
     }
 
     pub async fn handle_error(self, err: anyhow::Error) -> anyhow::Result<PageContext> {
-        if self.app_state.config.environment.is_prod() {
+        if self.app_state.config().environment.is_prod() {
             return Err(err);
         }
         log::debug!("Handling header error: {err}");
@@ -257,6 +290,11 @@ impl HeaderContext {
             let json_renderer = match body_type {
                 None | Some("array") => JsonBodyRenderer::new_array(self.writer),
                 Some("jsonlines") => JsonBodyRenderer::new_jsonlines(self.writer),
+                Some("jsonl") => {
+                    self.response
+                        .insert_header((header::CONTENT_TYPE, "application/x-ndjson"));
+                    JsonBodyRenderer::new_ndjson(self.writer)
+                }
                 Some("sse") => {
                     self.response
                         .insert_header((header::CONTENT_TYPE, "text/event-stream"));
@@ -275,18 +313,24 @@ impl HeaderContext {
         }
     }
 
+    /// Makes the browser download the response as a file with the given name,
+    /// to which the extension is added when the name does not have one.
+    fn set_attachment_filename(&mut self, filename: Option<&str>, extension: &str) {
+        if let Some(filename) = filename {
+            self.response.insert_header(content_disposition(
+                DispositionType::Attachment,
+                filename,
+                extension,
+            ));
+        }
+    }
+
     async fn csv(mut self, options: &JsonValue) -> anyhow::Result<PageContext> {
         self.response
             .insert_header((header::CONTENT_TYPE, "text/csv; charset=utf-8"));
-        if let Some(filename) =
-            get_object_str(options, "filename").or_else(|| get_object_str(options, "title"))
-        {
-            let extension = if filename.contains('.') { "" } else { ".csv" };
-            self.response.insert_header((
-                header::CONTENT_DISPOSITION,
-                format!("attachment; filename={filename}{extension}"),
-            ));
-        }
+        let filename =
+            get_object_str(options, "filename").or_else(|| get_object_str(options, "title"));
+        self.set_attachment_filename(filename, "csv");
         let csv_renderer = CsvBodyRenderer::new(self.writer, options).await?;
         let renderer = AnyRenderBodyContext::Csv(csv_renderer);
         let http_response = self.response.take();
@@ -296,13 +340,82 @@ impl HeaderContext {
         })
     }
 
+    fn xlsx(mut self, options: &JsonValue) -> anyhow::Result<PageContext> {
+        self.response
+            .insert_header((header::CONTENT_TYPE, XLSX_CONTENT_TYPE));
+        let filename =
+            get_object_str(options, "filename").or_else(|| get_object_str(options, "title"));
+        self.set_attachment_filename(filename, "xlsx");
+        let sheet_name = get_object_str(options, "sheet_name");
+        let xlsx_renderer = XlsxBodyRenderer::new(self.writer, sheet_name)?;
+        let renderer = AnyRenderBodyContext::Xlsx(xlsx_renderer);
+        let http_response = self.response.take();
+        Ok(PageContext::Body {
+            renderer,
+            http_response,
+        })
+    }
+
+    fn ical(mut self, options: &JsonValue) -> anyhow::Result<PageContext> {
+        self.response
+            .insert_header((header::CONTENT_TYPE, ICAL_CONTENT_TYPE));
+        self.set_attachment_filename(get_object_str(options, "filename"), "ics");
+        let ical_renderer = IcalBodyRenderer::new(self.writer, options)?;
+        let renderer = AnyRenderBodyContext::Ical(ical_renderer);
+        let http_response = self.response.take();
+        Ok(PageContext::Body {
+            renderer,
+            http_response,
+        })
+    }
+
+    fn xml(mut self, options: &JsonValue) -> anyhow::Result<PageContext> {
+        self.response
+            .insert_header((header::CONTENT_TYPE, XML_CONTENT_TYPE));
+        self.set_attachment_filename(get_object_str(options, "filename"), "xml");
+        let xml_renderer = XmlBodyRenderer::new(self.writer, options)?;
+        let renderer = AnyRenderBodyContext::Xml(xml_renderer);
+        let http_response = self.response.take();
+        Ok(PageContext::Body {
+            renderer,
+            http_response,
+        })
+    }
+
+    #[cfg(feature = "parquet")]
+    #[allow(clippy::unnecessary_wraps)] // fails when the parquet feature is disabled
+    fn parquet(mut self, options: &JsonValue) -> anyhow::Result<PageContext> {
+        self.response
+            .insert_header((header::CONTENT_TYPE, PARQUET_CONTENT_TYPE));
+        self.set_attachment_filename(get_object_str(options, "filename"), "parquet");
+        let renderer = AnyRenderBodyContext::Parquet(ParquetBodyRenderer::new(self.writer));
+        let http_response = self.response.take();
+        Ok(PageContext::Body {
+            renderer,
+            http_response,
+        })
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[allow(clippy::unused_self)]
+    fn parquet(self, _options: &JsonValue) -> anyhow::Result<PageContext> {
+        anyhow::bail!(
+            "The parquet component is not available: this version of SQLPage was built without the \"parquet\" cargo feature"
+        )
+    }
+
     async fn authentication(mut self, mut data: JsonValue) -> anyhow::Result<PageContext> {
         let password_hash = take_object_str(&mut data, "password_hash");
         let password = take_object_str(&mut data, "password");
         if let (Some(password), Some(password_hash)) = (password, password_hash) {
             log::debug!("Authentication with password_hash = {:?}", password_hash);
-            match verify_password_async(password_hash, password).await? {
-                Ok(()) => return Ok(PageContext::Header(self)),
+            match verify_password_async(password_hash, password.clone()).await? {
+                Ok(()) => {
+                    self.response
+                        .extensions_mut()
+                        .insert(VerifiedPassword(password));
+                    return Ok(PageContext::Header(self));
+                }
                 Err(e) => log::info!("Password didn't match: {}", e),
             }
         }
@@ -344,7 +457,7 @@ impl HeaderContext {
     }
 }
 
-async fn verify_password_async(
+pub(crate) async fn verify_password_async(
     password_hash: String,
     password: String,
 ) -> Result<Result<(), password_hash::Error>, anyhow::Error> {
@@ -367,6 +480,41 @@ fn get_backtrace(error: &anyhow::Error) -> Vec<String> {
     backtrace
 }
 
+/// A `Content-Disposition` header with both an ASCII `filename`, for old clients,
+/// and the full UTF-8 file name in an RFC 5987 `filename*` parameter.
+pub(crate) fn content_disposition(
+    disposition: DispositionType,
+    filename: &str,
+    extension: &str,
+) -> ContentDisposition {
+    let filename = if filename.contains('.') {
+        filename.to_string()
+    } else {
+        format!("{filename}.{extension}")
+    };
+    let ascii_filename = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    ContentDisposition {
+        disposition,
+        parameters: vec![
+            DispositionParam::Filename(ascii_filename),
+            DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: filename.into_bytes(),
+            }),
+        ],
+    }
+}
+
 fn get_object_str<'a>(json: &'a JsonValue, key: &str) -> Option<&'a str> {
     json.as_object()
         .and_then(|obj| obj.get(key))
@@ -383,10 +531,16 @@ fn take_object_str(json: &mut JsonValue, key: &str) -> Option<String> {
 /**
  * Can receive rows, and write them in a given format to an `io::Write`
  */
+#[allow(clippy::large_enum_variant)]
 pub enum AnyRenderBodyContext {
     Html(HtmlRenderContext<ResponseWriter>),
     Json(JsonBodyRenderer<ResponseWriter>),
     Csv(CsvBodyRenderer),
+    Xlsx(XlsxBodyRenderer),
+    Ical(IcalBodyRenderer<ResponseWriter>),
+    Xml(XmlBodyRenderer<ResponseWriter>),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetBodyRenderer),
 }
 
 /**
@@ -402,6 +556,11 @@ impl AnyRenderBodyContext {
             AnyRenderBodyContext::Html(render_context) => render_context.handle_row(data).await,
             AnyRenderBodyContext::Json(json_body_renderer) => json_body_renderer.handle_row(data),
             AnyRenderBodyContext::Csv(csv_renderer) => csv_renderer.handle_row(data).await,
+            AnyRenderBodyContext::Xlsx(xlsx_renderer) => xlsx_renderer.handle_row(data),
+            AnyRenderBodyContext::Ical(ical_renderer) => ical_renderer.handle_row(data),
+            AnyRenderBodyContext::Xml(xml_renderer) => xml_renderer.handle_row(data),
+            #[cfg(feature = "parquet")]
+            AnyRenderBodyContext::Parquet(parquet_renderer) => parquet_renderer.handle_row(data),
         }
     }
     pub async fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
@@ -412,6 +571,11 @@ impl AnyRenderBodyContext {
                 json_body_renderer.handle_error(error)
             }
             AnyRenderBodyContext::Csv(csv_renderer) => csv_renderer.handle_error(error).await,
+            AnyRenderBodyContext::Xlsx(xlsx_renderer) => xlsx_renderer.handle_error(error),
+            AnyRenderBodyContext::Ical(ical_renderer) => ical_renderer.handle_error(error),
+            AnyRenderBodyContext::Xml(xml_renderer) => xml_renderer.handle_error(error),
+            #[cfg(feature = "parquet")]
+            AnyRenderBodyContext::Parquet(parquet_renderer) => parquet_renderer.handle_error(error),
         }
     }
     pub async fn finish_query(&mut self) -> anyhow::Result<()> {
@@ -419,16 +583,26 @@ impl AnyRenderBodyContext {
             AnyRenderBodyContext::Html(render_context) => render_context.finish_query().await,
             AnyRenderBodyContext::Json(_json_body_renderer) => Ok(()),
             AnyRenderBodyContext::Csv(_csv_renderer) => Ok(()),
+            AnyRenderBodyContext::Xlsx(_xlsx_renderer) => Ok(()),
+            AnyRenderBodyContext::Ical(_ical_renderer) => Ok(()),
+            AnyRenderBodyContext::Xml(_xml_renderer) => Ok(()),
+            #[cfg(feature = "parquet")]
+            AnyRenderBodyContext::Parquet(_parquet_renderer) => Ok(()),
         }
     }
 
     pub async fn flush(&mut self) -> anyhow::Result<()> {
         match self {
             AnyRenderBodyContext::Html(HtmlRenderContext { writer, .. })
-            | AnyRenderBodyContext::Json(JsonBodyRenderer { writer, .. }) => {
+            | AnyRenderBodyContext::Json(JsonBodyRenderer { writer, .. })
+            | AnyRenderBodyContext::Ical(IcalBodyRenderer { writer, .. })
+            | AnyRenderBodyContext::Xml(XmlBodyRenderer { writer, .. }) => {
                 writer.async_flush().await?;
             }
             AnyRenderBodyContext::Csv(csv_renderer) => csv_renderer.flush().await?,
+            AnyRenderBodyContext::Xlsx(xlsx_renderer) => xlsx_renderer.flush().await?,
+            #[cfg(feature = "parquet")]
+            AnyRenderBodyContext::Parquet(parquet_renderer) => parquet_renderer.flush().await?,
         }
         Ok(())
     }
@@ -438,6 +612,11 @@ impl AnyRenderBodyContext {
             AnyRenderBodyContext::Html(render_context) => render_context.close().await,
             AnyRenderBodyContext::Json(json_body_renderer) => json_body_renderer.close(),
             AnyRenderBodyContext::Csv(csv_renderer) => csv_renderer.close().await,
+            AnyRenderBodyContext::Xlsx(xlsx_renderer) => xlsx_renderer.close().await,
+            AnyRenderBodyContext::Ical(ical_renderer) => ical_renderer.close(),
+            AnyRenderBodyContext::Xml(xml_renderer) => xml_renderer.close(),
+            #[cfg(feature = "parquet")]
+            AnyRenderBodyContext::Parquet(parquet_renderer) => parquet_renderer.close().await,
         }
     }
 }
@@ -448,6 +627,8 @@ pub struct JsonBodyRenderer<W: std::io::Write> {
     prefix: &'static [u8],
     suffix: &'static [u8],
     separator: &'static [u8],
+    /// Written after each row
+    terminator: &'static [u8],
 }
 
 impl<W: std::io::Write> JsonBodyRenderer<W> {
@@ -458,6 +639,7 @@ impl<W: std::io::Write> JsonBodyRenderer<W> {
             prefix: b"[\n",
             suffix: b"\n]",
             separator: b",\n",
+            terminator: b"",
         };
         let _ = renderer.write_prefix();
         renderer
@@ -469,6 +651,7 @@ impl<W: std::io::Write> JsonBodyRenderer<W> {
             prefix: b"",
             suffix: b"",
             separator: b"\n",
+            terminator: b"",
         };
         renderer.write_prefix().unwrap();
         renderer
@@ -480,10 +663,22 @@ impl<W: std::io::Write> JsonBodyRenderer<W> {
             prefix: b"data: ",
             suffix: b"\n\n",
             separator: b"\n\ndata: ",
+            terminator: b"",
         };
         renderer.write_prefix().unwrap();
         renderer
     }
+    /// Newline-delimited JSON, where every row is a JSON object followed by a newline
+    pub fn new_ndjson(writer: W) -> JsonBodyRenderer<W> {
+        Self {
+            writer,
+            is_first: true,
+            prefix: b"",
+            suffix: b"",
+            separator: b"",
+            terminator: b"\n",
+        }
+    }
     fn write_prefix(&mut self) -> anyhow::Result<()> {
         self.writer.write_all(self.prefix)?;
         Ok(())
@@ -495,6 +690,7 @@ impl<W: std::io::Write> JsonBodyRenderer<W> {
             let _ = self.writer.write_all(self.separator);
         }
         serde_json::to_writer(&mut self.writer, data)?;
+        self.writer.write_all(self.terminator)?;
         Ok(())
     }
     pub fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
@@ -619,8 +815,23 @@ pub struct HtmlRenderContext<W: std::io::Write> {
     shell_renderer: SplitTemplateRenderer,
     current_statement: usize,
     request_context: RequestContext,
+    /// State of the grid in which components with a `grid_width` are placed side by side
+    grid: GridState,
 }
 
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum GridState {
+    /// Components are stacked vertically
+    #[default]
+    None,
+    /// A row was opened for the previous components, but its last column was closed
+    InRow,
+    /// The current component is rendered in a column of a row
+    InColumn,
+}
+
+/// Top-level property of all components, that places consecutive components side by side
+pub const GRID_WIDTH_PROPERTY: &str = "grid_width";
 const DEFAULT_COMPONENT: &str = "table";
 const PAGE_SHELL_COMPONENT: &str = "shell";
 const FRAGMENT_SHELL_COMPONENT: &str = "shell-empty";
@@ -664,15 +875,16 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
                 "Embedded pages cannot use a shell component! Ignoring the '{shell_component}' component and its properties: {shell_row}"
             );
             shell_component = FRAGMENT_SHELL_COMPONENT;
+        } else if shell_component == PAGE_SHELL_COMPONENT {
+            if let Some(directory_shell) = &request_context.directory_shell {
+                log::debug!("Using the '{directory_shell}' shell configured for this directory");
+                shell_component = directory_shell;
+            }
         }
-        let mut shell_renderer = Self::create_renderer(
-            shell_component,
-            Arc::clone(&app_state),
-            0,
-            request_context.content_security_policy.nonce,
-        )
-        .await
-        .with_context(|| "The shell component should always exist")?;
+        let mut shell_renderer =
+            Self::create_renderer(shell_component, Arc::clone(&app_state), 0, &request_context)
+                .await
+                .with_context(|| "The shell component should always exist")?;
         log::debug!("Rendering the shell with properties: {shell_row}");
         shell_renderer.render_start(&mut writer, shell_row)?;
 
@@ -683,6 +895,7 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
             shell_renderer,
             current_statement: 1,
             request_context,
+            grid: GridState::None,
         };
 
         for row in rows_iter {
@@ -738,7 +951,7 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
     /// Returns whether the error is irrecoverable and the rendering must stop
     pub async fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
         self.close_component()?;
-        let data = if self.app_state.config.environment.is_prod() {
+        let data = if self.app_state.config().environment.is_prod() {
             json!({
                 "description": format!("Please contact the administrator for more information. The error has been logged."),
             })
@@ -790,18 +1003,19 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
         component: &str,
         app_state: Arc<AppState>,
         component_index: usize,
-        nonce: u64,
+        request_context: &RequestContext,
     ) -> anyhow::Result<SplitTemplateRenderer> {
         let split_template = app_state
             .all_templates
             .get_template(&app_state, component)
             .await?;
-        Ok(SplitTemplateRenderer::new(
-            split_template,
-            app_state,
-            component_index,
-            nonce,
-        ))
+        let nonce = request_context.content_security_policy.nonce;
+        Ok(
+            SplitTemplateRenderer::new(split_template, app_state, component_index, nonce)
+                .with_timezone(request_context.timezone)
+                .with_url_parameters(request_context.url_parameters.clone())
+                .with_csrf_secret(Arc::clone(&request_context.csrf_secret)),
+        )
     }
 
     /// Set a new current component and return the old one
@@ -817,7 +1031,7 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
             component,
             Arc::clone(&self.app_state),
             current_component_index + 1,
-            self.request_context.content_security_policy.nonce,
+            &self.request_context,
         )
         .await?;
         Ok(self.current_component.replace(new_component))
@@ -829,6 +1043,7 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
         data: &T,
     ) -> anyhow::Result<Option<SplitTemplateRenderer>> {
         self.close_component()?;
+        self.open_grid_column(&json!(data))?;
         let old_component = self.set_current_component(component).await?;
         self.current_component
             .as_mut()
@@ -838,19 +1053,56 @@ impl<W: std::io::Write> HtmlRenderContext<W> {
     }
 
     fn close_component(&mut self) -> anyhow::Result<()> {
-        if let Some(old_component) = self.current_component.as_mut().take() {
+        if let Some(old_component) = self.current_component.as_mut() {
             old_component.render_end(&mut self.writer)?;
         }
+        if self.grid == GridState::InColumn {
+            self.writer.write_all(b"</div>")?;
+            self.grid = GridState::InRow;
+        }
+        Ok(())
+    }
+
+    /// Components that have a `grid_width` between 1 and 12 are placed side by side,
+    /// in the columns of a row that contains all the consecutive components that have a width.
+    fn open_grid_column(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let width = match data.get(GRID_WIDTH_PROPERTY) {
+            Some(JsonValue::Number(n)) => n.as_u64(),
+            Some(JsonValue::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .filter(|w| (1..=12).contains(w));
+        match (width, self.grid) {
+            (Some(width), grid) => {
+                if grid == GridState::None {
+                    self.writer.write_all(b"<div class=\"row\">")?;
+                }
+                write!(self.writer, "<div class=\"col-12 col-lg-{width}\">")?;
+                self.grid = GridState::InColumn;
+            }
+            (None, GridState::None) => {}
+            (None, _) => {
+                self.writer.write_all(b"</div>")?;
+                self.grid = GridState::None;
+            }
+        }
         Ok(())
     }
 
     pub async fn close(mut self) -> W {
-        if let Some(old_component) = self.current_component.as_mut().take() {
+        if let Some(old_component) = self.current_component.as_mut() {
             let res = old_component
                 .render_end(&mut self.writer)
                 .map_err(|e| format_err!("Unable to render the component closing: {e}"));
             self.handle_result_and_log(&res).await;
         }
+        let res = match self.grid {
+            GridState::None => Ok(()),
+            GridState::InRow => self.writer.write_all(b"</div>"),
+            GridState::InColumn => self.writer.write_all(b"</div></div>"),
+        }
+        .map_err(|e| format_err!("Unable to close the grid: {e}"));
+        self.handle_result_and_log(&res).await;
         let res = self
             .shell_renderer
             .render_end(&mut self.writer)
@@ -874,27 +1126,93 @@ pub struct SplitTemplateRenderer {
     ctx: Context,
     app_state: Arc<AppState>,
     row_index: usize,
+    /// When the template reads `@last` or the rows are grouped, the row that was received last,
+    /// which is rendered when the next row or the end of the component arrives
+    pending_row: Option<JsonValue>,
+    /// The `group_by` top-level property: consecutive rows with the same value in this column form a group
+    group_by: Option<String>,
+    /// The value of the grouping column in the current group
+    current_group: Option<JsonValue>,
+    /// Number of rows in the current group, and sums of their numeric columns, up to the current row
+    group_row_count: usize,
+    group_sum: serde_json::Map<String, JsonValue>,
     component_index: usize,
     nonce: JsonValue,
+    /// Time zone of the viewer, available in templates as `@timezone`
+    timezone: JsonValue,
+    /// Parameters of the URL of the page, available in templates as `@url_parameters`
+    url_parameters: JsonValue,
+    /// Secret of the browser, to which the tokens created by the `csrf_token` helper are bound
+    csrf_secret: Arc<BrowserSecret>,
+    /// Properties that were already reported as unknown, to log each of them only once
+    reported_unknown_properties: HashSet<String>,
+    /// Time spent rendering this component so far, reported in the template statistics when it is closed
+    render_time: std::time::Duration,
 }
 
 impl SplitTemplateRenderer {
-    fn new(
+    /// Creates a renderer for a component.
+    /// `component_index` is the position of the component in its page, and `nonce` the page's CSP nonce.
+    #[must_use]
+    pub fn new(
         split_template: Arc<SplitTemplate>,
         app_state: Arc<AppState>,
         component_index: usize,
         nonce: u64,
     ) -> Self {
+        let timezone = crate::webserver::timezone::default_timezone(&app_state.config());
         Self {
             split_template,
             local_vars: None,
+            timezone: timezone.name().into(),
+            url_parameters: JsonValue::Object(serde_json::Map::new()),
+            csrf_secret: Arc::default(),
             app_state,
             row_index: 0,
+            pending_row: None,
+            group_by: None,
+            current_group: None,
+            group_row_count: 0,
+            group_sum: serde_json::Map::new(),
             ctx: Context::null(),
             component_index,
             nonce: nonce.into(),
+            reported_unknown_properties: HashSet::new(),
+            render_time: std::time::Duration::ZERO,
         }
     }
+
+    /// Renders the component for a viewer in the given time zone
+    #[must_use]
+    pub fn with_timezone(mut self, timezone: chrono_tz::Tz) -> Self {
+        self.timezone = timezone.name().into();
+        self
+    }
+
+    /// Renders the component for a page with the given URL parameters
+    #[must_use]
+    pub fn with_url_parameters(mut self, url_parameters: JsonValue) -> Self {
+        self.url_parameters = url_parameters;
+        self
+    }
+
+    /// Renders the component for the browser with the given secret
+    #[must_use]
+    pub fn with_csrf_secret(mut self, csrf_secret: Arc<BrowserSecret>) -> Self {
+        self.csrf_secret = csrf_secret;
+        self
+    }
+
+    /// Creates the context of a part of the template, with the helpers that depend on the request
+    fn new_render_context<'reg, 'rc>(&self) -> handlebars::RenderContext<'reg, 'rc> {
+        let mut render_context = handlebars::RenderContext::new(None);
+        render_context.register_local_helper(
+            "csrf_token",
+            Box::new(CsrfTokenHelper(Arc::clone(&self.csrf_secret))),
+        );
+        render_context
+    }
+
     fn name(&self) -> &str {
         self.split_template
             .list_content
@@ -903,11 +1221,55 @@ impl SplitTemplateRenderer {
             .unwrap_or_default()
     }
 
+    /// The name of the component, as used in the `component` column
+    fn component_name(&self) -> &str {
+        self.split_template
+            .name()
+            .and_then(|name| name.strip_suffix(" before each block"))
+            .unwrap_or_default()
+    }
+
+    /// Logs a warning for each property of the row that is not used by the component's template,
+    /// which is usually a typo in a column name. This is done only in development mode.
+    fn warn_unknown_properties(&mut self, data: &JsonValue, is_top_level: bool) {
+        if self.app_state.config().environment.is_prod() {
+            return;
+        }
+        let Some(row) = data.as_object() else {
+            return;
+        };
+        let properties = &self.split_template.properties;
+        for property in row.keys() {
+            if !properties.is_known(property, is_top_level)
+                && self.reported_unknown_properties.insert(property.clone())
+            {
+                let component = self.component_name();
+                log::warn!(
+                    "The '{component}' component does not use the '{property}' property, which will be ignored. \
+                    Check the spelling of the column names in your SQL query."
+                );
+            }
+        }
+    }
+
+    /// Removes the properties with a NULL value from the row, when `omit_null_properties` is enabled.
+    fn omit_null_properties(&self, data: &mut JsonValue) {
+        if !self.app_state.config().omit_null_properties {
+            return;
+        }
+        if let JsonValue::Object(row) = data {
+            row.retain(|_, value| !value.is_null());
+        }
+    }
+
     fn render_start<W: std::io::Write>(
         &mut self,
         writer: W,
-        data: JsonValue,
+        mut data: JsonValue,
     ) -> Result<(), RenderError> {
+        let started_at = Instant::now();
+        self.omit_null_properties(&mut data);
+        self.warn_unknown_properties(&data, true);
         log::trace!(
             "Starting rendering of a template{} with the following top-level parameters: {data}",
             self.split_template
@@ -915,13 +1277,19 @@ impl SplitTemplateRenderer {
                 .map(|n| format!(" ('{n}')"))
                 .unwrap_or_default(),
         );
-        let mut render_context = handlebars::RenderContext::new(None);
+        let mut render_context = self.new_render_context();
         let blk = render_context
             .block_mut()
             .expect("context created without block");
         blk.set_local_var("component_index", self.component_index.into());
         blk.set_local_var("csp_nonce", self.nonce.clone());
+        blk.set_local_var("timezone", self.timezone.clone());
+        blk.set_local_var("url_parameters", self.url_parameters.clone());
 
+        self.group_by = data
+            .get("group_by")
+            .and_then(JsonValue::as_str)
+            .map(str::to_owned);
         *self.ctx.data_mut() = data;
         let mut output = HandlebarWriterOutput(writer);
         self.split_template.before_list.render(
@@ -934,26 +1302,66 @@ impl SplitTemplateRenderer {
             .block_mut()
             .map(|blk| std::mem::take(blk.local_variables_mut()));
         self.row_index = 0;
+        self.pending_row = None;
+        self.current_group = None;
+        self.render_time = started_at.elapsed();
         Ok(())
     }
 
+    /// Whether each row is rendered only once the next one is known, to set `@last` and `@group_end`
+    fn looks_ahead(&self) -> bool {
+        self.split_template.uses_last_row || self.group_by.is_some()
+    }
+
     fn render_item<W: std::io::Write>(
         &mut self,
         writer: W,
-        data: JsonValue,
+        mut data: JsonValue,
     ) -> Result<(), RenderError> {
         log::trace!("Rendering a new item in the page: {data:?}");
+        let started_at = Instant::now();
+        self.omit_null_properties(&mut data);
+        self.warn_unknown_properties(&data, false);
+        if self.looks_ahead() {
+            if let Some(previous) = self.pending_row.take() {
+                self.render_row(writer, previous, Some(&data))?;
+            }
+            self.pending_row = Some(data);
+        } else {
+            self.render_row(writer, data, None)?;
+        }
+        self.render_time += started_at.elapsed();
+        Ok(())
+    }
+
+    /// Renders the `each_row` block for a row, with the `@row_index`, `@first`, `@last`, `@odd` and `@even` variables.
+    /// `@odd` is true for the first, third, fifth... rows, like the `:nth-child(odd)` CSS selector.
+    /// `next_row` is the row that follows, when the renderer looks ahead.
+    fn render_row<W: std::io::Write>(
+        &mut self,
+        writer: W,
+        data: JsonValue,
+        next_row: Option<&JsonValue>,
+    ) -> Result<(), RenderError> {
+        let is_last = self.looks_ahead() && next_row.is_none();
         if let Some(local_vars) = self.local_vars.take() {
-            let mut render_context = handlebars::RenderContext::new(None);
+            let mut render_context = self.new_render_context();
             let blk = render_context
                 .block_mut()
                 .expect("context created without block");
             *blk.local_variables_mut() = local_vars;
             let mut blk = BlockContext::new();
+            self.set_group_vars(&mut blk, &data, next_row);
             blk.set_base_value(data);
             blk.set_local_var("component_index", self.component_index.into());
             blk.set_local_var("row_index", self.row_index.into());
+            blk.set_local_var("first", (self.row_index == 0).into());
+            blk.set_local_var("last", is_last.into());
+            blk.set_local_var("odd", (self.row_index % 2 == 0).into());
+            blk.set_local_var("even", (self.row_index % 2 == 1).into());
             blk.set_local_var("csp_nonce", self.nonce.clone());
+            blk.set_local_var("timezone", self.timezone.clone());
+            blk.set_local_var("url_parameters", self.url_parameters.clone());
             render_context.push_block(blk);
             let mut output = HandlebarWriterOutput(writer);
             self.split_template.list_content.render(
@@ -971,7 +1379,56 @@ impl SplitTemplateRenderer {
         Ok(())
     }
 
-    fn render_end<W: std::io::Write>(&mut self, writer: W) -> Result<(), RenderError> {
+    /// Sets the `@group`, `@group_start`, `@group_end`, `@group_row_count` and `@group_sum` variables.
+    /// The count and the sums include the rows of the group up to the current one,
+    /// so they are the totals of the group on its last row, where `@group_end` is true.
+    fn set_group_vars(
+        &mut self,
+        blk: &mut BlockContext,
+        data: &JsonValue,
+        next_row: Option<&JsonValue>,
+    ) {
+        let Some(group_by) = &self.group_by else {
+            return;
+        };
+        let group = data.get(group_by).cloned().unwrap_or_default();
+        let group_start = self.current_group.as_ref() != Some(&group);
+        let group_end =
+            next_row.is_none_or(|next| next.get(group_by).unwrap_or(&JsonValue::Null) != &group);
+        if group_start {
+            self.group_row_count = 0;
+            self.group_sum.clear();
+        }
+        self.group_row_count += 1;
+        if let JsonValue::Object(row) = data {
+            for (key, value) in row.iter().filter(|(_, value)| value.is_number()) {
+                let sum = self.group_sum.entry(key.clone()).or_insert(0.into());
+                *sum = add_json_numbers(sum, value);
+            }
+        }
+        blk.set_local_var("group", group.clone());
+        blk.set_local_var("group_start", group_start.into());
+        blk.set_local_var("group_end", group_end.into());
+        blk.set_local_var("group_row_count", self.group_row_count.into());
+        blk.set_local_var("group_sum", self.group_sum.clone().into());
+        self.current_group = Some(group);
+    }
+
+    /// Renders a whole component at once: its top-level properties, then each of its rows.
+    pub fn render_all<W: std::io::Write>(
+        &mut self,
+        mut writer: W,
+        top_level: JsonValue,
+        rows: impl IntoIterator<Item = JsonValue>,
+    ) -> Result<(), RenderError> {
+        self.render_start(&mut writer, top_level)?;
+        for row in rows {
+            self.render_item(&mut writer, row)?;
+        }
+        self.render_end(writer)
+    }
+
+    fn render_end<W: std::io::Write>(&mut self, mut writer: W) -> Result<(), RenderError> {
         log::trace!(
             "Closing a template {}",
             self.split_template
@@ -979,11 +1436,18 @@ impl SplitTemplateRenderer {
                 .map(|name| format!("('{name}')"))
                 .unwrap_or_default(),
         );
+        let started_at = Instant::now();
+        if let Some(last_row) = self.pending_row.take() {
+            self.render_row(&mut writer, last_row, None)?;
+        }
         if let Some(mut local_vars) = self.local_vars.take() {
-            let mut render_context = handlebars::RenderContext::new(None);
+            let mut render_context = self.new_render_context();
             local_vars.put("row_index", self.row_index.into());
+            local_vars.put("row_count", self.row_index.into());
             local_vars.put("component_index", self.component_index.into());
             local_vars.put("csp_nonce", self.nonce.clone());
+            local_vars.put("timezone", self.timezone.clone());
+            local_vars.put("url_parameters", self.url_parameters.clone());
             log::trace!("Rendering the after_list template with the following local variables: {local_vars:?}");
             *render_context
                 .block_mut()
@@ -996,6 +1460,9 @@ impl SplitTemplateRenderer {
                 &mut render_context,
                 &mut output,
             )?;
+            self.render_time += started_at.elapsed();
+            let stats = &self.app_state.all_templates.stats;
+            stats.record_render(self.component_name(), self.render_time);
         }
         Ok(())
     }
@@ -1008,6 +1475,25 @@ mod tests {
     use crate::templates::split_template;
     use handlebars::Template;
 
+    #[test]
+    fn test_attachment_disposition() {
+        let header = |filename: &str, extension: &str| {
+            content_disposition(DispositionType::Attachment, filename, extension).to_string()
+        };
+        assert_eq!(
+            header("report", "csv"),
+            "attachment; filename=\"report.csv\"; filename*=UTF-8''report.csv"
+        );
+        assert_eq!(
+            header("Rapport \"été\".xlsx", "csv"),
+            "attachment; filename=\"Rapport \\\"_t_\\\".xlsx\"; filename*=UTF-8''Rapport%20%22%C3%A9t%C3%A9%22.xlsx"
+        );
+        assert_eq!(
+            header("a;b\r\nc", "ics"),
+            "attachment; filename=\"a;b__c.ics\"; filename*=UTF-8''a%3Bb%0D%0Ac.ics"
+        );
+    }
+
     #[actix_web::test]
     async fn test_split_template_render() -> anyhow::Result<()> {
         let template = Template::compile(
@@ -1031,6 +1517,26 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_null_values() -> anyhow::Result<()> {
+        let template = Template::compile(
+            "{{#each_row}}[{{default x}}|{{default x 'other'}}|{{#each this}}{{@key}}{{/each}}]{{/each_row}}",
+        )?;
+        let split = Arc::new(split_template(template));
+        let mut config = app_config::tests::test_config();
+        config.null_placeholder = Some("-".into());
+        config.omit_null_properties = true;
+        let app_state = Arc::new(AppState::init(&config).await.unwrap());
+        let mut output = Vec::new();
+        let mut rdr = SplitTemplateRenderer::new(split, app_state, 0, 0);
+        rdr.render_start(&mut output, json!({}))?;
+        rdr.render_item(&mut output, json!({"x": 1, "y": 2}))?;
+        rdr.render_item(&mut output, json!({"x": null, "y": 2}))?;
+        rdr.render_end(&mut output)?;
+        assert_eq!(String::from_utf8_lossy(&output), "[1|1|xy][-|other|y]");
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_delayed() -> anyhow::Result<()> {
         let template = Template::compile(
@@ -1051,15 +1557,58 @@ mod tests {
         );
         Ok(())
     }
+
+    #[actix_web::test]
+    async fn test_row_position_variables() -> anyhow::Result<()> {
+        let template = Template::compile(
+            "{{#each_row}}{{#if @first}}[{{/if}}{{x}}{{#if @odd}}o{{/if}}{{#if @even}}e{{/if}}\
+            {{#if @last}}]{{else}},{{/if}}{{/each_row}} {{@row_count}} rows",
+        )?;
+        let split = split_template(template);
+        let mut output = Vec::new();
+        let config = app_config::tests::test_config();
+        let app_state = Arc::new(AppState::init(&config).await.unwrap());
+        let mut rdr = SplitTemplateRenderer::new(Arc::new(split), app_state, 0, 0);
+        rdr.render_start(&mut output, json!(null))?;
+        for x in 1..=3 {
+            rdr.render_item(&mut output, json!({ "x": x }))?;
+        }
+        rdr.render_end(&mut output)?;
+        assert_eq!(String::from_utf8_lossy(&output), "[1o,2e,3o] 3 rows");
+        Ok(())
+    }
+}
+
+fn add_json_numbers(a: &JsonValue, b: &JsonValue) -> JsonValue {
+    if let Some(sum) = a
+        .as_i64()
+        .zip(b.as_i64())
+        .and_then(|(a, b)| a.checked_add(b))
+    {
+        sum.into()
+    } else {
+        (a.as_f64().unwrap_or_default() + b.as_f64().unwrap_or_default()).into()
+    }
+}
+
+/// Whether the component is handled directly by `SQLPage` instead of being rendered by a template,
+/// like `redirect`, `json`, or `dynamic`.
+#[must_use]
+pub fn is_template_free_component(name: &str) -> bool {
+    name == "dynamic" || HeaderComponent::try_from(name).is_ok()
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum HeaderComponent {
+pub(crate) enum HeaderComponent {
     StatusCode,
     HttpHeader,
     Redirect,
     Json,
     Csv,
+    Xlsx,
+    Ical,
+    Xml,
+    Parquet,
     Cookie,
     Authentication,
 }
@@ -1073,6 +1622,10 @@ impl TryFrom<&str> for HeaderComponent {
             "redirect" => Ok(Self::Redirect),
             "json" => Ok(Self::Json),
             "csv" => Ok(Self::Csv),
+            "xlsx" => Ok(Self::Xlsx),
+            "ical" => Ok(Self::Ical),
+            "xml" => Ok(Self::Xml),
+            "parquet" => Ok(Self::Parquet),
             "cookie" => Ok(Self::Cookie),
             "authentication" => Ok(Self::Authentication),
             _ => Err(()),
@@ -0,0 +1,495 @@
+//! Syntax highlighting of code blocks on the server, used by the `highlight` template helper.
+//!
+//! The code is split into tokens with a few rules per language: comments, strings, numbers, keywords,
+//! and the names of attributes in configuration files. Markup languages have their own rules, for tags.
+//! This is much simpler than a real parser, but good enough to make snippets of SQL or configuration readable,
+//! without loading a JavaScript library in the page.
+//! Tokens are wrapped in `span` elements with the classes of highlight.js, so that highlight.js themes apply.
+
+use handlebars::html_escape;
+
+/// The rules used to split the code of a programming or configuration language into tokens
+struct Language {
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    case_insensitive: bool,
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Names and strings followed by this character are attribute names, such as the keys of a JSON object
+    attribute_separator: Option<char>,
+}
+
+impl Language {
+    /// Configuration languages allow dashes in names, such as `font-size` or `max-connections`
+    fn is_identifier_char(&self, c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || (c == '-' && self.attribute_separator.is_some())
+    }
+
+    fn is_one_of(&self, words: &[&str], word: &str) -> bool {
+        words.iter().any(|w| {
+            if self.case_insensitive {
+                w.eq_ignore_ascii_case(word)
+            } else {
+                *w == word
+            }
+        })
+    }
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        names: &[
+            "sql",
+            "sqlite",
+            "postgresql",
+            "postgres",
+            "mysql",
+            "mssql",
+            "tsql",
+            "plsql",
+        ],
+        keywords: &[
+            "add",
+            "all",
+            "alter",
+            "and",
+            "as",
+            "asc",
+            "begin",
+            "between",
+            "by",
+            "case",
+            "cast",
+            "check",
+            "coalesce",
+            "column",
+            "commit",
+            "constraint",
+            "create",
+            "cross",
+            "default",
+            "delete",
+            "desc",
+            "distinct",
+            "drop",
+            "else",
+            "end",
+            "except",
+            "exists",
+            "foreign",
+            "from",
+            "full",
+            "function",
+            "group",
+            "having",
+            "if",
+            "in",
+            "index",
+            "inner",
+            "insert",
+            "intersect",
+            "into",
+            "is",
+            "join",
+            "key",
+            "left",
+            "like",
+            "limit",
+            "not",
+            "offset",
+            "on",
+            "or",
+            "order",
+            "outer",
+            "over",
+            "partition",
+            "primary",
+            "recursive",
+            "references",
+            "replace",
+            "returning",
+            "right",
+            "rollback",
+            "select",
+            "set",
+            "table",
+            "then",
+            "transaction",
+            "trigger",
+            "union",
+            "unique",
+            "update",
+            "using",
+            "values",
+            "view",
+            "when",
+            "where",
+            "with",
+        ],
+        literals: &["null", "true", "false"],
+        case_insensitive: true,
+        line_comments: &["--"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"', '`'],
+        attribute_separator: None,
+    },
+    Language {
+        names: &["json"],
+        keywords: &[],
+        literals: &["null", "true", "false"],
+        case_insensitive: false,
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        attribute_separator: Some(':'),
+    },
+    Language {
+        names: &["javascript", "js", "typescript", "ts"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "finally",
+            "for",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "of",
+            "return",
+            "static",
+            "switch",
+            "this",
+            "throw",
+            "try",
+            "type",
+            "typeof",
+            "var",
+            "while",
+            "yield",
+        ],
+        literals: &["null", "undefined", "true", "false", "NaN", "Infinity"],
+        case_insensitive: false,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"', '`'],
+        attribute_separator: None,
+    },
+    Language {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+            "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+            "type", "unsafe", "use", "where", "while",
+        ],
+        literals: &["true", "false", "None", "Some", "Ok", "Err"],
+        case_insensitive: false,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        attribute_separator: None,
+    },
+    Language {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+            "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+            "with", "yield",
+        ],
+        literals: &["None", "True", "False"],
+        case_insensitive: false,
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['\'', '"'],
+        attribute_separator: None,
+    },
+    Language {
+        names: &["bash", "sh", "shell", "zsh"],
+        keywords: &[
+            "case", "do", "done", "echo", "elif", "else", "esac", "exit", "export", "fi", "for",
+            "function", "if", "in", "local", "read", "return", "then", "until", "while",
+        ],
+        literals: &["true", "false"],
+        case_insensitive: false,
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['\'', '"'],
+        attribute_separator: None,
+    },
+    Language {
+        names: &["css"],
+        keywords: &[
+            "important",
+            "media",
+            "import",
+            "supports",
+            "keyframes",
+            "font-face",
+        ],
+        literals: &[],
+        case_insensitive: true,
+        line_comments: &[],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['\'', '"'],
+        attribute_separator: Some(':'),
+    },
+    Language {
+        names: &["toml", "ini", "env", "properties"],
+        keywords: &[],
+        literals: &["true", "false"],
+        case_insensitive: false,
+        line_comments: &["#", ";"],
+        block_comment: None,
+        quotes: &['\'', '"'],
+        attribute_separator: Some('='),
+    },
+    Language {
+        names: &["yaml", "yml"],
+        keywords: &[],
+        literals: &["true", "false", "null", "yes", "no", "on", "off"],
+        case_insensitive: true,
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['\'', '"'],
+        attribute_separator: Some(':'),
+    },
+];
+
+/// Languages written with tags
+const MARKUP_LANGUAGES: &[&str] = &["html", "xml", "svg", "xhtml", "handlebars", "hbs"];
+
+/// Returns the code as HTML, with its tokens highlighted,
+/// or `None` if the language is not supported, in which case the code should be displayed as plain text.
+#[must_use]
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+    let language = language.trim().to_ascii_lowercase();
+    if MARKUP_LANGUAGES.contains(&language.as_str()) {
+        return Some(highlight_markup(code));
+    }
+    let language = LANGUAGES
+        .iter()
+        .find(|l| l.names.contains(&language.as_str()))?;
+    Some(highlight_code(code, language))
+}
+
+fn push_token(output: &mut String, class: &str, token: &str) {
+    output.push_str("<span class=\"hljs-");
+    output.push_str(class);
+    output.push_str("\">");
+    output.push_str(&html_escape(token));
+    output.push_str("</span>");
+}
+
+/// Length of the string that starts at the beginning of `code` with the given quote, including its quotes
+fn string_length(code: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in code.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote != '`' {
+            escaped = true;
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    code.len()
+}
+
+fn highlight_code(code: &str, language: &Language) -> String {
+    let mut output = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+    // Whether the previous character continues an identifier, in which case a digit does not start a number
+    let mut in_word = false;
+    while let Some(c) = rest.chars().next() {
+        let (class, length) = if let Some(prefix) = language
+            .line_comments
+            .iter()
+            .find(|prefix| rest.starts_with(*prefix))
+        {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            (Some("comment"), end.max(prefix.len()))
+        } else if let Some((start, end)) = language
+            .block_comment
+            .filter(|(start, _)| rest.starts_with(start))
+        {
+            let length = rest[start.len()..]
+                .find(end)
+                .map_or(rest.len(), |i| start.len() + i + end.len());
+            (Some("comment"), length)
+        } else if language.quotes.contains(&c) {
+            let length = string_length(rest, c);
+            (
+                attribute_or(language, &rest[length..], Some("string")),
+                length,
+            )
+        } else if c.is_ascii_digit() && !in_word {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            (Some("number"), length)
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|c: char| !language.is_identifier_char(c))
+                .unwrap_or(rest.len());
+            let word = &rest[..length];
+            let class = if language.is_one_of(language.keywords, word) {
+                Some("keyword")
+            } else if language.is_one_of(language.literals, word) {
+                Some("literal")
+            } else {
+                None
+            };
+            (attribute_or(language, &rest[length..], class), length)
+        } else {
+            (None, c.len_utf8())
+        };
+        let (token, remaining) = rest.split_at(length);
+        match class {
+            Some(class) => push_token(&mut output, class, token),
+            None => output.push_str(&html_escape(token)),
+        }
+        in_word = token.ends_with(|c| language.is_identifier_char(c));
+        rest = remaining;
+    }
+    output
+}
+
+/// Returns `attr` if the token is followed by the attribute separator of the language, and `class` otherwise
+fn attribute_or(
+    language: &Language,
+    after_token: &str,
+    class: Option<&'static str>,
+) -> Option<&'static str> {
+    match language.attribute_separator {
+        Some(separator)
+            if after_token
+                .trim_start_matches([' ', '\t'])
+                .starts_with(separator) =>
+        {
+            Some("attr")
+        }
+        _ => class,
+    }
+}
+
+fn highlight_markup(code: &str) -> String {
+    let mut output = String::with_capacity(code.len() * 2);
+    let mut rest = code;
+    while let Some(lt) = rest.find('<') {
+        output.push_str(&html_escape(&rest[..lt]));
+        rest = &rest[lt..];
+        let end_of = |pattern: &str| rest.find(pattern).map_or(rest.len(), |i| i + pattern.len());
+        if rest.starts_with("<!--") {
+            let length = end_of("-->");
+            push_token(&mut output, "comment", &rest[..length]);
+            rest = &rest[length..];
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let length = end_of(">");
+            push_token(&mut output, "meta", &rest[..length]);
+            rest = &rest[length..];
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+            rest = highlight_tag(&mut output, rest);
+        } else {
+            output.push_str("&lt;");
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(&html_escape(rest));
+    output
+}
+
+/// Highlights the tag at the beginning of `code`, and returns the code after it
+fn highlight_tag<'a>(output: &mut String, code: &'a str) -> &'a str {
+    let start = if code.starts_with("</") { 2 } else { 1 };
+    let name_end = code[start..]
+        .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/'))
+        .map_or(code.len(), |i| start + i);
+    output.push_str("<span class=\"hljs-tag\">");
+    output.push_str(&html_escape(&code[..start]));
+    push_token(output, "name", &code[start..name_end]);
+    let mut rest = &code[name_end..];
+    while let Some(c) = rest.chars().next() {
+        let length = if c == '>' {
+            output.push_str("&gt;");
+            rest = &rest[1..];
+            break;
+        } else if c == '"' || c == '\'' {
+            let length = rest[1..].find(c).map_or(rest.len(), |i| i + 2);
+            push_token(output, "string", &rest[..length]);
+            length
+        } else if c.is_alphabetic() {
+            let length = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(rest.len());
+            push_token(output, "attr", &rest[..length]);
+            length
+        } else {
+            output.push_str(&html_escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[length..];
+    }
+    output.push_str("</span>");
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_sql() {
+        assert_eq!(
+            highlight("SELECT 'a''b' AS x1, 42 -- answer\nFROM t;", "sql").unwrap(),
+            "<span class=\"hljs-keyword\">SELECT</span> \
+            <span class=\"hljs-string\">&#x27;a&#x27;</span><span class=\"hljs-string\">&#x27;b&#x27;</span> \
+            <span class=\"hljs-keyword\">AS</span> x1, <span class=\"hljs-number\">42</span> \
+            <span class=\"hljs-comment\">-- answer</span>\n<span class=\"hljs-keyword\">FROM</span> t;"
+        );
+        assert_eq!(highlight("x", "cobol"), None);
+    }
+
+    #[test]
+    fn test_highlight_attributes() {
+        assert_eq!(
+            highlight(r#"{"a": [true, "<b>"]}"#, "json").unwrap(),
+            "{<span class=\"hljs-attr\">&quot;a&quot;</span>: [<span class=\"hljs-literal\">true</span>, \
+            <span class=\"hljs-string\">&quot;&lt;b&gt;&quot;</span>]}"
+        );
+        assert_eq!(
+            highlight("port = 8080 # default", "toml").unwrap(),
+            "<span class=\"hljs-attr\">port</span> &#x3D; <span class=\"hljs-number\">8080</span> \
+            <span class=\"hljs-comment\"># default</span>"
+        );
+    }
+
+    #[test]
+    fn test_highlight_markup() {
+        assert_eq!(
+            highlight(r#"<a href="/x">1 < 2</a><!-- c -->"#, "html").unwrap(),
+            "<span class=\"hljs-tag\">&lt;<span class=\"hljs-name\">a</span> \
+            <span class=\"hljs-attr\">href</span>&#x3D;<span class=\"hljs-string\">&quot;/x&quot;</span>&gt;</span>\
+            1 &lt; 2<span class=\"hljs-tag\">&lt;/<span class=\"hljs-name\">a</span>&gt;</span>\
+            <span class=\"hljs-comment\">&lt;!-- c --&gt;</span>"
+        );
+    }
+}
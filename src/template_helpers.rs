@@ -1,5 +1,7 @@
 use std::borrow::Cow;
+use std::sync::Arc;
 
+use crate::webserver::csrf::BrowserSecret;
 use crate::{app_config::AppConfig, utils::static_filename};
 use anyhow::Context as _;
 use handlebars::{
@@ -28,7 +30,11 @@ pub fn register_all_helpers(h: &mut Handlebars<'_>, config: &AppConfig) {
 
     register_helper(h, "stringify", stringify_helper as H);
     register_helper(h, "parse_json", parse_json_helper as EH);
-    register_helper(h, "default", default_helper as HH);
+    register_helper(
+        h,
+        "default",
+        DefaultHelper(config.null_placeholder.clone().into()),
+    );
     register_helper(h, "entries", entries_helper as H);
     register_helper(h, "replace", replace_helper as HHH);
     // delay helper: store a piece of information in memory that can be output later with flush_delayed
@@ -36,7 +42,9 @@ pub fn register_all_helpers(h: &mut Handlebars<'_>, config: &AppConfig) {
     h.register_helper("flush_delayed", Box::new(flush_delayed_helper));
     register_helper(h, "plus", plus_helper as HH);
     register_helper(h, "minus", minus_helper as HH);
+    register_helper(h, "percentage", percentage_helper as HH);
     h.register_helper("sum", Box::new(sum_helper));
+    h.register_helper("each_key", Box::new(each_key_helper));
     register_helper(h, "starts_with", starts_with_helper as HH);
 
     // to_array: convert a value to a single-element array. If the value is already an array, return it as-is.
@@ -66,13 +74,82 @@ pub fn register_all_helpers(h: &mut Handlebars<'_>, config: &AppConfig) {
     register_helper(h, "app_config", AppConfigHelper(config.clone()));
 
     // icon helper: generate an image with the specified icon
+    register_helper(h, "path_crumbs", PathCrumbsHelper(site_prefix.clone()));
     h.register_helper("icon_img", Box::new(IconImgHelper(site_prefix)));
+    // icon helper: generate an inline svg with the drawing of the specified icon
+    h.register_helper("icon", Box::new(icon_helper));
+    // sanitize_html: render user-submitted HTML, keeping only safe tags and attributes
+    h.register_helper("sanitize_html", Box::new(SanitizeHtmlHelper));
+    // highlight: render code with its syntax highlighted
+    h.register_helper("highlight", Box::new(highlight_helper));
     register_helper(h, "markdown", MarkdownHelper::new(config));
     register_helper(h, "buildinfo", buildinfo_helper as EH);
     register_helper(h, "typeof", typeof_helper as H);
     register_helper(h, "rfc2822_date", rfc2822_date_helper as EH);
+    register_helper(
+        h,
+        "to_timezone",
+        ToTimezoneHelper(crate::webserver::timezone::default_timezone(config)),
+    );
     register_helper(h, "url_encode", url_encode_helper as H);
+    register_helper(h, "query_string", QueryStringHelper);
     register_helper(h, "csv_escape", csv_escape_helper as HH);
+    register_helper(
+        h,
+        "form_protection_token",
+        form_protection_token_helper as H,
+    );
+    // Replaced by a helper bound to the browser when the page is rendered for a request
+    h.register_helper("csrf_token", Box::new(CsrfTokenHelper::default()));
+    register_helper(
+        h,
+        "signed_previous_values",
+        signed_previous_values_helper as EH,
+    );
+    register_helper(
+        h,
+        "live_subscription_signature",
+        live_subscription_signature_helper as H,
+    );
+}
+
+/// Signed token of a form with `bot_protection`, which is either `true` or the minimum number of seconds
+/// it takes to fill the form.
+fn form_protection_token_helper(bot_protection: &JsonValue) -> JsonValue {
+    let min_seconds = bot_protection
+        .as_u64()
+        .unwrap_or(crate::webserver::form_protection::DEFAULT_MIN_SECONDS);
+    crate::webserver::form_protection::new_token(min_seconds).into()
+}
+
+/// Signed token of the form of a button with a `method`, that protects it against cross-site request forgery.
+/// The token is bound to the secret of the browser that displays the page.
+#[derive(Default)]
+pub(crate) struct CsrfTokenHelper(pub Arc<BrowserSecret>);
+impl HelperDef for CsrfTokenHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        _helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        Ok(ScopedJson::Derived(self.0.new_token().into()))
+    }
+}
+
+/// Signed values of the previous steps of a form split on several pages
+fn signed_previous_values_helper(values: &JsonValue) -> anyhow::Result<JsonValue> {
+    crate::webserver::form_steps::sign_values(values).map(JsonValue::from)
+}
+
+/// Signature of the subscription of a `live` component to the notifications of a database channel
+fn live_subscription_signature_helper(channel: &JsonValue) -> JsonValue {
+    let channel = match channel {
+        JsonValue::String(s) => s,
+        other => &other.to_string(),
+    };
+    crate::webserver::live::subscription_signature(channel).into()
 }
 
 fn json_eq_case_insensitive(a: &JsonValue, b: &JsonValue) -> bool {
@@ -101,6 +178,20 @@ fn default_helper(v: &JsonValue, default: &JsonValue) -> JsonValue {
     }
 }
 
+/// The `default` helper can be called with a single argument,
+/// in which case NULL values are replaced with the configured `null_placeholder`.
+struct DefaultHelper(JsonValue);
+
+impl CanHelp for DefaultHelper {
+    fn call(&self, args: &[PathAndJson]) -> Result<JsonValue, String> {
+        match args {
+            [v] => Ok(default_helper(v.value(), &self.0)),
+            [v, default] => Ok(default_helper(v.value(), default.value())),
+            _ => Err("expected one or two arguments".to_string()),
+        }
+    }
+}
+
 fn plus_helper(a: &JsonValue, b: &JsonValue) -> JsonValue {
     if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
         (a + b).into()
@@ -121,6 +212,21 @@ fn minus_helper(a: &JsonValue, b: &JsonValue) -> JsonValue {
     }
 }
 
+/// The share of `total` that `value` represents, in percents between 0 and 100: `{{percentage sold target}}`
+fn percentage_helper(value: &JsonValue, total: &JsonValue) -> JsonValue {
+    let number = |v: &JsonValue| match v {
+        JsonValue::String(s) => s.trim().parse::<f64>().ok(),
+        other => other.as_f64(),
+    };
+    match (number(value), number(total)) {
+        (Some(value), Some(total)) if total != 0.0 => {
+            let percent = (value / total * 100.0).clamp(0.0, 100.0);
+            ((percent * 10.0).round() / 10.0).into()
+        }
+        _ => JsonValue::Null,
+    }
+}
+
 fn starts_with_helper(a: &JsonValue, b: &JsonValue) -> JsonValue {
     if let (Some(a), Some(b)) = (a.as_str(), b.as_str()) {
         a.starts_with(b)
@@ -180,6 +286,8 @@ impl CanHelp for StaticPathHelper {
             "sqlpage.css" => static_filename!("sqlpage.css"),
             "apexcharts.js" => static_filename!("apexcharts.js"),
             "tomselect.js" => static_filename!("tomselect.js"),
+            "leaflet.js" => static_filename!("leaflet.js"),
+            "leaflet.css" => static_filename!("leaflet.css"),
             "favicon.svg" => static_filename!("favicon.svg"),
             other => return Err(format!("unknown static file: {other:?}")),
         };
@@ -203,11 +311,96 @@ impl CanHelp for AppConfigHelper {
             "max_uploaded_file_size" => Ok(JsonValue::Number(self.0.max_uploaded_file_size.into())),
             "environment" => serde_json::to_value(self.0.environment).map_err(|e| e.to_string()),
             "site_prefix" => Ok(self.0.site_prefix.clone().into()),
+            "default_theme" => Ok(self.0.default_theme.clone().into()),
             other => Err(format!("unknown app config property: {other:?}")),
         }
     }
 }
 
+/// Converts a timestamp to a time zone: `{{to_timezone created_at @timezone "%d/%m/%Y %H:%M"}}`.
+/// Struct Param is the default time zone of the site, used when the time zone is null.
+struct ToTimezoneHelper(chrono_tz::Tz);
+
+impl CanHelp for ToTimezoneHelper {
+    fn call(&self, args: &[PathAndJson]) -> Result<JsonValue, String> {
+        let (value, timezone, format) = match args {
+            [v] => (v.value(), &JsonValue::Null, None),
+            [v, tz] => (v.value(), tz.value(), None),
+            [v, tz, format] => (v.value(), tz.value(), format.value().as_str()),
+            _ => return Err("expected a timestamp, a time zone and a format".to_string()),
+        };
+        let timezone = match timezone {
+            JsonValue::Null => self.0,
+            JsonValue::String(name) => name
+                .parse()
+                .map_err(|_| format!("unknown time zone: {name:?}"))?,
+            other => return Err(format!("expected a time zone name, got {other}")),
+        };
+        // Values that are not timestamps, such as NULL, are left unchanged
+        let Some(timestamp) = value.as_str() else {
+            return Ok(value.clone());
+        };
+        Ok(
+            crate::webserver::timezone::to_timezone(timestamp, timezone, format)
+                .map_or_else(|| value.clone(), JsonValue::from),
+        )
+    }
+}
+
+/// Splits a URL path into the links of a breadcrumb: `{{#each (path_crumbs "/blog/my-post.sql")}}`.
+/// Returns objects with a title, a link, and whether they are the current page. Struct Param is the site prefix
+struct PathCrumbsHelper(String);
+
+impl CanHelp for PathCrumbsHelper {
+    fn call(&self, args: &[PathAndJson]) -> Result<JsonValue, String> {
+        let (path, home) = match args {
+            [path] => (path.value(), "Home"),
+            [path, home] => (path.value(), home.value().as_str().unwrap_or("Home")),
+            _ => return Err("expected one or two arguments".to_string()),
+        };
+        let path = path
+            .as_str()
+            .ok_or_else(|| format!("path_crumbs: not a string: {path}"))?;
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let relative = path
+            .strip_prefix(self.0.as_str())
+            .unwrap_or_else(|| path.trim_start_matches('/'));
+        let mut link = self.0.clone();
+        let mut crumbs = vec![serde_json::json!({"title": home, "link": link})];
+        let segments: Vec<&str> = relative
+            .split('/')
+            .filter(|s| !s.is_empty() && *s != "index.sql")
+            .collect();
+        for (i, segment) in segments.iter().enumerate() {
+            link.push_str(segment);
+            let is_last = i + 1 == segments.len();
+            if !(is_last && relative.ends_with(segment)) {
+                link.push('/');
+            }
+            crumbs.push(serde_json::json!({"title": crumb_title(segment), "link": link}));
+        }
+        if let Some(JsonValue::Object(last)) = crumbs.last_mut() {
+            last.insert("active".into(), true.into());
+        }
+        Ok(crumbs.into())
+    }
+}
+
+/// Turns a path segment such as `my-blog_post.sql` into a title such as `My blog post`
+fn crumb_title(segment: &str) -> String {
+    let decoded = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+    let name = decoded.strip_suffix(".sql").unwrap_or(&decoded);
+    let mut title = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        match c {
+            '-' | '_' => title.push(' '),
+            c if i == 0 => title.extend(c.to_uppercase()),
+            c => title.push(c),
+        }
+    }
+    title
+}
+
 /// Generate an image with the specified icon. Struct Param is the site prefix
 struct IconImgHelper(String);
 impl HelperDef for IconImgHelper {
@@ -239,6 +432,79 @@ impl HelperDef for IconImgHelper {
     }
 }
 
+/// Renders HTML from an untrusted source, such as a rich text editor, without its scripts and dangerous attributes.
+/// The result is written as is, so the helper can be used in a double-stash expression: `{{sanitize_html html}}`
+struct SanitizeHtmlHelper;
+impl HelperDef for SanitizeHtmlHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        helper: &handlebars::Helper<'rc>,
+        _r: &'reg Handlebars<'reg>,
+        _ctx: &'rc Context,
+        _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+        writer: &mut dyn handlebars::Output,
+    ) -> handlebars::HelperResult {
+        let html = match helper.param(0).map(PathAndJson::value) {
+            None | Some(JsonValue::Null) => return Ok(()),
+            Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+            Some(other) => Cow::Owned(other.to_string()),
+        };
+        writer.write(&crate::html_sanitizer::sanitize_html(&html))?;
+        Ok(())
+    }
+}
+
+/// Generate an inline svg icon, that does not reference the icons sprite
+fn icon_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let Some(JsonValue::String(name)) = helper.param(0).map(PathAndJson::value) else {
+        log::debug!("icon: no icon name, not rendering anything");
+        return Ok(());
+    };
+    let Some(icon) = crate::icons::find_icon(name) else {
+        log::warn!("icon: there is no icon named {name:?}, see https://tabler.io/icons");
+        return Ok(());
+    };
+    let size = helper
+        .param(1)
+        .and_then(|size| size.value().as_u64())
+        .unwrap_or(24);
+    let mut svg = String::new();
+    icon.write_svg(&mut svg, size)
+        .map_err(|e| RenderErrorReason::Other(e.to_string()))?;
+    writer.write(&svg)?;
+    Ok(())
+}
+
+/// Highlights the syntax of code in the given language, or escapes it if the language is not supported
+fn highlight_helper<'reg, 'rc>(
+    helper: &handlebars::Helper<'rc>,
+    _r: &'reg Handlebars<'reg>,
+    _ctx: &'rc Context,
+    _rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    writer: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let code = match helper.param(0).map(PathAndJson::value) {
+        None | Some(JsonValue::Null) => return Ok(()),
+        Some(JsonValue::String(s)) => Cow::Borrowed(s.as_str()),
+        Some(other) => Cow::Owned(other.to_string()),
+    };
+    let language = helper
+        .param(1)
+        .and_then(|language| language.value().as_str())
+        .unwrap_or_default();
+    match crate::syntax_highlighting::highlight(&code, language) {
+        Some(html) => writer.write(&html)?,
+        None => writer.write(&handlebars::html_escape(&code))?,
+    }
+    Ok(())
+}
+
 fn typeof_helper(v: &JsonValue) -> JsonValue {
     match v {
         JsonValue::Null => "null",
@@ -335,6 +601,52 @@ fn url_encode_helper(v: &JsonValue) -> JsonValue {
         .into()
 }
 
+/// Builds a query string such as `?page=2&sort=name` from an object of URL parameters,
+/// like `@url_parameters`, followed by names and values of parameters to set.
+/// A null value removes the parameter: `{{query_string @url_parameters 'sort' 'name' 'page' null}}`.
+struct QueryStringHelper;
+
+impl CanHelp for QueryStringHelper {
+    fn call(&self, args: &[PathAndJson]) -> Result<JsonValue, String> {
+        let (parameters, changes) = args
+            .split_first()
+            .ok_or("expected an object of URL parameters")?;
+        let mut parameters = match parameters.value() {
+            JsonValue::Object(map) => map.clone(),
+            JsonValue::Null => serde_json::Map::new(),
+            _ => return Err("the URL parameters must be an object".into()),
+        };
+        if changes.len() % 2 != 0 {
+            return Err("expected pairs of parameter names and values".into());
+        }
+        for change in changes.chunks_exact(2) {
+            let name = match change[0].value() {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            match change[1].value() {
+                JsonValue::Null => parameters.shift_remove(&name),
+                value => parameters.insert(name, value.clone()),
+            };
+        }
+        let encode = |v: &JsonValue| url_encode_helper(v).as_str().unwrap_or_default().to_owned();
+        let mut query = String::new();
+        for (name, value) in &parameters {
+            let (name, values) = match value {
+                JsonValue::Array(values) => (format!("{name}[]"), values.as_slice()),
+                value => (name.clone(), std::slice::from_ref(value)),
+            };
+            for value in values {
+                query.push(if query.is_empty() { '?' } else { '&' });
+                query.push_str(&encode(&JsonValue::String(name.clone())));
+                query.push('=');
+                query.push_str(&encode(value));
+            }
+        }
+        Ok(query.into())
+    }
+}
+
 // Percent-encode a string
 fn csv_escape_helper(v: &JsonValue, separator: &JsonValue) -> JsonValue {
     let as_str = match v {
@@ -415,6 +727,53 @@ fn flush_delayed_helper<'reg, 'rc>(
     })
 }
 
+/// Iterates over the keys of an object (or the indices of an array), for components that display arbitrary columns:
+/// `{{#each_key this}}<th>{{this}}</th>{{/each_key}}`. The value is available as `@value`.
+fn each_key_helper<'reg, 'rc>(
+    h: &handlebars::Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let value = h
+        .param(0)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex("each_key", 0))?
+        .value();
+    let template = h
+        .template()
+        .ok_or(RenderErrorReason::BlockContentRequired)?;
+    let entries: Vec<(JsonValue, &JsonValue)> = match value {
+        JsonValue::Object(map) => map.iter().map(|(k, v)| (k.as_str().into(), v)).collect(),
+        JsonValue::Array(values) => values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.into(), v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if entries.is_empty() {
+        if let Some(inverse) = h.inverse() {
+            inverse.render(r, ctx, rc, out)?;
+        }
+        return Ok(());
+    }
+    let len = entries.len();
+    for (index, (key, value)) in entries.into_iter().enumerate() {
+        let mut block = handlebars::BlockContext::new();
+        block.set_base_value(key);
+        block.set_local_var("index", index.into());
+        block.set_local_var("first", (index == 0).into());
+        block.set_local_var("last", (index + 1 == len).into());
+        block.set_local_var("value", value.clone());
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result?;
+    }
+    Ok(())
+}
+
 fn sum_helper<'reg, 'rc>(
     helper: &handlebars::Helper<'rc>,
     _r: &'reg Handlebars<'reg>,
@@ -543,6 +902,20 @@ fn replace_helper(text: &JsonValue, original: &JsonValue, replacement: &JsonValu
     text_str.replace(original_str, replacement_str).into()
 }
 
+#[test]
+fn test_to_timezone_helper() {
+    let mut h = Handlebars::new();
+    register_helper(&mut h, "to_timezone", ToTimezoneHelper(chrono_tz::Tz::UTC));
+    let data = serde_json::json!({"at": "2024-07-14 10:00:00", "tz": "Europe/Paris"});
+    let render = |template: &str| h.render_template(template, &data).unwrap();
+    assert_eq!(render("{{to_timezone at}}"), "2024-07-14T10:00:00+00:00");
+    assert_eq!(render("{{to_timezone at tz \"%H:%M %Z\"}}"), "12:00 CEST");
+    assert_eq!(render("{{to_timezone tz tz}}"), "Europe/Paris");
+    assert!(h
+        .render_template("{{to_timezone at 'Nowhere'}}", &data)
+        .is_err());
+}
+
 #[test]
 fn test_rfc2822_date() {
     assert_eq!(
@@ -560,3 +933,115 @@ fn test_rfc2822_date() {
         "Fri, 02 Jan 1970 00:00:00 +0000"
     );
 }
+
+#[test]
+fn test_path_crumbs() {
+    let mut h = Handlebars::new();
+    register_helper(&mut h, "path_crumbs", PathCrumbsHelper("/app/".into()));
+    let crumbs = |path: &str| {
+        let data = serde_json::json!({ "path": path });
+        h.render_template(
+            "{{#each (path_crumbs path)}}[{{title}}|{{link}}{{#if active}}|active{{/if}}]{{/each}}",
+            &data,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        crumbs("/app/blog/2024/my-first_post.sql?id=1"),
+        "[Home|/app/][Blog|/app/blog/][2024|/app/blog/2024/][My first post|/app/blog/2024/my-first_post.sql|active]"
+    );
+    assert_eq!(
+        crumbs("/app/caf%C3%A9/index.sql"),
+        "[Home|/app/][Café|/app/caf%C3%A9/|active]"
+    );
+    assert_eq!(crumbs("/app/"), "[Home|/app/|active]");
+}
+
+#[test]
+fn test_percentage() {
+    use serde_json::json;
+    assert_eq!(percentage_helper(&json!(3), &json!(8)), json!(37.5));
+    assert_eq!(percentage_helper(&json!("2"), &json!("3")), json!(66.7));
+    assert_eq!(percentage_helper(&json!(150), &json!(100)), json!(100.0));
+    assert_eq!(percentage_helper(&json!(1), &json!(0)), JsonValue::Null);
+    assert_eq!(
+        percentage_helper(&json!("n/a"), &json!(10)),
+        JsonValue::Null
+    );
+}
+
+#[test]
+fn test_query_string() {
+    let mut h = Handlebars::new();
+    register_helper(&mut h, "query_string", QueryStringHelper);
+    let data = serde_json::json!({"params": {"page": "2", "sort": "name", "tag": ["a b", "c"]}});
+    let render = |template: &str| h.render_template(template, &data).unwrap();
+    assert_eq!(
+        render("{{{query_string params 'sort' 'age' 'dir' 'desc' 'page' null}}}"),
+        "?sort=age&tag%5B%5D=a%20b&tag%5B%5D=c&dir=desc"
+    );
+    assert_eq!(render("{{{query_string null 'q' 1}}}"), "?q=1");
+    assert_eq!(
+        render("{{{query_string params 'page' null 'sort' null 'tag' null}}}"),
+        ""
+    );
+}
+
+#[test]
+fn test_each_key() {
+    let mut h = Handlebars::new();
+    h.register_helper("each_key", Box::new(each_key_helper));
+    let data = serde_json::json!({"row": {"name": "Ophir", "age": 30}, "empty": {}});
+    let render = |template: &str| h.render_template(template, &data).unwrap();
+    assert_eq!(
+        render("{{#each_key row}}{{@index}}:{{this}}={{@value}}{{#unless @last}},{{/unless}}{{/each_key}}"),
+        "0:name=Ophir,1:age=30"
+    );
+    assert_eq!(
+        render("{{#each_key empty}}x{{else}}none{{/each_key}}"),
+        "none"
+    );
+}
+
+#[test]
+fn test_sanitize_html() {
+    let mut h = Handlebars::new();
+    h.register_helper("sanitize_html", Box::new(SanitizeHtmlHelper));
+    let data = serde_json::json!({"html": "<p onclick='x()'>Hi <b>there</b><script>alert(1)</script>", "n": 42});
+    let render = |template: &str| h.render_template(template, &data).unwrap();
+    assert_eq!(render("{{sanitize_html html}}"), "<p>Hi <b>there</b></p>");
+    assert_eq!(render("{{{sanitize_html html}}}"), "<p>Hi <b>there</b></p>");
+    assert_eq!(render("{{sanitize_html n}}"), "42");
+    assert_eq!(render("{{sanitize_html missing}}"), "");
+}
+
+#[test]
+fn test_highlight_helper() {
+    let mut h = Handlebars::new();
+    h.register_helper("highlight", Box::new(highlight_helper));
+    let data = serde_json::json!({"code": "select '<b>'", "sql": "sql", "other": "cobol"});
+    let render = |template: &str| h.render_template(template, &data).unwrap();
+    assert_eq!(
+        render("{{highlight code sql}}"),
+        "<span class=\"hljs-keyword\">select</span> <span class=\"hljs-string\">&#x27;&lt;b&gt;&#x27;</span>"
+    );
+    assert_eq!(
+        render("{{highlight code other}}"),
+        "select &#x27;&lt;b&gt;&#x27;"
+    );
+    assert_eq!(render("{{highlight code}}"), "select &#x27;&lt;b&gt;&#x27;");
+    assert_eq!(render("{{highlight missing sql}}"), "");
+}
+
+#[test]
+fn test_icon_helper_without_icon() {
+    let mut h = Handlebars::new();
+    h.register_helper("icon", Box::new(icon_helper));
+    let data = serde_json::json!({"n": 1});
+    assert_eq!(h.render_template("{{icon n}}", &data).unwrap(), "");
+    assert_eq!(
+        h.render_template("{{icon 'not-an-icon-name'}}", &data)
+            .unwrap(),
+        ""
+    );
+}
@@ -1,17 +1,268 @@
 use crate::app_config::AppConfig;
 use crate::file_cache::AsyncFromStrWithState;
+use crate::render::GRID_WIDTH_PROPERTY;
 use crate::template_helpers::register_all_helpers;
 use crate::{AppState, FileCache, TEMPLATES_DIR};
+use anyhow::Context;
 use async_trait::async_trait;
-use handlebars::{template::TemplateElement, Handlebars, Template};
+use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
+use handlebars::{Handlebars, Template};
 use include_dir::{include_dir, Dir};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 pub struct SplitTemplate {
     pub before_list: Template,
     pub list_content: Template,
     pub after_list: Template,
+    pub properties: ComponentProperties,
+    /// Whether the rows read `@last`. Each row is then rendered only when the next one arrives.
+    pub uses_last_row: bool,
+    /// Properties rendered without escaping, that do not follow the naming convention of [`is_html_property`]
+    pub unescaped_properties: Vec<UnescapedProperty>,
+}
+
+/// A property rendered without HTML escaping, with a triple-stash expression such as `{{{description}}}`.
+/// When it contains data that comes from the database, visitors may inject scripts in the page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnescapedProperty {
+    pub property: String,
+    /// The helper that receives the property, as in `{{{replace description "a" "b"}}}`
+    pub helper: Option<String>,
+}
+
+impl std::fmt::Display for UnescapedProperty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the '{}' property is rendered without escaping",
+            self.property
+        )?;
+        if let Some(helper) = &self.helper {
+            write!(f, " by the '{helper}' helper")?;
+        }
+        write!(
+            f,
+            ". Use {{{{{}}}}} instead of {{{{{{{}}}}}}}, or rename the property to '{}_html' \
+            if it only contains trusted HTML",
+            self.property, self.property, self.property
+        )
+    }
+}
+
+/// Properties whose name ends with `html`, such as `html` or `unsafe_html`, contain HTML on purpose,
+/// and are expected to be rendered without escaping.
+#[must_use]
+pub fn is_html_property(name: &str) -> bool {
+    name.ends_with("html")
+}
+
+/// Helpers whose output is safe to render without escaping, whatever their arguments:
+/// `markdown` escapes the HTML it receives, unless `markdown_allow_dangerous_html` is enabled.
+const ESCAPING_HELPERS: &[&str] = &[
+    "markdown",
+    "icon_img",
+    "icon",
+    "static_path",
+    "flush_delayed",
+    "sanitize_html",
+    "highlight",
+];
+
+/// Finds the properties that the elements render with triple-stash expressions, including inside blocks
+fn collect_unescaped_properties(elements: &[TemplateElement], found: &mut Vec<UnescapedProperty>) {
+    for element in elements {
+        match element {
+            TemplateElement::HtmlExpression(helper) => {
+                let helper_name = match &helper.name {
+                    Parameter::Name(name) if !helper.params.is_empty() => Some(name.clone()),
+                    _ => None,
+                };
+                if helper_name
+                    .as_deref()
+                    .is_some_and(|name| ESCAPING_HELPERS.contains(&name))
+                {
+                    continue;
+                }
+                let params = if helper_name.is_some() {
+                    &helper.params[..]
+                } else {
+                    std::slice::from_ref(&helper.name)
+                };
+                for param in params {
+                    let property = match param {
+                        Parameter::Name(name) => name,
+                        // `../description` or `item.description`
+                        Parameter::Path(handlebars::Path::Relative((segments, _))) => {
+                            match segments.last() {
+                                Some(handlebars::PathSeg::Named(name)) => name,
+                                _ => continue,
+                            }
+                        }
+                        _ => continue,
+                    };
+                    if !is_html_property(property) {
+                        found.push(UnescapedProperty {
+                            property: property.clone(),
+                            helper: helper_name.clone(),
+                        });
+                    }
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                for tpl in [&helper.template, &helper.inverse].into_iter().flatten() {
+                    collect_unescaped_properties(&tpl.elements, found);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The names of the properties that a component's template reads.
+/// `None` means that the template reads properties dynamically (for instance by iterating over `this`),
+/// so any property may be used.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ComponentProperties {
+    /// Properties of the row that opens the component
+    pub top_level: Option<HashSet<String>>,
+    /// Properties of the rows that follow it
+    pub row_level: Option<HashSet<String>>,
+}
+
+impl ComponentProperties {
+    fn from_templates(top_level: &[&Template], row_level: &Template) -> Self {
+        let mut top_level_collector = PropertyCollector::default();
+        let top_level_is_closed = top_level.iter().fold(true, |closed, tpl| {
+            closed & top_level_collector.collect_template(tpl, true)
+        });
+        let mut row_level_collector = PropertyCollector::default();
+        let row_level_is_closed = row_level_collector.collect_template(row_level, true);
+        // rows can read the properties of the top-level row using `../property`
+        let mut top_level_properties = top_level_collector.properties;
+        top_level_properties.extend(row_level_collector.parent_properties);
+        Self {
+            top_level: top_level_is_closed.then_some(top_level_properties),
+            row_level: row_level_is_closed.then_some(row_level_collector.properties),
+        }
+    }
+
+    /// Whether the template reads the given property.
+    /// `is_top_level` indicates whether the property belongs to the row that opened the component.
+    #[must_use]
+    pub fn is_known(&self, property: &str, is_top_level: bool) -> bool {
+        let known = if is_top_level {
+            &self.top_level
+        } else {
+            &self.row_level
+        };
+        (is_top_level
+            && (property.eq_ignore_ascii_case("component") || property == GRID_WIDTH_PROPERTY))
+            || known.as_ref().is_none_or(|known| known.contains(property))
+    }
+}
+
+/// Helpers that are used without arguments, and would otherwise be mistaken for properties
+const ARGUMENTLESS_HELPERS: &[&str] = &["delay", "flush_delayed"];
+
+/// Walks a template to find the names of the properties it reads.
+/// Names followed by arguments, as in `{{#if title}}`, are helpers and not properties.
+#[derive(Default)]
+struct PropertyCollector {
+    properties: HashSet<String>,
+    /// Properties read from a parent context, as in `{{../title}}`
+    parent_properties: HashSet<String>,
+}
+
+impl PropertyCollector {
+    /// `in_own_context` is false inside blocks such as `each` that change the current context.
+    /// Returns false if the template reads the whole current context, in which case its properties cannot be known.
+    /// The whole template is always visited, to find all the properties read from parent contexts.
+    fn collect_template(&mut self, tpl: &Template, in_own_context: bool) -> bool {
+        tpl.elements.iter().fold(true, |closed, element| {
+            closed & self.collect_element(element, in_own_context)
+        })
+    }
+
+    fn collect_element(&mut self, element: &TemplateElement, in_own_context: bool) -> bool {
+        match element {
+            TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                self.collect_helper(helper, in_own_context)
+            }
+            TemplateElement::HelperBlock(helper) => {
+                let changes_context = matches!(&helper.name, Parameter::Name(name) if name == "each" || name == "with");
+                let mut closed = self.collect_helper(helper, in_own_context);
+                if let Some(tpl) = &helper.template {
+                    closed &= self.collect_template(tpl, in_own_context && !changes_context);
+                }
+                if let Some(tpl) = &helper.inverse {
+                    closed &= self.collect_template(tpl, in_own_context);
+                }
+                closed
+            }
+            TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => [&decorator.name]
+                .into_iter()
+                .chain(&decorator.params)
+                .chain(decorator.hash.values())
+                .fold(true, |closed, param| {
+                    closed & self.collect_parameter(param, in_own_context)
+                }),
+            // partials receive the whole context
+            TemplateElement::PartialExpression(_) | TemplateElement::PartialBlock(_) => {
+                !in_own_context
+            }
+            _ => true,
+        }
+    }
+
+    fn collect_helper(&mut self, helper: &HelperTemplate, in_own_context: bool) -> bool {
+        let is_helper_call = !helper.params.is_empty()
+            || !helper.hash.is_empty()
+            || matches!(&helper.name, Parameter::Name(name) if ARGUMENTLESS_HELPERS.contains(&name.as_str()));
+        let name = (!is_helper_call).then_some(&helper.name);
+        name.into_iter()
+            .chain(&helper.params)
+            .chain(helper.hash.values())
+            .fold(true, |closed, param| {
+                closed & self.collect_parameter(param, in_own_context)
+            })
+    }
+
+    fn collect_parameter(&mut self, param: &Parameter, in_own_context: bool) -> bool {
+        match param {
+            Parameter::Name(name) => {
+                self.properties.insert(name.clone());
+                true
+            }
+            Parameter::Path(handlebars::Path::Relative((segments, _))) => {
+                let mut reads_parent = false;
+                for segment in segments {
+                    match segment {
+                        handlebars::PathSeg::Named(name) => {
+                            self.properties.insert(name.clone());
+                            if reads_parent {
+                                self.parent_properties.insert(name.clone());
+                            }
+                            return true;
+                        }
+                        // `..` or `@root`
+                        _ => reads_parent = true,
+                    }
+                }
+                // `this`
+                !in_own_context
+            }
+            Parameter::Subexpression(subexpression) => {
+                self.collect_element(&subexpression.element, in_own_context)
+            }
+            _ => true,
+        }
+    }
 }
 
 impl SplitTemplate {
@@ -43,26 +294,204 @@ pub fn split_template(mut original: Template) -> SplitTemplate {
     after_list.name = Some(format!("{original_name} after each block"));
     after_list.elements = elements_after;
     after_list.mapping = mapping_after;
+    let properties =
+        ComponentProperties::from_templates(&[&before_list, &after_list], &list_content);
+    let uses_last_row = reads_local_var(&list_content.elements, "last");
+    let mut unescaped_properties = Vec::new();
+    for tpl in [&before_list, &list_content, &after_list] {
+        collect_unescaped_properties(&tpl.elements, &mut unescaped_properties);
+    }
     SplitTemplate {
         before_list,
         list_content,
         after_list,
+        properties,
+        uses_last_row,
+        unescaped_properties,
+    }
+}
+
+/// Whether the elements may read a local variable such as `@last`.
+/// The contents of `each` blocks, which define their own local variables, are skipped.
+/// Partials are not visited, so they are assumed to read it.
+fn reads_local_var(elements: &[TemplateElement], var: &str) -> bool {
+    fn in_parameter(param: &Parameter, var: &str) -> bool {
+        match param {
+            Parameter::Path(handlebars::Path::Local((_, name, _))) => name == var,
+            Parameter::Subexpression(subexpression) => {
+                reads_local_var(std::slice::from_ref(&subexpression.element), var)
+            }
+            _ => false,
+        }
+    }
+    fn in_helper(helper: &HelperTemplate, var: &str) -> bool {
+        let is_loop =
+            matches!(&helper.name, Parameter::Name(name) if name == "each" || name == "each_key");
+        let block = helper.template.as_ref().filter(|_| !is_loop);
+        [&helper.name]
+            .into_iter()
+            .chain(&helper.params)
+            .chain(helper.hash.values())
+            .any(|param| in_parameter(param, var))
+            || [block, helper.inverse.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|tpl| reads_local_var(&tpl.elements, var))
+    }
+    elements.iter().any(|element| match element {
+        TemplateElement::Expression(helper)
+        | TemplateElement::HtmlExpression(helper)
+        | TemplateElement::HelperBlock(helper) => in_helper(helper, var),
+        TemplateElement::PartialExpression(_) | TemplateElement::PartialBlock(_) => true,
+        _ => false,
+    })
+}
+
+/// Compiles the source of a component, and splits it around its `each_row` block.
+pub fn compile_component(source: &str, name: String) -> anyhow::Result<SplitTemplate> {
+    let mut tpl = Template::compile_with_name(source, name)?;
+    inline_builtin_partials(&mut tpl)?;
+    Ok(split_template(tpl))
+}
+
+/// Prefix of the partials that insert a built-in component, as in `{{> builtin/table}}`
+const BUILTIN_PARTIAL_PREFIX: &str = "builtin/";
+
+/// Replaces the `{{> builtin/component}}` partials at the top level of a template
+/// with the contents of the built-in component, including its `each_row` block.
+/// This lets a custom component wrap the built-in component it overrides.
+fn inline_builtin_partials(tpl: &mut Template) -> anyhow::Result<()> {
+    let elements = std::mem::take(&mut tpl.elements);
+    let mapping = std::mem::take(&mut tpl.mapping);
+    for (element, element_mapping) in elements.into_iter().zip(mapping) {
+        let builtin_name = match &element {
+            TemplateElement::PartialExpression(partial) => match &partial.name {
+                Parameter::Name(name) => name.strip_prefix(BUILTIN_PARTIAL_PREFIX),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(builtin_name) = builtin_name {
+            let file = STATIC_TEMPLATES
+                .get_file(format!("{builtin_name}.handlebars"))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("There is no built-in component named {builtin_name:?}")
+                })?;
+            let builtin = Template::compile_with_name(
+                String::from_utf8_lossy(file.contents()),
+                builtin_name.to_string(),
+            )?;
+            tpl.elements.extend(builtin.elements);
+            tpl.mapping.extend(builtin.mapping);
+        } else {
+            tpl.elements.push(element);
+            tpl.mapping.push(element_mapping);
+        }
     }
+    Ok(())
 }
 
 #[async_trait(? Send)]
 impl AsyncFromStrWithState for SplitTemplate {
     async fn from_str_with_state(
-        _app_state: &AppState,
+        app_state: &AppState,
         source: &str,
         source_path: &Path,
     ) -> anyhow::Result<Self> {
-        log::debug!("Compiling template {:?}", source_path);
-        let tpl = Template::compile_with_name(source, "SQLPage component".to_string())?;
-        Ok(split_template(tpl))
+        let name = source_path
+            .file_stem()
+            .map_or_else(|| "SQLPage component".into(), |stem| stem.to_string_lossy());
+        let start = Instant::now();
+        let template = compile_component(source, name.into_owned());
+        let duration = start.elapsed();
+        app_state.all_templates.stats.record_compilation(duration);
+        log::debug!("Compiled template {source_path:?} in {duration:?}");
+        if let Ok(template) = &template {
+            if !app_state.config().environment.is_prod() {
+                for unescaped in &template.unescaped_properties {
+                    log::warn!("In {source_path:?}, {unescaped}");
+                }
+            }
+        }
+        template
+    }
+}
+
+/// Counters about the compilation and rendering of the templates, since the start of the server
+#[derive(Debug, Default)]
+pub struct TemplateStats {
+    compilations: AtomicU64,
+    compilation_micros: AtomicU64,
+    renders: Mutex<BTreeMap<String, ComponentRenderStats>>,
+}
+
+/// Number of times a component was rendered in a page, and the total time spent rendering it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentRenderStats {
+    pub renders: u64,
+    pub time: Duration,
+}
+
+impl ComponentRenderStats {
+    /// Average time spent rendering the component in a page, including all of its rows
+    #[must_use]
+    pub fn average_time(&self) -> Duration {
+        let renders = u32::try_from(self.renders).unwrap_or(u32::MAX).max(1);
+        self.time / renders
+    }
+}
+
+impl TemplateStats {
+    fn record_compilation(&self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.compilations.fetch_add(1, Relaxed);
+        self.compilation_micros.fetch_add(micros, Relaxed);
+    }
+
+    /// Number of templates compiled, including the ones compiled again after a change or an eviction from the cache
+    #[must_use]
+    pub fn compilations(&self) -> u64 {
+        self.compilations.load(Relaxed)
+    }
+
+    /// Total time spent compiling templates
+    #[must_use]
+    pub fn compilation_time(&self) -> Duration {
+        Duration::from_micros(self.compilation_micros.load(Relaxed))
+    }
+
+    pub(crate) fn record_render(&self, component: &str, duration: Duration) {
+        let mut renders = self.renders.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(stats) = renders.get_mut(component) {
+            stats.renders += 1;
+            stats.time += duration;
+        } else {
+            let stats = ComponentRenderStats {
+                renders: 1,
+                time: duration,
+            };
+            renders.insert(component.to_string(), stats);
+        }
+    }
+
+    /// Rendering statistics of each component that was used, sorted by component name
+    #[must_use]
+    pub fn render_times(&self) -> Vec<(String, ComponentRenderStats)> {
+        let renders = self.renders.lock().unwrap_or_else(PoisonError::into_inner);
+        renders
+            .iter()
+            .map(|(name, stats)| (name.clone(), *stats))
+            .collect()
     }
 }
 
+fn handlebars_file_stem(path: &Path) -> Option<String> {
+    if path.extension()? != "handlebars" {
+        return None;
+    }
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
+
 fn is_template_list_item(element: &TemplateElement) -> bool {
     use handlebars::template::Parameter;
     use Parameter::Name;
@@ -71,10 +500,32 @@ fn is_template_list_item(element: &TemplateElement) -> bool {
                         if matches!(&tpl.name, Name(name) if name == "each_row"))
 }
 
+/// Where the template of a component comes from, in decreasing order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ComponentSource {
+    /// The `templates` folder of the configuration directory
+    Site,
+    /// One of the `plugin_template_directories`
+    Plugin,
+    /// The components embedded in `SQLPage`
+    Builtin,
+}
+
+impl std::fmt::Display for ComponentSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Site => "site",
+            Self::Plugin => "plugin",
+            Self::Builtin => "built-in",
+        })
+    }
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct AllTemplates {
     pub handlebars: Handlebars<'static>,
-    split_templates: FileCache<SplitTemplate>,
+    pub(crate) split_templates: FileCache<SplitTemplate>,
+    pub stats: TemplateStats,
 }
 
 const STATIC_TEMPLATES: Dir = include_dir!("$CARGO_MANIFEST_DIR/sqlpage/templates");
@@ -85,37 +536,131 @@ impl AllTemplates {
         register_all_helpers(&mut handlebars, config);
         let mut this = Self {
             handlebars,
-            split_templates: FileCache::new(),
+            split_templates: FileCache::with_max_entries(config.max_cached_templates),
+            stats: TemplateStats::default(),
         };
         this.preregister_static_templates()?;
+        this.preregister_plugin_templates(&config.plugin_template_directories)?;
         Ok(this)
     }
 
-    /// Embeds pre-defined templates directly in the binary in release mode
+    /// Embeds pre-defined templates directly in the binary in release mode.
+    /// They are compiled the first time they are used.
     pub fn preregister_static_templates(&mut self) -> anyhow::Result<()> {
         for file in STATIC_TEMPLATES.files() {
             let mut path = PathBuf::from(TEMPLATES_DIR);
             path.push(file.path());
-            let name = file
-                .path()
-                .file_stem()
-                .unwrap()
-                .to_string_lossy()
-                .to_string();
-            let source = String::from_utf8_lossy(file.contents());
-            let tpl = Template::compile_with_name(&source, name)?;
-            let split_template = split_template(tpl);
-            self.split_templates.add_static(path, split_template);
+            let source = file.contents_utf8().with_context(|| {
+                format!(
+                    "The built-in template {} is not valid UTF-8",
+                    path.display()
+                )
+            })?;
+            self.split_templates
+                .add_static_source(path, Cow::Borrowed(source));
         }
         Ok(())
     }
 
+    /// Reads the components of the `plugin_template_directories` at startup,
+    /// and compiles them the first time they are used.
+    /// They replace the built-in components with the same name,
+    /// and are themselves overridden by the templates of the site.
+    /// When several plugins define the same component, the first one in the list wins.
+    fn preregister_plugin_templates(&mut self, directories: &[PathBuf]) -> anyhow::Result<()> {
+        for directory in directories.iter().rev() {
+            let entries = std::fs::read_dir(directory).with_context(|| {
+                format!(
+                    "Unable to read the plugin templates directory {}",
+                    directory.display()
+                )
+            })?;
+            for entry in entries {
+                let plugin_path = entry?.path();
+                if plugin_path.extension() != Some("handlebars".as_ref()) {
+                    continue;
+                }
+                let Some(file_name) = plugin_path.file_name() else {
+                    continue;
+                };
+                log::debug!("Loading plugin component {plugin_path:?}");
+                let source = std::fs::read_to_string(&plugin_path)
+                    .with_context(|| format!("Unable to read {}", plugin_path.display()))?;
+                self.split_templates.add_static_source(
+                    Path::new(TEMPLATES_DIR).join(file_name),
+                    Cow::Owned(source),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads a component without querying the database:
+    /// from the local templates directory, from the plugin template directories,
+    /// or from the components built into `SQLPage`, in this order of precedence.
+    /// Returns `None` if the component does not exist in any of these places.
+    pub fn get_builtin_or_local_template(
+        config: &AppConfig,
+        name: &str,
+    ) -> anyhow::Result<Option<SplitTemplate>> {
+        let file_name = format!("{name}.handlebars");
+        let local_path = std::iter::once(config.configuration_directory.join("templates"))
+            .chain(config.plugin_template_directories.iter().cloned())
+            .map(|directory| directory.join(&file_name))
+            .find(|path| path.is_file());
+        let source = if let Some(local_path) = local_path {
+            std::fs::read_to_string(&local_path)?
+        } else if let Some(file) = STATIC_TEMPLATES.get_file(&file_name) {
+            String::from_utf8_lossy(file.contents()).into_owned()
+        } else {
+            return Ok(None);
+        };
+        compile_component(&source, name.to_string()).map(Some)
+    }
+
+    /// Lists the names of the components available on the local filesystem, with the place
+    /// their template is loaded from. Components stored in the `sqlpage_files` table are not listed.
+    pub fn list_components(
+        config: &AppConfig,
+    ) -> anyhow::Result<BTreeMap<String, ComponentSource>> {
+        let mut components = BTreeMap::new();
+        for file in STATIC_TEMPLATES.files() {
+            if let Some(name) = handlebars_file_stem(file.path()) {
+                components.insert(name, ComponentSource::Builtin);
+            }
+        }
+        let directories = config
+            .plugin_template_directories
+            .iter()
+            .map(|directory| (directory.clone(), ComponentSource::Plugin))
+            .chain(std::iter::once((
+                config.configuration_directory.join("templates"),
+                ComponentSource::Site,
+            )));
+        for (directory, source) in directories {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                log::debug!("Not listing the components of {directory:?}, which cannot be read");
+                continue;
+            };
+            for entry in entries {
+                if let Some(name) = handlebars_file_stem(&entry?.path()) {
+                    components
+                        .entry(name)
+                        .and_modify(|existing: &mut ComponentSource| {
+                            *existing = (*existing).min(source);
+                        })
+                        .or_insert(source);
+                }
+            }
+        }
+        Ok(components)
+    }
+
     pub async fn get_template(
         &self,
         app_state: &AppState,
         name: &str,
     ) -> anyhow::Result<Arc<SplitTemplate>> {
-        use anyhow::Context;
         let mut path: PathBuf =
             PathBuf::with_capacity(TEMPLATES_DIR.len() + 1 + name.len() + ".handlebars".len());
         path.push(TEMPLATES_DIR);
@@ -149,4 +694,131 @@ fn test_split_template() {
         split.after_list.elements,
         Template::compile("end").unwrap().elements
     );
+    assert!(!split.uses_last_row);
+    let template =
+        Template::compile("{{#each_row}}{{#each this}}{{@last}}{{/each}}{{/each_row}}").unwrap();
+    assert!(!split_template(template).uses_last_row);
+    let template =
+        Template::compile("{{#each_row}}{{#if (not @last)}}, {{/if}}{{/each_row}}").unwrap();
+    assert!(split_template(template).uses_last_row);
+}
+
+#[test]
+fn test_inline_builtin_partials() {
+    let split = compile_component(
+        "<div class=\"wrapper\">{{> builtin/list}}</div>",
+        "list".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        split.before_list.elements.first(),
+        Template::compile("<div class=\"wrapper\">")
+            .unwrap()
+            .elements
+            .first()
+    );
+    assert!(!split.list_content.elements.is_empty());
+    assert_eq!(
+        split.after_list.elements.last(),
+        Template::compile("</div>").unwrap().elements.first()
+    );
+    assert!(!split.properties.is_known("not_a_list_property", true));
+
+    let Err(err) = compile_component("{{> builtin/not_a_component}}", "x".to_string()) else {
+        panic!("expected an error for an unknown built-in component");
+    };
+    assert!(err.to_string().contains("not_a_component"), "{err}");
+}
+
+#[test]
+fn test_component_properties() {
+    let template = Template::compile(
+        "{{#if title}}<h1>{{default title (concat prefix '!')}}</h1>{{/if}}\
+        {{#each_row}}\
+            <li class=\"{{../class}}\">{{name}}{{#each tags}}{{this}}{{/each}}</li>\
+        {{/each_row}}\
+        {{footer}}",
+    )
+    .unwrap();
+    let properties = split_template(template).properties;
+    for known_top_level in ["component", "title", "prefix", "class", "footer"] {
+        assert!(
+            properties.is_known(known_top_level, true),
+            "{known_top_level}"
+        );
+    }
+    assert!(!properties.is_known("titel", true));
+    assert!(!properties.is_known("component", false));
+    for known_row_level in ["name", "tags"] {
+        assert!(
+            properties.is_known(known_row_level, false),
+            "{known_row_level}"
+        );
+    }
+    assert!(!properties.is_known("nmae", false));
+
+    let template =
+        Template::compile("{{#each_row}}{{#each this}}{{@key}}{{/each}}{{/each_row}}").unwrap();
+    let properties = split_template(template).properties;
+    assert_eq!(properties.row_level, None);
+    assert!(properties.is_known("anything", false));
+}
+
+#[test]
+fn test_unescaped_properties() {
+    let split = compile_component(
+        "{{{title}}}{{{markdown description}}}{{#each_row}}{{#if x}}{{{replace ../label 'a' 'b'}}}{{/if}}\
+        {{{html}}}{{{unsafe_html}}}{{text}}{{/each_row}}",
+        "x".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        split.unescaped_properties,
+        [
+            UnescapedProperty {
+                property: "title".into(),
+                helper: None
+            },
+            UnescapedProperty {
+                property: "label".into(),
+                helper: Some("replace".into())
+            },
+        ]
+    );
+    assert!(split.unescaped_properties[0]
+        .to_string()
+        .contains("Use {{title}} instead of {{{title}}}, or rename the property to 'title_html'"));
+}
+
+#[test]
+fn test_builtin_templates_escape_their_properties() {
+    for file in STATIC_TEMPLATES.files() {
+        let name = file.path().to_string_lossy().to_string();
+        let source = file.contents_utf8().unwrap();
+        let split = compile_component(source, name.clone()).unwrap();
+        assert_eq!(split.unescaped_properties, [], "{name}");
+    }
+}
+
+#[test]
+fn test_all_builtin_templates_compile() {
+    for file in STATIC_TEMPLATES.files() {
+        let Some(name) = handlebars_file_stem(file.path()) else {
+            continue;
+        };
+        let source = file.contents_utf8().unwrap();
+        compile_component(source, name).unwrap();
+    }
+}
+
+#[actix_web::test]
+async fn test_templates_are_compiled_lazily() {
+    let config = crate::app_config::tests::test_config();
+    let app_state = AppState::init(&config).await.unwrap();
+    let templates = &app_state.all_templates;
+    assert_eq!(templates.stats.compilations(), 0);
+    let list = templates.get_template(&app_state, "list").await.unwrap();
+    assert_eq!(list.name(), Some("list before each block"));
+    templates.get_template(&app_state, "list").await.unwrap();
+    assert_eq!(templates.stats.compilations(), 1);
 }
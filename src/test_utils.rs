@@ -0,0 +1,363 @@
+//! Helpers to test `SQLPage` sites and components, available with the `test-utils` feature.
+//!
+//! Components are rendered exactly as they would be in a page,
+//! from a row of top-level properties and a list of item rows,
+//! and the resulting HTML can be compared to a snapshot stored in a file.
+//! Whole sites can be tested end-to-end over HTTP with a [`TestServer`].
+//!
+//! ```rust,ignore
+//! use serde_json::json;
+//! use sqlpage::test_utils::{app_state_for_tests, assert_snapshot, render_component, TestServer};
+//!
+//! #[actix_web::test]
+//! async fn test_my_component() {
+//!     let app_state = app_state_for_tests("./sqlpage").await.unwrap();
+//!     let html = render_component(
+//!         &app_state,
+//!         "my_component",
+//!         json!({"title": "Users"}),
+//!         vec![json!({"name": "Ophir"}), json!({"name": "Lovasoa"})],
+//!     )
+//!     .await
+//!     .unwrap();
+//!     assert_snapshot("tests/snapshots/my_component.html", &html);
+//! }
+//!
+//! #[actix_web::test]
+//! async fn test_my_site() {
+//!     let server = TestServer::start("./my_site").await.unwrap();
+//!     let response = awc::Client::default()
+//!         .get(server.url("/index.sql"))
+//!         .send()
+//!         .await
+//!         .unwrap();
+//!     assert_eq!(response.status(), 200);
+//! }
+//! ```
+
+use crate::app_config::{self, AppConfig};
+use crate::render::SplitTemplateRenderer;
+use crate::webserver::{self, http::bind_server, Database};
+use crate::AppState;
+use actix_web::dev::ServerHandle;
+use actix_web::web;
+use anyhow::Context;
+use serde_json::Value as JsonValue;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Environment variable that makes [`assert_snapshot`] overwrite the snapshots instead of comparing them.
+pub const UPDATE_SNAPSHOTS_ENV_VAR: &str = "SQLPAGE_UPDATE_SNAPSHOTS";
+
+/// Creates an application state that uses the components of the given configuration directory,
+/// and a temporary in-memory `SQLite` database.
+pub async fn app_state_for_tests(
+    configuration_directory: impl Into<PathBuf>,
+) -> anyhow::Result<Arc<AppState>> {
+    let configuration_directory = configuration_directory.into();
+    let mut config = app_config::load_from_directory(&configuration_directory)?;
+    config.database_url = "sqlite::memory:".to_string();
+    app_state_from_config(&config).await
+}
+
+/// Creates an application state from a configuration, for tests that need specific settings.
+pub async fn app_state_from_config(config: &AppConfig) -> anyhow::Result<Arc<AppState>> {
+    Ok(Arc::new(AppState::init(config).await?))
+}
+
+/// Renders a component with the given top-level properties and rows, and returns the generated HTML.
+/// The component is looked up like in a page: in the site's templates, then in the plugins, then in the built-in components.
+pub async fn render_component(
+    app_state: &Arc<AppState>,
+    component: &str,
+    top_level: JsonValue,
+    rows: impl IntoIterator<Item = JsonValue>,
+) -> anyhow::Result<String> {
+    let split_template = app_state
+        .all_templates
+        .get_template(app_state, component)
+        .await?;
+    let mut output = Vec::new();
+    SplitTemplateRenderer::new(split_template, Arc::clone(app_state), 0, 0)
+        .render_all(&mut output, top_level, rows)
+        .with_context(|| format!("Unable to render the '{component}' component"))?;
+    String::from_utf8(output).context("The component generated invalid UTF-8")
+}
+
+/// A complete `SQLPage` server, listening on a random local port, for end-to-end tests.
+/// It uses a new empty `SQLite` database, which is deleted when the server is dropped.
+/// The server must be started from an actix runtime, for instance in an `#[actix_web::test]`.
+pub struct TestServer {
+    address: SocketAddr,
+    handle: ServerHandle,
+    database_path: PathBuf,
+}
+
+impl TestServer {
+    /// Starts a server for the given web root.
+    /// The configuration is loaded from its `sqlpage` directory, if there is one,
+    /// and the migrations it contains are applied to the new database.
+    pub async fn start(web_root: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let web_root = web_root.into();
+        let mut config = app_config::load_from_directory(&web_root.join("sqlpage"))?;
+        config.web_root = web_root;
+        Self::start_with_config(config).await
+    }
+
+    /// Starts a server with the given configuration, including its HTTP server settings.
+    /// Its `database_url` and listening address are replaced, and it uses a single worker by default.
+    pub async fn start_with_config(mut config: AppConfig) -> anyhow::Result<Self> {
+        let database_path =
+            std::env::temp_dir().join(format!("sqlpage-test-{:016x}.db", rand::random::<u64>()));
+        config.database_url = format!("sqlite://{}?mode=rwc", database_path.display());
+        let db = Database::init(&config).await?;
+        webserver::database::migrations::apply(&config, &db).await?;
+        let state = web::Data::new(AppState::init_with_db(&config, db).await?);
+        config.listen_on = Some(SocketAddr::from(([127, 0, 0, 1], 0)));
+        config.port = None;
+        config.unix_socket = None;
+        config.https_domain = None;
+        config.http_workers.get_or_insert(1);
+        let (server, addresses) = bind_server(&config, state)
+            .context("Unable to bind the test server to a local port")?;
+        let address = *addresses
+            .first()
+            .context("The test server is not listening on any address")?;
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+        log::debug!("Test server listening on {address}");
+        Ok(Self {
+            address,
+            handle,
+            database_path,
+        })
+    }
+
+    /// The address the server listens on
+    #[must_use]
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The full URL of a path on the server, such as `http://127.0.0.1:41234/index.sql`
+    #[must_use]
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.address, path.trim_start_matches('/'))
+    }
+
+    /// Stops the server, waiting for the requests in progress to complete.
+    pub async fn stop(self) {
+        self.handle.stop(true).await;
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // The stop command is sent immediately, the returned future only waits for its completion
+        drop(self.handle.stop(false));
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.database_path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Compares the given text to the contents of a snapshot file.
+/// If the file does not exist, or if the `SQLPAGE_UPDATE_SNAPSHOTS` environment variable is set,
+/// the file is written instead, and the assertion passes.
+///
+/// # Panics
+/// Panics if the text is different from the snapshot, or if the snapshot cannot be read or written.
+pub fn assert_snapshot(snapshot_path: impl AsRef<Path>, actual: &str) {
+    let snapshot_path = snapshot_path.as_ref();
+    let displayed_path = snapshot_path.display();
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV_VAR).is_some() || !snapshot_path.exists() {
+        if let Some(parent) = snapshot_path.parent() {
+            std::fs::create_dir_all(parent)
+                .unwrap_or_else(|e| panic!("Unable to create {}: {e}", parent.display()));
+        }
+        std::fs::write(snapshot_path, actual)
+            .unwrap_or_else(|e| panic!("Unable to write the snapshot {displayed_path}: {e}"));
+        return;
+    }
+    let expected = std::fs::read_to_string(snapshot_path)
+        .unwrap_or_else(|e| panic!("Unable to read the snapshot {displayed_path}: {e}"));
+    assert!(
+        expected == actual,
+        "The output does not match the snapshot {displayed_path}.\n\
+        Run the tests with {UPDATE_SNAPSHOTS_ENV_VAR}=1 to update it.\n\
+        --- expected ---\n{expected}\n--- actual ---\n{actual}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SNAPSHOTS_DIR: &str = "tests/snapshots";
+
+    async fn test_app_state() -> Arc<AppState> {
+        app_state_from_config(&app_config::tests::test_config())
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_builtin_list() {
+        let html = render_component(
+            &test_app_state().await,
+            "list",
+            json!({"title": "Users"}),
+            vec![
+                json!({"title": "Ophir", "description": "Developer"}),
+                json!({"title": "Lovasoa", "link": "/users/lovasoa"}),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_snapshot(Path::new(SNAPSHOTS_DIR).join("list.html"), &html);
+    }
+
+    #[actix_web::test]
+    async fn test_builtin_alert() {
+        let html = render_component(
+            &test_app_state().await,
+            "alert",
+            json!({"title": "Warning", "description": "The database is read-only.", "color": "red"}),
+            vec![],
+        )
+        .await
+        .unwrap();
+        assert_snapshot(Path::new(SNAPSHOTS_DIR).join("alert.html"), &html);
+    }
+
+    #[actix_web::test]
+    async fn test_server() {
+        let mut config = app_config::tests::test_config();
+        config.web_root = PathBuf::from("tests");
+        let server = TestServer::start_with_config(config).await.unwrap();
+        let client = awc::Client::default();
+        let mut response = client
+            .get(server.url("/sql_test_files/it_works_simple.sql"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+        let body = response.body().await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("It works !"), "{body}");
+        let mut response = client.get(server.url("it_works.txt")).send().await.unwrap();
+        assert_eq!(response.body().await.unwrap(), "It works !");
+        server.stop().await;
+    }
+
+    /// Sends raw bytes to the server, and reads everything it answers until it closes the connection.
+    async fn raw_http_exchange(server: &TestServer, request: &'static [u8]) -> String {
+        use std::io::{Read, Write};
+        let address = server.address();
+        // Blocking IO happens in a separate thread, so that the server keeps being polled
+        actix_web::rt::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+                .unwrap();
+            stream.write_all(request).unwrap();
+            let mut response = Vec::new();
+            stream
+                .read_to_end(&mut response)
+                .expect("the server should close the connection");
+            String::from_utf8_lossy(&response).into_owned()
+        })
+        .await
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_server_http_settings() {
+        let mut config = app_config::tests::test_config();
+        config.web_root = PathBuf::from("tests");
+        config.http_workers = Some(2);
+        config.http_backlog = Some(16);
+        config.http_keep_alive_seconds = Some(0.0);
+        config.http_client_request_timeout_seconds = Some(0.2);
+        let server = TestServer::start_with_config(config).await.unwrap();
+
+        let response = raw_http_exchange(
+            &server,
+            b"GET /it_works.txt HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(
+            response.to_ascii_lowercase().contains("connection: close"),
+            "keep-alive should be disabled: {response}"
+        );
+
+        let response = raw_http_exchange(&server, b"GET /it_works.txt HTTP/1.1\r\n").await;
+        assert!(
+            response.starts_with("HTTP/1.1 408"),
+            "incomplete requests should time out: {response}"
+        );
+        server.stop().await;
+    }
+
+    #[actix_web::test]
+    async fn test_https_negotiates_http2() {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+        let certificate = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let certificate_der = CertificateDer::from(certificate.serialize_der().unwrap());
+        let private_key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            certificate.serialize_private_key_der(),
+        ));
+        let server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![certificate_der.clone()], private_key)
+            .unwrap();
+
+        let state = web::Data::from(test_app_state().await);
+        let server = actix_web::HttpServer::new(move || {
+            crate::webserver::http::create_app(web::Data::clone(&state))
+        })
+        .workers(1)
+        .bind_rustls_0_22(("127.0.0.1", 0), server_config)
+        .unwrap();
+        let address = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(certificate_der).unwrap();
+        let mut client_config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        let client = awc::Client::builder()
+            .connector(awc::Connector::new().rustls_0_22(Arc::new(client_config)))
+            .finish();
+        let response = client
+            .get(format!("https://localhost:{}/", address.port()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.version(), actix_web::http::Version::HTTP_2);
+        handle.stop(false).await;
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_component() {
+        let err = render_component(
+            &test_app_state().await,
+            "not_a_component",
+            json!({}),
+            vec![],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not_a_component"), "{err:#}");
+    }
+}
@@ -0,0 +1,999 @@
+//! Administration pages, under `/sqlpage/admin/`.
+//!
+//! They are disabled unless `admin_password_hash` is set in the configuration,
+//! and are protected by HTTP basic authentication, with the user name `admin`.
+//! The tables page lists the tables of the database with their row counts,
+//! and shows the columns of a table when its name is given in the `table` query parameter.
+//! The metrics page exposes the runtime statistics of the server in the Prometheus text format.
+//! Sending a POST request to the reload-config page reloads the configuration of the server.
+//! The version page, at `/sqlpage/version`, returns the details of the build as JSON.
+//! When `admin_sql_console` is enabled, the SQL console page runs the queries typed by the administrator.
+//! When `admin_file_manager_directory` is set, the files page lets the administrator browse, upload,
+//! rename, and delete the files of that directory of the web root.
+
+use crate::build_info::BuildInfo;
+use crate::render::{verify_password_async, HtmlRenderContext};
+use crate::webserver::config_reload::reload_config;
+use crate::webserver::content_security_policy::ContentSecurityPolicy;
+use crate::webserver::database::console::{run_console_query, MAX_CONSOLE_ROWS};
+use crate::webserver::database::schema::{self, Table};
+use crate::webserver::http::RequestContext;
+use crate::webserver::metrics::RuntimeMetrics;
+use crate::webserver::timezone;
+use crate::webserver::ErrorWithStatus;
+use crate::{AppConfig, AppState};
+use actix_multipart::form::tempfile::TempFile;
+use actix_multipart::form::{MultipartForm, MultipartFormConfig};
+use actix_web::http::header::{self, ContentType, Header};
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, Resource, ResponseError, Scope};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use anyhow::Context;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const ADMIN_TABLES_PATH: &str = "/sqlpage/admin/tables";
+pub const ADMIN_SQL_CONSOLE_PATH: &str = "/sqlpage/admin/sql";
+pub const ADMIN_METRICS_PATH: &str = "/sqlpage/admin/metrics";
+pub const ADMIN_RELOAD_CONFIG_PATH: &str = "/sqlpage/admin/reload-config";
+pub const ADMIN_VERSION_PATH: &str = "/sqlpage/version";
+pub const ADMIN_FILES_PATH: &str = "/sqlpage/admin/files";
+const ADMIN_USER_NAME: &str = "admin";
+
+/// Characters that are percent-encoded in the file names and paths of the file manager links
+const FILE_NAME_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Deserialize)]
+struct TablesQuery {
+    schema: Option<String>,
+    table: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ConsoleForm {
+    sql: String,
+}
+
+#[derive(Deserialize)]
+struct FilesQuery {
+    #[serde(default)]
+    dir: String,
+}
+
+#[derive(Deserialize)]
+struct RenameForm {
+    name: String,
+    new_name: String,
+}
+
+#[derive(Deserialize)]
+struct DeleteForm {
+    name: String,
+}
+
+#[derive(MultipartForm)]
+struct UploadForm {
+    file: TempFile,
+}
+
+pub fn admin_tables() -> Resource {
+    web::resource(ADMIN_TABLES_PATH).to(
+        |req: HttpRequest, app_state: web::Data<AppState>, query: web::Query<TablesQuery>| async move {
+            if let Err(response) = authenticate(&req, &app_state.config()).await {
+                return response;
+            }
+            let app_state = app_state.into_inner();
+            let rows = if let Some(table) = &query.table {
+                table_rows(&app_state, query.schema.as_deref(), table).await
+            } else {
+                tables_list_rows(&app_state).await
+            };
+            render_admin_page(app_state, rows).await
+        },
+    )
+}
+
+pub fn admin_sql_console() -> Resource {
+    web::resource(ADMIN_SQL_CONSOLE_PATH)
+        .route(web::get().to(
+            |req: HttpRequest, app_state: web::Data<AppState>| async move {
+                sql_console_page(&req, app_state, None).await
+            },
+        ))
+        .route(web::post().to(
+            |req: HttpRequest, app_state: web::Data<AppState>, form: web::Form<ConsoleForm>| async move {
+                sql_console_page(&req, app_state, Some(form.into_inner().sql)).await
+            },
+        ))
+}
+
+pub fn admin_metrics() -> Resource {
+    web::resource(ADMIN_METRICS_PATH).to(
+        |req: HttpRequest, app_state: web::Data<AppState>| async move {
+            if let Err(response) = authenticate(&req, &app_state.config()).await {
+                return response;
+            }
+            HttpResponse::Ok()
+                .content_type("text/plain; version=0.0.4; charset=utf-8")
+                .body(RuntimeMetrics::collect(&app_state).to_prometheus())
+        },
+    )
+}
+
+pub fn admin_version() -> Resource {
+    web::resource(ADMIN_VERSION_PATH).route(web::get().to(
+        |req: HttpRequest, app_state: web::Data<AppState>| async move {
+            if let Err(response) = authenticate(&req, &app_state.config()).await {
+                return response;
+            }
+            HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(BuildInfo::get().to_json())
+        },
+    ))
+}
+
+pub fn admin_reload_config() -> Resource {
+    web::resource(ADMIN_RELOAD_CONFIG_PATH).route(web::post().to(
+        |req: HttpRequest, app_state: web::Data<AppState>| async move {
+            if let Err(response) = authenticate(&req, &app_state.config()).await {
+                return response;
+            }
+            if !is_same_origin(&req) {
+                return HttpResponse::Forbidden()
+                    .body("The configuration cannot be reloaded from another site");
+            }
+            match reload_config(&app_state) {
+                Ok(needs_restart) if needs_restart.is_empty() => {
+                    HttpResponse::Ok().body("The configuration was reloaded.\n")
+                }
+                Ok(needs_restart) => HttpResponse::Ok().body(format!(
+                    "The configuration was reloaded. These settings only take effect after a restart: {}\n",
+                    needs_restart.join(", ")
+                )),
+                Err(e) => {
+                    log::error!("{e:#}");
+                    HttpResponse::InternalServerError()
+                        .body("Unable to reload the configuration. The details were logged.\n")
+                }
+            }
+        },
+    ))
+}
+
+pub fn admin_file_manager(config: &AppConfig) -> Scope {
+    web::scope(ADMIN_FILES_PATH)
+        .app_data(MultipartFormConfig::default().total_limit(config.max_uploaded_file_size))
+        .route(
+            "",
+            web::get().to(
+                |req: HttpRequest,
+                 app_state: web::Data<AppState>,
+                 query: web::Query<FilesQuery>| async move {
+                    match file_manager_directory(&req, &app_state.config()).await {
+                        Ok(base) => {
+                            file_manager_page(app_state.into_inner(), &base, &query.dir, None).await
+                        }
+                        Err(response) => response,
+                    }
+                },
+            ),
+        )
+        .route(
+            "/upload",
+            web::post().to(
+                |req: HttpRequest,
+                 app_state: web::Data<AppState>,
+                 query: web::Query<FilesQuery>,
+                 payload: web::Payload| async move {
+                    let request = req.clone();
+                    file_manager_action(&req, app_state, &query.dir, |directory| async move {
+                        // The body is only read once the administrator is authenticated
+                        let form = MultipartForm::<UploadForm>::from_request(
+                            &request,
+                            &mut payload.into_inner(),
+                        )
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Unable to read the uploaded file: {e}"))?;
+                        upload_file(&directory, &form.into_inner().file).await
+                    })
+                    .await
+                },
+            ),
+        )
+        .route(
+            "/rename",
+            web::post().to(
+                |req: HttpRequest,
+                 app_state: web::Data<AppState>,
+                 query: web::Query<FilesQuery>,
+                 form: web::Form<RenameForm>| async move {
+                    file_manager_action(&req, app_state, &query.dir, |directory| async move {
+                        rename_file(&directory, &form.name, &form.new_name).await
+                    })
+                    .await
+                },
+            ),
+        )
+        .route(
+            "/delete",
+            web::post().to(
+                |req: HttpRequest,
+                 app_state: web::Data<AppState>,
+                 query: web::Query<FilesQuery>,
+                 form: web::Form<DeleteForm>| async move {
+                    file_manager_action(&req, app_state, &query.dir, |directory| async move {
+                        delete_file(&directory, &form.name).await
+                    })
+                    .await
+                },
+            ),
+        )
+}
+
+/// Returns the directory of the file manager, relative to the web root,
+/// or the response to send when the file manager is disabled or the request is not authenticated.
+async fn file_manager_directory(
+    req: &HttpRequest,
+    config: &AppConfig,
+) -> Result<PathBuf, HttpResponse> {
+    let Some(directory) = &config.admin_file_manager_directory else {
+        return Err(HttpResponse::NotFound().finish());
+    };
+    authenticate(req, config).await?;
+    Ok(directory.clone())
+}
+
+/// Runs an action on a directory of the file manager, then redirects to the listing of the directory.
+/// When the action fails, the listing is displayed with the error.
+async fn file_manager_action<F, Fut>(
+    req: &HttpRequest,
+    app_state: web::Data<AppState>,
+    dir: &str,
+    action: F,
+) -> HttpResponse
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    let base = match file_manager_directory(req, &app_state.config()).await {
+        Ok(base) => base,
+        Err(response) => return response,
+    };
+    if !is_same_origin(req) {
+        log::warn!("Refusing a file manager action sent from another site");
+        return HttpResponse::Forbidden()
+            .body("Files can only be modified from the file manager page");
+    }
+    let result = match file_manager_subdirectory(&base, dir) {
+        Ok(directory) => action(app_state.config().web_root.join(directory)).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => HttpResponse::SeeOther()
+            .insert_header((
+                header::LOCATION,
+                format!(
+                    "{}{ADMIN_FILES_PATH}?dir={}",
+                    app_state.config().site_prefix.trim_end_matches('/'),
+                    utf8_percent_encode(dir, FILE_NAME_ENCODE)
+                ),
+            ))
+            .finish(),
+        Err(e) => file_manager_page(app_state.into_inner(), &base, dir, Some(e)).await,
+    }
+}
+
+/// Resolves a subdirectory of the file manager, given as a slash-separated path, such as `images/2025`.
+fn file_manager_subdirectory(base: &Path, dir: &str) -> anyhow::Result<PathBuf> {
+    let mut path = base.to_path_buf();
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        path.push(valid_file_name(segment)?);
+    }
+    Ok(path)
+}
+
+fn valid_file_name(name: &str) -> anyhow::Result<&str> {
+    anyhow::ensure!(
+        !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', ':']),
+        "Invalid file name: {name:?}"
+    );
+    Ok(name)
+}
+
+/// SQL files would be executed when they are requested, so they cannot be created from the file manager.
+/// Existing SQL files cannot be renamed or deleted either: removing an `authorize.sql` file
+/// would make the other pages of its directory accessible without authorization.
+fn writable_file_name(name: &str) -> anyhow::Result<&str> {
+    let name = valid_file_name(name)?;
+    anyhow::ensure!(
+        !Path::new(name)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("sql")),
+        "SQL files cannot be created, renamed, or deleted from the file manager: {name:?}"
+    );
+    Ok(name)
+}
+
+async fn ensure_not_exists(path: &Path, name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !tokio::fs::try_exists(path).await?,
+        "A file named {name:?} already exists"
+    );
+    Ok(())
+}
+
+async fn upload_file(directory: &Path, file: &TempFile) -> anyhow::Result<()> {
+    let file_name = file.file_name.as_deref().unwrap_or_default();
+    // Some browsers send the full path of the file on the client
+    let name = writable_file_name(file_name.rsplit(['/', '\\']).next().unwrap_or_default())?;
+    let target = directory.join(name);
+    ensure_not_exists(&target, name).await?;
+    tokio::fs::copy(file.file.path(), &target)
+        .await
+        .with_context(|| format!("Unable to save the uploaded file to {}", target.display()))?;
+    log::info!("File manager: uploaded {target:?}");
+    Ok(())
+}
+
+async fn rename_file(directory: &Path, name: &str, new_name: &str) -> anyhow::Result<()> {
+    let source = directory.join(writable_file_name(name)?);
+    let new_name = writable_file_name(new_name)?;
+    let target = directory.join(new_name);
+    ensure_not_exists(&target, new_name).await?;
+    tokio::fs::rename(&source, &target).await.with_context(|| {
+        format!(
+            "Unable to rename {} to {}",
+            source.display(),
+            target.display()
+        )
+    })?;
+    log::info!("File manager: renamed {source:?} to {target:?}");
+    Ok(())
+}
+
+async fn delete_file(directory: &Path, name: &str) -> anyhow::Result<()> {
+    let path = directory.join(writable_file_name(name)?);
+    tokio::fs::remove_file(&path)
+        .await
+        .with_context(|| format!("Unable to delete {}", path.display()))?;
+    log::info!("File manager: deleted {path:?}");
+    Ok(())
+}
+
+async fn file_manager_page(
+    app_state: Arc<AppState>,
+    base: &Path,
+    dir: &str,
+    error: Option<anyhow::Error>,
+) -> HttpResponse {
+    let rows = file_manager_rows(&app_state, base, dir, error).await;
+    render_admin_page(app_state, rows).await
+}
+
+async fn file_manager_rows(
+    app_state: &AppState,
+    base: &Path,
+    dir: &str,
+    error: Option<anyhow::Error>,
+) -> anyhow::Result<Vec<JsonValue>> {
+    let directory = file_manager_subdirectory(base, dir)?;
+    let entries = app_state
+        .file_system
+        .list_directory(app_state, &directory)
+        .await?;
+    let encode = |s: &str| utf8_percent_encode(s, FILE_NAME_ENCODE).to_string();
+    let mut rows = vec![
+        json!({"component": "breadcrumb"}),
+        json!({"title": "Files", "link": "files"}),
+    ];
+    let mut sub_path = String::new();
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        if !sub_path.is_empty() {
+            sub_path.push('/');
+        }
+        sub_path.push_str(segment);
+        rows.push(json!({"title": segment, "link": format!("files?dir={}", encode(&sub_path))}));
+    }
+    if let Some(e) = error {
+        rows.push(json!({
+            "component": "alert",
+            "title": "Error",
+            "description": format!("{e:#}"),
+            "color": "red",
+        }));
+    }
+    let site_prefix = &app_state.config().site_prefix;
+    let url_directory: Vec<String> = directory
+        .components()
+        .map(|c| encode(&c.as_os_str().to_string_lossy()))
+        .collect();
+    rows.push(json!({
+        "component": "table",
+        "markdown": ["Name"],
+        "sort": true,
+        "search": true,
+        "align_right": ["Size"],
+        "empty_description": "This directory is empty",
+    }));
+    for entry in &entries {
+        let label = entry.name.replace('[', "\\[").replace(']', "\\]");
+        let link = if entry.is_directory {
+            let child = if sub_path.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{sub_path}/{}", entry.name)
+            };
+            format!("[{label}/](files?dir={})", encode(&child))
+        } else {
+            format!(
+                "[{label}]({site_prefix}{}/{})",
+                url_directory.join("/"),
+                encode(&entry.name)
+            )
+        };
+        rows.push(json!({
+            "Name": link,
+            "Size": (!entry.is_directory).then_some(entry.size),
+            "Modified": entry.modified.map(|m| m.format("%Y-%m-%d %H:%M").to_string()),
+        }));
+    }
+    let action = |name: &str| format!("files/{name}?dir={}", encode(dir));
+    rows.push(json!({
+        "component": "form",
+        "title": "Upload a file",
+        "action": action("upload"),
+        "validate": "Upload",
+    }));
+    rows.push(json!({"name": "file", "label": "File", "type": "file", "required": true}));
+    let files: Vec<JsonValue> = entries
+        .iter()
+        .filter(|entry| writable_file_name(&entry.name).is_ok())
+        .map(|entry| json!({"label": entry.name, "value": entry.name}))
+        .collect();
+    if !files.is_empty() {
+        rows.extend([
+            json!({
+                "component": "form",
+                "title": "Rename a file",
+                "action": action("rename"),
+                "validate": "Rename",
+            }),
+            json!({"name": "name", "label": "File", "type": "select", "options": files, "required": true, "width": 6}),
+            json!({"name": "new_name", "label": "New name", "required": true, "width": 6}),
+            json!({
+                "component": "form",
+                "title": "Delete a file",
+                "action": action("delete"),
+                "validate": "Delete",
+                "validate_color": "red",
+            }),
+            json!({"name": "name", "label": "File", "type": "select", "options": files, "required": true}),
+        ]);
+    }
+    Ok(rows)
+}
+
+async fn sql_console_page(
+    req: &HttpRequest,
+    app_state: web::Data<AppState>,
+    sql: Option<String>,
+) -> HttpResponse {
+    if !app_state.config().admin_sql_console {
+        return HttpResponse::NotFound().finish();
+    }
+    if let Err(response) = authenticate(req, &app_state.config()).await {
+        return response;
+    }
+    if sql.is_some() && !is_same_origin(req) {
+        log::warn!("Refusing to run a SQL console query sent from another site");
+        return HttpResponse::Forbidden()
+            .body("SQL console queries must be sent from the console page");
+    }
+    let app_state = app_state.into_inner();
+    let rows = sql_console_rows(&app_state, sql.as_deref()).await;
+    render_admin_page(app_state, Ok(rows)).await
+}
+
+/// Browsers send the basic authentication credentials with cross-site requests too,
+/// so console queries are only accepted from pages of the same site.
+fn is_same_origin(req: &HttpRequest) -> bool {
+    let headers = req.headers();
+    if let Some(site) = headers.get("sec-fetch-site") {
+        return site == "same-origin" || site == "none";
+    }
+    match headers.get(header::ORIGIN).map(header::HeaderValue::to_str) {
+        Some(Ok(origin)) => origin
+            .split_once("://")
+            .is_some_and(|(_, host)| host == req.connection_info().host()),
+        Some(Err(_)) => false,
+        None => true,
+    }
+}
+
+async fn sql_console_rows(app_state: &AppState, sql: Option<&str>) -> Vec<JsonValue> {
+    let config = &app_state.config();
+    let mut restrictions = Vec::new();
+    if config.admin_sql_console_read_only {
+        restrictions.push(
+            "Queries are run in a read-only transaction that is rolled back, so they cannot modify the database."
+                .to_string(),
+        );
+    }
+    if !config.admin_sql_console_allowed_statements.is_empty() {
+        restrictions.push(format!(
+            "Allowed statements: {}.",
+            config.admin_sql_console_allowed_statements.join(", ")
+        ));
+    }
+    let mut rows = vec![
+        json!({
+            "component": "form",
+            "title": "SQL console",
+            "action": "sql",
+            "validate": "Run",
+        }),
+        json!({
+            "name": "sql",
+            "label": "Query",
+            "type": "textarea",
+            "value": sql,
+            "required": true,
+            "description": restrictions.join(" "),
+        }),
+    ];
+    let Some(sql) = sql else {
+        return rows;
+    };
+    match run_console_query(&app_state.db, config, sql).await {
+        Ok(results) => {
+            for result in results {
+                if result.rows.is_empty() {
+                    rows.push(json!({
+                        "component": "alert",
+                        "title": "Statement executed",
+                        "description": format!("{} rows affected", result.rows_affected),
+                        "color": "green",
+                    }));
+                    continue;
+                }
+                rows.push(json!({
+                    "component": "table",
+                    "sort": true,
+                    "search": true,
+                    "description": result.truncated.then(|| format!("Only the first {MAX_CONSOLE_ROWS} rows are displayed")),
+                }));
+                rows.extend(result.rows);
+            }
+        }
+        // The error is displayed even in production, since the administrator is authenticated
+        Err(e) => rows.push(json!({
+            "component": "alert",
+            "title": "Error",
+            "description": format!("{e:#}"),
+            "color": "red",
+        })),
+    }
+    rows
+}
+
+/// Checks the basic authentication credentials of the request against `admin_password_hash`.
+/// Returns the response to send instead of the page when they are missing or invalid.
+async fn authenticate(req: &HttpRequest, config: &AppConfig) -> Result<(), HttpResponse> {
+    let Some(password_hash) = &config.admin_password_hash else {
+        return Err(HttpResponse::NotFound().finish());
+    };
+    if let Ok(auth) = Authorization::<Basic>::parse(req) {
+        let credentials = auth.into_scheme();
+        if let (ADMIN_USER_NAME, Some(password)) = (credentials.user_id(), credentials.password()) {
+            match verify_password_async(password_hash.clone(), password.to_string()).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(e)) => log::info!("Invalid administration password: {e}"),
+                Err(e) => log::error!("Unable to verify the administration password: {e:#}"),
+            }
+        }
+    }
+    Err(ErrorWithStatus {
+        status: StatusCode::UNAUTHORIZED,
+    }
+    .error_response())
+}
+
+async fn tables_list_rows(app_state: &AppState) -> anyhow::Result<Vec<JsonValue>> {
+    let (db, config) = (&app_state.db, &app_state.config());
+    let tables = schema::list_tables(db, config).await?;
+    let mut rows = vec![json!({
+        "component": "list",
+        "title": format!("{} tables", tables.len()),
+        "empty_title": "This database does not contain any table",
+    })];
+    for table in &tables {
+        let row_count = match schema::count_rows(db, config, table).await {
+            Ok(count) => format!("{count} rows"),
+            Err(e) => {
+                log::warn!("{e:#}");
+                "unknown number of rows".to_string()
+            }
+        };
+        rows.push(json!({
+            "title": table.name,
+            "description": format!("{} · {row_count}", table.schema),
+            "link": format!(
+                "?schema={}&table={}",
+                utf8_percent_encode(&table.schema, NON_ALPHANUMERIC),
+                utf8_percent_encode(&table.name, NON_ALPHANUMERIC)
+            ),
+        }));
+    }
+    Ok(rows)
+}
+
+async fn table_rows(
+    app_state: &AppState,
+    schema_name: Option<&str>,
+    table_name: &str,
+) -> anyhow::Result<Vec<JsonValue>> {
+    let (db, config) = (&app_state.db, &app_state.config());
+    // Only tables from the catalog are queried, so that their names can safely be included in the queries
+    let table: Table = schema::list_tables(db, config)
+        .await?
+        .into_iter()
+        .find(|t| t.name == table_name && schema_name.is_none_or(|s| t.schema == s))
+        .ok_or_else(|| anyhow::anyhow!("There is no table named {table_name:?} in the database"))?;
+    let columns = schema::table_columns(db, config, &table).await?;
+    let row_count = schema::count_rows(db, config, &table).await?;
+    let mut rows = vec![
+        json!({"component": "breadcrumb"}),
+        json!({"title": "Tables", "link": "tables"}),
+        json!({"title": table.name, "active": true}),
+        json!({
+            "component": "text",
+            "title": table.name,
+            "contents": format!("{} · {} columns · {row_count} rows", table.schema, columns.len()),
+        }),
+        json!({"component": "table", "sort": true}),
+    ];
+    rows.extend(columns.into_iter().map(|column| {
+        json!({
+            "Column": column.name,
+            "Type": column.data_type,
+            "Nullable": if column.nullable { "yes" } else { "no" },
+        })
+    }));
+    Ok(rows)
+}
+
+async fn render_admin_page(
+    app_state: Arc<AppState>,
+    rows: anyhow::Result<Vec<JsonValue>>,
+) -> HttpResponse {
+    match render_page(app_state, rows).await {
+        Ok((html, csp)) => {
+            let mut response = HttpResponse::Ok();
+            response.insert_header(ContentType::html());
+            if let Some(csp) = csp {
+                response.insert_header((header::CONTENT_SECURITY_POLICY, csp));
+            }
+            response.body(html)
+        }
+        Err(e) => {
+            log::error!("Unable to render the administration page: {e:#}");
+            HttpResponse::InternalServerError().body("Unable to render the administration page")
+        }
+    }
+}
+
+async fn render_page(
+    app_state: Arc<AppState>,
+    rows: anyhow::Result<Vec<JsonValue>>,
+) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    let content_security_policy = ContentSecurityPolicy::default();
+    let csp_header = content_security_policy
+        .page_header_value(app_state.config().content_security_policy.as_deref());
+    let request_context = RequestContext {
+        is_embedded: false,
+        content_security_policy,
+        directory_shell: None,
+        timezone: timezone::default_timezone(&app_state.config()),
+        url_parameters: json!({}),
+        status: StatusCode::OK,
+        csrf_secret: Arc::default(),
+        is_https: false,
+    };
+    let mut menu_items = vec![json!({"title": "Tables", "link": "tables"})];
+    if app_state.config().admin_sql_console {
+        menu_items.push(json!({"title": "SQL console", "link": "sql"}));
+    }
+    if app_state.config().admin_file_manager_directory.is_some() {
+        menu_items.push(json!({"title": "Files", "link": "files"}));
+    }
+    let shell = json!({
+        "component": "shell",
+        "title": "Database",
+        "link": "tables",
+        "menu_item": menu_items,
+        "norobot": true,
+    });
+    let mut page = HtmlRenderContext::new(app_state, request_context, Vec::new(), shell).await?;
+    match rows {
+        Ok(rows) => {
+            for row in &rows {
+                page.handle_row(row).await?;
+            }
+        }
+        Err(e) => page.handle_error(&e).await?,
+    }
+    Ok((page.close().await, csp_header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use actix_web::{test, App};
+    use sqlx::Executor;
+
+    // Hash of the password "admin"
+    const PASSWORD_HASH: &str = "$argon2i$v=19$m=8,t=1,p=1$YWFhYWFhYWE$ROyXNhK0utkzTA";
+
+    fn admin_config() -> AppConfig {
+        let mut config = test_config();
+        config.admin_password_hash = Some(PASSWORD_HASH.to_string());
+        config
+    }
+
+    fn with_credentials(req: test::TestRequest, user: &str, password: &str) -> test::TestRequest {
+        req.insert_header(Authorization::from(Basic::new(
+            user.to_string(),
+            Some(password.to_string()),
+        )))
+    }
+
+    fn authenticated(req: test::TestRequest) -> test::TestRequest {
+        with_credentials(req, "admin", "admin")
+    }
+
+    async fn call_admin(config: &AppConfig, req: test::TestRequest) -> (StatusCode, String) {
+        let app_state = web::Data::new(AppState::init(config).await.unwrap());
+        app_state
+            .db
+            .connection()
+            .execute("CREATE TABLE IF NOT EXISTS sqlpage_admin_test (id INT NOT NULL, label TEXT)")
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .service(admin_tables())
+                .service(admin_sql_console())
+                .service(admin_metrics())
+                .service(admin_reload_config())
+                .service(admin_version())
+                .service(admin_file_manager(config)),
+        )
+        .await;
+        let resp = test::call_service(&app, req.to_request()).await;
+        let status = resp.status();
+        let body = test::read_body(resp).await;
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    fn get(uri: &str) -> test::TestRequest {
+        test::TestRequest::get().uri(uri)
+    }
+
+    fn post_query(sql: &str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(ADMIN_SQL_CONSOLE_PATH)
+            .set_form([("sql", sql)])
+    }
+
+    #[actix_web::test]
+    async fn test_admin_disabled_by_default() {
+        let (status, _) = call_admin(&test_config(), authenticated(get(ADMIN_TABLES_PATH))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_requires_password() {
+        let config = admin_config();
+        let requests = [
+            get(ADMIN_TABLES_PATH),
+            with_credentials(get(ADMIN_TABLES_PATH), "admin", "wrong"),
+            with_credentials(get(ADMIN_TABLES_PATH), "root", "admin"),
+        ];
+        for req in requests {
+            let (status, _) = call_admin(&config, req).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_admin_metrics() {
+        let config = admin_config();
+        let (status, _) = call_admin(&config, get(ADMIN_METRICS_PATH)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call_admin(&config, authenticated(get(ADMIN_METRICS_PATH))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.contains("# TYPE sqlpage_db_pool_connections gauge"),
+            "{body}"
+        );
+        assert!(body.contains("sqlpage_db_pool_queued_acquires 0"), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn test_admin_version() {
+        let config = admin_config();
+        let (status, _) = call_admin(&config, get(ADMIN_VERSION_PATH)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call_admin(&config, authenticated(get(ADMIN_VERSION_PATH))).await;
+        assert_eq!(status, StatusCode::OK);
+        let info: JsonValue = serde_json::from_str(&body).unwrap();
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(info["git_commit"].is_string(), "{body}");
+        assert!(info["features"].is_array(), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn test_admin_reload_config_requires_password() {
+        let req = test::TestRequest::post().uri(ADMIN_RELOAD_CONFIG_PATH);
+        let (status, _) = call_admin(&admin_config(), req).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call_admin(
+            &admin_config(),
+            authenticated(get(ADMIN_RELOAD_CONFIG_PATH)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_tables() {
+        let config = admin_config();
+        let (status, body) = call_admin(&config, authenticated(get(ADMIN_TABLES_PATH))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("sqlpage_admin_test"), "{body}");
+
+        let uri = format!("{ADMIN_TABLES_PATH}?table=sqlpage_admin_test");
+        let (status, body) = call_admin(&config, authenticated(get(&uri))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("label"), "{body}");
+        assert!(body.contains("0 rows"), "{body}");
+
+        let uri = format!("{ADMIN_TABLES_PATH}?table=not_a_table");
+        let (_, body) = call_admin(&config, authenticated(get(&uri))).await;
+        assert!(body.contains("no table named"), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn test_sql_console() {
+        let mut config = admin_config();
+        let (status, _) = call_admin(&config, authenticated(post_query("SELECT 1"))).await;
+        assert_eq!(
+            status,
+            StatusCode::NOT_FOUND,
+            "the console is disabled by default"
+        );
+
+        config.admin_sql_console = true;
+        let (status, _) = call_admin(&config, post_query("SELECT 1")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let query = "SELECT 'console result' AS sqlpage_admin_column";
+        let (status, body) = call_admin(&config, authenticated(post_query(query))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("sqlpage_admin_column"), "{body}");
+        assert!(body.contains("console result"), "{body}");
+
+        let cross_site = post_query(query).insert_header(("Sec-Fetch-Site", "cross-site"));
+        let (status, _) = call_admin(&config, authenticated(cross_site)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        config.admin_sql_console_allowed_statements = vec!["SELECT".to_string()];
+        let query = "DELETE FROM sqlpage_admin_test";
+        let (_, body) = call_admin(&config, authenticated(post_query(query))).await;
+        assert!(body.contains("DELETE statements are not allowed"), "{body}");
+    }
+
+    fn file_manager_config(name: &str) -> AppConfig {
+        let web_root = std::env::temp_dir().join(format!(
+            "sqlpage_file_manager_{name}_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&web_root);
+        std::fs::create_dir_all(web_root.join("uploads").join("images")).unwrap();
+        std::fs::write(web_root.join("uploads").join("report.txt"), "report").unwrap();
+        let mut config = admin_config();
+        config.web_root = web_root;
+        config.admin_file_manager_directory = Some(PathBuf::from("uploads"));
+        config
+    }
+
+    fn post_form(path: &str, form: &[(&str, &str)]) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(&format!("{ADMIN_FILES_PATH}{path}"))
+            .set_form(form)
+    }
+
+    fn upload_request() -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(&format!("{ADMIN_FILES_PATH}/upload?dir=images"))
+            .insert_header(("content-type", "multipart/form-data; boundary=1234567890"))
+            .set_payload(
+                "--1234567890\r\n\
+                Content-Disposition: form-data; name=\"file\"; filename=\"logo.svg\"\r\n\
+                Content-Type: image/svg+xml\r\n\
+                \r\n\
+                <svg/>\r\n\
+                --1234567890--\r\n",
+            )
+    }
+
+    #[actix_web::test]
+    async fn test_file_manager_listing() {
+        let (status, _) = call_admin(&admin_config(), authenticated(get(ADMIN_FILES_PATH))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "disabled by default");
+
+        let config = file_manager_config("listing");
+        let (status, _) = call_admin(&config, get(ADMIN_FILES_PATH)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call_admin(&config, authenticated(get(ADMIN_FILES_PATH))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("/uploads/report.txt"), "{body}");
+        assert!(body.contains("files?dir=images"), "{body}");
+
+        let uri = format!("{ADMIN_FILES_PATH}?dir=..");
+        let (_, body) = call_admin(&config, authenticated(get(&uri))).await;
+        assert!(body.contains("Invalid file name"), "{body}");
+        std::fs::remove_dir_all(&config.web_root).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_file_manager_actions() {
+        let config = file_manager_config("actions");
+        let uploads = config.web_root.join("uploads");
+
+        let (status, _) = call_admin(&config, upload_request()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call_admin(&config, authenticated(upload_request())).await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+        let uploaded = uploads.join("images").join("logo.svg");
+        assert_eq!(std::fs::read_to_string(&uploaded).unwrap(), "<svg/>");
+
+        let rename = [("name", "report.txt"), ("new_name", "report.sql")];
+        let (_, body) = call_admin(&config, authenticated(post_form("/rename", &rename))).await;
+        assert!(body.contains("SQL files cannot be created"), "{body}");
+
+        std::fs::write(uploads.join("authorize.sql"), "select 1").unwrap();
+        let rename = [("name", "authorize.sql"), ("new_name", "authorize.txt")];
+        let (_, body) = call_admin(&config, authenticated(post_form("/rename", &rename))).await;
+        assert!(body.contains("SQL files cannot be created"), "{body}");
+        let delete = [("name", "AUTHORIZE.SQL")];
+        let (_, body) = call_admin(&config, authenticated(post_form("/delete", &delete))).await;
+        assert!(body.contains("SQL files cannot be created"), "{body}");
+        assert!(uploads.join("authorize.sql").exists());
+
+        let rename = [("name", "report.txt"), ("new_name", "2025 report.txt")];
+        let cross_site =
+            post_form("/rename", &rename).insert_header(("Sec-Fetch-Site", "cross-site"));
+        let (status, _) = call_admin(&config, authenticated(cross_site)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call_admin(&config, authenticated(post_form("/rename", &rename))).await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+        assert!(uploads.join("2025 report.txt").exists());
+
+        let delete = [("name", "2025 report.txt")];
+        let (status, _) = call_admin(&config, authenticated(post_form("/delete", &delete))).await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+        assert!(!uploads.join("2025 report.txt").exists());
+        std::fs::remove_dir_all(&config.web_root).unwrap();
+    }
+}
@@ -0,0 +1,297 @@
+//! Audit trail of the requests received by the server, enabled by `audit_log_table`.
+//!
+//! Each request is recorded with its method, path, user, session, client address, status, and duration.
+//! Records are sent to a background task, which inserts them into the table in batches,
+//! so that requests are not slowed down by the audit log.
+//! Records that have not been inserted yet are lost when the server stops,
+//! and new records are dropped, and counted, when too many of them are waiting to be inserted.
+//!
+//! The user is only recorded when the page verified the password sent with HTTP basic authentication,
+//! with the `authentication` component, and the session cookie is recorded as a hash of its value.
+
+use super::database::current_pool;
+use super::http_request_info::client_ip;
+use super::{make_placeholder, Database};
+use crate::AppState;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::Header;
+use actix_web::middleware::Next;
+use actix_web::web;
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::any::AnyKind;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const LOG_TARGET: &str = "sqlpage::audit_log";
+/// Maximum number of requests inserted with a single statement
+const MAX_BATCH_SIZE: usize = 100;
+/// Maximum time a record waits for other records before it is inserted
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum number of records waiting to be inserted, after which new records are dropped
+const MAX_PENDING_RECORDS: usize = 10_000;
+const COLUMNS: [&str; 8] = [
+    "requested_at",
+    "method",
+    "path",
+    "user_name",
+    "session_id",
+    "client_ip",
+    "status",
+    "duration_ms",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub requested_at: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub user_name: Option<String>,
+    pub session_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub status: u16,
+    pub duration_ms: f64,
+}
+
+/// Password verified by the `authentication` component, stored in the extensions of the response,
+/// so that the user name sent with the same password is recorded in the audit log
+pub struct VerifiedPassword(pub String);
+
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: mpsc::Sender<AuditRecord>,
+    /// Records dropped because too many records were waiting to be inserted
+    dropped: Arc<AtomicU64>,
+}
+
+impl AuditLog {
+    /// Starts the background task that inserts the records into the table
+    #[must_use]
+    pub fn start(db: &Database, table: &str) -> Self {
+        let (sender, receiver) = mpsc::channel(MAX_PENDING_RECORDS);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let shared_pool = db.shared_pool();
+        let table = table.to_string();
+        let dropped_counter = Arc::clone(&dropped);
+        tokio::spawn(async move {
+            let mut receiver = receiver;
+            let mut reported_drops = 0;
+            while let Some(batch) = next_batch(&mut receiver).await {
+                let drops = dropped_counter.load(Relaxed);
+                if drops > reported_drops {
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "{} requests were not recorded in {table}, because the database could not keep up",
+                        drops - reported_drops
+                    );
+                    reported_drops = drops;
+                }
+                // The pool is replaced when the database credentials are renewed
+                let pool = current_pool(&shared_pool);
+                let insert_sql = insert_statement(pool.any_kind(), &table, batch.len());
+                let mut query = sqlx::query(&insert_sql);
+                for record in batch {
+                    query = query
+                        .bind(record.requested_at)
+                        .bind(record.method)
+                        .bind(record.path)
+                        .bind(record.user_name)
+                        .bind(record.session_id)
+                        .bind(record.client_ip)
+                        .bind(i32::from(record.status))
+                        .bind(record.duration_ms);
+                }
+                if let Err(e) = query.execute(&pool).await {
+                    log::error!(target: LOG_TARGET, "Unable to record requests in {table}: {e}");
+                }
+            }
+        });
+        Self { sender, dropped }
+    }
+
+    pub fn record(&self, record: AuditRecord) {
+        match self.sender.try_send(record) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Relaxed);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                log::error!(target: LOG_TARGET, "The audit log is not running anymore");
+            }
+        }
+    }
+
+    /// Number of records dropped since the server started, because too many records were waiting to be inserted
+    #[must_use]
+    pub fn dropped_records(&self) -> u64 {
+        self.dropped.load(Relaxed)
+    }
+}
+
+/// Hash of the session cookie, that links the requests of a session without storing a value that can be used to hijack it
+fn session_hash(cookie_value: &str) -> String {
+    format!("{:x}", Sha256::digest(cookie_value.as_bytes()))
+}
+
+/// Waits for a record, then collects the records received until the batch is full or the flush interval elapsed
+async fn next_batch(receiver: &mut mpsc::Receiver<AuditRecord>) -> Option<Vec<AuditRecord>> {
+    let first = receiver.recv().await?;
+    let mut batch = vec![first];
+    let deadline = tokio::time::sleep(FLUSH_INTERVAL);
+    tokio::pin!(deadline);
+    while batch.len() < MAX_BATCH_SIZE {
+        tokio::select! {
+            record = receiver.recv() => match record {
+                Some(record) => batch.push(record),
+                None => break,
+            },
+            () = &mut deadline => break,
+        }
+    }
+    Some(batch)
+}
+
+fn insert_statement(kind: AnyKind, table: &str, rows: usize) -> String {
+    let values: Vec<String> = (0..rows)
+        .map(|row| {
+            let placeholders: Vec<String> = (1..=COLUMNS.len())
+                .map(|col| make_placeholder(kind, row * COLUMNS.len() + col))
+                .collect();
+            format!("({})", placeholders.join(", "))
+        })
+        .collect();
+    format!(
+        "INSERT INTO {table} ({}) VALUES {}",
+        COLUMNS.join(", "),
+        values.join(", ")
+    )
+}
+
+/// Middleware that records every request in the audit log, once its response is ready to be sent.
+/// The duration does not include the time it takes to stream the body of the response.
+pub async fn record_request(
+    service_request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let app_state: &web::Data<AppState> = service_request.app_data().expect("app_state");
+    let Some(audit_log) = app_state.audit_log() else {
+        return next.call(service_request).await;
+    };
+    let audit_log = audit_log.clone();
+    let started = Instant::now();
+    let config = app_state.config();
+    let request = service_request.request();
+    let basic_auth = Authorization::<Basic>::parse(request)
+        .ok()
+        .map(Authorization::into_scheme);
+    let session_id = config
+        .audit_log_session_cookie
+        .as_deref()
+        .and_then(|name| request.cookie(name))
+        .map(|cookie| session_hash(cookie.value()));
+    let mut record = AuditRecord {
+        requested_at: Utc::now(),
+        method: request.method().to_string(),
+        path: request.path().to_string(),
+        user_name: None,
+        session_id,
+        client_ip: client_ip(request, &config).map(|ip| ip.to_string()),
+        status: 0,
+        duration_ms: 0.,
+    };
+    let result = next.call(service_request).await;
+    record.status = match &result {
+        Ok(response) => response.status(),
+        Err(e) => e.as_response_error().status_code(),
+    }
+    .as_u16();
+    if let (Ok(response), Some(credentials)) = (&result, basic_auth) {
+        let verified = response
+            .response()
+            .extensions()
+            .get::<VerifiedPassword>()
+            .is_some_and(|VerifiedPassword(password)| {
+                credentials.password() == Some(password.as_str())
+            });
+        if verified {
+            record.user_name = Some(credentials.user_id().to_string());
+        }
+    }
+    record.duration_ms = started.elapsed().as_secs_f64() * 1000.;
+    audit_log.record(record);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use sqlx::Executor;
+
+    #[test]
+    fn test_insert_statement() {
+        assert_eq!(
+            insert_statement(AnyKind::Postgres, "audit", 2),
+            "INSERT INTO audit (requested_at, method, path, user_name, session_id, client_ip, status, duration_ms) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8), ($9, $10, $11, $12, $13, $14, $15, $16)"
+        );
+    }
+
+    #[test]
+    fn test_session_hash() {
+        let hash = session_hash("secret session token");
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, session_hash("secret session token"));
+        assert_ne!(hash, session_hash("another session token"));
+    }
+
+    #[actix_web::test]
+    async fn test_audit_log_table() -> anyhow::Result<()> {
+        let config = test_config();
+        let db = Database::init(&config).await?;
+        db.connection()
+            .execute(
+                "DROP TABLE IF EXISTS sqlpage_audit_test; \
+                CREATE TABLE sqlpage_audit_test (requested_at TIMESTAMP, method VARCHAR(10), path TEXT, \
+                user_name TEXT, session_id TEXT, client_ip TEXT, status INT, duration_ms FLOAT)",
+            )
+            .await?;
+        let audit_log = AuditLog::start(&db, "sqlpage_audit_test");
+        for path in ["/index.sql", "/missing.sql"] {
+            audit_log.record(AuditRecord {
+                requested_at: Utc::now(),
+                method: "GET".into(),
+                path: path.into(),
+                user_name: Some("admin".into()),
+                session_id: None,
+                client_ip: Some("127.0.0.1".into()),
+                status: if path == "/index.sql" { 200 } else { 404 },
+                duration_ms: 1.5,
+            });
+        }
+        let mut rows = Vec::new();
+        for _ in 0..300 {
+            rows = sqlx::query_as::<_, (String, i32)>(
+                "SELECT path, status FROM sqlpage_audit_test ORDER BY path",
+            )
+            .fetch_all(&db.connection())
+            .await?;
+            if !rows.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            rows,
+            [("/index.sql".into(), 200), ("/missing.sql".into(), 404)]
+        );
+        db.connection()
+            .execute("DROP TABLE sqlpage_audit_test")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+//! Execution of several pages in a single request, at the path configured in `batch_path`.
+//!
+//! The request body is a JSON array of pages to run, such as
+//! `[{"path": "/users.sql", "parameters": {"id": 1}}, {"path": "/orders.sql?status=open"}]`.
+//! Each page is executed as a GET request with the given parameters, and the response is an array
+//! containing, for each page, its status and the rows it returned, in the same order.
+//! This lets single-page applications load the data they need from several pages with a single request.
+//! A page that redirects, or whose `authentication` component fails, stops there and gets a `302` status
+//! with the `location`. Pages cannot set HTTP headers or cookies: these components fail the page.
+//! The `authorize.sql` files of each page are executed before it, like in a normal request,
+//! and the rows of the file that denies access to a page are returned with a `403` status.
+
+use super::database::authorize::run_authorization_files;
+use super::database::execute_queries::{
+    stop_at_first_error, stream_query_results_with_conn, DbConn,
+};
+use super::database::user_role::set_user_role;
+use super::database::DbItem;
+use super::http::{load_authorization_files, SingleOrVec};
+use super::http_request_info::{extract_request_info, RequestInfo};
+use super::request_variables::{param_map, ParamMap};
+use super::routing::{calculate_route, AppFileStore, RoutingAction};
+use super::ErrorWithStatus;
+use crate::render::{verify_password_async, HeaderComponent};
+use crate::{AppState, ParsedSqlFile};
+use actix_web::dev::ServiceRequest;
+use actix_web::http::uri::PathAndQuery;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, Resource};
+use anyhow::Context;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Maximum number of pages that can be executed in a single batch
+pub const MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+struct BatchEntry {
+    /// URL path of the page, which can contain a query string
+    path: String,
+    /// Variables passed to the page, in addition to the ones of the query string
+    #[serde(default)]
+    parameters: HashMap<String, JsonValue>,
+}
+
+pub fn batch_endpoint(path: &str) -> Resource {
+    web::resource(path).route(web::post().to(
+        |req: HttpRequest, payload: web::Payload, app_state: web::Data<AppState>| async move {
+            let mut service_request = ServiceRequest::from_parts(req, payload.into_inner());
+            let environment = app_state.config().environment;
+            let mut request = match extract_request_info(&mut service_request, app_state.into_inner()).await {
+                Ok(request) => request,
+                Err(e) => return error_response(&e, environment.is_prod()),
+            };
+            let entries: Vec<BatchEntry> =
+                match serde_json::from_slice(request.raw_body.as_deref().unwrap_or_default()) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        return HttpResponse::BadRequest().body(format!(
+                            "The body of a batch request must be a JSON array of objects with a path and optional parameters: {e}"
+                        ))
+                    }
+                };
+            if entries.len() > MAX_BATCH_SIZE {
+                return HttpResponse::PayloadTooLarge()
+                    .body(format!("A batch cannot contain more than {MAX_BATCH_SIZE} pages"));
+            }
+            // The files are looked up before the connection is taken, since they may be stored in the database
+            let mut pages = Vec::with_capacity(entries.len());
+            for entry in entries {
+                pages.push(prepare_page(&request, entry).await);
+            }
+            let mut conn = None;
+            if let Err(e) = set_user_role(&mut request, &mut conn).await {
+                return error_response(&e, environment.is_prod());
+            }
+            let mut results = Vec::with_capacity(pages.len());
+            for page in pages {
+                results.push(match page {
+                    Ok(page) => run_prepared_page(page, &mut conn).await,
+                    Err(result) => result,
+                });
+            }
+            HttpResponse::Ok().json(results)
+        },
+    ))
+}
+
+fn error_response(e: &anyhow::Error, is_prod: bool) -> HttpResponse {
+    let (status, message) = error_status_and_message(e, is_prod);
+    HttpResponse::build(status).body(message)
+}
+
+/// The status of a failed page, and the error message returned to the client.
+/// In production, the details of the error are only logged.
+fn error_status_and_message(e: &anyhow::Error, is_prod: bool) -> (StatusCode, String) {
+    log::error!("{e:#}");
+    let status = e
+        .downcast_ref::<ErrorWithStatus>()
+        .map_or(StatusCode::INTERNAL_SERVER_ERROR, |e| e.status);
+    let message = if is_prod {
+        "Sorry, but we were not able to process your request. A detailed error message has been logged."
+            .to_string()
+    } else {
+        format!("{e:#}")
+    };
+    (status, message)
+}
+
+/// A page of the batch, with the files it needs, ready to be executed
+struct PreparedPage {
+    path: String,
+    request: RequestInfo,
+    sql_file: Arc<ParsedSqlFile>,
+    authorization_files: Vec<Arc<ParsedSqlFile>>,
+}
+
+/// The result of a page in the response, with its path and status
+fn entry_result(path: &str, status: StatusCode, mut result: JsonValue) -> JsonValue {
+    result["path"] = JsonValue::String(path.to_owned());
+    result["status"] = status.as_u16().into();
+    result
+}
+
+/// Finds and loads the files of a page of the batch.
+/// Returns the result of the page instead when it cannot be executed.
+async fn prepare_page(request: &RequestInfo, entry: BatchEntry) -> Result<PreparedPage, JsonValue> {
+    let app_state = Arc::clone(&request.app_state);
+    let config = app_state.config();
+    let result = |status: StatusCode, result: JsonValue| entry_result(&entry.path, status, result);
+    let Ok(path_and_query) = PathAndQuery::from_str(&entry.path) else {
+        return Err(result(
+            StatusCode::BAD_REQUEST,
+            json!({ "error": "Invalid path" }),
+        ));
+    };
+    if let Some(rule) = config.ip_access_rule_for_url(path_and_query.path()) {
+        if !rule.allows(request.client_ip) {
+            log::info!(
+                "Access to {path_and_query} in a batch denied by ip_access_rules for client {:?}",
+                request.client_ip
+            );
+            return Err(result(
+                StatusCode::FORBIDDEN,
+                json!({ "error": "Forbidden" }),
+            ));
+        }
+    }
+    let store = AppFileStore::new(
+        &app_state.sql_file_cache,
+        &app_state.file_system,
+        &app_state,
+    );
+    let sql_path = match calculate_route(&path_and_query, &store, config.as_ref()).await {
+        Ok(RoutingAction::Execute(sql_path)) => sql_path,
+        Ok(RoutingAction::Redirect(location)) => {
+            return Err(result(
+                StatusCode::MOVED_PERMANENTLY,
+                json!({ "location": location }),
+            ));
+        }
+        Ok(RoutingAction::NotFound | RoutingAction::CustomNotFound(_)) => {
+            return Err(result(
+                StatusCode::NOT_FOUND,
+                json!({ "error": "Not found" }),
+            ));
+        }
+        Ok(RoutingAction::Serve(_) | RoutingAction::Proxy { .. }) => {
+            return Err(result(
+                StatusCode::BAD_REQUEST,
+                json!({ "error": "Only SQL pages can be executed in a batch" }),
+            ));
+        }
+        Err(e) => {
+            let (status, message) = error_status_and_message(&e, config.environment.is_prod());
+            return Err(result(status, json!({ "error": message })));
+        }
+    };
+    let files = async {
+        let sql_file = app_state
+            .sql_file_cache
+            .get_with_privilege(&app_state, &sql_path, false)
+            .await?;
+        let authorization_files = load_authorization_files(&app_state, &sql_path).await?;
+        anyhow::Ok((sql_file, authorization_files))
+    };
+    let (sql_file, authorization_files) = match files.await {
+        Ok(files) => files,
+        Err(e) => {
+            let e = e.context(format!("Unable to run {} in a batch", entry.path));
+            let (status, message) = error_status_and_message(&e, config.environment.is_prod());
+            return Err(result(status, json!({ "error": message })));
+        }
+    };
+    let mut page_request = request.clone_without_variables();
+    page_request.method = actix_web::http::Method::GET;
+    page_request.path = path_and_query.path().to_string();
+    page_request.raw_body = None;
+    page_request.uploaded_files = Rc::default();
+    page_request.get_variables = query_variables(&path_and_query, entry.parameters);
+    Ok(PreparedPage {
+        path: entry.path,
+        request: page_request,
+        sql_file,
+        authorization_files,
+    })
+}
+
+async fn run_prepared_page(mut page: PreparedPage, conn: &mut DbConn) -> JsonValue {
+    let result = |status: StatusCode, result: JsonValue| entry_result(&page.path, status, result);
+    match run_page(
+        &page.sql_file,
+        &page.authorization_files,
+        &mut page.request,
+        conn,
+    )
+    .await
+    {
+        Ok(PageOutput::Rows(rows)) => result(StatusCode::OK, json!({ "body": rows })),
+        Ok(PageOutput::Denied(rows)) => result(StatusCode::FORBIDDEN, json!({ "body": rows })),
+        Ok(PageOutput::Redirect(location)) => {
+            result(StatusCode::FOUND, json!({ "location": location }))
+        }
+        Err(e) => {
+            let e = e.context(format!("Unable to run {} in a batch", page.path));
+            let environment = page.request.app_state.config().environment;
+            let (status, message) = error_status_and_message(&e, environment.is_prod());
+            result(status, json!({ "error": message }))
+        }
+    }
+}
+
+/// The variables of the query string, and the parameters given in the batch.
+/// Parameters that are not strings are converted to their JSON representation.
+fn query_variables(
+    path_and_query: &PathAndQuery,
+    parameters: HashMap<String, JsonValue>,
+) -> ParamMap {
+    let query = path_and_query.query().unwrap_or_default();
+    let mut variables = web::Query::<Vec<(String, String)>>::from_query(query)
+        .map(|q| param_map(q.into_inner()))
+        .unwrap_or_default();
+    let as_string = |value: JsonValue| match value {
+        JsonValue::String(s) => s,
+        other => other.to_string(),
+    };
+    for (name, value) in parameters {
+        let value = match value {
+            JsonValue::Null => continue,
+            JsonValue::Array(values) => {
+                SingleOrVec::Vec(values.into_iter().map(as_string).collect())
+            }
+            value => SingleOrVec::Single(as_string(value)),
+        };
+        variables.insert(name, value);
+    }
+    variables
+}
+
+/// What a page executed in a batch produced
+enum PageOutput {
+    /// The rows of the page, without the row that selects the `json` component
+    Rows(Vec<JsonValue>),
+    /// The page stopped at a redirection, or at a failed authentication with a login link
+    Redirect(String),
+    /// The rows of the `authorize.sql` file that denied access to the page
+    Denied(Vec<JsonValue>),
+}
+
+/// Runs a page until its end, or until a header component that cannot be rendered in a batch.
+/// Like in a normal request, the statements after a redirection or a failed authentication are not executed.
+async fn run_page(
+    sql_file: &ParsedSqlFile,
+    authorization_files: &[Arc<ParsedSqlFile>],
+    request: &mut RequestInfo,
+    conn: &mut DbConn,
+) -> anyhow::Result<PageOutput> {
+    if let Some(denial) = run_authorization_files(authorization_files, request, conn).await? {
+        return match page_output(futures_util::stream::iter(denial)).await? {
+            PageOutput::Rows(rows) => Ok(PageOutput::Denied(rows)),
+            output => Ok(output),
+        };
+    }
+    let stream = stream_query_results_with_conn(sql_file, request, conn);
+    Box::pin(page_output(stop_at_first_error(stream))).await
+}
+
+/// The rows of a page, or the redirection at which it stopped
+async fn page_output(stream: impl Stream<Item = DbItem>) -> anyhow::Result<PageOutput> {
+    let mut stream = std::pin::pin!(stream);
+    let mut rows = Vec::new();
+    while let Some(item) = stream.next().await {
+        match item {
+            DbItem::Row(row) => {
+                let component = row
+                    .get("component")
+                    .and_then(JsonValue::as_str)
+                    .and_then(|c| HeaderComponent::try_from(c).ok());
+                match component {
+                    Some(HeaderComponent::Json) => {}
+                    Some(HeaderComponent::Redirect) => {
+                        let link = row
+                            .get("link")
+                            .and_then(JsonValue::as_str)
+                            .context("The redirect component requires a 'link' property")?;
+                        return Ok(PageOutput::Redirect(link.to_string()));
+                    }
+                    Some(HeaderComponent::Authentication) => {
+                        if let Some(output) = check_authentication(row).await? {
+                            return Ok(output);
+                        }
+                    }
+                    Some(HeaderComponent::HttpHeader | HeaderComponent::Cookie) => {
+                        let component = row["component"].as_str().unwrap_or_default();
+                        return Err(anyhow::Error::new(ErrorWithStatus {
+                            status: StatusCode::BAD_REQUEST,
+                        })
+                        .context(format!(
+                            "The {component} component sets HTTP headers, which are not sent for the pages of a batch"
+                        )));
+                    }
+                    _ => rows.push(row),
+                }
+            }
+            DbItem::FinishedQuery => {}
+            DbItem::Error(e) => return Err(e),
+        }
+    }
+    Ok(PageOutput::Rows(rows))
+}
+
+/// Checks the password of an `authentication` component row.
+/// Returns `None` when the page can continue, and the redirection to the login page otherwise.
+async fn check_authentication(mut row: JsonValue) -> anyhow::Result<Option<PageOutput>> {
+    let mut take = |key: &str| match row.get_mut(key).map(JsonValue::take) {
+        Some(JsonValue::String(s)) => Some(s),
+        _ => None,
+    };
+    if let (Some(password), Some(password_hash)) = (take("password"), take("password_hash")) {
+        match verify_password_async(password_hash, password).await? {
+            Ok(()) => return Ok(None),
+            Err(e) => log::info!("Password didn't match: {e}"),
+        }
+    }
+    log::debug!("Authentication failed in a batch");
+    match row.get("link").and_then(JsonValue::as_str) {
+        Some(link) => Ok(Some(PageOutput::Redirect(link.to_string()))),
+        None => Err(ErrorWithStatus {
+            status: StatusCode::UNAUTHORIZED,
+        }
+        .into()),
+    }
+}
@@ -0,0 +1,196 @@
+//! A page listing all the components available to a site, at `/sqlpage/components`.
+//!
+//! Each component is documented with the properties its template reads,
+//! and rendered with example data in which every property contains its own name,
+//! so that site authors can see where each property is displayed.
+//! The page is only served when the `component_gallery` configuration option is enabled.
+
+use crate::render::{is_template_free_component, HtmlRenderContext};
+use crate::templates::{AllTemplates, ComponentSource};
+use crate::webserver::content_security_policy::ContentSecurityPolicy;
+use crate::webserver::http::RequestContext;
+use crate::webserver::timezone;
+use crate::AppState;
+use actix_web::http::header::{self, ContentType};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Resource};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub const COMPONENT_GALLERY_PATH: &str = "/sqlpage/components";
+
+pub fn component_gallery() -> Resource {
+    web::resource(COMPONENT_GALLERY_PATH).to(|app_state: web::Data<AppState>| async move {
+        if !app_state.config().component_gallery {
+            return HttpResponse::NotFound().finish();
+        }
+        match render_gallery(app_state.into_inner()).await {
+            Ok((html, csp)) => {
+                let mut response = HttpResponse::Ok();
+                response.insert_header(ContentType::html());
+                if let Some(csp) = csp {
+                    response.insert_header((header::CONTENT_SECURITY_POLICY, csp));
+                }
+                response.body(html)
+            }
+            Err(e) => {
+                log::error!("Unable to render the component gallery: {e:#}");
+                HttpResponse::InternalServerError().body(format!("{e:#}"))
+            }
+        }
+    })
+}
+
+async fn render_gallery(app_state: Arc<AppState>) -> anyhow::Result<(Vec<u8>, Option<String>)> {
+    let components = AllTemplates::list_components(&app_state.config())?;
+    let content_security_policy = ContentSecurityPolicy::default();
+    let csp_header = content_security_policy
+        .page_header_value(app_state.config().content_security_policy.as_deref());
+    let request_context = RequestContext {
+        is_embedded: false,
+        content_security_policy,
+        directory_shell: None,
+        timezone: timezone::default_timezone(&app_state.config()),
+        url_parameters: json!({}),
+        status: StatusCode::OK,
+        csrf_secret: Arc::default(),
+        is_https: false,
+    };
+    let shell = json!({
+        "component": "shell",
+        "title": "Components",
+        "description": "The components available to this site",
+        "norobot": true,
+    });
+    let mut page =
+        HtmlRenderContext::new(Arc::clone(&app_state), request_context, Vec::new(), shell).await?;
+    let summary = components
+        .iter()
+        .map(|(name, source)| json!({"title": name, "link": format!("#{name}"), "description": source.to_string()}))
+        .collect::<Vec<_>>();
+    page.handle_row(&json!({
+        "component": "text",
+        "contents_md": format!(
+            "This page lists the {} components that can be used in the SQL files of this site, \
+            and shows them with example data in which each property contains its own name. \
+            It is only available in the development environment.",
+            components.len()
+        ),
+    }))
+    .await?;
+    page.handle_row(&json!({"component": "list", "compact": true}))
+        .await?;
+    for row in &summary {
+        page.handle_row(row).await?;
+    }
+    for (name, source) in &components {
+        let result = render_component_documentation(&mut page, &app_state, name, *source).await;
+        page.handle_result_and_log(&result).await;
+    }
+    Ok((page.close().await, csp_header))
+}
+
+async fn render_component_documentation(
+    page: &mut HtmlRenderContext<Vec<u8>>,
+    app_state: &AppState,
+    name: &str,
+    source: ComponentSource,
+) -> anyhow::Result<()> {
+    page.handle_row(&json!({"component": "title", "contents": name, "level": 2, "id": name}))
+        .await?;
+    let template = app_state
+        .all_templates
+        .get_template(app_state, name)
+        .await?;
+    let properties = &template.properties;
+    let top_level = example_properties(properties.top_level.as_ref());
+    let row_level = example_properties(properties.row_level.as_ref());
+    page.handle_row(&json!({
+        "component": "text",
+        "contents_md": format!(
+            "Source: {source}\n\nTop-level properties: {}\n\nRow-level properties: {}",
+            describe_properties(&top_level, properties.top_level.is_some()),
+            describe_properties(&row_level, properties.row_level.is_some()),
+        ),
+    }))
+    .await?;
+    if name.starts_with("shell") || is_template_free_component(name) {
+        return Ok(());
+    }
+    let mut top_level_row = top_level;
+    top_level_row.insert("component".into(), name.into());
+    page.handle_row(&JsonValue::Object(top_level_row)).await?;
+    if !row_level.is_empty() {
+        page.handle_row(&JsonValue::Object(row_level)).await?;
+    }
+    Ok(())
+}
+
+/// Builds a row in which each property contains its own name
+fn example_properties(properties: Option<&HashSet<String>>) -> Map<String, JsonValue> {
+    let mut names = properties
+        .into_iter()
+        .flatten()
+        .filter(|p| *p != "component")
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+        .into_iter()
+        .map(|p| (p.clone(), JsonValue::String(p.clone())))
+        .collect()
+}
+
+fn describe_properties(example: &Map<String, JsonValue>, is_closed: bool) -> String {
+    let mut description = example
+        .keys()
+        .map(|p| format!("`{p}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !is_closed {
+        if !description.is_empty() {
+            description.push_str(", ");
+        }
+        description.push_str("and any other property");
+    } else if description.is_empty() {
+        description.push_str("none");
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use actix_web::{test, App};
+
+    async fn get_gallery(enabled: bool) -> (StatusCode, String) {
+        let mut config = test_config();
+        config.component_gallery = enabled;
+        let app_state = web::Data::new(AppState::init(&config).await.unwrap());
+        let app =
+            test::init_service(App::new().app_data(app_state).service(component_gallery())).await;
+        let req = test::TestRequest::get()
+            .uri(COMPONENT_GALLERY_PATH)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        let body = test::read_body(resp).await;
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[actix_web::test]
+    async fn test_component_gallery() {
+        let (status, body) = get_gallery(true).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(r#"id="alert""#), "{body}");
+        assert!(body.contains("<code>dismissible</code>"), "{body}");
+        assert!(!body.contains("<code>if</code>"), "{body}");
+    }
+
+    #[actix_web::test]
+    async fn test_component_gallery_disabled_by_default() {
+        let (status, _) = get_gallery(false).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
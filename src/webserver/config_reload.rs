@@ -0,0 +1,116 @@
+//! Reloading the configuration of a running server, when it receives a `SIGHUP` signal
+//! or when an administrator calls `/sqlpage/admin/reload-config`.
+//!
+//! The configuration is loaded again from the same sources as when the server started.
+//! Requests that are being handled finish with the configuration they started with.
+//! Settings that are only read at startup, such as the listening address or the database URL,
+//! keep their current value, and a warning lists the ones that changed.
+
+use crate::app_config::{AppConfig, Cli};
+use crate::AppState;
+use actix_web::web;
+use anyhow::Context;
+use clap::Parser;
+
+/// Reloads the configuration, and returns the names of the changed settings that require a restart
+pub fn reload_config(app_state: &AppState) -> anyhow::Result<Vec<&'static str>> {
+    log::info!("Reloading the configuration");
+    let cli = Cli::try_parse().context("Invalid command line arguments")?;
+    let new_config = AppConfig::from_cli(&cli).context("Unable to reload the configuration")?;
+    let needs_restart = app_state.reload_config(new_config);
+    if needs_restart.is_empty() {
+        log::info!("The configuration was reloaded");
+    } else {
+        log::warn!(
+            "The configuration was reloaded, but the following settings only take effect when SQLPage is restarted: {}",
+            needs_restart.join(", ")
+        );
+    }
+    Ok(needs_restart)
+}
+
+/// Reloads the configuration every time the process receives a `SIGHUP` signal
+#[cfg(unix)]
+pub fn reload_on_sighup(app_state: web::Data<AppState>) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangups =
+        signal(SignalKind::hangup()).context("Unable to listen for the SIGHUP signal")?;
+    actix_web::rt::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(e) = reload_config(&app_state) {
+                log::error!("{e:#}");
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+pub fn reload_on_sighup(_app_state: web::Data<AppState>) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+
+    #[actix_web::test]
+    async fn test_reload_keeps_startup_settings() {
+        let config = test_config();
+        let app_state = AppState::init(&config).await.unwrap();
+        let config_before_reload = app_state.config();
+
+        let mut new_config = config.clone();
+        new_config.port = Some(1234);
+        new_config.debug_token = Some("new token".into());
+        new_config.token_signing_secret =
+            Some("a new secret, that is long enough to be valid".into());
+        let needs_restart = app_state.reload_config(new_config);
+
+        assert_eq!(needs_restart, ["port", "token_signing_secret"]);
+        let reloaded = app_state.config();
+        assert_eq!(reloaded.port, config.port);
+        assert_eq!(reloaded.token_signing_secret, config.token_signing_secret);
+        assert_eq!(reloaded.debug_token.as_deref(), Some("new token"));
+        // Requests that started before the reload keep the previous configuration
+        assert_eq!(config_before_reload.debug_token, config.debug_token);
+    }
+
+    #[actix_web::test]
+    async fn test_reload_keeps_listener_pool_and_tls_settings() {
+        let config = test_config();
+        let app_state = AppState::init(&config).await.unwrap();
+
+        let mut new_config = config.clone();
+        new_config.listen_on = Some("127.0.0.1:4321".parse().unwrap());
+        new_config.max_database_pool_connections = Some(42);
+        new_config.database_connection_idle_timeout_seconds = Some(1.5);
+        new_config.https_domain = Some("example.com".into());
+        new_config.https_certificate_cache_dir = "/tmp/other-certificates".into();
+        let needs_restart = app_state.reload_config(new_config);
+
+        assert_eq!(
+            needs_restart,
+            [
+                "max_database_pool_connections",
+                "database_connection_idle_timeout_seconds",
+                "listen_on",
+                "https_domain",
+                "https_certificate_cache_dir",
+            ]
+        );
+        let reloaded = app_state.config();
+        assert_eq!(reloaded.listen_on, config.listen_on);
+        assert_eq!(
+            reloaded.max_database_pool_connections,
+            config.max_database_pool_connections
+        );
+        assert_eq!(reloaded.https_domain, config.https_domain);
+        assert_eq!(
+            reloaded.https_certificate_cache_dir,
+            config.https_certificate_cache_dir
+        );
+    }
+}
@@ -3,11 +3,31 @@ use std::fmt::Display;
 use awc::http::header::InvalidHeaderValue;
 use rand::random;
 
+/// Placeholder that can be used in a custom `content_security_policy`
+/// to insert the nonce of the current page.
+pub const NONCE_PLACEHOLDER: &str = "{NONCE}";
+
 #[derive(Debug, Clone, Copy)]
 pub struct ContentSecurityPolicy {
     pub nonce: u64,
 }
 
+impl ContentSecurityPolicy {
+    /// Returns the header value to send with a rendered page, given the configured policy.
+    /// Custom policies without a nonce placeholder are the same for all responses,
+    /// and are added by the default headers middleware, so `None` is returned for them.
+    #[must_use]
+    pub fn page_header_value(self, custom_policy: Option<&str>) -> Option<String> {
+        match custom_policy {
+            None => Some(self.to_string()),
+            Some(policy) if policy.contains(NONCE_PLACEHOLDER) => {
+                Some(policy.replace(NONCE_PLACEHOLDER, &self.nonce.to_string()))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
 impl Default for ContentSecurityPolicy {
     fn default() -> Self {
         Self { nonce: random() }
@@ -38,3 +58,23 @@ impl actix_web::http::header::TryIntoHeaderPair for &ContentSecurityPolicy {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_header_value() {
+        let csp = ContentSecurityPolicy { nonce: 42 };
+        assert_eq!(
+            csp.page_header_value(None).as_deref(),
+            Some("script-src 'self' 'nonce-42'")
+        );
+        assert_eq!(
+            csp.page_header_value(Some("default-src 'self'; script-src 'nonce-{NONCE}'"))
+                .as_deref(),
+            Some("default-src 'self'; script-src 'nonce-42'")
+        );
+        assert_eq!(csp.page_header_value(Some("default-src 'self'")), None);
+    }
+}
@@ -0,0 +1,262 @@
+//! Protection of the action buttons against cross-site request forgery.
+//!
+//! Buttons with a `method` are small forms, that contain a token signed when the page was rendered.
+//! The token is bound to a random secret stored in a cookie of the browser that displayed the page,
+//! so a token read by someone else is useless in another browser.
+//! Submissions that contain a token are only accepted when the token is valid, recent,
+//! and sent with the cookie it was created for.
+//!
+//! Whether they contain a token or not, submissions are rejected when the browser says,
+//! with the `Sec-Fetch-Site` or the `Origin` header, that they come from a page of another site.
+//! A page on another site can thus not make the browser of a logged-in visitor click on a "Delete" button,
+//! nor submit an ordinary form in its place.
+//! Submissions without these headers, such as the calls of other servers, are accepted:
+//! the target pages of action buttons refuse the ones without a token with `sqlpage.csrf_verified()`.
+
+use super::{form_protection, ErrorWithStatus};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header::{HeaderMap, ORIGIN};
+use actix_web::http::{Method, StatusCode};
+use rand::{distr::Alphanumeric, Rng};
+
+/// Name of the hidden field that contains the signed token
+pub const TOKEN_FIELD: &str = "_sqlpage_csrf_token";
+/// Name of the cookie that contains the secret of the browser, to which the tokens are bound
+pub const COOKIE_NAME: &str = "sqlpage_csrf";
+/// Tokens are rejected after this time, and the page with the button has to be loaded again
+const TOKEN_LIFETIME_SECONDS: i64 = 24 * 3600;
+/// Length of the random secret of a browser
+const SECRET_LENGTH: usize = 32;
+
+fn payload(issued_at: i64, secret: &str) -> String {
+    format!("csrf.{issued_at}.{secret}")
+}
+
+/// Random value stored in a cookie of the browser, to which the tokens of the pages it displays are bound
+#[derive(Debug, Clone, Default)]
+pub struct BrowserSecret {
+    value: String,
+    /// The request did not have the cookie, which has to be set on the response
+    is_new: bool,
+}
+
+impl BrowserSecret {
+    /// Reads the secret from the cookie of the request, or creates a new one when the cookie is missing or invalid
+    #[must_use]
+    pub fn from_cookie(cookie: Option<&str>) -> Self {
+        match cookie {
+            Some(value) if is_valid_secret(value) => Self {
+                value: value.to_owned(),
+                is_new: false,
+            },
+            _ => Self {
+                value: rand::rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(SECRET_LENGTH)
+                    .map(char::from)
+                    .collect(),
+                is_new: true,
+            },
+        }
+    }
+
+    /// Creates the token of an action button displayed now.
+    /// Without a secret, when the page is not rendered for a browser, there is no valid token to create.
+    #[must_use]
+    pub fn new_token(&self) -> String {
+        if self.value.is_empty() {
+            return String::new();
+        }
+        let issued_at = chrono::Utc::now().timestamp();
+        let signature = form_protection::sign(&payload(issued_at, &self.value));
+        format!("{issued_at}.{signature}")
+    }
+
+    /// The cookie to set on the response, when the browser does not have it yet
+    #[must_use]
+    pub fn new_cookie(&self, secure: bool) -> Option<Cookie<'static>> {
+        if !self.is_new || self.value.is_empty() {
+            return None;
+        }
+        Some(
+            Cookie::build(COOKIE_NAME, self.value.clone())
+                .path("/")
+                .http_only(true)
+                .secure(secure)
+                .same_site(SameSite::Lax)
+                .finish(),
+        )
+    }
+}
+
+fn is_valid_secret(value: &str) -> bool {
+    value.len() == SECRET_LENGTH && value.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Checks a submission, and removes the token of action buttons from the submitted values.
+/// `host` is the host of the site, as requested by the browser, and `cookie` the value of the [`COOKIE_NAME`] cookie.
+/// Submissions that change data and come from another site are rejected, with or without a token.
+/// Submissions that do not contain a token are not from action buttons, and are otherwise accepted.
+/// Returns whether the submission contained a valid token, and came from the same site.
+pub fn check_submission(
+    method: &Method,
+    post_variables: &mut Vec<(String, String)>,
+    headers: &HeaderMap,
+    host: Option<&str>,
+    cookie: Option<&str>,
+) -> anyhow::Result<bool> {
+    let mut token = None;
+    post_variables.retain(|(name, value)| {
+        if name == TOKEN_FIELD {
+            token = Some(value.clone());
+        }
+        name != TOKEN_FIELD
+    });
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let changes_data = !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let cross_site = cross_site_rejection(header("sec-fetch-site"), header(ORIGIN.as_str()), host)
+        .filter(|_| changes_data || token.is_some());
+    let rejection = match (cross_site, &token) {
+        (Some(reason), _) => Some(reason),
+        (None, Some(token)) => token_rejection(token, cookie, chrono::Utc::now().timestamp()),
+        (None, None) => return Ok(false),
+    };
+    if let Some(reason) = rejection {
+        log::info!("Rejecting the submission of an action button: {reason}");
+        return Err(anyhow::Error::new(ErrorWithStatus {
+            status: StatusCode::FORBIDDEN,
+        })
+        .context(format!(
+            "This action was rejected, because {reason}. Please reload the page and try again."
+        )));
+    }
+    Ok(true)
+}
+
+fn cross_site_rejection(
+    sec_fetch_site: Option<&str>,
+    origin: Option<&str>,
+    host: Option<&str>,
+) -> Option<&'static str> {
+    if matches!(sec_fetch_site, Some("cross-site" | "same-site")) {
+        return Some("it was submitted from another site");
+    }
+    let origin_host = origin.map(|origin| origin.split_once("://").map_or(origin, |(_, h)| h));
+    match (origin_host, host) {
+        // Sent by sandboxed frames and pages that hide where they come from
+        (Some("null"), _) => Some("the page it was submitted from is unknown"),
+        (Some(origin_host), Some(host)) if !origin_host.eq_ignore_ascii_case(host) => {
+            Some("it was submitted from another site")
+        }
+        _ => None,
+    }
+}
+
+fn token_rejection(token: &str, cookie: Option<&str>, now: i64) -> Option<&'static str> {
+    let Some((issued_at, signature)) = token.split_once('.') else {
+        return Some("the token is invalid");
+    };
+    let Ok(issued_at) = issued_at.parse::<i64>() else {
+        return Some("the token is invalid");
+    };
+    let Some(secret) = cookie.filter(|secret| is_valid_secret(secret)) else {
+        return Some("the cookies of the browser were blocked or cleared");
+    };
+    if !form_protection::is_signed(&payload(issued_at, secret), signature) {
+        return Some(
+            "the token is invalid, was issued before the server restarted, or for another browser",
+        );
+    }
+    if now - issued_at > TOKEN_LIFETIME_SECONDS {
+        return Some("the page was loaded too long ago");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_rejection() {
+        let secret = BrowserSecret::from_cookie(None);
+        let cookie = Some(secret.value.as_str());
+        let token = secret.new_token();
+        let issued_at: i64 = token.split_once('.').unwrap().0.parse().unwrap();
+        assert_eq!(token_rejection(&token, cookie, issued_at), None);
+        assert_eq!(
+            token_rejection(&token, cookie, issued_at + TOKEN_LIFETIME_SECONDS + 1),
+            Some("the page was loaded too long ago")
+        );
+        let forged = format!("{}.{}", issued_at + 1000, token.split_once('.').unwrap().1);
+        assert!(token_rejection(&forged, cookie, issued_at).is_some());
+        assert!(token_rejection("garbage", cookie, issued_at).is_some());
+    }
+
+    #[test]
+    fn test_token_bound_to_browser() {
+        let secret = BrowserSecret::from_cookie(None);
+        let token = secret.new_token();
+        let now = chrono::Utc::now().timestamp();
+        let other_browser = BrowserSecret::from_cookie(None);
+        assert!(token_rejection(&token, Some(&other_browser.value), now).is_some());
+        assert!(token_rejection(&token, None, now).is_some());
+        assert!(BrowserSecret::default().new_token().is_empty());
+    }
+
+    #[test]
+    fn test_browser_secret_cookie() {
+        let secret = BrowserSecret::from_cookie(None);
+        let cookie = secret.new_cookie(true).unwrap();
+        assert_eq!(cookie.name(), COOKIE_NAME);
+        assert_eq!(cookie.http_only(), Some(true));
+        let existing = BrowserSecret::from_cookie(Some(cookie.value()));
+        assert_eq!(existing.value, secret.value);
+        assert!(existing.new_cookie(true).is_none());
+        let invalid = BrowserSecret::from_cookie(Some("<script>"));
+        assert!(invalid.new_cookie(true).is_some());
+    }
+
+    #[test]
+    fn test_cross_site_rejection() {
+        let host = Some("example.com");
+        assert_eq!(cross_site_rejection(None, None, host), None);
+        assert_eq!(cross_site_rejection(Some("same-origin"), None, host), None);
+        assert_eq!(
+            cross_site_rejection(None, Some("https://example.com"), host),
+            None
+        );
+        assert!(cross_site_rejection(Some("cross-site"), None, host).is_some());
+        assert!(cross_site_rejection(None, Some("https://evil.com"), host).is_some());
+        assert!(cross_site_rejection(None, Some("null"), host).is_some());
+    }
+
+    #[test]
+    fn test_check_submission() {
+        let secret = BrowserSecret::from_cookie(None);
+        let cookie = Some(secret.value.as_str());
+        let host = Some("example.com");
+        let mut post = vec![
+            ("id".to_string(), "1".to_string()),
+            (TOKEN_FIELD.to_string(), secret.new_token()),
+        ];
+        let no_headers = HeaderMap::new();
+        assert!(check_submission(&Method::POST, &mut post, &no_headers, host, cookie).unwrap());
+        assert_eq!(post, vec![("id".to_string(), "1".to_string())]);
+        assert!(!check_submission(&Method::POST, &mut post, &no_headers, host, cookie).unwrap());
+    }
+
+    #[test]
+    fn test_cross_site_submission_without_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ORIGIN,
+            actix_web::http::header::HeaderValue::from_static("https://evil.com"),
+        );
+        let host = Some("example.com");
+        let mut post = vec![("id".to_string(), "1".to_string())];
+        assert!(check_submission(&Method::POST, &mut post, &headers, host, None).is_err());
+        // Links from other sites are not submissions
+        assert!(!check_submission(&Method::GET, &mut Vec::new(), &headers, host, None).unwrap());
+    }
+}
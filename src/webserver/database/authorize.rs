@@ -0,0 +1,57 @@
+//! Access control shared by all the pages of a directory, with `authorize.sql` files.
+//!
+//! Before a page is executed, the `authorize.sql` files of its directory and of all the parent directories
+//! are executed, from the root of the site to the directory of the page, with the same request and connection.
+//! A file that returns no row lets the request continue, and the variables it sets remain available to the page.
+//! The first file that returns rows denies access to the page: its rows are rendered instead of the page,
+//! typically a `redirect` to a login page, or an error message with a 403 status.
+
+use super::execute_queries::{stream_query_results_with_conn, DbConn};
+use super::{DbItem, ParsedSqlFile};
+use crate::webserver::http_request_info::RequestInfo;
+use anyhow::Context;
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+/// Executes the authorization files in order, and returns the results of the first one that denies access.
+pub async fn run_authorization_files(
+    files: &[Arc<ParsedSqlFile>],
+    request: &mut RequestInfo,
+    db_connection: &mut DbConn,
+) -> anyhow::Result<Option<Vec<DbItem>>> {
+    // The query plans of the authorization files would be rows, that deny access to the page
+    let debug_queries = std::mem::replace(&mut request.debug_queries, false);
+    let mut result = Ok(None);
+    for file in files {
+        result = run_authorization_file(file, request, db_connection).await;
+        if !matches!(result, Ok(None)) {
+            break;
+        }
+    }
+    request.debug_queries = debug_queries;
+    result
+}
+
+async fn run_authorization_file(
+    file: &ParsedSqlFile,
+    request: &mut RequestInfo,
+    db_connection: &mut DbConn,
+) -> anyhow::Result<Option<Vec<DbItem>>> {
+    let mut results = std::pin::pin!(stream_query_results_with_conn(file, request, db_connection));
+    let mut items = Vec::new();
+    let mut denied = false;
+    while let Some(item) = results.next().await {
+        match item {
+            DbItem::Error(e) => {
+                return Err(e).with_context(|| format!("{} failed", file.source_path().display()))
+            }
+            DbItem::Row(_) => denied = true,
+            DbItem::FinishedQuery => {}
+        }
+        items.push(item);
+    }
+    if denied {
+        log::debug!("{:?} denied access to the page", file.source_path());
+    }
+    Ok(denied.then_some(items))
+}
@@ -1,12 +1,18 @@
-use std::{mem::take, time::Duration};
+use std::{
+    mem::take,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use super::Database;
+use super::credentials::{credentials_provider, renew_periodically};
+use super::{AcquireStats, Database};
 use crate::{app_config::AppConfig, ON_CONNECT_FILE, ON_RESET_FILE};
 use anyhow::Context;
 use futures_util::future::BoxFuture;
 use sqlx::{
     any::{Any, AnyConnectOptions, AnyKind},
     pool::PoolOptions,
+    postgres::PgConnectOptions,
     sqlite::{Function, SqliteFunctionCtx},
     ConnectOptions, Executor,
 };
@@ -14,12 +20,7 @@ use sqlx::{
 impl Database {
     pub async fn init(config: &AppConfig) -> anyhow::Result<Self> {
         let database_url = &config.database_url;
-        let mut connect_options: AnyConnectOptions = database_url
-            .parse()
-            .with_context(|| format!("\"{database_url}\" is not a valid database URL. Please change the \"database_url\" option in the configuration file."))?;
-        if let Some(password) = &config.database_password {
-            set_database_password(&mut connect_options, password);
-        }
+        let mut connect_options = connect_options(config)?;
         connect_options.log_statements(log::LevelFilter::Trace);
         connect_options.log_slow_statements(
             log::LevelFilter::Warn,
@@ -31,11 +32,22 @@ impl Database {
             database_url
         );
         set_custom_connect_options(&mut connect_options, config);
+        let credentials_provider = credentials_provider(config)?;
+        let mut credentials_validity = None;
+        let mut pool_connect_options = connect_options.clone();
+        if let Some(provider) = &credentials_provider {
+            let credentials = provider
+                .fetch()
+                .await
+                .context("Unable to fetch the database credentials")?;
+            credentials.apply(&mut pool_connect_options);
+            credentials_validity = credentials.valid_for;
+        }
         log::debug!("Connecting to database: {database_url}");
         let mut retries = config.database_connection_retries;
         let connection = loop {
             match Self::create_pool_options(config, connect_options.kind())
-                .connect_with(connect_options.clone())
+                .connect_with(pool_connect_options.clone())
                 .await
             {
                 Ok(c) => break c,
@@ -51,28 +63,45 @@ impl Database {
             }
         };
         log::debug!("Initialized database pool: {connection:#?}");
-        Ok(Database { connection })
+        let pool = Arc::new(RwLock::new(connection));
+        if let Some(provider) = credentials_provider {
+            renew_periodically(
+                Arc::clone(&pool),
+                provider,
+                connect_options.clone(),
+                config.clone(),
+                credentials_validity,
+            );
+        }
+        Ok(Database {
+            pool,
+            acquire_stats: AcquireStats::default(),
+        })
     }
 
-    fn create_pool_options(config: &AppConfig, db_kind: AnyKind) -> PoolOptions<Any> {
-        let mut pool_options = PoolOptions::new()
-            .max_connections(if let Some(max) = config.max_database_pool_connections {
-                max
-            } else {
-                // Different databases have a different number of max concurrent connections allowed by default
-                match db_kind {
-                    AnyKind::Postgres => 50,
-                    AnyKind::MySql => 75,
-                    AnyKind::Sqlite => {
-                        if config.database_url.contains(":memory:") {
-                            128
-                        } else {
-                            16
-                        }
-                    }
-                    AnyKind::Mssql => 100,
+    /// The maximum number of connections of the pool
+    pub(super) fn max_pool_connections(config: &AppConfig, db_kind: AnyKind) -> u32 {
+        if let Some(max) = config.max_database_pool_connections {
+            return max;
+        }
+        // Different databases have a different number of max concurrent connections allowed by default
+        match db_kind {
+            AnyKind::Postgres => 50,
+            AnyKind::MySql => 75,
+            AnyKind::Sqlite => {
+                if config.database_url.contains(":memory:") {
+                    128
+                } else {
+                    16
                 }
-            })
+            }
+            AnyKind::Mssql => 100,
+        }
+    }
+
+    pub(super) fn create_pool_options(config: &AppConfig, db_kind: AnyKind) -> PoolOptions<Any> {
+        let mut pool_options = PoolOptions::new()
+            .max_connections(Self::max_pool_connections(config, db_kind))
             .idle_timeout(
                 config
                     .database_connection_idle_timeout_seconds
@@ -88,7 +117,7 @@ impl Database {
                     .map(Duration::from_secs_f64)
                     .or_else(|| match db_kind {
                         AnyKind::Sqlite => None,
-                        _ => Some(Duration::from_secs(60 * 60)),
+                        _ => Some(Duration::from_secs(3600)),
                     }),
             )
             .acquire_timeout(Duration::from_secs_f64(
@@ -101,32 +130,48 @@ impl Database {
 }
 
 fn add_on_return_to_pool(config: &AppConfig, pool_options: PoolOptions<Any>) -> PoolOptions<Any> {
+    // Connections that were used with the role of a user must not be reused with it by the next request
+    let reset_role = config.database_role_query.is_some();
+    let sql = read_on_reset_file(config);
+    if !reset_role && sql.is_none() {
+        return pool_options;
+    }
+    pool_options
+        .after_release(move |conn, meta| on_return_to_pool(conn, meta, reset_role, sql.clone()))
+}
+
+fn read_on_reset_file(config: &AppConfig) -> Option<std::sync::Arc<String>> {
     let on_disconnect_file = config.configuration_directory.join(ON_RESET_FILE);
     if !on_disconnect_file.exists() {
         log::debug!("Not creating a custom SQL connection cleanup handler because {on_disconnect_file:?} does not exist");
-        return pool_options;
+        return None;
     }
     log::info!("Creating a custom SQL connection cleanup handler from {on_disconnect_file:?}");
     let sql = match std::fs::read_to_string(&on_disconnect_file) {
         Ok(sql) => std::sync::Arc::new(sql),
         Err(e) => {
             log::error!("Unable to read the file {on_disconnect_file:?}: {e}");
-            return pool_options;
+            return None;
         }
     };
     log::trace!("The custom SQL connection cleanup handler is:\n{sql}");
-    let sql = sql.clone();
-    pool_options
-        .after_release(move |conn, meta| on_return_to_pool(conn, meta, std::sync::Arc::clone(&sql)))
+    Some(sql)
 }
 
 fn on_return_to_pool(
     conn: &mut sqlx::AnyConnection,
     meta: sqlx::pool::PoolConnectionMetadata,
-    sql: std::sync::Arc<String>,
+    reset_role: bool,
+    sql: Option<std::sync::Arc<String>>,
 ) -> BoxFuture<'_, Result<bool, sqlx::Error>> {
     use sqlx::Row;
     Box::pin(async move {
+        if reset_role {
+            conn.execute("RESET ROLE").await?;
+        }
+        let Some(sql) = sql else {
+            return Ok(true);
+        };
         log::trace!("Running the custom SQL connection cleanup handler. {meta:?}");
         let query_result = conn.fetch_optional(sql.as_str()).await?;
         if let Some(query_result) = query_result {
@@ -169,6 +214,25 @@ fn add_on_connection_handler(
     })
 }
 
+fn connect_options(config: &AppConfig) -> anyhow::Result<AnyConnectOptions> {
+    let database_url = &config.database_url;
+    let mut connect_options: AnyConnectOptions = database_url
+        .parse()
+        .with_context(|| format!("\"{database_url}\" is not a valid database URL. Please change the \"database_url\" option in the configuration file."))?;
+    if let Some(password) = &config.database_password {
+        set_database_password(&mut connect_options, password);
+    }
+    Ok(connect_options)
+}
+
+/// Options to open a connection to the database outside of the pool, when it is a `PostgreSQL` database.
+/// Credentials from `database_credentials` are not applied to these connections.
+pub(crate) fn postgres_connect_options(
+    config: &AppConfig,
+) -> anyhow::Result<Option<PgConnectOptions>> {
+    Ok(connect_options(config)?.as_postgres().cloned())
+}
+
 fn set_custom_connect_options(options: &mut AnyConnectOptions, config: &AppConfig) {
     if let Some(sqlite_options) = options.as_sqlite_mut() {
         for extension_name in &config.sqlite_extensions {
@@ -0,0 +1,222 @@
+//! Execution of the queries typed in the SQL console of the administration pages.
+
+use super::sql::dialect_for_db;
+use super::sql_to_json::row_to_json;
+use super::Database;
+use crate::app_config::AppConfig;
+use anyhow::Context;
+use futures_util::StreamExt;
+use serde_json::Value as JsonValue;
+use sqlparser::ast::Statement;
+use sqlparser::parser::Parser;
+use sqlx::any::{AnyConnection, AnyKind};
+use sqlx::{Connection, Either, Executor};
+
+/// Maximum number of rows returned for a single statement
+pub const MAX_CONSOLE_ROWS: usize = 1000;
+
+/// The result of one of the statements of a console query
+#[derive(Debug, Default)]
+pub struct StatementResult {
+    pub rows: Vec<JsonValue>,
+    /// Whether some rows were dropped because the statement returned more than [`MAX_CONSOLE_ROWS`]
+    pub truncated: bool,
+    pub rows_affected: u64,
+}
+
+/// Runs the statements of a console query, with the safety options of the configuration:
+/// statements that are not in `admin_sql_console_allowed_statements` are rejected before anything runs,
+/// and in `admin_sql_console_read_only` mode, the queries run in a read-only transaction that is rolled back.
+pub async fn run_console_query(
+    db: &Database,
+    config: &AppConfig,
+    sql: &str,
+) -> anyhow::Result<Vec<StatementResult>> {
+    let kind = db.connection().any_kind();
+    let read_only = config.admin_sql_console_read_only;
+    let sql = if config.admin_sql_console_allowed_statements.is_empty() && !read_only {
+        sql.to_string()
+    } else {
+        // The statements are run as they were parsed, so that what is checked is what is executed
+        checked_statements(kind, sql, config)?.join(";\n")
+    };
+    let mut connection = db.connection().acquire().await?;
+    let read_only_setting = match kind {
+        AnyKind::Sqlite => Some("PRAGMA query_only = ON"),
+        // Applies to the next transaction of the session
+        AnyKind::MySql => Some("SET TRANSACTION READ ONLY"),
+        _ => None,
+    };
+    if let Some(setting) = read_only_setting.filter(|_| read_only) {
+        connection.execute(setting).await?;
+    }
+    let results = run_statements(&mut connection, kind, config, &sql).await;
+    if read_only && kind == AnyKind::Sqlite {
+        if let Err(e) = connection.execute("PRAGMA query_only = OFF").await {
+            log::error!("Unable to make the SQL console connection writable again: {e}");
+            // The connection is closed instead of being returned to the pool read-only
+            drop(connection.detach());
+        }
+    }
+    results
+}
+
+async fn run_statements(
+    connection: &mut AnyConnection,
+    kind: AnyKind,
+    config: &AppConfig,
+    sql: &str,
+) -> anyhow::Result<Vec<StatementResult>> {
+    let mut transaction = connection.begin().await?;
+    if config.admin_sql_console_read_only && kind == AnyKind::Postgres {
+        transaction.execute("SET TRANSACTION READ ONLY").await?;
+    }
+    let mut results = Vec::new();
+    let mut current = StatementResult::default();
+    {
+        let mut stream = transaction.fetch_many(sql);
+        while let Some(item) = stream.next().await {
+            match item? {
+                Either::Left(query_result) => {
+                    current.rows_affected = query_result.rows_affected();
+                    results.push(std::mem::take(&mut current));
+                }
+                Either::Right(row) if current.rows.len() < MAX_CONSOLE_ROWS => {
+                    current.rows.push(row_to_json(&row, config));
+                }
+                Either::Right(_) => current.truncated = true,
+            }
+        }
+    }
+    if !current.rows.is_empty() {
+        results.push(current);
+    }
+    if config.admin_sql_console_read_only {
+        transaction.rollback().await?;
+    } else {
+        transaction.commit().await?;
+    }
+    Ok(results)
+}
+
+/// Parses the given sql, and returns its statements if the configuration allows all of them
+fn checked_statements(kind: AnyKind, sql: &str, config: &AppConfig) -> anyhow::Result<Vec<String>> {
+    let statements = Parser::parse_sql(&*dialect_for_db(kind), sql).context(
+        "Unable to parse the query, which is required to check the statements of the SQL console",
+    )?;
+    if config.admin_sql_console_read_only {
+        if let Some(statement) = statements.iter().find(|s| !is_allowed_in_read_only(s)) {
+            anyhow::bail!(
+                "This statement is not allowed in the read-only SQL console: {statement}"
+            );
+        }
+    }
+    let statements: Vec<String> = statements.iter().map(ToString::to_string).collect();
+    if !config.admin_sql_console_allowed_statements.is_empty() {
+        allowed_statements(&statements, &config.admin_sql_console_allowed_statements)?;
+    }
+    Ok(statements)
+}
+
+/// Transaction control, schema changes and procedure calls could end or escape the
+/// read-only transaction, so only queries and data changes are accepted in read-only mode.
+fn is_allowed_in_read_only(statement: &Statement) -> bool {
+    matches!(
+        statement,
+        Statement::Query(_)
+            | Statement::Insert(_)
+            | Statement::Update { .. }
+            | Statement::Delete(_)
+            | Statement::Explain { .. }
+            | Statement::ExplainTable { .. }
+            | Statement::ShowTables { .. }
+            | Statement::ShowColumns { .. }
+    )
+}
+
+/// Checks that all the statements start with one of the allowed keywords
+fn allowed_statements(statements: &[String], allowed_keywords: &[String]) -> anyhow::Result<()> {
+    for statement in statements {
+        let keyword = statement
+            .trim_start_matches('(')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        anyhow::ensure!(
+            allowed_keywords
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(keyword)),
+            "{keyword} statements are not allowed in the SQL console. Allowed statements: {}",
+            allowed_keywords.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+
+    #[test]
+    fn test_allowed_statements() {
+        let mut config = test_config();
+        config.admin_sql_console_read_only = false;
+        config.admin_sql_console_allowed_statements = vec!["select".into(), "EXPLAIN".into()];
+        let statements =
+            checked_statements(AnyKind::Sqlite, "SELECT 1; explain select 2", &config).unwrap();
+        assert_eq!(statements, ["SELECT 1", "EXPLAIN SELECT 2"]);
+        let err = checked_statements(AnyKind::Sqlite, "SELECT 1; DROP TABLE x", &config)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("DROP statements are not allowed"), "{err}");
+    }
+
+    #[test]
+    fn test_read_only_statements() {
+        let mut config = test_config();
+        config.admin_sql_console_read_only = true;
+        for sql in [
+            "SELECT 1; COMMIT",
+            "ROLLBACK; DELETE FROM x",
+            "BEGIN",
+            "PRAGMA query_only = 0",
+            "CREATE TABLE x (y INT)",
+            "DROP TABLE x",
+        ] {
+            let err = checked_statements(AnyKind::Sqlite, sql, &config).unwrap_err();
+            assert!(
+                err.to_string().contains("not allowed in the read-only"),
+                "{sql}: {err}"
+            );
+        }
+        checked_statements(AnyKind::Sqlite, "SELECT 1; UPDATE x SET y = 1", &config).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_read_only_console() -> anyhow::Result<()> {
+        let mut config = test_config();
+        config.admin_sql_console_read_only = true;
+        let db = Database::init(&config).await?;
+        db.connection()
+            .execute("CREATE TABLE IF NOT EXISTS sqlpage_console_test (x INT)")
+            .await?;
+        let insert = "INSERT INTO sqlpage_console_test (x) VALUES (1), (2)";
+        run_console_query(&db, &config, insert)
+            .await
+            .expect_err("the database should refuse writes in a read-only console");
+        let results = run_console_query(
+            &db,
+            &config,
+            "SELECT COUNT(*) AS n FROM sqlpage_console_test",
+        )
+        .await?;
+        assert_eq!(results[0].rows[0]["n"], 0);
+        // The connection is writable again once the console query is done
+        db.connection().execute(insert).await?;
+        db.connection()
+            .execute("DROP TABLE sqlpage_console_test")
+            .await?;
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use anyhow::Context;
@@ -146,6 +147,7 @@ pub(super) async fn run_csv_import(
     csv_import: &CsvImport,
     request: &RequestInfo,
 ) -> anyhow::Result<()> {
+    request.budget.count_statement()?;
     let named_temp_file = &request
         .uploaded_files
         .get(&csv_import.uploaded_file)
@@ -162,7 +164,7 @@ pub(super) async fn run_csv_import(
     let file_path = named_temp_file.path();
     let file_name = file_path.file_name().unwrap_or_default();
     let file = tokio::fs::File::open(file_path).await.with_context(|| {
-        format!("The CSV file {file_name:?} was uploaded correctly, but could not be opened",)
+        format!("The CSV file {file_name:?} was uploaded correctly, but could not be opened")
     })?;
     let buffered = tokio::io::BufReader::new(file);
     // private_get_mut is not supposed to be used outside of sqlx, but it is the only way to
@@ -222,7 +224,10 @@ async fn run_csv_import_insert(
     let mut records = reader.into_records();
     while let Some(record) = records.next().await {
         let r = record.with_context(|| "reading csv record")?;
-        process_csv_record(r, db, &insert_stmt, csv_import, &col_idxs).await?;
+        let line = r.position().map_or(0, csv_async::Position::line);
+        process_csv_record(r, db, &insert_stmt, csv_import, &col_idxs)
+            .await
+            .with_context(|| format!("Unable to import line {line} of the CSV file"))?;
     }
     Ok(())
 }
@@ -311,6 +316,182 @@ fn make_csv_reader<R: AsyncRead + Unpin + Send>(
         .create_reader(file)
 }
 
+/// Options of the `sqlpage.read_csv` function
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct CsvReadOptions {
+    /// Detected from the first line when not set
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    /// When false, rows are returned as arrays instead of objects
+    pub header: Option<bool>,
+    /// A label such as `utf-8` or `windows-1252`. Detected from the contents of the file when not set
+    pub encoding: Option<String>,
+}
+
+/// Characters that are tried as delimiters when the delimiter is not set
+const DELIMITER_CANDIDATES: [char; 4] = [',', ';', '\t', '|'];
+
+/// Parses a CSV file into a json array, with one object per row, whose keys are the column names of the header.
+/// Errors contain the number of the line that could not be read.
+pub(super) async fn csv_to_json(
+    bytes: &[u8],
+    options: &CsvReadOptions,
+) -> anyhow::Result<serde_json::Value> {
+    let text = decode_text(bytes, options.encoding.as_deref())?;
+    let delimiter = options.delimiter.unwrap_or_else(|| detect_delimiter(&text));
+    let has_headers = options.header.unwrap_or(true);
+    let mut reader = csv_async::AsyncReaderBuilder::new()
+        .delimiter(ascii_byte("delimiter", delimiter)?)
+        .quote(ascii_byte("quote", options.quote.unwrap_or('"'))?)
+        .has_headers(has_headers)
+        .create_reader(text.as_bytes());
+    let headers = if has_headers {
+        Some(
+            reader
+                .headers()
+                .await
+                .context("Unable to read the header of the CSV file")?
+                .clone(),
+        )
+    } else {
+        None
+    };
+    let mut rows = Vec::new();
+    let mut records = reader.into_records();
+    while let Some(record) = records.next().await {
+        let record = record.map_err(|e| match e.kind() {
+            csv_async::ErrorKind::UnequalLengths {
+                pos: Some(pos),
+                expected_len,
+                len,
+            } => anyhow::anyhow!(
+                "Line {} of the CSV file has {len} fields, but the first line has {expected_len}",
+                pos.line()
+            ),
+            _ => anyhow::Error::new(e).context("Unable to read the CSV file"),
+        })?;
+        let row = match &headers {
+            Some(headers) => serde_json::Value::Object(
+                headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(name, value)| (name.to_owned(), value.into()))
+                    .collect(),
+            ),
+            None => record.iter().collect(),
+        };
+        rows.push(row);
+    }
+    Ok(serde_json::Value::Array(rows))
+}
+
+fn ascii_byte(option: &str, c: char) -> anyhow::Result<u8> {
+    u8::try_from(c)
+        .ok()
+        .filter(u8::is_ascii)
+        .with_context(|| format!("The CSV {option} must be an ASCII character, not {c:?}"))
+}
+
+/// Decodes the file with the given encoding, or detects it: files that start with a byte order mark
+/// are in UTF-8 or UTF-16, valid UTF-8 files are in UTF-8, and other files are assumed to be in Windows-1252,
+/// the default encoding of spreadsheets exported on Windows.
+fn decode_text<'a>(bytes: &'a [u8], encoding: Option<&str>) -> anyhow::Result<Cow<'a, str>> {
+    let encoding = match encoding {
+        Some(label) => encoding_rs::Encoding::for_label(label.as_bytes())
+            .with_context(|| format!("Unknown CSV encoding: {label:?}"))?,
+        None if encoding_rs::Encoding::for_bom(bytes).is_some()
+            || std::str::from_utf8(bytes).is_ok() =>
+        {
+            encoding_rs::UTF_8
+        }
+        None => encoding_rs::WINDOWS_1252,
+    };
+    // The byte order mark, if there is one, overrides the encoding and is removed
+    let (text, _encoding, had_errors) = encoding.decode(bytes);
+    anyhow::ensure!(
+        !had_errors,
+        "The CSV file is not valid {} text",
+        encoding.name()
+    );
+    Ok(text)
+}
+
+/// Finds the candidate delimiter that appears the most in the first line, outside of quotes
+fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut counts = [0; DELIMITER_CANDIDATES.len()];
+    let mut in_quotes = false;
+    for c in first_line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(i) = DELIMITER_CANDIDATES.iter().position(|&d| d == c) {
+                counts[i] += 1;
+            }
+        }
+    }
+    let (best, &count) = counts
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|&(_, count)| count)
+        .unwrap_or((0, &0));
+    if count == 0 {
+        ','
+    } else {
+        DELIMITER_CANDIDATES[best]
+    }
+}
+
+#[test]
+fn test_detect_delimiter() {
+    assert_eq!(detect_delimiter("a,b,c\n1,2,3"), ',');
+    assert_eq!(detect_delimiter("name;\"price, in €\";qty"), ';');
+    assert_eq!(detect_delimiter("a\tb"), '\t');
+    assert_eq!(detect_delimiter("single column"), ',');
+}
+
+#[test]
+fn test_decode_text() {
+    assert_eq!(
+        decode_text(b"\xEF\xBB\xBFcaf\xC3\xA9", None).unwrap(),
+        "café"
+    );
+    assert_eq!(decode_text(b"caf\xE9", None).unwrap(), "café");
+    assert_eq!(decode_text(b"\xFF\xFEc\0a\0", None).unwrap(), "ca");
+    assert_eq!(decode_text(b"caf\xE9", Some("latin1")).unwrap(), "café");
+    assert!(decode_text(b"x", Some("klingon")).is_err());
+}
+
+#[actix_web::test]
+async fn test_csv_to_json() {
+    let csv = "\u{FEFF}name;price\nbook;12\n\"pen; blue\";1.5\n";
+    let options = CsvReadOptions::default();
+    assert_eq!(
+        csv_to_json(csv.as_bytes(), &options).await.unwrap(),
+        serde_json::json!([
+            {"name": "book", "price": "12"},
+            {"name": "pen; blue", "price": "1.5"}
+        ])
+    );
+    let options = CsvReadOptions {
+        header: Some(false),
+        ..CsvReadOptions::default()
+    };
+    assert_eq!(
+        csv_to_json(b"a,b\nc,d", &options).await.unwrap(),
+        serde_json::json!([["a", "b"], ["c", "d"]])
+    );
+    let error = csv_to_json(b"a,b\n1,2\n3\n", &CsvReadOptions::default())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Line 3 of the CSV file has 1 fields, but the first line has 2"
+    );
+}
+
 #[test]
 fn test_make_statement() {
     let csv_import = CsvImport {
@@ -1,26 +1,33 @@
 use anyhow::{anyhow, Context};
+use futures_util::future::LocalBoxFuture;
 use futures_util::stream::Stream;
 use futures_util::StreamExt;
 use serde_json::Value;
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::path::Path;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 use super::csv_import::run_csv_import;
 use super::error_highlighting::display_stmt_db_error;
+use super::query_debug::{self, StatementDebugInfo};
+use super::slow_query_log;
 use super::sql::{
     DelayedFunctionCall, ParsedSqlFile, ParsedStatement, SimpleSelectValue, StmtWithParams,
 };
+use crate::app_config::{AppConfig, OnError};
 use crate::dynamic_component::parse_dynamic_rows;
 use crate::utils::add_value_to_map;
 use crate::webserver::database::sql_to_json::row_to_string;
 use crate::webserver::http::SingleOrVec;
 use crate::webserver::http_request_info::RequestInfo;
+use crate::webserver::ErrorWithStatus;
 
 use super::syntax_tree::{extract_req_param, StmtParam};
 use super::{error_highlighting::display_db_error, Database, DbItem};
-use sqlx::any::{AnyArguments, AnyQueryResult, AnyRow, AnyStatement, AnyTypeInfo};
+use sqlx::any::{AnyArguments, AnyKind, AnyQueryResult, AnyRow, AnyStatement, AnyTypeInfo};
 use sqlx::pool::PoolConnection;
 use sqlx::{
     Any, AnyConnection, Arguments, Column, Either, Executor, Row as _, Statement, ValueRef,
@@ -34,7 +41,7 @@ impl Database {
         query: &str,
         param_types: &[AnyTypeInfo],
     ) -> anyhow::Result<AnyStatement<'static>> {
-        self.connection
+        self.connection()
             .prepare_with(query, param_types)
             .await
             .map(|s| s.to_owned())
@@ -49,7 +56,13 @@ pub fn stream_query_results_with_conn<'a>(
 ) -> impl Stream<Item = DbItem> + 'a {
     let source_file = &sql_file.source_path;
     async_stream::try_stream! {
-        for res in &sql_file.statements {
+        let mut debug_infos = Vec::new();
+        let mut on_error = request.app_state.config().on_error;
+        let max_depth = request.app_state.config().max_recursion_depth;
+        let mut optional = false;
+        let mut statements = sql_file.statements.iter();
+        'statements: while let Some(res) = statements.next() {
+            let optional_statement = std::mem::take(&mut optional);
             match res {
                 ParsedStatement::CsvImport(csv_import) => {
                     let connection = take_connection(&request.app_state.db, db_connection).await?;
@@ -57,47 +70,358 @@ pub fn stream_query_results_with_conn<'a>(
                     run_csv_import(connection, csv_import, request).await.with_context(|| format!("Failed to import the CSV file {:?} into the table {:?}", csv_import.uploaded_file, csv_import.table_name))?;
                 },
                 ParsedStatement::StmtWithParams(stmt) => {
-                    let query = bind_parameters(stmt, request, db_connection).await?;
+                    request.budget.count_statement()?;
+                    let mut query = bind_parameters(stmt, request, db_connection).await?;
+                    let (sql, parameter_shapes) = (query.sql, std::mem::take(&mut query.parameter_shapes));
+                    let plan = explain_if_debugging(request, db_connection, &mut query).await?;
                     let connection = take_connection(&request.app_state.db, db_connection).await?;
+                    remember_session(request, connection).await;
+                    let savepoint = optional_statement && request.in_transaction;
+                    if savepoint {
+                        create_savepoint(connection).await?;
+                    }
                     log::trace!("Executing query {:?}", query.sql);
+                    // Only the time spent waiting for the database is measured,
+                    // not the time during which the rows are rendered and sent to a slow client
+                    let mut duration = Duration::ZERO;
                     let mut stream = connection.fetch_many(query);
                     let mut error = None;
-                    while let Some(elem) = stream.next().await {
-                        let mut query_result = parse_single_sql_result(source_file, stmt, elem);
+                    while let Some(elem) = timed(&mut duration, stream.next()).await {
+                        let mut query_result = parse_single_sql_result(source_file, stmt, elem, &request.app_state.config());
                         if let DbItem::Error(e) = query_result {
                             error = Some(e);
                             break;
                         }
                         apply_json_columns(&mut query_result, &stmt.json_columns);
                         apply_delayed_functions(request, &stmt.delayed_functions, &mut query_result).await?;
-                        for db_item in parse_dynamic_rows(query_result) {
+                        for db_item in count_rows(request, parse_dynamic_rows(query_result, max_depth))? {
                             yield db_item;
                         }
                     }
                     drop(stream);
-                    if let Some(error) = error {
+                    let app_state = &request.app_state;
+                    slow_query_log::record(&app_state.db, &app_state.config(), source_file, sql, &parameter_shapes, duration);
+                    if let Some(plan) = plan {
+                        debug_infos.push(StatementDebugInfo { source_file: source_file.clone(), sql: sql.to_string(), parameter_shapes, plan, duration });
+                    }
+                    if savepoint {
+                        end_savepoint(connection, error.is_some()).await;
+                    }
+                    if let Some(error) = error.filter(|e| !ignore_optional_error(e, optional_statement)) {
                         try_rollback_transaction(connection).await;
-                        yield DbItem::Error(error);
+                        yield DbItem::Error(with_error_status(error, on_error));
+                        if !on_error.continues() {
+                            break 'statements;
+                        }
                     }
                 },
                 ParsedStatement::SetVariable { variable, value} => {
                     execute_set_variable_query(db_connection, request, variable, value, source_file).await
-                    .with_context(||
-                        format!("Failed to set the {variable} variable to {value:?}")
-                    )?;
+                        .with_context(|| format!("Failed to set the {variable} variable to {value:?}"))?;
                 },
                 ParsedStatement::StaticSimpleSelect(value) => {
-                    for i in parse_dynamic_rows(DbItem::Row(exec_static_simple_select(value, request, db_connection).await?)) {
+                    let row = DbItem::Row(exec_static_simple_select(value, request, db_connection).await?);
+                    for i in count_rows(request, parse_dynamic_rows(row, max_depth))? {
                         yield i;
                     }
                 }
-                ParsedStatement::Error(e) => yield DbItem::Error(clone_anyhow_err(source_file, e)),
+                ParsedStatement::OnError(policy) => on_error = *policy,
+                ParsedStatement::Optional => optional = true,
+                ParsedStatement::ConcurrentGroup(count) if can_run_concurrently(request) => {
+                    request.budget.count_statements(*count)?;
+                    let group = statements.by_ref().take(*count);
+                    let items = execute_concurrently(group, request, db_connection, source_file, on_error).await?;
+                    for item in count_rows(request, items)? {
+                        let failed = matches!(item, DbItem::Error(_));
+                        yield item;
+                        if failed && !on_error.continues() {
+                            break 'statements;
+                        }
+                    }
+                }
+                // The statements of the group are executed one after the other
+                ParsedStatement::ConcurrentGroup(_) | ParsedStatement::Concurrent(_) => {}
+                ParsedStatement::Error(e) => {
+                    yield DbItem::Error(with_error_status(clone_anyhow_err(source_file, e), on_error));
+                    if !on_error.continues() {
+                        break 'statements;
+                    }
+                }
+            }
+        }
+        if request.debug_queries {
+            for row in query_debug::debug_rows(&debug_infos) {
+                yield DbItem::Row(row);
             }
         }
     }
     .map(|res| res.unwrap_or_else(DbItem::Error))
 }
 
+/// Adds the time it takes for the future to complete to `total`
+async fn timed<F: std::future::Future>(total: &mut Duration, future: F) -> F::Output {
+    let started_at = Instant::now();
+    let output = future.await;
+    *total += started_at.elapsed();
+    output
+}
+
+/// Counts the rows in the [`RequestBudget`](super::request_budget::RequestBudget) of the request, before they are sent
+fn count_rows(
+    request: &RequestInfo,
+    items: impl IntoIterator<Item = DbItem>,
+) -> anyhow::Result<Vec<DbItem>> {
+    items
+        .into_iter()
+        .map(|item| {
+            if matches!(item, DbItem::Row(_)) {
+                request.budget.count_row()?;
+            }
+            Ok(item)
+        })
+        .collect()
+}
+
+/// The query plan of the statement, when the request displays them.
+/// The future is boxed so that it does not grow the stack of recursive `run_sql` calls.
+fn explain_if_debugging<'a>(
+    request: &'a RequestInfo,
+    db_connection: &'a mut DbConn,
+    query: &'a mut StatementWithParams<'_>,
+) -> LocalBoxFuture<'a, anyhow::Result<Option<String>>> {
+    Box::pin(async move {
+        if !request.debug_queries {
+            return Ok(None);
+        }
+        let values = std::mem::take(&mut query.debug_values);
+        let app_state = &request.app_state;
+        let connection = take_connection(&app_state.db, db_connection).await?;
+        // A failed EXPLAIN must not abort the transaction of the request,
+        // and rolling back to the savepoint is harmless since EXPLAIN does not change anything
+        if request.in_transaction {
+            create_savepoint(connection).await?;
+        }
+        let plan = query_debug::explain(connection, &app_state.config(), query.sql, values).await;
+        if request.in_transaction {
+            end_savepoint(connection, true).await;
+        }
+        Ok(Some(plan))
+    })
+}
+
+/// Separate connections do not share the transaction, the database role,
+/// or the query plans of the connection of the request.
+/// With `SQLite`, each connection to an in-memory database has its own database,
+/// or locks the tables shared with the other connections.
+fn can_run_concurrently(request: &RequestInfo) -> bool {
+    let config = request.app_state.config();
+    let in_memory_sqlite = request.app_state.db.connection().any_kind() == AnyKind::Sqlite
+        && (config.database_url.contains(":memory:")
+            || config.database_url.contains("mode=memory"));
+    !request.in_transaction
+        && !request.debug_queries
+        && config.database_role_query.is_none()
+        && !in_memory_sqlite
+}
+
+fn ignore_optional_error(error: &anyhow::Error, optional_statement: bool) -> bool {
+    if optional_statement {
+        log::warn!("Ignoring the error of an optional statement: {error:#}");
+    }
+    optional_statement
+}
+
+/// Executes independent queries at the same time, each on its own connection from the pool.
+/// Returns the items produced by the queries, in order.
+async fn execute_concurrently<'a>(
+    group: impl Iterator<Item = &'a ParsedStatement>,
+    request: &RequestInfo,
+    db_connection: &mut DbConn,
+    source_file: &Path,
+    on_error: OnError,
+) -> anyhow::Result<Vec<DbItem>> {
+    let group: Vec<&StmtWithParams> = group
+        .filter_map(|stmt| match stmt {
+            ParsedStatement::StmtWithParams(stmt) => Some(stmt),
+            _ => None,
+        })
+        .collect();
+    let mut queries = Vec::with_capacity(group.len());
+    for stmt in &group {
+        queries.push(bind_parameters(stmt, request, db_connection).await?);
+    }
+    let db = &request.app_state.db;
+    let config = &*request.app_state.config();
+    let max_connections = config.max_concurrent_queries_per_request.min(queries.len());
+    let mut connections = acquire_free_connections(db, config, max_connections).await;
+    let rows_left = Cell::new(request.budget.remaining_rows());
+    let fetch = Fetch {
+        source_file,
+        db,
+        config,
+        rows_left: &rows_left,
+    };
+    let results = if connections.is_empty() {
+        log::debug!(
+            "No free database connection: executing {} queries one after the other",
+            queries.len()
+        );
+        let connection = take_connection(db, db_connection).await?;
+        let mut results = Vec::with_capacity(queries.len());
+        for (stmt, query) in group.iter().zip(queries) {
+            results.push(fetch.all_items(connection, stmt, query).await);
+        }
+        results
+    } else {
+        log::debug!(
+            "Executing {} queries concurrently on {} connections",
+            queries.len(),
+            connections.len()
+        );
+        let queries = group.iter().copied().zip(queries).collect();
+        fetch_on_connections(&mut connections, queries, &fetch).await
+    };
+    let mut items = Vec::new();
+    for (stmt, results) in group.iter().zip(results) {
+        for mut query_result in results {
+            if let DbItem::Error(error) = query_result {
+                items.push(DbItem::Error(with_error_status(error, on_error)));
+                continue;
+            }
+            apply_json_columns(&mut query_result, &stmt.json_columns);
+            apply_delayed_functions(request, &stmt.delayed_functions, &mut query_result).await?;
+            items.extend(parse_dynamic_rows(query_result, config.max_recursion_depth));
+        }
+    }
+    Ok(items)
+}
+
+/// Takes up to `count` connections that can be used without waiting for the ones in use:
+/// idle connections of the pool, or new connections while the pool is not full.
+/// A page that already holds a connection thus never waits for other requests to release theirs.
+async fn acquire_free_connections(
+    db: &Database,
+    config: &AppConfig,
+    count: usize,
+) -> Vec<PoolConnection<sqlx::Any>> {
+    let pool = db.connection();
+    let max_size = Database::max_pool_connections(config, pool.any_kind());
+    let mut connections = Vec::with_capacity(count);
+    while connections.len() < count {
+        let connection = match pool.try_acquire() {
+            Some(connection) => connection,
+            None if pool.size() < max_size => match pool.acquire().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::debug!("Unable to open a connection for concurrent queries: {e}");
+                    break;
+                }
+            },
+            None => break,
+        };
+        connections.push(connection);
+    }
+    connections
+}
+
+/// Distributes the queries between the connections, which execute their queries one after the other.
+/// Returns the items produced by each query, in the order of the queries.
+async fn fetch_on_connections(
+    connections: &mut [PoolConnection<sqlx::Any>],
+    queries: Vec<(&StmtWithParams, StatementWithParams<'_>)>,
+    fetch: &Fetch<'_>,
+) -> Vec<Vec<DbItem>> {
+    let mut assigned: Vec<Vec<_>> = connections.iter().map(|_| Vec::new()).collect();
+    let query_count = queries.len();
+    for (index, query) in queries.into_iter().enumerate() {
+        assigned[index % connections.len()].push((index, query));
+    }
+    let executions = connections
+        .iter_mut()
+        .zip(assigned)
+        .map(|(connection, queries)| async move {
+            let mut results = Vec::with_capacity(queries.len());
+            for (index, (stmt, query)) in queries {
+                let items = fetch.all_items(connection, stmt, query).await;
+                results.push((index, items));
+            }
+            results
+        });
+    let mut results = Vec::with_capacity(query_count);
+    results.extend(
+        futures_util::future::join_all(executions)
+            .await
+            .into_iter()
+            .flatten(),
+    );
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, items)| items).collect()
+}
+
+/// What the queries of a concurrent group share while they collect their results
+struct Fetch<'a> {
+    source_file: &'a Path,
+    db: &'a Database,
+    config: &'a AppConfig,
+    /// Rows that can still be collected before the request exceeds `max_rows_per_request`
+    rows_left: &'a Cell<u64>,
+}
+
+impl Fetch<'_> {
+    /// Executes a query and collects its results, until the first error.
+    /// Once the rows of all the queries exceed the budget of the request, the query stops,
+    /// and its last row makes [`count_rows`] return the error of the budget.
+    async fn all_items(
+        &self,
+        connection: &mut PoolConnection<sqlx::Any>,
+        stmt: &StmtWithParams,
+        mut query: StatementWithParams<'_>,
+    ) -> Vec<DbItem> {
+        let (sql, parameter_shapes) = (query.sql, std::mem::take(&mut query.parameter_shapes));
+        let started_at = Instant::now();
+        let mut items = Vec::new();
+        let mut failed = false;
+        let mut stream = connection.fetch_many(query);
+        while let Some(elem) = stream.next().await {
+            let item = parse_single_sql_result(self.source_file, stmt, elem, self.config);
+            failed = matches!(item, DbItem::Error(_));
+            let is_row = matches!(item, DbItem::Row(_));
+            items.push(item);
+            if failed {
+                break;
+            }
+            if is_row {
+                if self.rows_left.get() == 0 {
+                    log::debug!("Stopping a concurrent query that exceeded max_rows_per_request");
+                    break;
+                }
+                self.rows_left.set(self.rows_left.get() - 1);
+            }
+        }
+        drop(stream);
+        slow_query_log::record(
+            self.db,
+            self.config,
+            self.source_file,
+            sql,
+            &parameter_shapes,
+            started_at.elapsed(),
+        );
+        if failed {
+            try_rollback_transaction(connection).await;
+        }
+        items
+    }
+}
+
+/// Attaches the HTTP status code of an `on_error` policy to the error of a failed statement
+fn with_error_status(error: anyhow::Error, on_error: OnError) -> anyhow::Error {
+    match on_error {
+        OnError::Status(status) => error.context(ErrorWithStatus { status }),
+        OnError::Continue | OnError::Abort => error,
+    }
+}
+
 /// Transforms a stream of database items to stop processing after encountering the first error.
 /// The error item itself is still emitted before stopping.
 pub fn stop_at_first_error(
@@ -140,6 +464,155 @@ async fn exec_static_simple_select(
     Ok(serde_json::Value::Object(map))
 }
 
+/// Starts the transaction that wraps all the statements of a request
+pub async fn begin_request_transaction(
+    db: &Database,
+    db_connection: &mut DbConn,
+) -> anyhow::Result<()> {
+    let connection = take_connection(db, db_connection).await?;
+    let begin = match connection.kind() {
+        sqlx::any::AnyKind::Mssql => "BEGIN TRANSACTION",
+        _ => "BEGIN",
+    };
+    connection
+        .execute(begin)
+        .await
+        .context("Unable to start the transaction of the request")?;
+    log::debug!("Started the transaction of the request");
+    Ok(())
+}
+
+/// Commits the transaction of the request
+pub async fn commit_request_transaction(db_connection: &mut DbConn) -> anyhow::Result<()> {
+    let Some(connection) = db_connection else {
+        return Ok(());
+    };
+    connection
+        .execute("COMMIT")
+        .await
+        .context("Unable to commit the transaction of the request. Its changes were not saved")?;
+    log::debug!("Committed the transaction of the request");
+    Ok(())
+}
+
+/// Rolls back the transaction of the request
+pub async fn rollback_request_transaction(db_connection: &mut DbConn) {
+    if let Some(connection) = db_connection {
+        try_rollback_transaction(connection).await;
+    }
+}
+
+/// Savepoint that lets an optional statement be rolled back alone in the transaction of the request
+const OPTIONAL_STATEMENT_SAVEPOINT: &str = "sqlpage_optional_statement";
+
+async fn create_savepoint(db_connection: &mut AnyConnection) -> anyhow::Result<()> {
+    let sql = match db_connection.kind() {
+        sqlx::any::AnyKind::Mssql => format!("SAVE TRANSACTION {OPTIONAL_STATEMENT_SAVEPOINT}"),
+        _ => format!("SAVEPOINT {OPTIONAL_STATEMENT_SAVEPOINT}"),
+    };
+    db_connection
+        .execute(sql.as_str())
+        .await
+        .context("Unable to create a savepoint before an optional statement")?;
+    Ok(())
+}
+
+/// Rolls back the changes of an optional statement that failed, or releases its savepoint
+async fn end_savepoint(db_connection: &mut AnyConnection, rollback: bool) {
+    let sql = match (db_connection.kind(), rollback) {
+        (sqlx::any::AnyKind::Mssql, true) => {
+            format!("ROLLBACK TRANSACTION {OPTIONAL_STATEMENT_SAVEPOINT}")
+        }
+        // SQL Server releases its savepoints when the transaction ends
+        (sqlx::any::AnyKind::Mssql, false) => return,
+        (_, true) => format!("ROLLBACK TO SAVEPOINT {OPTIONAL_STATEMENT_SAVEPOINT}"),
+        (_, false) => format!("RELEASE SAVEPOINT {OPTIONAL_STATEMENT_SAVEPOINT}"),
+    };
+    if let Err(e) = db_connection.execute(sql.as_str()).await {
+        log::error!("Unable to end the savepoint of an optional statement: {e}");
+    }
+}
+
+/// Identifies the database session of a connection, so that its running query can be cancelled from another connection
+#[derive(Debug, Clone, Copy)]
+pub struct SessionId {
+    kind: AnyKind,
+    id: i64,
+}
+
+impl SessionId {
+    /// Reads the identifier of the session of the connection, on the databases whose queries can be cancelled
+    async fn read(connection: &mut AnyConnection) -> Option<Self> {
+        let kind = connection.kind();
+        let sql = match kind {
+            AnyKind::Postgres => "SELECT CAST(pg_backend_pid() AS BIGINT)",
+            AnyKind::MySql => "SELECT CAST(CONNECTION_ID() AS SIGNED)",
+            AnyKind::Mssql => "SELECT CAST(@@SPID AS BIGINT)",
+            AnyKind::Sqlite => return None,
+        };
+        match sqlx::query_scalar::<_, i64>(sql)
+            .fetch_one(&mut *connection)
+            .await
+        {
+            Ok(id) => Some(Self { kind, id }),
+            Err(e) => {
+                log::debug!("Unable to read the database session of the request: {e}");
+                None
+            }
+        }
+    }
+
+    /// Stops the query running in the session, from another connection of the pool
+    async fn cancel_query(self, db: &Database) {
+        let Self { kind, id } = self;
+        let sql = match kind {
+            AnyKind::Postgres => format!("SELECT pg_cancel_backend({id})"),
+            AnyKind::MySql => format!("KILL QUERY {id}"),
+            // Ends the whole session, which is closed anyway
+            AnyKind::Mssql => format!("KILL {id}"),
+            AnyKind::Sqlite => return,
+        };
+        let cancelled = match db.connection().acquire().await {
+            Ok(mut connection) => connection.execute(sql.as_str()).await.map(drop),
+            Err(e) => Err(e),
+        };
+        match cancelled {
+            Ok(()) => log::debug!("Cancelled the query of the database session {id}"),
+            Err(e) => log::warn!("Unable to cancel the query of the database session {id}: {e}"),
+        }
+    }
+}
+
+/// Reads the database session of the connection of the request before its first query,
+/// so that the query can be cancelled if the client disconnects while it runs
+async fn remember_session(request: &RequestInfo, connection: &mut AnyConnection) {
+    if request.database_session.get().is_none() {
+        let session = SessionId::read(connection).await;
+        let _ = request.database_session.set(session);
+    }
+}
+
+/// Cancels the query that the connection may still be running, then closes the connection
+/// instead of returning it to the pool.
+/// Without the `session` of the connection, as with `SQLite`, the query is not cancelled:
+/// the database only stops it, and rolls back its transaction, when it notices that the connection is closed,
+/// which can take until the end of the query.
+pub async fn cancel_and_close(
+    db: &Database,
+    db_connection: &mut DbConn,
+    session: Option<SessionId>,
+) {
+    if let Some(connection) = db_connection.take() {
+        // Detaching the connection lets the pool open a new one, to cancel the query
+        let connection = connection.detach();
+        if let Some(session) = session {
+            session.cancel_query(db).await;
+        }
+        drop(connection);
+        log::debug!("Closed the database connection of the request");
+    }
+}
+
 async fn try_rollback_transaction(db_connection: &mut AnyConnection) {
     log::debug!("Attempting to rollback transaction");
     match db_connection.execute("ROLLBACK").await {
@@ -185,15 +658,28 @@ async fn execute_set_variable_query<'a>(
     statement: &StmtWithParams,
     source_file: &Path,
 ) -> anyhow::Result<()> {
-    let query = bind_parameters(statement, request, db_connection).await?;
+    request.budget.count_statement()?;
+    let mut query = bind_parameters(statement, request, db_connection).await?;
+    let (sql, parameter_shapes) = (query.sql, std::mem::take(&mut query.parameter_shapes));
     let connection = take_connection(&request.app_state.db, db_connection).await?;
     log::debug!(
         "Executing query to set the {variable:?} variable: {:?}",
         query.sql
     );
 
-    let value = match connection.fetch_optional(query).await {
-        Ok(Some(row)) => row_to_string(&row),
+    let started_at = Instant::now();
+    let result = connection.fetch_optional(query).await;
+    let app_state = &request.app_state;
+    slow_query_log::record(
+        &app_state.db,
+        &app_state.config(),
+        source_file,
+        sql,
+        &parameter_shapes,
+        started_at.elapsed(),
+    );
+    let value = match result {
+        Ok(Some(row)) => row_to_string(&row, &request.app_state.config()),
         Ok(None) => None,
         Err(e) => {
             try_rollback_transaction(connection).await;
@@ -235,21 +721,24 @@ fn vars_and_name<'a, 'b>(
     }
 }
 
-async fn take_connection<'a>(
+pub(super) async fn take_connection<'a>(
     db: &'a Database,
     conn: &'a mut DbConn,
 ) -> anyhow::Result<&'a mut PoolConnection<sqlx::Any>> {
     if let Some(c) = conn {
         return Ok(c);
     }
-    match db.connection.acquire().await {
+    let pending_acquire = db.acquire_stats.start();
+    let acquired = db.connection().acquire().await;
+    drop(pending_acquire);
+    match acquired {
         Ok(c) => {
             log::debug!("Acquired a database connection");
             *conn = Some(c);
             Ok(conn.as_mut().unwrap())
         }
         Err(e) => {
-            let err_msg = format!("Unable to acquire a database connection to execute the SQL file. All of the {} {:?} connections are busy.", db.connection.size(), db.connection.any_kind());
+            let err_msg = format!("Unable to acquire a database connection to execute the SQL file. All of the {} {:?} connections are busy.", db.connection().size(), db.connection().any_kind());
             Err(anyhow::Error::new(e).context(err_msg))
         }
     }
@@ -260,13 +749,14 @@ fn parse_single_sql_result(
     source_file: &Path,
     stmt: &StmtWithParams,
     res: sqlx::Result<Either<AnyQueryResult, AnyRow>>,
+    config: &AppConfig,
 ) -> DbItem {
     match res {
         Ok(Either::Right(r)) => {
             if log::log_enabled!(log::Level::Trace) {
                 debug_row(&r);
             }
-            DbItem::Row(super::sql_to_json::row_to_json(&r))
+            DbItem::Row(super::sql_to_json::row_to_json(&r, config))
         }
         Ok(Either::Left(res)) => {
             log::debug!("Finished query with result: {:?}", res);
@@ -315,6 +805,14 @@ async fn bind_parameters<'a>(
     let sql = stmt.query.as_str();
     log::debug!("Preparing statement: {}", sql);
     let mut arguments = AnyArguments::default();
+    let record_shapes = request.debug_queries
+        || request
+            .app_state
+            .config()
+            .slow_query_log_threshold_seconds
+            .is_some();
+    let mut parameter_shapes = Vec::new();
+    let mut debug_values = Vec::new();
     for (param_idx, param) in stmt.params.iter().enumerate() {
         log::trace!("\tevaluating parameter {}: {}", param_idx + 1, param);
         let argument = extract_req_param(param, request, db_connection).await?;
@@ -323,6 +821,12 @@ async fn bind_parameters<'a>(
             param_idx + 1,
             argument.as_ref().unwrap_or(&Cow::Borrowed("NULL"))
         );
+        if record_shapes {
+            parameter_shapes.push(slow_query_log::parameter_shape(param, argument.as_ref()));
+        }
+        if request.debug_queries {
+            debug_values.push(argument.as_deref().map(str::to_owned));
+        }
         match argument {
             None => arguments.add(None::<String>),
             Some(Cow::Owned(s)) => arguments.add(s),
@@ -334,6 +838,8 @@ async fn bind_parameters<'a>(
         sql,
         arguments,
         has_arguments,
+        parameter_shapes,
+        debug_values,
     })
 }
 
@@ -419,6 +925,10 @@ pub struct StatementWithParams<'a> {
     sql: &'a str,
     arguments: AnyArguments<'a>,
     has_arguments: bool,
+    /// Description of the parameters for the slow query log, without their values
+    parameter_shapes: Vec<String>,
+    /// Copy of the parameter values, to compute the query plan in debug mode
+    debug_values: Vec<Option<String>>,
 }
 
 impl<'q> sqlx::Execute<'q, Any> for StatementWithParams<'q> {
@@ -520,4 +1030,16 @@ mod tests {
         assert_json_value(&item, "json_col", json!({"key": "value"}));
         assert_json_value(&item, "normal_col", json!("text"));
     }
+
+    #[actix_web::test]
+    async fn test_timed_excludes_time_between_futures() {
+        let mut total = Duration::ZERO;
+        let sleep = || tokio::time::sleep(Duration::from_millis(20));
+        timed(&mut total, sleep()).await;
+        // The consumer of the rows is slow
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        timed(&mut total, sleep()).await;
+        assert!(total >= Duration::from_millis(40), "{total:?}");
+        assert!(total < Duration::from_millis(200), "{total:?}");
+    }
 }
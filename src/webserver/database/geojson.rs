@@ -0,0 +1,480 @@
+//! Conversion of spatial values to [GeoJSON](https://datatracker.ietf.org/doc/html/rfc7946) geometries,
+//! so that they can be used directly by the map component, and are readable in JSON responses.
+//!
+//! Supported formats are:
+//!  - WKB and the extended WKB of `PostGIS` (`geometry` and `geography` columns),
+//!  - the internal format of `MySQL` `GEOMETRY` values (a SRID followed by WKB),
+//!  - `SpatiaLite` and `GeoPackage` geometry blobs in `SQLite`,
+//!  - WKT (`POINT(1 2)`) and hex-encoded WKB text.
+//!
+//! Only the X, Y and Z coordinates are kept, since `GeoJSON` positions cannot contain M values.
+
+use serde_json::{json, Value};
+
+/// Converts a WKB or `PostGIS` extended WKB geometry
+#[must_use]
+pub fn wkb_to_geojson(bytes: &[u8]) -> Option<Value> {
+    let mut reader = GeometryReader::new(bytes, Format::Wkb);
+    let geometry = reader.read_geometry()?;
+    reader.bytes.is_empty().then_some(geometry)
+}
+
+/// Converts a `MySQL` geometry, which is a little-endian SRID followed by WKB
+#[must_use]
+pub fn mysql_geometry_to_geojson(bytes: &[u8]) -> Option<Value> {
+    wkb_to_geojson(bytes.get(4..)?)
+}
+
+/// Converts a `SpatiaLite` or `GeoPackage` geometry blob.
+/// Returns `None` for blobs that are not geometries.
+#[must_use]
+pub fn sqlite_geometry_to_geojson(bytes: &[u8]) -> Option<Value> {
+    match bytes {
+        [b'G', b'P', ..] => geopackage_to_geojson(bytes),
+        [0x00, 0x00 | 0x01, ..] => spatialite_to_geojson(bytes),
+        _ => None,
+    }
+}
+
+/// Converts a geometry written as WKT (optionally prefixed with `SRID=4326;`), or as hex-encoded WKB
+#[must_use]
+pub fn text_to_geojson(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if text.len() >= 10 && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let bytes = super::sql_to_json::decode_hex(text.as_bytes())?;
+        return wkb_to_geojson(&bytes);
+    }
+    let text = match text.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("SRID=") => text.split_once(';')?.1,
+        _ => text,
+    };
+    let mut parser = WktParser { rest: text };
+    let geometry = parser.geometry()?;
+    parser.rest.trim().is_empty().then_some(geometry)
+}
+
+/// A `SpatiaLite` blob starts with a header containing the SRID and the bounding box of the geometry,
+/// and ends with a 0xFE byte.
+fn spatialite_to_geojson(bytes: &[u8]) -> Option<Value> {
+    const HEADER_LEN: usize = 39;
+    let body = bytes.strip_suffix(&[0xFE])?;
+    if body.get(HEADER_LEN - 1) != Some(&0x7C) {
+        return None;
+    }
+    let mut reader = GeometryReader::new(&body[HEADER_LEN..], Format::SpatiaLite);
+    reader.little_endian = bytes[1] == 0x01;
+    let geometry = reader.read_geometry_body()?;
+    reader.bytes.is_empty().then_some(geometry)
+}
+
+/// A `GeoPackage` blob is WKB preceded by a header containing the SRID and an optional envelope
+fn geopackage_to_geojson(bytes: &[u8]) -> Option<Value> {
+    let flags = *bytes.get(3)?;
+    let envelope_len = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        _ => return None,
+    };
+    wkb_to_geojson(bytes.get(8 + envelope_len..)?)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Wkb,
+    /// In `SpatiaLite` blobs, the geometries inside collections start with a 0x69 marker instead of a byte order
+    SpatiaLite,
+}
+
+struct GeometryReader<'a> {
+    bytes: &'a [u8],
+    format: Format,
+    little_endian: bool,
+}
+
+impl<'a> GeometryReader<'a> {
+    fn new(bytes: &'a [u8], format: Format) -> Self {
+        Self {
+            bytes,
+            format,
+            little_endian: true,
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = rest;
+        Some(*bytes)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_array()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_f64(&mut self) -> Option<f64> {
+        let bytes = self.read_array()?;
+        Some(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_geometry(&mut self) -> Option<Value> {
+        let [marker] = self.read_array()?;
+        match (self.format, marker) {
+            (Format::Wkb, 0x00) => self.little_endian = false,
+            (Format::Wkb, 0x01) => self.little_endian = true,
+            (Format::SpatiaLite, 0x69) => {}
+            _ => return None,
+        }
+        self.read_geometry_body()
+    }
+
+    fn read_geometry_body(&mut self) -> Option<Value> {
+        let code = self.read_u32()?;
+        // PostGIS extended WKB stores the dimensions and the presence of a SRID in the high bits
+        let mut has_z = code & 0x8000_0000 != 0;
+        let mut has_m = code & 0x4000_0000 != 0;
+        if code & 0x2000_0000 != 0 {
+            let _srid = self.read_u32()?;
+        }
+        // ISO WKB and SpatiaLite add 1000 for Z, 2000 for M, and 3000 for ZM
+        let code = code & 0x0FFF_FFFF;
+        match code / 1000 {
+            0 => {}
+            1 => has_z = true,
+            2 => has_m = true,
+            3 => (has_z, has_m) = (true, true),
+            _ => return None,
+        }
+        let dimensions = Dimensions { has_z, has_m };
+        let (kind, coordinates) = match code % 1000 {
+            1 => ("Point", self.read_point(dimensions)?),
+            2 => ("LineString", self.read_points(dimensions)?),
+            3 => ("Polygon", self.read_rings(dimensions)?),
+            4 => ("MultiPoint", self.read_parts(coordinates)?),
+            5 => ("MultiLineString", self.read_parts(coordinates)?),
+            6 => ("MultiPolygon", self.read_parts(coordinates)?),
+            7 => {
+                let geometries = self.read_parts(std::convert::identity)?;
+                return Some(json!({"type": "GeometryCollection", "geometries": geometries}));
+            }
+            _ => return None,
+        };
+        Some(json!({"type": kind, "coordinates": coordinates}))
+    }
+
+    fn read_point(&mut self, dimensions: Dimensions) -> Option<Value> {
+        let x = self.read_f64()?;
+        let y = self.read_f64()?;
+        let z = if dimensions.has_z {
+            Some(self.read_f64()?)
+        } else {
+            None
+        };
+        if dimensions.has_m {
+            let _m = self.read_f64()?;
+        }
+        // Empty points are written with NaN coordinates
+        if x.is_nan() && y.is_nan() {
+            return Some(json!([]));
+        }
+        Some(match z {
+            Some(z) => json!([x, y, z]),
+            None => json!([x, y]),
+        })
+    }
+
+    fn read_points(&mut self, dimensions: Dimensions) -> Option<Value> {
+        let count = self.read_u32()?;
+        (0..count)
+            .map(|_| self.read_point(dimensions))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array)
+    }
+
+    fn read_rings(&mut self, dimensions: Dimensions) -> Option<Value> {
+        let count = self.read_u32()?;
+        (0..count)
+            .map(|_| self.read_points(dimensions))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array)
+    }
+
+    /// Reads the geometries of a multi-geometry or a collection
+    fn read_parts(&mut self, part: impl Fn(Value) -> Value) -> Option<Value> {
+        let count = self.read_u32()?;
+        let little_endian = self.little_endian;
+        let parts = (0..count)
+            .map(|_| self.read_geometry().map(&part))
+            .collect::<Option<Vec<_>>>()?;
+        self.little_endian = little_endian;
+        Some(Value::Array(parts))
+    }
+}
+
+fn coordinates(mut geometry: Value) -> Value {
+    geometry["coordinates"].take()
+}
+
+#[derive(Clone, Copy)]
+struct Dimensions {
+    has_z: bool,
+    has_m: bool,
+}
+
+struct WktParser<'a> {
+    rest: &'a str,
+}
+
+impl WktParser<'_> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn keyword(&mut self) -> String {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.rest.len());
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        word.to_ascii_uppercase()
+    }
+
+    fn consume(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if let Some(rest) = self.rest.strip_prefix(c) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.consume(c).then_some(())
+    }
+
+    fn geometry(&mut self) -> Option<Value> {
+        let kind = match self.keyword().as_str() {
+            "POINT" => "Point",
+            "LINESTRING" => "LineString",
+            "POLYGON" => "Polygon",
+            "MULTIPOINT" => "MultiPoint",
+            "MULTILINESTRING" => "MultiLineString",
+            "MULTIPOLYGON" => "MultiPolygon",
+            "GEOMETRYCOLLECTION" => "GeometryCollection",
+            _ => return None,
+        };
+        let has_m = match self.keyword().as_str() {
+            "" | "Z" => false,
+            "M" | "ZM" => true,
+            "EMPTY" => return Some(empty_geometry(kind)),
+            _ => return None,
+        };
+        if self
+            .rest
+            .trim_start()
+            .get(..5)
+            .is_some_and(|w| w.eq_ignore_ascii_case("EMPTY"))
+        {
+            self.keyword();
+            return Some(empty_geometry(kind));
+        }
+        if kind == "GeometryCollection" {
+            let geometries = self.list(Self::geometry)?;
+            return Some(json!({"type": kind, "geometries": geometries}));
+        }
+        let coordinates = match kind {
+            "Point" => {
+                self.expect('(')?;
+                let point = self.position(has_m)?;
+                self.expect(')')?;
+                point
+            }
+            "LineString" => self.list(|p| p.position(has_m))?,
+            "Polygon" => self.list(|p| p.list(|p| p.position(has_m)))?,
+            // The points of a MULTIPOINT can be written with or without parentheses
+            "MultiPoint" => self.list(|p| {
+                if p.consume('(') {
+                    let point = p.position(has_m)?;
+                    p.expect(')')?;
+                    Some(point)
+                } else {
+                    p.position(has_m)
+                }
+            })?,
+            "MultiLineString" => self.list(|p| p.list(|p| p.position(has_m)))?,
+            _ => self.list(|p| p.list(|p| p.list(|p| p.position(has_m))))?,
+        };
+        Some(json!({"type": kind, "coordinates": coordinates}))
+    }
+
+    /// A comma-separated list between parentheses
+    fn list(&mut self, mut item: impl FnMut(&mut Self) -> Option<Value>) -> Option<Value> {
+        self.expect('(')?;
+        let mut items = vec![item(self)?];
+        while self.consume(',') {
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Some(Value::Array(items))
+    }
+
+    fn position(&mut self, has_m: bool) -> Option<Value> {
+        let mut numbers = Vec::with_capacity(4);
+        loop {
+            self.skip_whitespace();
+            let end = self
+                .rest
+                .find(|c: char| c.is_whitespace() || c == ',' || c == ')')
+                .unwrap_or(self.rest.len());
+            if end == 0 {
+                break;
+            }
+            numbers.push(self.rest[..end].parse::<f64>().ok()?);
+            self.rest = &self.rest[end..];
+        }
+        // In "POINT M (1 2 3)" and "POINT ZM (1 2 3 4)", the last number is the M value
+        if has_m {
+            numbers.pop();
+        }
+        match numbers.len() {
+            2 | 3 => Some(json!(numbers)),
+            // 4 numbers without an explicit dimension are X, Y, Z and M
+            4 => Some(json!(numbers[..3])),
+            _ => None,
+        }
+    }
+}
+
+fn empty_geometry(kind: &str) -> Value {
+    if kind == "GeometryCollection" {
+        json!({"type": kind, "geometries": []})
+    } else {
+        json!({"type": kind, "coordinates": []})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        super::super::sql_to_json::decode_hex(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_wkb_point() {
+        // ST_AsBinary('POINT(1 2)'::geometry)
+        let wkb = hex("0101000000000000000000f03f0000000000000040");
+        assert_eq!(
+            wkb_to_geojson(&wkb),
+            Some(json!({"type": "Point", "coordinates": [1.0, 2.0]}))
+        );
+        assert_eq!(wkb_to_geojson(&wkb[..10]), None);
+    }
+
+    #[test]
+    fn test_ewkb_with_srid() {
+        // 'SRID=4326;LINESTRING(0 0,1 1)'::geometry in PostGIS, big-endian
+        let ewkb = hex(concat!(
+            "00",
+            "20000002",
+            "000010e6",
+            "00000002",
+            "0000000000000000",
+            "0000000000000000",
+            "3ff0000000000000",
+            "3ff0000000000000"
+        ));
+        assert_eq!(
+            wkb_to_geojson(&ewkb),
+            Some(json!({"type": "LineString", "coordinates": [[0.0, 0.0], [1.0, 1.0]]}))
+        );
+    }
+
+    #[test]
+    fn test_mysql_geometry() {
+        let mut bytes = 4326_u32.to_le_bytes().to_vec();
+        bytes.extend(hex("0101000000000000000000f03f0000000000000040"));
+        assert_eq!(
+            mysql_geometry_to_geojson(&bytes),
+            Some(json!({"type": "Point", "coordinates": [1.0, 2.0]}))
+        );
+    }
+
+    #[test]
+    fn test_spatialite_multipoint() {
+        let mut blob = vec![0x00, 0x01];
+        blob.extend(4326_u32.to_le_bytes());
+        blob.extend([0; 32]);
+        blob.push(0x7C);
+        blob.extend(4_u32.to_le_bytes());
+        blob.extend(2_u32.to_le_bytes());
+        for (x, y) in [(1.0_f64, 2.0_f64), (3.0, 4.0)] {
+            blob.push(0x69);
+            blob.extend(1_u32.to_le_bytes());
+            blob.extend(x.to_le_bytes());
+            blob.extend(y.to_le_bytes());
+        }
+        blob.push(0xFE);
+        assert_eq!(
+            sqlite_geometry_to_geojson(&blob),
+            Some(json!({"type": "MultiPoint", "coordinates": [[1.0, 2.0], [3.0, 4.0]]}))
+        );
+        assert_eq!(sqlite_geometry_to_geojson(b"hello world"), None);
+    }
+
+    #[test]
+    fn test_geopackage() {
+        let mut blob = b"GP\x00\x03".to_vec(); // little-endian header, with an XY envelope
+        blob.extend(4326_u32.to_le_bytes());
+        blob.extend([0; 32]);
+        blob.extend(hex("0101000000000000000000f03f0000000000000040"));
+        assert_eq!(
+            sqlite_geometry_to_geojson(&blob),
+            Some(json!({"type": "Point", "coordinates": [1.0, 2.0]}))
+        );
+    }
+
+    #[test]
+    fn test_wkt() {
+        assert_eq!(
+            text_to_geojson("POINT (1 2)"),
+            Some(json!({"type": "Point", "coordinates": [1.0, 2.0]}))
+        );
+        assert_eq!(
+            text_to_geojson("SRID=4326;polygon z ((0 0 1, 1 0 1, 1 1 1, 0 0 1))"),
+            Some(
+                json!({"type": "Polygon", "coordinates": [[[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 0.0, 1.0]]]})
+            )
+        );
+        assert_eq!(
+            text_to_geojson("MULTIPOINT M (1 2 9, (3 4 9))"),
+            Some(json!({"type": "MultiPoint", "coordinates": [[1.0, 2.0], [3.0, 4.0]]}))
+        );
+        assert_eq!(
+            text_to_geojson("GEOMETRYCOLLECTION(POINT(1 2), LINESTRING EMPTY)"),
+            Some(json!({"type": "GeometryCollection", "geometries": [
+                {"type": "Point", "coordinates": [1.0, 2.0]},
+                {"type": "LineString", "coordinates": []}
+            ]}))
+        );
+        assert_eq!(
+            text_to_geojson("0101000000000000000000f03f0000000000000040"),
+            Some(json!({"type": "Point", "coordinates": [1.0, 2.0]}))
+        );
+        assert_eq!(text_to_geojson("POINT (1 2"), None);
+        assert_eq!(text_to_geojson("Paris"), None);
+    }
+}
@@ -1,25 +1,112 @@
+pub mod authorize;
 mod connect;
+pub mod console;
+mod credentials;
 mod csv_import;
 pub mod execute_queries;
+mod geojson;
 pub mod migrations;
+pub mod query_debug;
+pub mod request_budget;
+pub mod schema;
+mod slow_query_log;
 mod sql;
 mod sqlpage_functions;
 mod syntax_tree;
+pub mod user_role;
 
 mod error_highlighting;
 mod sql_to_json;
 
-pub use sql::ParsedSqlFile;
+pub(crate) use connect::postgres_connect_options;
 use sql::{DbPlaceHolder, DB_PLACEHOLDERS};
+pub use sql::{LiteralComponent, ParameterSource, ParsedSqlFile, RequestParameter};
+pub(crate) use sqlpage_functions::functions::make_http_client;
 use sqlx::any::AnyKind;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::Relaxed};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 
 pub struct Database {
-    pub connection: sqlx::AnyPool,
+    /// Replaced by a new pool when the database credentials are renewed
+    pool: Arc<RwLock<sqlx::AnyPool>>,
+    pub acquire_stats: AcquireStats,
 }
+
+/// Statistics about the requests waiting for a connection from the pool
+#[derive(Default)]
+pub struct AcquireStats {
+    waiting: AtomicUsize,
+    acquires: AtomicU64,
+    wait_micros: AtomicU64,
+}
+
+impl AcquireStats {
+    /// Registers a request waiting for a connection, until the returned guard is dropped
+    fn start(&self) -> PendingAcquire<'_> {
+        self.waiting.fetch_add(1, Relaxed);
+        PendingAcquire {
+            stats: self,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Number of requests currently waiting for a connection
+    #[must_use]
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Relaxed)
+    }
+
+    /// Number of connection acquisitions, successful or not
+    #[must_use]
+    pub fn acquires(&self) -> u64 {
+        self.acquires.load(Relaxed)
+    }
+
+    /// Total time spent waiting for connections
+    #[must_use]
+    pub fn wait_time(&self) -> Duration {
+        Duration::from_micros(self.wait_micros.load(Relaxed))
+    }
+}
+
+struct PendingAcquire<'a> {
+    stats: &'a AcquireStats,
+    started_at: Instant,
+}
+
+impl Drop for PendingAcquire<'_> {
+    fn drop(&mut self) {
+        let micros = u64::try_from(self.started_at.elapsed().as_micros()).unwrap_or(u64::MAX);
+        self.stats.waiting.fetch_sub(1, Relaxed);
+        self.stats.acquires.fetch_add(1, Relaxed);
+        self.stats.wait_micros.fetch_add(micros, Relaxed);
+    }
+}
+
+/// The pool of connections to the database that is currently in a [`Database::shared_pool`]
+#[must_use]
+pub fn current_pool(pool: &RwLock<sqlx::AnyPool>) -> sqlx::AnyPool {
+    pool.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
 impl Database {
+    /// The current pool of connections to the database
+    #[must_use]
+    pub fn connection(&self) -> sqlx::AnyPool {
+        current_pool(&self.pool)
+    }
+
+    /// A handle on the pool, for background tasks that must use the new pool when the credentials are renewed.
+    /// Read it with [`current_pool`].
+    #[must_use]
+    pub fn shared_pool(&self) -> Arc<RwLock<sqlx::AnyPool>> {
+        Arc::clone(&self.pool)
+    }
+
     pub async fn close(&self) -> anyhow::Result<()> {
         log::info!("Closing all database connections...");
-        self.connection.close().await;
+        self.connection().close().await;
         Ok(())
     }
 }
@@ -33,7 +120,7 @@ pub enum DbItem {
 
 impl std::fmt::Display for Database {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.connection.any_kind())
+        write!(f, "{:?}", self.connection().any_kind())
     }
 }
 
@@ -0,0 +1,184 @@
+//! Debug mode, in which the query plan and the duration of each statement are displayed at the end of the page.
+//!
+//! It is enabled for the requests that contain the `debug_token` of the configuration
+//! in the `X-SQLPage-Debug` header. The token is never read from the URL,
+//! so that it does not end up in access logs, browser histories, or `Referer` headers.
+
+use super::sql_to_json::row_to_json;
+use crate::app_config::AppConfig;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value as JsonValue};
+use sha2::Sha256;
+use sqlx::any::{AnyConnection, AnyKind};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEBUG_HEADER: &str = "x-sqlpage-debug";
+
+/// Whether the debug token sent with the request matches the one in the configuration.
+/// The tokens are compared in constant time, through their HMAC, so that the response time
+/// does not tell how much of the token was guessed.
+#[must_use]
+pub fn is_debug_request(config: &AppConfig, sent_token: Option<&str>) -> bool {
+    let (Some(expected), Some(sent)) = (&config.debug_token, sent_token) else {
+        return false;
+    };
+    let expected_mac = token_mac(expected).finalize().into_bytes();
+    token_mac(sent).verify_slice(&expected_mac).is_ok()
+}
+
+fn token_mac(token: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(b"sqlpage debug token");
+    mac
+}
+
+/// Information displayed about a statement of the page
+#[derive(Debug)]
+pub struct StatementDebugInfo {
+    pub source_file: PathBuf,
+    pub sql: String,
+    pub parameter_shapes: Vec<String>,
+    pub plan: String,
+    pub duration: Duration,
+}
+
+/// Returns the query plan of a statement, or the reason why it is not available
+pub async fn explain(
+    connection: &mut AnyConnection,
+    config: &AppConfig,
+    sql: &str,
+    parameter_values: Vec<Option<String>>,
+) -> String {
+    let explain_prefix = match connection.kind() {
+        AnyKind::Sqlite => "EXPLAIN QUERY PLAN",
+        AnyKind::Postgres | AnyKind::MySql => "EXPLAIN",
+        AnyKind::Mssql => return "Query plans are not available on SQL Server".into(),
+    };
+    let explain_sql = format!("{explain_prefix} {sql}");
+    let mut query = sqlx::query(&explain_sql);
+    for value in parameter_values {
+        query = query.bind(value);
+    }
+    // The plan is computed on the connection of the request, which sees its temporary tables and session settings
+    match query.fetch_all(connection).await {
+        Ok(rows) => rows
+            .iter()
+            .map(|row| format_plan_row(&row_to_json(row, config)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(e) => format!("Unable to explain this statement: {e}"),
+    }
+}
+
+/// `SQLite` describes each step in a `detail` column, and `PostgreSQL` in a single `QUERY PLAN` column.
+/// Other databases return a table, whose columns are displayed as `name=value` pairs.
+fn format_plan_row(row: &JsonValue) -> String {
+    let Some(columns) = row.as_object() else {
+        return row.to_string();
+    };
+    let as_text = |value: &JsonValue| match value {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if let Some(detail) = columns.get("detail") {
+        return as_text(detail);
+    }
+    if columns.len() == 1 {
+        return columns.values().map(as_text).collect();
+    }
+    columns
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(name, value)| format!("{name}={}", as_text(value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Rows of the `foldable` component that displays the debug information at the end of the page
+#[must_use]
+pub fn debug_rows(statements: &[StatementDebugInfo]) -> Vec<JsonValue> {
+    let total: Duration = statements.iter().map(|s| s.duration).sum();
+    let mut rows = vec![json!({
+        "component": "foldable",
+        "id": "sqlpage_query_debug",
+    })];
+    rows.push(json!({
+        "title": format!("Total: {} statements, {} ms", statements.len(), total.as_millis()),
+        "description": "Click on a statement to see its query plan.",
+    }));
+    rows.extend(statements.iter().map(|statement| {
+        let first_line = statement.sql.lines().next().unwrap_or_default();
+        let parameters = if statement.parameter_shapes.is_empty() {
+            "none".to_string()
+        } else {
+            statement.parameter_shapes.join(", ")
+        };
+        json!({
+            "title": format!(
+                "{} ms · {} · {first_line}",
+                statement.duration.as_millis(),
+                statement.source_file.display()
+            ),
+            "description_md": format!(
+                "```sql\n{}\n```\n\nParameters: {parameters}\n\n```\n{}\n```",
+                statement.sql, statement.plan
+            ),
+        })
+    }));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use crate::webserver::database::{make_placeholder, Database};
+    use sqlx::Executor;
+
+    #[test]
+    fn test_is_debug_request() {
+        let mut config = test_config();
+        assert!(!is_debug_request(&config, Some("secret")));
+        config.debug_token = Some("secret".into());
+        assert!(is_debug_request(&config, Some("secret")));
+        assert!(!is_debug_request(&config, Some("wrong")));
+        assert!(!is_debug_request(&config, Some("secret2")));
+        assert!(!is_debug_request(&config, Some("")));
+        assert!(!is_debug_request(&config, None));
+    }
+
+    #[test]
+    fn test_format_plan_row() {
+        assert_eq!(
+            format_plan_row(&json!({"id": 2, "parent": 0, "notused": 0, "detail": "SCAN t"})),
+            "SCAN t"
+        );
+        assert_eq!(
+            format_plan_row(&json!({"QUERY PLAN": "Seq Scan on t"})),
+            "Seq Scan on t"
+        );
+        assert_eq!(
+            format_plan_row(&json!({"table": "t", "type": "ALL", "key": null})),
+            "table=t, type=ALL"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_explain() {
+        let config = test_config();
+        let db = Database::init(&config).await.unwrap();
+        let mut connection = db.connection().acquire().await.unwrap();
+        let placeholder = make_placeholder(connection.kind(), 1);
+        // Temporary tables only exist on the connection that created them
+        connection
+            .execute("CREATE TEMPORARY TABLE sqlpage_explain_test (x INT)")
+            .await
+            .unwrap();
+        let sql =
+            format!("SELECT x FROM sqlpage_explain_test WHERE CAST({placeholder} AS TEXT) = '1'");
+        let plan = explain(&mut connection, &config, &sql, vec![Some("1".into())]).await;
+        assert!(!plan.starts_with("Unable"), "{plan}");
+    }
+}
@@ -0,0 +1,96 @@
+//! Limits on the work of a single request, set by `max_statements_per_request` and `max_rows_per_request`.
+//!
+//! The statements and rows of the files included with `sqlpage.run_sql` count towards the limits of the request
+//! that includes them. A request that exceeds a limit is stopped with an error, instead of keeping the server busy
+//! with a recursive inclusion, or with an accidental cross join that returns millions of rows.
+
+use crate::AppConfig;
+use std::cell::Cell;
+
+#[derive(Debug, Default)]
+pub struct RequestBudget {
+    max_statements: Option<u64>,
+    max_rows: Option<u64>,
+    statements: Cell<u64>,
+    rows: Cell<u64>,
+}
+
+impl RequestBudget {
+    #[must_use]
+    pub fn new(config: &AppConfig) -> Self {
+        Self {
+            max_statements: config.max_statements_per_request,
+            max_rows: config.max_rows_per_request,
+            ..Self::default()
+        }
+    }
+
+    /// Counts a statement sent to the database, and fails when the request executed too many statements
+    pub fn count_statement(&self) -> anyhow::Result<()> {
+        self.count_statements(1)
+    }
+
+    pub fn count_statements(&self, statements: usize) -> anyhow::Result<()> {
+        let count = self.statements.get() + statements as u64;
+        self.statements.set(count);
+        match self.max_statements {
+            Some(max) if count > max => anyhow::bail!(
+                "This request executed more than {max} SQL statements, and was stopped. \n\
+                Check that the page does not include itself with sqlpage.run_sql or the dynamic component, \
+                or increase max_statements_per_request in the configuration."
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of rows the request can still return, or `u64::MAX` when they are not limited
+    #[must_use]
+    pub fn remaining_rows(&self) -> u64 {
+        self.max_rows
+            .map_or(u64::MAX, |max| max.saturating_sub(self.rows.get()))
+    }
+
+    /// Counts a row returned by the database, and fails when the request returned too many rows
+    pub fn count_row(&self) -> anyhow::Result<()> {
+        let count = self.rows.get() + 1;
+        self.rows.set(count);
+        match self.max_rows {
+            Some(max) if count > max => anyhow::bail!(
+                "This request returned more than {max} rows, and was stopped. \n\
+                Check the conditions of the joins of the queries of the page, add a LIMIT clause to them, \
+                or increase max_rows_per_request in the configuration."
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let budget = RequestBudget {
+            max_statements: Some(2),
+            ..RequestBudget::default()
+        };
+        assert!(budget.count_statement().is_ok());
+        assert!(budget.count_statement().is_ok());
+        let err = budget.count_statement().unwrap_err();
+        assert!(
+            err.to_string().contains("max_statements_per_request"),
+            "{err}"
+        );
+        for _ in 0..1000 {
+            assert!(budget.count_row().is_ok(), "rows are not limited");
+        }
+        assert_eq!(budget.remaining_rows(), u64::MAX);
+        let budget = RequestBudget {
+            max_rows: Some(2),
+            ..RequestBudget::default()
+        };
+        budget.count_row().unwrap();
+        assert_eq!(budget.remaining_rows(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+//! Introspection of the tables and columns of the connected database,
+//! for the administration pages.
+
+use super::sql_to_json::row_to_json;
+use super::{make_placeholder, Database};
+use crate::app_config::AppConfig;
+use anyhow::Context;
+use serde_json::Value as JsonValue;
+use sqlx::any::{AnyArguments, AnyKind};
+use sqlx::query::Query;
+use sqlx::Any;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub schema: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+const INFORMATION_SCHEMA_TABLES: &str =
+    "SELECT TABLE_SCHEMA AS table_schema, TABLE_NAME AS table_name \
+    FROM INFORMATION_SCHEMA.TABLES \
+    WHERE TABLE_TYPE = 'BASE TABLE'";
+
+/// Lists the tables created by the user, excluding the system catalogs
+pub async fn list_tables(db: &Database, config: &AppConfig) -> anyhow::Result<Vec<Table>> {
+    let sql = match db.connection().any_kind() {
+        AnyKind::Sqlite => "SELECT 'main' AS table_schema, name AS table_name FROM sqlite_master \
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+            .to_string(),
+        AnyKind::MySql => {
+            format!("{INFORMATION_SCHEMA_TABLES} AND TABLE_SCHEMA = DATABASE() ORDER BY TABLE_NAME")
+        }
+        AnyKind::Postgres | AnyKind::Mssql => format!(
+            "{INFORMATION_SCHEMA_TABLES} \
+            AND TABLE_SCHEMA NOT IN ('pg_catalog', 'information_schema', 'sys') \
+            ORDER BY TABLE_SCHEMA, TABLE_NAME"
+        ),
+    };
+    let rows = fetch_json(db, config, sqlx::query(&sql))
+        .await
+        .context("Unable to list the tables of the database")?;
+    rows.iter()
+        .map(|row| {
+            Ok(Table {
+                schema: json_string(row, "table_schema")?,
+                name: json_string(row, "table_name")?,
+            })
+        })
+        .collect()
+}
+
+/// Lists the columns of a table, in the order in which they were defined
+pub async fn table_columns(
+    db: &Database,
+    config: &AppConfig,
+    table: &Table,
+) -> anyhow::Result<Vec<Column>> {
+    let kind = db.connection().any_kind();
+    let rows = if kind == AnyKind::Sqlite {
+        let sql = "SELECT name AS column_name, type AS data_type, \
+            CASE WHEN \"notnull\" = 0 THEN 'YES' ELSE 'NO' END AS is_nullable \
+            FROM pragma_table_info(?) ORDER BY cid";
+        fetch_json(db, config, sqlx::query(sql).bind(&table.name)).await
+    } else {
+        let sql = format!(
+            "SELECT COLUMN_NAME AS column_name, DATA_TYPE AS data_type, IS_NULLABLE AS is_nullable \
+            FROM INFORMATION_SCHEMA.COLUMNS \
+            WHERE TABLE_SCHEMA = {} AND TABLE_NAME = {} \
+            ORDER BY ORDINAL_POSITION",
+            make_placeholder(kind, 1),
+            make_placeholder(kind, 2)
+        );
+        let query = sqlx::query(&sql).bind(&table.schema).bind(&table.name);
+        fetch_json(db, config, query).await
+    }
+    .with_context(|| format!("Unable to list the columns of {}", table.name))?;
+    rows.iter()
+        .map(|row| {
+            Ok(Column {
+                name: json_string(row, "column_name")?,
+                data_type: json_string(row, "data_type")?,
+                nullable: json_string(row, "is_nullable")? == "YES",
+            })
+        })
+        .collect()
+}
+
+/// Counts the rows of a table.
+/// The table must come from [`list_tables`], since its name is included in the query.
+pub async fn count_rows(db: &Database, config: &AppConfig, table: &Table) -> anyhow::Result<i64> {
+    let kind = db.connection().any_kind();
+    let sql = format!(
+        "SELECT COUNT(*) AS row_count FROM {}.{}",
+        quote_identifier(kind, &table.schema),
+        quote_identifier(kind, &table.name)
+    );
+    let rows = fetch_json(db, config, sqlx::query(&sql))
+        .await
+        .with_context(|| format!("Unable to count the rows of {}", table.name))?;
+    rows.first()
+        .and_then(|row| row.get("row_count"))
+        .and_then(JsonValue::as_i64)
+        .with_context(|| format!("Invalid row count for {}: {rows:?}", table.name))
+}
+
+#[must_use]
+pub fn quote_identifier(kind: AnyKind, identifier: &str) -> String {
+    match kind {
+        AnyKind::MySql => format!("`{}`", identifier.replace('`', "``")),
+        AnyKind::Mssql => format!("[{}]", identifier.replace(']', "]]")),
+        AnyKind::Postgres | AnyKind::Sqlite => format!("\"{}\"", identifier.replace('"', "\"\"")),
+    }
+}
+
+async fn fetch_json<'q>(
+    db: &Database,
+    config: &AppConfig,
+    query: Query<'q, Any, AnyArguments<'q>>,
+) -> anyhow::Result<Vec<JsonValue>> {
+    let rows = query.fetch_all(&db.connection()).await?;
+    Ok(rows.iter().map(|row| row_to_json(row, config)).collect())
+}
+
+fn json_string(row: &JsonValue, key: &str) -> anyhow::Result<String> {
+    match row.get(key) {
+        Some(JsonValue::String(s)) => Ok(s.clone()),
+        Some(JsonValue::Null) | None => anyhow::bail!("Missing {key} in {row}"),
+        Some(other) => Ok(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use sqlx::Executor;
+
+    #[actix_web::test]
+    async fn test_introspection() -> anyhow::Result<()> {
+        let config = test_config();
+        let db = Database::init(&config).await?;
+        db.connection()
+            .execute(
+                "DROP TABLE IF EXISTS sqlpage_schema_test; \
+                CREATE TABLE sqlpage_schema_test (id INT NOT NULL, label VARCHAR(20))",
+            )
+            .await?;
+        db.connection()
+            .execute("INSERT INTO sqlpage_schema_test (id, label) VALUES (1, 'a'), (2, NULL)")
+            .await?;
+        let tables = list_tables(&db, &config).await?;
+        let table = tables
+            .into_iter()
+            .find(|t| t.name == "sqlpage_schema_test")
+            .expect("the test table should be listed");
+        let columns = table_columns(&db, &config, &table).await?;
+        assert_eq!(
+            columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            ["id", "label"]
+        );
+        assert!(!columns[0].nullable);
+        assert!(columns[1].nullable);
+        assert_eq!(count_rows(&db, &config, &table).await?, 2);
+        db.connection()
+            .execute("DROP TABLE sqlpage_schema_test")
+            .await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier(AnyKind::Postgres, "a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_identifier(AnyKind::MySql, "a`b"), "`a``b`");
+        assert_eq!(quote_identifier(AnyKind::Mssql, "a]b"), "[a]]b]");
+    }
+}
@@ -0,0 +1,154 @@
+//! Logging of the statements that take longer than `slow_query_log_threshold_seconds` to execute.
+//!
+//! Slow statements are logged with the `sqlpage::slow_query_log` target,
+//! and inserted into the `slow_query_log_table` table when it is configured.
+//! The values of the parameters are not recorded, only their kind and length.
+
+use super::{make_placeholder, Database};
+use crate::app_config::AppConfig;
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::Duration;
+
+const LOG_TARGET: &str = "sqlpage::slow_query_log";
+
+/// Describes a parameter without revealing its value, such as `$id: text(3)` or `:name: null`
+#[must_use]
+pub fn parameter_shape(name: &impl std::fmt::Display, value: Option<&Cow<'_, str>>) -> String {
+    match value {
+        Some(value) => format!("{name}: text({})", value.chars().count()),
+        None => format!("{name}: null"),
+    }
+}
+
+/// Records the statement if its execution took longer than the configured threshold
+pub fn record(
+    db: &Database,
+    config: &AppConfig,
+    source_file: &Path,
+    sql: &str,
+    parameter_shapes: &[String],
+    duration: Duration,
+) {
+    let Some(threshold) = config.slow_query_log_threshold_seconds else {
+        return;
+    };
+    if duration.as_secs_f64() < threshold {
+        return;
+    }
+    log::warn!(
+        target: LOG_TARGET,
+        "Slow query in {} ({} ms, parameters: [{}]): {sql}",
+        source_file.display(),
+        duration.as_millis(),
+        parameter_shapes.join(", ")
+    );
+    if let Some(table) = &config.slow_query_log_table {
+        insert_into_table(db, table, source_file, sql, parameter_shapes, duration);
+    }
+}
+
+/// Inserts the slow query in the background, so that the page is not delayed further
+fn insert_into_table(
+    db: &Database,
+    table: &str,
+    source_file: &Path,
+    sql: &str,
+    parameter_shapes: &[String],
+    duration: Duration,
+) {
+    let kind = db.connection().any_kind();
+    let insert_sql = format!(
+        "INSERT INTO {table} (path, query, parameters, duration_ms) VALUES ({}, {}, {}, {})",
+        make_placeholder(kind, 1),
+        make_placeholder(kind, 2),
+        make_placeholder(kind, 3),
+        make_placeholder(kind, 4)
+    );
+    let pool = db.connection().clone();
+    let path = source_file.display().to_string();
+    let sql = sql.to_string();
+    let parameters = serde_json::to_string(parameter_shapes).unwrap_or_default();
+    let duration_ms = duration.as_secs_f64() * 1000.;
+    actix_web::rt::spawn(async move {
+        let result = sqlx::query(&insert_sql)
+            .bind(path)
+            .bind(sql)
+            .bind(parameters)
+            .bind(duration_ms)
+            .execute(&pool)
+            .await;
+        if let Err(e) = result {
+            log::error!(target: LOG_TARGET, "Unable to record a slow query with {insert_sql:?}: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use sqlx::Executor;
+
+    #[test]
+    fn test_parameter_shape() {
+        assert_eq!(
+            parameter_shape(&"$id", Some(&Cow::Borrowed("123"))),
+            "$id: text(3)"
+        );
+        assert_eq!(parameter_shape(&":name", None), ":name: null");
+    }
+
+    #[actix_web::test]
+    async fn test_slow_query_table() -> anyhow::Result<()> {
+        let mut config = test_config();
+        config.slow_query_log_threshold_seconds = Some(0.5);
+        config.slow_query_log_table = Some("sqlpage_slow_queries_test".into());
+        let db = Database::init(&config).await?;
+        db.connection()
+            .execute(
+                "DROP TABLE IF EXISTS sqlpage_slow_queries_test; \
+                CREATE TABLE sqlpage_slow_queries_test \
+                (path TEXT, query TEXT, parameters TEXT, duration_ms FLOAT)",
+            )
+            .await?;
+        let path = Path::new("index.sql");
+        let shapes = ["$id: text(1)".to_string()];
+        record(
+            &db,
+            &config,
+            path,
+            "SELECT 1",
+            &shapes,
+            Duration::from_millis(10),
+        );
+        record(
+            &db,
+            &config,
+            path,
+            "SELECT 2",
+            &shapes,
+            Duration::from_secs(1),
+        );
+        let mut rows = Vec::new();
+        for _ in 0..100 {
+            rows = sqlx::query_as::<_, (String, String)>(
+                "SELECT query, parameters FROM sqlpage_slow_queries_test",
+            )
+            .fetch_all(&db.connection())
+            .await?;
+            if !rows.is_empty() {
+                break;
+            }
+            actix_web::rt::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            rows,
+            [("SELECT 2".to_string(), r#"["$id: text(1)"]"#.to_string())]
+        );
+        db.connection()
+            .execute("DROP TABLE sqlpage_slow_queries_test")
+            .await?;
+        Ok(())
+    }
+}
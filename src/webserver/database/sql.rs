@@ -2,13 +2,14 @@ use super::csv_import::{extract_csv_copy_statement, CsvImport};
 use super::sqlpage_functions::functions::SqlPageFunctionName;
 use super::sqlpage_functions::{are_params_extractable, func_call_to_param};
 use super::syntax_tree::StmtParam;
+use crate::app_config::OnError;
 use crate::file_cache::AsyncFromStrWithState;
 use crate::webserver::database::error_highlighting::quote_source_with_highlight;
 use crate::{AppState, Database};
 use async_trait::async_trait;
 use sqlparser::ast::helpers::attached_token::AttachedToken;
 use sqlparser::ast::{
-    BinaryOperator, CastKind, CharacterLength, DataType, Expr, Function, FunctionArg,
+    BinaryOperator, CastKind, CharacterLength, DataType, Expr, ForClause, Function, FunctionArg,
     FunctionArgExpr, FunctionArgumentList, FunctionArguments, Ident, ObjectName, ObjectNamePart,
     OneOrManyWithParens, SelectFlavor, SelectItem, SetExpr, Spanned, Statement, Value,
     ValueWithSpan, Visit, VisitMut, Visitor, VisitorMut,
@@ -16,7 +17,7 @@ use sqlparser::ast::{
 use sqlparser::dialect::{Dialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
 use sqlparser::parser::{Parser, ParserError};
 use sqlparser::tokenizer::Token::{self, SemiColon, EOF};
-use sqlparser::tokenizer::{TokenWithSpan, Tokenizer};
+use sqlparser::tokenizer::{TokenWithSpan, Tokenizer, Whitespace};
 use sqlx::any::AnyKind;
 use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
@@ -31,24 +32,146 @@ pub struct ParsedSqlFile {
 impl ParsedSqlFile {
     #[must_use]
     pub fn new(db: &Database, sql: &str, source_path: &Path) -> ParsedSqlFile {
-        let dialect = dialect_for_db(db.connection.any_kind());
+        Self::new_for_db_kind(db.connection().any_kind(), sql, source_path)
+    }
+
+    /// Parses a SQL file for the given database type, without needing a database connection.
+    #[must_use]
+    pub fn new_for_db_kind(db_kind: AnyKind, sql: &str, source_path: &Path) -> ParsedSqlFile {
+        let dialect = dialect_for_db(db_kind);
         log::debug!("Parsing SQL file {:?}", source_path);
         let parsed_statements = match parse_sql(dialect.as_ref(), sql) {
             Ok(parsed) => parsed,
             Err(err) => return Self::from_err(err, source_path),
         };
-        let statements = parsed_statements.collect();
+        let statements = group_concurrent_statements(parsed_statements);
         ParsedSqlFile {
             statements,
             source_path: source_path.to_path_buf(),
         }
     }
 
+    /// Path of the file, relative to the web root.
+    #[must_use]
+    pub fn source_path(&self) -> &Path {
+        &self.source_path
+    }
+
+    /// Errors that were found while parsing the file.
+    pub fn parse_errors(&self) -> impl Iterator<Item = &anyhow::Error> {
+        self.statements.iter().filter_map(|stmt| match stmt {
+            ParsedStatement::Error(err) => Some(err),
+            _ => None,
+        })
+    }
+
+    /// The queries that are sent to the database when the file is executed.
+    pub fn database_queries(&self) -> impl Iterator<Item = &str> {
+        self.statements.iter().filter_map(|stmt| match stmt {
+            ParsedStatement::StmtWithParams(stmt)
+            | ParsedStatement::SetVariable { value: stmt, .. } => Some(stmt.query.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Components that are selected with a literal string, as in `select 'list' as component`.
+    /// Components whose name is computed at runtime are not included.
+    #[must_use]
+    pub fn literal_components(&self, db_kind: AnyKind) -> Vec<LiteralComponent> {
+        let dialect = dialect_for_db(db_kind);
+        let mut components = Vec::new();
+        for stmt in &self.statements {
+            match stmt {
+                ParsedStatement::StaticSimpleSelect(values) => {
+                    let name = values.iter().find_map(|(key, value)| match value {
+                        SimpleSelectValue::Static(serde_json::Value::String(name))
+                            if key.eq_ignore_ascii_case("component") =>
+                        {
+                            Some(name.clone())
+                        }
+                        _ => None,
+                    });
+                    if let Some(name) = name {
+                        let properties = values
+                            .iter()
+                            .map(|(key, _)| key.clone())
+                            .filter(|key| !key.eq_ignore_ascii_case("component"))
+                            .collect();
+                        components.push(LiteralComponent {
+                            name,
+                            properties: Some(properties),
+                        });
+                    }
+                }
+                ParsedStatement::StmtWithParams(stmt) => {
+                    let first_new_component = components.len();
+                    for parsed in
+                        Parser::parse_sql(dialect.as_ref(), &stmt.query).unwrap_or_default()
+                    {
+                        if let Statement::Query(query) = parsed {
+                            literal_components_in_set_expr(&query.body, &mut components);
+                        }
+                    }
+                    // sqlpage functions called after the query replace their arguments with their result
+                    for component in &mut components[first_new_component..] {
+                        if let Some(properties) = &mut component.properties {
+                            for func in &stmt.delayed_functions {
+                                properties.retain(|p| !func.argument_col_names.contains(p));
+                                properties.push(func.target_col_name.clone());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        components
+    }
+
+    /// The request parameters that the file reads, such as `$id` or `:name`, sorted by name.
+    /// Variables that the file defines itself with `SET` are not included.
+    #[must_use]
+    pub fn request_parameters(&self) -> Vec<RequestParameter> {
+        let mut set_variables = Vec::new();
+        let mut parameters = std::collections::BTreeSet::new();
+        for stmt in &self.statements {
+            match stmt {
+                ParsedStatement::StmtWithParams(stmt) => {
+                    collect_request_parameters(&stmt.params, &set_variables, &mut parameters);
+                }
+                ParsedStatement::SetVariable { variable, value } => {
+                    collect_request_parameters(&value.params, &set_variables, &mut parameters);
+                    if let StmtParam::PostOrGet(name) | StmtParam::Get(name) = variable {
+                        set_variables.push(name.as_str());
+                    }
+                }
+                ParsedStatement::StaticSimpleSelect(values) => {
+                    for (_, value) in values {
+                        if let SimpleSelectValue::Dynamic(param) = value {
+                            collect_request_parameters(
+                                std::slice::from_ref(param),
+                                &set_variables,
+                                &mut parameters,
+                            );
+                        }
+                    }
+                }
+                ParsedStatement::CsvImport(_)
+                | ParsedStatement::OnError(_)
+                | ParsedStatement::Optional
+                | ParsedStatement::Concurrent(_)
+                | ParsedStatement::ConcurrentGroup(_)
+                | ParsedStatement::Error(_) => {}
+            }
+        }
+        parameters.into_iter().collect()
+    }
+
     fn from_err(e: impl Into<anyhow::Error>, source_path: &Path) -> Self {
         Self {
             statements: vec![ParsedStatement::Error(
                 e.into()
-                    .context(format!("While parsing file {source_path:?}")),
+                    .context(format!("While parsing file {}", source_path.display())),
             )],
             source_path: source_path.to_path_buf(),
         }
@@ -66,6 +189,64 @@ impl AsyncFromStrWithState for ParsedSqlFile {
     }
 }
 
+/// A component selected with a literal name, as in `select 'list' as component, 'My list' as title`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LiteralComponent {
+    pub name: String,
+    /// The names of the other columns of the row,
+    /// or `None` if they cannot be known before running the query, as in `select 'list' as component, *`.
+    pub properties: Option<Vec<String>>,
+}
+
+/// A request parameter used in a SQL file.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct RequestParameter {
+    pub name: String,
+    pub source: ParameterSource,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ParameterSource {
+    /// `?name`, read from the URL query string
+    Query,
+    /// `:name`, read from the submitted form
+    Form,
+    /// `$name`, read from the submitted form, or from the URL query string
+    FormOrQuery,
+}
+
+fn collect_request_parameters(
+    params: &[StmtParam],
+    set_variables: &[&str],
+    parameters: &mut std::collections::BTreeSet<RequestParameter>,
+) {
+    for param in params {
+        let (name, source) = match param {
+            StmtParam::Get(name) => (name, ParameterSource::Query),
+            StmtParam::Post(name) => (name, ParameterSource::Form),
+            StmtParam::PostOrGet(name) if !set_variables.contains(&name.as_str()) => {
+                (name, ParameterSource::FormOrQuery)
+            }
+            StmtParam::Concat(items)
+            | StmtParam::Coalesce(items)
+            | StmtParam::JsonObject(items)
+            | StmtParam::JsonArray(items) => {
+                collect_request_parameters(items, set_variables, parameters);
+                continue;
+            }
+            StmtParam::FunctionCall(call) => {
+                collect_request_parameters(&call.arguments, set_variables, parameters);
+                continue;
+            }
+            _ => continue,
+        };
+        parameters.insert(RequestParameter {
+            name: name.clone(),
+            source,
+        });
+    }
+}
+
 /// A single SQL statement that has been parsed from a SQL file.
 #[derive(Debug, PartialEq)]
 pub(super) struct StmtWithParams {
@@ -108,6 +289,14 @@ pub(super) enum ParsedStatement {
         value: StmtWithParams,
     },
     CsvImport(CsvImport),
+    /// A `-- @on_error` comment, that applies to the statements that follow it
+    OnError(OnError),
+    /// A `-- @optional` comment: the errors of the next statement do not interrupt the page
+    Optional,
+    /// A `-- @concurrent` comment. It is replaced by [`ParsedStatement::ConcurrentGroup`] once the file is parsed.
+    Concurrent(Concurrency),
+    /// The next statements are database queries that are executed concurrently, on separate connections
+    ConcurrentGroup(usize),
     Error(anyhow::Error),
 }
 
@@ -144,6 +333,93 @@ fn parse_sql<'a>(
     }))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Concurrency {
+    /// Only the next statement can be executed concurrently
+    Next,
+    /// All the following statements can be executed concurrently
+    On,
+    Off,
+}
+
+/// Replaces the `-- @concurrent` annotations by groups of consecutive database queries,
+/// that do not depend on each other and can be executed at the same time.
+fn group_concurrent_statements(
+    statements: impl Iterator<Item = ParsedStatement>,
+) -> Vec<ParsedStatement> {
+    fn close_group(grouped: &mut Vec<ParsedStatement>, group: &mut Vec<ParsedStatement>) {
+        if group.len() > 1 {
+            grouped.push(ParsedStatement::ConcurrentGroup(group.len()));
+        }
+        grouped.append(group);
+    }
+    let mut grouped = Vec::new();
+    let mut group = Vec::new();
+    let mut concurrent_mode = false;
+    let mut concurrent_next = false;
+    // Optional statements are rolled back alone, so they are never executed concurrently
+    let mut sequential_next = false;
+    for statement in statements {
+        match statement {
+            ParsedStatement::Concurrent(Concurrency::Next) => concurrent_next = true,
+            ParsedStatement::Concurrent(Concurrency::On) => concurrent_mode = true,
+            ParsedStatement::Concurrent(Concurrency::Off) => concurrent_mode = false,
+            ParsedStatement::StmtWithParams(_)
+                if (std::mem::take(&mut concurrent_next) || concurrent_mode)
+                    && !std::mem::take(&mut sequential_next) =>
+            {
+                group.push(statement);
+            }
+            statement => {
+                close_group(&mut grouped, &mut group);
+                concurrent_next = false;
+                sequential_next = matches!(statement, ParsedStatement::Optional);
+                grouped.push(statement);
+            }
+        }
+    }
+    close_group(&mut grouped, &mut group);
+    grouped
+}
+
+/// Consumes the whitespace and comments before the next statement,
+/// stopping after the first comment that is an annotation such as `-- @on_error continue` or `-- @optional`.
+fn parse_annotation(parser: &mut Parser<'_>) -> Option<ParsedStatement> {
+    loop {
+        let Token::Whitespace(whitespace) = parser.peek_token_no_skip().token else {
+            return None;
+        };
+        parser.next_token_no_skip();
+        let Whitespace::SingleLineComment { comment, .. } = whitespace else {
+            continue;
+        };
+        let comment = comment.trim();
+        let (name, value) = comment
+            .split_once(char::is_whitespace)
+            .unwrap_or((comment, ""));
+        match name {
+            "@on_error" => {
+                return Some(match value.parse() {
+                    Ok(on_error) => ParsedStatement::OnError(on_error),
+                    Err(err) => ParsedStatement::Error(anyhow::anyhow!(err)),
+                })
+            }
+            "@optional" => return Some(ParsedStatement::Optional),
+            "@concurrent" => {
+                return Some(match value.trim() {
+                    "" => ParsedStatement::Concurrent(Concurrency::Next),
+                    "on" => ParsedStatement::Concurrent(Concurrency::On),
+                    "off" => ParsedStatement::Concurrent(Concurrency::Off),
+                    other => ParsedStatement::Error(anyhow::anyhow!(
+                        "Invalid @concurrent value {other:?}: expected nothing, 'on', or 'off'"
+                    )),
+                })
+            }
+            _ => {}
+        }
+    }
+}
+
 fn transform_to_positional_placeholders(stmt: &mut StmtWithParams, db_kind: AnyKind) {
     if let Some((_, DbPlaceHolder::Positional { placeholder })) =
         DB_PLACEHOLDERS.iter().find(|(kind, _)| *kind == db_kind)
@@ -169,6 +445,9 @@ fn parse_single_statement(
     db_kind: AnyKind,
     source_sql: &str,
 ) -> Option<ParsedStatement> {
+    if let Some(annotation) = parse_annotation(parser) {
+        return Some(annotation);
+    }
     if parser.peek_token() == EOF {
         return None;
     }
@@ -237,7 +516,55 @@ fn syntax_error(err: ParserError, parser: &Parser, sql: &str) -> ParsedStatement
     )))
 }
 
-fn dialect_for_db(db_kind: AnyKind) -> Box<dyn Dialect> {
+fn literal_components_in_set_expr(set_expr: &SetExpr, components: &mut Vec<LiteralComponent>) {
+    match set_expr {
+        SetExpr::Select(select) => {
+            let mut name = None;
+            let mut properties = Some(Vec::new());
+            for item in &select.projection {
+                let property = match item {
+                    SelectItem::ExprWithAlias { expr, alias } => {
+                        if alias.value.eq_ignore_ascii_case("component") {
+                            if let Expr::Value(ValueWithSpan {
+                                value: Value::SingleQuotedString(component),
+                                ..
+                            }) = expr
+                            {
+                                name = Some(component.clone());
+                            }
+                            continue;
+                        }
+                        Some(alias.value.clone())
+                    }
+                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => Some(ident.value.clone()),
+                    SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+                        idents.last().map(|ident| ident.value.clone())
+                    }
+                    // The name of the column is chosen by the database
+                    SelectItem::UnnamedExpr(_) => None,
+                    SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => {
+                        properties = None;
+                        None
+                    }
+                };
+                if let (Some(properties), Some(property)) = (&mut properties, property) {
+                    properties.push(property);
+                }
+            }
+            if let Some(name) = name {
+                components.push(LiteralComponent { name, properties });
+            }
+        }
+        SetExpr::SetOperation { left, right, .. } => {
+            literal_components_in_set_expr(left, components);
+            literal_components_in_set_expr(right, components);
+        }
+        SetExpr::Query(query) => literal_components_in_set_expr(&query.body, components),
+        _ => {}
+    }
+}
+
+pub(super) fn dialect_for_db(db_kind: AnyKind) -> Box<dyn Dialect> {
     match db_kind {
         AnyKind::Postgres => Box::new(PostgreSqlDialect {}),
         AnyKind::Mssql => Box::new(MsSqlDialect {}),
@@ -300,56 +627,55 @@ fn extract_toplevel_functions(stmt: &mut Statement) -> Vec<DelayedFunctionCall>
     let mut select_items_to_add: Vec<SelectItemToAdd> = Vec::new();
 
     for (position, select_item) in select_items.iter_mut().enumerate() {
-        match select_item {
-            SelectItem::ExprWithAlias {
-                expr:
-                    Expr::Function(Function {
-                        name: ObjectName(func_name_parts),
-                        args:
-                            FunctionArguments::List(FunctionArgumentList {
-                                args,
-                                duplicate_treatment: None,
-                                ..
-                            }),
+        let SelectItem::ExprWithAlias {
+            expr:
+                Expr::Function(Function {
+                    name: ObjectName(func_name_parts),
+                    args:
+                        FunctionArguments::List(FunctionArgumentList {
+                            args,
+                            duplicate_treatment: None,
+                            ..
+                        }),
+                    ..
+                }),
+            alias,
+        } = select_item
+        else {
+            continue;
+        };
+        if let Some(func_name) = extract_sqlpage_function_name(func_name_parts) {
+            func_name_parts.clear(); // mark the function for deletion
+            let mut argument_col_names = Vec::with_capacity(args.len());
+            for (arg_idx, arg) in args.iter_mut().enumerate() {
+                match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
+                    | FunctionArg::Named {
+                        arg: FunctionArgExpr::Expr(expr),
                         ..
-                    }),
-                alias,
-            } => {
-                if let Some(func_name) = extract_sqlpage_function_name(func_name_parts) {
-                    func_name_parts.clear(); // mark the function for deletion
-                    let mut argument_col_names = Vec::with_capacity(args.len());
-                    for (arg_idx, arg) in args.iter_mut().enumerate() {
-                        match arg {
-                            FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
-                            | FunctionArg::Named {
-                                arg: FunctionArgExpr::Expr(expr),
-                                ..
-                            } => {
-                                let func_idx = delayed_function_calls.len();
-                                let argument_col_name = format!("_sqlpage_f{func_idx}_a{arg_idx}");
-                                argument_col_names.push(argument_col_name.clone());
-                                let expr_to_insert = SelectItem::ExprWithAlias {
-                                    expr: std::mem::replace(expr, Expr::value(Value::Null)),
-                                    alias: Ident::new(argument_col_name),
-                                };
-                                select_items_to_add.push(SelectItemToAdd {
-                                    expr_to_insert,
-                                    position,
-                                });
-                            }
-                            other => {
-                                log::error!("Unsupported argument to {func_name}: {other}");
-                            }
-                        }
+                    } => {
+                        let func_idx = delayed_function_calls.len();
+                        let argument_col_name = format!("_sqlpage_f{func_idx}_a{arg_idx}");
+                        argument_col_names.push(argument_col_name.clone());
+                        let expr_to_insert = SelectItem::ExprWithAlias {
+                            expr: std::mem::replace(expr, Expr::value(Value::Null)),
+                            alias: Ident::new(argument_col_name),
+                        };
+                        select_items_to_add.push(SelectItemToAdd {
+                            expr_to_insert,
+                            position,
+                        });
+                    }
+                    other => {
+                        log::error!("Unsupported argument to {func_name}: {other}");
                     }
-                    delayed_function_calls.push(DelayedFunctionCall {
-                        function: func_name,
-                        argument_col_names,
-                        target_col_name: alias.value.clone(),
-                    });
                 }
             }
-            _ => continue,
+            delayed_function_calls.push(DelayedFunctionCall {
+                function: func_name,
+                argument_col_names,
+                target_col_name: alias.value.clone(),
+            });
         }
     }
     // Insert the new select items (the function arguments) at the positions where the function calls were
@@ -566,7 +892,7 @@ impl ParameterExtractor {
             db_kind,
             parameters: vec![],
         };
-        sql_ast.visit(&mut this);
+        let _ = sql_ast.visit(&mut this);
         this.parameters
     }
 
@@ -963,7 +1289,7 @@ fn sqlpage_func_name(func_name_parts: &[ObjectNamePart]) -> &str {
 
 fn extract_json_columns(stmt: &Statement, db_kind: AnyKind) -> Vec<String> {
     // Only extract JSON columns for databases without native JSON support
-    if matches!(db_kind, AnyKind::Postgres | AnyKind::Mssql) {
+    if matches!(db_kind, AnyKind::Postgres) {
         return Vec::new();
     }
 
@@ -973,7 +1299,7 @@ fn extract_json_columns(stmt: &Statement, db_kind: AnyKind) -> Vec<String> {
         if let SetExpr::Select(select) = query.body.as_ref() {
             for item in &select.projection {
                 if let SelectItem::ExprWithAlias { expr, alias } = item {
-                    if is_json_function(expr) {
+                    if is_json_expression(expr) {
                         json_columns.push(alias.value.clone());
                         log::trace!("Found JSON column: {alias}");
                     }
@@ -985,32 +1311,12 @@ fn extract_json_columns(stmt: &Statement, db_kind: AnyKind) -> Vec<String> {
     json_columns
 }
 
-fn is_json_function(expr: &Expr) -> bool {
+/// Whether the expression returns a JSON document: a call to a JSON function, a cast to JSON,
+/// or a subquery, `CASE` or `COALESCE` that returns one. This lets components receive
+/// aggregated sub-lists, like `(select json_group_array(tag) from tags where ...) as tags`.
+fn is_json_expression(expr: &Expr) -> bool {
     match expr {
-        Expr::Function(function) => {
-            if let [ObjectNamePart::Identifier(Ident { value, .. })] = function.name.0.as_slice() {
-                [
-                    "json_object",
-                    "json_array",
-                    "json_build_object",
-                    "json_build_array",
-                    "to_json",
-                    "to_jsonb",
-                    "json_agg",
-                    "jsonb_agg",
-                    "json_arrayagg",
-                    "json_objectagg",
-                    "json_group_array",
-                    "json_group_object",
-                    "json",
-                    "jsonb",
-                ]
-                .iter()
-                .any(|&func| value.eq_ignore_ascii_case(func))
-            } else {
-                false
-            }
-        }
+        Expr::Function(function) => is_json_function(function),
         Expr::Cast { data_type, .. } => {
             if matches!(data_type, DataType::JSON | DataType::JSONB) {
                 true
@@ -1024,10 +1330,78 @@ fn is_json_function(expr: &Expr) -> bool {
                 false
             }
         }
+        Expr::Nested(inner) => is_json_expression(inner),
+        // SQL Server returns the result of a subquery with a FOR JSON clause as text
+        Expr::Subquery(query) if matches!(query.for_clause, Some(ForClause::Json { .. })) => true,
+        Expr::Subquery(query) => match query.body.as_ref() {
+            SetExpr::Select(select) => match select.projection.as_slice() {
+                [SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. }] => {
+                    is_json_expression(expr)
+                }
+                _ => false,
+            },
+            _ => false,
+        },
+        // Every branch must be JSON or NULL, so that text values are never parsed by accident
+        Expr::Case {
+            conditions,
+            else_result,
+            ..
+        } => conditions
+            .iter()
+            .map(|case_when| &case_when.result)
+            .chain(else_result.as_deref())
+            .all(|result| {
+                matches!(
+                    result,
+                    Expr::Value(ValueWithSpan {
+                        value: Value::Null,
+                        ..
+                    })
+                ) || is_json_expression(result)
+            }),
         _ => false,
     }
 }
 
+fn is_json_function(function: &Function) -> bool {
+    let [ObjectNamePart::Identifier(Ident { value, .. })] = function.name.0.as_slice() else {
+        return false;
+    };
+    if ["coalesce", "ifnull", "isnull"]
+        .iter()
+        .any(|&func| value.eq_ignore_ascii_case(func))
+    {
+        // coalesce(json_group_array(x), '[]') is JSON when its first argument is
+        return match &function.args {
+            FunctionArguments::List(FunctionArgumentList { args, .. }) => matches!(
+                args.first(),
+                Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(first))) if is_json_expression(first)
+            ),
+            _ => false,
+        };
+    }
+    [
+        "json_object",
+        "json_array",
+        "json_build_object",
+        "json_build_array",
+        "to_json",
+        "to_jsonb",
+        "json_agg",
+        "jsonb_agg",
+        "json_arrayagg",
+        "json_objectagg",
+        "json_group_array",
+        "json_group_object",
+        "json_query",
+        "json",
+        "jsonb",
+    ]
+    .iter()
+    .any(|&func| value.eq_ignore_ascii_case(func))
+}
+
 fn expr_to_statement(expr: Expr) -> Statement {
     Statement::Query(Box::new(sqlparser::ast::Query {
         with: None,
@@ -1250,7 +1624,7 @@ mod test {
                 assert!(query.starts_with("SELECT "));
                 assert_eq!(params, [StmtParam::PostOrGet("y".to_string())]);
             } else {
-                panic!("Failed for dialect {dialect:?}: {stmt:#?}",);
+                panic!("Failed for dialect {dialect:?}: {stmt:#?}");
             }
         }
     }
@@ -1385,7 +1759,7 @@ mod test {
                 assert_eq!(query, "SELECT 42 AS sqlpage_set_expr");
                 assert!(params.is_empty());
             } else {
-                panic!("Failed for dialect {dialect:?}: {stmt:#?}",);
+                panic!("Failed for dialect {dialect:?}: {stmt:#?}");
             }
         }
     }
@@ -1449,12 +1823,14 @@ mod test {
                 json_array(1, 2, 3) AS json_col2,
                 (SELECT json_build_object('nested', subq.val) 
                  FROM (SELECT AVG(x) AS val FROM generate_series(1, 5) x) subq
-            ) AS json_col3, -- not supported because of the subquery
+            ) AS json_col3,
             CASE 
                 WHEN EXISTS (SELECT 1 FROM json_cte WHERE cte_json->>'a' = '2')
                 THEN to_json(ARRAY(SELECT cte_json FROM json_cte))
                 ELSE json_build_array()
-            END AS json_col4, -- not supported because of the CASE
+            END AS json_col4,
+            CASE WHEN x THEN json_array() ELSE 'text' END AS text_col,
+            COALESCE((SELECT json_group_array(tag) FROM tags), '[]') AS json_col5,
             json_unknown_fn(regular_column) AS non_json_col,
             CAST(json_col1 AS json) AS json_col6
         FROM some_table
@@ -1470,6 +1846,9 @@ mod test {
             vec![
                 "json_col1".to_string(),
                 "json_col2".to_string(),
+                "json_col3".to_string(),
+                "json_col4".to_string(),
+                "json_col5".to_string(),
                 "json_col6".to_string()
             ]
         );
@@ -1530,6 +1909,17 @@ mod test {
         assert!(!json_columns.contains(&"title".to_string()));
     }
 
+    #[test]
+    fn test_extract_json_columns_mssql() {
+        let sql = "SELECT title, \
+            (SELECT tag FROM tags WHERE tags.post_id = posts.id FOR JSON PATH) AS tags, \
+            JSON_QUERY(metadata) AS metadata \
+            FROM posts";
+        let stmt = parse_stmt(sql, &MsSqlDialect {});
+        let json_columns = extract_json_columns(&stmt, AnyKind::Mssql);
+        assert_eq!(json_columns, ["tags", "metadata"]);
+    }
+
     #[test]
     fn test_positional_placeholders() {
         let sql = "select \
@@ -1617,4 +2007,141 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_on_error_annotation() {
+        use actix_web::http::StatusCode;
+        use ParsedStatement::{OnError as Annotation, StmtWithParams as Stmt};
+        let sql = "-- a comment
+            -- @on_error continue
+            select 1;
+            --@on_error 404
+            select 2;
+            -- @on_error_x abort
+            select 3;
+            -- @optional
+            select 4;";
+        let parsed: Vec<ParsedStatement> = parse_sql(&SQLiteDialect {}, sql).unwrap().collect();
+        assert!(
+            matches!(
+                &parsed[..],
+                [
+                    Annotation(OnError::Continue),
+                    Stmt(_),
+                    Annotation(OnError::Status(StatusCode::NOT_FOUND)),
+                    Stmt(_),
+                    Stmt(_),
+                    ParsedStatement::Optional,
+                    Stmt(_)
+                ]
+            ),
+            "{parsed:#?}"
+        );
+        let sql = "-- @on_error retry\nselect 1";
+        let parsed: Vec<ParsedStatement> = parse_sql(&SQLiteDialect {}, sql).unwrap().collect();
+        match &parsed[..] {
+            [ParsedStatement::Error(err)] => assert!(err.to_string().contains("retry"), "{err}"),
+            other => panic!("expected an error, got: {other:#?}"),
+        }
+    }
+
+    #[test]
+    fn test_group_concurrent_statements() {
+        let sql = "select 1;
+            -- @concurrent
+            select 2;
+            -- @concurrent
+            select 3;
+            select 4;
+            -- @concurrent on
+            select 5;
+            select 6;
+            -- @optional
+            select 7;
+            select 8;
+            select 9;";
+        let file = ParsedSqlFile::new_for_db_kind(AnyKind::Sqlite, sql, Path::new("test.sql"));
+        let shape: Vec<String> = file
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                ParsedStatement::StmtWithParams(stmt) => stmt.query.trim_end_matches(';').into(),
+                ParsedStatement::ConcurrentGroup(count) => format!("group of {count}"),
+                ParsedStatement::Optional => "optional".into(),
+                other => format!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            [
+                "SELECT 1",
+                "group of 2",
+                "SELECT 2",
+                "SELECT 3",
+                "SELECT 4",
+                "group of 2",
+                "SELECT 5",
+                "SELECT 6",
+                "optional",
+                "SELECT 7",
+                "group of 2",
+                "SELECT 8",
+                "SELECT 9",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literal_components() {
+        let sql = "select 'shell' as component, 'Title' as title;
+            select 'list' as component, $x as title;
+            select 'table' as component, users.name, 1 + 1, email as contact from users;
+            select 'text' as COMPONENT union all select 'card' as component, *;
+            select 'code' as component, sqlpage.read_file_as_text(path) as contents from files;
+            select $dynamic as component;
+            select * from users;";
+        let component = |name: &str, properties: Option<&[&str]>| LiteralComponent {
+            name: name.to_string(),
+            properties: properties.map(|p| p.iter().map(ToString::to_string).collect()),
+        };
+        for &(_dialect, db_kind) in ALL_DIALECTS {
+            let parsed = ParsedSqlFile::new_for_db_kind(db_kind, sql, Path::new("x.sql"));
+            assert_eq!(
+                parsed.literal_components(db_kind),
+                [
+                    component("shell", Some(&["title"])),
+                    component("list", Some(&["title"])),
+                    component("table", Some(&["name", "contact"])),
+                    component("text", Some(&[])),
+                    component("card", None),
+                    component("code", Some(&["contents"])),
+                ],
+                "{db_kind:?}"
+            );
+            assert_eq!(parsed.parse_errors().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_request_parameters() {
+        let sql = "select 'list' as component, $title as title;
+            set page = coalesce($page, 1);
+            select * from items where name = :name and page = $page limit 10;
+            select 'text' as component, sqlpage.url_encode($search) as contents;
+            select 'text' as component, $title || :name as contents;";
+        let param = |name: &str, source| RequestParameter {
+            name: name.to_string(),
+            source,
+        };
+        let parsed = ParsedSqlFile::new_for_db_kind(AnyKind::Sqlite, sql, Path::new("x.sql"));
+        assert_eq!(
+            parsed.request_parameters(),
+            [
+                param("name", ParameterSource::Form),
+                param("page", ParameterSource::FormOrQuery),
+                param("search", ParameterSource::FormOrQuery),
+                param("title", ParameterSource::FormOrQuery),
+            ]
+        );
+    }
 }
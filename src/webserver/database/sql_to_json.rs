@@ -1,33 +1,47 @@
+use super::geojson;
+use crate::app_config::AppConfig;
 use crate::utils::add_value_to_map;
-use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use base64::Engine;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
 use serde_json::{self, Map, Value};
 use sqlx::any::{AnyRow, AnyTypeInfo, AnyTypeInfoKind};
 use sqlx::Decode;
 use sqlx::{Column, Row, TypeInfo, ValueRef};
+use std::borrow::Cow;
+use std::fmt::Write;
 
-pub fn row_to_json(row: &AnyRow) -> Value {
+pub fn row_to_json(row: &AnyRow, config: &AppConfig) -> Value {
     use Value::Object;
 
     let columns = row.columns();
     let mut map = Map::new();
     for col in columns {
         let key = col.name().to_string();
-        let value: Value = sql_to_json(row, col);
+        let mut value: Value = sql_to_json(row, col, config);
+        // Spatial values stored as text are converted when they are used as the geojson property of the map component
+        if key == "geojson" {
+            if let Some(geometry) = value.as_str().and_then(geojson::text_to_geojson) {
+                value = geometry;
+            }
+        }
         map = add_value_to_map(map, (key, value));
     }
     Object(map)
 }
 
-pub fn sql_to_json(row: &AnyRow, col: &sqlx::any::AnyColumn) -> Value {
+pub fn sql_to_json(row: &AnyRow, col: &sqlx::any::AnyColumn, config: &AppConfig) -> Value {
     let raw_value_result = row.try_get_raw(col.ordinal());
     match raw_value_result {
         Ok(raw_value) if !raw_value.is_null() => {
             let mut raw_value = Some(raw_value);
-            let decoded = sql_nonnull_to_json(|| {
-                raw_value
-                    .take()
-                    .unwrap_or_else(|| row.try_get_raw(col.ordinal()).unwrap())
-            });
+            let decoded = sql_nonnull_to_json(
+                || {
+                    raw_value
+                        .take()
+                        .unwrap_or_else(|| row.try_get_raw(col.ordinal()).unwrap())
+                },
+                config,
+            );
             log::trace!("Decoded value: {:?}", decoded);
             decoded
         }
@@ -52,15 +66,30 @@ fn decode_raw<'a, T: Decode<'a, sqlx::any::Any> + Default>(
     }
 }
 
-pub fn sql_nonnull_to_json<'r>(mut get_ref: impl FnMut() -> sqlx::any::AnyValueRef<'r>) -> Value {
+pub fn sql_nonnull_to_json<'r>(
+    mut get_ref: impl FnMut() -> sqlx::any::AnyValueRef<'r>,
+    config: &AppConfig,
+) -> Value {
     let raw_value = get_ref();
     let type_info = raw_value.type_info();
     let type_name = type_info.name();
     log::trace!("Decoding a value of type {type_name:?} (type info: {type_info:?})");
+    let is_postgres = matches!(*type_info, AnyTypeInfo(AnyTypeInfoKind::Postgres(_)));
     match type_name {
-        "REAL" | "FLOAT" | "FLOAT4" | "FLOAT8" | "DOUBLE" | "NUMERIC" | "DECIMAL" => {
-            decode_raw::<f64>(raw_value).into()
+        "NUMERIC" if is_postgres => {
+            let (bytes, format) = pg_raw_value(raw_value, get_ref);
+            pg_value_to_json("NUMERIC", &bytes, format, config)
         }
+        "NUMERIC" | "DECIMAL" => {
+            let decimal = if matches!(*type_info, AnyTypeInfo(AnyTypeInfoKind::MySql(_))) {
+                // MySQL always sends decimals as text
+                decode_raw::<String>(raw_value)
+            } else {
+                decode_raw::<sqlx::types::BigDecimal>(raw_value).to_plain_string()
+            };
+            decimal_to_json(decimal, config)
+        }
+        "REAL" | "FLOAT" | "FLOAT4" | "FLOAT8" | "DOUBLE" => decode_raw::<f64>(raw_value).into(),
         "INT8" | "BIGINT" | "SERIAL8" | "BIGSERIAL" | "IDENTITY" | "INT64" | "INTEGER8"
         | "BIGINT SIGNED" => decode_raw::<i64>(raw_value).into(),
         "INT" | "INT4" | "INTEGER" | "MEDIUMINT" | "YEAR" => decode_raw::<i32>(raw_value).into(),
@@ -85,34 +114,412 @@ pub fn sql_nonnull_to_json<'r>(mut get_ref: impl FnMut() -> sqlx::any::AnyValueR
         "TIME" | "TIMETZ" => decode_raw::<chrono::NaiveTime>(raw_value)
             .to_string()
             .into(),
+        // Postgres timestamps without a time zone are not in UTC: they are displayed as they are
+        "TIMESTAMP" if is_postgres => {
+            naive_datetime_to_json(decode_raw::<NaiveDateTime>(raw_value))
+        }
         "DATETIMEOFFSET" | "TIMESTAMP" | "TIMESTAMPTZ" => {
             decode_raw::<DateTime<FixedOffset>>(raw_value)
                 .to_rfc3339()
                 .into()
         }
-        "DATETIME" | "DATETIME2" => decode_raw::<NaiveDateTime>(raw_value)
-            .format("%FT%T%.f")
-            .to_string()
-            .into(),
-        "JSON" | "JSON[]" | "JSONB" | "JSONB[]" => decode_raw::<Value>(raw_value),
+        "DATETIME" | "DATETIME2" => naive_datetime_to_json(decode_raw::<NaiveDateTime>(raw_value)),
+        "JSON" | "JSONB" => decode_raw::<Value>(raw_value),
+        "UUID" | "BYTEA" if is_postgres => {
+            let type_name = type_name.to_owned();
+            let (bytes, format) = pg_raw_value(raw_value, get_ref);
+            pg_value_to_json(&type_name, &bytes, format, config)
+        }
+        "geometry" | "geography" if is_postgres => {
+            let (bytes, format) = pg_raw_value(raw_value, get_ref);
+            let geometry = match format {
+                PgFormat::Binary => geojson::wkb_to_geojson(&bytes),
+                PgFormat::Text => decode_hex(&bytes).and_then(|wkb| geojson::wkb_to_geojson(&wkb)),
+            };
+            geometry.unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned().into())
+        }
+        "GEOMETRY" if matches!(*type_info, AnyTypeInfo(AnyTypeInfoKind::MySql(_))) => {
+            let bytes = decode_raw::<&[u8]>(raw_value);
+            geojson::mysql_geometry_to_geojson(bytes).unwrap_or_else(|| base64_encode(bytes).into())
+        }
+        "BLOB" if matches!(*type_info, AnyTypeInfo(AnyTypeInfoKind::Sqlite(_))) => {
+            let bytes = decode_raw::<&[u8]>(raw_value);
+            geojson::sqlite_geometry_to_geojson(bytes)
+                .unwrap_or_else(|| base64_encode(bytes).into())
+        }
+        "UNIQUEIDENTIFIER" => {
+            let bytes = decode_raw::<&[u8]>(raw_value);
+            <[u8; 16]>::try_from(bytes).map_or(Value::Null, |guid| {
+                // SQL Server stores the first three groups of a GUID in little-endian order
+                let mut uuid = guid;
+                uuid[0..4].reverse();
+                uuid[4..6].reverse();
+                uuid[6..8].reverse();
+                format_uuid(&uuid).into()
+            })
+        }
+        "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" | "BIGBINARY"
+        | "BIGVARBINARY" | "IMAGE" => base64_encode(decode_raw::<&[u8]>(raw_value)).into(),
+        array_type if is_postgres && array_type.ends_with("[]") => {
+            let array_type = array_type.to_owned();
+            let element_type = array_type.trim_end_matches("[]");
+            let (bytes, format) = pg_raw_value(raw_value, get_ref);
+            pg_array_to_json(element_type, &bytes, format, config).unwrap_or_else(|| {
+                log::error!("Failed to decode a value of type {array_type}");
+                Value::Null
+            })
+        }
         // Deserialize as a string by default
         _ => decode_raw::<String>(raw_value).into(),
     }
 }
 
 /// Takes the first column of a row and converts it to a string.
-pub fn row_to_string(row: &AnyRow) -> Option<String> {
+pub fn row_to_string(row: &AnyRow, config: &AppConfig) -> Option<String> {
     let col = row.columns().first()?;
-    match sql_to_json(row, col) {
+    match sql_to_json(row, col, config) {
         serde_json::Value::String(s) => Some(s),
         serde_json::Value::Null => None,
         other => Some(other.to_string()),
     }
 }
 
+fn base64_encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let mut uuid = String::with_capacity(36);
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+        let _ = write!(uuid, "{byte:02x}");
+    }
+    uuid
+}
+
+/// Postgres sends values in text format in response to simple queries (without parameters),
+/// and in binary format in response to prepared statements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PgFormat {
+    Text,
+    Binary,
+}
+
+/// Returns the raw bytes of a postgres value, and the format they are in.
+/// The `Any` driver does not expose the format, but it refuses to decode text values as raw bytes.
+fn pg_raw_value<'r>(
+    raw_value: sqlx::any::AnyValueRef<'r>,
+    mut get_ref: impl FnMut() -> sqlx::any::AnyValueRef<'r>,
+) -> (Cow<'r, [u8]>, PgFormat) {
+    match <&[u8] as Decode<sqlx::any::Any>>::decode(raw_value) {
+        Ok(bytes) => (Cow::Borrowed(bytes), PgFormat::Binary),
+        Err(_) => (
+            Cow::Owned(decode_raw::<String>(get_ref()).into_bytes()),
+            PgFormat::Text,
+        ),
+    }
+}
+
+/// Converts a postgres value of the given type to json. Used for array elements,
+/// and for scalar types that the `Any` driver cannot decode in both formats.
+fn pg_value_to_json(type_name: &str, bytes: &[u8], format: PgFormat, config: &AppConfig) -> Value {
+    let decoded = match (type_name, format) {
+        ("BOOL", PgFormat::Binary) => bytes.first().map(|&b| Value::Bool(b != 0)),
+        ("BOOL", PgFormat::Text) => Some(Value::Bool(bytes == b"t")),
+        ("INT2" | "INT4" | "INT8", PgFormat::Binary) => match bytes.len() {
+            2 => Some(i16::from_be_bytes([bytes[0], bytes[1]]).into()),
+            4 => bytes.try_into().ok().map(|b| i32::from_be_bytes(b).into()),
+            8 => bytes.try_into().ok().map(|b| i64::from_be_bytes(b).into()),
+            _ => None,
+        },
+        ("FLOAT4", PgFormat::Binary) => bytes.try_into().ok().map(|b| f32::from_be_bytes(b).into()),
+        ("FLOAT8", PgFormat::Binary) => bytes.try_into().ok().map(|b| f64::from_be_bytes(b).into()),
+        ("INT2" | "INT4" | "INT8" | "FLOAT4" | "FLOAT8", PgFormat::Text) => {
+            std::str::from_utf8(bytes).ok().and_then(parse_json_number)
+        }
+        ("NUMERIC", _) => pg_numeric_to_string(bytes, format).map(|s| decimal_to_json(s, config)),
+        ("UUID", PgFormat::Binary) => bytes.try_into().ok().map(|b| format_uuid(b).into()),
+        ("BYTEA", PgFormat::Binary) => Some(base64_encode(bytes).into()),
+        ("BYTEA", PgFormat::Text) => bytes
+            .strip_prefix(b"\\x")
+            .and_then(decode_hex)
+            .map(|b| base64_encode(&b).into()),
+        ("JSON", _) | ("JSONB", PgFormat::Text) => serde_json::from_slice(bytes).ok(),
+        // Binary jsonb values are prefixed with a version number
+        ("JSONB", PgFormat::Binary) => bytes
+            .split_first()
+            .and_then(|(_version, json)| serde_json::from_slice(json).ok()),
+        ("DATE", PgFormat::Binary) => bytes.try_into().ok().and_then(|b| {
+            pg_epoch()
+                .date()
+                .checked_add_signed(chrono::Duration::days(i32::from_be_bytes(b).into()))
+                .map(|date| date.to_string().into())
+        }),
+        ("TIMESTAMP", PgFormat::Binary) => pg_binary_timestamp(bytes).map(naive_datetime_to_json),
+        ("TIMESTAMPTZ", PgFormat::Binary) => {
+            pg_binary_timestamp(bytes).map(|datetime| datetime.and_utc().to_rfc3339().into())
+        }
+        ("DATE", PgFormat::Text) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+            .map(|date| date.to_string().into()),
+        ("TIMESTAMP", PgFormat::Text) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").ok())
+            .map(naive_datetime_to_json),
+        ("TIMESTAMPTZ", PgFormat::Text) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%#z").ok())
+            .map(|datetime| datetime.to_rfc3339().into()),
+        _ => None,
+    };
+    // Values of other types have the same representation in text and binary formats
+    decoded.unwrap_or_else(|| String::from_utf8_lossy(bytes).into_owned().into())
+}
+
+/// Date and time values without a time zone, in all databases, and in arrays as well as in columns
+fn naive_datetime_to_json(datetime: NaiveDateTime) -> Value {
+    datetime.format("%FT%T%.f").to_string().into()
+}
+
+/// Binary timestamps are the number of microseconds since the Postgres epoch
+fn pg_binary_timestamp(bytes: &[u8]) -> Option<NaiveDateTime> {
+    let microseconds = i64::from_be_bytes(bytes.try_into().ok()?);
+    pg_epoch().checked_add_signed(chrono::Duration::microseconds(microseconds))
+}
+
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap_or_default()
+}
+
+/// Converts an exact decimal value to a JSON number when the number has the same value,
+/// and keeps it as a string otherwise, so that no digit is silently lost.
+fn decimal_to_json(decimal: String, config: &AppConfig) -> Value {
+    if config.decimals_as_strings {
+        return Value::String(decimal);
+    }
+    if let Ok(i) = decimal.parse::<i64>() {
+        return i.into();
+    }
+    let Ok(float) = decimal.parse::<f64>() else {
+        return Value::String(decimal);
+    };
+    let significant = if decimal.contains('.') {
+        decimal.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &decimal
+    };
+    if float.is_finite() && float.to_string() == significant {
+        float.into()
+    } else {
+        Value::String(decimal)
+    }
+}
+
+fn parse_json_number(s: &str) -> Option<Value> {
+    if let Ok(i) = s.parse::<i64>() {
+        Some(i.into())
+    } else {
+        s.parse::<f64>().ok().map(Value::from)
+    }
+}
+
+pub(super) fn decode_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.chunks_exact(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Converts a postgres NUMERIC to its exact decimal representation.
+/// The binary format is a sequence of big-endian 16-bit integers:
+/// the number of digits, the weight of the first digit, the sign, the display scale,
+/// and then the digits themselves, in base 10000.
+fn pg_numeric_to_string(bytes: &[u8], format: PgFormat) -> Option<String> {
+    if format == PgFormat::Text {
+        return std::str::from_utf8(bytes).ok().map(str::to_owned);
+    }
+    let mut words = bytes
+        .chunks_exact(2)
+        .map(|w| u16::from_be_bytes([w[0], w[1]]));
+    let ndigits = usize::from(words.next()?);
+    let weight = i64::from(i16::from_ne_bytes(words.next()?.to_ne_bytes()));
+    let sign = words.next()?;
+    let dscale = usize::from(words.next()?);
+    let digits: Vec<u16> = words.collect();
+    if digits.len() != ndigits {
+        return None;
+    }
+    match sign {
+        0x0000 | 0x4000 => {}
+        0xC000 => return Some("NaN".into()),
+        0xD000 => return Some("Infinity".into()),
+        0xF000 => return Some("-Infinity".into()),
+        _ => return None,
+    }
+    let digit_at = |position: i64| {
+        usize::try_from(position)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+    let mut result = String::new();
+    if sign == 0x4000 {
+        result.push('-');
+    }
+    if weight < 0 {
+        result.push('0');
+    } else {
+        result.push_str(&digit_at(0).to_string());
+        for position in 1..=weight {
+            let _ = write!(result, "{:04}", digit_at(position));
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut position = weight + 1;
+        while fraction.len() < dscale {
+            let _ = write!(fraction, "{:04}", digit_at(position));
+            position += 1;
+        }
+        fraction.truncate(dscale);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+    Some(result)
+}
+
+/// Converts a postgres array, in text or binary format, to a (possibly nested) json array.
+fn pg_array_to_json(
+    element_type: &str,
+    bytes: &[u8],
+    format: PgFormat,
+    config: &AppConfig,
+) -> Option<Value> {
+    match format {
+        PgFormat::Binary => {
+            let mut reader = PgBinaryReader(bytes);
+            let ndim = usize::try_from(reader.read_i32()?).ok()?;
+            let _has_nulls = reader.read_i32()?;
+            let _element_oid = reader.read_i32()?;
+            let mut dimensions = Vec::with_capacity(ndim);
+            for _ in 0..ndim {
+                dimensions.push(usize::try_from(reader.read_i32()?).ok()?);
+                let _lower_bound = reader.read_i32()?;
+            }
+            read_pg_binary_array(element_type, &dimensions, &mut reader, config)
+        }
+        PgFormat::Text => {
+            let text = std::str::from_utf8(bytes).ok()?;
+            // Arrays with custom bounds are prefixed with their dimensions, like [0:1]={1,2}
+            let text = text.find('{').map_or(text, |start| &text[start..]);
+            let mut chars = text.chars().peekable();
+            let array = read_pg_text_array(element_type, &mut chars, config)?;
+            chars.next().is_none().then_some(array)
+        }
+    }
+}
+
+struct PgBinaryReader<'a>(&'a [u8]);
+
+impl<'a> PgBinaryReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (bytes, rest) = self.0.split_at_checked(len)?;
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn read_i32(&mut self) -> Option<i32> {
+        self.read_bytes(4)?.try_into().ok().map(i32::from_be_bytes)
+    }
+}
+
+fn read_pg_binary_array(
+    element_type: &str,
+    dimensions: &[usize],
+    reader: &mut PgBinaryReader,
+    config: &AppConfig,
+) -> Option<Value> {
+    let Some((&len, inner_dimensions)) = dimensions.split_first() else {
+        return Some(Value::Array(vec![]));
+    };
+    let mut values = Vec::with_capacity(len);
+    for _ in 0..len {
+        let value = if inner_dimensions.is_empty() {
+            // NULL elements have a length of -1
+            match usize::try_from(reader.read_i32()?) {
+                Ok(element_len) => {
+                    let element = reader.read_bytes(element_len)?;
+                    pg_value_to_json(element_type, element, PgFormat::Binary, config)
+                }
+                Err(_) => Value::Null,
+            }
+        } else {
+            read_pg_binary_array(element_type, inner_dimensions, reader, config)?
+        };
+        values.push(value);
+    }
+    Some(Value::Array(values))
+}
+
+fn read_pg_text_array(
+    element_type: &str,
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    config: &AppConfig,
+) -> Option<Value> {
+    if chars.next()? != '{' {
+        return None;
+    }
+    let mut values = Vec::new();
+    if chars.next_if_eq(&'}').is_some() {
+        return Some(Value::Array(values));
+    }
+    loop {
+        let value = match chars.peek()? {
+            '{' => read_pg_text_array(element_type, chars, config)?,
+            '"' => {
+                chars.next();
+                let mut element = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => element.push(chars.next()?),
+                        c => element.push(c),
+                    }
+                }
+                pg_value_to_json(element_type, element.as_bytes(), PgFormat::Text, config)
+            }
+            _ => {
+                let mut element = String::new();
+                while let Some(c) = chars.next_if(|&c| c != ',' && c != '}') {
+                    element.push(c);
+                }
+                if element.eq_ignore_ascii_case("NULL") {
+                    Value::Null
+                } else {
+                    pg_value_to_json(element_type, element.as_bytes(), PgFormat::Text, config)
+                }
+            }
+        };
+        values.push(value);
+        match chars.next()? {
+            ',' => {}
+            '}' => return Some(Value::Array(values)),
+            _ => return None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::app_config::tests::test_database_url;
+    use crate::app_config::tests::{test_config, test_database_url};
 
     use super::*;
     use sqlx::Connection;
@@ -135,6 +542,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decimal_to_json() {
+        let mut config = test_config();
+        let convert = |s: &str, config: &AppConfig| decimal_to_json(s.to_string(), config);
+        assert_eq!(convert("42", &config), Value::from(42));
+        assert_eq!(convert("123.4500", &config), Value::from(123.45));
+        assert_eq!(convert("-0.00012", &config), Value::from(-0.00012));
+        assert_eq!(
+            convert("12345678901234567.89", &config),
+            Value::from("12345678901234567.89")
+        );
+        config.decimals_as_strings = true;
+        assert_eq!(convert("123.4500", &config), Value::from("123.4500"));
+    }
+
     #[actix_web::test]
     async fn test_row_to_json() -> anyhow::Result<()> {
         use sqlx::Connection;
@@ -147,17 +569,19 @@ mod tests {
             2 as two_values, \
             'x' as three_values, \
             'y' as three_values, \
-            'z' as three_values \
+            'z' as three_values, \
+            'POINT(1 2)' as geojson \
         ",
         )
         .fetch_one(&mut c)
         .await?;
         assert_eq!(
-            row_to_json(&row),
+            row_to_json(&row, &test_config()),
             serde_json::json!({
                 "one_value": 123.456,
                 "two_values": [1,2],
                 "three_values": ["x","y","z"],
+                "geojson": {"type": "Point", "coordinates": [1.0, 2.0]},
             })
         );
         Ok(())
@@ -193,7 +617,7 @@ mod tests {
         .await?;
 
         expect_json_object_equal(
-            &row_to_json(&row),
+            &row_to_json(&row, &test_config()),
             &serde_json::json!({
                 "small_int": 42,
                 "integer": 42,
@@ -203,7 +627,7 @@ mod tests {
                 "boolean": true,
                 "date": "2024-03-14",
                 "time": "13:14:15",
-                "timestamp": "2024-03-14T13:14:15+00:00",
+                "timestamp": "2024-03-14T13:14:15",
                 "timestamptz": "2024-03-14T11:14:15+00:00",
                 "complex_interval": "1 year 2 mons 3 days",
                 "hour_interval": "04:00:00",
@@ -217,6 +641,65 @@ mod tests {
         Ok(())
     }
 
+    #[actix_web::test]
+    async fn test_postgres_rich_types() -> anyhow::Result<()> {
+        let Some(db_url) = db_specific_test("postgres") else {
+            return Ok(());
+        };
+        let mut c = sqlx::AnyConnection::connect(&db_url).await?;
+        // Queries without parameters return values in text format, and queries with parameters in binary format
+        let sql = "SELECT
+                $1 as param,
+                123.4500::NUMERIC as numeric,
+                -0.00012::NUMERIC as small_numeric,
+                100000000000000000000.000000000000000000001::NUMERIC as big_numeric,
+                'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::UUID as uuid,
+                '\\x68656c6c6f'::BYTEA as bytea,
+                ARRAY[1, 2, 3] as int_array,
+                '{{1,2},{3,4}}'::INT[] as nested_array,
+                '{}'::INT[] as empty_array,
+                ARRAY['a b', NULL, 'c\"', 'NULL'] as text_array,
+                ARRAY[1.5]::NUMERIC[] as numeric_array,
+                ARRAY['{\"key\": \"value\"}'::JSONB] as jsonb_array,
+                ARRAY['2024-03-14 13:14:15+02:00'::TIMESTAMPTZ] as timestamptz_array,
+                '2024-03-14 13:14:15.5'::TIMESTAMP as timestamp,
+                ARRAY['2024-03-14 13:14:15.5'::TIMESTAMP] as timestamp_array";
+        let text_sql = sql.replace("$1", "'x'");
+        let text_row = sqlx::query(&text_sql).fetch_one(&mut c).await?;
+        let binary_row = sqlx::query(sql).bind("x").fetch_one(&mut c).await?;
+
+        let mut config = test_config();
+        let expected = serde_json::json!({
+            "param": "x",
+            "numeric": 123.45,
+            "small_numeric": -0.00012,
+            "big_numeric": "100000000000000000000.000000000000000000001",
+            "uuid": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "bytea": "aGVsbG8=",
+            "int_array": [1, 2, 3],
+            "nested_array": [[1, 2], [3, 4]],
+            "empty_array": [],
+            "text_array": ["a b", null, "c\"", "NULL"],
+            "numeric_array": [1.5],
+            "jsonb_array": [{"key": "value"}],
+            "timestamptz_array": ["2024-03-14T11:14:15+00:00"],
+            "timestamp": "2024-03-14T13:14:15.500",
+            "timestamp_array": ["2024-03-14T13:14:15.500"],
+        });
+        expect_json_object_equal(&row_to_json(&text_row, &config), &expected);
+        expect_json_object_equal(&row_to_json(&binary_row, &config), &expected);
+
+        config.decimals_as_strings = true;
+        let mut expected = expected;
+        expected["numeric"] = "123.4500".into();
+        expected["small_numeric"] = "-0.00012".into();
+        expected["big_numeric"] = "100000000000000000000.000000000000000000001".into();
+        expected["numeric_array"] = serde_json::json!(["1.5"]);
+        expect_json_object_equal(&row_to_json(&text_row, &config), &expected);
+        expect_json_object_equal(&row_to_json(&binary_row, &config), &expected);
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_mysql_types() -> anyhow::Result<()> {
         let db_url = db_specific_test("mysql").or_else(|| db_specific_test("mariadb"));
@@ -282,7 +765,7 @@ mod tests {
             .await?;
 
         expect_json_object_equal(
-            &row_to_json(&row),
+            &row_to_json(&row, &test_config()),
             &serde_json::json!({
                 "tiny_int": 127,
                 "small_int": 32767,
@@ -331,12 +814,12 @@ mod tests {
         .await?;
 
         expect_json_object_equal(
-            &row_to_json(&row),
+            &row_to_json(&row, &test_config()),
             &serde_json::json!({
                 "integer": 42,
                 "real": 42.25,
                 "string": "xxx",
-                "blob": "hello world",
+                "blob": "aGVsbG8gd29ybGQ=",
             }),
         );
         Ok(())
@@ -372,7 +855,7 @@ mod tests {
         .await?;
 
         expect_json_object_equal(
-            &row_to_json(&row),
+            &row_to_json(&row, &test_config()),
             &serde_json::json!({
                 "true_bit": true,
                 "false_bit": false,
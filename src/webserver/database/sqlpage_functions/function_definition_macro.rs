@@ -52,17 +52,23 @@ macro_rules! sqlpage_functions {
             }
         }
         impl SqlPageFunctionName {
-            pub(crate) async fn evaluate<'a>(
-                &self,
+            /// The future of each function is boxed, so that polling a function call does not use
+            /// a stack frame large enough for all the functions, at each level of nested `run_sql` calls
+            pub(crate) fn evaluate<'a, 'c>(
+                self,
                 #[allow(unused_variables)]
                 request: &'a RequestInfo,
-                db_connection: &mut Option<sqlx::pool::PoolConnection<sqlx::Any>>,
+                #[allow(unused_variables)]
+                db_connection: &'c mut Option<sqlx::pool::PoolConnection<sqlx::Any>>,
                 params: Vec<Option<Cow<'a, str>>>
-            ) -> anyhow::Result<Option<Cow<'a, str>>> {
+            ) -> futures_util::future::LocalBoxFuture<'c, anyhow::Result<Option<Cow<'a, str>>>>
+            where
+                'a: 'c,
+            {
                 use $crate::webserver::database::sqlpage_functions::function_traits::*;
                 match self {
                     $(
-                        SqlPageFunctionName::$func_name => {
+                        SqlPageFunctionName::$func_name => Box::pin(async move {
                             let mut iter_params = params.into_iter();
                             $(
                                 let $param_name = <$param_type as FunctionParamType<'_>>::from_args(&mut iter_params)
@@ -79,7 +85,7 @@ macro_rules! sqlpage_functions {
                                 $($param_name.into()),*
                             ).await;
                             result.into_cow_result()
-                        }
+                        }),
                     )*
                 }
             }
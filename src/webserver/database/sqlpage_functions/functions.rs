@@ -1,23 +1,35 @@
 use super::RequestInfo;
 use crate::webserver::{
     database::{
-        execute_queries::DbConn, sqlpage_functions::url_parameter_deserializer::URLParameters,
+        csv_import::{csv_to_json, CsvReadOptions},
+        execute_queries::DbConn,
+        sqlpage_functions::{signed_token, url_parameter_deserializer::URLParameters},
     },
-    http::SingleOrVec,
+    http::{asset_version, SingleOrVec, ASSET_VERSION_PARAMETER},
     request_variables::ParamMap,
-    ErrorWithStatus,
+    routing::canonical_path,
+    signed_url, ErrorWithStatus,
 };
 use anyhow::{anyhow, Context};
 use futures_util::StreamExt;
 use mime_guess::mime;
-use std::{borrow::Cow, ffi::OsStr, str::FromStr, sync::OnceLock};
+use std::{borrow::Cow, collections::BTreeMap, ffi::OsStr, str::FromStr, sync::OnceLock};
 
 super::function_definition_macro::sqlpage_functions! {
+    asset_url((&RequestInfo), file_path: Option<Cow<str>>);
+
     basic_auth_password((&RequestInfo));
     basic_auth_username((&RequestInfo));
+    bot_protection_passed((&RequestInfo));
 
+    canonical_url((&RequestInfo));
+    client_browser((&RequestInfo));
+    client_country((&RequestInfo));
     client_ip((&RequestInfo));
+    client_is_mobile((&RequestInfo));
+    client_os((&RequestInfo));
     cookie((&RequestInfo), name: Cow<str>);
+    csrf_verified((&RequestInfo));
     current_working_directory();
 
     environment_variable(name: Cow<str>);
@@ -26,33 +38,81 @@ super::function_definition_macro::sqlpage_functions! {
     fetch((&RequestInfo), http_request: SqlPageFunctionParam<super::http_fetch_request::HttpFetchRequest<'_>>);
     fetch_with_meta((&RequestInfo), http_request: SqlPageFunctionParam<super::http_fetch_request::HttpFetchRequest<'_>>);
 
+    geoip((&RequestInfo), ip: Option<Cow<str>>);
+
     hash_password(password: Option<String>);
     header((&RequestInfo), name: Cow<str>);
     headers((&RequestInfo));
 
     link(file: Cow<str>, parameters: Option<Cow<str>>, hash: Option<Cow<str>>);
+    list_files((&RequestInfo), path: Option<Cow<str>>);
 
     path((&RequestInfo));
     persist_uploaded_file((&RequestInfo), field_name: Cow<str>, folder: Option<Cow<str>>, allowed_extensions: Option<Cow<str>>);
     protocol((&RequestInfo));
 
     random_string(string_length: SqlPageFunctionParam<usize>);
+    read_csv((&RequestInfo), file_path: Option<Cow<str>>, options: Option<Cow<str>>);
     read_file_as_data_url((&RequestInfo), file_path: Option<Cow<str>>);
     read_file_as_text((&RequestInfo), file_path: Option<Cow<str>>);
     request_method((&RequestInfo));
     run_sql((&RequestInfo, &mut DbConn), sql_file_path: Option<Cow<str>>, variables: Option<Cow<str>>);
 
+    sign_token((&RequestInfo), payload: Option<Cow<str>>, expires_in_seconds: Option<Cow<str>>);
+    sign_url((&RequestInfo), url: Option<Cow<str>>, expires_in_seconds: Option<Cow<str>>);
+
+    timezone((&RequestInfo));
+    to_timezone((&RequestInfo), timestamp: Option<Cow<str>>, timezone: Option<Cow<str>>);
+
     uploaded_file_mime_type((&RequestInfo), upload_name: Cow<str>);
     uploaded_file_path((&RequestInfo), upload_name: Cow<str>);
     uploaded_file_name((&RequestInfo), upload_name: Cow<str>);
     url_encode(raw_text: Option<Cow<str>>);
+    url_is_signed((&RequestInfo));
 
     variables((&RequestInfo), get_or_post: Option<Cow<str>>);
-    version();
+    verify_token((&RequestInfo), token: Option<Cow<str>>);
+    version(detail: Option<Cow<str>>);
     request_body((&RequestInfo));
     request_body_base64((&RequestInfo));
 }
 
+/// Returns the given URL of a static file from the web root, with a hash of the file's contents
+/// in a `v` query parameter. Such URLs change when the file changes,
+/// so they are served with headers that let browsers cache them indefinitely.
+async fn asset_url<'a>(
+    request: &'a RequestInfo,
+    file_path: Option<Cow<'a, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(file_path) = file_path else {
+        log::debug!("asset_url: first argument is NULL, returning NULL");
+        return Ok(None);
+    };
+    let relative_path = file_path.split(['?', '#']).next().unwrap_or_default();
+    let relative_path = relative_path.trim_start_matches('/');
+    let contents = request
+        .app_state
+        .file_system
+        .read_file(
+            &request.app_state,
+            std::path::Path::new(relative_path),
+            false,
+        )
+        .await
+        .with_context(|| format!("asset_url: unable to read {file_path:?}"))?;
+    let version = asset_version(&contents);
+    let (url, fragment) = file_path
+        .split_once('#')
+        .map_or((file_path.as_ref(), None), |(u, f)| (u, Some(f)));
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let mut versioned = format!("{url}{separator}{ASSET_VERSION_PARAMETER}={version}");
+    if let Some(fragment) = fragment {
+        versioned.push('#');
+        versioned.push_str(fragment);
+    }
+    Ok(Some(versioned))
+}
+
 /// Returns the password from the HTTP basic auth header, if present.
 async fn basic_auth_password(request: &RequestInfo) -> anyhow::Result<&str> {
     let password = extract_basic_auth(request)?.password().ok_or_else(|| {
@@ -109,7 +169,7 @@ async fn exec<'a>(
     program_name: Cow<'a, str>,
     args: Vec<Cow<'a, str>>,
 ) -> anyhow::Result<String> {
-    if !request.app_state.config.allow_exec {
+    if !request.app_state.config().allow_exec {
         anyhow::bail!("The sqlpage.exec() function is disabled in the configuration, for security reasons.
         Make sure you understand the security implications before enabling it, and never allow user input to be passed as the first argument to this function.
         You can enable it by setting the allow_exec option to true in the sqlpage.json configuration file.")
@@ -180,7 +240,7 @@ async fn fetch(
     request: &RequestInfo,
     http_request: super::http_fetch_request::HttpFetchRequest<'_>,
 ) -> anyhow::Result<String> {
-    let client = make_http_client(&request.app_state.config)
+    let client = make_http_client(&request.app_state.config())
         .with_context(|| "Unable to create an HTTP client")?;
     let req = build_request(&client, &http_request)?;
 
@@ -223,7 +283,7 @@ async fn fetch_with_meta(
 ) -> anyhow::Result<String> {
     use serde::{ser::SerializeMap, Serializer};
 
-    let client = make_http_client(&request.app_state.config)
+    let client = make_http_client(&request.app_state.config())
         .with_context(|| "Unable to create an HTTP client")?;
     let req = build_request(&client, &http_request)?;
 
@@ -314,7 +374,9 @@ async fn fetch_with_meta(
 
 static NATIVE_CERTS: OnceLock<anyhow::Result<rustls::RootCertStore>> = OnceLock::new();
 
-fn make_http_client(config: &crate::app_config::AppConfig) -> anyhow::Result<awc::Client> {
+pub(crate) fn make_http_client(
+    config: &crate::app_config::AppConfig,
+) -> anyhow::Result<awc::Client> {
     let connector = if config.system_root_ca_certificates {
         let roots = NATIVE_CERTS
             .get_or_init(|| {
@@ -402,6 +464,27 @@ async fn link<'a>(
     Ok(url)
 }
 
+/// Returns the absolute URL of the current page, in the form preferred by the `url_style` configuration,
+/// with its query string parameters sorted by name, and without the internal `_sqlpage_*` parameters.
+async fn canonical_url(request: &RequestInfo) -> anyhow::Result<String> {
+    let path = canonical_path(&request.path, request.app_state.config().url_style);
+    let mut url = match &request.host {
+        Some(host) => format!("{}://{host}{path}", request.protocol),
+        None => path.into_owned(),
+    };
+    let parameters: BTreeMap<&String, &SingleOrVec> = request
+        .get_variables
+        .iter()
+        .filter(|(name, _)| !name.starts_with("_sqlpage"))
+        .collect();
+    if !parameters.is_empty() {
+        let encoded = serde_json::from_str::<URLParameters>(&serde_json::to_string(&parameters)?)?;
+        url.push('?');
+        url.push_str(encoded.get());
+    }
+    Ok(url)
+}
+
 /// Returns the path component of the URL of the current request.
 async fn path(request: &RequestInfo) -> &str {
     &request.path
@@ -424,7 +507,7 @@ async fn persist_uploaded_file<'a>(
         return Ok(None);
     };
     let file_name = uploaded_file.file_name.as_deref().unwrap_or_default();
-    let extension = file_name.split('.').last().unwrap_or_default();
+    let extension = file_name.split('.').next_back().unwrap_or_default();
     if !allowed_extensions
         .clone()
         .any(|x| x.eq_ignore_ascii_case(extension))
@@ -433,12 +516,12 @@ async fn persist_uploaded_file<'a>(
         anyhow::bail!("file extension {extension} is not allowed. Allowed extensions: {exts}");
     }
     // resolve the folder path relative to the web root
-    let web_root = &request.app_state.config.web_root;
+    let web_root = &request.app_state.config().web_root;
     let target_folder = web_root.join(&*folder);
     // create the folder if it doesn't exist
     tokio::fs::create_dir_all(&target_folder)
         .await
-        .with_context(|| format!("unable to create folder {target_folder:?}"))?;
+        .with_context(|| format!("unable to create folder {}", target_folder.display()))?;
     let date = chrono::Utc::now().format("%Y-%m-%d_%Hh%Mm%Ss");
     let random_part = random_string_sync(8);
     let random_target_name = format!("{date}_{random_part}.{extension}");
@@ -446,14 +529,22 @@ async fn persist_uploaded_file<'a>(
     tokio::fs::copy(&uploaded_file.file.path(), &target_path)
         .await
         .with_context(|| {
-            format!("unable to copy uploaded file {field_name:?} to {target_path:?}")
+            format!(
+                "unable to copy uploaded file {field_name:?} to {}",
+                target_path.display()
+            )
         })?;
     // remove the WEB_ROOT prefix from the path, but keep the leading slash
     let path = "/".to_string()
         + target_path
             .strip_prefix(web_root)?
             .to_str()
-            .with_context(|| format!("unable to convert path {target_path:?} to a string"))?;
+            .with_context(|| {
+                format!(
+                    "unable to convert path {} to a string",
+                    target_path.display()
+                )
+            })?;
     Ok(Some(path))
 }
 
@@ -497,7 +588,7 @@ async fn read_file_bytes(request: &RequestInfo, path_str: &str) -> Result<Vec<u8
     } else {
         tokio::fs::read(path)
             .await
-            .with_context(|| format!("Unable to read file {path:?}"))
+            .with_context(|| format!("Unable to read file {}", path.display()))
     }
 }
 
@@ -523,6 +614,75 @@ async fn read_file_as_data_url<'a>(
     Ok(Some(Cow::Owned(data_url)))
 }
 
+/// Returns the files and directories in a directory of the web root as a JSON array of objects,
+/// with their name, URL path, size, MIME type and modification date, sorted by name.
+/// Hidden files, the `sqlpage` configuration directory, and files stored in the database are not listed.
+async fn list_files(
+    request: &RequestInfo,
+    path: Option<Cow<'_, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(path) = path else {
+        log::debug!("list_files: first argument is NULL, returning NULL");
+        return Ok(None);
+    };
+    let directory = path.trim_matches('/');
+    let entries = request
+        .app_state
+        .file_system
+        .list_directory(&request.app_state, std::path::Path::new(directory))
+        .await
+        .with_context(|| format!("list_files: unable to list the files in {path:?}"))?;
+    let rows: Vec<serde_json::Value> = entries
+        .into_iter()
+        .map(|entry| {
+            let url_path = if directory.is_empty() {
+                format!("/{}", entry.name)
+            } else {
+                format!("/{directory}/{}", entry.name)
+            };
+            let mime_type = (!entry.is_directory)
+                .then(|| mime_guess_from_filename(&entry.name).to_string());
+            serde_json::json!({
+                "name": entry.name,
+                "path": url_path,
+                "is_directory": entry.is_directory,
+                "size": entry.size,
+                "mime_type": mime_type,
+                "modified": entry.modified.map(|m| m.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+            })
+        })
+        .collect();
+    Ok(Some(serde_json::Value::Array(rows).to_string()))
+}
+
+/// Parses a CSV file, such as an uploaded file, and returns its rows as a json array
+async fn read_csv<'a>(
+    request: &'a RequestInfo,
+    file_path: Option<Cow<'a, str>>,
+    options: Option<Cow<'a, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(file_path) = file_path else {
+        log::debug!("read_csv: first argument is NULL, returning NULL");
+        return Ok(None);
+    };
+    let options: CsvReadOptions = match options {
+        Some(options) => serde_json::from_str(&options)
+            .with_context(|| format!("read_csv: invalid options: {options}"))?,
+        None => CsvReadOptions::default(),
+    };
+    let bytes = read_file_bytes(request, &file_path).await?;
+    let file_name = request
+        .uploaded_files
+        .values()
+        .find(|f| f.file.path() == OsStr::new(&*file_path))
+        .and_then(|f| f.file_name.as_deref())
+        .unwrap_or(&file_path);
+    let rows = csv_to_json(&bytes, &options)
+        .await
+        .with_context(|| format!("read_csv: unable to read {file_name}"))?;
+    Ok(Some(rows.to_string()))
+}
+
 /// Returns the contents of a file as a string
 async fn read_file_as_text<'a>(
     request: &'a RequestInfo,
@@ -587,7 +747,7 @@ async fn run_sql<'a>(
     } else {
         request.clone()
     };
-    let max_recursion_depth = app_state.config.max_recursion_depth;
+    let max_recursion_depth = app_state.config().max_recursion_depth;
     if tmp_req.clone_depth > max_recursion_depth {
         anyhow::bail!("Too many nested inclusions. run_sql can include a file that includes another file, but the depth is limited to {max_recursion_depth} levels. \n\
         Executing sqlpage.run_sql('{sql_file_path}') would exceed this limit. \n\
@@ -718,8 +878,16 @@ async fn variables<'a>(
 }
 
 /// Returns the version of the sqlpage that is running.
-async fn version() -> &'static str {
-    env!("CARGO_PKG_VERSION")
+/// With `'full'` as argument, returns a JSON object with the details of the build.
+async fn version(detail: Option<Cow<'_, str>>) -> anyhow::Result<Cow<'static, str>> {
+    use crate::build_info::{BuildInfo, VERSION};
+    match detail.as_deref() {
+        None => Ok(Cow::Borrowed(VERSION)),
+        Some("full") => Ok(Cow::Owned(BuildInfo::get().to_json())),
+        Some(other) => anyhow::bail!(
+            "Invalid argument for sqlpage.version(): {other:?}. Call it without arguments, or with 'full'."
+        ),
+    }
 }
 
 /// Returns the raw request body as a string.
@@ -736,7 +904,7 @@ async fn request_body(request: &RequestInfo) -> Option<String> {
 /// application/x-www-form-urlencoded or multipart/form-data (in this case, the body is accessible via the `post_variables` field).
 async fn request_body_base64(request: &RequestInfo) -> Option<String> {
     let raw_body = request.raw_body.as_ref()?;
-    let mut base64_string = String::with_capacity((raw_body.len() * 4 + 2) / 3);
+    let mut base64_string = String::with_capacity((raw_body.len() * 4).div_ceil(3));
     base64::Engine::encode_string(
         &base64::engine::general_purpose::STANDARD,
         raw_body,
@@ -752,3 +920,241 @@ async fn headers(request: &RequestInfo) -> String {
 async fn client_ip(request: &RequestInfo) -> Option<String> {
     Some(request.client_ip?.to_string())
 }
+
+/// Parses the `User-Agent` header of the request
+fn client_user_agent(request: &RequestInfo) -> Option<woothee::parser::WootheeResult<'_>> {
+    let Some(SingleOrVec::Single(user_agent)) = request.headers.get("user-agent") else {
+        return None;
+    };
+    woothee::parser::Parser::new().parse(user_agent)
+}
+
+/// Returns `true` if the client is a phone or a tablet, `false` if it is another kind of device,
+/// and `NULL` if the request has no `User-Agent` header.
+async fn client_is_mobile(request: &RequestInfo) -> Option<&'static str> {
+    let user_agent = client_user_agent(request)?;
+    let is_mobile = matches!(user_agent.category, "smartphone" | "mobilephone");
+    Some(if is_mobile { "true" } else { "false" })
+}
+
+/// Returns the name of the browser of the client, such as `Chrome` or `Firefox`.
+async fn client_browser(request: &RequestInfo) -> Option<&str> {
+    let browser = client_user_agent(request)?.name;
+    (browser != woothee::woothee::VALUE_UNKNOWN).then_some(browser)
+}
+
+/// Returns the name of the operating system of the client, such as `Windows 10`, `Android` or `iPhone`.
+async fn client_os(request: &RequestInfo) -> Option<&str> {
+    let os = client_user_agent(request)?.os;
+    (os != woothee::woothee::VALUE_UNKNOWN).then_some(os)
+}
+
+/// Returns the ISO code of the country of the client, such as `FR`, from the IP geolocation database.
+async fn client_country(request: &RequestInfo) -> anyhow::Result<Option<String>> {
+    let Some(ip) = request.client_ip else {
+        return Ok(None);
+    };
+    let location = geoip_lookup(request, ip)?;
+    Ok(location.and_then(|l| Some(l.country?.iso_code?.to_string())))
+}
+
+/// Returns a json object describing the location of an IP address, from the IP geolocation database.
+async fn geoip<'a>(
+    request: &'a RequestInfo,
+    ip: Option<Cow<'a, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(ip) = ip else {
+        return Ok(None);
+    };
+    let ip = std::net::IpAddr::from_str(ip.trim())
+        .with_context(|| format!("geoip: invalid IP address: {ip:?}"))?;
+    let Some(location) = geoip_lookup(request, ip)? else {
+        return Ok(None);
+    };
+    let country = location.country.as_ref();
+    let coordinates = location.location.as_ref();
+    let json = serde_json::json!({
+        "country": country.and_then(|c| c.iso_code),
+        "country_name": country.and_then(|c| english_name(c.names.as_ref())),
+        "continent": location.continent.as_ref().and_then(|c| c.code),
+        "city": location.city.as_ref().and_then(|c| english_name(c.names.as_ref())),
+        "latitude": coordinates.and_then(|l| l.latitude),
+        "longitude": coordinates.and_then(|l| l.longitude),
+        "time_zone": coordinates.and_then(|l| l.time_zone),
+    });
+    Ok(Some(json.to_string()))
+}
+
+fn english_name<'a>(names: Option<&BTreeMap<&'a str, &'a str>>) -> Option<&'a str> {
+    names?.get("en").copied()
+}
+
+/// Finds an IP address in the database of the `geoip_database_path` configuration option.
+/// Country databases are read as city databases without the city information.
+fn geoip_lookup(
+    request: &RequestInfo,
+    ip: std::net::IpAddr,
+) -> anyhow::Result<Option<maxminddb::geoip2::City<'_>>> {
+    let reader = request.app_state.geoip.as_ref().context(
+        "GeoIP lookups require a GeoIP database, configured with the geoip_database_path option",
+    )?;
+    match reader.lookup(ip.to_canonical()) {
+        Ok(location) => Ok(Some(location)),
+        Err(maxminddb::MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+        Err(e) => Err(anyhow!(e).context(format!("Unable to look up {ip} in the GeoIP database"))),
+    }
+}
+
+/// Tokens expire after one hour by default
+const DEFAULT_TOKEN_VALIDITY_SECONDS: i64 = 3600;
+
+fn token_signing_secret(request: &RequestInfo) -> anyhow::Result<String> {
+    request
+        .app_state
+        .config()
+        .token_signing_secret
+        .clone()
+        .ok_or_else(|| {
+            anyhow!("Signed tokens require the token_signing_secret configuration option to be set")
+        })
+}
+
+/// Returns a token containing the payload, signed with the `token_signing_secret`, and valid for the given number of seconds.
+async fn sign_token(
+    request: &RequestInfo,
+    payload: Option<Cow<'_, str>>,
+    expires_in_seconds: Option<Cow<'_, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(payload) = payload else {
+        log::debug!("sign_token: payload is NULL, returning NULL");
+        return Ok(None);
+    };
+    let expires_at = expiration_timestamp("sign_token", expires_in_seconds)?;
+    let secret = token_signing_secret(request)?;
+    Ok(Some(signed_token::sign(&secret, &payload, expires_at)))
+}
+
+/// The unix timestamp after which a token or URL created now is not valid anymore
+fn expiration_timestamp(
+    function_name: &str,
+    expires_in_seconds: Option<Cow<'_, str>>,
+) -> anyhow::Result<i64> {
+    let validity = match expires_in_seconds {
+        Some(s) => s
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|&seconds| seconds > 0)
+            .with_context(|| {
+                format!("{function_name}: expires_in_seconds must be a positive number, not {s:?}")
+            })?,
+        None => DEFAULT_TOKEN_VALIDITY_SECONDS,
+    };
+    Ok(chrono::Utc::now().timestamp().saturating_add(validity))
+}
+
+/// Returns the given URL, with a signature that makes it valid for the given number of seconds.
+/// Relative URLs are resolved from the URL of the current page.
+async fn sign_url(
+    request: &RequestInfo,
+    url: Option<Cow<'_, str>>,
+    expires_in_seconds: Option<Cow<'_, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(url) = url else {
+        log::debug!("sign_url: url is NULL, returning NULL");
+        return Ok(None);
+    };
+    let expires_at = expiration_timestamp("sign_url", expires_in_seconds)?;
+    let secret = token_signing_secret(request)?;
+    let current_page = url::Url::parse("http://localhost")?.join(&request.path)?;
+    let target = current_page
+        .join(&url)
+        .with_context(|| format!("sign_url: invalid URL {url:?}"))?;
+    let path_and_query = match target.query() {
+        Some(query) => format!("{}?{query}", target.path()),
+        None => target.path().to_string(),
+    };
+    let mut signed = signed_url::sign(&secret, &path_and_query, expires_at);
+    if url::Url::parse(&url).is_ok() {
+        // Absolute URLs keep their protocol and host name
+        signed.insert_str(0, &target[..url::Position::BeforePath]);
+    }
+    if let Some(fragment) = target.fragment() {
+        signed.push('#');
+        signed.push_str(fragment);
+    }
+    Ok(Some(signed))
+}
+
+/// Returns `true` when the current request is the submission of a form protected with `bot_protection`.
+/// Submissions that fail the protection are rejected before the page is executed.
+async fn bot_protection_passed(request: &RequestInfo) -> &'static str {
+    if request.bot_protection_passed {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Returns `true` when the current request is the submission of an action button, with a valid token, from the same site.
+/// Submissions of action buttons with an invalid token, or from another site, are rejected before the page is executed.
+async fn csrf_verified(request: &RequestInfo) -> &'static str {
+    if request.csrf_verified {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Returns `true` when the current page was opened from a link created by `sign_url`, that has not expired.
+/// Modified and expired signed links are rejected before the page is executed.
+async fn url_is_signed(request: &RequestInfo) -> &'static str {
+    if request.signed_url {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Returns the payload of a token created by `sign_token`, or NULL if it is invalid or expired.
+async fn verify_token(
+    request: &RequestInfo,
+    token: Option<Cow<'_, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(token) = token else {
+        log::debug!("verify_token: token is NULL, returning NULL");
+        return Ok(None);
+    };
+    let secret = token_signing_secret(request)?;
+    let payload = signed_token::verify(&secret, token.trim(), chrono::Utc::now().timestamp());
+    if payload.is_none() {
+        log::info!("sqlpage.verify_token: rejecting an invalid or expired token");
+    }
+    Ok(payload)
+}
+
+/// Returns the IANA name of the time zone of the viewer, such as `Europe/Paris`.
+async fn timezone(request: &RequestInfo) -> &'static str {
+    request.timezone().name()
+}
+
+/// Converts a timestamp to a time zone, which is the time zone of the viewer by default.
+/// Timestamps without a time zone are considered to be in UTC.
+async fn to_timezone<'a>(
+    request: &'a RequestInfo,
+    timestamp: Option<Cow<'a, str>>,
+    timezone: Option<Cow<'a, str>>,
+) -> anyhow::Result<Option<String>> {
+    let Some(timestamp) = timestamp else {
+        return Ok(None);
+    };
+    let timezone = match timezone {
+        Some(name) => name
+            .parse()
+            .map_err(|_| anyhow!("to_timezone: unknown time zone {name:?}"))?,
+        None => request.timezone(),
+    };
+    crate::webserver::timezone::to_timezone(&timestamp, timezone, None)
+        .map(Some)
+        .with_context(|| format!("to_timezone: {timestamp:?} is not a valid timestamp"))
+}
@@ -2,6 +2,7 @@ mod function_definition_macro;
 mod function_traits;
 pub(super) mod functions;
 mod http_fetch_request;
+mod signed_token;
 mod url_parameter_deserializer;
 
 use sqlparser::ast::FunctionArg;
@@ -0,0 +1,66 @@
+//! Signed, expiring tokens, created by `sqlpage.sign_token` and checked by `sqlpage.verify_token`.
+//!
+//! A token is `<payload>.<expiration>.<signature>`, where the payload is encoded in base64,
+//! the expiration is a unix timestamp in seconds, and the signature is an HMAC-SHA256 of the first two parts
+//! with the `token_signing_secret` of the configuration.
+//! The payload is not encrypted: anyone who has the token can read it, but nobody can modify it without the secret.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+fn mac(secret: &str, signed_part: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(signed_part.as_bytes());
+    mac
+}
+
+pub(super) fn sign(secret: &str, payload: &str, expires_at: i64) -> String {
+    let signed_part = format!("{}.{expires_at}", URL_SAFE_NO_PAD.encode(payload));
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, &signed_part).finalize().into_bytes());
+    format!("{signed_part}.{signature}")
+}
+
+/// Returns the payload of the token, or `None` when it was not signed with the secret or has expired.
+pub(super) fn verify(secret: &str, token: &str, now: i64) -> Option<String> {
+    let (signed_part, signature) = token.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    mac(secret, signed_part).verify_slice(&signature).ok()?;
+    let (payload, expires_at) = signed_part.split_once('.')?;
+    if expires_at.parse::<i64>().ok()? <= now {
+        log::debug!("The token expired at {expires_at}");
+        return None;
+    }
+    String::from_utf8(URL_SAFE_NO_PAD.decode(payload).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "a secret that is long enough for the tests";
+
+    #[test]
+    fn test_roundtrip() {
+        let token = sign(SECRET, r#"{"user":"jo@example.com"}"#, 1000);
+        assert_eq!(
+            verify(SECRET, &token, 999).as_deref(),
+            Some(r#"{"user":"jo@example.com"}"#)
+        );
+    }
+
+    #[test]
+    fn test_rejected_tokens() {
+        let token = sign(SECRET, "42", 1000);
+        assert_eq!(verify(SECRET, &token, 1000), None, "expired");
+        assert_eq!(verify("another secret", &token, 0), None, "wrong secret");
+        let (_, rest) = token.split_once('.').unwrap();
+        let forged = format!("{}.{rest}", URL_SAFE_NO_PAD.encode("43"));
+        assert_eq!(verify(SECRET, &forged, 0), None, "modified payload");
+        let extended = token.replace(".1000.", ".9999.");
+        assert_eq!(verify(SECRET, &extended, 1500), None, "modified expiration");
+        assert_eq!(verify(SECRET, "not a token", 0), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Executing the statements of each request with the database role of the user who made it,
+//! so that the row-level security policies of the database decide what each user can see and change.
+//!
+//! The role is returned by the `database_role_query` of the configuration, which is executed
+//! with the login role of the connection, at the beginning of each request, and can use request variables
+//! such as session cookies. The role is reset when the connection returns to the pool.
+
+use super::execute_queries::{stream_query_results_with_conn, take_connection, DbConn};
+use super::DbItem;
+use crate::webserver::http_request_info::RequestInfo;
+use anyhow::{anyhow, Context};
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Switches the connection of the request to the role returned by `database_role_query`.
+/// When the query returns no row or a null role, the request keeps the login role of the connection.
+pub async fn set_user_role(
+    request: &mut RequestInfo,
+    db_connection: &mut DbConn,
+) -> anyhow::Result<()> {
+    let app_state = Arc::clone(&request.app_state);
+    let Some(role_query) = &app_state.database_role_query else {
+        return Ok(());
+    };
+    // The query plans of the role query are not displayed on the page
+    let debug_queries = std::mem::replace(&mut request.debug_queries, false);
+    let role = {
+        let mut results = std::pin::pin!(stream_query_results_with_conn(
+            role_query,
+            request,
+            db_connection
+        ));
+        let mut role = None;
+        while let Some(item) = results.next().await {
+            match item {
+                DbItem::Row(row) if role.is_none() => role = Some(role_from_row(&row)?),
+                DbItem::Error(e) => return Err(e.context("database_role_query failed")),
+                _ => {}
+            }
+        }
+        role.flatten()
+    };
+    request.debug_queries = debug_queries;
+    let Some(role) = role else {
+        log::debug!("database_role_query returned no role, using the login role of the connection");
+        return Ok(());
+    };
+    log::debug!("Executing the request with the database role {role:?}");
+    let connection = take_connection(&app_state.db, db_connection).await?;
+    sqlx::query("SELECT set_config('role', $1, false)")
+        .bind(&role)
+        .execute(&mut **connection)
+        .await
+        .with_context(|| format!("Unable to switch to the database role {role:?}"))?;
+    Ok(())
+}
+
+fn role_from_row(row: &Value) -> anyhow::Result<Option<String>> {
+    match row.get("role") {
+        Some(Value::String(role)) => Ok(Some(role.clone())),
+        Some(Value::Null) => Ok(None),
+        Some(other) => Err(anyhow!(
+            "database_role_query must return the name of a role, not {other}"
+        )),
+        None => Err(anyhow!(
+            "database_role_query must return a column named \"role\", but it returned {row}"
+        )),
+    }
+}
@@ -0,0 +1,118 @@
+//! In development mode, the files of the site are watched for changes,
+//! so that modified pages and components are dropped from the cache as soon as they are saved,
+//! instead of checking the modification time of every file on every request.
+
+use crate::{AppState, TEMPLATES_DIR};
+use actix_web::web;
+use anyhow::Context;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+/// Starts watching the web root and the templates directory.
+/// The files are watched for as long as the returned watcher is alive.
+pub fn watch_files(app_state: &web::Data<AppState>) -> anyhow::Result<RecommendedWatcher> {
+    let config = &app_state.config();
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<PathBuf>>();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(Event {
+                kind: EventKind::Access(_),
+                ..
+            }) => {}
+            Ok(event) => {
+                // The receiver is only dropped when the server stops
+                let _ = sender.send(event.paths);
+            }
+            Err(e) => log::warn!("Error while watching the files of the site: {e}"),
+        })?;
+    let web_root = config
+        .web_root
+        .canonicalize()
+        .with_context(|| format!("Unable to open the web root {}", config.web_root.display()))?;
+    watcher
+        .watch(&web_root, RecursiveMode::Recursive)
+        .with_context(|| format!("Unable to watch {}", web_root.display()))?;
+    let templates_dir = config
+        .configuration_directory
+        .join("templates")
+        .canonicalize()
+        .ok();
+    if let Some(dir) = templates_dir.as_ref().filter(|d| !d.starts_with(&web_root)) {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| format!("Unable to watch {}", dir.display()))?;
+    }
+    log::info!("Watching {web_root:?} for changes");
+
+    let state = web::Data::clone(app_state);
+    actix_web::rt::spawn(async move {
+        while let Some(paths) = receiver.recv().await {
+            for path in paths {
+                invalidate(&state, &web_root, templates_dir.as_deref(), &path).await;
+            }
+        }
+    });
+    app_state.sql_file_cache.set_watched();
+    app_state.all_templates.split_templates.set_watched();
+    Ok(watcher)
+}
+
+async fn invalidate(
+    app_state: &AppState,
+    web_root: &Path,
+    templates_dir: Option<&Path>,
+    path: &Path,
+) {
+    log::trace!("File system change detected on {path:?}");
+    if let Ok(relative) = path.strip_prefix(web_root) {
+        app_state.sql_file_cache.invalidate(relative).await;
+    }
+    // Components are cached under TEMPLATES_DIR, whatever the configuration directory
+    if let Some(template) = templates_dir.and_then(|dir| path.strip_prefix(dir).ok()) {
+        let cache_key = Path::new(TEMPLATES_DIR).join(template);
+        app_state
+            .all_templates
+            .split_templates
+            .invalidate(&cache_key)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+    use crate::webserver::routing::FileStore;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_modified_files_are_removed_from_the_cache() {
+        let web_root =
+            std::env::temp_dir().join(format!("sqlpage-watch-{:016x}", rand::random::<u64>()));
+        std::fs::create_dir_all(&web_root).unwrap();
+        let page = web_root.join("page.sql");
+        std::fs::write(&page, "select 'text' as component, 'before' as contents;").unwrap();
+        let mut config = test_config();
+        config.web_root.clone_from(&web_root);
+        let state = web::Data::new(AppState::init(&config).await.unwrap());
+        let path = Path::new("page.sql");
+        state.sql_file_cache.get(&state, path).await.unwrap();
+        assert!(state.sql_file_cache.contains(path).await.unwrap());
+
+        let _watcher = watch_files(&state).unwrap();
+        std::fs::write(&page, "select 'text' as component, 'after' as contents;").unwrap();
+        let mut invalidated = false;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if !state.sql_file_cache.contains(path).await.unwrap() {
+                invalidated = true;
+                break;
+            }
+        }
+        std::fs::remove_dir_all(&web_root).unwrap();
+        assert!(
+            invalidated,
+            "the modified file was not removed from the cache"
+        );
+    }
+}
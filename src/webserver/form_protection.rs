@@ -0,0 +1,260 @@
+//! Protection of forms against spam bots, enabled with the `bot_protection` property of the form component.
+//!
+//! Protected forms contain a hidden honeypot field, that humans never see and leave empty,
+//! and a token with the time at which the form was displayed, signed with a key derived from the `token_signing_secret`,
+//! or generated when the server starts when there is no secret.
+//! Submissions that fill the honeypot, that arrive sooner after the form was displayed than a human could fill it,
+//! whose token was tampered with, or whose form was displayed more than a day ago, are rejected before the page is executed.
+//! Submissions without any of these fields are accepted, since they may come from unprotected forms:
+//! pages that must only accept protected submissions check `sqlpage.bot_protection_passed()`.
+
+use super::ErrorWithStatus;
+use actix_web::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+/// Name of the hidden field that contains the signed token
+pub const TOKEN_FIELD: &str = "_sqlpage_form_token";
+/// Name of the field that bots fill, and humans do not see
+pub const HONEYPOT_FIELD: &str = "_sqlpage_website";
+/// Minimum time between the display and the submission of a form, when `bot_protection` is `true`
+pub const DEFAULT_MIN_SECONDS: u64 = 2;
+/// Maximum time between the display and the submission of a form, after which its token cannot be replayed
+pub const MAX_TOKEN_AGE_SECONDS: i64 = 24 * 60 * 60;
+
+static SIGNING_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// Derives the signing key from the `token_signing_secret`, so that the instances of a site that share a secret
+/// accept the forms displayed by each other, behind a load balancer without sticky sessions.
+/// The key is set when the server starts, and does not change when the configuration is reloaded.
+pub fn init_signing_key(token_signing_secret: Option<&str>) {
+    let Some(secret) = token_signing_secret else {
+        return;
+    };
+    if SIGNING_KEY.set(derive_key(secret)).is_err() {
+        log::debug!("The form protection key was already set");
+    }
+}
+
+fn derive_key(secret: &str) -> [u8; 32] {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(b"sqlpage form protection");
+    mac.finalize().into_bytes().into()
+}
+
+fn signing_key() -> &'static [u8; 32] {
+    SIGNING_KEY.get_or_init(rand::random)
+}
+
+fn mac(key: &[u8], payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Key of the signatures made for another purpose, derived from the form protection key,
+/// so that a signature made for one purpose is never accepted for another one
+fn purpose_key(purpose: &str) -> [u8; 32] {
+    mac(signing_key(), purpose).finalize().into_bytes().into()
+}
+
+fn encoded_signature(key: &[u8], payload: &str) -> String {
+    URL_SAFE_NO_PAD.encode(mac(key, payload).finalize().into_bytes())
+}
+
+fn has_signature(key: &[u8], payload: &str, signature: &str) -> bool {
+    URL_SAFE_NO_PAD
+        .decode(signature)
+        .is_ok_and(|signature| mac(key, payload).verify_slice(&signature).is_ok())
+}
+
+/// Signs a value with the form protection key. Also used for action buttons and forms in several steps.
+pub(crate) fn sign(payload: &str) -> String {
+    encoded_signature(signing_key(), payload)
+}
+
+pub(crate) fn is_signed(payload: &str, signature: &str) -> bool {
+    has_signature(signing_key(), payload, signature)
+}
+
+/// Signs a value with a key dedicated to the given purpose, such as the subscriptions of live components
+pub(crate) fn sign_for(purpose: &str, payload: &str) -> String {
+    encoded_signature(&purpose_key(purpose), payload)
+}
+
+pub(crate) fn is_signed_for(purpose: &str, payload: &str, signature: &str) -> bool {
+    has_signature(&purpose_key(purpose), payload, signature)
+}
+
+/// Creates the token of a form displayed now, that cannot be submitted before `min_seconds` have elapsed.
+#[must_use]
+pub fn new_token(min_seconds: u64) -> String {
+    let payload = format!("{}.{min_seconds}", chrono::Utc::now().timestamp_millis());
+    let signature = sign(&payload);
+    format!("{payload}.{signature}")
+}
+
+/// Checks the submission of a protected form, and removes the protection fields from the submitted values.
+/// Submissions that do not contain a token are from unprotected forms, and are accepted.
+/// Returns whether the submission contained a valid token, and an empty honeypot.
+pub fn check_submission(post_variables: &mut Vec<(String, String)>) -> anyhow::Result<bool> {
+    let mut token = None;
+    let mut honeypot = None;
+    post_variables.retain(|(name, value)| match name.as_str() {
+        TOKEN_FIELD => {
+            token = Some(value.clone());
+            false
+        }
+        HONEYPOT_FIELD => {
+            honeypot = Some(value.clone());
+            false
+        }
+        _ => true,
+    });
+    if token.is_none() && honeypot.is_none() {
+        return Ok(false);
+    }
+    let rejection = if honeypot.is_some_and(|v| !v.is_empty()) {
+        Some("the honeypot field was filled")
+    } else {
+        token.map_or(Some("the token is missing"), |token| {
+            token_rejection(&token, chrono::Utc::now().timestamp_millis())
+        })
+    };
+    if let Some(reason) = rejection {
+        log::info!("Rejecting a form submission that looks automated: {reason}");
+        return Err(anyhow::Error::new(ErrorWithStatus {
+            status: StatusCode::BAD_REQUEST,
+        })
+        .context(format!(
+            "This form submission was rejected, because {reason}. Please reload the page and submit the form again."
+        )));
+    }
+    Ok(true)
+}
+
+fn token_rejection(token: &str, now_millis: i64) -> Option<&'static str> {
+    let Some((payload, signature)) = token.rsplit_once('.') else {
+        return Some("the token is invalid");
+    };
+    if !is_signed(payload, signature) {
+        return Some("the token is invalid or was issued before the server restarted");
+    }
+    let Some((issued_at, min_seconds)) = payload
+        .split_once('.')
+        .and_then(|(t, s)| Some((t.parse::<i64>().ok()?, s.parse::<i64>().ok()?)))
+    else {
+        return Some("the token is invalid");
+    };
+    if now_millis - issued_at < min_seconds.saturating_mul(1000) {
+        return Some("the form was submitted too quickly");
+    }
+    if now_millis - issued_at > MAX_TOKEN_AGE_SECONDS * 1000 {
+        return Some("the form was displayed too long ago");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unprotected_form() {
+        let mut post = vars(&[("email", "a@b.c")]);
+        assert!(!check_submission(&mut post).unwrap());
+        assert_eq!(post, vars(&[("email", "a@b.c")]));
+    }
+
+    #[test]
+    fn test_valid_submission() {
+        let token = new_token(0);
+        let mut post = vars(&[
+            ("email", "a@b.c"),
+            (TOKEN_FIELD, &token),
+            (HONEYPOT_FIELD, ""),
+        ]);
+        assert!(check_submission(&mut post).unwrap());
+        assert_eq!(post, vars(&[("email", "a@b.c")]));
+    }
+
+    #[test]
+    fn test_rejected_submissions() {
+        let token = new_token(0);
+        let mut post = vars(&[(TOKEN_FIELD, &token), (HONEYPOT_FIELD, "http://spam")]);
+        let err = check_submission(&mut post).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorWithStatus>(),
+            Some(&ErrorWithStatus {
+                status: StatusCode::BAD_REQUEST
+            })
+        );
+        assert!(err.to_string().contains("honeypot"), "{err}");
+
+        let mut post = vars(&[(TOKEN_FIELD, &new_token(60)), (HONEYPOT_FIELD, "")]);
+        let err = check_submission(&mut post).unwrap_err();
+        assert!(err.to_string().contains("too quickly"), "{err}");
+
+        let mut post = vars(&[(HONEYPOT_FIELD, "")]);
+        assert!(check_submission(&mut post).is_err(), "missing token");
+    }
+
+    #[test]
+    fn test_token_rejection() {
+        let token = new_token(2);
+        let (payload, _) = token.rsplit_once('.').unwrap();
+        let issued_at: i64 = payload.split_once('.').unwrap().0.parse().unwrap();
+        assert_eq!(token_rejection(&token, issued_at + 2000), None);
+        assert_eq!(
+            token_rejection(&token, issued_at + 1999),
+            Some("the form was submitted too quickly")
+        );
+        let max_age = MAX_TOKEN_AGE_SECONDS * 1000;
+        assert_eq!(token_rejection(&token, issued_at + max_age), None);
+        assert_eq!(
+            token_rejection(&token, issued_at + max_age + 1),
+            Some("the form was displayed too long ago")
+        );
+        let forged = format!(
+            "{}.0.{}",
+            issued_at - 10_000,
+            token.rsplit_once('.').unwrap().1
+        );
+        assert!(token_rejection(&forged, issued_at).is_some());
+        assert!(token_rejection("garbage", issued_at).is_some());
+    }
+
+    #[test]
+    fn test_purpose_keys() {
+        let signature = sign_for("live", "orders");
+        assert!(is_signed_for("live", "orders", &signature));
+        assert!(!is_signed_for("steps", "orders", &signature));
+        assert!(!is_signed("orders", &signature));
+        assert!(!is_signed_for("live", "orders", &sign("orders")));
+    }
+
+    #[test]
+    fn test_key_derived_from_secret() {
+        let secret = "a secret that is long enough for the tests";
+        assert_eq!(
+            derive_key(secret),
+            derive_key(secret),
+            "same key on every instance"
+        );
+        assert_ne!(
+            derive_key(secret),
+            derive_key("another secret of the same length!!")
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Forms split in several steps, on several pages, with the `previous_values` property of the form component.
+//!
+//! The form of each step contains the values submitted in the previous steps, in a hidden field signed
+//! when the form is rendered. When it is submitted, the values are verified and added to the POST variables,
+//! so that the page of the last step receives the values of all the steps, as if they had been submitted together.
+//! Visitors cannot modify the values of the previous steps, which the pages of these steps may already have checked.
+
+use super::{form_protection, ErrorWithStatus};
+use actix_web::http::StatusCode;
+use serde_json::Value as JsonValue;
+
+/// Name of the hidden field that contains the signed values of the previous steps
+pub const PREVIOUS_VALUES_FIELD: &str = "_sqlpage_previous_values";
+
+fn payload(values: &str) -> String {
+    format!("steps.{values}")
+}
+
+/// Signs the values of the previous steps, a JSON object such as the result of `sqlpage.variables('post')`.
+/// Returns the value of the hidden field that carries them to the next step.
+pub fn sign_values(values: &JsonValue) -> anyhow::Result<String> {
+    let values = match values {
+        JsonValue::String(s) => serde_json::from_str(s)?,
+        other => other.clone(),
+    };
+    anyhow::ensure!(
+        values.is_object(),
+        "previous_values must be a JSON object, such as sqlpage.variables('post'), not {values}"
+    );
+    let values = values.to_string();
+    let signature = form_protection::sign(&payload(&values));
+    Ok(format!("{signature}.{values}"))
+}
+
+/// Adds the values of the previous steps to the submitted values, and removes the hidden field.
+/// Values submitted in the current step take precedence over the values of the previous steps with the same name.
+pub fn restore_previous_values(post_variables: &mut Vec<(String, String)>) -> anyhow::Result<()> {
+    let Some(index) = post_variables
+        .iter()
+        .position(|(name, _)| name == PREVIOUS_VALUES_FIELD)
+    else {
+        return Ok(());
+    };
+    let (_, signed) = post_variables.remove(index);
+    let values = signed
+        .split_once('.')
+        .filter(|(signature, values)| form_protection::is_signed(&payload(values), signature))
+        .and_then(|(_, values)| serde_json::from_str::<serde_json::Map<_, _>>(values).ok());
+    let Some(values) = values else {
+        log::info!("Rejecting a form submission with modified values of previous steps");
+        return Err(anyhow::Error::new(ErrorWithStatus {
+            status: StatusCode::BAD_REQUEST,
+        })
+        .context(
+            "The values of the previous steps of this form are invalid, or were signed before the server restarted. \
+            Please fill the form again from its first step.",
+        ));
+    };
+    for (name, value) in values {
+        if post_variables
+            .iter()
+            .any(|(submitted, _)| *submitted == name)
+        {
+            continue;
+        }
+        match value {
+            JsonValue::Array(values) => {
+                post_variables.extend(values.into_iter().map(|v| (name.clone(), to_text(v))));
+            }
+            value => post_variables.push((name, to_text(value))),
+        }
+    }
+    Ok(())
+}
+
+fn to_text(value: JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_restore_previous_values() {
+        let signed = sign_values(
+            &serde_json::json!({"customer": "12", "product": "old", "tags[]": ["a", "b"]}),
+        )
+        .unwrap();
+        let mut post = vars(&[("product", "new"), (PREVIOUS_VALUES_FIELD, &signed)]);
+        restore_previous_values(&mut post).unwrap();
+        post.sort();
+        assert_eq!(
+            post,
+            vars(&[
+                ("customer", "12"),
+                ("product", "new"),
+                ("tags[]", "a"),
+                ("tags[]", "b")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_modified_previous_values() {
+        let signed = sign_values(&serde_json::json!(r#"{"customer": "12"}"#)).unwrap();
+        let modified = signed.replace("12", "13");
+        let mut post = vars(&[(PREVIOUS_VALUES_FIELD, &modified)]);
+        let err = restore_previous_values(&mut post).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorWithStatus>(),
+            Some(&ErrorWithStatus {
+                status: StatusCode::BAD_REQUEST
+            })
+        );
+        assert!(sign_values(&serde_json::json!([1, 2])).is_err());
+    }
+}
@@ -3,16 +3,30 @@
 //! request contexts and response headers.
 
 use crate::render::{AnyRenderBodyContext, HeaderContext, PageContext};
-use crate::webserver::content_security_policy::ContentSecurityPolicy;
-use crate::webserver::database::execute_queries::stop_at_first_error;
-use crate::webserver::database::{execute_queries::stream_query_results_with_conn, DbItem};
-use crate::webserver::http_request_info::extract_request_info;
+use crate::webserver::content_security_policy::{ContentSecurityPolicy, NONCE_PLACEHOLDER};
+use crate::webserver::csrf::{self, BrowserSecret};
+use crate::webserver::database::{
+    authorize::run_authorization_files,
+    execute_queries::{
+        begin_request_transaction, cancel_and_close, commit_request_transaction,
+        rollback_request_transaction, stream_query_results_with_conn, DbConn,
+    },
+    user_role::set_user_role,
+    DbItem,
+};
+use crate::webserver::http_request_info::{client_ip, extract_request_info, RequestInfo};
+use crate::webserver::idempotency::{self, Submission};
+use crate::webserver::pdf::PdfRequest;
 use crate::webserver::ErrorWithStatus;
+use crate::webserver::{admin, audit_log, batch, component_gallery, live, openapi, pdf, proxy};
 use crate::{app_config, AppConfig, AppState, ParsedSqlFile};
 use actix_web::dev::{fn_service, ServiceFactory, ServiceRequest};
 use actix_web::error::{ErrorBadRequest, ErrorInternalServerError};
-use actix_web::http::header::{ContentType, Header, HttpDate, IfModifiedSince, LastModified};
-use actix_web::http::{header, StatusCode};
+use actix_web::http::header::{
+    ContentRange, ContentRangeSpec, ContentType, ETag, EntityTag, Header, HttpDate,
+    IfModifiedSince, IfNoneMatch, IfRange, LastModified, Range,
+};
+use actix_web::http::{header, KeepAlive, StatusCode};
 use actix_web::web::PayloadConfig;
 use actix_web::{
     dev::ServiceResponse, middleware, middleware::Logger, web, App, HttpResponse, HttpServer,
@@ -20,37 +34,89 @@ use actix_web::{
 use actix_web::{HttpResponseBuilder, ResponseError};
 
 use super::https::make_auto_rustls_config;
-use super::response_writer::ResponseWriter;
+use super::response_writer::{PendingBytesLimit, ResponseWriter};
 use super::static_content;
 use crate::webserver::routing::RoutingAction::{
-    CustomNotFound, Execute, NotFound, Redirect, Serve,
+    CustomNotFound, Execute, NotFound, Proxy, Redirect, Serve,
 };
-use crate::webserver::routing::{calculate_route, AppFileStore};
+use crate::webserver::routing::{calculate_route, find_authorization_files, AppFileStore};
 use actix_web::body::MessageBody;
 use anyhow::{bail, Context};
 use chrono::{DateTime, Utc};
+use futures_util::future::{self, Either};
 use futures_util::stream::Stream;
 use futures_util::StreamExt;
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::mem;
-use std::path::PathBuf;
-use std::pin::Pin;
+use std::path::{Path, PathBuf};
+use std::pin::{pin, Pin};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 pub struct RequestContext {
     pub is_embedded: bool,
     pub content_security_policy: ContentSecurityPolicy,
+    /// Shell to use instead of the default `shell` component, configured with `directory_shells`
+    pub directory_shell: Option<String>,
+    /// Time zone of the viewer, available in templates as `@timezone`
+    pub timezone: chrono_tz::Tz,
+    /// Parameters of the URL of the page, available in templates as `@url_parameters`
+    pub url_parameters: serde_json::Value,
+    /// Status of the response, unless the page sets another one
+    pub status: StatusCode,
+    /// Secret of the browser, to which the tokens of action buttons are bound
+    pub csrf_secret: Arc<BrowserSecret>,
+    /// The page is served over HTTPS, so its cookies are only sent over HTTPS
+    pub is_https: bool,
 }
 
-async fn stream_response(stream: impl Stream<Item = DbItem>, mut renderer: AnyRenderBodyContext) {
-    let mut stream = Box::pin(stream);
+/// Number of database items that can be fetched in advance, while the previous ones are rendered and sent to the client
+const PREFETCHED_DATABASE_ITEMS: usize = 16;
+
+/// Executes the queries and renders their results concurrently:
+/// the next rows are fetched from the database while the rendered ones wait to be sent to a slow client.
+/// Rendering stays in control of the pace: when the client stops receiving data,
+/// at most [`PREFETCHED_DATABASE_ITEMS`] items are fetched before the execution pauses,
+/// and when the rendering stops early, the remaining statements are not executed.
+/// Returns whether the whole page was rendered.
+async fn stream_response(
+    stream: impl Stream<Item = DbItem>,
+    renderer: AnyRenderBodyContext,
+) -> bool {
+    let (item_sender, item_receiver) = mpsc::channel(PREFETCHED_DATABASE_ITEMS);
+    let fetch_items = pin!(async move {
+        let mut stream = pin!(stream);
+        while let Some(item) = stream.next().await {
+            if item_sender.send(item).await.is_err() {
+                log::debug!("Rendering stopped, not executing the remaining statements");
+                break;
+            }
+        }
+    });
+    let render = pin!(render_items(
+        tokio_stream::wrappers::ReceiverStream::new(item_receiver),
+        renderer
+    ));
+    match future::select(fetch_items, render).await {
+        Either::Left(((), render)) => render.await,
+        Either::Right((completed, _)) => completed,
+    }
+}
+
+async fn render_items(
+    stream: impl Stream<Item = DbItem>,
+    mut renderer: AnyRenderBodyContext,
+) -> bool {
+    let mut stream = pin!(stream);
+    let mut render_failed = false;
 
     if let Err(e) = &renderer.flush().await {
         log::error!("Unable to flush initial data to client: {e}");
-        return;
+        return false;
     }
 
     while let Some(item) = stream.next().await {
@@ -61,6 +127,7 @@ async fn stream_response(stream: impl Stream<Item = DbItem>, mut renderer: AnyRe
             DbItem::Error(e) => renderer.handle_error(&e).await,
         };
         if let Err(e) = render_result {
+            render_failed = true;
             if let Err(nested_err) = renderer.handle_error(&e).await {
                 renderer
                     .close()
@@ -72,7 +139,7 @@ async fn stream_response(stream: impl Stream<Item = DbItem>, mut renderer: AnyRe
                     \nRoot error: {e}\n
                     \nNested error: {nested_err}"
                 );
-                return;
+                return false;
             }
         }
         if let Err(e) = &renderer.flush().await {
@@ -81,24 +148,27 @@ async fn stream_response(stream: impl Stream<Item = DbItem>, mut renderer: AnyRe
                 The user has probably closed the connection before we finished rendering the page: {e:#}"
             );
             // If we cannot write to the client anymore, there is nothing we can do, so we just stop rendering
-            return;
+            return false;
         }
     }
     if let Err(e) = &renderer.close().await.async_flush().await {
         log::error!("Unable to flush data to client after rendering the page end: {e}");
-        return;
+        return false;
     }
     log::debug!("Successfully finished rendering the page");
+    !render_failed
 }
 
 async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
     app_state: Arc<AppState>,
     database_entries: S,
     request_context: RequestContext,
+    client_disconnected: &CancellationToken,
 ) -> anyhow::Result<ResponseWithWriter<S>> {
-    let chan_size = app_state.config.max_pending_rows;
+    let chan_size = app_state.config().max_pending_rows;
     let (sender, receiver) = mpsc::channel(chan_size);
-    let writer = ResponseWriter::new(sender);
+    let pending_bytes = PendingBytesLimit::new(app_state.config().max_pending_response_bytes);
+    let writer = ResponseWriter::new(sender, pending_bytes.clone());
     let mut head_context = HeaderContext::new(app_state, request_context, writer);
     let mut stream = Box::pin(database_entries);
     while let Some(item) = stream.next().await {
@@ -127,7 +197,13 @@ async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
                 renderer,
             } => {
                 let body_stream = tokio_stream::wrappers::ReceiverStream::new(receiver);
-                let result_stream = body_stream.map(Ok::<_, actix_web::Error>);
+                // The body is dropped before its end when the client disconnects
+                let disconnect_guard = client_disconnected.clone().drop_guard();
+                let result_stream = body_stream.map(move |bytes| {
+                    let _ = &disconnect_guard;
+                    pending_bytes.release(bytes.len());
+                    Ok::<_, actix_web::Error>(bytes)
+                });
                 let http_response = http_response.streaming(result_stream);
                 return Ok(ResponseWithWriter::RenderStream {
                     http_response,
@@ -145,6 +221,7 @@ async fn build_response_header_and_stream<S: Stream<Item = DbItem>>(
     Ok(ResponseWithWriter::FinishedResponse { http_response })
 }
 
+#[allow(clippy::large_enum_variant)]
 enum ResponseWithWriter<S> {
     RenderStream {
         http_response: HttpResponse,
@@ -156,9 +233,42 @@ enum ResponseWithWriter<S> {
     },
 }
 
+/// Executes the statements of the page, and commits the transaction of the request after the last one
+/// when none failed. The commit happens before the end of the page is sent, and its failure is displayed in the page.
+fn stream_and_commit<'a>(
+    sql_file: &'a ParsedSqlFile,
+    request: &'a mut RequestInfo,
+    conn: &'a mut DbConn,
+    failed: &'a Cell<bool>,
+    committed: &'a Cell<bool>,
+) -> impl Stream<Item = DbItem> + 'a {
+    let in_transaction = request.in_transaction;
+    let items = async_stream::stream! {
+        {
+            let mut statements = pin!(stream_query_results_with_conn(sql_file, request, conn));
+            while let Some(item) = statements.next().await {
+                yield item;
+            }
+        }
+        if in_transaction && !failed.get() {
+            match commit_request_transaction(conn).await {
+                Ok(()) => committed.set(true),
+                Err(e) => yield DbItem::Error(e),
+            }
+        }
+    };
+    items.inspect(|item| {
+        if let DbItem::Error(err) = item {
+            log::error!("{err:?}");
+            failed.set(true);
+        }
+    })
+}
+
 async fn render_sql(
     srv_req: &mut ServiceRequest,
     sql_file: Arc<ParsedSqlFile>,
+    authorization_files: Vec<Arc<ParsedSqlFile>>,
 ) -> actix_web::Result<HttpResponse> {
     let app_state = srv_req
         .app_data::<web::Data<AppState>>()
@@ -168,47 +278,226 @@ async fn render_sql(
 
     let mut req_param = extract_request_info(srv_req, Arc::clone(&app_state))
         .await
-        .map_err(|e| anyhow_err_to_actix(e, app_state.config.environment))?;
+        .map_err(|e| anyhow_err_to_actix(e, app_state.config().environment))?;
     log::debug!("Received a request with the following parameters: {req_param:?}");
+    let pdf_request = PdfRequest::new(&req_param, srv_req, sql_file.source_path());
+    let environment = app_state.config().environment;
+    let pending_idempotency_key = match idempotency::check(&app_state, &req_param).await {
+        Ok(Some(Submission::Duplicate(response))) => return Ok(response),
+        Ok(Some(Submission::New(key))) => Some(key),
+        Ok(None) => None,
+        Err(e) => return Err(anyhow_err_to_actix(e, app_state.config().environment)),
+    };
 
     let (resp_send, resp_recv) = tokio::sync::oneshot::channel::<HttpResponse>();
+    let (succeeded_send, succeeded_recv) = tokio::sync::oneshot::channel::<bool>();
+    let client_disconnected = CancellationToken::new();
+    // Dropped with this handler when the client disconnects before the response headers are sent
+    let disconnect_guard = client_disconnected.clone().drop_guard();
     actix_web::rt::spawn(async move {
-        let request_context = RequestContext {
-            is_embedded: req_param.get_variables.contains_key("_sqlpage_embed"),
-            content_security_policy: ContentSecurityPolicy::default(),
-        };
+        let request_context = page_request_context(&app_state, &req_param, sql_file.source_path());
         let mut conn = None;
-        let database_entries_stream =
-            stream_query_results_with_conn(&sql_file, &mut req_param, &mut conn);
-        let database_entries_stream = stop_at_first_error(database_entries_stream);
-        let response_with_writer = build_response_header_and_stream(
-            Arc::clone(&app_state),
-            database_entries_stream,
-            request_context,
-        )
-        .await;
-        match response_with_writer {
-            Ok(ResponseWithWriter::RenderStream {
-                http_response,
-                renderer,
-                database_entries_stream,
-            }) => {
-                resp_send
-                    .send(http_response)
-                    .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
-                stream_response(database_entries_stream, renderer).await;
-            }
-            Ok(ResponseWithWriter::FinishedResponse { http_response }) => {
-                resp_send
-                    .send(http_response)
-                    .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
-            }
-            Err(err) => {
-                send_anyhow_error(&err, resp_send, app_state.config.environment);
+        let authorization = set_role_and_authorize(&authorization_files, &mut req_param, &mut conn);
+        if let Some(denial) = authorization.await.transpose() {
+            let denied = send_denial(
+                Arc::clone(&app_state),
+                denial,
+                request_context,
+                resp_send,
+                &client_disconnected,
+            );
+            Box::pin(denied).await;
+            return;
+        }
+        let in_transaction = app_state.config().post_transactions
+            && req_param.method == actix_web::http::Method::POST;
+        if in_transaction {
+            if let Err(err) = begin_request_transaction(&app_state.db, &mut conn).await {
+                send_anyhow_error(&err, resp_send, app_state.config().environment);
+                return;
             }
+            req_param.in_transaction = true;
+        }
+        let failed = Cell::new(false);
+        let committed = Cell::new(false);
+        // The statements borrow the connection until the page is rendered
+        let render_page = async {
+            let database_entries_stream =
+                stream_and_commit(&sql_file, &mut req_param, &mut conn, &failed, &committed);
+            let http_response = match Box::pin(build_response_header_and_stream(
+                Arc::clone(&app_state),
+                database_entries_stream,
+                request_context,
+                &client_disconnected,
+            ))
+            .await
+            {
+                Ok(ResponseWithWriter::FinishedResponse { http_response })
+                    if in_transaction && !failed.get() =>
+                {
+                    http_response
+                }
+                other => return Box::pin(send_response(other, resp_send, environment)).await,
+            };
+            let sent = commit_and_send(&mut conn, http_response, resp_send, environment).await;
+            committed.set(sent);
+            sent
+        };
+        // Stop executing the statements as soon as the client is gone
+        let completed = tokio::select! {
+            biased;
+            completed = render_page => completed,
+            () = client_disconnected.cancelled() => false,
+        };
+        if !completed && client_disconnected.is_cancelled() {
+            log::info!(
+                "The client disconnected before the end of the page, stopping its execution"
+            );
+            // The connection may still be busy with a query whose results nobody will read
+            let session = req_param.database_session.get().copied().flatten();
+            cancel_and_close(&app_state.db, &mut conn, session).await;
         }
+        let succeeded = completed && !failed.get();
+        if in_transaction && !committed.get() {
+            rollback_request_transaction(&mut conn).await;
+        }
+        // The receiver is dropped when the request has no idempotency key
+        let _ = succeeded_send.send(succeeded);
     });
-    resp_recv.await.map_err(ErrorInternalServerError)
+    let response = resp_recv.await.map_err(ErrorInternalServerError)?;
+    disconnect_guard.disarm();
+    let response = print_if_requested(pdf_request, response, environment).await?;
+    Ok(match pending_idempotency_key {
+        Some(key) => key.record_response(response, succeeded_recv),
+        None => response,
+    })
+}
+
+fn page_request_context(
+    app_state: &AppState,
+    req_param: &RequestInfo,
+    source_path: &Path,
+) -> RequestContext {
+    RequestContext {
+        is_embedded: req_param.is_fragment_request(),
+        content_security_policy: ContentSecurityPolicy::default(),
+        directory_shell: app_state
+            .config()
+            .directory_shell(source_path)
+            .map(str::to_owned),
+        timezone: req_param.timezone(),
+        url_parameters: req_param.url_parameters(),
+        status: StatusCode::OK,
+        csrf_secret: Arc::new(BrowserSecret::from_cookie(
+            req_param
+                .cookies
+                .get(csrf::COOKIE_NAME)
+                .map(SingleOrVec::as_json_str)
+                .as_deref(),
+        )),
+        is_https: req_param.protocol == "https",
+    }
+}
+
+/// Switches to the database role of the user, then executes the `authorize.sql` files of the page.
+/// Returns the rows of the file that denied access to the page, if any.
+async fn set_role_and_authorize(
+    authorization_files: &[Arc<ParsedSqlFile>],
+    req_param: &mut RequestInfo,
+    conn: &mut DbConn,
+) -> anyhow::Result<Option<Vec<DbItem>>> {
+    set_user_role(req_param, conn).await?;
+    run_authorization_files(authorization_files, req_param, conn).await
+}
+
+/// Sends the rows of the `authorize.sql` file that denied access to the page, with a 403 status by default,
+/// or the error that prevented checking the access
+async fn send_denial(
+    app_state: Arc<AppState>,
+    denial: anyhow::Result<Vec<DbItem>>,
+    mut request_context: RequestContext,
+    resp_send: tokio::sync::oneshot::Sender<HttpResponse>,
+    client_disconnected: &CancellationToken,
+) {
+    let environment = app_state.config().environment;
+    let denial = match denial {
+        Ok(denial) => denial,
+        Err(err) => return send_anyhow_error(&err, resp_send, environment),
+    };
+    request_context.status = StatusCode::FORBIDDEN;
+    let response_with_writer = Box::pin(build_response_header_and_stream(
+        app_state,
+        futures_util::stream::iter(denial),
+        request_context,
+        client_disconnected,
+    ))
+    .await;
+    Box::pin(send_response(response_with_writer, resp_send, environment)).await;
+}
+
+/// Commits the transaction of a page that ends with its headers, such as a redirection, before sending its response,
+/// so that the client never sees a success for changes that were not saved.
+/// Returns whether the transaction was committed.
+async fn commit_and_send(
+    conn: &mut DbConn,
+    http_response: HttpResponse,
+    resp_send: tokio::sync::oneshot::Sender<HttpResponse>,
+    environment: app_config::DevOrProd,
+) -> bool {
+    let response = commit_request_transaction(conn)
+        .await
+        .map(
+            |()| ResponseWithWriter::<futures_util::stream::Empty<DbItem>>::FinishedResponse {
+                http_response,
+            },
+        );
+    Box::pin(send_response(response, resp_send, environment)).await
+}
+
+/// Sends the response headers, and renders the rest of the page in its body.
+/// Returns whether the page was rendered completely.
+async fn send_response<S: Stream<Item = DbItem>>(
+    response_with_writer: anyhow::Result<ResponseWithWriter<S>>,
+    resp_send: tokio::sync::oneshot::Sender<HttpResponse>,
+    environment: app_config::DevOrProd,
+) -> bool {
+    match response_with_writer {
+        Ok(ResponseWithWriter::RenderStream {
+            http_response,
+            renderer,
+            database_entries_stream,
+        }) => {
+            resp_send
+                .send(http_response)
+                .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
+            stream_response(database_entries_stream, renderer).await
+        }
+        Ok(ResponseWithWriter::FinishedResponse { http_response }) => {
+            resp_send
+                .send(http_response)
+                .unwrap_or_else(|e| log::error!("could not send headers {e:?}"));
+            true
+        }
+        Err(err) => {
+            send_anyhow_error(&err, resp_send, environment);
+            false
+        }
+    }
+}
+
+/// Converts the response to PDF when the page was requested with `?_format=pdf`
+async fn print_if_requested(
+    pdf_request: Option<PdfRequest>,
+    response: HttpResponse,
+    environment: app_config::DevOrProd,
+) -> actix_web::Result<HttpResponse> {
+    match pdf_request {
+        Some(pdf_request) => pdf_request
+            .convert(response)
+            .await
+            .map_err(|e| anyhow_err_to_actix(e, environment)),
+        None => Ok(response),
+    }
 }
 
 fn anyhow_err_to_actix_resp(e: &anyhow::Error, env: app_config::DevOrProd) -> HttpResponse {
@@ -309,50 +598,297 @@ async fn process_sql_request(
         .sql_file_cache
         .get_with_privilege(app_state, &sql_path, false)
         .await
-        .with_context(|| format!("Unable to get SQL file {sql_path:?}"))
-        .map_err(|e| anyhow_err_to_actix(e, app_state.config.environment))?;
-    render_sql(req, sql_file).await
+        .with_context(|| format!("Unable to get SQL file {}", sql_path.display()))
+        .map_err(|e| anyhow_err_to_actix(e, app_state.config().environment))?;
+    let authorization_files = load_authorization_files(app_state, &sql_path)
+        .await
+        .map_err(|e| anyhow_err_to_actix(e, app_state.config().environment))?;
+    render_sql(req, sql_file, authorization_files).await
+}
+
+/// Executes the `authorize.sql` files of the directories of a static file before it is served.
+/// Returns the response of the file that denied access to it, if any.
+async fn authorize_static_file(
+    req: &mut ServiceRequest,
+    path: &Path,
+) -> actix_web::Result<Option<HttpResponse>> {
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or_else(|| ErrorInternalServerError("no state"))?
+        .clone()
+        .into_inner();
+    let environment = app_state.config().environment;
+    let authorization_files = load_authorization_files(&app_state, path)
+        .await
+        .map_err(|e| anyhow_err_to_actix(e, environment))?;
+    if authorization_files.is_empty() {
+        return Ok(None);
+    }
+    let mut req_param = extract_request_info(req, Arc::clone(&app_state))
+        .await
+        .map_err(|e| anyhow_err_to_actix(e, environment))?;
+    let mut conn = None;
+    let authorization = set_role_and_authorize(&authorization_files, &mut req_param, &mut conn);
+    let Some(denial) = authorization.await.transpose() else {
+        return Ok(None);
+    };
+    let request_context = page_request_context(&app_state, &req_param, path);
+    let (resp_send, resp_recv) = tokio::sync::oneshot::channel::<HttpResponse>();
+    // The body of the denial is rendered while it is sent
+    actix_web::rt::spawn(async move {
+        let client_disconnected = CancellationToken::new();
+        let denied = send_denial(
+            app_state,
+            denial,
+            request_context,
+            resp_send,
+            &client_disconnected,
+        );
+        Box::pin(denied).await;
+    });
+    resp_recv.await.map(Some).map_err(ErrorInternalServerError)
+}
+
+/// Loads the `authorize.sql` files that must be executed before the given page or static file
+pub(crate) async fn load_authorization_files(
+    app_state: &AppState,
+    sql_path: &Path,
+) -> anyhow::Result<Vec<Arc<ParsedSqlFile>>> {
+    let store = AppFileStore::new(&app_state.sql_file_cache, &app_state.file_system, app_state);
+    let mut files = Vec::new();
+    for path in find_authorization_files(sql_path, &store).await? {
+        let file = app_state
+            .sql_file_cache
+            .get_with_privilege(app_state, &path, false)
+            .await
+            .with_context(|| format!("Unable to get the authorization file {}", path.display()))?;
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// Name of the query parameter that `sqlpage.asset_url` adds to the URLs of static files.
+/// It contains a hash of the file's contents, so responses to such URLs never become stale.
+pub(crate) const ASSET_VERSION_PARAMETER: &str = "v";
+
+/// The version of a static file, as computed by `sqlpage.asset_url`: a hash of its contents
+pub(crate) fn asset_version(contents: &[u8]) -> String {
+    let mut hasher = std::hash::DefaultHasher::new();
+    std::hash::Hasher::write(&mut hasher, contents);
+    format!("{:016x}", std::hash::Hasher::finish(&hasher))
+}
+
+fn requested_asset_version(query_string: &str) -> Option<&str> {
+    query_string.split('&').find_map(|pair| {
+        pair.strip_prefix(ASSET_VERSION_PARAMETER)?
+            .strip_prefix('=')
+    })
+}
+
+/// Whether the requested version is the current version of the file.
+/// Only then can the response be cached indefinitely: an outdated or made-up version
+/// must not pin the current contents of the file in caches under its URL.
+async fn is_current_asset_version(state: &AppState, path: &str, version: &str) -> bool {
+    match state
+        .file_system
+        .read_file(state, Path::new(path), false)
+        .await
+    {
+        Ok(contents) => asset_version(&contents) == version,
+        Err(e) => {
+            log::debug!("Unable to compute the version of {path}: {e:#}");
+            false
+        }
+    }
 }
 
 async fn serve_file(
     path: &str,
     state: &AppState,
-    if_modified_since: Option<IfModifiedSince>,
+    request: &mut ServiceRequest,
+    requested_version: Option<&str>,
 ) -> actix_web::Result<HttpResponse> {
     let path = strip_site_prefix(path, state);
-    if let Some(IfModifiedSince(date)) = if_modified_since {
+    let local_file = state
+        .file_system
+        .open_local_file(state, path.as_ref())
+        .await
+        .map_err(|e| anyhow_err_to_actix(e, state.config().environment))?;
+    let mut response = match local_file {
+        Some(file) => serve_local_file(file, request),
+        None => serve_database_file(path, state, request).await?,
+    };
+    let immutable = match requested_version {
+        Some(version) if response.status().is_success() => {
+            is_current_asset_version(state, path, version).await
+        }
+        _ => false,
+    };
+    if immutable {
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            header::HeaderValue::from_static("public, max-age=31536000, immutable"),
+        );
+    }
+    Ok(response)
+}
+
+/// Serves a file of the web root. Conditional requests are answered from its metadata,
+/// and only the requested part of the file is read, while it is sent.
+fn serve_local_file(file: actix_files::NamedFile, request: &mut ServiceRequest) -> HttpResponse {
+    let etag = metadata_etag(file.metadata());
+    match IfNoneMatch::parse(request) {
+        Ok(IfNoneMatch::Any) => return HttpResponse::NotModified().finish(),
+        Ok(IfNoneMatch::Items(tags)) if tags.iter().any(|tag| tag.weak_eq(&etag)) => {
+            return HttpResponse::NotModified()
+                .insert_header(ETag(etag))
+                .finish();
+        }
+        _ => {}
+    }
+    if !range_is_current(request, &etag) {
+        request.headers_mut().remove(header::RANGE);
+    }
+    let mut response = file
+        .use_etag(false)
+        .disable_content_disposition()
+        .into_response(request.request());
+    if let Ok(etag) = header::HeaderValue::from_str(&etag.to_string()) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    response
+}
+
+/// Strong validator of a local file, that changes when it is modified
+fn metadata_etag(metadata: &std::fs::Metadata) -> EntityTag {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    EntityTag::new_strong(format!(
+        "{:x}-{:x}.{:x}",
+        metadata.len(),
+        modified.as_secs(),
+        modified.subsec_nanos()
+    ))
+}
+
+/// Whether the `Range` header of the request applies to the current version of the file,
+/// according to its `If-Range` header. Outdated ranges are ignored, and the full file is sent.
+fn range_is_current(request: &ServiceRequest, etag: &EntityTag) -> bool {
+    if !request.headers().contains_key(header::IF_RANGE) {
+        return true;
+    }
+    matches!(IfRange::parse(request), Ok(IfRange::EntityTag(tag)) if tag.strong_eq(etag))
+}
+
+/// Serves a file of the `sqlpage_files` table, whose contents are loaded from the database
+async fn serve_database_file(
+    path: &str,
+    state: &AppState,
+    request: &ServiceRequest,
+) -> actix_web::Result<HttpResponse> {
+    let if_none_match = IfNoneMatch::parse(request).ok();
+    // If-None-Match takes precedence over If-Modified-Since
+    if let (None, Ok(IfModifiedSince(date))) = (&if_none_match, IfModifiedSince::parse(request)) {
         let since = DateTime::<Utc>::from(SystemTime::from(date));
         let modified = state
             .file_system
             .modified_since(state, path.as_ref(), since, false)
             .await
             .with_context(|| format!("Unable to get modification time of file {path:?}"))
-            .map_err(|e| anyhow_err_to_actix(e, state.config.environment))?;
+            .map_err(|e| anyhow_err_to_actix(e, state.config().environment))?;
         if !modified {
             return Ok(HttpResponse::NotModified().finish());
         }
     }
-    state
+    let contents = state
         .file_system
         .read_file(state, path.as_ref(), false)
         .await
         .with_context(|| format!("Unable to read file {path:?}"))
-        .map_err(|e| anyhow_err_to_actix(e, state.config.environment))
-        .map(|b| {
-            HttpResponse::Ok()
-                .insert_header(
-                    mime_guess::from_path(path)
-                        .first()
-                        .map_or_else(ContentType::octet_stream, ContentType),
-                )
-                .insert_header(LastModified(HttpDate::from(SystemTime::now())))
-                .body(b)
-        })
+        .map_err(|e| anyhow_err_to_actix(e, state.config().environment))?;
+    let etag = contents_etag(&contents);
+    let etag_matches = |tag: &EntityTag| tag.weak_eq(&etag);
+    match if_none_match {
+        Some(IfNoneMatch::Any) => return Ok(HttpResponse::NotModified().finish()),
+        Some(IfNoneMatch::Items(tags)) if tags.iter().any(etag_matches) => {
+            return Ok(HttpResponse::NotModified()
+                .insert_header(ETag(etag))
+                .finish());
+        }
+        _ => {}
+    }
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(
+            mime_guess::from_path(path)
+                .first()
+                .map_or_else(ContentType::octet_stream, ContentType),
+        )
+        .insert_header(LastModified(HttpDate::from(SystemTime::now())))
+        .insert_header((header::ACCEPT_RANGES, "bytes"));
+    let instance_length = Some(contents.len() as u64);
+    Ok(match requested_range(request, &etag, contents.len()) {
+        RequestedRange::Full => response.insert_header(ETag(etag)).body(contents),
+        RequestedRange::Partial(range) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .insert_header(ETag(etag))
+            .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                range: Some((*range.start() as u64, *range.end() as u64)),
+                instance_length,
+            }))
+            .body(contents[range].to_vec()),
+        RequestedRange::Unsatisfiable => HttpResponse::RangeNotSatisfiable()
+            .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                range: None,
+                instance_length,
+            }))
+            .finish(),
+    })
+}
+
+/// Strong validator of a file stored in the database.
+/// Its contents are already in memory, and their hash does not change between versions of `SQLPage`.
+fn contents_etag(contents: &[u8]) -> EntityTag {
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(contents);
+    EntityTag::new_strong(format!("{:x}-{hash:x}", contents.len()))
+}
+
+enum RequestedRange {
+    Full,
+    Partial(std::ops::RangeInclusive<usize>),
+    Unsatisfiable,
+}
+
+/// The part of a database file that the request asks for in a `Range` header.
+/// Requests for several ranges at once, and ranges of an outdated version of the file
+/// (according to `If-Range`), receive the full file.
+fn requested_range(request: &ServiceRequest, etag: &EntityTag, len: usize) -> RequestedRange {
+    let Ok(Range::Bytes(ranges)) = Range::parse(request) else {
+        return RequestedRange::Full;
+    };
+    let [range] = ranges.as_slice() else {
+        return RequestedRange::Full;
+    };
+    if !range_is_current(request, etag) {
+        return RequestedRange::Full;
+    }
+    match range.to_satisfiable_range(len as u64) {
+        Some((start, end)) => match (usize::try_from(start), usize::try_from(end)) {
+            (Ok(start), Ok(end)) => RequestedRange::Partial(start..=end),
+            _ => RequestedRange::Unsatisfiable,
+        },
+        None => RequestedRange::Unsatisfiable,
+    }
 }
 
 /// Strips the site prefix from a path
 fn strip_site_prefix<'a>(path: &'a str, state: &AppState) -> &'a str {
-    path.strip_prefix(&state.config.site_prefix).unwrap_or(path)
+    path.strip_prefix(&state.config().site_prefix)
+        .unwrap_or(path)
 }
 
 /// Serves a fallback 404 error page (when no 404 handler is found)
@@ -363,7 +899,7 @@ fn serve_not_found(service_request: &mut ServiceRequest) -> actix_web::Error {
     });
     let path = service_request.path();
     let mut message = format!("{path} does not exist.");
-    if !app_state.config.environment.is_prod() {
+    if !app_state.config().environment.is_prod() {
         message.push_str("\n\nRouting Debug Info:\n\
         - SQLPage first looks for an exact match of your file (e.g. 'page.sql')\n\
         - For paths without extensions that end in '/', SQLPage looks for 'index.sql' in that directory (e.g. '/dir/' loads 'dir/index.sql')\n\
@@ -375,7 +911,7 @@ fn serve_not_found(service_request: &mut ServiceRequest) -> actix_web::Error {
     }
 
     let err = source_err.context(message);
-    anyhow_err_to_actix(err, app_state.config.environment)
+    anyhow_err_to_actix(err, app_state.config().environment)
 }
 
 pub async fn main_handler(
@@ -387,15 +923,16 @@ pub async fn main_handler(
         .uri()
         .path_and_query()
         .ok_or_else(|| ErrorBadRequest("expected valid path with query from request"))?;
-    let routing_action = match calculate_route(path_and_query, &store, &app_state.config).await {
-        Ok(action) => action,
-        Err(e) => {
-            let e = e.context(format!(
-                "Unable to calculate the routing action for: {path_and_query:?}"
-            ));
-            return Err(anyhow_err_to_actix(e, app_state.config.environment));
-        }
-    };
+    let routing_action =
+        match calculate_route(path_and_query, &store, app_state.config().as_ref()).await {
+            Ok(action) => action,
+            Err(e) => {
+                let e = e.context(format!(
+                    "Unable to calculate the routing action for: {path_and_query:?}"
+                ));
+                return Err(anyhow_err_to_actix(e, app_state.config().environment));
+            }
+        };
     match routing_action {
         NotFound => Err(serve_not_found(&mut service_request)),
         Execute(path) => process_sql_request(&mut service_request, path).await,
@@ -403,16 +940,28 @@ pub async fn main_handler(
             // Currently, we do not set a 404 status when the user provides a fallback 404.sql file.
             process_sql_request(&mut service_request, path).await
         }
+        Proxy { route, url } => {
+            let environment = app_state.config().environment;
+            proxy::forward(&mut service_request, &route, &url)
+                .await
+                .map_err(|e| anyhow_err_to_actix(e, environment))
+        }
         Redirect(redirect_target) => Ok(HttpResponse::MovedPermanently()
             .insert_header((header::LOCATION, redirect_target))
             .finish()),
         Serve(path) => {
-            let if_modified_since = IfModifiedSince::parse(&service_request).ok();
-            let app_state: &web::Data<AppState> = service_request.app_data().expect("app_state");
+            if let Some(denial) = authorize_static_file(&mut service_request, &path).await? {
+                return Ok(service_request.into_response(denial));
+            }
+            let requested_version =
+                requested_asset_version(service_request.query_string()).map(str::to_owned);
+            let app_state: web::Data<AppState> =
+                service_request.app_data().cloned().expect("app_state");
             serve_file(
                 path.as_os_str().to_str().unwrap(),
-                app_state,
-                if_modified_since,
+                &app_state,
+                &mut service_request,
+                requested_version.as_deref(),
             )
             .await
         }
@@ -426,7 +975,7 @@ async fn default_prefix_redirect(
 ) -> actix_web::Result<ServiceResponse> {
     let app_state: &web::Data<AppState> = service_request.app_data().expect("app_state");
     let original_path = service_request.path();
-    let site_prefix = &app_state.config.site_prefix;
+    let site_prefix = &app_state.config().site_prefix;
     let redirect_path = site_prefix.trim_end_matches('/').to_string() + original_path;
     log::info!(
         "Received request to {original_path} (outside of site prefix {site_prefix}), redirecting to {redirect_path}"
@@ -438,6 +987,66 @@ async fn default_prefix_redirect(
     ))
 }
 
+/// Rejects the requests from clients that are not allowed by the `ip_access_rules` of their path,
+/// before any file is read or any SQL is executed
+async fn check_ip_access(
+    service_request: ServiceRequest,
+    next: middleware::Next<impl MessageBody + 'static>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let app_state: &web::Data<AppState> = service_request.app_data().expect("app_state");
+    let config = &app_state.config();
+    if let Some(rule) = config.ip_access_rule_for_url(service_request.path()) {
+        let client_ip = client_ip(service_request.request(), config);
+        if !rule.allows(client_ip) {
+            log::info!(
+                "Access to {} denied by ip_access_rules for client {client_ip:?}",
+                service_request.path()
+            );
+            let response = ErrorWithStatus {
+                status: StatusCode::FORBIDDEN,
+            }
+            .error_response();
+            return Ok(service_request
+                .into_response(response)
+                .map_into_right_body());
+        }
+    }
+    next.call(service_request)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+/// Tells browsers which sites can embed the page in a frame, according to `frame_ancestors`
+async fn set_frame_options(
+    service_request: ServiceRequest,
+    next: middleware::Next<impl MessageBody + 'static>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let app_state: &web::Data<AppState> = service_request.app_data().expect("app_state");
+    let sources = app_state
+        .config()
+        .frame_ancestors_for_url(service_request.path());
+    let mut response = next.call(service_request).await?;
+    if let Some(sources) = sources {
+        // Browsers that do not support the frame-ancestors policy only understand X-Frame-Options
+        let frame_options = match sources.as_str() {
+            "'none'" => Some("DENY"),
+            "'self'" => Some("SAMEORIGIN"),
+            _ => None,
+        };
+        let headers = response.headers_mut();
+        let policy = header::HeaderValue::from_str(&format!("frame-ancestors {sources}"))
+            .map_err(ErrorInternalServerError)?;
+        headers.append(header::CONTENT_SECURITY_POLICY, policy);
+        if let Some(frame_options) = frame_options {
+            headers.insert(
+                header::X_FRAME_OPTIONS,
+                header::HeaderValue::from_static(frame_options),
+            );
+        }
+    }
+    Ok(response)
+}
+
 pub fn create_app(
     app_state: web::Data<AppState>,
 ) -> App<
@@ -451,7 +1060,8 @@ pub fn create_app(
         InitError = (),
     >,
 > {
-    let encoded_scope: &str = app_state.config.site_prefix.trim_end_matches('/');
+    let config = app_state.config();
+    let encoded_scope: &str = config.site_prefix.trim_end_matches('/');
     let decoded_scope = percent_encoding::percent_decode_str(encoded_scope).decode_utf8_lossy();
     App::new()
         .service(
@@ -459,17 +1069,41 @@ pub fn create_app(
                 .service(static_content::js())
                 .service(static_content::apexcharts_js())
                 .service(static_content::tomselect_js())
+                .service(static_content::leaflet_js())
+                .service(static_content::leaflet_css())
                 .service(static_content::css())
                 .service(static_content::icons())
                 .service(static_content::favicon())
+                .service(component_gallery::component_gallery())
+                .service(admin::admin_tables())
+                .service(admin::admin_sql_console())
+                .service(admin::admin_metrics())
+                .service(admin::admin_reload_config())
+                .service(admin::admin_version())
+                .service(admin::admin_file_manager(&config))
+                .service(live::live_updates())
+                .service(pdf::printed_pages())
+                .configure(|cfg| {
+                    if let Some(path) = &config.openapi_path {
+                        cfg.service(openapi::openapi_spec(path));
+                    }
+                    if let Some(path) = &config.batch_path {
+                        cfg.service(batch::batch_endpoint(path));
+                    }
+                })
                 .default_service(fn_service(main_handler)),
         )
         // when receiving a request outside of the prefix, redirect to the prefix
         .default_service(fn_service(default_prefix_redirect))
+        .wrap(middleware::from_fn(check_ip_access))
+        .wrap(middleware::from_fn(audit_log::record_request))
         .wrap(Logger::default())
-        .wrap(default_headers(&app_state))
+        .wrap(default_headers())
+        .wrap(middleware::from_fn(set_content_security_policy))
+        // Added after the fallback policy, that is only set when the response does not have one yet
+        .wrap(middleware::from_fn(set_frame_options))
         .wrap(middleware::Condition::new(
-            app_state.config.compress_responses,
+            app_state.config().compress_responses,
             middleware::Compress::default(),
         ))
         .wrap(middleware::NormalizePath::new(
@@ -483,7 +1117,7 @@ pub fn create_app(
 #[must_use]
 pub fn form_config(app_state: &web::Data<AppState>) -> web::FormConfig {
     web::FormConfig::default()
-        .limit(app_state.config.max_uploaded_file_size)
+        .limit(app_state.config().max_uploaded_file_size)
         .error_handler(|decode_err, _req| {
             match decode_err {
                 actix_web::error::UrlencodedError::Overflow { size, limit } => {
@@ -501,34 +1135,102 @@ pub fn form_config(app_state: &web::Data<AppState>) -> web::FormConfig {
 
 #[must_use]
 pub fn payload_config(app_state: &web::Data<AppState>) -> PayloadConfig {
-    PayloadConfig::default().limit(app_state.config.max_uploaded_file_size * 2)
+    PayloadConfig::default().limit(app_state.config().max_uploaded_file_size * 2)
 }
 
-fn default_headers(app_state: &web::Data<AppState>) -> middleware::DefaultHeaders {
+fn default_headers() -> middleware::DefaultHeaders {
     let server_header = format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-    let mut headers = middleware::DefaultHeaders::new().add(("Server", server_header));
-    if let Some(csp) = &app_state.config.content_security_policy {
-        headers = headers.add(("Content-Security-Policy", csp.as_str()));
+    middleware::DefaultHeaders::new().add(("Server", server_header))
+}
+
+/// Sets the custom `content_security_policy` on the responses that are not rendered pages,
+/// which set their own policy, with the nonce of the page.
+/// Each response gets a new nonce, so that a nonce seen on one response cannot be reused on a page.
+async fn set_content_security_policy(
+    service_request: ServiceRequest,
+    next: middleware::Next<impl MessageBody + 'static>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let app_state: web::Data<AppState> =
+        web::Data::clone(service_request.app_data().expect("app_state"));
+    let mut response = next.call(service_request).await?;
+    if let Some(policy) = &app_state.config().content_security_policy {
+        let headers = response.headers_mut();
+        if !headers.contains_key(header::CONTENT_SECURITY_POLICY) {
+            let nonce = ContentSecurityPolicy::default().nonce.to_string();
+            let policy = header::HeaderValue::from_str(&policy.replace(NONCE_PLACEHOLDER, &nonce))
+                .map_err(ErrorInternalServerError)?;
+            headers.insert(header::CONTENT_SECURITY_POLICY, policy);
+        }
     }
-    headers
+    Ok(response)
 }
 
 pub async fn run_server(config: &AppConfig, state: AppState) -> anyhow::Result<()> {
-    let listen_on = config.listen_on();
     let state = web::Data::new(state);
     let final_state = web::Data::clone(&state);
-    let factory = move || create_app(web::Data::clone(&state));
 
     #[cfg(feature = "lambda-web")]
     if lambda_web::is_running_on_lambda() {
+        let factory = move || create_app(web::Data::clone(&state));
         lambda_web::run_actix_on_lambda(factory)
             .await
             .map_err(|e| anyhow::anyhow!("Unable to start the lambda: {e}"))?;
         return Ok(());
     }
-    let mut server = HttpServer::new(factory);
+    let (server, _addresses) = bind_server(config, state)?;
+
+    // In development, pages are reloaded as soon as they change instead of checking them on every request
+    let _file_watcher = if config.environment.is_prod() {
+        None
+    } else {
+        super::file_watcher::watch_files(&final_state)
+            .map_err(|e| log::warn!("Changes to the files of the site will not be watched: {e:#}"))
+            .ok()
+    };
+    super::metrics::log_periodically(web::Data::clone(&final_state));
+    if let Err(e) = super::config_reload::reload_on_sighup(web::Data::clone(&final_state)) {
+        log::warn!("The configuration cannot be reloaded without a restart: {e:#}");
+    }
+    log_welcome_message(config);
+    server
+        .await
+        .with_context(|| "Unable to start the application")?;
+
+    // We are done, we can close the database connection
+    final_state.db.close().await?;
+    Ok(())
+}
+
+/// Creates the web server with the HTTP settings of the configuration, and binds it to the configured addresses.
+/// Returns the server, that has to be awaited, and the TCP addresses it listens on.
+pub fn bind_server(
+    config: &AppConfig,
+    state: web::Data<AppState>,
+) -> anyhow::Result<(actix_web::dev::Server, Vec<std::net::SocketAddr>)> {
+    let listen_on = config.listen_on();
+    let mut server = HttpServer::new(move || create_app(web::Data::clone(&state)));
+    // The backlog must be set before binding, because it is only applied to new listeners
+    if let Some(backlog) = config.http_backlog {
+        server = server.backlog(backlog);
+    }
+    if let Some(workers) = config.http_workers {
+        server = server.workers(workers);
+    }
+    if let Some(keep_alive) = config.http_keep_alive_seconds {
+        server = server.keep_alive(if keep_alive > 0.0 {
+            KeepAlive::Timeout(Duration::from_secs_f64(keep_alive))
+        } else {
+            KeepAlive::Disabled
+        });
+    }
+    if let Some(timeout) = config.http_client_request_timeout_seconds {
+        server = server.client_request_timeout(Duration::from_secs_f64(timeout));
+    }
     if let Some(unix_socket) = &config.unix_socket {
-        log::info!("Will start HTTP server on UNIX socket: {:?}", unix_socket);
+        log::info!(
+            "Will start HTTP server on UNIX socket: {}",
+            unix_socket.display()
+        );
         #[cfg(target_family = "unix")]
         {
             server = server
@@ -556,21 +1258,13 @@ pub async fn run_server(config: &AppConfig, state: AppState) -> anyhow::Result<(
                 .map_err(|e| bind_error(e, listen_on))?;
         }
     }
-
-    log_welcome_message(config);
-    server
-        .run()
-        .await
-        .with_context(|| "Unable to start the application")?;
-
-    // We are done, we can close the database connection
-    final_state.db.close().await?;
-    Ok(())
+    let addresses = server.addrs();
+    Ok((server.run(), addresses))
 }
 
 fn log_welcome_message(config: &AppConfig) {
     let address_message = if let Some(unix_socket) = &config.unix_socket {
-        format!("unix socket {unix_socket:?}")
+        format!("unix socket {}", unix_socket.display())
     } else if let Some(domain) = &config.https_domain {
         format!("https://{domain}")
     } else {
@@ -632,14 +1326,18 @@ fn bind_error(e: std::io::Error, listen_on: std::net::SocketAddr) -> anyhow::Err
 }
 
 #[cfg(target_family = "unix")]
-fn bind_unix_socket_err(e: std::io::Error, unix_socket: &PathBuf) -> anyhow::Error {
+fn bind_unix_socket_err(e: std::io::Error, unix_socket: &Path) -> anyhow::Error {
     let ctx = if e.kind() == std::io::ErrorKind::PermissionDenied {
         format!(
-            "You do not have permission to bind to the UNIX socket {unix_socket:?}. \
+            "You do not have permission to bind to the UNIX socket {}. \
             You can change the socket path in the configuration file or check the permissions.",
+            unix_socket.display(),
         )
     } else {
-        format!("Unable to bind to UNIX socket {unix_socket:?} {e:?}")
+        format!(
+            "Unable to bind to UNIX socket {} {e:?}",
+            unix_socket.display()
+        )
     };
     anyhow::anyhow!(e).context(ctx)
 }
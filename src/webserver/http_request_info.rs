@@ -1,3 +1,4 @@
+use crate::app_config::AppConfig;
 use crate::AppState;
 use actix_multipart::form::bytes::Bytes;
 use actix_multipart::form::tempfile::TempFile;
@@ -7,6 +8,7 @@ use actix_multipart::Multipart;
 use actix_web::dev::ServiceRequest;
 use actix_web::http::header::Header;
 use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::header::HOST;
 use actix_web::web;
 use actix_web::web::Form;
 use actix_web::FromRequest;
@@ -15,16 +17,27 @@ use actix_web_httpauth::headers::authorization::Authorization;
 use actix_web_httpauth::headers::authorization::Basic;
 use anyhow::anyhow;
 use anyhow::Context;
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::rc::Rc;
 use std::sync::Arc;
 use tokio_stream::StreamExt;
 
+use super::database::execute_queries::SessionId;
+use super::database::query_debug;
+use super::database::request_budget::RequestBudget;
+use super::http::SingleOrVec;
 use super::request_variables::param_map;
 use super::request_variables::ParamMap;
+use super::signed_url;
+use super::timezone::{viewer_timezone, TIMEZONE_COOKIE};
+
+/// Request header used by scripts (such as HTMX) to get a page without its shell, to update a part of the current page
+pub const FRAGMENT_HEADER: &str = "sqlpage-fragment";
 
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct RequestInfo {
     pub method: actix_web::http::Method,
     pub path: String,
@@ -33,12 +46,30 @@ pub struct RequestInfo {
     pub post_variables: ParamMap,
     pub uploaded_files: Rc<HashMap<String, TempFile>>,
     pub headers: ParamMap,
+    /// Address of the client, which is read from `X-Forwarded-For` when the request comes from a trusted proxy
     pub client_ip: Option<IpAddr>,
+    /// Host name used by the client, which is read from `X-Forwarded-Host` when the request comes from a trusted proxy
+    pub host: Option<String>,
     pub cookies: ParamMap,
     pub basic_auth: Option<Basic>,
     pub app_state: Arc<AppState>,
     pub clone_depth: u8,
     pub raw_body: Option<Vec<u8>>,
+    /// Whether the query plans and durations of the statements should be displayed at the end of the page
+    pub debug_queries: bool,
+    /// Whether the statements are executed in the transaction of the request, when `post_transactions` is enabled
+    pub in_transaction: bool,
+    /// Whether the URL of the request was created by `sqlpage.sign_url`, and has not expired
+    pub signed_url: bool,
+    /// Whether the request was the submission of a form protected with `bot_protection`, that passed its checks
+    pub bot_protection_passed: bool,
+    /// Whether the request was the submission of an action button, with a valid token, from the same site
+    pub csrf_verified: bool,
+    /// Statements and rows of the request, shared with the files it includes
+    pub budget: Rc<RequestBudget>,
+    /// Database session of the connection of the request, read before its first query,
+    /// to cancel the query when the client disconnects
+    pub database_session: Rc<OnceCell<Option<SessionId>>>,
 }
 
 impl RequestInfo {
@@ -53,13 +84,52 @@ impl RequestInfo {
             uploaded_files: self.uploaded_files.clone(),
             headers: self.headers.clone(),
             client_ip: self.client_ip,
+            host: self.host.clone(),
             cookies: self.cookies.clone(),
             basic_auth: self.basic_auth.clone(),
             app_state: self.app_state.clone(),
             clone_depth: self.clone_depth + 1,
             raw_body: self.raw_body.clone(),
+            // Only the statements of the top-level file are displayed
+            debug_queries: false,
+            in_transaction: self.in_transaction,
+            // The signature only covers the variables of the original request
+            signed_url: false,
+            // The protection fields are not passed to the files executed with run_sql
+            bot_protection_passed: false,
+            csrf_verified: false,
+            budget: Rc::clone(&self.budget),
+            database_session: Rc::clone(&self.database_session),
         }
     }
+
+    /// The time zone of the browser that made the request, or the default time zone of the site
+    #[must_use]
+    pub fn timezone(&self) -> chrono_tz::Tz {
+        let cookie = self
+            .cookies
+            .get(TIMEZONE_COOKIE)
+            .map(SingleOrVec::as_json_str);
+        viewer_timezone(cookie.as_deref(), &self.app_state.config())
+    }
+
+    /// The GET variables of the request, as a JSON object sorted by name
+    #[must_use]
+    pub fn url_parameters(&self) -> serde_json::Value {
+        let sorted: std::collections::BTreeMap<_, _> = self.get_variables.iter().collect();
+        serde_json::to_value(sorted).unwrap_or_default()
+    }
+
+    /// Whether only the HTML of the components was requested, without the shell,
+    /// with the `_sqlpage_embed` URL parameter or the [`FRAGMENT_HEADER`] header.
+    #[must_use]
+    pub fn is_fragment_request(&self) -> bool {
+        self.get_variables.contains_key("_sqlpage_embed")
+            || matches!(
+                self.headers.get(FRAGMENT_HEADER),
+                Some(SingleOrVec::Single(value)) if value.trim().eq_ignore_ascii_case("true")
+            )
+    }
 }
 
 impl Clone for RequestInfo {
@@ -77,20 +147,49 @@ pub(crate) async fn extract_request_info(
 ) -> anyhow::Result<RequestInfo> {
     let (http_req, payload) = req.parts_mut();
     let method = http_req.method().clone();
-    let protocol = http_req.connection_info().scheme().to_string();
-    let config = &app_state.config;
-    let (post_variables, uploaded_files, raw_body) =
+    let config = &app_state.config();
+    let RequestOrigin {
+        client_ip,
+        protocol,
+        host,
+    } = request_origin(http_req, config);
+    let (mut post_variables, uploaded_files, raw_body) =
         extract_post_data(http_req, payload, config).await?;
+    let bot_protection_passed = super::form_protection::check_submission(&mut post_variables)?;
+    let csrf_cookie = req.cookie(super::csrf::COOKIE_NAME);
+    let csrf_verified = super::csrf::check_submission(
+        &method,
+        &mut post_variables,
+        req.headers(),
+        host.as_deref(),
+        csrf_cookie.as_ref().map(actix_web::cookie::Cookie::value),
+    )?;
+    super::form_steps::restore_previous_values(&mut post_variables)?;
     let headers = req.headers().iter().map(|(name, value)| {
         (
             name.to_string(),
             String::from_utf8_lossy(value.as_bytes()).to_string(),
         )
     });
-    let get_variables = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
+    let signed_url = signed_url::check_request(
+        config.token_signing_secret.as_deref(),
+        req.path(),
+        req.query_string(),
+        chrono::Utc::now().timestamp(),
+    )?;
+    let mut get_variables = web::Query::<Vec<(String, String)>>::from_query(req.query_string())
         .map(web::Query::into_inner)
         .unwrap_or_default();
-    let client_ip = req.peer_addr().map(|addr| addr.ip());
+    if signed_url {
+        get_variables.retain(|(name, _)| {
+            name != signed_url::EXPIRES_PARAMETER && name != signed_url::SIGNATURE_PARAMETER
+        });
+    }
+    let debug_token = req
+        .headers()
+        .get(query_debug::DEBUG_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let debug_queries = query_debug::is_debug_request(config, debug_token);
 
     let raw_cookies = req.cookies();
     let cookies = raw_cookies
@@ -110,15 +209,108 @@ pub(crate) async fn extract_request_info(
         post_variables: param_map(post_variables),
         uploaded_files: Rc::new(HashMap::from_iter(uploaded_files)),
         client_ip,
+        host,
         cookies: param_map(cookies),
         basic_auth,
         app_state,
         protocol,
         clone_depth: 0,
         raw_body,
+        debug_queries,
+        in_transaction: false,
+        signed_url,
+        bot_protection_passed,
+        csrf_verified,
+        budget: Rc::new(RequestBudget::new(config)),
+        database_session: Rc::default(),
     })
 }
 
+/// Address of the client, and protocol and host name it used to reach the server
+pub(crate) struct RequestOrigin {
+    pub client_ip: Option<IpAddr>,
+    pub protocol: String,
+    pub host: Option<String>,
+}
+
+/// When the request comes from one of the `trusted_proxies`, the client information is read from the
+/// `X-Forwarded-*` headers set by the proxy. Otherwise, these headers are ignored, since anyone can send them.
+pub(crate) fn request_origin(http_req: &HttpRequest, config: &AppConfig) -> RequestOrigin {
+    let peer_ip = http_req.peer_addr().map(|addr| addr.ip());
+    let host_header = header_value(http_req, HOST.as_str())
+        .map(str::to_string)
+        .or_else(|| http_req.uri().authority().map(ToString::to_string));
+    if config.trusted_proxies.is_empty() {
+        // Without trusted proxies, the protocol is read from the forwarding headers, as in previous versions
+        return RequestOrigin {
+            client_ip: peer_ip,
+            protocol: http_req.connection_info().scheme().to_string(),
+            host: host_header,
+        };
+    }
+    let direct_protocol = if http_req.app_config().secure() {
+        "https"
+    } else {
+        "http"
+    };
+    if !peer_ip.is_some_and(|ip| is_trusted_proxy(config, ip)) {
+        return RequestOrigin {
+            client_ip: peer_ip,
+            protocol: direct_protocol.to_string(),
+            host: host_header,
+        };
+    }
+    RequestOrigin {
+        client_ip: forwarded_client_ip(http_req, config).or(peer_ip),
+        protocol: header_value(http_req, "x-forwarded-proto")
+            .unwrap_or(direct_protocol)
+            .to_ascii_lowercase(),
+        host: header_value(http_req, "x-forwarded-host")
+            .map(str::to_string)
+            .or(host_header),
+    }
+}
+
+/// Address of the client, read from `X-Forwarded-For` when the request comes from a trusted proxy
+pub(crate) fn client_ip(http_req: &HttpRequest, config: &AppConfig) -> Option<IpAddr> {
+    request_origin(http_req, config).client_ip
+}
+
+fn is_trusted_proxy(config: &AppConfig, ip: IpAddr) -> bool {
+    let ip = ip.to_canonical();
+    config.trusted_proxies.iter().any(|net| net.contains(&ip))
+}
+
+/// Each proxy appends the address it received the request from to `X-Forwarded-For`.
+/// The client is the last address that was not added by a trusted proxy.
+fn forwarded_client_ip(http_req: &HttpRequest, config: &AppConfig) -> Option<IpAddr> {
+    let forwarded_ips: Vec<&str> = http_req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let mut client_ip = None;
+    for forwarded_ip in forwarded_ips.into_iter().rev() {
+        let Ok(ip) = forwarded_ip.parse::<IpAddr>() else {
+            break;
+        };
+        client_ip = Some(ip);
+        if !is_trusted_proxy(config, ip) {
+            break;
+        }
+    }
+    client_ip
+}
+
+/// The first value of a header, when a proxy sends a comma-separated list
+fn header_value<'a>(http_req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    let value = http_req.headers().get(name)?.to_str().ok()?;
+    let first = value.split(',').next()?.trim();
+    (!first.is_empty()).then_some(first)
+}
+
 async fn extract_post_data(
     http_req: &mut actix_web::HttpRequest,
     payload: &mut actix_web::dev::Payload,
@@ -255,8 +447,7 @@ async fn is_file_field_empty(
             && uploaded_file
                 .file_name
                 .as_ref()
-                .filter(|x| !x.is_empty())
-                .is_none()
+                .is_none_or(String::is_empty)
             && tokio::fs::metadata(&uploaded_file.file.path()).await?.len() == 0,
     )
 }
@@ -282,6 +473,36 @@ mod test {
         assert_eq!(request_info.get_variables.len(), 0);
     }
 
+    fn proxy_request(peer: &str) -> HttpRequest {
+        TestRequest::default()
+            .peer_addr(peer.parse().unwrap())
+            .insert_header(("host", "internal:8080"))
+            .insert_header(("x-forwarded-for", "203.0.113.7, 10.0.0.2"))
+            .insert_header(("x-forwarded-proto", "https"))
+            .insert_header(("x-forwarded-host", "example.com"))
+            .to_http_request()
+    }
+
+    #[test]
+    fn test_request_origin_from_trusted_proxy() {
+        let config: AppConfig =
+            serde_json::from_str(r#"{"trusted_proxies": ["10.0.0.0/8"]}"#).unwrap();
+        let origin = request_origin(&proxy_request("10.0.0.1:1234"), &config);
+        assert_eq!(origin.client_ip, Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(origin.protocol, "https");
+        assert_eq!(origin.host.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn test_request_origin_from_untrusted_client() {
+        let config: AppConfig =
+            serde_json::from_str(r#"{"trusted_proxies": ["10.0.0.0/8"]}"#).unwrap();
+        let origin = request_origin(&proxy_request("198.51.100.1:1234"), &config);
+        assert_eq!(origin.client_ip, Some("198.51.100.1".parse().unwrap()));
+        assert_eq!(origin.protocol, "http");
+        assert_eq!(origin.host.as_deref(), Some("internal:8080"));
+    }
+
     #[actix_web::test]
     async fn test_extract_urlencoded_request() {
         let config =
@@ -0,0 +1,299 @@
+//! Idempotency keys, which prevent a form from being processed twice when it is submitted again
+//! after a double click or a browser retry.
+//!
+//! When `idempotency_table` is configured, a POST request that contains a key, in the `Idempotency-Key` header
+//! or in the `_sqlpage_idempotency_key` form field, is recorded in the table before the page is executed.
+//! The rendered response is saved next to the key when the page succeeds, and sent back as-is
+//! to the later requests with the same key, without executing the page again.
+//! When the page fails, the key is deleted, so that the submission can be retried.
+//!
+//! Keys are bound to the cookies and the `Authorization` header of the request, so that a response,
+//! which can contain personal data, is never sent to another user who submits the same key.
+//! The cookies set by the page are not recorded, so that replaying a response never opens a session.
+
+use super::database::make_placeholder;
+use super::http::SingleOrVec;
+use super::http_request_info::RequestInfo;
+use super::ErrorWithStatus;
+use crate::AppState;
+use actix_web::body::{BodyStream, MessageBody};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use anyhow::Context;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::AnyPool;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENCY_KEY_FIELD: &str = "_sqlpage_idempotency_key";
+/// Header added to the responses that are sent again instead of executing the page
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LENGTH: usize = 255;
+/// How long a duplicate submission waits for the first one to finish
+const MAX_WAIT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum Submission {
+    /// The key was not used before. The page is executed, and its response recorded.
+    New(PendingKey),
+    /// The response to the previous submission with the same key
+    Duplicate(HttpResponse),
+}
+
+/// Records the idempotency key of the request, or returns the response to the previous request with the same key.
+/// Returns `None` when the request has no idempotency key or when idempotency keys are not enabled.
+pub async fn check(
+    app_state: &AppState,
+    request: &RequestInfo,
+) -> anyhow::Result<Option<Submission>> {
+    let config = app_state.config();
+    let Some(table) = &config.idempotency_table else {
+        return Ok(None);
+    };
+    if request.method != actix_web::http::Method::POST {
+        return Ok(None);
+    }
+    let key = match (
+        request.headers.get(IDEMPOTENCY_KEY_HEADER),
+        request.post_variables.get(IDEMPOTENCY_KEY_FIELD),
+    ) {
+        (Some(SingleOrVec::Single(key)), _) | (None, Some(SingleOrVec::Single(key))) => key,
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(bad_request(
+                "A request can only have a single idempotency key",
+            ))
+        }
+    };
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(bad_request(&format!(
+            "Idempotency keys must contain between 1 and {MAX_KEY_LENGTH} bytes"
+        )));
+    }
+    let pending = PendingKey {
+        pool: app_state.db.connection(),
+        table: table.clone(),
+        key: scoped_key(request, key),
+        path: request.path.clone(),
+        keep_row: false,
+    };
+    pending.insert_or_fetch().await
+}
+
+/// The key recorded in the table: a hash of the key sent by the client and of its credentials
+fn scoped_key(request: &RequestInfo, key: &str) -> String {
+    let cookies: BTreeMap<_, _> = request.cookies.iter().collect();
+    let identity = serde_json::json!([request.headers.get("authorization"), cookies, key]);
+    let hash = Sha256::digest(identity.to_string().as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(hash)
+}
+
+fn bad_request(message: &str) -> anyhow::Error {
+    anyhow::Error::new(ErrorWithStatus {
+        status: StatusCode::BAD_REQUEST,
+    })
+    .context(message.to_string())
+}
+
+/// A key that is being processed. It is deleted when dropped before its response is recorded.
+pub struct PendingKey {
+    pool: AnyPool,
+    table: String,
+    key: String,
+    path: String,
+    /// Whether the row of the key must be kept when this is dropped
+    keep_row: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// base64-encoded body
+    body: String,
+}
+
+impl PendingKey {
+    fn placeholder(&self, arg_number: usize) -> String {
+        make_placeholder(self.pool.any_kind(), arg_number)
+    }
+
+    async fn insert_or_fetch(mut self) -> anyhow::Result<Option<Submission>> {
+        let insert_sql = format!(
+            "INSERT INTO {} (idempotency_key, path) VALUES ({}, {})",
+            self.table,
+            self.placeholder(1),
+            self.placeholder(2)
+        );
+        let select_sql = format!(
+            "SELECT path, response FROM {} WHERE idempotency_key = {}",
+            self.table,
+            self.placeholder(1)
+        );
+        let started = Instant::now();
+        let mut missing_after_failed_insert = false;
+        loop {
+            let inserted = sqlx::query(&insert_sql)
+                .bind(&self.key)
+                .bind(&self.path)
+                .execute(&self.pool)
+                .await;
+            let Err(insert_error) = inserted else {
+                return Ok(Some(Submission::New(self)));
+            };
+            let previous: Option<(String, Option<String>)> = sqlx::query_as(&select_sql)
+                .bind(&self.key)
+                .fetch_optional(&self.pool)
+                .await
+                .with_context(|| {
+                    format!("Unable to read the idempotency keys with {select_sql:?}")
+                })?;
+            match previous {
+                Some((path, _)) if path != self.path => {
+                    // The key belongs to another request, it must not be deleted
+                    self.keep_row = true;
+                    return Err(anyhow::Error::new(ErrorWithStatus {
+                        status: StatusCode::UNPROCESSABLE_ENTITY,
+                    })
+                    .context(format!(
+                        "The idempotency key was already used for another page: {path}"
+                    )));
+                }
+                Some((_, Some(response))) => {
+                    self.keep_row = true;
+                    log::debug!(
+                        "Sending the recorded response for the idempotency key {:?}",
+                        self.key
+                    );
+                    return replay(&response).map(|r| Some(Submission::Duplicate(r)));
+                }
+                Some((_, None)) if started.elapsed() < MAX_WAIT => {
+                    log::debug!(
+                        "Waiting for the previous request with the idempotency key {:?}",
+                        self.key
+                    );
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+                Some((_, None)) => {
+                    self.keep_row = true;
+                    return Err(anyhow::Error::new(ErrorWithStatus {
+                        status: StatusCode::CONFLICT,
+                    })
+                    .context("A request with the same idempotency key is still being processed"));
+                }
+                // The previous request failed and its key was deleted: try again
+                None if !missing_after_failed_insert => missing_after_failed_insert = true,
+                None => {
+                    self.keep_row = true;
+                    return Err(anyhow::Error::new(insert_error).context(format!(
+                        "Unable to record the idempotency key with {insert_sql:?}"
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Passes the response through to the client, and records it once it has been sent completely,
+    /// if `succeeded` resolves to true. Otherwise, the key is deleted.
+    pub fn record_response(
+        mut self,
+        response: HttpResponse,
+        succeeded: tokio::sync::oneshot::Receiver<bool>,
+    ) -> HttpResponse {
+        let (head, body) = response.into_parts();
+        let status = head.status().as_u16();
+        let headers = head
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != actix_web::http::header::SET_COOKIE)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect::<Vec<_>>();
+        let body_stream = async_stream::stream! {
+            let mut body = Box::pin(body);
+            let mut captured = Vec::new();
+            while let Some(chunk) = std::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                if let Ok(bytes) = &chunk {
+                    captured.extend_from_slice(bytes);
+                }
+                yield chunk;
+            }
+            if succeeded.await.unwrap_or(false) {
+                let stored = StoredResponse {
+                    status,
+                    headers,
+                    body: base64::engine::general_purpose::STANDARD.encode(&captured),
+                };
+                self.save(&stored).await;
+            }
+        };
+        head.set_body(BodyStream::new(body_stream))
+            .map_into_boxed_body()
+    }
+
+    async fn save(&mut self, response: &StoredResponse) {
+        let update_sql = format!(
+            "UPDATE {} SET response = {} WHERE idempotency_key = {}",
+            self.table,
+            self.placeholder(1),
+            self.placeholder(2)
+        );
+        let response = serde_json::to_string(response).unwrap_or_default();
+        match sqlx::query(&update_sql)
+            .bind(response)
+            .bind(&self.key)
+            .execute(&self.pool)
+            .await
+        {
+            Ok(_) => {
+                self.keep_row = true;
+                log::debug!(
+                    "Recorded the response for the idempotency key {:?}",
+                    self.key
+                );
+            }
+            Err(e) => log::error!(
+                "Unable to record the response of an idempotent request with {update_sql:?}: {e}"
+            ),
+        }
+    }
+}
+
+impl Drop for PendingKey {
+    fn drop(&mut self) {
+        if self.keep_row {
+            return;
+        }
+        let delete_sql = format!(
+            "DELETE FROM {} WHERE idempotency_key = {}",
+            self.table,
+            self.placeholder(1)
+        );
+        let pool = self.pool.clone();
+        let key = std::mem::take(&mut self.key);
+        log::debug!(
+            "The request with the idempotency key {key:?} did not succeed, deleting the key"
+        );
+        actix_web::rt::spawn(async move {
+            if let Err(e) = sqlx::query(&delete_sql).bind(key).execute(&pool).await {
+                log::error!("Unable to delete an idempotency key with {delete_sql:?}: {e}");
+            }
+        });
+    }
+}
+
+fn replay(stored: &str) -> anyhow::Result<HttpResponse> {
+    let stored: StoredResponse =
+        serde_json::from_str(stored).context("Invalid response recorded for an idempotency key")?;
+    let body = base64::engine::general_purpose::STANDARD
+        .decode(stored.body)
+        .context("Invalid body recorded for an idempotency key")?;
+    let mut response = HttpResponse::build(StatusCode::from_u16(stored.status)?);
+    for header in stored.headers {
+        response.append_header(header);
+    }
+    response.insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"));
+    Ok(response.body(body))
+}
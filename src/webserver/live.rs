@@ -0,0 +1,257 @@
+//! Live updates of parts of a page, triggered by `PostgreSQL` notifications.
+//!
+//! The `live` component displays a page fragment, and loads it again each time a notification is sent
+//! to its channel, with `NOTIFY channel` or `pg_notify('channel', '')`, for instance from a trigger.
+//! Browsers are told about the notifications by a stream of server-sent events, at [`LIVE_PATH`].
+//! They then request the fragment again, with their own cookies, so that it is rendered with the permissions
+//! of each viewer.
+//!
+//! A single database connection listens to the channels of all the viewers. It is opened when the first
+//! viewer subscribes, and opened again with an increasing delay when the connection fails.
+//! Subscriptions are signed when the component is rendered, with a key dedicated to them,
+//! so that viewers can only subscribe to the channels used by the pages of the site.
+
+use super::database::{postgres_connect_options, Database};
+use super::{form_protection, ErrorWithStatus};
+use crate::{AppConfig, AppState};
+use actix_web::http::{header, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse, Resource};
+use serde::Deserialize;
+use sqlx::pool::PoolOptions;
+use sqlx::postgres::{PgConnectOptions, PgListener, Postgres};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Path of the stream of notifications, relative to the site prefix
+pub const LIVE_PATH: &str = "/sqlpage/live";
+/// Channel used to make the listening connection subscribe to new channels
+const WAKE_UP_CHANNEL: &str = "sqlpage_live";
+/// Comments are sent at this interval, so that proxies do not close idle streams
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Purpose of the signatures of the subscriptions, which have their own key
+const SIGNING_PURPOSE: &str = "sqlpage live subscription";
+/// Attempts to open the listening connection, after which the next subscription tries again
+const MAX_LISTEN_ATTEMPTS: u32 = 5;
+/// Delay before the second attempt to open the listening connection, doubled after each failure
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+type Subscribers = Arc<Mutex<HashMap<String, broadcast::Sender<()>>>>;
+
+pub struct LiveNotifications {
+    connect_options: PgConnectOptions,
+    subscribers: Subscribers,
+    /// Channels that the listening connection does not listen to yet
+    pending_channels: Arc<Mutex<Vec<String>>>,
+    /// Whether the listening connection is open, or being opened
+    listening: Arc<AtomicBool>,
+}
+
+impl LiveNotifications {
+    /// Live updates are only available with `PostgreSQL`
+    pub fn new(config: &AppConfig) -> anyhow::Result<Option<Self>> {
+        Ok(
+            postgres_connect_options(config)?.map(|connect_options| Self {
+                connect_options,
+                subscribers: Subscribers::default(),
+                pending_channels: Arc::default(),
+                listening: Arc::default(),
+            }),
+        )
+    }
+
+    /// Returns a receiver of the notifications of the channel
+    pub async fn subscribe(&self, db: &Database, channel: &str) -> broadcast::Receiver<()> {
+        let (receiver, is_new_channel) = {
+            let mut subscribers = self
+                .subscribers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(sender) = subscribers.get(channel) {
+                (sender.subscribe(), false)
+            } else {
+                let (sender, receiver) = broadcast::channel(16);
+                subscribers.insert(channel.to_string(), sender);
+                (receiver, true)
+            }
+        };
+        if is_new_channel {
+            self.pending_channels
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(channel.to_string());
+        }
+        if !self.listening.swap(true, Ordering::SeqCst) {
+            // Also after a failure to open the listening connection, which kept the channels pending
+            tokio::spawn(listen(
+                self.connect_options.clone(),
+                Arc::clone(&self.subscribers),
+                Arc::clone(&self.pending_channels),
+                Arc::clone(&self.listening),
+            ));
+        } else if is_new_channel {
+            // The listening connection cannot receive commands while it waits for notifications
+            let wake_up = sqlx::query("SELECT pg_notify($1, '')")
+                .bind(WAKE_UP_CHANNEL)
+                .execute(&db.connection())
+                .await;
+            if let Err(e) = wake_up {
+                log::error!("Unable to subscribe to the notifications of {channel}: {e}");
+            }
+        }
+        receiver
+    }
+}
+
+/// Opens the listening connection, waiting longer after each failed attempt
+async fn connect_listener(pool: &sqlx::Pool<Postgres>) -> Option<PgListener> {
+    let mut delay = FIRST_RETRY_DELAY;
+    for attempt in 1..=MAX_LISTEN_ATTEMPTS {
+        let listener = match PgListener::connect_with(pool).await {
+            Ok(mut listener) => listener.listen(WAKE_UP_CHANNEL).await.map(|()| listener),
+            Err(e) => Err(e),
+        };
+        match listener {
+            Ok(listener) => return Some(listener),
+            Err(e) if attempt < MAX_LISTEN_ATTEMPTS => {
+                log::warn!(
+                    "Unable to listen to database notifications, retrying in {delay:?}: {e}"
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => log::error!("Unable to listen to database notifications: {e}"),
+        }
+    }
+    None
+}
+
+async fn listen(
+    connect_options: PgConnectOptions,
+    subscribers: Subscribers,
+    pending_channels: Arc<Mutex<Vec<String>>>,
+    listening: Arc<AtomicBool>,
+) {
+    let pool = PoolOptions::<Postgres>::new()
+        .max_connections(1)
+        .max_lifetime(None)
+        .idle_timeout(None)
+        .connect_lazy_with(connect_options);
+    let Some(mut listener) = connect_listener(&pool).await else {
+        // The pending channels are kept, and the next subscription opens a new connection
+        listening.store(false, Ordering::SeqCst);
+        return;
+    };
+    loop {
+        let channels = std::mem::take(
+            &mut *pending_channels
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        for channel in channels {
+            log::debug!("Listening to the database notifications of {channel}");
+            if let Err(e) = listener.listen(&channel).await {
+                log::error!("Unable to listen to the database notifications of {channel}: {e}");
+            }
+        }
+        match listener.recv().await {
+            Ok(notification) => {
+                let subscribers = subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+                if let Some(sender) = subscribers.get(notification.channel()) {
+                    // Fails when nobody is viewing the page anymore
+                    let _ = sender.send(());
+                }
+            }
+            Err(e) => {
+                log::error!("Unable to receive database notifications: {e}");
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+/// Signature that allows the viewers of a page to subscribe to a channel
+#[must_use]
+pub fn subscription_signature(channel: &str) -> String {
+    form_protection::sign_for(SIGNING_PURPOSE, channel)
+}
+
+#[derive(Deserialize)]
+struct Subscription {
+    channel: String,
+    signature: String,
+}
+
+pub fn live_updates() -> Resource {
+    web::resource(LIVE_PATH).route(web::get().to(notification_stream))
+}
+
+async fn notification_stream(
+    app_state: web::Data<AppState>,
+    subscription: web::Query<Subscription>,
+) -> actix_web::Result<HttpResponse> {
+    let Subscription { channel, signature } = subscription.into_inner();
+    if !form_protection::is_signed_for(SIGNING_PURPOSE, &channel, &signature) {
+        log::info!("Rejecting a subscription to {channel:?} with an invalid signature");
+        return Err(ErrorWithStatus {
+            status: StatusCode::FORBIDDEN,
+        }
+        .into());
+    }
+    let Some(live_notifications) = app_state.live_notifications() else {
+        return Err(actix_web::error::ErrorNotImplemented(
+            "Live updates require a PostgreSQL database",
+        ));
+    };
+    let mut receiver = live_notifications.subscribe(&app_state.db, &channel).await;
+    let events = async_stream::stream! {
+        yield Ok::<_, Infallible>(Bytes::from_static(b": connected\n\n"));
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+        keep_alive.tick().await;
+        loop {
+            tokio::select! {
+                notification = receiver.recv() => match notification {
+                    Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        yield Ok(Bytes::from_static(b"event: notification\ndata:\n\n"));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = keep_alive.tick() => yield Ok(Bytes::from_static(b": keep-alive\n\n")),
+            }
+        }
+    };
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Compressed events would wait in the buffer of the encoder
+        .insert_header((header::CONTENT_ENCODING, "identity"))
+        .streaming(events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_signature() {
+        let signature = subscription_signature("orders");
+        assert!(form_protection::is_signed_for(
+            SIGNING_PURPOSE,
+            "orders",
+            &signature
+        ));
+        assert!(!form_protection::is_signed_for(
+            SIGNING_PURPOSE,
+            "salaries",
+            &signature
+        ));
+        assert!(
+            !form_protection::is_signed("orders", &signature),
+            "the subscriptions are not signed with the key of the forms"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Runtime statistics of the server, to help with capacity planning.
+//!
+//! They are served in the Prometheus text format at `/sqlpage/admin/metrics`, next to the other
+//! administration pages, and logged every minute under the `sqlpage::metrics` log target
+//! when it is enabled at the debug level, for instance with `RUST_LOG=sqlpage=info,sqlpage::metrics=debug`.
+
+use crate::file_cache::CacheStats;
+use crate::templates::ComponentRenderStats;
+use crate::AppState;
+use actix_web::web;
+use std::fmt::Write;
+use std::time::Duration;
+
+pub const METRICS_LOG_TARGET: &str = "sqlpage::metrics";
+const METRICS_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A snapshot of the state of the database pool, the caches, and the renderer
+#[derive(Debug)]
+pub struct RuntimeMetrics {
+    pub pool_connections: u32,
+    pub pool_idle_connections: usize,
+    /// Requests waiting for a database connection to become available
+    pub queued_acquires: usize,
+    pub acquires: u64,
+    pub acquire_wait_time: Duration,
+    pub sql_file_cache: CacheStats,
+    pub template_cache: CacheStats,
+    pub template_compilations: u64,
+    pub template_compilation_time: Duration,
+    pub component_renders: Vec<(String, ComponentRenderStats)>,
+    /// Requests that were not recorded in the `audit_log_table`, because too many were waiting to be inserted
+    pub audit_log_dropped_records: u64,
+}
+
+impl RuntimeMetrics {
+    #[must_use]
+    pub fn collect(app_state: &AppState) -> Self {
+        let pool = &app_state.db.connection();
+        let acquire_stats = &app_state.db.acquire_stats;
+        let template_stats = &app_state.all_templates.stats;
+        Self {
+            pool_connections: pool.size(),
+            pool_idle_connections: pool.num_idle(),
+            queued_acquires: acquire_stats.waiting(),
+            acquires: acquire_stats.acquires(),
+            acquire_wait_time: acquire_stats.wait_time(),
+            sql_file_cache: app_state.sql_file_cache.stats(),
+            template_cache: app_state.all_templates.split_templates.stats(),
+            template_compilations: template_stats.compilations(),
+            template_compilation_time: template_stats.compilation_time(),
+            component_renders: template_stats.render_times(),
+            audit_log_dropped_records: app_state
+                .audit_log()
+                .map_or(0, super::audit_log::AuditLog::dropped_records),
+        }
+    }
+
+    #[must_use]
+    pub fn pool_connections_in_use(&self) -> usize {
+        usize::try_from(self.pool_connections)
+            .unwrap_or(usize::MAX)
+            .saturating_sub(self.pool_idle_connections)
+    }
+
+    /// Formats the metrics in the Prometheus text exposition format
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+        metric(
+            "sqlpage_db_pool_connections",
+            "gauge",
+            "Open database connections, by state.",
+            &[
+                (
+                    r#"{state="in_use"}"#.into(),
+                    self.pool_connections_in_use().to_string(),
+                ),
+                (
+                    r#"{state="idle"}"#.into(),
+                    self.pool_idle_connections.to_string(),
+                ),
+            ],
+        );
+        metric(
+            "sqlpage_db_pool_queued_acquires",
+            "gauge",
+            "Requests waiting for a database connection.",
+            &[(String::new(), self.queued_acquires.to_string())],
+        );
+        metric(
+            "sqlpage_db_pool_acquires_total",
+            "counter",
+            "Database connections requested from the pool.",
+            &[(String::new(), self.acquires.to_string())],
+        );
+        metric(
+            "sqlpage_db_pool_acquire_wait_seconds_total",
+            "counter",
+            "Time spent waiting for database connections.",
+            &[(String::new(), seconds(self.acquire_wait_time))],
+        );
+        let cache_samples = [
+            ("sql", "hit", self.sql_file_cache.hits),
+            ("sql", "miss", self.sql_file_cache.misses),
+            ("template", "hit", self.template_cache.hits),
+            ("template", "miss", self.template_cache.misses),
+        ]
+        .map(|(cache, result, count)| {
+            (
+                format!(r#"{{cache="{cache}",result="{result}"}}"#),
+                count.to_string(),
+            )
+        });
+        metric(
+            "sqlpage_file_cache_requests_total",
+            "counter",
+            "Files requested from the caches of parsed SQL files and compiled templates.",
+            &cache_samples,
+        );
+        metric(
+            "sqlpage_template_compilations_total",
+            "counter",
+            "Templates compiled.",
+            &[(String::new(), self.template_compilations.to_string())],
+        );
+        metric(
+            "sqlpage_template_compilation_seconds_total",
+            "counter",
+            "Time spent compiling templates.",
+            &[(String::new(), seconds(self.template_compilation_time))],
+        );
+        metric(
+            "sqlpage_component_renders_total",
+            "counter",
+            "Components rendered in pages.",
+            &self.component_samples(|stats| stats.renders.to_string()),
+        );
+        metric(
+            "sqlpage_component_render_seconds_total",
+            "counter",
+            "Time spent rendering components, including all of their rows.",
+            &self.component_samples(|stats| seconds(stats.time)),
+        );
+        metric(
+            "sqlpage_audit_log_dropped_records_total",
+            "counter",
+            "Requests not recorded in the audit log, because too many were waiting to be inserted.",
+            &[(String::new(), self.audit_log_dropped_records.to_string())],
+        );
+        out
+    }
+
+    /// One sample per component, labelled with its name
+    fn component_samples(
+        &self,
+        value: impl Fn(&ComponentRenderStats) -> String,
+    ) -> Vec<(String, String)> {
+        self.component_renders
+            .iter()
+            .map(|(name, stats)| {
+                let labels = format!(r#"{{component="{}"}}"#, escape_label(name));
+                (labels, value(stats))
+            })
+            .collect()
+    }
+
+    fn log(&self) {
+        let hit_rate = |stats: CacheStats| {
+            stats.hit_rate().map_or_else(
+                || "no requests".into(),
+                |rate| format!("{:.1}%", rate * 100.),
+            )
+        };
+        let average_wait = if self.acquires > 0 {
+            self.acquire_wait_time / u32::try_from(self.acquires).unwrap_or(u32::MAX)
+        } else {
+            Duration::ZERO
+        };
+        log::debug!(
+            target: METRICS_LOG_TARGET,
+            "Database pool: {} connections in use, {} idle, {} requests waiting, {average_wait:?} average wait. \
+            Cache hit rate: {} for SQL files, {} for templates. {} templates compiled in {:?}.",
+            self.pool_connections_in_use(),
+            self.pool_idle_connections,
+            self.queued_acquires,
+            hit_rate(self.sql_file_cache),
+            hit_rate(self.template_cache),
+            self.template_compilations,
+            self.template_compilation_time,
+        );
+        for (component, stats) in &self.component_renders {
+            log::debug!(
+                target: METRICS_LOG_TARGET,
+                "Component '{component}': rendered {} times, {:?} on average",
+                stats.renders,
+                stats.average_time(),
+            );
+        }
+    }
+}
+
+/// Logs the runtime metrics every minute, if the metrics log target is enabled
+pub fn log_periodically(app_state: web::Data<AppState>) {
+    if !log::log_enabled!(target: METRICS_LOG_TARGET, log::Level::Debug) {
+        return;
+    }
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(METRICS_LOG_INTERVAL);
+        // The first tick completes immediately, before any request was handled
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            RuntimeMetrics::collect(&app_state).log();
+        }
+    });
+}
+
+fn seconds(duration: Duration) -> String {
+    duration.as_secs_f64().to_string()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_format() {
+        let metrics = RuntimeMetrics {
+            pool_connections: 5,
+            pool_idle_connections: 2,
+            queued_acquires: 1,
+            acquires: 10,
+            acquire_wait_time: Duration::from_millis(1500),
+            sql_file_cache: CacheStats { hits: 8, misses: 2 },
+            template_cache: CacheStats::default(),
+            template_compilations: 3,
+            template_compilation_time: Duration::from_millis(20),
+            component_renders: vec![(
+                "my \"card\"".into(),
+                ComponentRenderStats {
+                    renders: 4,
+                    time: Duration::from_millis(2),
+                },
+            )],
+            audit_log_dropped_records: 7,
+        };
+        let text = metrics.to_prometheus();
+        for line in [
+            "# TYPE sqlpage_db_pool_connections gauge",
+            r#"sqlpage_db_pool_connections{state="in_use"} 3"#,
+            r#"sqlpage_db_pool_connections{state="idle"} 2"#,
+            "sqlpage_db_pool_queued_acquires 1",
+            "sqlpage_db_pool_acquire_wait_seconds_total 1.5",
+            r#"sqlpage_file_cache_requests_total{cache="sql",result="hit"} 8"#,
+            r#"sqlpage_file_cache_requests_total{cache="template",result="miss"} 0"#,
+            r#"sqlpage_component_renders_total{component="my \"card\""} 4"#,
+            r#"sqlpage_component_render_seconds_total{component="my \"card\""} 0.002"#,
+            "sqlpage_audit_log_dropped_records_total 7",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line:?} not in:\n{text}");
+        }
+    }
+}
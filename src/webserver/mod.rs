@@ -27,14 +27,36 @@
 //!
 //! - [`response_writer`]: Streaming response generation
 //! - [`static_content`]: Static asset handling (JS, CSS, icons)
+//! - [`metrics`]: Runtime statistics of the database pool, caches, and renderer
+//! - [`audit_log`]: Records every request in a database table, when `audit_log_table` is set
+//! - `file_watcher`: Drops cached pages and components when they change on disk, in development
+//! - [`live`]: Reloads parts of pages when the database sends notifications, with `PostgreSQL`
+//! - [`form_protection`]: Rejects the spam sent through forms with the `bot_protection` property
+//! - [`form_steps`]: Carries the values of the previous steps of forms split on several pages
+//! - [`csrf`]: Rejects the actions of buttons with a `method` that were submitted from other sites
 //!
 
+mod admin;
+pub mod audit_log;
+mod batch;
+mod component_gallery;
+pub mod config_reload;
 mod content_security_policy;
+pub mod csrf;
 pub mod database;
 pub mod error_with_status;
+mod file_watcher;
+pub mod form_protection;
+pub mod form_steps;
 pub mod http;
 pub mod http_request_info;
 mod https;
+pub mod idempotency;
+pub mod live;
+pub mod metrics;
+mod openapi;
+pub mod pdf;
+mod proxy;
 pub mod request_variables;
 
 pub use database::Database;
@@ -44,4 +66,6 @@ pub use database::make_placeholder;
 pub use database::migrations::apply;
 pub mod response_writer;
 pub mod routing;
+pub mod signed_url;
 mod static_content;
+pub mod timezone;
@@ -0,0 +1,177 @@
+//! Generation of an [OpenAPI 3](https://spec.openapis.org/oas/v3.0.3) description of the pages of the site,
+//! served at the path configured in `openapi_path`.
+//!
+//! Every `.sql` file in the web root becomes an operation on its URL.
+//! The request parameters it reads become query parameters (`$name` and `?name`)
+//! or fields of a submitted form (`:name`), in which case the operation is a `POST`.
+//! Pages that use the `json` component are described as returning JSON, and the others as returning HTML.
+
+use crate::app_config::AppConfig;
+use crate::check::list_sql_files;
+use crate::webserver::database::{ParameterSource, ParsedSqlFile};
+use crate::webserver::routing::canonical_path;
+use crate::AppState;
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse, Resource};
+use serde_json::{json, Map, Value as JsonValue};
+use sqlx::any::AnyKind;
+use std::path::Path;
+
+pub fn openapi_spec(path: &str) -> Resource {
+    web::resource(path).route(web::get().to(|app_state: web::Data<AppState>| async move {
+        let config = app_state.config();
+        let db_kind = app_state.db.connection().any_kind();
+        match web::block(move || generate(&config, db_kind)).await {
+            Ok(Ok(spec)) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(spec.to_string()),
+            Ok(Err(e)) => {
+                log::error!("Unable to generate the OpenAPI description of the site: {e:#}");
+                HttpResponse::InternalServerError().finish()
+            }
+            Err(e) => {
+                log::error!("Unable to generate the OpenAPI description of the site: {e}");
+                HttpResponse::InternalServerError().finish()
+            }
+        }
+    }))
+}
+
+/// Builds the `OpenAPI` document describing all the SQL files of the web root.
+/// Files that cannot be read or parsed are skipped.
+pub fn generate(config: &AppConfig, db_kind: AnyKind) -> anyhow::Result<JsonValue> {
+    let mut paths = Map::new();
+    for path in list_sql_files(config)? {
+        let sql = match std::fs::read_to_string(config.web_root.join(&path)) {
+            Ok(sql) => sql,
+            Err(e) => {
+                log::warn!(
+                    "Not including {} in the OpenAPI description: {e}",
+                    path.display()
+                );
+                continue;
+            }
+        };
+        let parsed = ParsedSqlFile::new_for_db_kind(db_kind, &sql, &path);
+        if parsed.parse_errors().next().is_some() {
+            log::warn!(
+                "Not including {} in the OpenAPI description because it contains errors",
+                path.display()
+            );
+            continue;
+        }
+        paths.insert(url_path(config, &path), path_item(&parsed, db_kind));
+    }
+    Ok(json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "SQLPage website",
+            "version": crate::build_info::VERSION,
+        },
+        "servers": [{ "url": config.site_prefix }],
+        "paths": paths,
+    }))
+}
+
+/// The URL path of a file, relative to the `site_prefix`, which is listed in the `servers` of the document
+fn url_path(config: &AppConfig, path: &Path) -> String {
+    let path = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .fold(String::new(), |url, component| url + "/" + &component);
+    canonical_path(&path, config.url_style).into_owned()
+}
+
+fn path_item(parsed: &ParsedSqlFile, db_kind: AnyKind) -> JsonValue {
+    let mut query_parameters = Vec::new();
+    let mut form_fields = Map::new();
+    for parameter in parsed.request_parameters() {
+        let string_schema = json!({ "type": "string" });
+        match parameter.source {
+            ParameterSource::Query | ParameterSource::FormOrQuery => {
+                query_parameters.push(json!({
+                    "name": parameter.name,
+                    "in": "query",
+                    "required": false,
+                    "schema": string_schema,
+                }));
+            }
+            ParameterSource::Form => {
+                form_fields.insert(parameter.name, string_schema);
+            }
+        }
+    }
+    let returns_json = parsed
+        .literal_components(db_kind)
+        .iter()
+        .any(|component| component.name.eq_ignore_ascii_case("json"));
+    let response = if returns_json {
+        json!({
+            "description": "The rows returned by the page, as JSON",
+            "content": { "application/json": { "schema": {} } },
+        })
+    } else {
+        json!({
+            "description": "The page, rendered as HTML",
+            "content": { "text/html": { "schema": { "type": "string" } } },
+        })
+    };
+    let mut operation = json!({
+        "summary": parsed.source_path().display().to_string(),
+        "parameters": query_parameters,
+        "responses": { "200": response },
+    });
+    if form_fields.is_empty() {
+        return json!({ "get": operation });
+    }
+    let form_schema = json!({ "type": "object", "properties": form_fields });
+    operation["requestBody"] = json!({
+        "content": {
+            "application/x-www-form-urlencoded": { "schema": form_schema },
+            "multipart/form-data": { "schema": form_schema },
+        },
+    });
+    json!({ "post": operation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+
+    #[test]
+    fn test_generate() {
+        let web_root = std::env::temp_dir().join("sqlpage_test_openapi");
+        let _ = std::fs::remove_dir_all(&web_root);
+        std::fs::create_dir_all(web_root.join("api")).unwrap();
+        std::fs::write(
+            web_root.join("index.sql"),
+            "select 'list' as component, $title as title;",
+        )
+        .unwrap();
+        std::fs::write(
+            web_root.join("api/users.sql"),
+            "select 'json' as component; select * from users where name = :name and age > $min_age;",
+        )
+        .unwrap();
+        std::fs::write(web_root.join("broken.sql"), "select (1 + ;").unwrap();
+
+        let mut config = test_config();
+        config.web_root.clone_from(&web_root);
+        config.configuration_directory = web_root.join("sqlpage");
+        let spec = generate(&config, AnyKind::Sqlite).unwrap();
+        std::fs::remove_dir_all(&web_root).unwrap();
+
+        let paths = spec["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 2, "{spec:#}");
+        let index = &spec["paths"]["/index.sql"]["get"];
+        assert_eq!(index["parameters"][0]["name"], "title");
+        assert!(index["responses"]["200"]["content"]["text/html"].is_object());
+
+        let users = &spec["paths"]["/api/users.sql"]["post"];
+        assert_eq!(users["parameters"][0]["name"], "min_age");
+        let form = &users["requestBody"]["content"]["application/x-www-form-urlencoded"];
+        assert!(form["schema"]["properties"]["name"].is_object(), "{spec:#}");
+        assert!(users["responses"]["200"]["content"]["application/json"].is_object());
+    }
+}
@@ -0,0 +1,320 @@
+//! Rendering of pages to PDF, when they are requested with `?_format=pdf`
+//! and `pdf_chromium_path` is set in the configuration.
+//!
+//! The page is rendered to HTML as usual, then printed to PDF by a headless Chromium (or Google Chrome)
+//! started for each request. The browser loads the rendered page from this server, at a single-use URL
+//! under [`PRINTED_PAGES_PATH`] on the configured `site_url`, with the Content Security Policy of the page.
+//! A `<base>` element pointing to the URL of the page is added to the HTML,
+//! so that the browser loads the stylesheets, scripts and images of the page from this server.
+//! The page is never written to disk, and the browser cannot read local files.
+//! At most `pdf_max_concurrent_renders` browsers run at the same time.
+
+use crate::render::content_disposition;
+use crate::webserver::http_request_info::RequestInfo;
+use crate::AppState;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{self, DispositionType, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Resource};
+use anyhow::Context;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// Query parameter that selects the format of the response
+pub const FORMAT_PARAMETER: &str = "_format";
+/// Path of the pages loaded by the browser that prints them, relative to the site prefix
+pub const PRINTED_PAGES_PATH: &str = "/sqlpage/pdf";
+/// How long the browser has to print a page
+const PRINT_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long scripts can run in the page before it is printed, in milliseconds
+const SCRIPT_TIME_BUDGET_MS: u32 = 5000;
+
+/// A page that must be printed to PDF once it is rendered
+pub struct PdfRequest {
+    app_state: Arc<AppState>,
+    chromium: PathBuf,
+    /// The URL of the site, from which the browser loads the page
+    site_url: String,
+    /// The prefix of the paths of the site
+    site_prefix: String,
+    /// The URL of the page, used to resolve the relative links of the HTML page when it is printed
+    page_url: String,
+    /// Name of the downloaded file, without the extension
+    file_name: String,
+}
+
+impl PdfRequest {
+    /// Returns `None` if the request does not ask for a PDF, or if PDF rendering is disabled
+    #[must_use]
+    pub fn new(request: &RequestInfo, req: &ServiceRequest, sql_path: &Path) -> Option<Self> {
+        let config = request.app_state.config();
+        let chromium = config.pdf_chromium_path.clone()?;
+        let format = request.get_variables.get(FORMAT_PARAMETER)?;
+        if format.as_json_str() != "pdf" {
+            return None;
+        }
+        // The Host header is chosen by the client, so it cannot decide where the browser loads resources from
+        let site_url = config.site_url();
+        let page_url = page_url(&site_url, req.path());
+        let file_name = sql_path
+            .file_stem()
+            .map_or_else(|| "page".into(), |stem| stem.to_string_lossy().into_owned());
+        Some(Self {
+            app_state: Arc::clone(&request.app_state),
+            chromium,
+            site_url,
+            site_prefix: config.site_prefix.clone(),
+            page_url,
+            file_name,
+        })
+    }
+
+    /// Prints an HTML response to PDF. Responses that are not successful HTML pages are returned unchanged.
+    pub async fn convert(self, response: HttpResponse) -> anyhow::Result<HttpResponse> {
+        let is_html = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/html"));
+        if response.status() != StatusCode::OK || !is_html {
+            return Ok(response);
+        }
+        let (head, body) = response.into_parts();
+        let html = actix_web::body::to_bytes(body)
+            .await
+            .map_err(|e| anyhow::anyhow!("Unable to read the rendered page: {e}"))?;
+        let html = with_base_url(&String::from_utf8_lossy(&html), &self.page_url);
+        let content_security_policy = head.headers().get(header::CONTENT_SECURITY_POLICY).cloned();
+        let permit = self.app_state.pdf_renders.acquire().await?;
+        let printed_page = self.app_state.printed_pages.add(PrintedPage {
+            html,
+            content_security_policy,
+        });
+        let printed_page_url = page_url(
+            &self.site_url,
+            &format!(
+                "{}{}/{}",
+                self.site_prefix.trim_end_matches('/'),
+                PRINTED_PAGES_PATH,
+                printed_page.id
+            ),
+        );
+        let pdf = print_to_pdf(&self.chromium, &printed_page_url).await?;
+        drop(printed_page);
+        drop(permit);
+        let mut response = HttpResponse::build(head.status());
+        for (name, value) in head.headers() {
+            if name != header::CONTENT_TYPE {
+                response.append_header((name, value));
+            }
+        }
+        Ok(response
+            .content_type("application/pdf")
+            .insert_header(content_disposition(
+                DispositionType::Inline,
+                &self.file_name,
+                "pdf",
+            ))
+            .body(pdf))
+    }
+}
+
+/// A rendered page, waiting to be loaded by the browser that prints it
+pub struct PrintedPage {
+    pub html: String,
+    /// The `Content-Security-Policy` header of the page
+    pub content_security_policy: Option<HeaderValue>,
+}
+
+/// The pages being printed, by the random identifier of their URL
+#[derive(Default)]
+pub struct PrintedPages(Mutex<HashMap<String, PrintedPage>>);
+
+impl PrintedPages {
+    /// Makes the page available to the browser, until the returned guard is dropped
+    pub fn add(&self, page: PrintedPage) -> PrintedPageGuard<'_> {
+        let id = format!("{:032x}", rand::random::<u128>());
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id.clone(), page);
+        PrintedPageGuard { pages: self, id }
+    }
+
+    /// Removes the page, so that it can only be loaded once
+    fn take(&self, id: &str) -> Option<PrintedPage> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id)
+    }
+}
+
+/// Removes a page from [`PrintedPages`] when it is dropped, even if the browser did not load it
+pub struct PrintedPageGuard<'a> {
+    pages: &'a PrintedPages,
+    pub id: String,
+}
+
+impl Drop for PrintedPageGuard<'_> {
+    fn drop(&mut self) {
+        self.pages.take(&self.id);
+    }
+}
+
+/// Serves the pages to the browser that prints them
+pub fn printed_pages() -> Resource {
+    web::resource(format!("{PRINTED_PAGES_PATH}/{{id}}")).route(web::get().to(printed_page))
+}
+
+async fn printed_page(app_state: web::Data<AppState>, id: web::Path<String>) -> HttpResponse {
+    let Some(page) = app_state.printed_pages.take(&id) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let mut response = HttpResponse::Ok();
+    response.content_type("text/html; charset=utf-8");
+    if let Some(csp) = page.content_security_policy {
+        response.insert_header((header::CONTENT_SECURITY_POLICY, csp));
+    }
+    response.body(page.html)
+}
+
+/// The URL of the page at the given path on the site, ignoring the path of `site_url`
+fn page_url(site_url: &str, path: &str) -> String {
+    match url::Url::parse(site_url).and_then(|url| url.join(path)) {
+        Ok(url) => url.to_string(),
+        Err(e) => {
+            log::error!("Invalid site_url {site_url:?}: {e}");
+            format!("{}{path}", site_url.trim_end_matches('/'))
+        }
+    }
+}
+
+/// Adds a `<base>` element at the start of the `<head>` of the page
+fn with_base_url(html: &str, page_url: &str) -> String {
+    let base = format!(r#"<base href="{}">"#, page_url.replace('"', "%22"));
+    match html.find("<head>") {
+        Some(head) => {
+            let insert_at = head + "<head>".len();
+            format!("{}{base}{}", &html[..insert_at], &html[insert_at..])
+        }
+        None => format!("{base}{html}"),
+    }
+}
+
+async fn print_to_pdf(chromium: &Path, page_url: &str) -> anyhow::Result<Vec<u8>> {
+    let work_dir = std::env::temp_dir().join(format!("sqlpage-pdf-{:016x}", rand::random::<u64>()));
+    tokio::fs::create_dir_all(&work_dir)
+        .await
+        .with_context(|| format!("Unable to create {}", work_dir.display()))?;
+    let result = print_in_dir(chromium, page_url, &work_dir).await;
+    if let Err(e) = tokio::fs::remove_dir_all(&work_dir).await {
+        log::warn!("Unable to remove {}: {e}", work_dir.display());
+    }
+    result
+}
+
+/// Prints the page at the given URL, using `work_dir` for the browser profile and the PDF file
+async fn print_in_dir(chromium: &Path, page_url: &str, work_dir: &Path) -> anyhow::Result<Vec<u8>> {
+    let pdf_path = work_dir.join("page.pdf");
+    let mut command = tokio::process::Command::new(chromium);
+    command
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-first-run")
+        .arg("--no-pdf-header-footer")
+        // Pages loaded over HTTP cannot open local files, nor store files with the file system APIs
+        .arg("--disable-file-system")
+        .arg(format!("--virtual-time-budget={SCRIPT_TIME_BUDGET_MS}"))
+        .arg(format!(
+            "--user-data-dir={}",
+            work_dir.join("profile").display()
+        ))
+        .arg(format!("--print-to-pdf={}", pdf_path.display()))
+        .arg(page_url)
+        .kill_on_drop(true);
+    log::debug!("Printing a page to PDF with {command:?}");
+    let output = tokio::time::timeout(PRINT_TIMEOUT, command.output())
+        .await
+        .with_context(|| {
+            format!(
+                "{} did not print the page within {PRINT_TIMEOUT:?}",
+                chromium.display()
+            )
+        })?
+        .with_context(|| format!("Unable to start {}", chromium.display()))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} failed to print the page with {}: {}",
+            chromium.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    tokio::fs::read(&pdf_path).await.with_context(|| {
+        format!(
+            "{} did not produce a PDF file: {}",
+            chromium.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_url() {
+        assert_eq!(
+            page_url("https://example.com", "/invoice.sql"),
+            "https://example.com/invoice.sql"
+        );
+        assert_eq!(
+            page_url("https://example.com/app/", "/app/invoice.sql"),
+            "https://example.com/app/invoice.sql"
+        );
+        assert_eq!(
+            page_url("http://127.0.0.1:8080", "/a%20b.sql"),
+            "http://127.0.0.1:8080/a%20b.sql"
+        );
+    }
+
+    #[test]
+    fn test_printed_pages_are_removed() {
+        let pages = PrintedPages::default();
+        let page = || PrintedPage {
+            html: "<p>hi</p>".into(),
+            content_security_policy: None,
+        };
+        let guard = pages.add(page());
+        let id = guard.id.clone();
+        assert_eq!(pages.take(&id).unwrap().html, "<p>hi</p>");
+        assert!(pages.take(&id).is_none(), "pages are only loaded once");
+        drop(guard);
+        let guard = pages.add(page());
+        let id = guard.id.clone();
+        drop(guard);
+        assert!(
+            pages.take(&id).is_none(),
+            "pages are removed after printing"
+        );
+    }
+
+    #[test]
+    fn test_with_base_url() {
+        assert_eq!(
+            with_base_url(
+                "<!DOCTYPE html><html><head><title>x</title>",
+                "http://localhost:8080/invoice.sql"
+            ),
+            r#"<!DOCTYPE html><html><head><base href="http://localhost:8080/invoice.sql"><title>x</title>"#
+        );
+        assert_eq!(
+            with_base_url("<p>hi</p>", "http://a/\"b"),
+            r#"<base href="http://a/%22b"><p>hi</p>"#
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Forwarding of the requests to the paths of `proxy_routes` to another server,
+//! so that a site can serve an existing API or asset server on the same origin.
+//!
+//! Request and response bodies are streamed, without being loaded in memory.
+//! Hop-by-hop headers are not forwarded, and the `X-Forwarded-*` headers tell the upstream server
+//! where the request comes from. The credentials of the clients, in their `Cookie` and `Authorization` headers,
+//! and the cookies set by the upstream server, are only forwarded when the route has `forward_credentials`.
+
+use super::database::make_http_client;
+use super::http_request_info::request_origin;
+use super::ErrorWithStatus;
+use crate::AppState;
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpMessage, HttpResponse};
+use anyhow::Context;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Headers that carry the credentials of the clients of this site, which the upstream server
+/// must not receive, nor set, unless the route allows it
+const REQUEST_CREDENTIAL_HEADERS: [HeaderName; 2] = [header::COOKIE, header::AUTHORIZATION];
+const RESPONSE_CREDENTIAL_HEADERS: [HeaderName; 1] = [header::SET_COOKIE];
+
+/// Headers that only apply to a single connection
+const HOP_BY_HOP_HEADERS: [HeaderName; 8] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Forwards the request to `url`, with the settings of the proxy route `route`
+pub async fn forward(
+    service_request: &mut ServiceRequest,
+    route: &str,
+    url: &str,
+) -> anyhow::Result<HttpResponse> {
+    let app_state: web::Data<AppState> = service_request
+        .app_data::<web::Data<AppState>>()
+        .expect("app_state")
+        .clone();
+    let config = app_state.config();
+    let proxy_route = config
+        .proxy_routes
+        .get(route)
+        .with_context(|| format!("The proxy route {route:?} does not exist anymore"))?;
+    log::debug!("Forwarding {} to {url}", service_request.path());
+
+    let mut upstream_request = http_client(&config)?
+        .request(service_request.method().clone(), url)
+        .timeout(Duration::from_secs_f64(proxy_route.timeout_seconds))
+        .no_decompress();
+    let headers = upstream_request.headers_mut();
+    let credentials: &[HeaderName] = if proxy_route.forward_credentials {
+        &[]
+    } else {
+        &REQUEST_CREDENTIAL_HEADERS
+    };
+    for (name, value) in forwarded_headers(service_request.headers(), credentials) {
+        if name != header::HOST && name != header::CONTENT_LENGTH {
+            headers.append(name.clone(), value.clone());
+        }
+    }
+    add_forwarding_headers(service_request, &config, headers);
+    rewrite_headers(headers, &proxy_route.request_headers)?;
+
+    let has_body = service_request
+        .headers()
+        .contains_key(header::CONTENT_LENGTH)
+        || service_request
+            .headers()
+            .contains_key(header::TRANSFER_ENCODING);
+    let sent = if has_body {
+        upstream_request
+            .send_stream(service_request.take_payload())
+            .await
+    } else {
+        upstream_request.send().await
+    };
+    let upstream_response = sent.map_err(|e| {
+        anyhow::Error::new(ErrorWithStatus {
+            status: StatusCode::BAD_GATEWAY,
+        })
+        .context(format!("Unable to forward the request to {url}: {e}"))
+    })?;
+
+    let mut response = HttpResponse::build(upstream_response.status());
+    let credentials: &[HeaderName] = if proxy_route.forward_credentials {
+        &[]
+    } else {
+        &RESPONSE_CREDENTIAL_HEADERS
+    };
+    for (name, value) in forwarded_headers(upstream_response.headers(), credentials) {
+        if name != header::CONTENT_LENGTH {
+            response.append_header((name.clone(), value.clone()));
+        }
+    }
+    let mut response = response.streaming(upstream_response);
+    rewrite_headers(response.headers_mut(), &proxy_route.response_headers)?;
+    Ok(response)
+}
+
+thread_local! {
+    /// Clients cannot be shared between threads, so each worker thread keeps its own,
+    /// and reuses its connections to the upstream servers
+    static HTTP_CLIENT: OnceCell<awc::Client> = const { OnceCell::new() };
+}
+
+fn http_client(config: &crate::app_config::AppConfig) -> anyhow::Result<awc::Client> {
+    HTTP_CLIENT.with(|client| {
+        if let Some(client) = client.get() {
+            return Ok(client.clone());
+        }
+        let new_client = make_http_client(config)?;
+        Ok(client.get_or_init(|| new_client).clone())
+    })
+}
+
+/// The headers that are forwarded: all of them except the hop-by-hop headers,
+/// the ones listed in the `Connection` header, and the given credential headers
+fn forwarded_headers<'a>(
+    headers: &'a HeaderMap,
+    credentials: &'a [HeaderName],
+) -> impl Iterator<Item = (&'a HeaderName, &'a HeaderValue)> {
+    let connection_headers: Vec<&str> = headers
+        .get_all(header::CONNECTION)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    headers.iter().filter(move |(name, _)| {
+        !HOP_BY_HOP_HEADERS.contains(name)
+            && !credentials.contains(name)
+            && !connection_headers
+                .iter()
+                .any(|listed| listed.eq_ignore_ascii_case(name.as_str()))
+    })
+}
+
+fn add_forwarding_headers(
+    service_request: &ServiceRequest,
+    config: &crate::app_config::AppConfig,
+    headers: &mut HeaderMap,
+) {
+    let origin = request_origin(service_request.request(), config);
+    if let Some(ip) = origin.client_ip {
+        let forwarded_for = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+            Some(previous) => format!("{previous}, {ip}"),
+            None => ip.to_string(),
+        };
+        if let Ok(value) = HeaderValue::try_from(forwarded_for) {
+            headers.insert(HeaderName::from_static("x-forwarded-for"), value);
+        }
+    }
+    if let Ok(value) = HeaderValue::try_from(origin.protocol) {
+        headers.insert(HeaderName::from_static("x-forwarded-proto"), value);
+    }
+    if let Some(value) = origin.host.and_then(|h| HeaderValue::try_from(h).ok()) {
+        headers.insert(HeaderName::from_static("x-forwarded-host"), value);
+    }
+}
+
+/// Sets the headers with a value, and removes the ones without
+fn rewrite_headers(
+    headers: &mut HeaderMap,
+    rewrites: &HashMap<String, Option<String>>,
+) -> anyhow::Result<()> {
+    for (name, value) in rewrites {
+        let name = HeaderName::try_from(name.as_str())
+            .with_context(|| format!("Invalid header name in proxy_routes: {name:?}"))?;
+        match value {
+            Some(value) => {
+                let value = HeaderValue::try_from(value.as_str())
+                    .with_context(|| format!("Invalid value for the header {name}: {value:?}"))?;
+                headers.insert(name, value);
+            }
+            None => {
+                headers.remove(name);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forwarded_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("session=1"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic eA=="),
+        );
+        headers.insert(
+            header::CONNECTION,
+            HeaderValue::from_static("close, X-Secret"),
+        );
+        headers.insert(header::TE, HeaderValue::from_static("trailers"));
+        headers.insert(
+            HeaderName::from_static("x-secret"),
+            HeaderValue::from_static("1"),
+        );
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        let names = |credentials| {
+            forwarded_headers(&headers, credentials)
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&REQUEST_CREDENTIAL_HEADERS), ["accept"]);
+        let mut with_credentials = names(&[]);
+        with_credentials.sort_unstable();
+        assert_eq!(with_credentials, ["accept", "authorization", "cookie"]);
+    }
+
+    #[test]
+    fn test_rewrite_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("session=1"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        let rewrites = HashMap::from([
+            ("cookie".to_string(), None),
+            ("X-Api-Key".to_string(), Some("secret".to_string())),
+        ]);
+        rewrite_headers(&mut headers, &rewrites).unwrap();
+        assert!(headers.get(header::COOKIE).is_none());
+        assert_eq!(headers.get("x-api-key").unwrap(), "secret");
+        assert_eq!(headers.get(header::ACCEPT).unwrap(), "*/*");
+    }
+}
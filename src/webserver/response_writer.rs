@@ -2,36 +2,90 @@ use actix_web::web::Bytes;
 use std::io::Write;
 use std::mem;
 use std::pin::Pin;
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use std::task::{ready, Poll};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_util::sync::PollSemaphore;
+
+/// Limits the total size of the messages that were sent to the client channel,
+/// but not yet taken out of it by the http response stream.
+/// Sending a message consumes one permit per byte, and the permits are released with [`PendingBytesLimit::release`]
+/// when the message leaves the channel.
+#[derive(Clone)]
+pub struct PendingBytesLimit {
+    semaphore: Arc<Semaphore>,
+    max_bytes: u32,
+}
+
+impl PendingBytesLimit {
+    #[must_use]
+    pub fn new(max_bytes: usize) -> Self {
+        let max_bytes = u32::try_from(max_bytes).unwrap_or(u32::MAX).max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_bytes as usize)),
+            max_bytes,
+        }
+    }
+
+    /// A message larger than the limit waits for the channel to be empty, and is then sent alone
+    fn permits_for(&self, len: usize) -> u32 {
+        u32::try_from(len).unwrap_or(u32::MAX).min(self.max_bytes)
+    }
+
+    async fn acquire(&self, len: usize) -> std::io::Result<()> {
+        let permit = self
+            .semaphore
+            .acquire_many(self.permits_for(len))
+            .await
+            .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+        permit.forget();
+        Ok(())
+    }
+
+    fn try_acquire(&self, len: usize) -> std::io::Result<()> {
+        let permit = self.semaphore.try_acquire_many(self.permits_for(len)).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                format!("{e}: The server cannot store more than {} bytes of pending data in memory. Try again later or increase max_pending_response_bytes in the configuration.", self.max_bytes),
+            )
+        })?;
+        permit.forget();
+        Ok(())
+    }
+
+    /// Called when a message of the given size has been taken out of the channel
+    pub fn release(&self, len: usize) {
+        self.semaphore.add_permits(self.permits_for(len) as usize);
+    }
+}
 
 /// The response writer is a buffered async writer that sends data to the client.
 /// Writing to it just appends to an in-memory buffer, which is flushed to the client asynchronously
 /// when `async_flush()` is called.
 /// This allows streaming data to the client without blocking, and has built-in back-pressure:
-/// if the client cannot keep up with the data, `async_flush()` will fill the sending queue,
-/// then block until the client has consumed some data.
+/// if the client cannot keep up with the data, `async_flush()` will fill the sending queue
+/// (up to a number of messages and a number of bytes), then block until the client has consumed some data.
 #[derive(Clone)]
 pub struct ResponseWriter {
     buffer: Vec<u8>,
     response_bytes: mpsc::Sender<Bytes>,
+    pending_bytes: PendingBytesLimit,
 }
 
 impl ResponseWriter {
     #[must_use]
-    pub fn new(response_bytes: mpsc::Sender<Bytes>) -> Self {
+    pub fn new(response_bytes: mpsc::Sender<Bytes>, pending_bytes: PendingBytesLimit) -> Self {
         Self {
             response_bytes,
             buffer: Vec::new(),
+            pending_bytes,
         }
     }
 
-    pub async fn close_with_error(&mut self, mut msg: String) {
+    pub async fn close_with_error(&mut self, msg: String) {
         if !self.response_bytes.is_closed() {
+            self.buffer.extend_from_slice(msg.as_bytes());
             if let Err(e) = self.async_flush().await {
-                use std::fmt::Write;
-                write!(&mut msg, "Unable to flush data: {e}").unwrap();
-            }
-            if let Err(e) = self.response_bytes.send(msg.into()).await {
                 log::error!("Unable to send error back to client: {e}");
             }
         }
@@ -45,6 +99,7 @@ impl ResponseWriter {
             "Flushing data to client: {}",
             String::from_utf8_lossy(&self.buffer)
         );
+        self.pending_bytes.acquire(self.buffer.len()).await?;
         let sender = self
             .response_bytes
             .reserve()
@@ -70,6 +125,7 @@ impl Write for ResponseWriter {
             "Flushing data to client: {}",
             String::from_utf8_lossy(&self.buffer)
         );
+        self.pending_bytes.try_acquire(self.buffer.len())?;
         self.response_bytes
             .try_send(mem::take(&mut self.buffer).into())
             .map_err(|e|
@@ -84,6 +140,9 @@ impl Write for ResponseWriter {
 #[allow(clippy::module_name_repetitions)]
 pub struct AsyncResponseWriter {
     poll_sender: tokio_util::sync::PollSender<Bytes>,
+    poll_pending_bytes: PollSemaphore,
+    /// Number of bytes at the start of the buffer for which a [`PendingBytesLimit`] permit was acquired
+    acquired_len: Option<usize>,
     writer: ResponseWriter,
 }
 
@@ -93,6 +152,8 @@ impl AsyncResponseWriter {
         let sender = writer.response_bytes.clone();
         Self {
             poll_sender: tokio_util::sync::PollSender::new(sender),
+            poll_pending_bytes: PollSemaphore::new(Arc::clone(&writer.pending_bytes.semaphore)),
+            acquired_len: None,
             writer,
         }
     }
@@ -108,34 +169,50 @@ impl tokio::io::AsyncWrite for AsyncResponseWriter {
         mut self: Pin<&mut Self>,
         _cx: &mut std::task::Context<'_>,
         buf: &[u8],
-    ) -> std::task::Poll<std::io::Result<usize>> {
-        std::task::Poll::Ready(self.as_mut().writer.write(buf))
+    ) -> Poll<std::io::Result<usize>> {
+        Poll::Ready(self.as_mut().writer.write(buf))
     }
 
     fn poll_flush(
         self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<std::io::Result<()>> {
+    ) -> Poll<std::io::Result<()>> {
         let Self {
             poll_sender,
+            poll_pending_bytes,
+            acquired_len,
             writer,
         } = self.get_mut();
-        match poll_sender.poll_reserve(cx) {
-            std::task::Poll::Ready(Ok(())) => {
-                let res = poll_sender.send_item(std::mem::take(&mut writer.buffer).into());
-                std::task::Poll::Ready(res.map_err(|_| std::io::ErrorKind::BrokenPipe.into()))
+        while !writer.buffer.is_empty() {
+            let len = if let Some(len) = *acquired_len {
+                len
+            } else {
+                let len = writer.buffer.len();
+                let permits = writer.pending_bytes.permits_for(len);
+                let Some(permit) = ready!(poll_pending_bytes.poll_acquire_many(cx, permits)) else {
+                    return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+                };
+                permit.forget();
+                *acquired_len = Some(len);
+                len
+            };
+            if ready!(poll_sender.poll_reserve(cx)).is_err() {
+                return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
             }
-            std::task::Poll::Pending => std::task::Poll::Pending,
-            std::task::Poll::Ready(Err(_e)) => {
-                std::task::Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()))
+            let rest = writer.buffer.split_off(len);
+            let bytes = mem::replace(&mut writer.buffer, rest);
+            *acquired_len = None;
+            if poll_sender.send_item(bytes.into()).is_err() {
+                return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
             }
         }
+        Poll::Ready(Ok(()))
     }
 
     fn poll_shutdown(
         self: Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), std::io::Error>> {
+    ) -> Poll<Result<(), std::io::Error>> {
         self.poll_flush(cx)
     }
 }
@@ -147,3 +224,37 @@ impl Drop for ResponseWriter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_pending_bytes_limit() {
+        let (sender, mut receiver) = mpsc::channel(100);
+        let limit = PendingBytesLimit::new(10);
+        let mut writer = ResponseWriter::new(sender, limit.clone());
+        writer.write_all(b"12345678").unwrap();
+        writer.async_flush().await.unwrap();
+        writer.write_all(b"12345678").unwrap();
+        let blocked = tokio::time::timeout(Duration::from_millis(50), writer.async_flush()).await;
+        assert!(
+            blocked.is_err(),
+            "the second message should wait for the first one"
+        );
+        let first = receiver.recv().await.unwrap();
+        limit.release(first.len());
+        writer.async_flush().await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "12345678");
+    }
+
+    #[actix_web::test]
+    async fn test_message_larger_than_limit() {
+        let (sender, mut receiver) = mpsc::channel(100);
+        let mut writer = ResponseWriter::new(sender, PendingBytesLimit::new(4));
+        writer.write_all(b"larger than the limit").unwrap();
+        writer.async_flush().await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap(), "larger than the limit");
+    }
+}
@@ -1,15 +1,19 @@
+use crate::app_config::UrlStyle;
 use crate::filesystem::FileSystem;
 use crate::webserver::database::ParsedSqlFile;
 use crate::{file_cache::FileCache, AppState};
 use awc::http::uri::PathAndQuery;
 use log::debug;
 use percent_encoding;
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
-use RoutingAction::{CustomNotFound, Execute, NotFound, Redirect, Serve};
+use RoutingAction::{CustomNotFound, Execute, NotFound, Proxy, Redirect, Serve};
 
 const INDEX: &str = "index.sql";
 const NOT_FOUND: &str = "404.sql";
+const AUTHORIZE: &str = "authorize.sql";
 const SQL_EXTENSION: &str = "sql";
+const DOT_SQL: &str = ".sql";
 const FORWARD_SLASH: &str = "/";
 
 #[derive(Debug, PartialEq)]
@@ -19,6 +23,11 @@ pub enum RoutingAction {
     NotFound,
     Redirect(String),
     Serve(PathBuf),
+    /// Forward the request to the given URL, for the route of `proxy_routes` with the given path
+    Proxy {
+        route: String,
+        url: String,
+    },
 }
 
 #[expect(async_fn_in_trait)]
@@ -28,6 +37,9 @@ pub trait FileStore {
 
 pub trait RoutingConfig {
     fn prefix(&self) -> &str;
+    fn url_style(&self) -> UrlStyle;
+    /// The path and the upstream URL of the proxy route that matches the given path, relative to the site prefix
+    fn proxy_upstream(&self, path: &str) -> Option<(&str, &str)>;
 }
 
 pub(crate) struct AppFileStore<'a> {
@@ -69,20 +81,62 @@ where
     T: FileStore,
     C: RoutingConfig,
 {
+    if let Some(action) = find_proxy_route(path_and_query, config) {
+        debug!("Route: [{}] -> {:?}", path_and_query, action);
+        return Ok(action);
+    }
+    let url_style = config.url_style();
     let result = match check_path(path_and_query, config) {
-        Ok(path) => match path.extension() {
-            None => calculate_route_without_extension(path_and_query, path, store).await?,
-            Some(extension) => {
-                let ext = extension.to_str().unwrap_or_default();
-                find_file_or_not_found(&path, ext, store).await?
-            }
-        },
+        Ok(path) => {
+            let action = match path.extension() {
+                None => {
+                    calculate_route_without_extension(path_and_query, path, store, url_style)
+                        .await?
+                }
+                Some(extension) => {
+                    let ext = extension.to_str().unwrap_or_default();
+                    find_file_or_not_found(&path, ext, store).await?
+                }
+            };
+            redirect_to_canonical_path(path_and_query, action, url_style)
+        }
         Err(action) => action,
     };
     debug!("Route: [{}] -> {:?}", path_and_query, result);
     Ok(result)
 }
 
+fn find_proxy_route<C: RoutingConfig>(
+    path_and_query: &PathAndQuery,
+    config: &C,
+) -> Option<RoutingAction> {
+    let path = path_and_query.path().strip_prefix(config.prefix())?;
+    let path = format!("{FORWARD_SLASH}{path}");
+    let (route, upstream) = config.proxy_upstream(&path)?;
+    if has_dot_segment(&path[route.len()..]) {
+        log::debug!("Refusing to proxy {path:?}: it contains a dot segment");
+        return Some(NotFound);
+    }
+    let mut url = format!("{upstream}{}", &path[route.len()..]);
+    if let Some(query) = path_and_query.query() {
+        url.push('?');
+        url.push_str(query);
+    }
+    Some(Proxy {
+        route: route.to_string(),
+        url,
+    })
+}
+
+/// Whether the path contains a `.` or `..` segment, once percent-decoded,
+/// that the upstream server could use to escape the proxied route.
+fn has_dot_segment(path: &str) -> bool {
+    percent_encoding::percent_decode_str(path)
+        .decode_utf8_lossy()
+        .split(['/', '\\'])
+        .any(|segment| segment == "." || segment == "..")
+}
+
 fn check_path<C>(path_and_query: &PathAndQuery, config: &C) -> Result<PathBuf, RoutingAction>
 where
     C: RoutingConfig,
@@ -107,17 +161,69 @@ where
     }
 }
 
+/// The preferred form of the URL path of a page, according to the configured [`UrlStyle`]
+#[must_use]
+pub fn canonical_path(path: &str, url_style: UrlStyle) -> Cow<'_, str> {
+    match url_style {
+        UrlStyle::Any => Cow::Borrowed(path),
+        UrlStyle::WithoutExtension => {
+            if let Some(directory) = path.strip_suffix(INDEX) {
+                if directory.ends_with(FORWARD_SLASH) {
+                    return Cow::Borrowed(directory);
+                }
+            }
+            Cow::Borrowed(path.strip_suffix(DOT_SQL).unwrap_or(path))
+        }
+        UrlStyle::WithExtension => {
+            let file_name = path.rsplit(FORWARD_SLASH).next().unwrap_or_default();
+            if file_name.is_empty() || file_name.contains('.') {
+                Cow::Borrowed(path)
+            } else {
+                Cow::Owned(format!("{path}{DOT_SQL}"))
+            }
+        }
+    }
+}
+
+fn redirect_to_canonical_path(
+    path_and_query: &PathAndQuery,
+    action: RoutingAction,
+    url_style: UrlStyle,
+) -> RoutingAction {
+    if !matches!(action, Execute(_)) {
+        return action;
+    }
+    let path = path_and_query.path();
+    match canonical_path(path, url_style) {
+        Cow::Borrowed(canonical) if canonical == path => action,
+        canonical => Redirect(replace_path(path_and_query, &canonical)),
+    }
+}
+
 async fn calculate_route_without_extension<T>(
     path_and_query: &PathAndQuery,
     mut path: PathBuf,
     store: &T,
+    url_style: UrlStyle,
 ) -> anyhow::Result<RoutingAction>
 where
     T: FileStore,
 {
     if path_and_query.path().ends_with(FORWARD_SLASH) {
+        let page = path.with_extension(SQL_EXTENSION);
         path.push(INDEX);
-        find_file_or_not_found(&path, SQL_EXTENSION, store).await
+        if let Some(action) = find_file(&path, SQL_EXTENSION, store).await? {
+            return Ok(action);
+        }
+        // `/page/` redirects to `/page` when there is a `page.sql` file but no `page/index.sql`
+        if url_style == UrlStyle::WithoutExtension
+            && page.file_name().is_some()
+            && store.contains(&page).await?
+        {
+            let without_slash = path_and_query.path().trim_end_matches(FORWARD_SLASH);
+            return Ok(Redirect(replace_path(path_and_query, without_slash)));
+        }
+        find_not_found(&path, store).await
     } else {
         let path_with_ext = path.with_extension(SQL_EXTENSION);
         match find_file(&path_with_ext, SQL_EXTENSION, store).await? {
@@ -149,6 +255,12 @@ async fn find_file<T>(
 where
     T: FileStore,
 {
+    // Authorization files are executed before the pages of their directory, never on their own.
+    // The comparison ignores case, since file systems that ignore it would serve `Authorize.sql`.
+    let file_name = path.file_name().and_then(|name| name.to_str());
+    if file_name.is_some_and(|name| name.eq_ignore_ascii_case(AUTHORIZE)) {
+        return Ok(None);
+    }
     if store.contains(path).await? {
         Ok(Some(if extension == SQL_EXTENSION {
             Execute(path.to_path_buf())
@@ -176,6 +288,30 @@ where
     Ok(NotFound)
 }
 
+/// The `authorize.sql` files that control the access to the given page,
+/// from the root of the site to the directory of the page.
+pub async fn find_authorization_files<T>(page: &Path, store: &T) -> anyhow::Result<Vec<PathBuf>>
+where
+    T: FileStore,
+{
+    let mut files = Vec::new();
+    for directory in page.ancestors().skip(1) {
+        let target = directory.join(AUTHORIZE);
+        if store.contains(&target).await? {
+            files.push(target);
+        }
+    }
+    files.reverse();
+    Ok(files)
+}
+
+fn replace_path(path_and_query: &PathAndQuery, new_path: &str) -> String {
+    match path_and_query.query() {
+        Some(query) => format!("{new_path}?{query}"),
+        None => new_path.to_string(),
+    }
+}
+
 fn append_to_path(path_and_query: &PathAndQuery, append: &str) -> String {
     let mut full_uri = path_and_query.to_string();
     full_uri.insert_str(path_and_query.path().len(), append);
@@ -186,6 +322,7 @@ fn append_to_path(path_and_query: &PathAndQuery, append: &str) -> String {
 mod tests {
     use super::RoutingAction::{CustomNotFound, Execute, NotFound, Redirect, Serve};
     use super::{calculate_route, FileStore, RoutingAction, RoutingConfig};
+    use crate::app_config::UrlStyle;
     use awc::http::uri::PathAndQuery;
     use std::default::Default as StdDefault;
     use std::path::{Path, PathBuf};
@@ -363,6 +500,44 @@ mod tests {
         }
     }
 
+    mod authorization {
+        use super::super::find_authorization_files;
+        use super::StoreConfig::File;
+        use super::{custom_not_found, do_route, Store};
+        use std::path::{Path, PathBuf};
+
+        #[tokio::test]
+        async fn authorization_file_is_not_executed_directly() {
+            let actual = do_route("/admin/authorize.sql", File("admin/authorize.sql"), None).await;
+            let expected = custom_not_found("404.sql");
+
+            assert_eq!(expected, actual);
+        }
+
+        #[tokio::test]
+        async fn authorization_file_name_ignores_case() {
+            let actual = do_route("/admin/Authorize.SQL", File("admin/Authorize.SQL"), None).await;
+            let expected = custom_not_found("404.sql");
+
+            assert_eq!(expected, actual);
+        }
+
+        #[tokio::test]
+        async fn authorization_files_from_root_to_page_directory() {
+            let mut store = Store::new("authorize.sql");
+            store.contents.push("admin/users/authorize.sql".to_string());
+            let actual = find_authorization_files(Path::new("admin/users/edit.sql"), &store)
+                .await
+                .unwrap();
+            let expected = [
+                PathBuf::from("authorize.sql"),
+                PathBuf::from("admin/users/authorize.sql"),
+            ];
+
+            assert_eq!(expected.as_slice(), actual);
+        }
+    }
+
     mod asset {
         use super::StoreConfig::File;
         use super::{do_route, serve};
@@ -439,6 +614,205 @@ mod tests {
         }
     }
 
+    mod url_style {
+        use super::super::canonical_path;
+        use super::StoreConfig::{Default, File};
+        use super::{do_route_with_style, execute, redirect};
+        use crate::app_config::UrlStyle::{Any, WithExtension, WithoutExtension};
+
+        #[tokio::test]
+        async fn any_style_serves_both_forms() {
+            let with_ext = do_route_with_style("/path.sql", File("path.sql"), Any).await;
+            let without_ext = do_route_with_style("/path", File("path.sql"), Any).await;
+
+            assert_eq!(execute("path.sql"), with_ext);
+            assert_eq!(execute("path.sql"), without_ext);
+        }
+
+        #[tokio::test]
+        async fn without_extension_redirects_sql_files() {
+            let actual =
+                do_route_with_style("/path.sql?x=1", File("path.sql"), WithoutExtension).await;
+
+            assert_eq!(redirect("/path?x=1"), actual);
+        }
+
+        #[tokio::test]
+        async fn without_extension_redirects_index_to_directory() {
+            let actual = do_route_with_style(
+                "/folder/index.sql",
+                File("folder/index.sql"),
+                WithoutExtension,
+            )
+            .await;
+
+            assert_eq!(redirect("/folder/"), actual);
+        }
+
+        #[tokio::test]
+        async fn without_extension_executes_canonical_path() {
+            let actual = do_route_with_style("/path", File("path.sql"), WithoutExtension).await;
+
+            assert_eq!(execute("path.sql"), actual);
+        }
+
+        #[tokio::test]
+        async fn without_extension_removes_trailing_slash_of_pages() {
+            let actual = do_route_with_style("/path/", File("path.sql"), WithoutExtension).await;
+
+            assert_eq!(redirect("/path"), actual);
+        }
+
+        #[tokio::test]
+        async fn without_extension_keeps_unknown_files_not_found() {
+            let actual = do_route_with_style("/unknown.sql", Default, WithoutExtension).await;
+
+            assert_eq!(super::custom_not_found("404.sql"), actual);
+        }
+
+        #[tokio::test]
+        async fn with_extension_redirects_to_sql_file() {
+            let actual = do_route_with_style("/path?x=1", File("path.sql"), WithExtension).await;
+
+            assert_eq!(redirect("/path.sql?x=1"), actual);
+        }
+
+        #[tokio::test]
+        async fn with_extension_keeps_directories() {
+            let actual = do_route_with_style("/", Default, WithExtension).await;
+
+            assert_eq!(execute("index.sql"), actual);
+        }
+
+        #[test]
+        fn canonical_paths() {
+            assert_eq!(canonical_path("/a/b.sql", WithoutExtension), "/a/b");
+            assert_eq!(canonical_path("/a/index.sql", WithoutExtension), "/a/");
+            assert_eq!(canonical_path("/index.sql", WithoutExtension), "/");
+            assert_eq!(canonical_path("/a/b", WithExtension), "/a/b.sql");
+            assert_eq!(canonical_path("/a/", WithExtension), "/a/");
+            assert_eq!(canonical_path("/a/b", Any), "/a/b");
+        }
+    }
+
+    mod proxy {
+        use super::{calculate_route, default_not_found, Config, RoutingAction, Store};
+        use awc::http::uri::PathAndQuery;
+        use std::str::FromStr;
+
+        async fn do_proxy_route(path: &str, prefix: &str) -> RoutingAction {
+            let config = Config {
+                proxy_routes: vec![("/api/", "http://localhost:8080/v1/")],
+                ..Config::new(prefix)
+            };
+            let store = Store::empty();
+            calculate_route(&PathAndQuery::from_str(path).unwrap(), &store, &config)
+                .await
+                .unwrap()
+        }
+
+        fn proxy(url: &str) -> RoutingAction {
+            RoutingAction::Proxy {
+                route: "/api/".to_string(),
+                url: url.to_string(),
+            }
+        }
+
+        #[tokio::test]
+        async fn forwards_path_and_query() {
+            let actual = do_proxy_route("/api/users/1?x=%20y", "/").await;
+
+            assert_eq!(proxy("http://localhost:8080/v1/users/1?x=%20y"), actual);
+        }
+
+        #[tokio::test]
+        async fn forwards_path_after_site_prefix() {
+            let actual = do_proxy_route("/prefix/api/users", "/prefix/").await;
+
+            assert_eq!(proxy("http://localhost:8080/v1/users"), actual);
+        }
+
+        #[tokio::test]
+        async fn other_paths_are_not_forwarded() {
+            let actual = do_proxy_route("/api.txt", "/").await;
+
+            assert_eq!(default_not_found(), actual);
+        }
+
+        #[tokio::test]
+        async fn routes_end_at_a_slash() {
+            let config = Config {
+                proxy_routes: vec![("/api", "http://localhost:8080/v1")],
+                ..Config::new("/")
+            };
+            let route = |path| {
+                let config = &config;
+                async move {
+                    let path = PathAndQuery::from_str(path).unwrap();
+                    calculate_route(&path, &Store::empty(), config)
+                        .await
+                        .unwrap()
+                }
+            };
+            let expected = |url: &str| RoutingAction::Proxy {
+                route: "/api".to_string(),
+                url: url.to_string(),
+            };
+            assert_eq!(route("/api").await, expected("http://localhost:8080/v1"));
+            assert_eq!(
+                route("/api/users").await,
+                expected("http://localhost:8080/v1/users")
+            );
+            assert!(
+                !matches!(route("/apiary").await, RoutingAction::Proxy { .. }),
+                "/apiary is not in the /api route"
+            );
+        }
+
+        #[tokio::test]
+        async fn dot_segments_are_not_forwarded() {
+            for path in [
+                "/api/../admin",
+                "/api/./users",
+                "/api/users/..",
+                "/api/%2e%2e/admin",
+                "/api/%2E./admin",
+                "/api/..%2Fadmin",
+                "/api/..%5Cadmin",
+            ] {
+                let actual = do_proxy_route(path, "/").await;
+
+                assert_eq!(default_not_found(), actual, "{path}");
+            }
+        }
+
+        #[tokio::test]
+        async fn dots_inside_segments_are_forwarded() {
+            let actual = do_proxy_route("/api/files/a..b/.hidden", "/").await;
+
+            assert_eq!(proxy("http://localhost:8080/v1/files/a..b/.hidden"), actual);
+        }
+    }
+
+    async fn do_route_with_style(
+        path: &str,
+        config: StoreConfig,
+        url_style: UrlStyle,
+    ) -> RoutingAction {
+        let store = match config {
+            Default => Store::with_default_contents(),
+            Empty => Store::empty(),
+            File(file) => Store::new(file),
+        };
+        let config = Config {
+            url_style,
+            ..Config::default()
+        };
+        calculate_route(&PathAndQuery::from_str(path).unwrap(), &store, &config)
+            .await
+            .unwrap()
+    }
+
     async fn do_route(path: &str, config: StoreConfig, prefix: Option<&str>) -> RoutingAction {
         let store = match config {
             Default => Store::with_default_contents(),
@@ -522,12 +896,16 @@ mod tests {
 
     struct Config {
         prefix: String,
+        url_style: UrlStyle,
+        proxy_routes: Vec<(&'static str, &'static str)>,
     }
 
     impl Config {
         fn new(prefix: &str) -> Self {
             Self {
                 prefix: prefix.to_string(),
+                url_style: UrlStyle::Any,
+                proxy_routes: vec![],
             }
         }
     }
@@ -535,6 +913,17 @@ mod tests {
         fn prefix(&self) -> &str {
             &self.prefix
         }
+
+        fn url_style(&self) -> UrlStyle {
+            self.url_style
+        }
+
+        fn proxy_upstream(&self, path: &str) -> Option<(&str, &str)> {
+            self.proxy_routes
+                .iter()
+                .find(|(prefix, _)| crate::app_config::is_in_route(path, prefix))
+                .copied()
+        }
     }
 
     impl StdDefault for Config {
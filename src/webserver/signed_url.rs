@@ -0,0 +1,134 @@
+//! Time-limited links that cannot be modified, created by `sqlpage.sign_url`.
+//!
+//! A signed URL ends with two query parameters: `_sqlpage_expires`, a unix timestamp in seconds,
+//! and `_sqlpage_signature`, an HMAC-SHA256 of the path and query string that precede it,
+//! with the `token_signing_secret` of the configuration.
+//! Requests to signed URLs are verified before the page is executed, and rejected with a 403 status
+//! when the URL was modified or has expired. Pages check that they were opened from a valid signed link
+//! with `sqlpage.url_is_signed()`.
+
+use super::ErrorWithStatus;
+use actix_web::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Query parameter that contains the expiration date of a signed URL
+pub const EXPIRES_PARAMETER: &str = "_sqlpage_expires";
+/// Query parameter that contains the signature of a signed URL. It is always the last parameter.
+pub const SIGNATURE_PARAMETER: &str = "_sqlpage_signature";
+
+fn mac(secret: &str, signed_part: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(signed_part.as_bytes());
+    mac
+}
+
+/// Signs a path with an optional query string, such as `/invoice.sql?id=12`
+#[must_use]
+pub fn sign(secret: &str, path_and_query: &str, expires_at: i64) -> String {
+    let separator = if path_and_query.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    let signed_part = format!("{path_and_query}{separator}{EXPIRES_PARAMETER}={expires_at}");
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, &signed_part).finalize().into_bytes());
+    format!("{signed_part}&{SIGNATURE_PARAMETER}={signature}")
+}
+
+/// Verifies the signature of a request, when its URL has one.
+/// Returns whether the URL is signed, or an error when the URL was modified or has expired.
+pub fn check_request(
+    secret: Option<&str>,
+    path: &str,
+    query_string: &str,
+    now: i64,
+) -> anyhow::Result<bool> {
+    let Some((signed_query, signature)) =
+        query_string.rsplit_once(&format!("&{SIGNATURE_PARAMETER}="))
+    else {
+        return Ok(false);
+    };
+    let Some(secret) = secret else {
+        anyhow::bail!(
+            "Signed URLs require the token_signing_secret configuration option to be set"
+        );
+    };
+    if let Some(reason) = rejection(secret, &format!("{path}?{signed_query}"), signature, now) {
+        log::info!("Rejecting a request to a signed URL: {reason}");
+        return Err(anyhow::Error::new(ErrorWithStatus {
+            status: StatusCode::FORBIDDEN,
+        })
+        .context(format!("This link cannot be used, because {reason}.")));
+    }
+    Ok(true)
+}
+
+fn rejection(secret: &str, signed_part: &str, signature: &str, now: i64) -> Option<&'static str> {
+    let is_signed = URL_SAFE_NO_PAD
+        .decode(signature)
+        .is_ok_and(|signature| mac(secret, signed_part).verify_slice(&signature).is_ok());
+    if !is_signed {
+        return Some("it was modified");
+    }
+    let expires_at = signed_part
+        .rsplit_once(['?', '&'])
+        .and_then(|(_, last)| last.strip_prefix(EXPIRES_PARAMETER)?.strip_prefix('='))
+        .and_then(|expires_at| expires_at.parse::<i64>().ok());
+    match expires_at {
+        Some(expires_at) if expires_at > now => None,
+        Some(_) => Some("it has expired"),
+        None => Some("it is invalid"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "a secret that is long enough for the tests";
+
+    fn check(url: &str, now: i64) -> anyhow::Result<bool> {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        check_request(Some(SECRET), path, query, now)
+    }
+
+    #[test]
+    fn test_sign() {
+        let url = sign(SECRET, "/invoice.sql?id=12", 1000);
+        assert!(url.starts_with("/invoice.sql?id=12&_sqlpage_expires=1000&_sqlpage_signature="));
+        let url = sign(SECRET, "/unsubscribe.sql", 1000);
+        assert!(url.starts_with("/unsubscribe.sql?_sqlpage_expires=1000&_sqlpage_signature="));
+    }
+
+    #[test]
+    fn test_valid_url() {
+        let url = sign(SECRET, "/invoice.sql?id=12", 1000);
+        assert!(check(&url, 999).unwrap());
+        assert!(!check("/invoice.sql?id=12", 999).unwrap(), "unsigned");
+    }
+
+    #[test]
+    fn test_rejected_urls() {
+        let url = sign(SECRET, "/invoice.sql?id=12", 1000);
+        let rejected = |url: &str, now: i64| {
+            let err = check(url, now).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<ErrorWithStatus>(),
+                Some(&ErrorWithStatus {
+                    status: StatusCode::FORBIDDEN
+                })
+            );
+            err.to_string()
+        };
+        assert!(rejected(&url, 1000).contains("expired"));
+        assert!(rejected(&url.replace("id=12", "id=13"), 0).contains("modified"));
+        assert!(rejected(&url.replace("=1000", "=9999"), 0).contains("modified"));
+        assert!(rejected(&url.replace("/invoice", "/admin"), 0).contains("modified"));
+        assert!(rejected(&format!("{url}&id=13"), 0).contains("modified"));
+        assert!(check_request(None, "/invoice.sql", &url[13..], 0).is_err());
+    }
+}
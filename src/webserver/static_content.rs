@@ -42,6 +42,14 @@ pub fn tomselect_js() -> Resource {
     static_file_endpoint!("tomselect", "js", "application/javascript")
 }
 
+pub fn leaflet_js() -> Resource {
+    static_file_endpoint!("leaflet", "js", "application/javascript")
+}
+
+pub fn leaflet_css() -> Resource {
+    static_file_endpoint!("leaflet", "css", "text/css")
+}
+
 pub fn css() -> Resource {
     static_file_endpoint!("sqlpage", "css", "text/css")
 }
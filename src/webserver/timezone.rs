@@ -0,0 +1,107 @@
+//! Time zones of the people viewing the site.
+//!
+//! Timestamps are usually stored in UTC. To display them in the local time of the viewer,
+//! `sqlpage.js` stores the time zone of the browser in the [`TIMEZONE_COOKIE`] cookie,
+//! and the server uses it in `sqlpage.to_timezone` and in the `to_timezone` handlebars helper.
+//! Before the cookie is set (on the first page viewed), the `default_timezone` of the configuration is used.
+
+use crate::app_config::AppConfig;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+
+/// Name of the cookie that contains the IANA time zone of the viewer, such as `Europe/Paris`
+pub const TIMEZONE_COOKIE: &str = "sqlpage_timezone";
+
+/// The time zone in which pages are rendered when the viewer's time zone is unknown
+#[must_use]
+pub fn default_timezone(config: &AppConfig) -> Tz {
+    config.default_timezone.unwrap_or(Tz::UTC)
+}
+
+/// The time zone of the viewer, from the value of the [`TIMEZONE_COOKIE`] cookie
+#[must_use]
+pub fn viewer_timezone(cookie: Option<&str>, config: &AppConfig) -> Tz {
+    cookie
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_else(|| default_timezone(config))
+}
+
+/// Converts a timestamp to the given time zone, and returns it in the RFC 3339 format,
+/// or formatted with a [`chrono` format string](https://docs.rs/chrono/latest/chrono/format/strftime/index.html).
+/// Timestamps without a time zone are considered to be in UTC.
+/// Returns `None` if the value is not a timestamp.
+#[must_use]
+pub fn to_timezone(timestamp: &str, timezone: Tz, format: Option<&str>) -> Option<String> {
+    let timestamp = timestamp.trim();
+    let utc = if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp) {
+        datetime.naive_utc()
+    } else {
+        parse_naive(timestamp)?
+    };
+    let local = timezone.from_utc_datetime(&utc);
+    Some(match format {
+        Some(format) => {
+            let mut formatted = String::new();
+            std::fmt::Write::write_fmt(&mut formatted, format_args!("{}", local.format(format)))
+                .ok()?;
+            formatted
+        }
+        None => local.to_rfc3339(),
+    })
+}
+
+fn parse_naive(timestamp: &str) -> Option<NaiveDateTime> {
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(timestamp, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_config::tests::test_config;
+
+    #[test]
+    fn test_to_timezone() {
+        let paris = Tz::Europe__Paris;
+        assert_eq!(
+            to_timezone("2024-07-14 10:00:00", paris, None).as_deref(),
+            Some("2024-07-14T12:00:00+02:00")
+        );
+        assert_eq!(
+            to_timezone("2024-01-14T10:00:00.5Z", paris, None).as_deref(),
+            Some("2024-01-14T11:00:00.500+01:00")
+        );
+        assert_eq!(
+            to_timezone("2024-01-14T10:00:00-05:00", paris, Some("%d/%m/%Y %H:%M")).as_deref(),
+            Some("14/01/2024 16:00")
+        );
+        assert_eq!(to_timezone("yesterday", paris, None), None);
+        assert_eq!(
+            to_timezone("2024-01-14", paris, Some("%Y-%m-%d %Z %{")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_viewer_timezone() {
+        let mut config = test_config();
+        assert_eq!(
+            viewer_timezone(Some("Asia/Tokyo"), &config),
+            Tz::Asia__Tokyo
+        );
+        assert_eq!(viewer_timezone(Some("Mars/Olympus"), &config), Tz::UTC);
+        config.default_timezone = Some(Tz::America__New_York);
+        assert_eq!(viewer_timezone(None, &config), Tz::America__New_York);
+    }
+}
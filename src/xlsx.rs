@@ -0,0 +1,402 @@
+//! Streaming of query results into an Excel spreadsheet (`.xlsx`), for the `xlsx` component.
+//!
+//! An xlsx file is a zip archive containing XML files. The rows of the sheet are compressed
+//! and sent to the client as they are received from the database,
+//! and the small files that describe the workbook are written at the end of the archive.
+//! The zip archive uses data descriptors, so that it can be written without seeking back.
+//!
+//! Numbers and booleans become typed cells, and strings that look like dates
+//! (`2024-01-31` or `2024-01-31T12:00:00`) become dates formatted as such in Excel.
+//! The first row contains the column names, in bold, with an auto-filter.
+
+use crate::webserver::response_writer::ResponseWriter;
+use crate::xml::escape;
+use anyhow::{bail, Context};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde_json::Value as JsonValue;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::rc::Rc;
+use zip::write::{SimpleFileOptions, StreamWriter};
+use zip::{CompressionMethod, ZipWriter};
+
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+const DEFAULT_SHEET_NAME: &str = "Sheet1";
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+const MAX_ROWS: usize = 1_048_576;
+const MAX_COLUMNS: usize = 16_384;
+
+/// Indices of the cell formats defined in `xl/styles.xml`
+const STYLE_HEADER: u8 = 1;
+const STYLE_DATE: u8 = 2;
+const STYLE_DATETIME: u8 = 3;
+
+/// Bytes written by the zip writer, waiting to be sent to the client
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct XlsxBodyRenderer {
+    zip: ZipWriter<StreamWriter<SharedBuffer>>,
+    buffer: SharedBuffer,
+    writer: ResponseWriter,
+    columns: Vec<String>,
+    /// Number of rows written to the sheet, including the header row
+    rows: usize,
+    sheet_name: String,
+}
+
+impl XlsxBodyRenderer {
+    pub fn new(writer: ResponseWriter, sheet_name: Option<&str>) -> anyhow::Result<Self> {
+        let sheet_name = sheet_name.unwrap_or(DEFAULT_SHEET_NAME);
+        validate_sheet_name(sheet_name)?;
+        let buffer = SharedBuffer::default();
+        let mut zip = ZipWriter::new_stream(buffer.clone());
+        zip.start_file("xl/worksheets/sheet1.xml", file_options())?;
+        zip.write_all(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+                r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+                r#"<sheetViews><sheetView workbookViewId="0">"#,
+                r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#,
+                r#"</sheetView></sheetViews>"#,
+                r#"<sheetData>"#
+            )
+            .as_bytes(),
+        )?;
+        Ok(Self {
+            zip,
+            buffer,
+            writer,
+            columns: Vec::new(),
+            rows: 0,
+            sheet_name: sheet_name.to_string(),
+        })
+    }
+
+    pub fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let Some(obj) = data.as_object() else {
+            return Ok(());
+        };
+        if self.columns.is_empty() {
+            if obj.len() > MAX_COLUMNS {
+                bail!("An Excel sheet cannot contain more than {MAX_COLUMNS} columns");
+            }
+            self.columns = obj.keys().cloned().collect();
+            let mut header = String::new();
+            for (col, name) in self.columns.iter().enumerate() {
+                write_string_cell(
+                    &mut header,
+                    &cell_reference(col, 1),
+                    name,
+                    Some(STYLE_HEADER),
+                );
+            }
+            self.write_row(&header)?;
+        }
+        let mut cells = String::new();
+        let row = self.rows + 1;
+        for (col, name) in self.columns.iter().enumerate() {
+            if let Some(value) = obj.get(name) {
+                write_cell(&mut cells, &cell_reference(col, row), value);
+            }
+        }
+        self.write_row(&cells)
+    }
+
+    pub fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        let mut cells = String::new();
+        let reference = cell_reference(0, self.rows + 1);
+        write_string_cell(&mut cells, &reference, &error.to_string(), None);
+        self.write_row(&cells)
+    }
+
+    fn write_row(&mut self, cells: &str) -> anyhow::Result<()> {
+        if self.rows >= MAX_ROWS {
+            bail!("An Excel sheet cannot contain more than {MAX_ROWS} rows");
+        }
+        self.rows += 1;
+        write!(self.zip, r#"<row r="{}">{cells}</row>"#, self.rows)?;
+        Ok(())
+    }
+
+    /// Sends the compressed data that is ready to the client
+    pub async fn flush(&mut self) -> anyhow::Result<()> {
+        send_pending(&self.buffer, &mut self.writer).await
+    }
+
+    pub async fn close(self) -> ResponseWriter {
+        let Self {
+            zip,
+            buffer,
+            mut writer,
+            columns,
+            rows,
+            sheet_name,
+        } = self;
+        if let Err(e) = finish_workbook(zip, columns.len(), rows, &sheet_name) {
+            log::error!("Unable to finish writing the xlsx file: {e:#}");
+        }
+        if let Err(e) = send_pending(&buffer, &mut writer).await {
+            log::error!("Unable to send the end of the xlsx file: {e:#}");
+        }
+        writer
+    }
+}
+
+async fn send_pending(buffer: &SharedBuffer, writer: &mut ResponseWriter) -> anyhow::Result<()> {
+    let pending = std::mem::take(&mut *buffer.0.borrow_mut());
+    writer.write_all(&pending)?;
+    writer.async_flush().await?;
+    Ok(())
+}
+
+/// Ends the sheet, and writes the other files of the workbook
+fn finish_workbook(
+    mut zip: ZipWriter<StreamWriter<SharedBuffer>>,
+    columns: usize,
+    rows: usize,
+    sheet_name: &str,
+) -> anyhow::Result<()> {
+    zip.write_all(b"</sheetData>")?;
+    let last_column = columns.checked_sub(1);
+    let last_row = rows.max(1);
+    if let Some(last_column) = last_column {
+        let last_cell = cell_reference(last_column, last_row);
+        write!(zip, r#"<autoFilter ref="A1:{last_cell}"/>"#)?;
+    }
+    zip.write_all(b"</worksheet>")?;
+
+    zip.start_file("[Content_Types].xml", file_options())?;
+    zip.write_all(CONTENT_TYPES.as_bytes())?;
+    zip.start_file("_rels/.rels", file_options())?;
+    zip.write_all(ROOT_RELATIONSHIPS.as_bytes())?;
+    zip.start_file("xl/_rels/workbook.xml.rels", file_options())?;
+    zip.write_all(WORKBOOK_RELATIONSHIPS.as_bytes())?;
+    zip.start_file("xl/styles.xml", file_options())?;
+    zip.write_all(STYLES.as_bytes())?;
+    zip.start_file("xl/workbook.xml", file_options())?;
+    let sheet_name = escape(sheet_name);
+    // The hidden name used by Excel to store the range of the auto-filter
+    let defined_names = last_column.map_or_else(String::new, |last_column| {
+        format!(
+            r#"<definedNames><definedName name="_xlnm._FilterDatabase" localSheetId="0" hidden="1">'{}'!$A$1:${}${last_row}</definedName></definedNames>"#,
+            sheet_name.replace('\'', "''"),
+            column_name(last_column)
+        )
+    });
+    write!(
+        zip,
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+            r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+            r#"<sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets>{}</workbook>"#
+        ),
+        sheet_name, defined_names
+    )?;
+    zip.finish()
+        .context("Unable to write the end of the zip archive")?;
+    Ok(())
+}
+
+fn file_options() -> SimpleFileOptions {
+    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated)
+}
+
+fn validate_sheet_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name.chars().count() > MAX_SHEET_NAME_LENGTH {
+        bail!("Invalid xlsx sheet name: {name:?}. It must contain between 1 and {MAX_SHEET_NAME_LENGTH} characters.");
+    }
+    if let Some(c) = name.chars().find(|c| "[]:*?/\\".contains(*c)) {
+        bail!("Invalid xlsx sheet name: {name:?}. It cannot contain {c:?}.");
+    }
+    Ok(())
+}
+
+/// The name of a cell in Excel, such as `A1` or `AB12`, from a zero-based column and a one-based row
+fn cell_reference(column: usize, row: usize) -> String {
+    format!("{}{row}", column_name(column))
+}
+
+/// The letters of a zero-based column: `A` to `Z`, then `AA`, `AB`, ...
+fn column_name(column: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = column + 1;
+    while n > 0 {
+        let remainder = (n - 1) % 26;
+        letters.push(b'A' + u8::try_from(remainder).unwrap_or_default());
+        n = (n - 1) / 26;
+    }
+    letters.reverse();
+    String::from_utf8_lossy(&letters).into_owned()
+}
+
+fn write_cell(out: &mut String, reference: &str, value: &JsonValue) {
+    match value {
+        JsonValue::Null => {}
+        JsonValue::Bool(b) => {
+            let _ = write!(
+                out,
+                r#"<c r="{reference}" t="b"><v>{}</v></c>"#,
+                u8::from(*b)
+            );
+        }
+        JsonValue::Number(n) => {
+            let _ = write!(out, r#"<c r="{reference}"><v>{n}</v></c>"#);
+        }
+        JsonValue::String(s) => {
+            if let Some((serial, style)) = date_serial(s) {
+                let _ = write!(out, r#"<c r="{reference}" s="{style}"><v>{serial}</v></c>"#);
+            } else {
+                write_string_cell(out, reference, s, None);
+            }
+        }
+        JsonValue::Array(_) | JsonValue::Object(_) => {
+            write_string_cell(out, reference, &value.to_string(), None);
+        }
+    }
+}
+
+fn write_string_cell(out: &mut String, reference: &str, value: &str, style: Option<u8>) {
+    let _ = write!(out, r#"<c r="{reference}""#);
+    if let Some(style) = style {
+        let _ = write!(out, r#" s="{style}""#);
+    }
+    let _ = write!(
+        out,
+        r#" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+        escape(value)
+    );
+}
+
+/// The value of a date in Excel, as a number of days since 1899-12-30, and the style to display it.
+/// Dates before March 1900 are left as strings, because Excel wrongly considers 1900 a leap year.
+fn date_serial(s: &str) -> Option<(f64, u8)> {
+    let (datetime, style) = if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        (date.and_hms_opt(0, 0, 0)?, STYLE_DATE)
+    } else {
+        let datetime = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
+            .or_else(|_| chrono::DateTime::parse_from_rfc3339(s).map(|d| d.naive_local()))
+            .ok()?;
+        (datetime, STYLE_DATETIME)
+    };
+    let excel_epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let first_valid_date = NaiveDate::from_ymd_opt(1900, 3, 1)?.and_hms_opt(0, 0, 0)?;
+    if datetime < first_valid_date || datetime.year() > 9999 {
+        return None;
+    }
+    let milliseconds = (datetime - excel_epoch).num_milliseconds();
+    #[allow(clippy::cast_precision_loss)]
+    let days = milliseconds as f64 / 86_400_000.0;
+    Some((days, style))
+}
+
+const CONTENT_TYPES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+    r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+    r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+    r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    r#"<Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+    r#"<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+    r#"</Types>"#
+);
+
+const ROOT_RELATIONSHIPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+    r#"</Relationships>"#
+);
+
+const WORKBOOK_RELATIONSHIPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>"#,
+    r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+    r#"</Relationships>"#
+);
+
+/// Cell formats: default, bold header, date and date with time
+const STYLES: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+    r#"<numFmts count="2"><numFmt numFmtId="164" formatCode="yyyy-mm-dd"/><numFmt numFmtId="165" formatCode="yyyy-mm-dd hh:mm:ss"/></numFmts>"#,
+    r#"<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts>"#,
+    r#"<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>"#,
+    r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>"#,
+    r#"<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+    r#"<cellXfs count="4">"#,
+    r#"<xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>"#,
+    r#"<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>"#,
+    r#"<xf numFmtId="164" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>"#,
+    r#"<xf numFmtId="165" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>"#,
+    r#"</cellXfs>"#,
+    r#"<cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles>"#,
+    r#"</styleSheet>"#
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_reference() {
+        assert_eq!(cell_reference(0, 1), "A1");
+        assert_eq!(cell_reference(25, 3), "Z3");
+        assert_eq!(cell_reference(26, 10), "AA10");
+        assert_eq!(cell_reference(MAX_COLUMNS - 1, MAX_ROWS), "XFD1048576");
+    }
+
+    #[test]
+    fn test_date_serial() {
+        assert_eq!(date_serial("1900-03-01"), Some((61.0, STYLE_DATE)));
+        assert_eq!(date_serial("2024-01-31"), Some((45322.0, STYLE_DATE)));
+        assert_eq!(
+            date_serial("2024-01-31T18:00:00"),
+            Some((45322.75, STYLE_DATETIME))
+        );
+        assert_eq!(
+            date_serial("2024-01-31 06:00:00.000"),
+            Some((45322.25, STYLE_DATETIME))
+        );
+        assert_eq!(date_serial("1900-01-01"), None);
+        assert_eq!(date_serial("not a date"), None);
+        assert_eq!(date_serial("2024-13-01"), None);
+    }
+
+    #[test]
+    fn test_write_cell() {
+        let mut out = String::new();
+        write_cell(&mut out, "A2", &serde_json::json!(1.5));
+        write_cell(&mut out, "B2", &serde_json::json!(true));
+        write_cell(&mut out, "C2", &serde_json::json!("a < b\u{1}"));
+        write_cell(&mut out, "D2", &JsonValue::Null);
+        assert_eq!(
+            out,
+            r#"<c r="A2"><v>1.5</v></c><c r="B2" t="b"><v>1</v></c><c r="C2" t="inlineStr"><is><t xml:space="preserve">a &lt; b</t></is></c>"#
+        );
+    }
+
+    #[test]
+    fn test_sheet_name() {
+        assert!(validate_sheet_name("Sales 2024").is_ok());
+        assert!(validate_sheet_name("").is_err());
+        assert!(validate_sheet_name("a/b").is_err());
+        assert!(validate_sheet_name(&"x".repeat(32)).is_err());
+    }
+}
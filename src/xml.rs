@@ -0,0 +1,233 @@
+//! Rendering of query results as an XML document, for the `xml` component.
+//!
+//! The document has a root element (`<rows>` by default) containing one element per row (`<row>` by default).
+//! Each column becomes a child element of the row, or an attribute of the row when it is listed in `attributes`.
+//! JSON objects and arrays returned by the database become nested elements.
+
+use anyhow::bail;
+use serde_json::{Map, Value as JsonValue};
+use std::fmt::Write as _;
+use std::io::Write;
+
+pub const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+pub struct XmlBodyRenderer<W: Write> {
+    pub writer: W,
+    root: String,
+    row: String,
+    /// Columns that are written as attributes of the row element
+    attributes: Vec<String>,
+}
+
+impl<W: Write> XmlBodyRenderer<W> {
+    pub fn new(mut writer: W, options: &JsonValue) -> anyhow::Result<Self> {
+        let name_option = |name: &str, default: &str| -> anyhow::Result<String> {
+            match options.get(name) {
+                None | Some(JsonValue::Null) => Ok(default.to_string()),
+                Some(JsonValue::String(s)) if is_valid_name(s) => Ok(s.clone()),
+                Some(other) => bail!("Invalid {name} element name for the xml component: {other}"),
+            }
+        };
+        let root = name_option("root", "rows")?;
+        let row = name_option("row", "row")?;
+        let attributes = match options.get("attributes") {
+            None | Some(JsonValue::Null) => Vec::new(),
+            Some(JsonValue::String(s)) => s.split(',').map(|c| c.trim().to_string()).collect(),
+            Some(JsonValue::Array(columns)) => columns
+                .iter()
+                .filter_map(JsonValue::as_str)
+                .map(str::to_string)
+                .collect(),
+            Some(other) => bail!(
+                "The attributes of the xml component must be a comma-separated list of columns, not {other}"
+            ),
+        };
+        write!(
+            writer,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{root}>\n"
+        )?;
+        Ok(Self {
+            writer,
+            root,
+            row,
+            attributes,
+        })
+    }
+
+    pub fn handle_row(&mut self, data: &JsonValue) -> anyhow::Result<()> {
+        let Some(columns) = data.as_object() else {
+            return Ok(());
+        };
+        let mut out = format!("<{}", self.row);
+        for name in &self.attributes {
+            match columns.get(name) {
+                None | Some(JsonValue::Null) => {}
+                Some(value) => {
+                    out.push(' ');
+                    out.push_str(&element_name(name));
+                    out.push_str("=\"");
+                    out.push_str(&escape_attribute(&text(value)));
+                    out.push('"');
+                }
+            }
+        }
+        out.push('>');
+        for (name, value) in columns {
+            if !self.attributes.contains(name) {
+                write_element(&mut out, &element_name(name), value);
+            }
+        }
+        out.push_str("</");
+        out.push_str(&self.row);
+        out.push_str(">\n");
+        self.writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn handle_error(&mut self, error: &anyhow::Error) -> anyhow::Result<()> {
+        let mut out = String::new();
+        write_element(&mut out, "error", &JsonValue::String(error.to_string()));
+        out.push('\n');
+        self.writer.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn close(mut self) -> W {
+        if let Err(e) = writeln!(self.writer, "</{}>", self.root) {
+            log::error!("Unable to write the end of the xml document: {e}");
+        }
+        self.writer
+    }
+}
+
+/// Writes a value as an element. Null values are omitted, arrays become repeated elements,
+/// and objects contain one element per key.
+fn write_element(out: &mut String, name: &str, value: &JsonValue) {
+    match value {
+        JsonValue::Null => {}
+        JsonValue::Array(items) => {
+            for item in items {
+                write_element(out, name, item);
+            }
+        }
+        JsonValue::Object(fields) => {
+            let _ = write!(out, "<{name}>");
+            write_fields(out, fields);
+            let _ = write!(out, "</{name}>");
+        }
+        value => {
+            let _ = write!(out, "<{name}>{}</{name}>", escape(&text(value)));
+        }
+    }
+}
+
+fn write_fields(out: &mut String, fields: &Map<String, JsonValue>) {
+    for (name, value) in fields {
+        write_element(out, &element_name(name), value);
+    }
+}
+
+fn text(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && element_name(name) == name
+}
+
+/// Turns a column name into a valid XML element name, by replacing the characters
+/// that cannot appear in a name with underscores
+fn element_name(column: &str) -> String {
+    let mut name = String::with_capacity(column.len());
+    for c in column.chars() {
+        let can_start = c.is_alphabetic() || c == '_';
+        let can_continue = can_start || c.is_numeric() || c == '-' || c == '.';
+        if name.is_empty() && !can_start {
+            name.push('_');
+            if !can_continue {
+                continue;
+            }
+        }
+        name.push(if can_continue { c } else { '_' });
+    }
+    if name.is_empty() {
+        name.push('_');
+    }
+    name
+}
+
+/// Escapes a string for XML, and removes the control characters that XML cannot contain
+#[must_use]
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\0'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes an attribute value, keeping the whitespace characters that parsers would normalize to spaces
+fn escape_attribute(s: &str) -> String {
+    escape(s)
+        .replace('\t', "&#9;")
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_element_name() {
+        assert_eq!(element_name("name"), "name");
+        assert_eq!(element_name("first name"), "first_name");
+        assert_eq!(element_name("1st"), "_1st");
+        assert_eq!(element_name("(x)"), "_x_");
+        assert_eq!(element_name(""), "_");
+        assert!(is_valid_name("order-line"));
+        assert!(!is_valid_name("a b"));
+    }
+
+    #[test]
+    fn test_document() {
+        let options = json!({"root": "orders", "row": "order", "attributes": "id, status"});
+        let mut renderer = XmlBodyRenderer::new(Vec::new(), &options).unwrap();
+        renderer
+            .handle_row(&json!({
+                "id": 1,
+                "status": "paid \"now\"",
+                "customer": "Tom & Jerry",
+                "note": null,
+                "lines": [{"sku": "A1", "qty": 2}, {"sku": "B2", "qty": 1}]
+            }))
+            .unwrap();
+        let out = String::from_utf8(renderer.close()).unwrap();
+        assert_eq!(
+            out,
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<orders>\n",
+                "<order id=\"1\" status=\"paid &quot;now&quot;\"><customer>Tom &amp; Jerry</customer>",
+                "<lines><sku>A1</sku><qty>2</qty></lines><lines><sku>B2</sku><qty>1</qty></lines></order>\n",
+                "</orders>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalid_root() {
+        assert!(XmlBodyRenderer::new(Vec::new(), &json!({"root": "<rows>"})).is_err());
+    }
+}
@@ -0,0 +1 @@
+select 'shell-empty' as component, sqlpage.asset_url('/tests/it_works.txt') as html;
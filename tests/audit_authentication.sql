@@ -0,0 +1,4 @@
+select 'authentication' as component,
+    sqlpage.hash_password('secret') as password_hash,
+    sqlpage.basic_auth_password() as password;
+select 'text' as component, 'Welcome' as contents;
@@ -0,0 +1 @@
+select 'text' as component, 'Reserved to administrators' as contents where $user <> 'admin';
@@ -0,0 +1 @@
+select 'text' as component, 'It works !' as contents;
@@ -0,0 +1,3 @@
+-- Visitors who do not say who they are are sent to the login page
+select 'redirect' as component, 'login.sql' as link where $user is null;
+set greeting = 'Hello, ' || $user;
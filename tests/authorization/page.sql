@@ -0,0 +1 @@
+select 'text' as component, $greeting as contents;
@@ -0,0 +1,2 @@
+select 'authentication' as component;
+select 'secret' as x;
@@ -0,0 +1,2 @@
+select 'cookie' as component, 'session' as name, 'x' as value;
+select 'not returned' as x;
@@ -0,0 +1,2 @@
+select 'json' as component;
+select $id as id, $tags as tags;
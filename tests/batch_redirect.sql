@@ -0,0 +1,2 @@
+select 'redirect' as component, '/login.sql' as link;
+select 'not returned' as x;
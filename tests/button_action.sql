@@ -0,0 +1,5 @@
+select 'status_code' as component, 403 as status
+where :id is not null and sqlpage.csrf_verified() = 'false';
+select 'button' as component;
+select 'button_action.sql' as link, 'DELETE' as method, '{"id": 42}' as parameters, 'Delete' as title;
+select 'text' as component, 'deleted ' || :id || ' with ' || :_method as contents where :id is not null;
@@ -0,0 +1,5 @@
+select 'list' as component, 'Concurrent statements' as title;
+-- @concurrent on
+select 'First item' as title from (select 1 as x) t;
+select $second as title from (select 1 as x) t;
+select 'Third item' as title from (select 1 as x) t;
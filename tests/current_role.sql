@@ -0,0 +1 @@
+select 'text' as component, current_user as contents;
@@ -0,0 +1,9 @@
+#!/bin/sh
+# Stands in for a headless Chromium in the tests of PDF rendering:
+# writes a fake PDF containing the arguments it was started with, one per line.
+for arg in "$@"; do
+    case "$arg" in
+        --print-to-pdf=*) output="${arg#--print-to-pdf=}" ;;
+    esac
+done
+{ echo "%PDF-1.4"; printf '%s\n' "$@"; } > "$output"
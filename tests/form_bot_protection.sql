@@ -0,0 +1,5 @@
+select 'status_code' as component, 403 as status
+where :email is not null and sqlpage.bot_protection_passed() = 'false';
+select 'form' as component, true as bot_protection;
+select 'email' as name;
+select 'text' as component, 'received ' || :email as contents where :email is not null;
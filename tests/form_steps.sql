@@ -0,0 +1,3 @@
+select 'form' as component, sqlpage.variables('post') as previous_values;
+select 'product' as name;
+select 'text' as component, 'customer ' || :customer || ', product ' || :product as contents where :product is not null;
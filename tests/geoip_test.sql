@@ -0,0 +1,2 @@
+select 'text' as component,
+    sqlpage.geoip('81.2.69.160') || ' / ' || coalesce(sqlpage.geoip('10.0.0.1'), 'not found') || ' / ' || coalesce(sqlpage.client_country(), 'unknown client') as contents;
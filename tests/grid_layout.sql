@@ -0,0 +1,3 @@
+select 'text' as component, 6 as grid_width, 'left' as contents;
+select 'text' as component, '6' as grid_width, 'right' as contents;
+select 'text' as component, 'below' as contents;
@@ -0,0 +1,2 @@
+select 'ical' as component, 'Events' as title;
+select 'Launch party' as summary, '2024-06-01 19:00:00' as "start", '2024-06-01 23:00:00' as "end", 'Paris' as location, 'launch' as uid;
@@ -0,0 +1,3 @@
+INSERT INTO idempotency_test(x) VALUES (:x);
+select 'cookie' as component, 'last_inserted' as name, :x as value;
+select 'text' as component, 'inserted rows: ' || (select count(*) from idempotency_test) as contents;
@@ -11,4 +11,7 @@ select
     ) as item,
     JSON (
         '{"icon":"headset","color":"green","description":"Priority Support"}'
-    ) as item;
\ No newline at end of file
+    ) as item,
+    (
+        select JSON ('{"icon":"world","color":"red","description":"Custom Domains"}')
+    ) as item;
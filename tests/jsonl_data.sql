@@ -0,0 +1,3 @@
+select 'json' as component, 'jsonl' as type;
+select 'It works!' as message;
+select 'cool' as cool;
@@ -0,0 +1 @@
+select 'live' as component, 'sql_test_files/it_works_simple.sql' as embed, 'orders' as channel;
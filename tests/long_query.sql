@@ -0,0 +1 @@
+select 'text' as component, 'never displayed' as sqlpage_cancel_test from pg_sleep(30);
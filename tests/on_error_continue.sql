@@ -0,0 +1,7 @@
+-- @on_error continue
+select 'text' as component, 'before the error' as contents;
+select * from table_that_does_not_exist;
+select 'text' as component, 'after the error' as contents;
+-- @on_error abort
+select * from another_table_that_does_not_exist;
+select 'text' as component, 'never displayed' as contents;
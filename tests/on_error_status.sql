@@ -0,0 +1,3 @@
+-- @on_error 404
+select * from table_that_does_not_exist;
+select 'text' as component, 'never displayed' as contents;
@@ -0,0 +1,4 @@
+INSERT INTO optional_statement_test(x) VALUES ('1');
+-- @optional
+INSERT INTO optional_statement_test(x) VALUES (:x);
+select 'text' as component, 'inserted' as contents;
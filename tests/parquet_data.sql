@@ -0,0 +1,3 @@
+select 'parquet' as component, 'people' as filename;
+select 1 as id, 'Ophir' as name, 1.5 as score;
+select 2 as id, NULL as name, 2 as score;
@@ -0,0 +1,3 @@
+INSERT INTO post_transaction_test(x) VALUES ('1');
+INSERT INTO post_transaction_test(x) VALUES (:x);
+select 'text' as component, 'inserted' as contents;
@@ -0,0 +1,2 @@
+INSERT INTO post_transaction_child(parent_id) VALUES (CAST(:parent AS INTEGER));
+select 'redirect' as component, 'post_transaction.sql' as link;
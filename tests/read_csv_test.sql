@@ -0,0 +1,2 @@
+select 'json' as component;
+select sqlpage.read_csv(sqlpage.uploaded_file_path('people_file')) as people;
@@ -0,0 +1,2 @@
+-- Renders the components and rows given as a JSON array in the $properties parameter
+select 'dynamic' as component, $properties as properties;
@@ -0,0 +1,4 @@
+select 'list' as component;
+select 'one' as title;
+select 'two' as title;
+select 'three' as title;
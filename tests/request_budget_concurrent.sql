@@ -0,0 +1,6 @@
+select 'list' as component;
+-- @concurrent on
+select 'small' as title from (select 1 as x) t;
+-- Would return a hundred million rows, if the query did not stop once the budget is used up
+with recursive n(i) as (select 1 union all select i + 1 from n where i < 100000000)
+select 'row ' || i as title from n;
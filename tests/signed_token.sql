@@ -0,0 +1,8 @@
+set token = sqlpage.sign_token('{"user":"jo@example.com"}', 60);
+select 'text' as component,
+    case
+        when sqlpage.verify_token($token) = '{"user":"jo@example.com"}'
+            and sqlpage.verify_token($token || 'x') is null
+        then 'It works !'
+        else 'error: ' || coalesce(sqlpage.verify_token($token), 'NULL')
+    end as contents;
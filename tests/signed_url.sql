@@ -0,0 +1,5 @@
+select 'text' as component,
+    case sqlpage.url_is_signed()
+        when 'true' then 'Signed link to invoice ' || $id || ' with parameters ' || sqlpage.variables('get')
+        else 'Link: ' || sqlpage.sign_url('signed_url.sql?id=12', 60) || ' .'
+    end as contents;
@@ -0,0 +1 @@
+select 'text' as component, sqlpage.geoip('81.2.69.160') as contents;
@@ -124,6 +124,6 @@ select 'dynamic' as component,'{"component":"dynamic","properties":
 {"component":"dynamic","properties":
 {"component":"dynamic","properties":
 {"component":"dynamic","properties":
-{"component":"text", "contents": "It works ! (but it shouldn''t)"}
+{"component":"text", "contents": "This is nested too deeply to be displayed"}
 }}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}}
 ' as properties;
\ No newline at end of file
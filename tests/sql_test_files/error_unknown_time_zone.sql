@@ -0,0 +1 @@
+select 'text' as component, sqlpage.to_timezone('2024-07-14 10:00:00', 'Mars/Olympus_Mons') as contents;
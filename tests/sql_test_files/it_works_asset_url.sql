@@ -0,0 +1,5 @@
+select 'text' as component,
+    case
+        when sqlpage.asset_url('/tests/it_works.txt') like '/tests/it_works.txt?v=%' then 'It works !'
+        else 'error: ' || coalesce(sqlpage.asset_url('/tests/it_works.txt'), 'NULL')
+    end AS contents;
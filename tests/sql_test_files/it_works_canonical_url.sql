@@ -0,0 +1,6 @@
+select 'text' as component,
+    CASE sqlpage.canonical_url()
+        WHEN '/tests/sql_test_files/it_works_canonical_url.sql?x=1' THEN 'It works !'
+        ELSE 'It failed ! Got "' || sqlpage.canonical_url() || '".'
+    END
+    AS contents;
@@ -0,0 +1,4 @@
+select 'text' as component,
+    case when coalesce(sqlpage.client_is_mobile(), sqlpage.client_browser(), sqlpage.client_os()) is null then 'It works !'
+        else 'It failed ! The test request has no user agent.'
+    end as contents;
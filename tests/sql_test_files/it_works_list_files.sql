@@ -0,0 +1,6 @@
+set files = sqlpage.list_files('/tests/');
+select 'text' as component,
+    case
+        when $files like '%{"name":"it_works.txt","path":"/tests/it_works.txt","is_directory":false,"size":10,"mime_type":"text/plain","modified":"%Z"}%' then 'It works !'
+        else 'error: ' || coalesce($files, 'NULL')
+    end AS contents;
@@ -0,0 +1,7 @@
+-- Without a sqlpage_timezone cookie, the timezone is the default timezone of the site, which is UTC
+select 'text' as component,
+    CASE sqlpage.timezone()
+        WHEN 'UTC' THEN 'It works !'
+        ELSE 'It failed ! Got: ' || sqlpage.timezone()
+    END
+    AS contents;
@@ -0,0 +1,6 @@
+select 'text' as component,
+    CASE sqlpage.to_timezone('2024-07-14 10:00:00', 'Europe/Paris')
+        WHEN '2024-07-14T12:00:00+02:00' THEN 'It works !'
+        ELSE 'It failed ! Got: ' || sqlpage.to_timezone('2024-07-14 10:00:00', 'Europe/Paris')
+    END
+    AS contents;
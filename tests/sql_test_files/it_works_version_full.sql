@@ -0,0 +1,5 @@
+select 'text' as component,
+    case
+        when sqlpage.version('full') like '{"version":"%","git_commit":"%","features":[%' then 'It works !'
+        else 'error: ' || coalesce(sqlpage.version('full'), 'NULL')
+    end AS contents;
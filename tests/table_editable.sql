@@ -0,0 +1,2 @@
+select 'table' as component, 'Price' as editable, 'save_price.sql' as save_url;
+select 3 as _sqlpage_id, 'Pen' as name, 1.5 as price;
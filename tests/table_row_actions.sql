@@ -0,0 +1,2 @@
+select 'table' as component, 'edit.sql?id={id}' as edit_url, 'delete.sql?id={id}' as delete_url, 'Delete this person?' as delete_confirmation;
+select 7 as _sqlpage_id, 'Alice' as name;
@@ -0,0 +1,11 @@
+select 'table' as component, true as sort_links;
+select name, age from (
+    select 'Alice' as name, 31 as age
+    union all
+    select 'Bob', 25
+) as people
+order by
+    case when $sort = 'age' and $dir = 'asc' then age end,
+    case when $sort = 'age' and $dir = 'desc' then age end desc,
+    case when $dir = 'desc' then name end desc,
+    name;
@@ -0,0 +1,2 @@
+select 'json' as component;
+select sqlpage.timezone() as timezone, sqlpage.to_timezone('2024-01-01 00:00:00') as local_time;
@@ -0,0 +1,2 @@
+select 'text' as component,
+    'mobile: ' || sqlpage.client_is_mobile() || ', browser: ' || sqlpage.client_browser() || ', os: ' || sqlpage.client_os() as contents;
@@ -0,0 +1,4 @@
+select 'xlsx' as component, 'report' as filename, 'Data & more' as sheet_name;
+select 1 as id, 'Hello <World>' as msg, '2024-01-31' as day
+union all
+select 2.5 as id, NULL as msg, '2024-01-31 18:00:00' as day;
@@ -0,0 +1,2 @@
+select 'xml' as component, 'people' as root, 'person' as row, 'id' as attributes;
+select 1 as id, 'Ophir' as name, 'Tom & Jerry' as nickname;